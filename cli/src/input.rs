@@ -0,0 +1,12 @@
+use std::io::{stdin, BufRead};
+
+/// Read targets from stdin one line at a time, as they arrive, so heimdall can be used at the end
+/// of a pipeline such as `cast ... | heimdall decode -`. Blank lines are skipped.
+pub fn stdin_targets() -> impl Iterator<Item = String> {
+    stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
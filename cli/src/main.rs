@@ -1,11 +1,15 @@
+pub(crate) mod input;
 pub(crate) mod output;
 
 use backtrace::Backtrace;
-use output::{build_output_path, print_with_less};
-use std::{io, panic};
+use input::stdin_targets;
+use output::{
+    build_output_path, print_json, print_json_line, print_with_less, set_output_dir,
+    set_output_module,
+};
+use std::{io, panic, time::Duration};
 
 use clap::{Parser, Subcommand};
-use colored::Colorize;
 use crossterm::{
     event::DisableMouseCapture,
     execute,
@@ -13,21 +17,77 @@ use crossterm::{
 };
 
 use heimdall_cache::{cache, CacheArgs};
-use heimdall_common::utils::{
-    io::{
-        file::{write_file, write_lines_to_file},
-        logging::Logger,
+use heimdall_common::{
+    ether::{
+        chains::Chain,
+        evm::ext::exec::{ExecutionLimits, LimitReached},
+        rpc::{set_rpc_cache, set_rpc_retries, set_rpc_timeout},
+    },
+    utils::{
+        determinism::set_deterministic,
+        failure::{report_failure, set_json_failures, FailureClass},
+        http::{set_lookup_retries, set_lookup_timeout},
+        io::{
+            file::{append_lines_to_file, write_file, write_lines_to_file},
+            json::json_document,
+            logging::{set_interactive, set_stderr_logs, Logger},
+            progress::set_progress_lines,
+            subscriber::{init_tracing, LogFormat},
+            table::TableFormat,
+            theme::{set_theme, Paint, Role, Theme},
+        },
+        version::{current_version, remote_version},
     },
-    version::{current_version, remote_version},
 };
 use heimdall_config::{config, get_config, ConfigArgs};
 use heimdall_core::{
-    cfg::{cfg, output::build_cfg, CFGArgs},
+    assemble::{assemble, AssemblerArgs},
+    audit::{audit, AuditArgs},
+    callgraph::{
+        callgraph,
+        output::{build_callgraph, build_callgraph_json},
+        CallGraphArgs,
+    },
+    cfg::{
+        cfg,
+        diff::cfg_diff,
+        output::{
+            build_cfg, build_cfg_diff, build_cfg_diff_html, build_cfg_diff_json, build_cfg_html,
+            build_cfg_json,
+        },
+        CFGArgs,
+    },
+    collisions::{collisions, CollisionsArgs},
+    conformance::{conformance, ConformanceArgs},
+    debug::{debug, DebugArgs},
     decode::{decode, DecodeArgs},
     decompile::{decompile, out::abi::ABIStructure, DecompilerArgs},
+    dependencies::{dependencies, DependenciesArgs},
+    diff::{diff, diff_decompiled, DiffArgs},
     disassemble::{disassemble, DisassemblerArgs},
-    dump::{dump, DumpArgs},
-    snapshot::{snapshot, util::csv::generate_csv, SnapshotArgs},
+    dump::{
+        dump,
+        output::{build_parquet, build_sqlite},
+        watch::watch,
+        DumpArgs,
+    },
+    encode::{encode, EncodeArgs},
+    error::Error,
+    exec::{exec, ExecArgs},
+    inspect::{inspect, tenderly::build_tenderly_trace, InspectArgs},
+    layout::{layout, LayoutArgs},
+    proxy::{proxy, ProxyArgs},
+    scan::{scan, ScanArgs},
+    selectors::{build_selectors_table, format_selectors, selectors, SelectorsArgs},
+    serve::{serve, ServeArgs},
+    simulate::{simulate, SimulateArgs},
+    snapshot::{
+        snapshot,
+        util::{csv::generate_csv, json::generate_json},
+        SnapshotArgs,
+    },
+    verify::{verify, VerifyArgs},
+    watch::{watch as watch_pending, WatchArgs},
 };
 use tui::{backend::CrosstermBackend, Terminal};
 
@@ -36,6 +96,88 @@ use tui::{backend::CrosstermBackend, Terminal};
 pub struct Arguments {
     #[clap(subcommand)]
     pub sub: Subcommands,
+
+    /// Always fetch on-chain data from the RPC provider, rather than reading or writing cached
+    /// responses.
+    #[clap(long = "no-rpc-cache", global = true)]
+    pub no_rpc_cache: bool,
+
+    /// The most seconds a single RPC request may take before it fails, in place of the
+    /// configuration's `rpc_timeout`, which defaults to 30.
+    #[clap(long = "rpc-timeout", value_name = "SECONDS", global = true)]
+    pub rpc_timeout: Option<u64>,
+
+    /// How many times a failed RPC request is retried, with an exponential backoff, in place of
+    /// the configuration's `rpc_retries`, which defaults to 5.
+    #[clap(long = "rpc-retries", value_name = "RETRIES", global = true)]
+    pub rpc_retries: Option<u32>,
+
+    /// The most seconds a single lookup to an external service, such as a signature database,
+    /// Sourcify, or a block explorer, may take before it fails, in place of the configuration's
+    /// `lookup_timeout`, which defaults to 15.
+    #[clap(long = "lookup-timeout", value_name = "SECONDS", global = true)]
+    pub lookup_timeout: Option<u64>,
+
+    /// How many times a failed lookup to an external service is retried, in place of the
+    /// configuration's `lookup_retries`, which defaults to 3.
+    #[clap(long = "lookup-retries", value_name = "RETRIES", global = true)]
+    pub lookup_retries: Option<u32>,
+
+    /// The chain to use, by name or id, such as `mainnet`, `base`, or `137`. The chain's provider
+    /// from the configuration's `rpc_urls`, or its public RPC provider, is used in place of the
+    /// configured one, unless `--rpc-url` is given.
+    #[clap(long = "chain", global = true)]
+    pub chain: Option<Chain>,
+
+    /// The format of the spans and events written to stderr at the `-v` verbosity, either `text`
+    /// or `json`.
+    #[clap(long = "log-format", global = true, default_value = "text")]
+    pub log_format: LogFormat,
+
+    /// The format failures are reported in, either `text` or `json`. JSON failures are written to
+    /// stderr as an object with the failure's class, exit code, and whether it's worth retrying.
+    #[clap(long = "error-format", global = true, default_value = "text")]
+    pub error_format: LogFormat,
+
+    /// Print the result to stdout as a versioned JSON document, rather than as text or files. The
+    /// document's `schema`, such as `heimdall.decode.v1`, names the module and the version of the
+    /// shape of its `result`.
+    #[clap(long = "json", short = 'j', global = true)]
+    pub json: bool,
+
+    /// Rather than drawing progress bars, write the progress of long-running operations, such as
+    /// decompiling many selectors or dumping storage, to stderr as a line of JSON every few
+    /// seconds, for logs.
+    #[clap(long = "no-progress", global = true)]
+    pub no_progress: bool,
+
+    /// Run non-interactively, such as in a CI pipeline. Prompts select their default option
+    /// rather than reading stdin, spinners are hidden and progress is written as lines, the dump
+    /// TUI isn't opened, and symbolic execution is limited if no limits are configured, so runs
+    /// finish with partial results and warnings rather than hanging.
+    #[clap(long = "ci", global = true)]
+    pub ci: bool,
+
+    /// The theme logs, traces, and reports are styled with, either `default`, `light` for light
+    /// terminals, `mono` for no colors, or `plain` for ASCII text without escape codes. Defaults
+    /// to `mono` if the `NO_COLOR` environment variable is set.
+    #[clap(long = "theme", global = true)]
+    pub theme: Option<Theme>,
+
+    /// The directory output is written to when `--output` isn't given, in place of the
+    /// configuration's `output`. This may be a template, such as `out/{chain}/{address}/{module}`,
+    /// and a template with `{filename}`, such as `out/{address}.{filename}`, names each file
+    /// rather than a directory. `{chain}` and `{address}` are `local` for targets which aren't
+    /// contract addresses.
+    #[clap(long = "output-dir", global = true)]
+    pub output_dir: Option<String>,
+
+    /// Make output identical between runs on the same input, so it can be committed and diffed.
+    /// Functions are analyzed and written in selector order, which also fixes the order variables
+    /// are named in, timestamps are omitted from logs, and the `TIMESTAMP` opcode returns a fixed
+    /// value rather than the current time.
+    #[clap(long = "deterministic", global = true)]
+    pub deterministic: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -48,15 +190,48 @@ pub enum Subcommands {
     #[clap(name = "disassemble", about = "Disassemble EVM bytecode to assembly")]
     Disassemble(DisassemblerArgs),
 
+    #[clap(name = "assemble", about = "Assemble EVM assembly back into bytecode")]
+    Assemble(AssemblerArgs),
+
     #[clap(name = "decompile", about = "Decompile EVM bytecode to Solidity")]
     Decompile(DecompilerArgs),
 
     #[clap(name = "cfg", about = "Generate a visual control flow graph for EVM bytecode")]
     CFG(CFGArgs),
 
+    #[clap(name = "callgraph", about = "Generate a function-level call graph for EVM bytecode")]
+    CallGraph(CallGraphArgs),
+
+    #[clap(
+        name = "audit",
+        about = "Flag likely vulnerabilities in EVM bytecode, such as unprotected selfdestructs and unchecked calls"
+    )]
+    Audit(AuditArgs),
+
+    #[clap(
+        name = "dependencies",
+        about = "List the contracts which EVM bytecode calls at constant addresses, with their labels"
+    )]
+    Dependencies(DependenciesArgs),
+
+    #[clap(
+        name = "conformance",
+        about = "Check EVM bytecode against the ERC-20, ERC-721, ERC-1155, and ERC-4626 token standards"
+    )]
+    Conformance(ConformanceArgs),
+
+    #[clap(
+        name = "collisions",
+        about = "Detect function selectors which collide across a proxy, its implementation, and a diamond's facets"
+    )]
+    Collisions(CollisionsArgs),
+
     #[clap(name = "decode", about = "Decode calldata into readable types")]
     Decode(DecodeArgs),
 
+    #[clap(name = "encode", about = "Encode calldata from a function signature and its arguments")]
+    Encode(EncodeArgs),
+
     #[clap(name = "config", about = "Display and edit the current configuration")]
     Config(ConfigArgs),
 
@@ -71,11 +246,117 @@ pub enum Subcommands {
     consumption, storage accesses, event emissions, and more"
     )]
     Snapshot(SnapshotArgs),
+
+    #[clap(name = "selectors", about = "Extract the function selectors from EVM bytecode")]
+    Selectors(SelectorsArgs),
+
+    #[clap(name = "diff", about = "Compare two EVM bytecodes, ignoring metadata and constants")]
+    Diff(DiffArgs),
+
+    #[clap(name = "exec", about = "Execute EVM bytecode with the given calldata")]
+    Exec(ExecArgs),
+
+    #[clap(
+        name = "inspect",
+        about = "Inspect a transaction's calls, events, value transfers, and storage writes"
+    )]
+    Inspect(InspectArgs),
+
+    #[clap(
+        name = "simulate",
+        about = "Simulate a call to a contract, decoding its result, events, and storage writes"
+    )]
+    Simulate(SimulateArgs),
+
+    #[clap(
+        name = "proxy",
+        about = "Detect whether a contract is a proxy, and report its pattern, implementation, and upgrades"
+    )]
+    Proxy(ProxyArgs),
+
+    #[clap(
+        name = "layout",
+        about = "Recover a contract's storage layout, in solc's storage layout format"
+    )]
+    Layout(LayoutArgs),
+
+    #[clap(
+        name = "verify",
+        about = "Verify that a contract's bytecode was compiled from local source"
+    )]
+    Verify(VerifyArgs),
+
+    #[clap(
+        name = "scan",
+        about = "Scan contracts created over a block range, or a list of addresses, for matching bytecode"
+    )]
+    Scan(ScanArgs),
+
+    #[clap(
+        name = "serve",
+        about = "Serve decompile, decode, cfg, and snapshot as JSON HTTP endpoints"
+    )]
+    Serve(ServeArgs),
+
+    #[clap(
+        name = "watch",
+        about = "Stream decoded calldata of pending transactions from the mempool"
+    )]
+    Watch(WatchArgs),
+
+    #[clap(name = "debug", about = "Step through the execution of EVM bytecode interactively")]
+    Debug(DebugArgs),
+}
+
+impl Subcommands {
+    /// The name of the subcommand, such as `decompile`.
+    fn name(&self) -> &'static str {
+        match self {
+            Subcommands::Disassemble(_) => "disassemble",
+            Subcommands::Assemble(_) => "assemble",
+            Subcommands::Decompile(_) => "decompile",
+            Subcommands::CFG(_) => "cfg",
+            Subcommands::CallGraph(_) => "callgraph",
+            Subcommands::Audit(_) => "audit",
+            Subcommands::Dependencies(_) => "dependencies",
+            Subcommands::Conformance(_) => "conformance",
+            Subcommands::Collisions(_) => "collisions",
+            Subcommands::Decode(_) => "decode",
+            Subcommands::Encode(_) => "encode",
+            Subcommands::Config(_) => "config",
+            Subcommands::Cache(_) => "cache",
+            Subcommands::Dump(_) => "dump",
+            Subcommands::Snapshot(_) => "snapshot",
+            Subcommands::Selectors(_) => "selectors",
+            Subcommands::Diff(_) => "diff",
+            Subcommands::Exec(_) => "exec",
+            Subcommands::Inspect(_) => "inspect",
+            Subcommands::Simulate(_) => "simulate",
+            Subcommands::Proxy(_) => "proxy",
+            Subcommands::Layout(_) => "layout",
+            Subcommands::Verify(_) => "verify",
+            Subcommands::Scan(_) => "scan",
+            Subcommands::Serve(_) => "serve",
+            Subcommands::Watch(_) => "watch",
+            Subcommands::Debug(_) => "debug",
+        }
+    }
+
+    /// Whether the subcommand reads its targets from stdin, one per line, printing a line of JSON
+    /// for each.
+    fn streams_stdin(&self) -> bool {
+        match self {
+            Subcommands::Decompile(cmd) => cmd.target == "-",
+            Subcommands::Decode(cmd) => cmd.target == "-",
+            _ => false,
+        }
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
     let args = Arguments::parse();
+    set_theme(args.theme.unwrap_or_else(Theme::from_env));
     // handle catching panics with
     panic::set_hook(Box::new(|panic_info| {
         // cleanup the terminal (break out of alternate screen, disable mouse capture, and show the
@@ -92,12 +373,61 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let (logger, _) = Logger::new("TRACE");
         logger.fatal(&format!(
             "thread 'main' encountered a fatal error: '{}'!",
-            panic_info.to_string().bright_white().on_bright_red().bold(),
+            panic_info.to_string().paint(Role::Fatal),
         ));
         logger.fatal(&format!("Stack Trace:\n\n{backtrace:#?}"));
     }));
 
-    let configuration = get_config();
+    init_tracing(args.log_format);
+    set_json_failures(args.error_format == LogFormat::Json);
+    set_progress_lines(args.no_progress || args.ci);
+    set_interactive(!args.ci);
+    set_deterministic(args.deterministic);
+
+    if let Err(e) = run(args).await {
+        // heimdall's own errors are classified, so their exit codes tell orchestration systems
+        // which failures are worth retrying
+        let class =
+            e.downcast_ref::<Error>().map(Error::failure_class).unwrap_or(FailureClass::Internal);
+        exit_with_failure(class, &e.to_string())
+    }
+}
+
+/// Report a failure, then exit with its class's exit code.
+fn exit_with_failure(class: FailureClass, message: &str) -> ! {
+    report_failure(class, message);
+    std::process::exit(class.exit_code())
+}
+
+async fn run(args: Arguments) -> Result<(), Box<dyn std::error::Error>> {
+    // flags override the configuration files, which override the defaults
+    let mut configuration = get_config()?;
+    if let Some(chain) = &args.chain {
+        configuration.rpc_url =
+            configuration.chain_rpc_url(chain).unwrap_or_else(|| chain.rpc_url.to_string());
+    }
+    set_rpc_cache(configuration.rpc_cache && !args.no_rpc_cache);
+    set_rpc_timeout(Duration::from_secs(args.rpc_timeout.unwrap_or(configuration.rpc_timeout)));
+    set_rpc_retries(args.rpc_retries.unwrap_or(configuration.rpc_retries));
+    set_lookup_timeout(Duration::from_secs(
+        args.lookup_timeout.unwrap_or(configuration.lookup_timeout),
+    ));
+    set_lookup_retries(args.lookup_retries.unwrap_or(configuration.lookup_retries));
+
+    // stdout only holds the result when it's JSON, or a stream of results for targets read from
+    // stdin, which can't be prompted for
+    let json = args.json;
+    let streaming = args.sub.streams_stdin();
+    set_stderr_logs(json || streaming);
+    if streaming {
+        set_interactive(false);
+    }
+    if args.ci {
+        configuration.limits =
+            configuration.limits.with_defaults(&ExecutionLimits::non_interactive());
+    }
+    set_output_dir(args.output_dir.as_deref().unwrap_or(&configuration.output));
+    set_output_module(args.sub.name());
     match args.sub {
         Subcommands::Disassemble(mut cmd) => {
             // if the user has not specified a rpc url, use the default
@@ -105,16 +435,309 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 cmd.rpc_url = configuration.rpc_url;
             }
 
+            cmd.json = json;
             let assembly = disassemble(cmd.clone()).await?;
 
-            if cmd.output == "print" {
+            if json {
+                print_json("disassemble", &serde_json::from_str::<serde_json::Value>(&assembly)?)?;
+            } else if cmd.output == "print" {
                 print_with_less(&assembly).await?;
+            } else {
+                let output_path = build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    if cmd.json { "disassembled.json" } else { "disassembled.asm" },
+                )
+                .await?;
+
+                write_file(&output_path, &assembly)?;
+            }
+        }
+
+        Subcommands::Selectors(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            cmd.json = json;
+            let function_selectors = selectors(cmd.clone()).await?;
+            let table = cmd.format.parse::<TableFormat>().ok();
+            let output = match (cmd.json, table) {
+                (true, _) => serde_json::to_string_pretty(&function_selectors)?,
+                (false, Some(table)) => build_selectors_table(&function_selectors, table),
+                (false, None) => format_selectors(&function_selectors),
+            };
+
+            if json {
+                print_json("selectors", &function_selectors)?;
+            } else if cmd.output == "print" {
+                print!("{output}");
+            } else {
+                let output_path = build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    &match (cmd.json, table) {
+                        (true, _) => String::from("selectors.json"),
+                        (false, Some(table)) => format!("selectors.{}", table.extension()),
+                        (false, None) => String::from("selectors.txt"),
+                    },
+                )
+                .await?;
+
+                write_file(&output_path, &output)?;
+            }
+        }
+
+        Subcommands::Diff(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let (output, filename) = match cmd.decompiled {
+                true => (diff_decompiled(cmd.clone()).await?.to_string(), "decompiled.diff"),
+                false => (diff(cmd.clone()).await?.to_string(), "bytecode.diff"),
+            };
+
+            if json {
+                print_json("diff", &serde_json::json!({ "diff": output }))?;
+            } else if cmd.output == "print" {
+                print_with_less(&output).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, filename).await?;
+
+                write_file(&output_path, &output)?;
+            }
+        }
+
+        Subcommands::Exec(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = exec(cmd.clone()).await?;
+            let execution = serde_json::to_string_pretty(&result)?;
+
+            if json {
+                print_json("exec", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&execution).await?;
+            } else {
+                let output_path = build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    if cmd.trace { "trace.json" } else { "execution.json" },
+                )
+                .await?;
+
+                write_file(&output_path, &execution)?;
+            }
+        }
+
+        Subcommands::Inspect(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = inspect(cmd.clone()).await?;
+            let (report, filename) = match cmd.format.as_str() {
+                "tenderly" => (
+                    serde_json::to_string_pretty(&build_tenderly_trace(&result))?,
+                    "inspect.tenderly.json",
+                ),
+                _ => (serde_json::to_string_pretty(&result)?, "inspect.json"),
+            };
+
+            if json {
+                print_json("inspect", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&report).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, filename).await?;
+
+                write_file(&output_path, &report)?;
+            }
+        }
+
+        Subcommands::Simulate(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = simulate(cmd.clone()).await?;
+            let simulation = serde_json::to_string_pretty(&result)?;
+
+            if json {
+                print_json("simulate", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&simulation).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "simulation.json")
+                        .await?;
+
+                write_file(&output_path, &simulation)?;
+            }
+        }
+
+        Subcommands::Proxy(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = proxy(cmd.clone()).await?;
+            let report = serde_json::to_string_pretty(&result)?;
+
+            if json {
+                print_json("proxy", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&report).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "proxy.json").await?;
+
+                write_file(&output_path, &report)?;
+            }
+        }
+
+        Subcommands::Layout(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = layout(cmd.clone()).await?;
+            let storage_layout = serde_json::to_string_pretty(&result)?;
+
+            if json {
+                print_json("layout", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&storage_layout).await?;
+            } else {
+                let output_path = build_output_path(
+                    &cmd.output,
+                    &cmd.target,
+                    &cmd.rpc_url,
+                    "storage_layout.json",
+                )
+                .await?;
+
+                write_file(&output_path, &storage_layout)?;
+            }
+        }
+
+        Subcommands::Verify(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = verify(cmd.clone()).await?;
+            let verification = serde_json::to_string_pretty(&result)?;
+
+            if json {
+                print_json("verify", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&verification).await?;
             } else {
                 let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "disassembled.asm")
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "verification.json")
                         .await?;
 
-                write_file(&output_path, &assembly);
+                write_file(&output_path, &verification)?;
+            }
+        }
+
+        Subcommands::Scan(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let result = scan(cmd.clone()).await?;
+            let matches = serde_json::to_string_pretty(&result)?;
+
+            if json {
+                print_json("scan", &result)?;
+            } else if cmd.output == "print" {
+                print_with_less(&matches).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, "", &cmd.rpc_url, "scan.json").await?;
+
+                write_file(&output_path, &matches)?;
+            }
+        }
+
+        Subcommands::Serve(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            serve(cmd).await?;
+        }
+
+        Subcommands::Watch(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // each transaction is printed as a compact document, so the stream is one per line
+            cmd.json = json;
+            watch_pending(cmd, |call| match json {
+                true => match json_document("watch", call) {
+                    Ok(document) => println!("{document}"),
+                    Err(e) => Logger::default().error(&format!("failed to serialize call: {e}")),
+                },
+                false => println!("{call}"),
+            })
+            .await?;
+        }
+
+        Subcommands::Debug(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // the debugger reads its commands from stdin, so it can't run non-interactively
+            if args.ci {
+                exit_with_failure(
+                    FailureClass::InvalidInput,
+                    "the debugger is interactive, and can't be used with --ci.",
+                )
+            }
+
+            debug(cmd).await?;
+        }
+
+        Subcommands::Assemble(cmd) => {
+            let bytecode = assemble(cmd.clone()).await?;
+
+            if json {
+                print_json("assemble", &serde_json::json!({ "bytecode": bytecode }))?;
+            } else if cmd.output == "print" {
+                println!("{bytecode}");
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, "", "assembled.bin").await?;
+
+                write_file(&output_path, &bytecode)?;
             }
         }
 
@@ -124,9 +747,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 cmd.rpc_url = configuration.rpc_url;
             }
 
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            // if the user has not specified an etherscan api key, use the default
+            if cmd.etherscan_api_key.as_str() == "" {
+                cmd.etherscan_api_key = configuration.etherscan_api_key;
+            }
+
+            if streaming {
+                for target in stdin_targets() {
+                    let result =
+                        decompile(DecompilerArgs { target: target.clone(), ..cmd.clone() })
+                            .await
+                            .map_err(|e| e.to_string());
+                    print_json_line("decompile", &target, result)?;
+                }
+                return Ok(())
+            }
+
             let result = decompile(cmd.clone()).await?;
 
-            if cmd.output == "print" {
+            if json {
+                print_json("decompile", &result)?;
+            } else if cmd.output == "print" {
                 let mut output_str = String::new();
 
                 if let Some(abi) = &result.abi {
@@ -138,6 +782,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 if let Some(source) = &result.source {
                     output_str.push_str(&format!("Source:\n\n{}\n", source));
                 }
+                if let Some(slither) = &result.slither {
+                    output_str.push_str(&format!(
+                        "Slither:\n\n{}\n",
+                        serde_json::to_string_pretty(slither)?
+                    ));
+                }
+                if let Some(artifact) = &result.artifact {
+                    output_str.push_str(&format!(
+                        "Artifact:\n\n{}\n",
+                        serde_json::to_string_pretty(artifact)?
+                    ));
+                }
 
                 print_with_less(&output_str).await?;
             } else {
@@ -168,7 +824,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 .collect::<Vec<String>>()
                                 .join(",\n")
                         ),
-                    );
+                    )?;
                 }
 
                 // write the contract source
@@ -180,8 +836,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "decompiled.yul")
                             .await?
                     };
-                    write_file(&output_path, source);
+                    write_file(&output_path, source)?;
+                }
+
+                // write the findings in slither's format
+                if let Some(slither) = &result.slither {
+                    let output_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "slither.json")
+                            .await?;
+                    write_file(&output_path, &serde_json::to_string_pretty(slither)?)?;
                 }
+
+                // write the hardhat artifact
+                if let Some(artifact) = &result.artifact {
+                    let output_path = build_output_path(
+                        &cmd.output,
+                        &cmd.target,
+                        &cmd.rpc_url,
+                        "DecompiledContract.json",
+                    )
+                    .await?;
+                    write_file(&output_path, &serde_json::to_string_pretty(artifact)?)?;
+                }
+            }
+
+            // the output is written, but it's incomplete if any function stopped early
+            if !result.limits_reached.is_empty() {
+                let class = match result
+                    .limits_reached
+                    .iter()
+                    .any(|(_, limit)| *limit == LimitReached::Timeout)
+                {
+                    true => FailureClass::AnalysisTimeout,
+                    false => FailureClass::PartialSuccess,
+                };
+                exit_with_failure(
+                    class,
+                    &format!(
+                        "symbolic execution stopped early for {}.",
+                        result
+                            .limits_reached
+                            .iter()
+                            .map(|(selector, limit)| format!("'0x{selector}' at the {limit}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
             }
         }
 
@@ -199,7 +899,46 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             // set cmd.verbose to 6
             cmd.verbose = clap_verbosity_flag::Verbosity::new(5, 0);
 
-            let _ = decode(cmd).await;
+            // decode prints its own result, which is a JSON document in the json format
+            if json {
+                cmd.format = String::from("json");
+            }
+
+            // decode prints a line for each target which decodes, and failures are printed here.
+            // tables are streamed as one table, with a single header
+            if streaming {
+                let tabular = !json && ["tsv", "csv"].contains(&cmd.format.as_str());
+                if !tabular {
+                    cmd.format = String::from("ndjson");
+                }
+                let mut header = !cmd.no_header;
+                for target in stdin_targets() {
+                    let no_header = !header;
+                    match decode(DecodeArgs { target: target.clone(), no_header, ..cmd.clone() })
+                        .await
+                    {
+                        Ok(_) => header = false,
+                        Err(e) => match tabular {
+                            true => Logger::default()
+                                .error(&format!("failed to decode '{target}': {e}")),
+                            false => print_json_line::<()>("decode", &target, Err(e.to_string()))?,
+                        },
+                    }
+                }
+                return Ok(())
+            }
+
+            decode(cmd).await?;
+        }
+
+        Subcommands::Encode(cmd) => {
+            let calldata = encode(cmd).await?;
+
+            if json {
+                print_json("encode", &serde_json::json!({ "calldata": calldata }))?;
+            } else {
+                println!("{calldata}");
+            }
         }
 
         Subcommands::CFG(mut cmd) => {
@@ -208,15 +947,151 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 cmd.rpc_url = configuration.rpc_url;
             }
 
-            let cfg = cfg(cmd.clone()).await?;
-            let stringified_dot = build_cfg(&cfg, &cmd);
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            let (stringified_cfg, filename) = if !cmd.diff.is_empty() {
+                let diff_cfg = cfg_diff(cmd.clone()).await?;
+                match if json { "json" } else { cmd.format.as_str() } {
+                    "json" => (build_cfg_diff_json(&diff_cfg)?, "cfg.diff.json"),
+                    "html" => (build_cfg_diff_html(&diff_cfg)?, "cfg.diff.html"),
+                    _ => (build_cfg_diff(&diff_cfg, &cmd), "cfg.diff.dot"),
+                }
+            } else {
+                let cfg = cfg(cmd.clone()).await?;
+                match if json { "json" } else { cmd.format.as_str() } {
+                    "json" => (build_cfg_json(&cfg)?, "cfg.json"),
+                    "html" => (build_cfg_html(&cfg)?, "cfg.html"),
+                    _ => (build_cfg(&cfg, &cmd), "cfg.dot"),
+                }
+            };
+
+            if json {
+                let module = if cmd.diff.is_empty() { "cfg" } else { "cfg.diff" };
+                print_json(module, &serde_json::from_str::<serde_json::Value>(&stringified_cfg)?)?;
+            } else if cmd.output == "print" {
+                print_with_less(&stringified_cfg).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, filename).await?;
+                write_file(&output_path, &stringified_cfg)?;
+            }
+        }
+
+        Subcommands::CallGraph(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            let call_graph = callgraph(cmd.clone()).await?;
+            let stringified_dot = build_callgraph(&call_graph);
 
-            if cmd.output == "print" {
+            if json {
+                print_json("callgraph", &build_callgraph_json(&call_graph))?;
+            } else if cmd.output == "print" {
                 print_with_less(&stringified_dot).await?;
             } else {
                 let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "cfg.dot").await?;
-                write_file(&output_path, &stringified_dot);
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "callgraph.dot")
+                        .await?;
+                write_file(&output_path, &stringified_dot)?;
+            }
+        }
+
+        Subcommands::Audit(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            let report = audit(cmd.clone()).await?;
+
+            if json {
+                print_json("audit", &report)?;
+            } else if cmd.output == "print" {
+                print_with_less(&report.to_string()).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "audit.json").await?;
+                write_file(&output_path, &serde_json::to_string_pretty(&report)?)?;
+            }
+        }
+
+        Subcommands::Dependencies(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // if the user has not specified an etherscan api key, use the default
+            if cmd.etherscan_api_key.as_str() == "" {
+                cmd.etherscan_api_key = configuration.etherscan_api_key;
+            }
+
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            let report = dependencies(cmd.clone()).await?;
+
+            if json {
+                print_json("dependencies", &report)?;
+            } else if cmd.output == "print" {
+                print_with_less(&report.to_string()).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dependencies.json")
+                        .await?;
+                write_file(&output_path, &serde_json::to_string_pretty(&report)?)?;
+            }
+        }
+
+        Subcommands::Conformance(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
+            let report = conformance(cmd.clone()).await?;
+
+            if json {
+                print_json("conformance", &report)?;
+            } else if cmd.output == "print" {
+                print_with_less(&report.to_string()).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "conformance.json")
+                        .await?;
+                write_file(&output_path, &serde_json::to_string_pretty(&report)?)?;
+            }
+        }
+
+        Subcommands::Collisions(mut cmd) => {
+            // if the user has not specified a rpc url, use the default
+            if cmd.rpc_url.as_str() == "" {
+                cmd.rpc_url = configuration.rpc_url;
+            }
+
+            let report = collisions(cmd.clone()).await?;
+
+            if json {
+                print_json("collisions", &report)?;
+            } else if cmd.output == "print" {
+                print_with_less(&report.to_string()).await?;
+            } else {
+                let output_path =
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "collisions.json")
+                        .await?;
+                write_file(&output_path, &serde_json::to_string_pretty(&report)?)?;
             }
         }
 
@@ -231,27 +1106,200 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 cmd.transpose_api_key = configuration.transpose_api_key;
             }
 
-            let result = dump(cmd.clone()).await?;
-            let mut lines = Vec::new();
+            // the TUI can't be used non-interactively
+            if args.ci {
+                cmd.no_tui = true;
+            }
 
-            // add header
-            lines.push(String::from("last_modified,alias,slot,decoded_type,value"));
+            let result = dump(cmd.clone()).await?;
 
-            // add rows
-            for row in result {
-                lines.push(format!(
-                    "{},{},{},{},{}",
-                    row.last_modified, row.alias, row.slot, row.decoded_type, row.value
-                ));
+            // slots labeled with the recovered storage layout are written alongside the dump
+            let mut decoded_lines = Vec::new();
+            if cmd.decode_layout {
+                decoded_lines.push(String::from("slot,variable,type,value"));
+                for row in &result {
+                    if let Some(decoded) = &row.decoded {
+                        decoded_lines.push(format!(
+                            "{},{},{},{}",
+                            row.slot, decoded.variable, decoded.value_type, decoded.value
+                        ));
+                    }
+                }
             }
 
-            if cmd.output == "print" {
-                print_with_less(&lines.join("\n")).await?;
+            // sqlite and parquet files are written as-is, rather than as lines of CSV
+            if json {
+                print_json("dump", &result)?;
+            } else if ["sqlite", "parquet"].contains(&cmd.output_format.as_str()) {
+                if cmd.output == "print" {
+                    exit_with_failure(
+                        FailureClass::InvalidInput,
+                        &format!(
+                            "the {} output format can't be printed. use --output instead.",
+                            cmd.output_format
+                        ),
+                    )
+                }
+
+                if cmd.output_format == "sqlite" {
+                    let output_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dump.db")
+                            .await?;
+                    build_sqlite(&result).write(&output_path)?;
+                } else {
+                    let output_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dump.parquet")
+                            .await?;
+                    build_parquet(&result).write(&output_path)?;
+                }
             } else {
+                let mut lines = Vec::new();
+
+                // a diff shows each slot's old and new values, and the transactions which changed
+                // it, rather than its current value
+                if cmd.diff {
+                    lines.push(String::from(
+                        "last_modified,slot,decoded_type,old_value,new_value,transactions",
+                    ));
+                    for row in &result {
+                        lines.push(format!(
+                            "{},{},{},{},{},{}",
+                            row.last_modified,
+                            row.slot,
+                            row.decoded_type,
+                            row.previous_value.as_deref().unwrap_or_default(),
+                            row.value,
+                            row.modifiers
+                                .iter()
+                                .map(|(_, transaction_hash)| transaction_hash.as_str())
+                                .collect::<Vec<_>>()
+                                .join(";")
+                        ));
+                    }
+                } else {
+                    // add header
+                    lines.push(String::from("last_modified,alias,slot,decoded_type,value"));
+
+                    // add rows
+                    for row in &result {
+                        lines.push(format!(
+                            "{},{},{},{},{}",
+                            row.last_modified, row.alias, row.slot, row.decoded_type, row.value
+                        ));
+                    }
+                }
+
+                if cmd.output == "print" {
+                    // the decoded slots are printed after the dump, rather than written
+                    if !decoded_lines.is_empty() {
+                        lines.push(String::new());
+                        lines.append(&mut decoded_lines);
+                    }
+                    print_with_less(&lines.join("\n")).await?;
+                } else {
+                    let output_path =
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dump.csv")
+                            .await?;
+
+                    write_lines_to_file(&output_path, lines)?;
+                }
+            }
+
+            if !json && !decoded_lines.is_empty() {
                 let output_path =
-                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dump.csv").await?;
+                    build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dump.decoded.csv")
+                        .await?;
+                write_lines_to_file(&output_path, decoded_lines)?;
+            }
 
-                write_lines_to_file(&output_path, lines);
+            // the proofs are written with the account proof they depend on, so the dump can be
+            // verified against the state root without trusting heimdall or the RPC provider
+            let proofs = result
+                .iter()
+                .filter_map(|row| Some((row, row.proof.as_ref()?)))
+                .collect::<Vec<_>>();
+            if let Some((_, first)) = proofs.first() {
+                if !json && cmd.output != "print" {
+                    let snapshot = serde_json::json!({
+                        "address": cmd.target,
+                        "blockNumber": first.account.block_number,
+                        "stateRoot": first.account.state_root,
+                        "storageHash": first.account.storage_hash,
+                        "accountProof": first.account.proof,
+                        "storageProof": proofs
+                            .iter()
+                            .map(|(row, proof)| {
+                                serde_json::json!({
+                                    "key": format!("0x{}", row.slot),
+                                    "value": proof.value,
+                                    "proof": proof.proof,
+                                    "verified": proof.verified,
+                                })
+                            })
+                            .collect::<Vec<_>>(),
+                    });
+                    let output_path = build_output_path(
+                        &cmd.output,
+                        &cmd.target,
+                        &cmd.rpc_url,
+                        "dump.proof.json",
+                    )
+                    .await?;
+                    write_file(&output_path, &serde_json::to_string_pretty(&snapshot)?)?;
+                }
+            }
+
+            // new storage writes are appended to the feed as each block is traced, or printed
+            if cmd.watch {
+                let output_path = match cmd.output.as_str() {
+                    _ if json => None,
+                    "print" => None,
+                    _ => Some(
+                        build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "dump.watch.csv")
+                            .await?,
+                    ),
+                };
+                if let Some(output_path) = &output_path {
+                    if !std::path::Path::new(output_path).exists() {
+                        append_lines_to_file(
+                            output_path,
+                            vec![String::from(
+                                "block_number,transaction_hash,slot,old_value,new_value",
+                            )],
+                        )?;
+                    }
+                }
+
+                watch(&cmd, |write| {
+                    if json {
+                        match json_document("dump.watch", write) {
+                            Ok(document) => println!("{document}"),
+                            Err(e) => {
+                                Logger::default().error(&format!("failed to serialize write: {e}"))
+                            }
+                        }
+                        return
+                    }
+
+                    let line = format!(
+                        "{},{},{:?},{:?},{:?}",
+                        write.block_number,
+                        write.transaction_hash,
+                        write.slot,
+                        write.old_value,
+                        write.new_value
+                    );
+                    match &output_path {
+                        Some(output_path) => {
+                            if let Err(e) = append_lines_to_file(output_path, vec![line]) {
+                                Logger::default()
+                                    .error(&format!("failed to write storage change: {e}"))
+                            }
+                        }
+                        None => println!("{line}"),
+                    }
+                })
+                .await?;
             }
         }
 
@@ -261,6 +1309,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 cmd.rpc_url = configuration.rpc_url;
             }
 
+            // any limits the user has not specified are taken from the configuration
+            cmd.limits = cmd.limits.with_defaults(&configuration.limits);
+
             let snapshot_result = snapshot(cmd.clone()).await?;
             let csv_lines = generate_csv(
                 &snapshot_result.snapshots,
@@ -268,19 +1319,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 &snapshot_result.resolved_events,
             );
 
-            if cmd.output == "print" {
+            if json {
+                print_json(
+                    "snapshot",
+                    &generate_json(
+                        &snapshot_result.snapshots,
+                        &snapshot_result.resolved_errors,
+                        &snapshot_result.resolved_events,
+                    ),
+                )?;
+            } else if cmd.output == "print" {
                 print_with_less(&csv_lines.join("\n")).await?;
             } else {
                 let output_path =
                     build_output_path(&cmd.output, &cmd.target, &cmd.rpc_url, "snapshot.csv")
                         .await?;
 
-                write_lines_to_file(&output_path, csv_lines);
+                write_lines_to_file(&output_path, csv_lines)?;
             }
         }
 
         Subcommands::Config(cmd) => {
-            config(cmd);
+            config(cmd)?;
         }
 
         Subcommands::Cache(cmd) => {
@@ -288,6 +1348,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // the update notice would corrupt the JSON document on stdout, and isn't useful in CI
+    if json || args.ci {
+        return Ok(())
+    }
+
     // check if the version is up to date
     let remote_version = remote_version().await;
     let current_version = current_version();
@@ -1,10 +1,55 @@
-use std::{env, io::Write};
+use std::{env, io::Write, sync::OnceLock};
 
-use heimdall_common::{constants::ADDRESS_REGEX, ether::rpc};
+use heimdall_common::{constants::ADDRESS_REGEX, ether::rpc, utils::io::json::json_document};
+use serde::Serialize;
+use serde_json::Value;
+
+// the directory default output is written to, relative to the working directory
+static OUTPUT_DIR: OnceLock<String> = OnceLock::new();
+
+// the module which is writing output, for the `{module}` placeholder
+static OUTPUT_MODULE: OnceLock<String> = OnceLock::new();
+
+/// Sets the directory default output is written to, in place of `output`. Only the first call has
+/// any effect.
+pub fn set_output_dir(output_dir: &str) {
+    let _ = OUTPUT_DIR.set(output_dir.to_string());
+}
+
+/// Sets the module which is writing output, such as `decompile`. Only the first call has any
+/// effect.
+pub fn set_output_module(module: &str) {
+    let _ = OUTPUT_MODULE.set(module.to_string());
+}
+
+/// Fill the placeholders of an output directory template, such as `out/{chain}/{address}/{module}`.
+/// `{chain}` and `{address}` are `local` if the target isn't a contract address. If the template
+/// has no `{filename}` placeholder, the filename is written inside the directory, and otherwise
+/// the template is the file's path, such as `out/{address}.{filename}` for a flat layout.
+pub fn fill_output_template(
+    template: &str,
+    chain: &str,
+    address: &str,
+    module: &str,
+    filename: &str,
+) -> String {
+    let path = template
+        .replace("{chain}", chain)
+        .replace("{address}", address)
+        .replace("{module}", module);
+
+    match path.contains("{filename}") {
+        true => path.replace("{filename}", filename),
+        false => format!("{}/{}", path.trim_end_matches('/'), filename),
+    }
+}
 
 /// build a standardized output path for the given parameters. follows the following cases:
 /// - if `output` is `print`, return `None`
-/// - if `output` is the default value (`output`)
+/// - if `output` is the default value (`output`), write to the configured output directory, which
+///   defaults to `output`
+///   - if the directory is a template with placeholders, return the filled template, see
+///     [`fill_output_template`]
 ///   - if `target` is a contract_address, return `/output/{chain_id}/{target}/{filename}`
 ///   - if `target` is a file or raw bytes, return `/output/local/{filename}`
 /// - if `output` is specified, return `/{output}/{filename}`
@@ -16,14 +61,34 @@ pub async fn build_output_path(
 ) -> Result<String, Box<dyn std::error::Error>> {
     // if output is the default value, build a path based on the target
     if output == "output" {
-        // get the current working directory
-        let cwd = env::current_dir()?.into_os_string().into_string().unwrap();
+        // the output directory is relative to the current working directory, unless it's absolute
+        let output_dir = env::current_dir()?
+            .join(OUTPUT_DIR.get().map(String::as_str).unwrap_or("output"))
+            .into_os_string()
+            .into_string()
+            .unwrap();
+
+        // templates are filled, only looking up the chain if it's used
+        if output_dir.contains('{') {
+            let is_address = ADDRESS_REGEX.is_match(target)?;
+            let chain = match is_address && output_dir.contains("{chain}") {
+                true => rpc::chain_id(rpc_url).await?.to_string(),
+                false => String::from("local"),
+            };
+            return Ok(fill_output_template(
+                &output_dir,
+                &chain,
+                if is_address { target } else { "local" },
+                OUTPUT_MODULE.get().map(String::as_str).unwrap_or("heimdall"),
+                filename,
+            ))
+        }
 
         if ADDRESS_REGEX.is_match(target)? {
             let chain_id = rpc::chain_id(rpc_url).await?;
-            return Ok(format!("{}/output/{}/{}/{}", cwd, chain_id, target, filename))
+            return Ok(format!("{}/{}/{}/{}", output_dir, chain_id, target, filename))
         } else {
-            return Ok(format!("{}/output/local/{}", cwd, filename))
+            return Ok(format!("{}/local/{}", output_dir, filename))
         }
     }
 
@@ -43,6 +108,46 @@ pub async fn print_with_less(input: &str) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+/// print a module's result to stdout as a versioned JSON document, for `--json`
+pub fn print_json<T: Serialize>(
+    module: &str,
+    result: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(&json_document(module, result)?)?);
+    Ok(())
+}
+
+/// print the result of one of the targets read from stdin as a single line of JSON, with the
+/// target it's for, or the error the target failed with in place of its result
+pub fn print_json_line<T: Serialize>(
+    module: &str,
+    target: &str,
+    result: Result<T, String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", json_line(module, target, result)?);
+    Ok(())
+}
+
+// build the line of JSON printed for one of the targets read from stdin
+fn json_line<T: Serialize>(
+    module: &str,
+    target: &str,
+    result: Result<T, String>,
+) -> Result<Value, serde_json::Error> {
+    let mut document = match &result {
+        Ok(result) => json_document(module, result)?,
+        Err(_) => json_document(module, &Value::Null)?,
+    };
+    if let Some(fields) = document.as_object_mut() {
+        fields.insert(String::from("target"), Value::from(target));
+        if let Err(e) = result {
+            fields.remove("result");
+            fields.insert(String::from("error"), Value::from(e));
+        }
+    }
+    Ok(document)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +190,37 @@ mod tests {
         assert!(path.is_ok());
         assert_eq!(path.unwrap(), "/some_dir/cfg.dot".to_string());
     }
+
+    #[test]
+    fn test_json_line() {
+        let line = json_line("decode", "0xa9059cbb", Ok(serde_json::json!({ "name": "transfer" })))
+            .unwrap();
+        assert_eq!(line["schema"], "heimdall.decode.v1");
+        assert_eq!(line["target"], "0xa9059cbb");
+        assert_eq!(line["result"]["name"], "transfer");
+
+        let line = json_line::<Value>("decode", "0x", Err(String::from("invalid target"))).unwrap();
+        assert_eq!(line["error"], "invalid target");
+        assert!(line.get("result").is_none());
+    }
+
+    #[test]
+    fn test_fill_output_template() {
+        let address = "0x0000000000000000000000000000000000000001";
+
+        assert_eq!(
+            fill_output_template("out/{chain}/{address}/{module}", "1", address, "cfg", "cfg.dot"),
+            format!("out/1/{address}/cfg/cfg.dot")
+        );
+        assert_eq!(
+            fill_output_template("out/{module}/", "local", "local", "decompile", "abi.json"),
+            "out/decompile/abi.json"
+        );
+
+        // flat layouts name each file, rather than a directory
+        assert_eq!(
+            fill_output_template("out/{address}.{filename}", "1", address, "cfg", "cfg.dot"),
+            format!("out/{address}.cfg.dot")
+        );
+    }
 }
@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod integration_tests {
+    use std::{path::PathBuf, process::Command};
+
+    #[test]
+    fn test_decompile_deterministic_output_is_identical() {
+        let home = std::env::temp_dir().join("heimdall_test_deterministic");
+        let target =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/testdata/uniswap_v2_pair.bin");
+
+        // decompile the same contract twice, reading back everything that was written
+        let outputs = (0..2)
+            .map(|run| {
+                let output = home.join(format!("run_{run}"));
+                let _ = std::fs::remove_dir_all(&output);
+
+                let status = Command::new(env!("CARGO_BIN_EXE_heimdall"))
+                    .arg("decompile")
+                    .arg(&target)
+                    .args(["--deterministic", "--skip-resolving", "--include-sol", "-o"])
+                    .arg(&output)
+                    .env("HOME", &home)
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .status()
+                    .unwrap();
+                assert!(status.success());
+
+                ["abi.json", "decompiled.sol"]
+                    .map(|file| std::fs::read_to_string(output.join(file)).unwrap())
+            })
+            .collect::<Vec<_>>();
+
+        assert!(outputs[0][1].contains("function "));
+        assert_eq!(outputs[0], outputs[1]);
+    }
+}
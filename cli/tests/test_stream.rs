@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod integration_tests {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    use serde_json::Value;
+
+    #[test]
+    fn test_decompile_stream_continues_after_failures() {
+        // an empty configuration, so the address below has no RPC provider to be fetched with
+        let home = std::env::temp_dir().join("heimdall_test_decompile_stream");
+        std::fs::create_dir_all(&home).unwrap();
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_heimdall"))
+            .args(["decompile", "-", "--skip-resolving"])
+            .env("HOME", &home)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(
+                b"0x6001600201\n0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2\n./missing.bin\n0x60016002\n",
+            )
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+
+        let lines = String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let targets = lines.iter().map(|line| line["target"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            [
+                "0x6001600201",
+                "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                "./missing.bin",
+                "0x60016002"
+            ]
+        );
+
+        assert!(lines[0].get("error").is_none());
+        assert!(lines[1]["error"].as_str().unwrap().contains("requires an RPC provider"));
+        assert!(lines[2]["error"].is_string());
+        assert!(lines[3].get("error").is_none());
+    }
+}
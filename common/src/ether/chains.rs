@@ -0,0 +1,223 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A network in heimdall's built-in chain registry, along with the defaults used when it's
+/// selected with `--chain`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain {
+    pub id: u64,
+    pub name: &'static str,
+
+    // other names the chain can be selected by
+    pub aliases: &'static [&'static str],
+
+    // a public RPC provider, used when no other provider is given
+    pub rpc_url: &'static str,
+
+    // the base URL of the chain's etherscan-compatible explorer API
+    pub explorer_api: &'static str,
+
+    // the base URL of the chain's Blockscout API, if it has a Blockscout instance
+    pub blockscout_api: Option<&'static str>,
+
+    // the decimals of the chain's native token
+    pub decimals: u8,
+}
+
+/// Every chain in the registry.
+pub const CHAINS: &[Chain] = &[
+    Chain {
+        id: 1,
+        name: "mainnet",
+        aliases: &["ethereum", "eth"],
+        rpc_url: "https://eth.llamarpc.com",
+        explorer_api: "https://api.etherscan.io/api",
+        blockscout_api: Some("https://eth.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 11155111,
+        name: "sepolia",
+        aliases: &[],
+        rpc_url: "https://rpc.sepolia.org",
+        explorer_api: "https://api-sepolia.etherscan.io/api",
+        blockscout_api: Some("https://eth-sepolia.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 5,
+        name: "goerli",
+        aliases: &[],
+        rpc_url: "https://rpc.ankr.com/eth_goerli",
+        explorer_api: "https://api-goerli.etherscan.io/api",
+        blockscout_api: Some("https://eth-goerli.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 17000,
+        name: "holesky",
+        aliases: &[],
+        rpc_url: "https://ethereum-holesky-rpc.publicnode.com",
+        explorer_api: "https://api-holesky.etherscan.io/api",
+        blockscout_api: Some("https://eth-holesky.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 42161,
+        name: "arbitrum",
+        aliases: &["arbitrum-one", "arb"],
+        rpc_url: "https://arb1.arbitrum.io/rpc",
+        explorer_api: "https://api.arbiscan.io/api",
+        blockscout_api: Some("https://arbitrum.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 10,
+        name: "optimism",
+        aliases: &["op"],
+        rpc_url: "https://mainnet.optimism.io",
+        explorer_api: "https://api-optimistic.etherscan.io/api",
+        blockscout_api: Some("https://optimism.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 8453,
+        name: "base",
+        aliases: &[],
+        rpc_url: "https://mainnet.base.org",
+        explorer_api: "https://api.basescan.org/api",
+        blockscout_api: Some("https://base.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 56,
+        name: "bsc",
+        aliases: &["bnb", "binance"],
+        rpc_url: "https://bsc-dataseed.bnbchain.org",
+        explorer_api: "https://api.bscscan.com/api",
+        blockscout_api: None,
+        decimals: 18,
+    },
+    Chain {
+        id: 137,
+        name: "polygon",
+        aliases: &["matic"],
+        rpc_url: "https://polygon-rpc.com",
+        explorer_api: "https://api.polygonscan.com/api",
+        blockscout_api: Some("https://polygon.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 43114,
+        name: "avalanche",
+        aliases: &["avax"],
+        rpc_url: "https://api.avax.network/ext/bc/C/rpc",
+        explorer_api: "https://api.routescan.io/v2/network/mainnet/evm/43114/etherscan/api",
+        blockscout_api: None,
+        decimals: 18,
+    },
+    Chain {
+        id: 100,
+        name: "gnosis",
+        aliases: &["xdai"],
+        rpc_url: "https://rpc.gnosischain.com",
+        explorer_api: "https://api.gnosisscan.io/api",
+        blockscout_api: Some("https://gnosis.blockscout.com/api"),
+        decimals: 18,
+    },
+    Chain {
+        id: 250,
+        name: "fantom",
+        aliases: &["ftm"],
+        rpc_url: "https://rpc.ftm.tools",
+        explorer_api: "https://api.ftmscan.com/api",
+        blockscout_api: None,
+        decimals: 18,
+    },
+    Chain {
+        id: 7700,
+        name: "canto",
+        aliases: &[],
+        rpc_url: "https://canto.slingshot.finance",
+        explorer_api: "https://tuber.build/api",
+        blockscout_api: Some("https://tuber.build/api"),
+        decimals: 18,
+    },
+];
+
+/// Looks up a chain in the registry by its id.
+///
+/// ```
+/// use heimdall_common::ether::chains::chain_by_id;
+///
+/// assert_eq!(chain_by_id(8453).unwrap().name, "base");
+/// assert!(chain_by_id(0).is_none());
+/// ```
+pub fn chain_by_id(id: u64) -> Option<&'static Chain> {
+    CHAINS.iter().find(|chain| chain.id == id)
+}
+
+impl FromStr for Chain {
+    type Err = String;
+
+    /// Looks up a chain in the registry by its name, one of its aliases, or its id.
+    ///
+    /// ```
+    /// use heimdall_common::ether::chains::Chain;
+    ///
+    /// assert_eq!("Arbitrum".parse::<Chain>().unwrap().id, 42161);
+    /// assert_eq!("137".parse::<Chain>().unwrap().name, "polygon");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let chain = match s.parse::<u64>() {
+            Ok(id) => chain_by_id(id),
+            Err(_) => {
+                CHAINS.iter().find(|chain| chain.name == s || chain.aliases.contains(&s.as_str()))
+            }
+        };
+
+        chain.cloned().ok_or_else(|| {
+            format!(
+                "unknown chain '{s}'. known chains: {}",
+                CHAINS.iter().map(|chain| chain.name).collect::<Vec<&str>>().join(", ")
+            )
+        })
+    }
+}
+
+impl Display for Chain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::ether::chains::{Chain, CHAINS};
+
+    #[test]
+    fn test_chains_are_unique() {
+        let mut ids = HashSet::new();
+        let mut names = HashSet::new();
+        for chain in CHAINS {
+            assert!(ids.insert(chain.id));
+            for name in std::iter::once(&chain.name).chain(chain.aliases) {
+                assert!(names.insert(*name), "'{name}' selects more than one chain");
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_chain() {
+        assert_eq!("eth".parse::<Chain>().unwrap().id, 1);
+        assert_eq!(" 10 ".parse::<Chain>().unwrap().name, "optimism");
+
+        // every chain the dump module supports can be selected
+        for chain in ["ethereum", "polygon", "goerli", "canto", "arbitrum"] {
+            assert!(chain.parse::<Chain>().is_ok());
+        }
+        assert!("zksync".parse::<Chain>().unwrap_err().contains("known chains: mainnet"));
+    }
+}
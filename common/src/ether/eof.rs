@@ -0,0 +1,464 @@
+use super::evm::core::opcodes::Opcode;
+
+/// The magic bytes which prefix every EOF container.
+pub const EOF_MAGIC: [u8; 2] = [0xef, 0x00];
+
+/// The `outputs` value of a code section which never returns to its caller.
+pub const EOF_NON_RETURNING: u8 = 0x80;
+
+// section kinds within the EOF header
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+/// An error encountered while parsing or validating an EOF container.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EofError {
+    #[error("bytecode is not an EOF container")]
+    InvalidMagic,
+    #[error("unsupported EOF version {0}")]
+    UnsupportedVersion(u8),
+    #[error("EOF header is truncated")]
+    TruncatedHeader,
+    #[error("expected EOF section kind 0x{expected:02x}, found 0x{found:02x}")]
+    InvalidSectionKind { expected: u8, found: u8 },
+    #[error("EOF container must have between 1 and 1024 code sections, found {0}")]
+    InvalidCodeSectionCount(usize),
+    #[error("EOF types section is {0} bytes, which doesn't match the number of code sections")]
+    InvalidTypesSize(usize),
+    #[error("EOF code section {0} is empty")]
+    EmptyCodeSection(usize),
+    #[error("EOF container is {found} bytes, but its header declares {expected} bytes")]
+    InvalidContainerSize { expected: usize, found: usize },
+    #[error("the first EOF code section must take no inputs and be non-returning")]
+    InvalidEntryType,
+    #[error("undefined opcode 0x{opcode:02x} at offset {pc} of EOF code section {section}")]
+    UndefinedOpcode { section: usize, pc: usize, opcode: u8 },
+    #[error("truncated immediate at offset {pc} of EOF code section {section}")]
+    TruncatedImmediate { section: usize, pc: usize },
+    #[error("invalid relative jump at offset {pc} of EOF code section {section}")]
+    InvalidJumpTarget { section: usize, pc: usize },
+    #[error("call to missing code section {target} at offset {pc} of EOF code section {section}")]
+    InvalidSectionTarget { section: usize, pc: usize, target: usize },
+}
+
+/// A single code section of an [`EofContainer`], along with its entry in the types section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EofCodeSection {
+    // the offset of the section within the container
+    pub offset: usize,
+
+    // the number of stack items the section consumes
+    pub inputs: u8,
+
+    // the number of stack items the section returns, or EOF_NON_RETURNING
+    pub outputs: u8,
+
+    // the maximum height of the stack while executing the section
+    pub max_stack_height: u16,
+
+    // the section's bytecode
+    pub code: Vec<u8>,
+}
+
+/// A parsed and validated EOF (EIP-3540) container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EofContainer {
+    pub version: u8,
+    pub code_sections: Vec<EofCodeSection>,
+    pub container_sections: Vec<Vec<u8>>,
+
+    // the offset of the data section within the container
+    pub data_offset: usize,
+    pub data: Vec<u8>,
+}
+
+/// A single decoded instruction within an EOF code section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EofInstruction {
+    // the offset of the instruction within its code section
+    pub pc: usize,
+    pub opcode: Opcode,
+    pub immediate: Vec<u8>,
+}
+
+/// Whether or not the given bytecode is an EOF container.
+///
+/// ```
+/// use heimdall_common::ether::eof::is_eof;
+///
+/// assert!(is_eof(&[0xef, 0x00, 0x01]));
+/// assert!(!is_eof(&[0x60, 0x80]));
+/// ```
+pub fn is_eof(bytecode: &[u8]) -> bool {
+    bytecode.starts_with(&EOF_MAGIC)
+}
+
+/// Returns the [`Opcode`] for instructions which only exist within EOF code sections.
+pub fn eof_opcode(code: u8) -> Option<Opcode> {
+    match code {
+        0xe0 => Some(Opcode { code, name: "RJUMP", mingas: 2, inputs: 0, outputs: 0 }),
+        0xe1 => Some(Opcode { code, name: "RJUMPI", mingas: 4, inputs: 1, outputs: 0 }),
+        0xe2 => Some(Opcode { code, name: "RJUMPV", mingas: 4, inputs: 1, outputs: 0 }),
+        0xe3 => Some(Opcode { code, name: "CALLF", mingas: 5, inputs: 0, outputs: 0 }),
+        0xe4 => Some(Opcode { code, name: "RETF", mingas: 3, inputs: 0, outputs: 0 }),
+        0xe5 => Some(Opcode { code, name: "JUMPF", mingas: 5, inputs: 0, outputs: 0 }),
+        _ => None,
+    }
+}
+
+/// Returns the [`Opcode`] for the given byte within an EOF code section, or `None` if it is
+/// undefined or deprecated by EIP-3670 and EIP-4750.
+fn opcode_in_eof(code: u8) -> Option<Opcode> {
+    match code {
+        // JUMP, JUMPI, PC, CALLCODE, and SELFDESTRUCT are invalid in EOF code
+        0x56 | 0x57 | 0x58 | 0xf2 | 0xff => None,
+        _ => eof_opcode(code).or_else(|| {
+            let opcode = Opcode::new(code);
+            (opcode.name != "unknown").then_some(opcode)
+        }),
+    }
+}
+
+/// Whether or not execution of an EOF basic block ends after the given instruction.
+pub fn is_eof_terminator(opcode: &Opcode) -> bool {
+    matches!(
+        opcode.name,
+        "STOP" | "RETURN" | "REVERT" | "INVALID" | "RJUMP" | "RJUMPI" | "RJUMPV" | "RETF" | "JUMPF"
+    )
+}
+
+impl EofInstruction {
+    /// The offsets of all instructions this instruction may jump to, relative to the start of
+    /// its code section.
+    pub fn jump_targets(&self) -> Vec<isize> {
+        let next = (self.pc + 1 + self.immediate.len()) as isize;
+        let relative_offset =
+            |bytes: &[u8]| i16::from_be_bytes([bytes[0], bytes[1]]) as isize + next;
+
+        match self.opcode.name {
+            "RJUMP" | "RJUMPI" => vec![relative_offset(&self.immediate)],
+            "RJUMPV" => self.immediate[1..].chunks(2).map(relative_offset).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The code section this instruction calls or jumps to, if it is a CALLF or JUMPF.
+    pub fn section_target(&self) -> Option<usize> {
+        match self.opcode.name {
+            "CALLF" | "JUMPF" => {
+                Some(u16::from_be_bytes([self.immediate[0], self.immediate[1]]) as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Decode the instructions of a single EOF code section, returning an error if the section
+/// contains undefined opcodes or truncated immediates.
+pub fn decode_eof_instructions(
+    code: &[u8],
+    section: usize,
+) -> Result<Vec<EofInstruction>, EofError> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        let opcode = opcode_in_eof(code[pc]).ok_or(EofError::UndefinedOpcode {
+            section,
+            pc,
+            opcode: code[pc],
+        })?;
+
+        // determine the size of the instruction's immediate
+        let immediate_size = match opcode.code {
+            0x60..=0x7f => (opcode.code - 0x5f) as usize,
+            0xe0 | 0xe1 | 0xe3 | 0xe5 => 2,
+            0xe2 => match code.get(pc + 1) {
+                Some(max_index) => 1 + (*max_index as usize + 1) * 2,
+                None => return Err(EofError::TruncatedImmediate { section, pc }),
+            },
+            _ => 0,
+        };
+
+        let immediate = code
+            .get(pc + 1..pc + 1 + immediate_size)
+            .ok_or(EofError::TruncatedImmediate { section, pc })?
+            .to_vec();
+
+        instructions.push(EofInstruction { pc, opcode, immediate });
+        pc += 1 + immediate_size;
+    }
+
+    Ok(instructions)
+}
+
+// read a big-endian u16 from the header, advancing the cursor
+fn read_u16(bytecode: &[u8], cursor: &mut usize) -> Result<usize, EofError> {
+    let bytes = bytecode.get(*cursor..*cursor + 2).ok_or(EofError::TruncatedHeader)?;
+    *cursor += 2;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+// read a section kind from the header, advancing the cursor
+fn read_kind(bytecode: &[u8], cursor: &mut usize) -> Result<u8, EofError> {
+    let kind = *bytecode.get(*cursor).ok_or(EofError::TruncatedHeader)?;
+    *cursor += 1;
+    Ok(kind)
+}
+
+fn expect_kind(bytecode: &[u8], cursor: &mut usize, expected: u8) -> Result<(), EofError> {
+    match read_kind(bytecode, cursor)? {
+        found if found == expected => Ok(()),
+        found => Err(EofError::InvalidSectionKind { expected, found }),
+    }
+}
+
+impl EofContainer {
+    /// Parse and validate an EOF container, as specified by EIP-3540, EIP-3670, and EIP-4750.
+    ///
+    /// ```
+    /// use heimdall_common::ether::eof::EofContainer;
+    ///
+    /// // a container with a single code section containing STOP
+    /// let bytecode = [
+    ///     0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x01, 0x04, 0x00, 0x00,
+    ///     0x00, 0x00, 0x80, 0x00, 0x00, 0x00,
+    /// ];
+    ///
+    /// let container = EofContainer::parse(&bytecode).unwrap();
+    /// assert_eq!(container.code_sections.len(), 1);
+    /// assert_eq!(container.code_sections[0].code, vec![0x00]);
+    /// ```
+    pub fn parse(bytecode: &[u8]) -> Result<EofContainer, EofError> {
+        if !is_eof(bytecode) {
+            return Err(EofError::InvalidMagic)
+        }
+
+        let version = *bytecode.get(2).ok_or(EofError::TruncatedHeader)?;
+        if version != 1 {
+            return Err(EofError::UnsupportedVersion(version))
+        }
+
+        // read the header
+        let mut cursor = 3;
+        expect_kind(bytecode, &mut cursor, KIND_TYPES)?;
+        let types_size = read_u16(bytecode, &mut cursor)?;
+
+        expect_kind(bytecode, &mut cursor, KIND_CODE)?;
+        let code_section_count = read_u16(bytecode, &mut cursor)?;
+        if !(1..=1024).contains(&code_section_count) {
+            return Err(EofError::InvalidCodeSectionCount(code_section_count))
+        }
+        if types_size != code_section_count * 4 {
+            return Err(EofError::InvalidTypesSize(types_size))
+        }
+        let code_sizes = (0..code_section_count)
+            .map(|_| read_u16(bytecode, &mut cursor))
+            .collect::<Result<Vec<usize>, EofError>>()?;
+        if let Some(section) = code_sizes.iter().position(|size| *size == 0) {
+            return Err(EofError::EmptyCodeSection(section))
+        }
+
+        // container sections are optional
+        let mut container_sizes = Vec::new();
+        let mut kind = read_kind(bytecode, &mut cursor)?;
+        if kind == KIND_CONTAINER {
+            let container_count = read_u16(bytecode, &mut cursor)?;
+            container_sizes = (0..container_count)
+                .map(|_| read_u16(bytecode, &mut cursor))
+                .collect::<Result<Vec<usize>, EofError>>()?;
+            kind = read_kind(bytecode, &mut cursor)?;
+        }
+        if kind != KIND_DATA {
+            return Err(EofError::InvalidSectionKind { expected: KIND_DATA, found: kind })
+        }
+        let data_size = read_u16(bytecode, &mut cursor)?;
+        expect_kind(bytecode, &mut cursor, TERMINATOR)?;
+
+        // make sure the body matches the sizes declared in the header
+        let expected = cursor +
+            types_size +
+            code_sizes.iter().sum::<usize>() +
+            container_sizes.iter().sum::<usize>() +
+            data_size;
+        if bytecode.len() != expected {
+            return Err(EofError::InvalidContainerSize { expected, found: bytecode.len() })
+        }
+
+        // read the body
+        let types = &bytecode[cursor..cursor + types_size];
+        cursor += types_size;
+
+        let mut code_sections = Vec::new();
+        for (index, size) in code_sizes.iter().enumerate() {
+            code_sections.push(EofCodeSection {
+                offset: cursor,
+                inputs: types[index * 4],
+                outputs: types[index * 4 + 1],
+                max_stack_height: u16::from_be_bytes([types[index * 4 + 2], types[index * 4 + 3]]),
+                code: bytecode[cursor..cursor + size].to_vec(),
+            });
+            cursor += size;
+        }
+
+        let mut container_sections = Vec::new();
+        for size in container_sizes {
+            container_sections.push(bytecode[cursor..cursor + size].to_vec());
+            cursor += size;
+        }
+
+        let container = EofContainer {
+            version,
+            code_sections,
+            container_sections,
+            data_offset: cursor,
+            data: bytecode[cursor..].to_vec(),
+        };
+        container.validate()?;
+
+        Ok(container)
+    }
+
+    // validate the types and instructions of each code section
+    fn validate(&self) -> Result<(), EofError> {
+        let entry = &self.code_sections[0];
+        if entry.inputs != 0 || entry.outputs != EOF_NON_RETURNING {
+            return Err(EofError::InvalidEntryType)
+        }
+
+        for (section, code_section) in self.code_sections.iter().enumerate() {
+            let instructions = decode_eof_instructions(&code_section.code, section)?;
+
+            for instruction in &instructions {
+                // relative jumps must land on the start of an instruction
+                for target in instruction.jump_targets() {
+                    if instructions.binary_search_by_key(&target, |i| i.pc as isize).is_err() {
+                        return Err(EofError::InvalidJumpTarget { section, pc: instruction.pc })
+                    }
+                }
+
+                if let Some(target) = instruction.section_target() {
+                    if target >= self.code_sections.len() {
+                        return Err(EofError::InvalidSectionTarget {
+                            section,
+                            pc: instruction.pc,
+                            target,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::eof::*;
+
+    // build a container from the given code sections, where each section is (inputs, outputs,
+    // code), and the given data
+    fn build_container(sections: &[(u8, u8, &[u8])], data: &[u8]) -> Vec<u8> {
+        let mut bytecode = vec![0xef, 0x00, 0x01, 0x01];
+        bytecode.extend(((sections.len() * 4) as u16).to_be_bytes());
+        bytecode.push(0x02);
+        bytecode.extend((sections.len() as u16).to_be_bytes());
+        for (_, _, code) in sections {
+            bytecode.extend((code.len() as u16).to_be_bytes());
+        }
+        bytecode.push(0x04);
+        bytecode.extend((data.len() as u16).to_be_bytes());
+        bytecode.push(0x00);
+        for (inputs, outputs, _) in sections {
+            bytecode.extend([*inputs, *outputs, 0x00, 0x10]);
+        }
+        for (_, _, code) in sections {
+            bytecode.extend(*code);
+        }
+        bytecode.extend(data);
+        bytecode
+    }
+
+    #[test]
+    fn test_parse_sections() {
+        // CALLF 1, STOP | PUSH1 0x01 RETF
+        let bytecode = build_container(
+            &[(0, 0x80, &[0xe3, 0x00, 0x01, 0x00]), (0, 1, &[0x60, 0x01, 0xe4])],
+            &[0xaa, 0xbb],
+        );
+        let container = EofContainer::parse(&bytecode).unwrap();
+
+        assert_eq!(container.code_sections.len(), 2);
+        assert_eq!(container.code_sections[0].offset, 25);
+        assert_eq!(container.code_sections[1].code, vec![0x60, 0x01, 0xe4]);
+        assert_eq!(container.code_sections[1].outputs, 1);
+        assert_eq!(container.data_offset, 32);
+        assert_eq!(container.data, vec![0xaa, 0xbb]);
+    }
+
+    #[test]
+    fn test_parse_invalid_magic() {
+        assert_eq!(EofContainer::parse(&[0x60, 0x00]), Err(EofError::InvalidMagic));
+    }
+
+    #[test]
+    fn test_parse_invalid_size() {
+        let mut bytecode = build_container(&[(0, 0x80, &[0x00])], &[]);
+        bytecode.push(0x00);
+
+        assert_eq!(
+            EofContainer::parse(&bytecode),
+            Err(EofError::InvalidContainerSize { expected: 20, found: 21 })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_legacy_jumps() {
+        // PUSH1 0x00 JUMP
+        let bytecode = build_container(&[(0, 0x80, &[0x60, 0x00, 0x56])], &[]);
+
+        assert_eq!(
+            EofContainer::parse(&bytecode),
+            Err(EofError::UndefinedOpcode { section: 0, pc: 2, opcode: 0x56 })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_jump_into_immediate() {
+        // RJUMP -2 (into its own immediate)
+        let bytecode = build_container(&[(0, 0x80, &[0xe0, 0xff, 0xfe])], &[]);
+
+        assert_eq!(
+            EofContainer::parse(&bytecode),
+            Err(EofError::InvalidJumpTarget { section: 0, pc: 0 })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_missing_section() {
+        // CALLF 1, STOP
+        let bytecode = build_container(&[(0, 0x80, &[0xe3, 0x00, 0x01, 0x00])], &[]);
+
+        assert_eq!(
+            EofContainer::parse(&bytecode),
+            Err(EofError::InvalidSectionTarget { section: 0, pc: 0, target: 1 })
+        );
+    }
+
+    #[test]
+    fn test_decode_rjumpv_targets() {
+        // PUSH0 RJUMPV [+1, +2] STOP STOP STOP
+        let instructions = decode_eof_instructions(
+            &[0x5f, 0xe2, 0x01, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00],
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(instructions.len(), 5);
+        assert_eq!(instructions[1].jump_targets(), vec![8, 9]);
+    }
+}
@@ -0,0 +1,93 @@
+use ethers::types::U256;
+
+use super::opcodes::Opcode;
+
+/// The ways execution can halt exceptionally, other than through REVERT, INVALID, or running out
+/// of gas.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum FaultKind {
+    #[error("stack underflow, {required} items required but {available} available")]
+    StackUnderflow { required: usize, available: usize },
+    #[error("invalid jump destination {0:#x}")]
+    InvalidJump(U256),
+    #[error("memory access of {size:#x} bytes at offset {offset:#x} is out of bounds")]
+    MemoryOutOfBounds { offset: U256, size: U256 },
+}
+
+/// An exceptional halt, along with the instruction and call context it happened in.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at pc {pc} ({}) in {address} at depth {depth}", Opcode::new(*.opcode).name)]
+pub struct Fault {
+    pub kind: FaultKind,
+
+    // the program counter and opcode of the instruction which faulted
+    pub pc: u128,
+    pub opcode: u8,
+
+    // the address of the contract executing, and how many calls deep it was
+    pub address: String,
+    pub depth: usize,
+}
+
+/// The offset and size of every range of memory the given opcode accesses, as indices into its
+/// inputs. MLOAD and MSTORE always access a word, and MSTORE8 a byte, so their sizes are given
+/// instead.
+pub(crate) fn memory_operands(opcode: u8) -> &'static [(usize, MemorySize)] {
+    match opcode {
+        // SHA3, RETURN, REVERT, LOG0 -> LOG4
+        0x20 | 0xf3 | 0xfd | 0xa0..=0xa4 => &[(0, MemorySize::Input(1))],
+
+        // CALLDATACOPY, CODECOPY, RETURNDATACOPY
+        0x37 | 0x39 | 0x3e => &[(0, MemorySize::Input(2))],
+
+        // EXTCODECOPY
+        0x3c => &[(1, MemorySize::Input(3))],
+
+        // MLOAD, MSTORE, MSTORE8
+        0x51 | 0x52 => &[(0, MemorySize::Fixed(32))],
+        0x53 => &[(0, MemorySize::Fixed(1))],
+
+        // MCOPY
+        0x5e => &[(0, MemorySize::Input(2)), (1, MemorySize::Input(2))],
+
+        // CREATE, CREATE2
+        0xf0 | 0xf5 => &[(1, MemorySize::Input(2))],
+
+        // CALL, CALLCODE
+        0xf1 | 0xf2 => &[(3, MemorySize::Input(4)), (5, MemorySize::Input(6))],
+
+        // DELEGATECALL, STATICCALL
+        0xf4 | 0xfa => &[(2, MemorySize::Input(3)), (4, MemorySize::Input(5))],
+
+        _ => &[],
+    }
+}
+
+/// The size of a range of memory an opcode accesses.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MemorySize {
+    Input(usize),
+    Fixed(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use crate::ether::evm::core::fault::{Fault, FaultKind};
+
+    #[test]
+    fn test_fault_display() {
+        let fault = Fault {
+            kind: FaultKind::InvalidJump(U256::from(0x2a)),
+            pc: 3,
+            opcode: 0x56,
+            address: String::from("0x6865696d64616c6c000000000061646472657373"),
+            depth: 0,
+        };
+        assert_eq!(
+            fault.to_string(),
+            "invalid jump destination 0x2a at pc 3 (JUMP) in 0x6865696d64616c6c000000000061646472657373 at depth 0"
+        );
+    }
+}
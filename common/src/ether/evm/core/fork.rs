@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use ethers::{
+    prelude::U256,
+    providers::Middleware,
+    types::{Address, BigEndianHash, BlockId, BlockNumber, H256},
+};
+use heimdall_cache::{read_cache, store_cache};
+
+use crate::{
+    ether::{
+        pool::RpcPool,
+        rpc::{chain_id, rpc_backoff, rpc_cache_enabled},
+    },
+    utils::{
+        io::logging::Logger,
+        strings::{decode_hex, encode_hex},
+    },
+};
+
+/// The [`Fork`] struct is a state backend for the [`VM`](super::vm::VM), which lazily fetches
+/// storage slots, balances, and code from an RPC provider. \
+/// \
+/// Fetched state is cached in memory and shared between clones of the [`Fork`], so each value is
+/// only fetched once. When forking from a fixed block, fetched state can never change, so it's
+/// also kept in heimdall's cache.
+#[derive(Clone, Debug)]
+pub struct Fork {
+    pub rpc_url: String,
+    pub block: Option<u64>,
+    state: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+/// A single piece of account state which can be fetched from the node.
+enum Query {
+    Storage(Address, H256),
+    Balance(Address),
+    Code(Address),
+}
+
+impl Fork {
+    /// Creates a new [`Fork`] of the chain served by the given RPC provider, at the given block
+    /// number, or the latest block if none is given.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::fork::Fork;
+    ///
+    /// let fork = Fork::new("https://eth.llamarpc.com", Some(18_000_000));
+    /// ```
+    pub fn new(rpc_url: &str, block: Option<u64>) -> Fork {
+        Fork { rpc_url: rpc_url.to_string(), block, state: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Gets the value of the given storage slot of the given account.
+    ///
+    /// ```
+    /// use ethers::prelude::U256;
+    /// use heimdall_common::ether::evm::core::fork::Fork;
+    ///
+    /// let fork = Fork::new("https://eth.llamarpc.com", None);
+    /// fork.set_storage(U256::from(1), U256::zero(), U256::from(2));
+    ///
+    /// assert_eq!(fork.storage(U256::from(1), U256::zero()).unwrap(), U256::from(2));
+    /// ```
+    pub fn storage(&self, address: U256, slot: U256) -> Result<U256, Box<dyn std::error::Error>> {
+        let value = self.get(
+            storage_key(address, slot),
+            Query::Storage(to_address(address), H256::from_uint(&slot)),
+        )?;
+        Ok(U256::from_big_endian(&value))
+    }
+
+    /// Gets the balance of the given account, in wei.
+    ///
+    /// ```
+    /// use ethers::prelude::U256;
+    /// use heimdall_common::ether::evm::core::fork::Fork;
+    ///
+    /// let fork = Fork::new("https://eth.llamarpc.com", None);
+    /// fork.set_balance(U256::from(1), U256::from(100));
+    ///
+    /// assert_eq!(fork.balance(U256::from(1)).unwrap(), U256::from(100));
+    /// ```
+    pub fn balance(&self, address: U256) -> Result<U256, Box<dyn std::error::Error>> {
+        let value = self.get(balance_key(address), Query::Balance(to_address(address)))?;
+        Ok(U256::from_big_endian(&value))
+    }
+
+    /// Gets the runtime bytecode of the given account, which is empty for accounts without code.
+    ///
+    /// ```
+    /// use ethers::prelude::U256;
+    /// use heimdall_common::ether::evm::core::fork::Fork;
+    ///
+    /// let fork = Fork::new("https://eth.llamarpc.com", None);
+    /// fork.set_code(U256::from(1), vec![0x00]);
+    ///
+    /// assert_eq!(fork.code(U256::from(1)).unwrap(), vec![0x00]);
+    /// ```
+    pub fn code(&self, address: U256) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.get(code_key(address), Query::Code(to_address(address)))
+    }
+
+    /// Overrides the value of the given storage slot, so it's never fetched from the node.
+    pub fn set_storage(&self, address: U256, slot: U256, value: U256) {
+        self.state
+            .lock()
+            .unwrap()
+            .insert(storage_key(address, slot), H256::from_uint(&value).as_bytes().to_vec());
+    }
+
+    /// Overrides the balance of the given account, so it's never fetched from the node.
+    pub fn set_balance(&self, address: U256, balance: U256) {
+        self.state
+            .lock()
+            .unwrap()
+            .insert(balance_key(address), H256::from_uint(&balance).as_bytes().to_vec());
+    }
+
+    /// Overrides the code of the given account, so it's never fetched from the node.
+    pub fn set_code(&self, address: U256, code: Vec<u8>) {
+        self.state.lock().unwrap().insert(code_key(address), code);
+    }
+
+    /// Gets the state with the given key from memory, heimdall's cache, or the node, in that
+    /// order.
+    fn get(&self, key: String, query: Query) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        if let Some(value) = self.state.lock().unwrap().get(&key) {
+            return Ok(value.clone())
+        }
+
+        // state at a fixed block can be shared between runs
+        let cache_key = match self.block {
+            Some(block) if rpc_cache_enabled() => {
+                block_on(async { chain_id(&self.rpc_url).await.unwrap_or(1) })
+                    .ok()
+                    .map(|chain_id| format!("fork.{}.{}.{}", chain_id, block, key))
+            }
+            _ => None,
+        };
+        let cached = cache_key
+            .as_ref()
+            .and_then(|cache_key| read_cache::<String>(cache_key))
+            .and_then(|value| decode_hex(&value).ok());
+
+        let value = match cached {
+            Some(value) => value,
+            None => {
+                let value = self.fetch(&query)?;
+                if let Some(cache_key) = &cache_key {
+                    store_cache(cache_key, encode_hex(value.clone()), None);
+                }
+                value
+            }
+        };
+
+        self.state.lock().unwrap().insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// Fetches the given state from the node, retrying with exponential backoff.
+    fn fetch(&self, query: &Query) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let logger = Logger::default();
+        let block = self.block.map(|block| BlockId::Number(BlockNumber::Number(block.into())));
+
+        // each attempt goes to the next endpoint in the pool, so requests fail over between them
+        let pool = RpcPool::new(&self.rpc_url);
+        if pool.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(Box::from("no RPC provider given"))
+        }
+
+        let result = block_on(backoff::future::retry(rpc_backoff(), || async {
+            let (endpoint, provider) =
+                pool.provider().await.ok_or(backoff::Error::Permanent(()))?;
+            let result = match query {
+                Query::Storage(address, slot) => provider
+                    .get_storage_at(*address, *slot, block)
+                    .await
+                    .map(|value| value.as_bytes().to_vec()),
+                Query::Balance(address) => provider
+                    .get_balance(*address, block)
+                    .await
+                    .map(|balance| H256::from_uint(&balance).as_bytes().to_vec()),
+                Query::Code(address) => {
+                    provider.get_code(*address, block).await.map(|code| code.to_vec())
+                }
+            };
+
+            match result {
+                Ok(value) => {
+                    pool.report_success(&endpoint);
+                    Ok(value)
+                }
+                Err(_) => {
+                    pool.report_failure(&endpoint);
+                    Err(backoff::Error::Transient { err: (), retry_after: None })
+                }
+            }
+        }))?;
+
+        match result {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                let (kind, address) = match query {
+                    Query::Storage(address, _) => ("storage", address),
+                    Query::Balance(address) => ("balance", address),
+                    Query::Code(address) => ("bytecode", address),
+                };
+                logger.error(&format!("failed to fetch {} of '{:?}' from node.", kind, address));
+                Err(Box::from(format!("failed to fetch {kind} from node")))
+            }
+        }
+    }
+}
+
+/// Runs the given future to completion on a new thread, so the synchronous [`VM`](super::vm::VM)
+/// can query the node whether or not it's already running inside an async runtime.
+#[cfg(not(target_arch = "wasm32"))]
+fn block_on<F>(future: F) -> Result<F::Output, Box<dyn std::error::Error>>
+where
+    F: Future + Send,
+    F::Output: Send, {
+    Ok(std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .expect("failed to build runtime")
+                    .block_on(future)
+            })
+            .join()
+            .expect("failed to query node")
+    }))
+}
+
+/// The browser can't block on a request, so the synchronous [`VM`](super::vm::VM) can't query
+/// the node when targeting wasm32, and only state which was set beforehand is available.
+#[cfg(target_arch = "wasm32")]
+fn block_on<F: Future>(_future: F) -> Result<F::Output, Box<dyn std::error::Error>> {
+    Err(Box::from("querying the node from the VM isn't supported when targeting wasm32"))
+}
+
+fn to_address(address: U256) -> Address {
+    Address::from(H256::from_uint(&address))
+}
+
+fn storage_key(address: U256, slot: U256) -> String {
+    format!("storage.{:?}.{:x}", to_address(address), slot)
+}
+
+fn balance_key(address: U256) -> String {
+    format!("balance.{:?}", to_address(address))
+}
+
+fn code_key(address: U256) -> String {
+    format!("code.{:?}", to_address(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::prelude::U256;
+
+    use crate::ether::evm::core::fork::Fork;
+
+    #[test]
+    fn test_overrides_are_shared_between_clones() {
+        let fork = Fork::new("http://localhost:1", None);
+        let clone = fork.clone();
+
+        clone.set_storage(U256::from(1), U256::from(2), U256::from(3));
+        clone.set_code(U256::from(1), vec![0x60, 0x00]);
+
+        assert_eq!(fork.storage(U256::from(1), U256::from(2)).unwrap(), U256::from(3));
+        assert_eq!(fork.code(U256::from(1)).unwrap(), vec![0x60, 0x00]);
+    }
+
+    #[test]
+    fn test_unreachable_node() {
+        let fork = Fork::new("not a url", None);
+
+        assert!(fork.balance(U256::from(1)).is_err());
+    }
+}
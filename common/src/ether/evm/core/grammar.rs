@@ -0,0 +1,633 @@
+use ethers::abi::ParamType;
+
+use super::types::{ParameterNames, SignatureError};
+
+// the words which may follow a parameter's type in a human-readable signature, besides its name
+const PARAMETER_QUALIFIERS: [&str; 5] = ["calldata", "memory", "storage", "indexed", "payable"];
+
+// the characters which end a word, besides whitespace
+const DELIMITERS: [char; 5] = ['(', ')', '[', ']', ','];
+
+/// A parameter parsed from a human-readable signature.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// The parameter's type.
+    pub kind: ParamType,
+
+    /// The parameter's name, which is empty if it isn't named.
+    pub name: String,
+
+    /// The components of the tuple this parameter is, or is an array of.
+    pub components: Vec<Parameter>,
+}
+
+impl From<&Parameter> for ParameterNames {
+    fn from(parameter: &Parameter) -> Self {
+        ParameterNames {
+            name: parameter.name.clone(),
+            components: parameter.components.iter().map(ParameterNames::from).collect(),
+        }
+    }
+}
+
+/// A function, event, or error signature parsed from its human-readable form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    /// The signature's name, which is empty if a lenient parse couldn't find one.
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Signature {
+    /// The canonical form of the signature, which its selector is hashed from.
+    pub fn canonical(&self) -> String {
+        format!(
+            "{}({})",
+            self.name,
+            self.parameters
+                .iter()
+                .map(|parameter| parameter.kind.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    }
+}
+
+/// Parse a human-readable signature, such as `function swap((address tokenIn, uint256 amountIn)
+/// calldata params, bool)`. Anything following the parameter list, such as `returns (bool)`, is
+/// ignored.
+///
+/// When parsing strictly, anything which isn't valid Solidity is rejected with a
+/// [`SignatureError`]. Otherwise, unknown types are read as `bytes`, invalid sizes are kept as
+/// written, and empty parameters and unexpected words are skipped.
+///
+/// ```
+/// use ethers::abi::ParamType;
+/// use heimdall_common::ether::evm::core::grammar::parse_signature;
+///
+/// let signature = parse_signature("swap((address tokenIn, uint256 amountIn) params)", true).unwrap();
+/// assert_eq!(signature.canonical(), "swap((address,uint256))");
+/// assert_eq!(signature.parameters[0].components[1].name, "amountIn");
+/// ```
+pub fn parse_signature(signature: &str, strict: bool) -> Result<Signature, SignatureError> {
+    // every parenthesis and bracket must be closed, in the order they were opened
+    if strict {
+        let mut open = Vec::new();
+        let balanced = signature.chars().all(|c| match c {
+            '(' | '[' => {
+                open.push(c);
+                true
+            }
+            ')' => open.pop() == Some('('),
+            ']' => open.pop() == Some('['),
+            _ => true,
+        });
+        if !balanced || !open.is_empty() {
+            return Err(SignatureError::Unbalanced(signature.to_string()))
+        }
+    }
+
+    Parser { input: signature, position: 0, strict }.signature()
+}
+
+/// Parse a single type, such as `(address,uint96)[2]`, along with the components of the tuple it
+/// is, or is an array of. The whole string must be the type.
+pub fn parse_type(
+    string: &str,
+    strict: bool,
+) -> Result<(ParamType, Vec<Parameter>), SignatureError> {
+    let mut parser = Parser { input: string, position: 0, strict };
+    let parsed = parser.type_()?;
+
+    parser.skip_whitespace();
+    match parser.rest() {
+        "" => Ok(parsed),
+        rest => Err(SignatureError::InvalidType(
+            string.trim().to_string(),
+            format!("unexpected '{}'", rest.trim()),
+        )),
+    }
+}
+
+/// Whether the given string is a valid Solidity identifier.
+pub fn is_identifier(string: &str) -> bool {
+    string.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$') &&
+        string.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// A recursive-descent parser over a signature, which reads it from left to right:
+///
+/// ```text
+/// signature  := head '(' parameters ')' ...
+/// parameters := [parameter] (',' [parameter])*
+/// parameter  := type word*
+/// type       := ('(' parameters ')' | 'tuple(' parameters ')' | word) ('[' [word] ']')*
+/// ```
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    strict: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume the given character if it's next, after any whitespace.
+    fn eat(&mut self, c: char) -> bool {
+        let position = self.position;
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            return true
+        }
+
+        self.position = position;
+        false
+    }
+
+    /// Consume the next word, which is empty if a delimiter or the end of the input is next.
+    fn word(&mut self) -> &'a str {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let length =
+            rest.find(|c: char| c.is_whitespace() || DELIMITERS.contains(&c)).unwrap_or(rest.len());
+        self.position += length;
+
+        &rest[..length]
+    }
+
+    /// Skip to the end of the current parameter, the next comma or closing parenthesis which isn't
+    /// nested, so a lenient parse can continue past a parameter it couldn't read.
+    fn recover(&mut self) {
+        let mut depth = 0;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '(' | '[' => depth += 1,
+                ')' | ']' if depth > 0 => depth -= 1,
+                ',' | ')' if depth == 0 => {
+                    self.position += i;
+                    return
+                }
+                _ => {}
+            }
+        }
+
+        self.position = self.input.len();
+    }
+
+    fn unbalanced(&self) -> SignatureError {
+        SignatureError::Unbalanced(self.input.to_string())
+    }
+
+    fn signature(&mut self) -> Result<Signature, SignatureError> {
+        let open = self
+            .input
+            .find('(')
+            .ok_or_else(|| SignatureError::MissingParameters(self.input.to_string()))?;
+
+        // the name is the last word before the parameters, after any `function` or `event` keyword
+        let head = &self.input[..open];
+        let name = head.split_whitespace().last().unwrap_or_default();
+        if self.strict && !is_identifier(name) {
+            return Err(SignatureError::InvalidName(head.trim().to_string()))
+        }
+
+        self.position = open + 1;
+        Ok(Signature { name: name.to_string(), parameters: self.parameters()? })
+    }
+
+    /// Parse a list of parameters, following its opening parenthesis, through its closing one.
+    fn parameters(&mut self) -> Result<Vec<Parameter>, SignatureError> {
+        let mut parameters = Vec::new();
+        if self.eat(')') {
+            return Ok(parameters)
+        }
+
+        for index in 0.. {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') | Some(')') if self.strict => {
+                    return Err(SignatureError::EmptyParameter(index))
+                }
+                Some(',') | Some(')') => {}
+                None => return Err(self.unbalanced()),
+                _ => parameters.push(self.parameter()?),
+            }
+
+            if !self.eat(',') {
+                break
+            }
+        }
+
+        match self.eat(')') {
+            true => Ok(parameters),
+            false => Err(self.unbalanced()),
+        }
+    }
+
+    fn parameter(&mut self) -> Result<Parameter, SignatureError> {
+        let start = self.position;
+        let (kind, components) = match self.type_() {
+            Ok(parsed) => parsed,
+            Err(e) if self.strict => return Err(e),
+            Err(_) => {
+                self.recover();
+                (ParamType::Bytes, Vec::new())
+            }
+        };
+
+        // the words following the type are its qualifiers and name
+        let mut words = Vec::new();
+        loop {
+            let word = self.word();
+            if !word.is_empty() {
+                if !PARAMETER_QUALIFIERS.contains(&word) {
+                    words.push(word);
+                }
+                continue
+            }
+
+            match self.peek() {
+                None | Some(',') | Some(')') => break,
+                Some(c) if self.strict => {
+                    return Err(SignatureError::InvalidParameter(
+                        self.input[start..self.position + c.len_utf8()].trim().to_string(),
+                        format!("unexpected '{c}'"),
+                    ))
+                }
+                Some(_) => {
+                    self.recover();
+                    break
+                }
+            }
+        }
+
+        let parameter = self.input[start..self.position].trim();
+        let name = words.last().copied().unwrap_or_default();
+        if self.strict {
+            if words.len() > 1 {
+                return Err(SignatureError::InvalidParameter(
+                    parameter.to_string(),
+                    format!("unexpected '{}'", words[..words.len() - 1].join(" ")),
+                ))
+            }
+            if !name.is_empty() && !is_identifier(name) {
+                return Err(SignatureError::InvalidParameter(
+                    parameter.to_string(),
+                    format!("'{name}' is not a valid name"),
+                ))
+            }
+        }
+
+        Ok(Parameter { kind, name: name.to_string(), components })
+    }
+
+    fn type_(&mut self) -> Result<(ParamType, Vec<Parameter>), SignatureError> {
+        self.skip_whitespace();
+        let start = self.position;
+
+        // tuples contain their own list of parameters, and may be written with the `tuple` keyword
+        if self.rest().starts_with("tuple(") {
+            self.position += "tuple".len();
+        }
+        let (mut kind, components) = if self.eat('(') {
+            let components = self.parameters()?;
+            (ParamType::Tuple(components.iter().map(|c| c.kind.clone()).collect()), components)
+        } else {
+            match self.word() {
+                "" => {
+                    return Err(SignatureError::InvalidType(
+                        self.rest().trim().to_string(),
+                        String::from("expected a type"),
+                    ))
+                }
+                word => (self.elementary(word)?, Vec::new()),
+            }
+        };
+
+        // arrays are built from their element type, with each suffix wrapping the last
+        while self.eat('[') {
+            let size = self.word();
+            if !self.eat(']') {
+                return Err(self.unbalanced())
+            }
+
+            kind = match (size.parse::<usize>(), self.strict) {
+                (Ok(size), _) if size > 0 || !self.strict => {
+                    ParamType::FixedArray(Box::new(kind), size)
+                }
+                (Err(_), _) if size.is_empty() || !self.strict => ParamType::Array(Box::new(kind)),
+                _ => {
+                    return Err(SignatureError::InvalidType(
+                        self.input[start..self.position].trim().to_string(),
+                        String::from("array sizes must be positive integers"),
+                    ))
+                }
+            };
+        }
+
+        Ok((kind, components))
+    }
+
+    /// Converts the name of an elementary type, such as `address` or `uint96`, to a ParamType.
+    /// Types without an equivalent ParamType are converted to the type they're encoded as, so
+    /// `function` becomes `bytes24`, and `fixedMxN` and `ufixedMxN` become `intM` and `uintM`.
+    fn elementary(&self, word: &str) -> Result<ParamType, SignatureError> {
+        let strict = self.strict;
+        let invalid =
+            |reason: &str| SignatureError::InvalidType(word.to_string(), reason.to_string());
+
+        let kind = match word {
+            "address" => Ok(ParamType::Address),
+            "bool" => Ok(ParamType::Bool),
+            "string" => Ok(ParamType::String),
+            "bytes" => Ok(ParamType::Bytes),
+            "uint" => Ok(ParamType::Uint(256)),
+            "int" => Ok(ParamType::Int(256)),
+
+            // external function pointers are encoded as an address followed by a selector
+            "function" => Ok(ParamType::FixedBytes(24)),
+
+            // fixed point numbers are encoded as the integer of their total size, and default to
+            // 128 bits with 18 decimals
+            "fixed" => Ok(ParamType::Int(128)),
+            "ufixed" => Ok(ParamType::Uint(128)),
+            _ => {
+                let integer_size = |size: &str| match (size.parse::<usize>(), strict) {
+                    (Ok(size), _) if (size % 8 == 0 && (8..=256).contains(&size)) || !strict => {
+                        Ok(size)
+                    }
+                    (Err(_), false) => Ok(256),
+                    _ => Err(invalid("integer sizes must be a multiple of 8 between 8 and 256")),
+                };
+
+                let fixed_size = |size: &str| {
+                    let (bits, decimals) = size.split_once('x').unwrap_or((size, ""));
+                    match (integer_size(bits)?, decimals.parse::<usize>()) {
+                        (bits, Ok(decimals)) if decimals <= 80 => Ok(bits),
+                        (bits, _) if !strict => Ok(bits),
+                        _ => Err(invalid(
+                            "fixed point types must be fixedMxN, with 0 to 80 decimals",
+                        )),
+                    }
+                };
+
+                if let Some(stripped) = word.strip_prefix("ufixed") {
+                    fixed_size(stripped).map(ParamType::Uint)
+                } else if let Some(stripped) = word.strip_prefix("fixed") {
+                    fixed_size(stripped).map(ParamType::Int)
+                } else if let Some(stripped) = word.strip_prefix("uint") {
+                    integer_size(stripped).map(ParamType::Uint)
+                } else if let Some(stripped) = word.strip_prefix("int") {
+                    integer_size(stripped).map(ParamType::Int)
+                } else if let Some(size) =
+                    word.strip_prefix("bytes").and_then(|size| size.parse::<usize>().ok())
+                {
+                    match (1..=32).contains(&size) || !strict {
+                        true => Ok(ParamType::FixedBytes(size)),
+                        false => Err(invalid("fixed bytes sizes must be between 1 and 32")),
+                    }
+                } else {
+                    Err(invalid("unknown type"))
+                }
+            }
+        };
+
+        // unknown types are read as bytes when parsing leniently
+        match (kind, strict) {
+            (Err(_), false) => Ok(ParamType::Bytes),
+            (kind, _) => kind,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::{param_type::Reader, ParamType};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::ether::evm::core::{
+        grammar::{parse_signature, parse_type, Parameter},
+        types::SignatureError,
+    };
+
+    // a random type which is valid Solidity, nested at most `depth` times
+    fn random_type(rng: &mut StdRng, depth: usize) -> ParamType {
+        match rng.gen_range(0..if depth == 0 { 7 } else { 10 }) {
+            0 => ParamType::Address,
+            1 => ParamType::Bool,
+            2 => ParamType::String,
+            3 => ParamType::Bytes,
+            4 => ParamType::Uint(rng.gen_range(1..=32) * 8),
+            5 => ParamType::Int(rng.gen_range(1..=32) * 8),
+            6 => ParamType::FixedBytes(rng.gen_range(1..=32)),
+            7 => ParamType::Array(Box::new(random_type(rng, depth - 1))),
+            8 => ParamType::FixedArray(Box::new(random_type(rng, depth - 1)), rng.gen_range(1..=4)),
+            _ => ParamType::Tuple(
+                (0..rng.gen_range(1..=3)).map(|_| random_type(rng, depth - 1)).collect(),
+            ),
+        }
+    }
+
+    // the type written as a human-readable parameter, with names, qualifiers, and extra whitespace
+    fn human_readable(rng: &mut StdRng, kind: &ParamType, name: &str) -> String {
+        let space = |rng: &mut StdRng| [" ", "", "  ", "\t"][rng.gen_range(0..4)].to_string();
+        let written = match kind {
+            ParamType::Tuple(components) => format!(
+                "{}({})",
+                ["", "tuple"][rng.gen_range(0..2)],
+                components
+                    .iter()
+                    .enumerate()
+                    .map(|(i, component)| {
+                        format!(
+                            "{}{}",
+                            space(rng),
+                            human_readable(rng, component, &format!("c{i}"))
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            ParamType::Array(element) => {
+                format!("{}{}[{}]", human_readable(rng, element, ""), space(rng), space(rng))
+            }
+            ParamType::FixedArray(element, size) => {
+                format!("{}[{size}]", human_readable(rng, element, ""))
+            }
+            kind => kind.to_string(),
+        };
+
+        match (name.is_empty(), rng.gen_bool(0.5)) {
+            (true, _) => written,
+            (false, true) => format!("{written} calldata {name}"),
+            (false, false) => format!("{written}{}{name}", [" ", "\t", "  "][rng.gen_range(0..3)]),
+        }
+    }
+
+    // the components of the tuple this type is, or is an array of, named as `human_readable` does
+    fn components(kind: &ParamType) -> Vec<(ParamType, String)> {
+        match kind {
+            ParamType::Tuple(components) => {
+                components.iter().enumerate().map(|(i, c)| (c.clone(), format!("c{i}"))).collect()
+            }
+            ParamType::Array(element) | ParamType::FixedArray(element, _) => components(element),
+            _ => Vec::new(),
+        }
+    }
+
+    fn assert_components(parameter: &Parameter) {
+        assert_eq!(
+            parameter
+                .components
+                .iter()
+                .map(|component| (component.kind.clone(), component.name.clone()))
+                .collect::<Vec<_>>(),
+            components(&parameter.kind)
+        );
+        parameter.components.iter().for_each(assert_components);
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        let signature = parse_signature(
+            "function fill( tuple(address maker, (uint96 amount, bytes32) terms) [ 2 ][] calldata orders , uint ) external returns (bool)",
+            true,
+        )
+        .unwrap();
+
+        let terms = ParamType::Tuple(vec![ParamType::Uint(96), ParamType::FixedBytes(32)]);
+        assert_eq!(signature.name, "fill");
+        assert_eq!(signature.canonical(), "fill((address,(uint96,bytes32))[2][],uint256)");
+        assert_eq!(signature.parameters[0].name, "orders");
+        assert_eq!(signature.parameters[0].components[1].kind, terms);
+        assert_eq!(signature.parameters[0].components[1].components[0].name, "amount");
+        assert_eq!(signature.parameters[1].name, "");
+    }
+
+    #[test]
+    fn test_parse_signature_lenient_recovery() {
+        // unreadable parameters are read as bytes, and the rest of the signature still parses
+        let signature =
+            parse_signature("test(uint256[, (address a) b c, uint7, , 名前 x)", false).unwrap();
+        assert_eq!(
+            signature.parameters.iter().map(|p| p.kind.clone()).collect::<Vec<_>>(),
+            vec![
+                ParamType::Bytes,
+                ParamType::Tuple(vec![ParamType::Address]),
+                ParamType::Uint(7),
+                ParamType::Bytes
+            ]
+        );
+        assert_eq!(signature.parameters[1].name, "c");
+
+        assert!(parse_signature("test((uint256,address)", false).is_err());
+        assert!(parse_signature("test", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_type() {
+        assert_eq!(
+            parse_type("(uint256,bool)[2][]", true).map(|(kind, _)| kind),
+            Ok(ParamType::Array(Box::new(ParamType::FixedArray(
+                Box::new(ParamType::Tuple(vec![ParamType::Uint(256), ParamType::Bool])),
+                2
+            ))))
+        );
+        assert!(matches!(parse_type("uint256 amount", true), Err(SignatureError::InvalidType(..))));
+        assert!(matches!(parse_type("[]", true), Err(SignatureError::InvalidType(..))));
+    }
+
+    #[test]
+    fn test_round_trip_against_ethers() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut read_by_ethers = 0;
+        for _ in 0..2000 {
+            let kind = random_type(&mut rng, 3);
+
+            // canonical types are read as ethers reads them, though ethers' reader rejects some
+            // tuples nested within tuples, which are checked against the type they were built from
+            let canonical = kind.to_string();
+            assert_eq!(parse_type(&canonical, true).map(|(kind, _)| kind), Ok(kind.clone()));
+            if let Ok(read) = Reader::read(&canonical) {
+                assert_eq!(read, kind, "{canonical}");
+                read_by_ethers += 1;
+            }
+
+            // as are human-readable parameters, which keep their names
+            let written = human_readable(&mut rng, &kind, "value");
+            let signature = parse_signature(&format!("function test({written}, bool)"), true)
+                .unwrap_or_else(|e| panic!("failed to parse '{written}': {e}"));
+            assert_eq!(signature.canonical(), format!("test({canonical},bool)"), "{written}");
+            assert_eq!(signature.parameters[0].name, "value");
+            assert_components(&signature.parameters[0]);
+
+            // a lenient parse of a valid signature reads the same parameters
+            assert_eq!(
+                parse_signature(&format!("function test({written}, bool)"), false),
+                Ok(signature)
+            );
+        }
+        assert!(read_by_ethers > 1500);
+    }
+
+    #[test]
+    fn test_fuzz_signatures() {
+        let tokens = [
+            "(",
+            ")",
+            "[",
+            "]",
+            ",",
+            " ",
+            "uint256",
+            "uint7",
+            "tuple",
+            "bytes33",
+            "address",
+            "x",
+            "2",
+            "0",
+            "calldata",
+            "名",
+            "\t",
+            "int",
+            "fixed128x18",
+        ];
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20000 {
+            let signature = format!(
+                "f({}",
+                (0..rng.gen_range(0..16))
+                    .map(|_| tokens[rng.gen_range(0..tokens.len())])
+                    .collect::<String>()
+            );
+
+            // neither parse may panic, and whatever parses strictly parses leniently the same way
+            let strict = parse_signature(&signature, true);
+            let lenient = parse_signature(&signature, false);
+            if let Ok(strict) = strict {
+                assert_eq!(lenient, Ok(strict.clone()), "{signature}");
+
+                // and its canonical form reads as the same type in ethers, too
+                for parameter in &strict.parameters {
+                    if let Ok(read) = Reader::read(&parameter.kind.to_string()) {
+                        assert_eq!(read, parameter.kind, "{signature}");
+                    }
+                }
+            }
+        }
+    }
+}
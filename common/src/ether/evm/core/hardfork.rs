@@ -0,0 +1,335 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use ethers::prelude::U256;
+
+use super::opcodes::Opcode;
+
+/// The [`Hardfork`] whose rules the [`VM`](super::vm::VM) follows, which determines the gas
+/// schedule, from Istanbul onwards. Defaults to the latest hardfork.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hardfork {
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    #[default]
+    Cancun,
+}
+
+// the gas refunded for clearing a storage slot, before and after EIP-3529
+const SSTORE_CLEARS_SCHEDULE: i128 = 15000;
+const SSTORE_CLEARS_SCHEDULE_EIP3529: i128 = 4800;
+
+impl Hardfork {
+    /// Every supported hardfork, oldest first.
+    pub const ALL: [Hardfork; 6] = [
+        Hardfork::Istanbul,
+        Hardfork::Berlin,
+        Hardfork::London,
+        Hardfork::Paris,
+        Hardfork::Shanghai,
+        Hardfork::Cancun,
+    ];
+
+    /// The hardfork which introduced the given opcode, or `None` if it isn't an opcode at all.
+    /// Opcodes which predate Istanbul are reported as introduced in Istanbul.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// assert_eq!(Hardfork::introduced(0x01), Some(Hardfork::Istanbul));
+    /// assert_eq!(Hardfork::introduced(0x5f), Some(Hardfork::Shanghai));
+    /// assert_eq!(Hardfork::introduced(0x0c), None);
+    /// ```
+    pub fn introduced(opcode: u8) -> Option<Hardfork> {
+        match opcode {
+            // BASEFEE
+            0x48 => Some(Hardfork::London),
+            // PUSH0
+            0x5f => Some(Hardfork::Shanghai),
+            // BLOBHASH, BLOBBASEFEE, TLOAD, TSTORE, MCOPY
+            0x49 | 0x4a | 0x5c | 0x5d | 0x5e => Some(Hardfork::Cancun),
+            _ if Opcode::new(opcode).name != "unknown" => Some(Hardfork::Istanbul),
+            _ => None,
+        }
+    }
+
+    /// Whether the given opcode is valid in this hardfork.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// assert!(!Hardfork::London.supports(0x5f));
+    /// assert!(Hardfork::Shanghai.supports(0x5f));
+    /// ```
+    pub fn supports(&self, opcode: u8) -> bool {
+        Hardfork::introduced(opcode).is_some_and(|hardfork| hardfork <= *self)
+    }
+
+    /// The [`Opcode`] the given byte decodes to in this hardfork. Opcodes which haven't been
+    /// introduced yet decode to `unknown`, just like bytes which aren't opcodes.
+    pub fn opcode(&self, code: u8) -> Opcode {
+        if self.supports(code) {
+            Opcode::new(code)
+        } else {
+            Opcode { code, name: "unknown", mingas: 0, inputs: 0, outputs: 0 }
+        }
+    }
+
+    /// Finds every instruction in the given bytecode which uses an opcode from a later hardfork,
+    /// returning the program counter of each along with the opcode. Push data is skipped over.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// // PUSH1 0x5f PUSH0 STOP
+    /// let unsupported = Hardfork::London.unsupported_opcodes(&[0x60, 0x5f, 0x5f, 0x00]);
+    /// assert_eq!(unsupported.len(), 1);
+    /// assert_eq!(unsupported[0].0, 2);
+    /// assert_eq!(unsupported[0].1.name, "PUSH0");
+    /// ```
+    pub fn unsupported_opcodes(&self, bytecode: &[u8]) -> Vec<(usize, Opcode)> {
+        let mut unsupported = Vec::new();
+        let mut program_counter = 0;
+
+        while program_counter < bytecode.len() {
+            let opcode = Opcode::new(bytecode[program_counter]);
+            if Hardfork::introduced(opcode.code).is_some_and(|hardfork| hardfork > *self) {
+                unsupported.push((program_counter, opcode));
+            } else if let Some(byte_count) = opcode.name.strip_prefix("PUSH") {
+                program_counter += byte_count.parse::<usize>().unwrap_or(0);
+            }
+            program_counter += 1;
+        }
+
+        unsupported
+    }
+
+    /// The cost of accessing an account with BALANCE, EXTCODESIZE, EXTCODECOPY, EXTCODEHASH, or
+    /// the CALL family. Since EIP-2929, the first access of an account in a transaction is cold
+    /// and more expensive.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// assert_eq!(Hardfork::Istanbul.account_access_cost(false), 700);
+    /// assert_eq!(Hardfork::Berlin.account_access_cost(false), 2600);
+    /// assert_eq!(Hardfork::Berlin.account_access_cost(true), 100);
+    /// ```
+    pub fn account_access_cost(&self, warm: bool) -> u128 {
+        match (self, warm) {
+            (Hardfork::Istanbul, _) => 700,
+            (_, true) => 100,
+            (_, false) => 2600,
+        }
+    }
+
+    /// The cost of reading a storage slot with SLOAD.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// assert_eq!(Hardfork::Istanbul.sload_cost(false), 800);
+    /// assert_eq!(Hardfork::London.sload_cost(false), 2100);
+    /// assert_eq!(Hardfork::London.sload_cost(true), 100);
+    /// ```
+    pub fn sload_cost(&self, warm: bool) -> u128 {
+        match (self, warm) {
+            (Hardfork::Istanbul, _) => 800,
+            (_, true) => 100,
+            (_, false) => 2100,
+        }
+    }
+
+    /// The cost of writing `new` to a storage slot with SSTORE, along with the change to the gas
+    /// refund counter, using the net gas metering of EIP-2200, with the access costs of EIP-2929
+    /// and the reduced refunds of EIP-3529 where they apply. `original` is the value of the slot
+    /// at the start of the transaction, and `current` its value before this write.
+    ///
+    /// ```
+    /// use ethers::prelude::U256;
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// // setting a fresh slot
+    /// let (zero, one) = (U256::zero(), U256::one());
+    /// assert_eq!(Hardfork::Cancun.sstore_cost(zero, zero, one, false), (22100, 0));
+    ///
+    /// // clearing a slot which was set before the transaction
+    /// assert_eq!(Hardfork::Istanbul.sstore_cost(one, one, zero, false), (5000, 15000));
+    /// assert_eq!(Hardfork::Cancun.sstore_cost(one, one, zero, true), (2900, 4800));
+    /// ```
+    pub fn sstore_cost(
+        &self,
+        original: U256,
+        current: U256,
+        new: U256,
+        warm: bool,
+    ) -> (u128, i128) {
+        let (sload_gas, sstore_reset_gas, cold_cost) = match self {
+            Hardfork::Istanbul => (800, 5000, 0),
+            _ => (100, 5000 - 2100, if warm { 0 } else { 2100 }),
+        };
+        let clears_schedule = self.sstore_clears_schedule();
+
+        // no-op writes only cost as much as a read
+        if current == new {
+            return (sload_gas + cold_cost, 0)
+        }
+
+        // the first write to a slot in this transaction
+        if original == current {
+            if original.is_zero() {
+                return (20000 + cold_cost, 0)
+            }
+            let refund = if new.is_zero() { clears_schedule } else { 0 };
+            return (sstore_reset_gas + cold_cost, refund)
+        }
+
+        // the slot has already been written, so it's charged as a read, with refunds adjusted
+        let mut refund = 0;
+        if !original.is_zero() {
+            if current.is_zero() {
+                refund -= clears_schedule;
+            }
+            if new.is_zero() {
+                refund += clears_schedule;
+            }
+        }
+        if original == new {
+            refund += if original.is_zero() {
+                20000 - sload_gas as i128
+            } else {
+                sstore_reset_gas as i128 - sload_gas as i128
+            };
+        }
+
+        (sload_gas + cold_cost, refund)
+    }
+
+    /// The gas refunded for clearing a storage slot.
+    fn sstore_clears_schedule(&self) -> i128 {
+        match self {
+            Hardfork::Istanbul | Hardfork::Berlin => SSTORE_CLEARS_SCHEDULE,
+            _ => SSTORE_CLEARS_SCHEDULE_EIP3529,
+        }
+    }
+
+    /// The largest refund a transaction can receive, as a fraction of the gas it used.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    ///
+    /// assert_eq!(Hardfork::Berlin.max_refund(100000), 50000);
+    /// assert_eq!(Hardfork::London.max_refund(100000), 20000);
+    /// ```
+    pub fn max_refund(&self, gas_used: u128) -> u128 {
+        match self {
+            Hardfork::Istanbul | Hardfork::Berlin => gas_used / 2,
+            _ => gas_used / 5,
+        }
+    }
+
+    /// The gas refunded for the first SELFDESTRUCT of a contract, which EIP-3529 removed.
+    pub fn selfdestruct_refund(&self) -> i128 {
+        match self {
+            Hardfork::Istanbul | Hardfork::Berlin => 24000,
+            _ => 0,
+        }
+    }
+
+    /// The cost per word of init code passed to CREATE or CREATE2, introduced in EIP-3860.
+    pub fn initcode_word_cost(&self) -> u128 {
+        match self {
+            Hardfork::Istanbul | Hardfork::Berlin | Hardfork::London | Hardfork::Paris => 0,
+            _ => 2,
+        }
+    }
+}
+
+impl FromStr for Hardfork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "istanbul" => Ok(Hardfork::Istanbul),
+            "berlin" => Ok(Hardfork::Berlin),
+            "london" => Ok(Hardfork::London),
+            "paris" | "merge" => Ok(Hardfork::Paris),
+            "shanghai" => Ok(Hardfork::Shanghai),
+            "cancun" => Ok(Hardfork::Cancun),
+            _ => Err(format!(
+                "unknown evm version '{}', expected one of: {}",
+                s,
+                Hardfork::ALL.map(|hardfork| hardfork.to_string()).join(", ")
+            )),
+        }
+    }
+}
+
+impl Display for Hardfork {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Hardfork::Istanbul => "istanbul",
+            Hardfork::Berlin => "berlin",
+            Hardfork::London => "london",
+            Hardfork::Paris => "paris",
+            Hardfork::Shanghai => "shanghai",
+            Hardfork::Cancun => "cancun",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::prelude::U256;
+
+    use crate::ether::evm::core::hardfork::Hardfork;
+
+    #[test]
+    fn test_sstore_net_metering() {
+        let (zero, one, two) = (U256::zero(), U256::one(), U256::from(2));
+
+        // writing the same value is charged as a read
+        assert_eq!(Hardfork::Istanbul.sstore_cost(one, one, one, true), (800, 0));
+        assert_eq!(Hardfork::Cancun.sstore_cost(one, one, one, true), (100, 0));
+
+        // updating a slot which was set before the transaction
+        assert_eq!(Hardfork::Berlin.sstore_cost(one, one, two, false), (5000, 0));
+
+        // restoring a slot's original value refunds most of the first write
+        assert_eq!(Hardfork::Istanbul.sstore_cost(zero, one, zero, true), (800, 19200));
+        assert_eq!(Hardfork::Cancun.sstore_cost(one, two, one, true), (100, 2800));
+
+        // un-clearing a slot takes back its refund
+        assert_eq!(Hardfork::London.sstore_cost(one, zero, two, true), (100, -4800));
+    }
+
+    #[test]
+    fn test_opcode_availability() {
+        assert!(Hardfork::ALL.iter().all(|hardfork| hardfork.supports(0x01)));
+        assert!(!Hardfork::Berlin.supports(0x48));
+        assert!(Hardfork::London.supports(0x48));
+        assert!(!Hardfork::Shanghai.supports(0x5c));
+        assert!(Hardfork::Cancun.supports(0x5c));
+
+        // opcodes from later hardforks decode as unknown
+        assert_eq!(Hardfork::Shanghai.opcode(0x5e).name, "unknown");
+        assert_eq!(Hardfork::Cancun.opcode(0x5e).name, "MCOPY");
+
+        // bytes which aren't opcodes in any hardfork aren't reported
+        assert!(Hardfork::Istanbul.unsupported_opcodes(&[0x0c, 0xef]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_hardfork() {
+        assert_eq!("Shanghai".parse::<Hardfork>(), Ok(Hardfork::Shanghai));
+        assert_eq!("merge".parse::<Hardfork>(), Ok(Hardfork::Paris));
+        assert!("frontier".parse::<Hardfork>().is_err());
+        assert_eq!(Hardfork::default().to_string(), "cancun");
+    }
+}
@@ -1,8 +1,70 @@
+use super::opcodes::WrappedOpcode;
+
+/// The largest offset, and the largest size, of a memory access that is modeled. The VM halts
+/// on accesses beyond it, and [`Memory`] caps them to it.
+pub const MEMORY_LIMIT: usize = 1 << 16;
+
 /// The [`Memory`] struct represents the memory of an EVM.
 #[derive(Clone, Debug)]
 pub struct Memory {
     pub memory: Vec<u8>,
-    // TODO: add bit-tracking for memory
+    // the regions of memory whose contents came from a known source, sorted by offset and never
+    // overlapping
+    pub regions: Vec<MemoryRegion>,
+}
+
+/// Where the contents of a [`MemoryRegion`] came from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemorySource {
+    /// Copied from calldata, starting at the given offset.
+    Calldata(usize),
+    /// Copied from the executing code, starting at the given offset.
+    Code(usize),
+    /// Copied from the returndata of the last call, starting at the given offset.
+    ReturnData(usize),
+    /// A word written by MSTORE, along with the operation which produced it.
+    Stored(WrappedOpcode),
+    /// A word written by MSTORE which has since been moved by MCOPY, along with the operation
+    /// which produced it.
+    Copied(WrappedOpcode),
+}
+
+/// A contiguous range of memory, along with where its contents came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryRegion {
+    pub offset: usize,
+    pub size: usize,
+    pub source: MemorySource,
+}
+
+impl MemoryRegion {
+    /// The part of this region between `start` and `end`, if any. Offsets into copied data are
+    /// shifted along with the region, but a part of a stored word isn't the stored value, so
+    /// stored words are only returned whole.
+    fn slice(&self, start: usize, end: usize) -> Option<MemoryRegion> {
+        let start = start.max(self.offset);
+        let end = end.min(self.offset + self.size);
+        if start >= end {
+            return None
+        }
+
+        let delta = start - self.offset;
+        let source = match &self.source {
+            MemorySource::Calldata(offset) => MemorySource::Calldata(offset.saturating_add(delta)),
+            MemorySource::Code(offset) => MemorySource::Code(offset.saturating_add(delta)),
+            MemorySource::ReturnData(offset) => {
+                MemorySource::ReturnData(offset.saturating_add(delta))
+            }
+            MemorySource::Stored(_) | MemorySource::Copied(_) => {
+                if start != self.offset || end != self.offset + self.size {
+                    return None
+                }
+                self.source.clone()
+            }
+        };
+
+        Some(MemoryRegion { offset: start, size: end - start, source })
+    }
 }
 
 impl Default for Memory {
@@ -14,7 +76,7 @@ impl Default for Memory {
 impl Memory {
     /// Creates a new [`Memory`] with an empty memory vector.
     pub fn new() -> Memory {
-        Memory { memory: Vec::new() }
+        Memory { memory: Vec::new(), regions: Vec::new() }
     }
 
     /// Gets the current size of the memory in bytes.
@@ -64,8 +126,8 @@ impl Memory {
     /// ```
     pub fn store(&mut self, mut offset: usize, mut size: usize, value: &[u8]) {
         // Cap offset and size to 2**16
-        offset = offset.min(65536);
-        size = size.min(65536);
+        offset = offset.min(MEMORY_LIMIT);
+        size = size.min(MEMORY_LIMIT);
 
         let value_len = value.len();
 
@@ -87,6 +149,110 @@ impl Memory {
 
         // Store the value in memory by replacing bytes in the memory
         self.memory.splice(offset..offset + size, value);
+
+        // the stored bytes no longer come from wherever the old ones did
+        self.clear_source(offset, size);
+    }
+
+    /// Records where the given range of memory was copied from. This should be called right after
+    /// the range is written with [`Memory::store`].
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::memory::{Memory, MemorySource};
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.store(0, 64, &[0xff]);
+    /// memory.set_source(0, 64, MemorySource::Calldata(4));
+    /// assert_eq!(memory.source(32, 32), Some(MemorySource::Calldata(36)));
+    /// ```
+    pub fn set_source(&mut self, offset: usize, size: usize, source: MemorySource) {
+        let offset = offset.min(MEMORY_LIMIT);
+        let size = size.min(MEMORY_LIMIT);
+        if size == 0 {
+            return
+        }
+
+        self.clear_source(offset, size);
+        let index = self.regions.partition_point(|region| region.offset < offset);
+        self.regions.insert(index, MemoryRegion { offset, size, source });
+    }
+
+    /// Where the given range of memory came from, if it all came from the same place.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::memory::{Memory, MemorySource};
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.store(0, 32, &[0xff]);
+    /// memory.set_source(0, 32, MemorySource::Code(0));
+    /// assert_eq!(memory.source(0, 32), Some(MemorySource::Code(0)));
+    /// assert_eq!(memory.source(16, 32), None);
+    /// ```
+    pub fn source(&self, offset: usize, size: usize) -> Option<MemorySource> {
+        let region = self.regions.iter().find(|region| {
+            region.offset <= offset && offset + size <= region.offset + region.size
+        })?;
+        region.slice(offset, offset + size).map(|region| region.source)
+    }
+
+    /// Copies the given number of bytes from one offset in memory to another, along with where
+    /// they came from, as MCOPY does. The ranges may overlap.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::memory::{Memory, MemorySource};
+    ///
+    /// let mut memory = Memory::new();
+    /// memory.store(0, 32, &[0xff]);
+    /// memory.set_source(0, 32, MemorySource::Calldata(4));
+    /// memory.copy(0, 64, 32);
+    /// assert_eq!(memory.read(64, 32), memory.read(0, 32));
+    /// assert_eq!(memory.source(64, 32), Some(MemorySource::Calldata(4)));
+    /// ```
+    pub fn copy(&mut self, offset: usize, dest_offset: usize, size: usize) {
+        let value = self.read(offset, size);
+        let regions = self
+            .regions
+            .iter()
+            .filter_map(|region| region.slice(offset, offset.saturating_add(size)))
+            .collect::<Vec<MemoryRegion>>();
+
+        self.store(dest_offset, size, &value);
+        for region in regions {
+            let source = match region.source {
+                MemorySource::Stored(operation) => MemorySource::Copied(operation),
+                source => source,
+            };
+            self.set_source(dest_offset + (region.offset - offset), region.size, source);
+        }
+    }
+
+    /// Forgets where the given range of memory came from, splitting any regions which only
+    /// partly overlap it.
+    fn clear_source(&mut self, offset: usize, size: usize) {
+        let end = offset.saturating_add(size);
+        if !self
+            .regions
+            .iter()
+            .any(|region| region.offset < end && offset < region.offset + region.size)
+        {
+            return
+        }
+
+        self.regions = std::mem::take(&mut self.regions)
+            .into_iter()
+            .flat_map(|region| {
+                if region.offset >= end || offset >= region.offset + region.size {
+                    return vec![region]
+                }
+                [
+                    region.slice(region.offset, offset),
+                    region.slice(end, region.offset + region.size),
+                ]
+                .into_iter()
+                .flatten()
+                .collect()
+            })
+            .collect();
     }
 
     /// Read the given number of bytes from the memory at the given offset.
@@ -102,8 +268,8 @@ impl Memory {
     /// ```
     pub fn read(&self, offset: usize, size: usize) -> Vec<u8> {
         // Cap size to 2**16 and offset to 2**16 for optimization
-        let size = size.min(65536);
-        let offset = offset.min(65536);
+        let size = size.min(MEMORY_LIMIT);
+        let offset = offset.min(MEMORY_LIMIT);
 
         // If the offset + size will be out of bounds, append null bytes until the size is met
         if offset + size > self.size() as usize {
@@ -159,7 +325,13 @@ impl Memory {
 
 #[cfg(test)]
 mod tests {
-    use crate::{ether::evm::core::memory::Memory, utils::strings::decode_hex};
+    use crate::{
+        ether::evm::core::{
+            memory::{Memory, MemorySource},
+            opcodes::WrappedOpcode,
+        },
+        utils::strings::decode_hex,
+    };
 
     #[test]
     fn test_mstore_simple() {
@@ -306,4 +478,44 @@ mod tests {
         let memory = Memory::new();
         assert_eq!(memory.expansion_cost(32 * 32, 32), 101);
     }
+
+    #[test]
+    fn test_source_overwritten() {
+        let mut memory = Memory::new();
+        memory.store(0, 96, &[]);
+        memory.set_source(0, 96, MemorySource::Calldata(4));
+
+        // overwriting the middle word splits the copied region around it
+        memory.store(32, 32, &[0xff]);
+        assert_eq!(memory.regions.len(), 2);
+        assert_eq!(memory.source(0, 32), Some(MemorySource::Calldata(4)));
+        assert_eq!(memory.source(32, 32), None);
+        assert_eq!(memory.source(64, 32), Some(MemorySource::Calldata(68)));
+        assert_eq!(memory.source(0, 96), None);
+    }
+
+    #[test]
+    fn test_source_stored_word() {
+        let mut memory = Memory::new();
+        memory.store(0, 32, &[0xff]);
+        memory.set_source(0, 32, MemorySource::Stored(WrappedOpcode::new(0x33, vec![])));
+
+        // only the whole stored word has a source
+        assert_eq!(memory.source(1, 31), None);
+        memory.store(31, 1, &[0x00]);
+        assert_eq!(memory.source(0, 32), None);
+        assert!(memory.regions.is_empty());
+    }
+
+    #[test]
+    fn test_copy_overlapping() {
+        let mut memory = Memory::new();
+        memory.store(0, 64, &[0xff]);
+        memory.set_source(0, 64, MemorySource::Calldata(4));
+        memory.copy(0, 32, 64);
+
+        assert_eq!(memory.source(0, 32), Some(MemorySource::Calldata(4)));
+        assert_eq!(memory.source(32, 64), Some(MemorySource::Calldata(4)));
+        assert_eq!(memory.read(32, 64), [vec![0u8; 63], vec![0xff]].concat());
+    }
 }
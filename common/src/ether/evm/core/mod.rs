@@ -1,7 +1,16 @@
+pub mod fault;
+pub mod fork;
+pub mod grammar;
+pub mod hardfork;
 pub mod log;
 pub mod memory;
 pub mod opcodes;
+pub mod precompiles;
+pub mod preimages;
+pub mod snapshot;
 pub mod stack;
 pub mod storage;
+pub mod taint;
+pub mod trace;
 pub mod types;
 pub mod vm;
@@ -75,6 +75,8 @@ impl Opcode {
             0x46 => Opcode { code, name: "CHAINID", mingas: 2, inputs: 0, outputs: 1 },
             0x47 => Opcode { code, name: "SELFBALANCE", mingas: 5, inputs: 0, outputs: 1 },
             0x48 => Opcode { code, name: "BASEFEE", mingas: 2, inputs: 0, outputs: 1 },
+            0x49 => Opcode { code, name: "BLOBHASH", mingas: 3, inputs: 1, outputs: 1 },
+            0x4a => Opcode { code, name: "BLOBBASEFEE", mingas: 2, inputs: 0, outputs: 1 },
             0x50 => Opcode { code, name: "POP", mingas: 2, inputs: 1, outputs: 0 },
             0x51 => Opcode { code, name: "MLOAD", mingas: 3, inputs: 1, outputs: 1 },
             0x52 => Opcode { code, name: "MSTORE", mingas: 3, inputs: 2, outputs: 0 },
@@ -87,7 +89,10 @@ impl Opcode {
             0x59 => Opcode { code, name: "MSIZE", mingas: 2, inputs: 0, outputs: 1 },
             0x5a => Opcode { code, name: "GAS", mingas: 2, inputs: 0, outputs: 1 },
             0x5b => Opcode { code, name: "JUMPDEST", mingas: 1, inputs: 0, outputs: 0 },
-            0x5f => Opcode { code, name: "PUSH0", mingas: 3, inputs: 0, outputs: 1 },
+            0x5c => Opcode { code, name: "TLOAD", mingas: 100, inputs: 1, outputs: 1 },
+            0x5d => Opcode { code, name: "TSTORE", mingas: 100, inputs: 2, outputs: 0 },
+            0x5e => Opcode { code, name: "MCOPY", mingas: 3, inputs: 3, outputs: 0 },
+            0x5f => Opcode { code, name: "PUSH0", mingas: 2, inputs: 0, outputs: 1 },
             0x60 => Opcode { code, name: "PUSH1", mingas: 3, inputs: 0, outputs: 1 },
             0x61 => Opcode { code, name: "PUSH2", mingas: 3, inputs: 0, outputs: 1 },
             0x62 => Opcode { code, name: "PUSH3", mingas: 3, inputs: 0, outputs: 1 },
@@ -198,6 +203,24 @@ impl WrappedOpcode {
     pub fn depth(&self) -> u32 {
         self.inputs.iter().map(|x| x.depth()).max().unwrap_or(0) + 1
     }
+
+    /// Returns whether this operation, or any operation among its inputs, matches the predicate
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::opcodes::*;
+    ///
+    /// let caller = WrappedOpcode::new(0x33, vec![]);
+    /// let opcode = WrappedOpcode::new(0x14, vec![WrappedInput::Opcode(caller), WrappedInput::Raw(1.into())]);
+    /// assert!(opcode.any(&|operation| operation.opcode.name == "CALLER"));
+    /// assert!(!opcode.any(&|operation| operation.opcode.name == "ORIGIN"));
+    /// ```
+    pub fn any(&self, predicate: &impl Fn(&WrappedOpcode) -> bool) -> bool {
+        predicate(self) ||
+            self.inputs.iter().any(|input| match input {
+                WrappedInput::Raw(_) => false,
+                WrappedInput::Opcode(opcode) => opcode.any(predicate),
+            })
+    }
 }
 
 impl WrappedInput {
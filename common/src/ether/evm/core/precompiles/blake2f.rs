@@ -0,0 +1,114 @@
+// the BLAKE2b initialization vector
+const IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+// the message word permutations used by each round
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The BLAKE2b mixing function.
+fn mix(v: &mut [u64; 16], (a, b, c, d): (usize, usize, usize, usize), x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// Reads `N` little-endian u64 words from the given bytes.
+fn read_words<const N: usize>(bytes: &[u8]) -> [u64; N] {
+    let mut words = [0u64; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    words
+}
+
+/// Runs the BLAKE2b compression function F, as defined in EIP-152. The input must be exactly 213
+/// bytes: the round count, state vector, message block, offset counters, and final block flag.
+pub(super) fn compress(input: &[u8]) -> Option<(u128, Vec<u8>)> {
+    if input.len() != 213 || input[212] > 1 {
+        return None
+    }
+
+    let rounds = u32::from_be_bytes(input[0..4].try_into().unwrap());
+    let mut h: [u64; 8] = read_words(&input[4..68]);
+    let m: [u64; 16] = read_words(&input[68..196]);
+    let t: [u64; 2] = read_words(&input[196..212]);
+
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(&h);
+    v[8..].copy_from_slice(&IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if input[212] == 1 {
+        v[14] = !v[14];
+    }
+
+    for round in 0..rounds as usize {
+        let s = &SIGMA[round % 10];
+        mix(&mut v, (0, 4, 8, 12), m[s[0]], m[s[1]]);
+        mix(&mut v, (1, 5, 9, 13), m[s[2]], m[s[3]]);
+        mix(&mut v, (2, 6, 10, 14), m[s[4]], m[s[5]]);
+        mix(&mut v, (3, 7, 11, 15), m[s[6]], m[s[7]]);
+        mix(&mut v, (0, 5, 10, 15), m[s[8]], m[s[9]]);
+        mix(&mut v, (1, 6, 11, 12), m[s[10]], m[s[11]]);
+        mix(&mut v, (2, 7, 8, 13), m[s[12]], m[s[13]]);
+        mix(&mut v, (3, 4, 9, 14), m[s[14]], m[s[15]]);
+    }
+
+    for (i, word) in h.iter_mut().enumerate() {
+        *word ^= v[i] ^ v[i + 8];
+    }
+
+    Some((rounds as u128, h.iter().flat_map(|word| word.to_le_bytes()).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ether::evm::core::precompiles::blake2f::compress, utils::strings::decode_hex};
+
+    #[test]
+    fn test_blake2f() {
+        // BLAKE2b-512("abc"), from EIP-152
+        let input = decode_hex("0000000c48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b61626300000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000300000000000000000000000000000001").unwrap();
+
+        assert_eq!(
+            compress(&input),
+            Some((
+                12,
+                decode_hex("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_blake2f_invalid_input() {
+        assert_eq!(compress(&[0; 212]), None);
+
+        let mut input = vec![0; 213];
+        input[212] = 2;
+        assert_eq!(compress(&input), None);
+    }
+}
@@ -0,0 +1,168 @@
+use substrate_bn::{pairing_batch, AffineG1, AffineG2, Fq, Fq2, Fr, Group, Gt, G1, G2};
+
+use super::right_pad;
+
+/// Reads a G1 point from its 64 byte encoding, where all zeroes is the point at infinity. Returns
+/// `None` if either coordinate isn't in the field, or the point isn't on the curve.
+fn read_g1(bytes: &[u8]) -> Option<G1> {
+    let x = Fq::from_slice(&bytes[..32]).ok()?;
+    let y = Fq::from_slice(&bytes[32..64]).ok()?;
+
+    if x.is_zero() && y.is_zero() {
+        return Some(G1::zero())
+    }
+    AffineG1::new(x, y).ok().map(G1::from)
+}
+
+/// Reads a G2 point from its 128 byte encoding, where each coordinate's imaginary part comes
+/// before its real part, and all zeroes is the point at infinity. Returns `None` if any part isn't
+/// in the field, or the point isn't in the G2 subgroup.
+fn read_g2(bytes: &[u8]) -> Option<G2> {
+    let x = Fq2::new(Fq::from_slice(&bytes[32..64]).ok()?, Fq::from_slice(&bytes[..32]).ok()?);
+    let y = Fq2::new(Fq::from_slice(&bytes[96..128]).ok()?, Fq::from_slice(&bytes[64..96]).ok()?);
+
+    if x.is_zero() && y.is_zero() {
+        return Some(G2::zero())
+    }
+    AffineG2::new(x, y).ok().map(G2::from)
+}
+
+/// Encodes a G1 point as 64 bytes, where all zeroes is the point at infinity.
+fn encode_g1(point: G1) -> Vec<u8> {
+    let mut output = vec![0u8; 64];
+    if let Some(point) = AffineG1::from_jacobian(point) {
+        point.x().to_big_endian(&mut output[..32]).expect("output holds a field element");
+        point.y().to_big_endian(&mut output[32..]).expect("output holds a field element");
+    }
+    output
+}
+
+/// ecAdd, at address 0x06. Adds two G1 points.
+pub(super) fn add(input: &[u8]) -> Option<(u128, Vec<u8>)> {
+    let input = right_pad(input, 128);
+    let a = read_g1(&input[..64])?;
+    let b = read_g1(&input[64..])?;
+
+    Some((150, encode_g1(a + b)))
+}
+
+/// ecMul, at address 0x07. Multiplies a G1 point by a scalar.
+pub(super) fn multiply(input: &[u8]) -> Option<(u128, Vec<u8>)> {
+    let input = right_pad(input, 96);
+    let point = read_g1(&input[..64])?;
+    let scalar = Fr::from_slice(&input[64..]).ok()?;
+
+    Some((6000, encode_g1(point * scalar)))
+}
+
+/// ecPairing, at address 0x08. Checks whether the product of the pairings of each (G1, G2) pair
+/// in the input is one.
+pub(super) fn pairing(input: &[u8]) -> Option<(u128, Vec<u8>)> {
+    if !input.len().is_multiple_of(192) {
+        return None
+    }
+
+    let mut pairs = Vec::new();
+    for pair in input.chunks_exact(192) {
+        let p = read_g1(&pair[..64])?;
+        let q = read_g2(&pair[64..])?;
+
+        // pairings with the point at infinity are one
+        if !p.is_zero() && !q.is_zero() {
+            pairs.push((p, q));
+        }
+    }
+
+    let mut output = vec![0u8; 32];
+    output[31] = (pairs.is_empty() || pairing_batch(&pairs) == Gt::one()) as u8;
+
+    Some((45000 + 34000 * (input.len() / 192) as u128, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ether::evm::core::precompiles::bn128::*, utils::strings::decode_hex};
+
+    // the G1 and G2 generators, and their doubles
+    const G1: &str = "00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000000002";
+    const G1_DOUBLE: &str = "030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd315ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4";
+    const G2: &str = "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c21800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+    const G2_DOUBLE: &str = "203e205db4f19b37b60121b83a7333706db86431c6d835849957ed8c3928ad7927dc7234fd11d3e8c36c59277c3e6f149d5cd3cfa9a62aee49f8130962b4b3b9195e8aa5b7827463722b8c153931579d3505566b4edf48d498e185f0509de15204bb53b8977e5f92a0bc372742c4830944a59b4fe6b1c0466e2a6dad122b5d2e";
+
+    // the modulus of the bn128 base field, and the order of its G1 and G2 groups
+    const FIELD_MODULUS: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+    const CURVE_ORDER: &str = "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+
+    #[test]
+    fn test_ec_add() {
+        let input = decode_hex(&format!("{G1}{G1}")).unwrap();
+        assert_eq!(add(&input), Some((150, decode_hex(G1_DOUBLE).unwrap())));
+
+        // adding the point at infinity returns the other point
+        assert_eq!(add(&decode_hex(G1).unwrap()), Some((150, decode_hex(G1).unwrap())));
+
+        // points not on the curve are rejected, as are coordinates outside of the field
+        let input = decode_hex(&format!("{G1}{}", "00".repeat(63) + "01")).unwrap();
+        assert_eq!(add(&input), None);
+        let input = decode_hex(&format!("{FIELD_MODULUS}{}", "00".repeat(32))).unwrap();
+        assert_eq!(add(&input), None);
+    }
+
+    #[test]
+    fn test_ec_add_eip196() {
+        let input = decode_hex("18b18acfb4c2c30276db5411368e7185b311dd124691610c5d3b74034e093dc9063c909c4720840cb5134cb9f59fa749755796819658d32efc0d288198f3726607c2b7f58a84bd6145f00c9c2bc0bb1a187f20ff2c92963a88019e7c6a014eed06614e20c147e940f2d70da3f74c9a17df361706a4485c742bd6788478fa17d7").unwrap();
+        let expected = decode_hex("2243525c5efd4b9c3d3c45ac0ca3fe4dd85e830a4ce6b65fa1eeaee202839703301d1d33be6da8e509df21cc35964723180eed7532537db9ae5e7d48f195c915").unwrap();
+        assert_eq!(add(&input), Some((150, expected)));
+    }
+
+    #[test]
+    fn test_ec_multiply() {
+        let input = decode_hex(&format!("{G1}{:064x}", 2)).unwrap();
+        assert_eq!(multiply(&input), Some((6000, decode_hex(G1_DOUBLE).unwrap())));
+
+        // multiplying by the curve order returns the point at infinity
+        let input = decode_hex(&format!("{G1}{CURVE_ORDER}")).unwrap();
+        assert_eq!(multiply(&input), Some((6000, vec![0; 64])));
+    }
+
+    #[test]
+    fn test_ec_multiply_eip196() {
+        let input = decode_hex("2bd3e6d0f3b142924f5ca7b49ce5b9d54c4703d7ae5648e61d02268b1a0a9fb721611ce0a6af85915e2f1d70300909ce2e49dfad4a4619c8390cae66cefdb20400000000000000000000000000000000000000000000000011138ce750fa15c2").unwrap();
+        let expected = decode_hex("070a8d6a982153cae4be29d434e8faef8a47b274a053f5a4ee2a6c9c13c31e5c031b8ce914eba3a9ffb989f9cdd5b0f01943074bf4f0f315690ec3cec6981afc").unwrap();
+        assert_eq!(multiply(&input), Some((6000, expected)));
+    }
+
+    #[test]
+    fn test_ec_pairing() {
+        let negated_g1 = format!(
+            "{:064x}{}",
+            1, "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45"
+        );
+
+        // e(2 * G1, G2) * e(-G1, 2 * G2) = 1
+        let input = decode_hex(&format!("{G1_DOUBLE}{G2}{negated_g1}{G2_DOUBLE}")).unwrap();
+        let mut expected = vec![0u8; 32];
+        expected[31] = 1;
+        assert_eq!(pairing(&input), Some((113000, expected)));
+
+        // e(G1, G2) != 1
+        let input = decode_hex(&format!("{G1}{G2}")).unwrap();
+        assert_eq!(pairing(&input), Some((79000, vec![0; 32])));
+
+        // the empty product is one, and partial pairs are rejected
+        assert_eq!(pairing(&[]).unwrap().1[31], 1);
+        assert_eq!(pairing(&[0; 191]), None);
+    }
+
+    #[test]
+    fn test_ec_pairing_eip197() {
+        let input = decode_hex("1c76476f4def4bb94541d57ebba1193381ffa7aa76ada664dd31c16024c43f593034dd2920f673e204fee2811c678745fc819b55d3e9d294e45c9b03a76aef41209dd15ebff5d46c4bd888e51a93cf99a7329636c63514396b4a452003a35bf704bf11ca01483bfa8b34b43561848d28905960114c8ac04049af4b6315a416782bb8324af6cfc93537a2ad1a445cfd0ca2a71acd7ac41fadbf933c2a51be344d120a2a4cf30c1bf9845f20c6fe39e07ea2cce61f0c9bb048165fe5e4de877550111e129f1cf1097710d41c4ac70fcdfa5ba2023c6ff1cbeac322de49d1b6df7c2032c61a830e3c17286de9462bf242fca2883585b93870a73853face6a6bf411198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c21800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa").unwrap();
+        let mut expected = vec![0u8; 32];
+        expected[31] = 1;
+        assert_eq!(pairing(&input), Some((113000, expected)));
+
+        // points outside of the G2 subgroup are rejected
+        let input = decode_hex(&format!("{G1}{}", "00".repeat(127) + "01")).unwrap();
+        assert_eq!(pairing(&input), None);
+    }
+}
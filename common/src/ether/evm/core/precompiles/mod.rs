@@ -0,0 +1,216 @@
+mod blake2f;
+mod bn128;
+
+use ethers::{
+    prelude::U256,
+    types::{Signature, H256},
+};
+use num_bigint::BigUint;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+// the order of the secp256k1 curve, used to normalize high-s signatures
+const SECP256K1_ORDER: U256 =
+    U256([0xbfd25e8cd0364141, 0xbaaedce6af48a03b, 0xfffffffffffffffe, 0xffffffffffffffff]);
+
+// modexp inputs larger than the VM's memory can't be provided by a contract
+const MODEXP_MAX_LENGTH: usize = 65536;
+
+/// Whether the given address is one of the precompiled contracts, 0x01 through 0x09.
+///
+/// ```
+/// use ethers::prelude::U256;
+/// use heimdall_common::ether::evm::core::precompiles::is_precompile;
+///
+/// assert!(is_precompile(U256::from(1)));
+/// assert!(!is_precompile(U256::from(10)));
+/// ```
+pub fn is_precompile(address: U256) -> bool {
+    address >= U256::one() && address <= U256::from(9)
+}
+
+/// Executes the precompiled contract at the given address with the given input, returning the gas
+/// used and its output. Returns `None` if the address isn't a precompile, or if the call fails,
+/// in which case all gas passed to the call is consumed.
+///
+/// ```
+/// use ethers::prelude::U256;
+/// use heimdall_common::ether::evm::core::precompiles::execute_precompile;
+///
+/// // the identity precompile returns its input
+/// assert_eq!(execute_precompile(U256::from(4), &[1, 2, 3]), Some((18, vec![1, 2, 3])));
+/// ```
+pub fn execute_precompile(address: U256, input: &[u8]) -> Option<(u128, Vec<u8>)> {
+    if !is_precompile(address) {
+        return None
+    }
+
+    let words = (input.len() as u128).div_ceil(32);
+    match address.as_u64() {
+        1 => Some((3000, ecrecover(input))),
+        2 => Some((60 + 12 * words, Sha256::digest(input).to_vec())),
+        3 => Some((600 + 120 * words, left_pad(&Ripemd160::digest(input), 32))),
+        4 => Some((15 + 3 * words, input.to_vec())),
+        5 => modexp(input),
+        6 => bn128::add(input),
+        7 => bn128::multiply(input),
+        8 => bn128::pairing(input),
+        _ => blake2f::compress(input),
+    }
+}
+
+/// Right-pads the given input with zeros to the given length, truncating it if it's longer.
+fn right_pad(input: &[u8], length: usize) -> Vec<u8> {
+    let mut padded = input[..input.len().min(length)].to_vec();
+    padded.resize(length, 0);
+    padded
+}
+
+/// Left-pads the given input with zeros to the given length.
+fn left_pad(input: &[u8], length: usize) -> Vec<u8> {
+    let mut padded = vec![0; length.saturating_sub(input.len())];
+    padded.extend_from_slice(input);
+    padded
+}
+
+/// Recovers the address which signed the given hash, returning nothing if the signature is
+/// invalid.
+fn ecrecover(input: &[u8]) -> Vec<u8> {
+    let input = right_pad(input, 128);
+    let v = U256::from_big_endian(&input[32..64]);
+    let r = U256::from_big_endian(&input[64..96]);
+    let mut s = U256::from_big_endian(&input[96..128]);
+
+    if v != U256::from(27) && v != U256::from(28) {
+        return Vec::new()
+    }
+    let mut v = v.as_u64();
+
+    // the precompile accepts high-s signatures, which must be normalized before recovery
+    if s > SECP256K1_ORDER / 2 && s < SECP256K1_ORDER {
+        s = SECP256K1_ORDER - s;
+        v = 55 - v;
+    }
+
+    match (Signature { r, s, v }).recover(H256::from_slice(&input[..32])) {
+        Ok(address) => left_pad(address.as_bytes(), 32),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Computes `base ** exponent % modulus` for arbitrary length inputs, as defined in EIP-198, with
+/// gas costs from EIP-2565.
+fn modexp(input: &[u8]) -> Option<(u128, Vec<u8>)> {
+    let header = right_pad(input, 96);
+    let mut lengths = [0usize; 3];
+    for (i, length) in lengths.iter_mut().enumerate() {
+        let value = U256::from_big_endian(&header[i * 32..(i + 1) * 32]);
+        if value > U256::from(MODEXP_MAX_LENGTH) {
+            return None
+        }
+        *length = value.as_usize();
+    }
+    let [base_length, exponent_length, modulus_length] = lengths;
+
+    let data = right_pad(
+        input.get(96..).unwrap_or_default(),
+        base_length + exponent_length + modulus_length,
+    );
+    let (base, rest) = data.split_at(base_length);
+    let (exponent, modulus) = rest.split_at(exponent_length);
+
+    // only the first 32 bytes of the exponent are used to calculate gas
+    let exponent_head = U256::from_big_endian(&exponent[..exponent_length.min(32)]);
+    let iteration_count = match exponent_length {
+        0..=32 => exponent_head.bits().saturating_sub(1) as u128,
+        _ => 8 * (exponent_length as u128 - 32) + exponent_head.bits().saturating_sub(1) as u128,
+    };
+    let words = (base_length.max(modulus_length) as u128).div_ceil(8);
+    let gas = (words * words * iteration_count.max(1) / 3).max(200);
+
+    let modulus = BigUint::from_bytes_be(modulus);
+    if modulus == BigUint::default() {
+        return Some((gas, vec![0; modulus_length]))
+    }
+
+    let result = BigUint::from_bytes_be(base)
+        .modpow(&BigUint::from_bytes_be(exponent), &modulus)
+        .to_bytes_be();
+    Some((gas, left_pad(&result, modulus_length)))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        prelude::U256,
+        signers::{LocalWallet, Signer},
+        types::{BigEndianHash, H256},
+    };
+
+    use crate::{ether::evm::core::precompiles::*, utils::strings::decode_hex};
+
+    #[test]
+    fn test_ecrecover() {
+        let wallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse::<LocalWallet>()
+            .unwrap();
+        let hash = H256::from_low_u64_be(0x1234);
+        let signature = wallet.sign_hash(hash).unwrap();
+
+        let mut input = hash.as_bytes().to_vec();
+        input.extend(left_pad(&[signature.v as u8], 32));
+        input.extend(H256::from_uint(&signature.r).as_bytes());
+        input.extend(H256::from_uint(&signature.s).as_bytes());
+
+        let (gas, output) = execute_precompile(U256::from(1), &input).unwrap();
+        assert_eq!(gas, 3000);
+        assert_eq!(output, left_pad(wallet.address().as_bytes(), 32));
+
+        // the high-s form of the same signature recovers the same address
+        let mut high_s = input.clone();
+        high_s[63] = 55 - high_s[63];
+        high_s[96..].copy_from_slice(H256::from_uint(&(SECP256K1_ORDER - signature.s)).as_bytes());
+        assert_eq!(execute_precompile(U256::from(1), &high_s).unwrap().1, output);
+
+        // invalid signatures return nothing
+        input[63] = 29;
+        assert_eq!(execute_precompile(U256::from(1), &input), Some((3000, Vec::new())));
+    }
+
+    #[test]
+    fn test_hash_precompiles() {
+        assert_eq!(
+            execute_precompile(U256::from(2), &[]),
+            Some((
+                60,
+                decode_hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                    .unwrap()
+            ))
+        );
+        assert_eq!(
+            execute_precompile(U256::from(3), &[]),
+            Some((
+                600,
+                decode_hex("0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31")
+                    .unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_modexp() {
+        // 3 ** 5 % 7
+        let input = decode_hex(&format!("{:064x}{:064x}{:064x}030507", 1, 1, 1)).unwrap();
+        assert_eq!(execute_precompile(U256::from(5), &input), Some((200, vec![5])));
+
+        // a zero modulus returns zero
+        let input = decode_hex(&format!("{:064x}{:064x}{:064x}0305", 1, 1, 2)).unwrap();
+        assert_eq!(execute_precompile(U256::from(5), &input), Some((200, vec![0, 0])));
+    }
+
+    #[test]
+    fn test_not_a_precompile() {
+        assert_eq!(execute_precompile(U256::zero(), &[]), None);
+        assert_eq!(execute_precompile(U256::from(10), &[]), None);
+    }
+}
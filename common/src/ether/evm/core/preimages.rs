@@ -0,0 +1,163 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+use ethers::types::U256;
+
+use crate::utils::strings::encode_hex;
+
+// preimages longer than this are hashes of data, such as strings, rather than of mapping keys
+const MAX_PREIMAGE_SIZE: usize = 1024;
+
+// members of a struct or fixed-size array stored in a mapping are stored at small offsets from the
+// slot the mapping computes, so slots up to this far past a known hash are resolved too
+const MAX_MEMBER_OFFSET: u64 = 256;
+
+/// A storage slot computed as `keccak(key . slot)`, the way Solidity lays out mappings. Slots of
+/// nested mappings are unwrapped, so `keys` holds the key used at each level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MappingSlot {
+    // the slot the outermost mapping is declared at
+    pub slot: U256,
+
+    // the key used at each level of the mapping, outermost first
+    pub keys: Vec<Vec<u8>>,
+
+    // how far past the computed slot the slot is, for members of structs and fixed-size arrays
+    pub offset: u64,
+}
+
+impl Display for MappingSlot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "mapping[{:#x}]", self.slot)?;
+        for key in &self.keys {
+            match key.len() {
+                32 => write!(f, "[{:#x}]", U256::from_big_endian(key))?,
+                _ => write!(f, "[0x{}]", encode_hex(key.to_vec()))?,
+            }
+        }
+        match self.offset {
+            0 => Ok(()),
+            offset => write!(f, " + {offset:#x}"),
+        }
+    }
+}
+
+/// The [`Preimages`] of every SHA3 computed during execution, which are kept so hashed storage
+/// slots can be traced back to the mapping keys they were computed from. Clones share the same
+/// preimages.
+#[derive(Clone, Debug, Default)]
+pub struct Preimages {
+    preimages: Arc<Mutex<BTreeMap<U256, Vec<u8>>>>,
+}
+
+impl Preimages {
+    /// Records the data the given hash was computed from.
+    pub(crate) fn insert(&self, hash: U256, preimage: &[u8]) {
+        if preimage.len() > MAX_PREIMAGE_SIZE {
+            return
+        }
+        self.preimages.lock().expect("preimages lock poisoned").insert(hash, preimage.to_vec());
+    }
+
+    /// The data the given hash was computed from, if it was computed during execution.
+    pub fn get(&self, hash: U256) -> Option<Vec<u8>> {
+        self.preimages.lock().expect("preimages lock poisoned").get(&hash).cloned()
+    }
+
+    /// Reverses the given storage slot into the mapping access it was computed from, if it was
+    /// computed as `keccak(key . slot)` during execution.
+    ///
+    /// ```
+    /// use ethers::{types::U256, utils::keccak256};
+    /// use heimdall_common::ether::evm::core::vm::VM;
+    ///
+    /// // balances[caller], where balances is declared at slot 1
+    /// // mstore(0, caller), mstore(32, 1), sload(sha3(0, 64))
+    /// let mut vm = VM::new(
+    ///     String::from("0x33600052600160205260406000205400"),
+    ///     String::from("0x"),
+    ///     String::from("0x6865696d64616c6c000000000061646472657373"),
+    ///     String::from("0x6865696d64616c6c0000000000006f726967696e"),
+    ///     String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+    ///     0,
+    ///     u128::MAX,
+    /// );
+    /// vm.execute();
+    ///
+    /// let mut preimage = [0u8; 64];
+    /// preimage[12..32].copy_from_slice(&vm.caller);
+    /// preimage[63] = 1;
+    /// let slot = U256::from_big_endian(&keccak256(preimage));
+    ///
+    /// let mapping = vm.preimages.resolve(slot).unwrap();
+    /// assert_eq!(mapping.to_string(), "mapping[0x1][0x6865696d64616c6c00000000000063616c6c6572]");
+    /// ```
+    pub fn resolve(&self, slot: U256) -> Option<MappingSlot> {
+        let preimages = self.preimages.lock().expect("preimages lock poisoned");
+        resolve(&preimages, slot)
+    }
+}
+
+fn resolve(preimages: &BTreeMap<U256, Vec<u8>>, slot: U256) -> Option<MappingSlot> {
+    // the slot is either a hash, or a small offset past one
+    let (hash, preimage) = preimages.range(..=slot).next_back()?;
+    let offset = slot - hash;
+    if offset >= U256::from(MAX_MEMBER_OFFSET) || preimage.len() <= 32 {
+        return None
+    }
+
+    // mappings hash the key followed by the slot of the mapping
+    let (key, base) = preimage.split_at(preimage.len() - 32);
+    let base = U256::from_big_endian(base);
+
+    // the mapping may itself be the value of another mapping
+    let mut mapping = match resolve(preimages, base) {
+        Some(parent) if parent.offset == 0 => parent,
+        _ => MappingSlot { slot: base, keys: Vec::new(), offset: 0 },
+    };
+    mapping.keys.push(key.to_vec());
+    mapping.offset = offset.as_u64();
+    Some(mapping)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{types::U256, utils::keccak256};
+
+    use crate::ether::evm::core::preimages::Preimages;
+
+    fn hash(preimages: &Preimages, key: U256, slot: U256) -> U256 {
+        let mut preimage = [0u8; 64];
+        key.to_big_endian(&mut preimage[..32]);
+        slot.to_big_endian(&mut preimage[32..]);
+        let hash = U256::from_big_endian(&keccak256(preimage));
+        preimages.insert(hash, &preimage);
+        hash
+    }
+
+    #[test]
+    fn test_resolve_nested_mapping() {
+        // allowances[owner][spender], where allowances is declared at slot 2
+        let preimages = Preimages::default();
+        let inner = hash(&preimages, U256::from(0xa), U256::from(2));
+        let slot = hash(&preimages, U256::from(0xb), inner);
+
+        let mapping = preimages.resolve(slot).unwrap();
+        assert_eq!(mapping.slot, U256::from(2));
+        assert_eq!(mapping.keys.len(), 2);
+        assert_eq!(mapping.to_string(), "mapping[0x2][0xa][0xb]");
+    }
+
+    #[test]
+    fn test_resolve_struct_member() {
+        let preimages = Preimages::default();
+        let slot = hash(&preimages, U256::from(0xa), U256::from(3));
+
+        assert_eq!(preimages.resolve(slot + 2).unwrap().to_string(), "mapping[0x3][0xa] + 0x2");
+        assert!(preimages.resolve(U256::from(3)).is_none());
+        assert!(preimages.resolve(slot + 1000).is_none());
+    }
+}
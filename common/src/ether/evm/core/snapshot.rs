@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+
+use ethers::types::U256;
+
+use super::{fault::Fault, log::Log, memory::Memory, stack::Stack, storage::Storage, vm::VM};
+
+/// A [`VmSnapshot`] is the execution state of a [`VM`] at a single point, which the VM can be
+/// rolled back to with [`VM::restore`]. \
+/// \
+/// Unlike cloning the [`VM`], a snapshot leaves out everything execution doesn't change, such as
+/// the bytecode, calldata, and caches, and only keeps the storage of the contracts the VM has
+/// called, so forking exploration at a decision point is cheap.
+#[derive(Clone, Debug)]
+pub struct VmSnapshot {
+    pub stack: Stack,
+    pub memory: Memory,
+    pub storage: Storage,
+    pub transient_storage: Storage,
+    pub events: Vec<Log>,
+    pub instruction: u128,
+    pub gas_remaining: u128,
+    pub gas_used: u128,
+    pub gas_refund: i128,
+    pub returndata: Vec<u8>,
+    pub exitcode: u128,
+    pub fault: Option<Fault>,
+    pub nonce: u64,
+
+    // the storage and transient storage of every contract the VM has called or created
+    contracts: HashMap<U256, (Storage, Storage)>,
+    address_access_set: HashSet<U256>,
+    call_returndata: Option<Vec<u8>>,
+}
+
+impl VM {
+    /// Takes a [`VmSnapshot`] of the VM's current execution state.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::vm::VM;
+    ///
+    /// // sstore(0, 1), stop
+    /// let mut vm = VM::new(
+    ///     String::from("0x600160005500"),
+    ///     String::from("0x"),
+    ///     String::from("0x6865696d64616c6c000000000061646472657373"),
+    ///     String::from("0x6865696d64616c6c0000000000006f726967696e"),
+    ///     String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+    ///     0,
+    ///     u128::MAX,
+    /// );
+    ///
+    /// let snapshot = vm.snapshot();
+    /// vm.execute();
+    /// assert_eq!(vm.storage.storage.len(), 1);
+    ///
+    /// vm.restore(&snapshot);
+    /// assert!(vm.storage.storage.is_empty());
+    /// assert_eq!(vm.instruction, 1);
+    /// ```
+    pub fn snapshot(&self) -> VmSnapshot {
+        VmSnapshot {
+            stack: self.stack.clone(),
+            memory: self.memory.clone(),
+            storage: self.storage.clone(),
+            transient_storage: self.transient_storage.clone(),
+            events: self.events.clone(),
+            instruction: self.instruction,
+            gas_remaining: self.gas_remaining,
+            gas_used: self.gas_used,
+            gas_refund: self.gas_refund,
+            returndata: self.returndata.clone(),
+            exitcode: self.exitcode,
+            fault: self.fault.clone(),
+            nonce: self.nonce,
+            contracts: self
+                .contracts
+                .iter()
+                .map(|(address, contract)| {
+                    (*address, (contract.storage.clone(), contract.transient_storage.clone()))
+                })
+                .collect(),
+            address_access_set: self.address_access_set.clone(),
+            call_returndata: self.call_returndata.clone(),
+        }
+    }
+
+    /// Rolls the VM back to the given [`VmSnapshot`], which should have been taken from this VM.
+    /// Contracts created since the snapshot was taken are removed, and the storage of every other
+    /// contract is rolled back along with the VM's own.
+    pub fn restore(&mut self, snapshot: &VmSnapshot) {
+        self.stack = snapshot.stack.clone();
+        self.memory = snapshot.memory.clone();
+        self.storage = snapshot.storage.clone();
+        self.transient_storage = snapshot.transient_storage.clone();
+        self.events = snapshot.events.clone();
+        self.instruction = snapshot.instruction;
+        self.gas_remaining = snapshot.gas_remaining;
+        self.gas_used = snapshot.gas_used;
+        self.gas_refund = snapshot.gas_refund;
+        self.returndata = snapshot.returndata.clone();
+        self.exitcode = snapshot.exitcode;
+        self.fault = snapshot.fault.clone();
+        self.nonce = snapshot.nonce;
+        self.address_access_set = snapshot.address_access_set.clone();
+        self.call_returndata = snapshot.call_returndata.clone();
+
+        self.contracts.retain(|address, _| snapshot.contracts.contains_key(address));
+        for (address, contract) in self.contracts.iter_mut() {
+            let (storage, transient_storage) = &snapshot.contracts[address];
+            contract.storage = storage.clone();
+            contract.transient_storage = transient_storage.clone();
+        }
+    }
+
+    /// Executes instructions until the next JUMPI, stopping before it's executed. This is where
+    /// execution forks, so a [`VmSnapshot`] taken here can be restored once for each branch. \
+    /// \
+    /// Returns the instruction of the JUMPI, or `None` if execution ends first.
+    pub fn step_until_branch(&mut self) -> Option<u128> {
+        while self.bytecode.len() >= self.instruction as usize && self.exitcode == 255 {
+            if self.bytecode.get(self.instruction as usize - 1) == Some(&0x57) {
+                return Some(self.instruction)
+            }
+            self.step();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::core::vm::new_test_vm;
+
+    #[test]
+    fn test_restore_explores_both_branches() {
+        // if calldataload(0) { sstore(0, 2) } else { sstore(0, 1) }, log0(0, 0)
+        let mut vm = new_test_vm("0x600035600e5760016000556014565b60026000555b60006000a000", "0x");
+        assert_eq!(vm.step_until_branch(), Some(6));
+        let snapshot = vm.snapshot();
+
+        vm.execute();
+        assert_eq!(vm.storage.storage[&[0u8; 32]][31], 1);
+        assert_eq!(vm.events.len(), 1);
+
+        // take the other branch from the same point
+        vm.restore(&snapshot);
+        assert!(vm.storage.storage.is_empty());
+        assert!(vm.events.is_empty());
+        vm.stack.stack[1].value = 1.into();
+        vm.execute();
+        assert_eq!(vm.storage.storage[&[0u8; 32]][31], 2);
+        assert_eq!(vm.events.len(), 1);
+    }
+}
@@ -3,11 +3,12 @@ use std::collections::{HashMap, HashSet};
 /// The [`Storage`] struct represents the storage of a contract. \
 /// \
 /// We keep track of the storage as a HashMap, as well as a HashSet of keys that have been accessed
-/// for gas calculation purposes.
+/// and the original values of keys that have been written, for gas calculation purposes.
 #[derive(Clone, Debug)]
 pub struct Storage {
     pub storage: HashMap<[u8; 32], [u8; 32]>,
     access_set: HashSet<[u8; 32]>,
+    original: HashMap<[u8; 32], [u8; 32]>,
 }
 
 impl Default for Storage {
@@ -25,7 +26,7 @@ impl Storage {
     /// let storage = Storage::new();
     /// ```
     pub fn new() -> Storage {
-        Storage { storage: HashMap::new(), access_set: HashSet::new() }
+        Storage { storage: HashMap::new(), access_set: HashSet::new(), original: HashMap::new() }
     }
 
     /// Store a key-value pair in the storage map.
@@ -64,6 +65,49 @@ impl Storage {
         }
     }
 
+    /// Whether the key has been accessed in the current transaction.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::Storage;
+    ///
+    /// let mut storage = Storage::new();
+    /// assert!(!storage.is_warm([1u8; 32]));
+    ///
+    /// storage.load([1u8; 32]);
+    /// assert!(storage.is_warm([1u8; 32]));
+    /// ```
+    pub fn is_warm(&self, key: [u8; 32]) -> bool {
+        self.access_set.contains(&key)
+    }
+
+    /// Records the value of a key before its first write in the current transaction. Later
+    /// values are ignored, so this can be called before every write.
+    pub fn set_original(&mut self, key: [u8; 32], value: [u8; 32]) {
+        self.original.entry(key).or_insert(value);
+    }
+
+    /// The value of a key at the start of the current transaction, if it has been written.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::storage::Storage;
+    ///
+    /// let mut storage = Storage::new();
+    /// storage.set_original([1u8; 32], [0u8; 32]);
+    /// storage.store([1u8; 32], [2u8; 32]);
+    /// storage.set_original([1u8; 32], [2u8; 32]);
+    ///
+    /// assert_eq!(storage.original([1u8; 32]), Some([0u8; 32]));
+    /// ```
+    pub fn original(&self, key: [u8; 32]) -> Option<[u8; 32]> {
+        self.original.get(&key).copied()
+    }
+
+    /// Starts a new transaction, so every key is cold and has no recorded original value.
+    pub fn reset_access(&mut self) {
+        self.access_set.clear();
+        self.original.clear();
+    }
+
     /// calculate the cost of accessing a key in storage
     ///
     /// ```
@@ -0,0 +1,375 @@
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+use ethers::types::U256;
+
+use crate::utils::strings::encode_hex_reduced;
+
+use super::vm::Instruction;
+
+// tainted memory beyond this size isn't tracked, since offsets this large are almost always the
+// result of fuzzed or symbolic values rather than real allocations
+const MAX_MEMORY: usize = 1 << 17;
+
+/// The calldata bytes a value was derived from, as sorted, non-overlapping ranges of offsets.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Taint {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl Taint {
+    /// A taint covering `size` bytes of calldata, starting at `offset`.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::core::taint::Taint;
+    ///
+    /// let taint = Taint::calldata(4, 32).union(&Taint::calldata(36, 32));
+    /// assert_eq!(taint.ranges(), &[(4, 68)]);
+    /// assert_eq!(taint.arguments(), vec![0, 1]);
+    /// ```
+    pub fn calldata(offset: usize, size: usize) -> Taint {
+        match size {
+            0 => Taint::default(),
+            _ => Taint { ranges: vec![(offset, offset.saturating_add(size))] },
+        }
+    }
+
+    /// The ranges of calldata offsets covered by this taint, with exclusive ends.
+    pub fn ranges(&self) -> &[(usize, usize)] {
+        &self.ranges
+    }
+
+    /// Whether this value was derived from calldata at all.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The taint of a value derived from both this value and the other.
+    pub fn union(&self, other: &Taint) -> Taint {
+        if other.is_empty() {
+            return self.clone()
+        }
+        if self.is_empty() {
+            return other.clone()
+        }
+
+        let mut ranges = self.ranges.iter().chain(&other.ranges).copied().collect::<Vec<_>>();
+        ranges.sort();
+
+        // merge overlapping and adjacent ranges
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        Taint { ranges: merged }
+    }
+
+    /// The indices of the ABI-encoded argument words covered by this taint, which follow the
+    /// 4-byte selector. Bytes of the selector itself aren't included.
+    pub fn arguments(&self) -> Vec<usize> {
+        let mut arguments = BTreeSet::new();
+        for (start, end) in &self.ranges {
+            let start = start.max(&4) - 4;
+            let end = end.saturating_sub(4);
+            if start < end {
+                arguments.extend(start / 32..=(end - 1) / 32);
+            }
+        }
+        arguments.into_iter().collect()
+    }
+}
+
+impl Display for Taint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|(start, end)| format!("calldata[{start}:{end}]"))
+            .collect::<Vec<String>>();
+        write!(f, "{}", ranges.join(", "))
+    }
+}
+
+/// An operation whose effect can be controlled by calldata.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sink {
+    // an SSTORE to the given slot, tainted by either the slot or the value written
+    StorageWrite(U256),
+
+    // the address called by CALL, CALLCODE, DELEGATECALL, or STATICCALL
+    CallTarget,
+
+    // the value sent by CALL, CALLCODE, CREATE, or CREATE2
+    ValueTransfer,
+
+    // the account which receives the contract's balance on SELFDESTRUCT
+    SelfdestructRecipient,
+}
+
+impl Display for Sink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sink::StorageWrite(slot) => write!(f, "storage write to {}", encode_hex_reduced(*slot)),
+            Sink::CallTarget => write!(f, "call target"),
+            Sink::ValueTransfer => write!(f, "value transfer"),
+            Sink::SelfdestructRecipient => write!(f, "selfdestruct recipient"),
+        }
+    }
+}
+
+/// A [`Sink`] reached with a value derived from calldata.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaintedSink {
+    pub instruction: u128,
+    pub sink: Sink,
+    pub taint: Taint,
+}
+
+impl Display for TaintedSink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at instruction {} from {}", self.sink, self.instruction, self.taint)
+    }
+}
+
+/// The [`TaintTracker`] shadows the stack, memory, and storage of a [`VM`](super::vm::VM) with the
+/// calldata bytes each value was derived from, recording every [`TaintedSink`] reached. Taint
+/// follows data flow only, so a value loaded from a calldata-dependent offset isn't tainted by the
+/// offset, and it isn't tracked into child contexts. \
+/// \
+/// Clones share the same recorded sinks, so a tracker attached to a [`VM`](super::vm::VM)
+/// collects the sinks of every path symbolic execution explores from it.
+#[derive(Clone, Debug, Default)]
+pub struct TaintTracker {
+    stack: VecDeque<Taint>,
+    memory: Vec<Taint>,
+    storage: HashMap<U256, Taint>,
+    sinks: Arc<Mutex<BTreeSet<TaintedSink>>>,
+}
+
+impl TaintTracker {
+    /// The sinks reached with tainted values so far, ordered by instruction.
+    pub fn sinks(&self) -> Vec<TaintedSink> {
+        self.sinks.lock().expect("taint lock poisoned").iter().cloned().collect()
+    }
+
+    /// Propagates taint through the given instruction, which the VM has just executed.
+    pub(crate) fn apply(&mut self, instruction: &Instruction) {
+        let opcode = instruction.opcode;
+        let Some(details) = &instruction.opcode_details else { return };
+
+        // DUP and SWAP move taint around the stack without combining it
+        if (0x80..=0x8f).contains(&opcode) {
+            let taint = self.stack.get((opcode - 0x80) as usize).cloned().unwrap_or_default();
+            self.stack.push_front(taint);
+            return
+        }
+        if (0x90..=0x9f).contains(&opcode) {
+            let depth = (opcode - 0x8f) as usize;
+            if self.stack.len() > depth {
+                self.stack.swap(0, depth);
+            }
+            return
+        }
+
+        let inputs = (0..details.inputs)
+            .map(|_| self.stack.pop_front().unwrap_or_default())
+            .collect::<Vec<Taint>>();
+        let word = |index: usize| instruction.inputs.get(index).copied().unwrap_or_default();
+        let value = |index: usize| -> Option<usize> { word(index).try_into().ok() };
+
+        let output = match opcode {
+            // arithmetic, comparison, and bitwise operations combine the taint of their inputs
+            0x01..=0x1d => inputs.iter().fold(Taint::default(), |taint, input| taint.union(input)),
+
+            // CALLDATALOAD
+            0x35 => value(0).map(|offset| Taint::calldata(offset, 32)).unwrap_or_default(),
+
+            // SHA3 and MLOAD
+            0x20 => self.read_memory(value(0), value(1)),
+            0x51 => self.read_memory(value(0), Some(32)),
+
+            // SLOAD
+            0x54 => self.storage.get(&word(0)).cloned().unwrap_or_default(),
+
+            // CALLDATACOPY
+            0x37 => {
+                if let (Some(dest), Some(offset), Some(size)) = (value(0), value(1), value(2)) {
+                    if dest.saturating_add(size) <= MAX_MEMORY {
+                        let taints = (0..size)
+                            .map(|i| Taint::calldata(offset.saturating_add(i), 1))
+                            .collect();
+                        self.write_memory(dest, taints);
+                    }
+                }
+                Taint::default()
+            }
+
+            // CODECOPY and RETURNDATACOPY overwrite memory with untainted data
+            0x39 | 0x3e => {
+                self.clear_memory(value(0), value(2));
+                Taint::default()
+            }
+
+            // EXTCODECOPY
+            0x3c => {
+                self.clear_memory(value(1), value(3));
+                Taint::default()
+            }
+
+            // MSTORE and MSTORE8
+            0x52 | 0x53 => {
+                if let Some(offset) = value(0) {
+                    let size = if opcode == 0x52 { 32 } else { 1 };
+                    self.write_memory(offset, vec![inputs[1].clone(); size]);
+                }
+                Taint::default()
+            }
+
+            // SSTORE
+            0x55 => {
+                let taint = inputs[0].union(&inputs[1]);
+                self.record(instruction, Sink::StorageWrite(word(0)), &taint);
+                self.storage.insert(word(0), inputs[1].clone());
+                Taint::default()
+            }
+
+            // MCOPY
+            0x5e => {
+                if let (Some(dest), Some(source), Some(size)) = (value(0), value(1), value(2)) {
+                    if dest.saturating_add(size) <= MAX_MEMORY {
+                        let taints = (source..source.saturating_add(size))
+                            .map(|i| self.memory.get(i).cloned().unwrap_or_default())
+                            .collect();
+                        self.write_memory(dest, taints);
+                    }
+                }
+                Taint::default()
+            }
+
+            // CREATE and CREATE2
+            0xf0 | 0xf5 => {
+                self.record(instruction, Sink::ValueTransfer, &inputs[0]);
+                Taint::default()
+            }
+
+            // CALL and CALLCODE
+            0xf1 | 0xf2 => {
+                self.record(instruction, Sink::CallTarget, &inputs[1]);
+                self.record(instruction, Sink::ValueTransfer, &inputs[2]);
+                self.clear_memory(value(5), value(6));
+                Taint::default()
+            }
+
+            // DELEGATECALL and STATICCALL
+            0xf4 | 0xfa => {
+                self.record(instruction, Sink::CallTarget, &inputs[1]);
+                self.clear_memory(value(4), value(5));
+                Taint::default()
+            }
+
+            // SELFDESTRUCT
+            0xff => {
+                self.record(instruction, Sink::SelfdestructRecipient, &inputs[0]);
+                Taint::default()
+            }
+
+            // everything else reads the execution environment, which isn't tainted
+            _ => Taint::default(),
+        };
+
+        for _ in 0..details.outputs {
+            self.stack.push_front(output.clone());
+        }
+    }
+
+    /// Records that the given sink was reached with the given taint, if it's tainted at all.
+    fn record(&self, instruction: &Instruction, sink: Sink, taint: &Taint) {
+        if !taint.is_empty() {
+            self.sinks.lock().expect("taint lock poisoned").insert(TaintedSink {
+                instruction: instruction.instruction,
+                sink,
+                taint: taint.clone(),
+            });
+        }
+    }
+
+    /// The combined taint of the given range of memory.
+    fn read_memory(&self, offset: Option<usize>, size: Option<usize>) -> Taint {
+        let (Some(offset), Some(size)) = (offset, size) else { return Taint::default() };
+        let end = offset.saturating_add(size).min(self.memory.len());
+        self.memory
+            .get(offset..end)
+            .unwrap_or_default()
+            .iter()
+            .fold(Taint::default(), |taint, byte| taint.union(byte))
+    }
+
+    /// Sets the taint of each byte of memory from the given offset.
+    fn write_memory(&mut self, offset: usize, taints: Vec<Taint>) {
+        let end = offset.saturating_add(taints.len());
+        if end > MAX_MEMORY {
+            return
+        }
+        if end > self.memory.len() {
+            self.memory.resize(end, Taint::default());
+        }
+        for (byte, taint) in self.memory[offset..end].iter_mut().zip(taints) {
+            *byte = taint;
+        }
+    }
+
+    /// Removes the taint of the given range of memory.
+    fn clear_memory(&mut self, offset: Option<usize>, size: Option<usize>) {
+        let (Some(offset), Some(size)) = (offset, size) else { return };
+        let end = offset.saturating_add(size).min(self.memory.len());
+        if offset < end {
+            self.memory[offset..end].iter_mut().for_each(|byte| *byte = Taint::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::core::{
+        taint::{Sink, Taint, TaintTracker},
+        vm::new_test_vm,
+    };
+
+    #[test]
+    fn test_taint_through_memory_and_hashing() {
+        // mstore(0, calldataload(4)), mstore(32, 1), sstore(keccak256(0, 64), calldataload(36))
+        let mut vm = new_test_vm("0x60043560005260016020526024356040600020550000", "0x");
+        let taint = TaintTracker::default();
+        vm.taint = Some(taint.clone());
+        vm.execute();
+
+        let sinks = taint.sinks();
+        assert_eq!(sinks.len(), 1);
+        assert!(matches!(sinks[0].sink, Sink::StorageWrite(_)));
+        assert_eq!(sinks[0].taint, Taint::calldata(4, 64));
+        assert_eq!(sinks[0].taint.arguments(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_taint_call_target_and_value() {
+        // call(gas, calldataload(4), callvalue, 0, 0, 0, 0), sstore(0, 1)
+        let mut vm = new_test_vm("0x6000600060006000346004355af150600160005500", "0x");
+        let taint = TaintTracker::default();
+        vm.taint = Some(taint.clone());
+        vm.execute();
+
+        // the value sent is untainted, and the untainted storage write isn't reported
+        let sinks = taint.sinks();
+        assert_eq!(sinks.len(), 1);
+        assert_eq!(sinks[0].sink, Sink::CallTarget);
+        assert_eq!(sinks[0].taint, Taint::calldata(4, 32));
+        assert_eq!(sinks[0].instruction, 14);
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use serde::Serialize;
+
+use crate::utils::strings::encode_hex;
+
+use super::vm::VM;
+
+/// A single step of execution, in the format of geth's `structLogs` tracer. The stack, memory, and
+/// gas are captured before the instruction is executed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: u128,
+    pub op: String,
+    pub gas: u128,
+    pub gas_cost: u128,
+    pub depth: usize,
+    pub stack: Vec<String>,
+    pub memory: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The result of a traced execution, in the format returned by geth's `debug_traceTransaction`
+/// and `debug_traceCall`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionTrace {
+    pub gas: u128,
+    pub failed: bool,
+    pub return_value: String,
+    pub struct_logs: Vec<StructLog>,
+}
+
+/// The [`Tracer`] records a [`StructLog`] for every instruction executed by a [`VM`], including
+/// the instructions of any child contexts it creates. Clones share the same logs.
+#[derive(Clone, Debug, Default)]
+pub struct Tracer {
+    logs: Arc<Mutex<Vec<StructLog>>>,
+}
+
+impl Tracer {
+    /// Records the step the given VM is about to execute, returning its index so the gas cost can
+    /// be filled in once it has executed.
+    pub(crate) fn before_step(&self, vm: &VM) -> usize {
+        let pc = vm.instruction - 1;
+        let opcode = vm.bytecode.get(pc as usize).copied().unwrap_or(0);
+
+        let log = StructLog {
+            pc,
+            op: vm.hardfork.opcode(opcode).name.to_string(),
+            gas: vm.gas_remaining,
+            gas_cost: 0,
+            depth: vm.depth + 1,
+            // the stack is listed from bottom to top
+            stack: vm.stack.stack.iter().rev().map(|frame| format!("{:#x}", frame.value)).collect(),
+            memory: vm.memory.memory.chunks(32).map(|word| encode_hex(word.to_vec())).collect(),
+            storage: None,
+            error: None,
+        };
+
+        let mut logs = self.logs.lock().expect("tracer lock poisoned");
+        logs.push(log);
+        logs.len() - 1
+    }
+
+    /// Fills in the gas cost of the step at the given index, along with the storage of the
+    /// contract if the step was an SLOAD or SSTORE, and the fault if the step faulted.
+    pub(crate) fn after_step(&self, index: usize, vm: &VM) {
+        let mut logs = self.logs.lock().expect("tracer lock poisoned");
+        let log = &mut logs[index];
+        log.gas_cost = log.gas.saturating_sub(vm.gas_remaining);
+
+        if log.op == "SLOAD" || log.op == "SSTORE" {
+            log.storage = Some(
+                vm.storage
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (encode_hex(slot.to_vec()), encode_hex(value.to_vec())))
+                    .collect(),
+            );
+        }
+
+        if let Some(fault) = vm.fault.as_ref().filter(|fault| fault.pc == log.pc) {
+            log.error = Some(fault.kind.to_string());
+        }
+    }
+
+    /// The steps recorded so far.
+    pub fn logs(&self) -> Vec<StructLog> {
+        self.logs.lock().expect("tracer lock poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::core::{trace::Tracer, vm::VM};
+
+    #[test]
+    fn test_struct_logs() {
+        // PUSH1 0x2a PUSH1 0x00 SSTORE STOP
+        let mut vm = VM::new(
+            String::from("0x602a60005500"),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            100000,
+        );
+        let tracer = Tracer::default();
+        vm.tracer = Some(tracer.clone());
+        vm.execute();
+
+        let logs = tracer.logs();
+        assert_eq!(
+            logs.iter().map(|log| log.op.as_str()).collect::<Vec<&str>>(),
+            vec!["PUSH1", "PUSH1", "SSTORE", "STOP"]
+        );
+        assert_eq!(logs.iter().map(|log| log.pc).collect::<Vec<u128>>(), vec![0, 2, 4, 5]);
+
+        // the stack is captured before each step, bottom first
+        assert_eq!(logs[2].stack, vec!["0x2a", "0x0"]);
+        assert_eq!(logs[0].gas, 79000);
+        assert_eq!(logs[0].gas_cost, 3);
+        assert_eq!(logs[1].gas, 78997);
+
+        let storage = logs[2].storage.as_ref().unwrap();
+        assert_eq!(storage.get(&"00".repeat(32)), Some(&format!("{}2a", "00".repeat(31))));
+        assert!(logs[3].storage.is_none());
+    }
+}
@@ -1,13 +1,15 @@
-use std::collections::VecDeque;
-
-use ethers::abi::{AbiEncode, ParamType};
+use ethers::{abi::ParamType, types::U256};
 
 use crate::{
     constants::TYPE_CAST_REGEX,
     utils::strings::{decode_hex, find_balanced_encapsulator},
 };
 
-use super::vm::Instruction;
+use super::{
+    grammar::{parse_signature, parse_type},
+    opcodes::{WrappedInput, WrappedOpcode},
+    vm::Instruction,
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Padding {
@@ -16,6 +18,32 @@ pub enum Padding {
     None,
 }
 
+/// An error encountered while strictly parsing a human-readable signature, with
+/// [`parse_human_readable_signature`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    #[error("signature '{0}' has unbalanced parentheses or brackets")]
+    Unbalanced(String),
+    #[error("signature '{0}' has no parameter list")]
+    MissingParameters(String),
+    #[error("'{0}' is not a valid function name")]
+    InvalidName(String),
+    #[error("parameter {0} is empty")]
+    EmptyParameter(usize),
+    #[error("invalid parameter '{0}': {1}")]
+    InvalidParameter(String, String),
+    #[error("invalid type '{0}': {1}")]
+    InvalidType(String, String),
+}
+
+/// The name of a parameter in a human-readable signature, along with the names of its components
+/// if it's a tuple, or an array of tuples. Names which aren't given are empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParameterNames {
+    pub name: String,
+    pub components: Vec<ParameterNames>,
+}
+
 /// Parse function parameters [`ParamType`]s from a function signature.
 ///
 /// ```
@@ -28,250 +56,200 @@ pub enum Padding {
 /// assert_eq!(function_parameters, vec![ParamType::Uint(256), ParamType::Uint(256)]);
 /// ```
 pub fn parse_function_parameters(function_signature: &str) -> Option<Vec<ParamType>> {
-    // remove the function name from the signature, only keep the parameters
-    let (start, end, valid) = find_balanced_encapsulator(function_signature, ('(', ')'));
-    if !valid {
-        return None
-    }
-
-    let function_inputs = function_signature[start + 1..end - 1].to_string();
+    parse_named_function_parameters(function_signature)
+        .map(|parameters| parameters.into_iter().map(|(param_type, _)| param_type).collect())
+}
 
-    // get inputs from the string
-    extract_types_from_string(&function_inputs)
+/// Parse function parameters [`ParamType`]s, along with their names, from a human-readable
+/// signature. Data locations, `indexed`, and `payable` are ignored, and unnamed parameters have
+/// an empty name. This is a lenient [`parse_signature`], kept for its callers.
+///
+/// ```
+/// use heimdall_common::ether::evm::core::types::parse_named_function_parameters;
+/// use ethers::abi::ParamType;
+///
+/// let function_signature = "transfer(address recipient, uint256[] calldata amounts)";
+/// let function_parameters = parse_named_function_parameters(function_signature).unwrap();
+///
+/// assert_eq!(
+///     function_parameters,
+///     vec![
+///         (ParamType::Address, String::from("recipient")),
+///         (ParamType::Array(Box::new(ParamType::Uint(256))), String::from("amounts"))
+///     ]
+/// );
+/// ```
+pub fn parse_named_function_parameters(
+    function_signature: &str,
+) -> Option<Vec<(ParamType, String)>> {
+    match parse_signature(function_signature, false) {
+        Ok(signature) if !signature.parameters.is_empty() => Some(
+            signature
+                .parameters
+                .into_iter()
+                .map(|parameter| (parameter.kind, parameter.name))
+                .collect(),
+        ),
+        _ => None,
+    }
 }
 
-/// Helper function for extracting types from a string. Used by [`parse_function_parameters`],
-/// typically after entering a nested tuple or similar.
-fn extract_types_from_string(string: &str) -> Option<Vec<ParamType>> {
-    let mut types = Vec::new();
+/// Strictly parse a human-readable signature into its name and its parameters, as
+/// [`parse_named_function_parameters`] does. Rather than defaulting types it can't parse, such as
+/// `uint7` or `bytes33`, or skipping empty parameters, a [`SignatureError`] is returned. This is
+/// a strict [`parse_signature`], kept for its callers.
+///
+/// ```
+/// use heimdall_common::ether::evm::core::types::{parse_human_readable_signature, SignatureError};
+///
+/// let (name, parameters) = parse_human_readable_signature("transfer(address to, uint256)").unwrap();
+/// assert_eq!(name, "transfer");
+/// assert_eq!(parameters.len(), 2);
+///
+/// assert_eq!(
+///     parse_human_readable_signature("transfer(address to, uint7)"),
+///     Err(SignatureError::InvalidType(
+///         String::from("uint7"),
+///         String::from("integer sizes must be a multiple of 8 between 8 and 256")
+///     ))
+/// );
+/// ```
+pub fn parse_human_readable_signature(
+    function_signature: &str,
+) -> Result<(String, Vec<(ParamType, String)>), SignatureError> {
+    let signature = parse_signature(function_signature, true)?;
+
+    Ok((
+        signature.name,
+        signature
+            .parameters
+            .into_iter()
+            .map(|parameter| (parameter.kind, parameter.name))
+            .collect(),
+    ))
+}
 
-    // if string is empty, return None
-    if string.is_empty() {
-        return None
-    }
+/// Parse the names of each parameter in a human-readable signature, including the names of the
+/// components of tuples, such as those of a struct. Unnamed parameters have empty names.
+///
+/// ```
+/// use heimdall_common::ether::evm::core::types::parse_parameter_names;
+///
+/// let names = parse_parameter_names("swap((address tokenIn, uint256 amountIn) params)");
+/// assert_eq!(names[0].name, "params");
+/// assert_eq!(names[0].components[1].name, "amountIn");
+/// ```
+pub fn parse_parameter_names(function_signature: &str) -> Vec<ParameterNames> {
+    parse_signature(function_signature, false)
+        .map(|signature| signature.parameters.iter().map(ParameterNames::from).collect())
+        .unwrap_or_default()
+}
 
-    // if the string contains a tuple we cant simply split on commas
-    if ['(', ')'].iter().any(|c| string.contains(*c)) {
-        // check if first type is a tuple
-        if is_first_type_tuple(string) {
-            // get balanced encapsulator
-            let (tuple_start, tuple_end, valid) = find_balanced_encapsulator(string, ('(', ')'));
-            if !valid {
-                return None
-            }
+/// Convert a human-readable signature into the canonical signature its selector is hashed from.
+///
+/// ```
+/// use heimdall_common::ether::evm::core::types::canonical_signature;
+///
+/// assert_eq!(
+///     canonical_signature("function transfer(address to, uint256 amount) external"),
+///     Some(String::from("transfer(address,uint256)"))
+/// );
+/// ```
+pub fn canonical_signature(function_signature: &str) -> Option<String> {
+    parse_signature(function_signature, false)
+        .ok()
+        .filter(|signature| !signature.name.is_empty())
+        .map(|signature| signature.canonical())
+}
 
-            // extract the tuple
-            let tuple_types = string[tuple_start + 1..tuple_end - 1].to_string();
-
-            // remove the tuple from the string
-            let mut string = string[tuple_end..].to_string();
-
-            // if string is not empty, split on commas and check if tuple is an array
-            let mut is_array = false;
-            let mut array_size: Option<usize> = None;
-            if !string.is_empty() {
-                let split = string.splitn(2, ',').collect::<Vec<&str>>()[0];
-
-                is_array = split.ends_with(']');
-
-                // get array size, or none if []
-                if is_array {
-                    let (start, end, valid) = find_balanced_encapsulator(split, ('[', ']'));
-                    if !valid {
-                        return None
-                    }
-
-                    let size = split[start + 1..end - 1].to_string();
-                    array_size = match size.parse::<usize>() {
-                        Ok(size) => Some(size),
-                        Err(_) => None,
-                    };
-                }
-            }
+/// Converts a string type to a ParamType. For example, "address" will be converted to
+/// [`ParamType::Address`]. Invalid types default to [`ParamType::Bytes`].
+pub fn to_type(string: &str) -> ParamType {
+    parse_type(string, false).map(|(kind, _)| kind).unwrap_or(ParamType::Bytes)
+}
 
-            if is_array {
-                // if the string doesnt contain a comma, this is the last type
-                if string.contains(',') {
-                    // remove the array from the string by splitting on the first comma and taking
-                    // the second half
-                    string = string.splitn(2, ',').collect::<Vec<&str>>()[1].to_string();
-                } else {
-                    // set string to empty string
-                    string = "".to_string();
-                }
-
-                if let Some(array_size) = array_size {
-                    // recursively call this function to extract the tuple types
-                    let inner_types = extract_types_from_string(&tuple_types);
-
-                    types.push(ParamType::FixedArray(
-                        Box::new(ParamType::Tuple(inner_types.unwrap())),
-                        array_size,
-                    ))
-                } else {
-                    // recursively call this function to extract the tuple types
-                    let inner_types = extract_types_from_string(&tuple_types);
-
-                    types.push(ParamType::Array(Box::new(ParamType::Tuple(inner_types.unwrap()))))
-                }
-            } else {
-                // recursively call this function to extract the tuple types
-                let inner_types = extract_types_from_string(&tuple_types);
-
-                types.push(ParamType::Tuple(inner_types.unwrap()));
-            }
+/// Convert a bitwise masking operation to a tuple containing: \
+/// 1. The size of the type being masked \
+/// 2. Potential types that the type being masked could be, sorted by likelihood descending.
+pub fn convert_bitmask(instruction: Instruction) -> (usize, Vec<String>) {
+    let mask = instruction.output_operations[0].clone();
 
-            // recursively call this function to extract the remaining types
-            if let Some(mut remaining_types) = extract_types_from_string(&string) {
-                types.append(&mut remaining_types);
-            }
-        } else {
-            // first type is not a tuple, so we can extract it
-            let string_parts = string.splitn(2, ',').collect::<Vec<&str>>();
-
-            // convert the string type to a ParamType
-            if string_parts[0].is_empty() {
-                // the first type is empty, so we can just recursively call this function to extract
-                // the remaining types
-                if let Some(mut remaining_types) = extract_types_from_string(string_parts[1]) {
-                    types.append(&mut remaining_types);
-                }
-            } else {
-                let param_type = to_type(string_parts[0]);
-                types.push(param_type);
-
-                // remove the first type from the string
-                let string = string[string_parts[0].len() + 1..].to_string();
-
-                // recursively call this function to extract the remaining types
-                if let Some(mut remaining_types) = extract_types_from_string(&string) {
-                    types.append(&mut remaining_types);
-                }
-            }
-        }
-    } else {
-        // split on commas
-        let split = string.split(',').collect::<Vec<&str>>();
-
-        // iterate over the split string and convert each type to a ParamType
-        for string_type in split {
-            if string_type.is_empty() {
-                continue
+    // determine which input contains the bitmask, which is the operand not loaded from calldata
+    let bitmask =
+        mask.inputs.iter().zip(instruction.inputs.iter()).find_map(|(input, value)| match input {
+            WrappedInput::Opcode(opcode)
+                if !(opcode.opcode.name == "CALLDATALOAD" ||
+                    opcode.opcode.name == "CALLDATACOPY") =>
+            {
+                Some(*value)
             }
+            _ => None,
+        });
 
-            let param_type = to_type(string_type);
-            types.push(param_type);
-        }
-    }
+    match bitmask {
+        Some(bitmask) => bitmask_to_type(bitmask, mask.opcode.name),
 
-    match types.len() {
-        0 => None,
-        _ => Some(types),
+        // use 32 as the default size, as it is the default word size in the EVM
+        None => byte_size_to_type(32),
     }
 }
 
-/// A helper function used by [`extract_types_from_string`] to check if the first type in a string
-/// is a tuple.
-fn is_first_type_tuple(string: &str) -> bool {
-    // split by first comma
-    let split = string.splitn(2, ',').collect::<Vec<&str>>();
+/// Given the constant operand of an AND or OR bitmask, return a tuple containing: \
+/// 1. The byte size of the masked type \
+/// 2. Potential types that the masked value could be, sorted by likelihood descending.
+///
+/// Solidity cleans unsigned values and addresses by masking their low-order bytes, and fixed
+/// bytes by masking their high-order bytes. An OR which fills the high-order bytes is how a
+/// negative value is sign extended, so it implies a signed integer.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::evm::core::types::bitmask_to_type;
+///
+/// let (byte_size, potential_types) = bitmask_to_type(U256::from(0xffff), "AND");
+/// assert_eq!(byte_size, 2);
+/// assert_eq!(potential_types, vec!["uint16".to_string(), "bytes2".to_string()]);
+/// ```
+pub fn bitmask_to_type(bitmask: U256, opcode: &str) -> (usize, Vec<String>) {
+    // the bits of the value are those which an OR leaves unset
+    let bitmask = if opcode == "OR" { !bitmask } else { bitmask };
+
+    let mut bytes = [0u8; 32];
+    bitmask.to_big_endian(&mut bytes);
+    let byte_size = bytes.iter().filter(|byte| **byte == 0xff).count();
+    if byte_size == 0 || byte_size == 32 {
+        return byte_size_to_type(32)
+    }
 
-    // if the first element starts with a (, it is a tuple
-    split[0].starts_with('(')
-}
+    let low_aligned = bitmask == (U256::one() << (byte_size * 8)) - 1;
+    let high_aligned = bitmask == !((U256::one() << ((32 - byte_size) * 8)) - 1);
 
-/// A helper function used by [`extract_types_from_string`] that converts a string type to a
-/// ParamType. For example, "address" will be converted to [`ParamType::Address`].
-pub fn to_type(string: &str) -> ParamType {
-    let is_array = string.ends_with(']');
-    let mut array_size: VecDeque<Option<usize>> = VecDeque::new();
-    let mut string = string.to_string();
-
-    // while string contains a [..]
-    while string.ends_with(']') {
-        let (start, end, valid) = find_balanced_encapsulator(&string, ('[', ']'));
-        if !valid {
-            return ParamType::Bytes // default to bytes if invalid
+    let (bits, mut potential_types) = (byte_size * 8, Vec::new());
+    match (low_aligned, high_aligned) {
+        (true, _) if opcode == "OR" => {
+            potential_types.push(format!("int{bits}"));
+            potential_types.push(format!("uint{bits}"));
         }
-
-        let size = string[start + 1..end - 1].to_string();
-
-        array_size.push_back(match size.parse::<usize>() {
-            Ok(size) => Some(size),
-            Err(_) => None,
-        });
-
-        string = string.replacen(&format!("[{}]", &size), "", 1);
-    }
-
-    let arg_type = match string.as_str() {
-        "address" => ParamType::Address,
-        "bool" => ParamType::Bool,
-        "string" => ParamType::String,
-        "bytes" => ParamType::Bytes,
-        _ => {
-            if let Some(stripped) = string.strip_prefix("uint") {
-                let size = stripped.parse::<usize>().unwrap_or(256);
-                ParamType::Uint(size)
-            } else if let Some(stripped) = string.strip_prefix("int") {
-                let size = stripped.parse::<usize>().unwrap_or(256);
-                ParamType::Int(size)
-            } else if let Some(stripped) = string.strip_prefix("bytes") {
-                let size = stripped.parse::<usize>().unwrap();
-                ParamType::FixedBytes(size)
-            } else {
-                // default to bytes if invalid
-                ParamType::Bytes
+        (true, _) => {
+            if byte_size == 20 {
+                potential_types.push("address".to_string());
             }
-        }
-    };
-
-    if is_array {
-        let mut arg_type = arg_type;
-
-        // while array_size is not empty
-        while !array_size.is_empty() {
-            // pop off first element of array_size
-            if let Some(size) = array_size.pop_front().unwrap() {
-                arg_type = ParamType::FixedArray(Box::new(arg_type), size);
-            } else {
-                arg_type = ParamType::Array(Box::new(arg_type));
+            potential_types.push(format!("uint{bits}"));
+            if byte_size == 1 {
+                potential_types.push("bool".to_string());
             }
+            potential_types.push(format!("bytes{byte_size}"));
+        }
+        (_, true) => {
+            potential_types.push(format!("bytes{byte_size}"));
+            potential_types.push(format!("uint{bits}"));
         }
 
-        return arg_type;
-    }
-
-    arg_type
-}
-
-/// Convert a bitwise masking operation to a tuple containing: \
-/// 1. The size of the type being masked \
-/// 2. Potential types that the type being masked could be.
-pub fn convert_bitmask(instruction: Instruction) -> (usize, Vec<String>) {
-    let mask = instruction.output_operations[0].clone();
-
-    // use 32 as the default size, as it is the default word size in the EVM
-    let mut type_byte_size = 32;
-
-    // determine which input contains the bitmask
-    for (i, input) in mask.inputs.iter().enumerate() {
-        match input {
-            crate::ether::evm::core::opcodes::WrappedInput::Raw(_) => continue,
-            crate::ether::evm::core::opcodes::WrappedInput::Opcode(opcode) => {
-                if !(opcode.opcode.name == "CALLDATALOAD" || opcode.opcode.name == "CALLDATACOPY") {
-                    if mask.opcode.name == "AND" {
-                        type_byte_size = instruction.inputs[i].encode_hex().matches("ff").count();
-                    } else if mask.opcode.name == "OR" {
-                        type_byte_size = instruction.inputs[i].encode_hex().matches("00").count();
-                    }
-                }
-            }
-        };
+        // masks which aren't contiguous don't tell us much besides the size
+        _ => return byte_size_to_type(byte_size),
     }
 
-    // determine the solidity type based on the resulting size of the masked data
-    byte_size_to_type(type_byte_size)
+    (byte_size, potential_types)
 }
 
 /// Given a byte size, return a tuple containing: \
@@ -303,6 +281,57 @@ pub fn byte_size_to_type(byte_size: usize) -> (usize, Vec<String>) {
     (byte_size, potential_types)
 }
 
+/// Given an instruction which treats its operands as signed integers, returns each signed
+/// operand's operation along with the byte size of the signed type it implies. SIGNEXTEND implies
+/// the size it extends from, while SDIV, SMOD, SLT, SGT, and SAR imply a full word unless the
+/// operand was sign extended first.
+///
+/// ```
+/// use ethers::types::U256;
+/// use heimdall_common::ether::evm::core::{
+///     opcodes::{WrappedInput, WrappedOpcode},
+///     types::signed_operands,
+///     vm::Instruction,
+/// };
+///
+/// // signextend(1, calldataload(4))
+/// let value = WrappedOpcode::new(0x35, vec![WrappedInput::Raw(U256::from(4))]);
+/// let instruction = Instruction {
+///     instruction: 1,
+///     opcode: 0x0b,
+///     opcode_details: None,
+///     inputs: vec![U256::from(1), U256::zero()],
+///     outputs: Vec::new(),
+///     input_operations: vec![WrappedOpcode::new(0x60, vec![]), value.clone()],
+///     output_operations: Vec::new(),
+/// };
+/// assert_eq!(signed_operands(&instruction), vec![(value, 2)]);
+/// ```
+pub fn signed_operands(instruction: &Instruction) -> Vec<(WrappedOpcode, usize)> {
+    let operands = &instruction.input_operations;
+    match instruction.opcode {
+        // SIGNEXTEND extends the sign of the lowest `b + 1` bytes
+        0x0b if operands.len() == 2 => {
+            let byte_size = match instruction.inputs.first() {
+                Some(b) if *b < U256::from(31) => b.as_usize() + 1,
+                _ => 32,
+            };
+            vec![(operands[1].clone(), byte_size)]
+        }
+
+        // SDIV, SMOD, SLT, SGT
+        0x05 | 0x07 | 0x12 | 0x13 => operands.iter().map(|operand| (operand.clone(), 32)).collect(),
+
+        // SAR shifts its second operand
+        0x1d if operands.len() == 2 => vec![(operands[1].clone(), 32)],
+        _ => Vec::new(),
+    }
+    .into_iter()
+    // operands which were sign extended already have a narrower size, implied by the SIGNEXTEND
+    .filter(|(operand, _)| instruction.opcode == 0x0b || operand.opcode.code != 0x0b)
+    .collect()
+}
+
 /// Given a string (typically a line of decompiled source code), extract a type cast if one exists.
 pub fn find_cast(line: &str) -> (usize, usize, Option<String>) {
     // find the start of the cast
@@ -424,9 +453,107 @@ pub fn get_potential_types_for_word(word: &str) -> (usize, Vec<String>) {
 
 #[cfg(test)]
 mod tests {
-    use ethers::abi::ParamType;
+    use ethers::{
+        abi::{decode, encode, ParamType, Token},
+        types::U256,
+    };
+
+    use crate::ether::evm::core::{
+        types::{
+            bitmask_to_type, byte_size_to_type, canonical_signature, convert_bitmask, get_padding,
+            parse_function_parameters, parse_human_readable_signature,
+            parse_named_function_parameters, parse_parameter_names, signed_operands, Padding,
+            ParameterNames, SignatureError,
+        },
+        vm::VM,
+    };
+
+    #[test]
+    fn test_signed_operands() {
+        // slt(signextend(0, calldataload(4)), 0), sdiv(calldataload(36), 2)
+        let mut vm = VM::new(
+            String::from("0x600060043560000b12506002602435055000"),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        );
+
+        let mut signed = Vec::new();
+        while vm.exitcode == 255 {
+            let instruction = vm.step().last_instruction;
+            signed.extend(
+                signed_operands(&instruction)
+                    .into_iter()
+                    .map(|(operand, byte_size)| (operand.opcode.name, byte_size)),
+            );
+        }
+
+        // the sign extended argument is an int8, so SLT doesn't widen it
+        assert_eq!(
+            signed,
+            vec![("CALLDATALOAD", 1), ("PUSH1", 32), ("CALLDATALOAD", 32), ("PUSH1", 32)]
+        );
+    }
+
+    #[test]
+    fn test_convert_bitmask() {
+        // and(calldataload(4), 0xff..ff) with a 20 byte mask, then the mask on the other side
+        let mut vm = VM::new(
+            format!(
+                "0x73{}60043516506004357f{}{}165000",
+                "ff".repeat(20),
+                "ff".repeat(4),
+                "00".repeat(28)
+            ),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        );
+
+        let mut masks = Vec::new();
+        while vm.exitcode == 255 {
+            let instruction = vm.step().last_instruction;
+            if instruction.opcode == 0x16 {
+                masks.push(convert_bitmask(instruction));
+            }
+        }
+
+        assert_eq!(
+            masks,
+            vec![
+                (20, vec!["address".to_string(), "uint160".to_string(), "bytes20".to_string()]),
+                (4, vec!["bytes4".to_string(), "uint32".to_string()]),
+            ]
+        );
+    }
 
-    use crate::ether::evm::core::types::{get_padding, parse_function_parameters, Padding};
+    #[test]
+    fn test_bitmask_to_type() {
+        assert_eq!(
+            bitmask_to_type(U256::from(0xff), "AND"),
+            (1, vec!["uint8".to_string(), "bool".to_string(), "bytes1".to_string()])
+        );
+        assert_eq!(
+            bitmask_to_type((U256::one() << 128) - 1, "AND"),
+            (16, vec!["uint128".to_string(), "bytes16".to_string()])
+        );
+
+        // an OR filling every byte but the lowest sign extends an int8
+        assert_eq!(
+            bitmask_to_type(!U256::from(0xff), "OR"),
+            (1, vec!["int8".to_string(), "uint8".to_string()])
+        );
+
+        // masks which aren't aligned fall back to the size of the mask
+        assert_eq!(bitmask_to_type(U256::from(0xff00), "AND"), byte_size_to_type(1));
+        assert_eq!(bitmask_to_type(U256::MAX, "AND"), byte_size_to_type(32));
+    }
 
     #[test]
     fn test_simple_signature() {
@@ -578,6 +705,288 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fixedarray_of_tuples_signature() {
+        let solidity_type = "test((uint256,address)[5],uint256)";
+        let param_type = parse_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![
+                ParamType::FixedArray(
+                    Box::new(ParamType::Tuple(vec![ParamType::Uint(256), ParamType::Address])),
+                    5
+                ),
+                ParamType::Uint(256)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fixedarray_of_bytes_signature() {
+        let solidity_type = "test(bytes32[4],bytes1[2][])";
+        let param_type = parse_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![
+                ParamType::FixedArray(Box::new(ParamType::FixedBytes(32)), 4),
+                ParamType::Array(Box::new(ParamType::FixedArray(
+                    Box::new(ParamType::FixedBytes(1)),
+                    2
+                )))
+            ])
+        );
+    }
+
+    #[test]
+    fn test_nested_tuple_fixedarray_signature() {
+        let solidity_type = "test(((bytes32,uint8)[2][],bool)[3],uint256[2][3])";
+        let param_type = parse_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![
+                ParamType::FixedArray(
+                    Box::new(ParamType::Tuple(vec![
+                        ParamType::Array(Box::new(ParamType::FixedArray(
+                            Box::new(ParamType::Tuple(vec![
+                                ParamType::FixedBytes(32),
+                                ParamType::Uint(8)
+                            ])),
+                            2
+                        ))),
+                        ParamType::Bool
+                    ])),
+                    3
+                ),
+                ParamType::FixedArray(
+                    Box::new(ParamType::FixedArray(Box::new(ParamType::Uint(256)), 2)),
+                    3
+                )
+            ])
+        );
+    }
+
+    #[test]
+    fn test_multidimensional_array_signature() {
+        let solidity_type = "test(uint256[][],address[3][],bytes32[][2])";
+        let param_type = parse_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![
+                ParamType::Array(Box::new(ParamType::Array(Box::new(ParamType::Uint(256))))),
+                ParamType::Array(Box::new(ParamType::FixedArray(Box::new(ParamType::Address), 3))),
+                ParamType::FixedArray(
+                    Box::new(ParamType::Array(Box::new(ParamType::FixedBytes(32)))),
+                    2
+                )
+            ])
+        );
+    }
+
+    #[test]
+    fn test_multidimensional_array_decoding() {
+        // bytes32[][2] is two dynamic arrays, which decode in the order they were encoded
+        let tokens = vec![Token::FixedArray(vec![
+            Token::Array(vec![Token::FixedBytes(vec![0x11; 32])]),
+            Token::Array(vec![
+                Token::FixedBytes(vec![0x22; 32]),
+                Token::FixedBytes(vec![0x33; 32]),
+            ]),
+        ])];
+        let param_types = parse_function_parameters("test(bytes32[][2])").unwrap();
+        assert_eq!(decode(&param_types, &encode(&tokens)).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_named_signature() {
+        let solidity_type = "function transfer(address payable recipient, uint256 amount) external";
+        let param_type = parse_named_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![
+                (ParamType::Address, String::from("recipient")),
+                (ParamType::Uint(256), String::from("amount"))
+            ])
+        );
+        assert_eq!(
+            canonical_signature(solidity_type),
+            Some(String::from("transfer(address,uint256)"))
+        );
+    }
+
+    #[test]
+    fn test_named_signature_qualifiers() {
+        let solidity_type = "event Fill(address indexed maker, (uint256 id, bytes data)[] orders, string memory, bytes32)";
+        let param_type = parse_named_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![
+                (ParamType::Address, String::from("maker")),
+                (
+                    ParamType::Array(Box::new(ParamType::Tuple(vec![
+                        ParamType::Uint(256),
+                        ParamType::Bytes
+                    ]))),
+                    String::from("orders")
+                ),
+                (ParamType::String, String::new()),
+                (ParamType::FixedBytes(32), String::new())
+            ])
+        );
+        assert_eq!(
+            canonical_signature(solidity_type),
+            Some(String::from("Fill(address,(uint256,bytes)[],string,bytes32)"))
+        );
+    }
+
+    #[test]
+    fn test_tuple_keyword_signature() {
+        let solidity_type = "test(tuple(uint256,address) calldata order)";
+        let param_type = parse_function_parameters(solidity_type);
+        assert_eq!(
+            param_type,
+            Some(vec![ParamType::Tuple(vec![ParamType::Uint(256), ParamType::Address])])
+        );
+    }
+
+    #[test]
+    fn test_strict_signature() {
+        let (name, parameters) = parse_human_readable_signature(
+            "function swap((address token, uint96 amount)[2] calldata legs, bytes32[] proofs)",
+        )
+        .unwrap();
+        assert_eq!(name, "swap");
+        assert_eq!(
+            parameters,
+            vec![
+                (
+                    ParamType::FixedArray(
+                        Box::new(ParamType::Tuple(vec![ParamType::Address, ParamType::Uint(96)])),
+                        2
+                    ),
+                    String::from("legs")
+                ),
+                (ParamType::Array(Box::new(ParamType::FixedBytes(32))), String::from("proofs"))
+            ]
+        );
+        assert_eq!(parse_human_readable_signature("test()"), Ok((String::from("test"), vec![])));
+    }
+
+    #[test]
+    fn test_strict_signature_invalid_types() {
+        for (signature, invalid_type) in [
+            ("test(uint7)", "uint7"),
+            ("test(int0)", "int0"),
+            ("test(uint264)", "uint264"),
+            ("test(bytes33)", "bytes33"),
+            ("test(bytes0)", "bytes0"),
+            ("test(address[0])", "address[0]"),
+            ("test((uint256,bytes33)[])", "bytes33"),
+            ("test(uint256,adress)", "adress"),
+            ("test(fixed128x81)", "fixed128x81"),
+            ("test(ufixed7x18)", "ufixed7x18"),
+            ("test(fixed128)", "fixed128"),
+        ] {
+            match parse_human_readable_signature(signature) {
+                Err(SignatureError::InvalidType(found, _)) => assert_eq!(found, invalid_type),
+                result => panic!("expected '{signature}' to be invalid, found {result:?}"),
+            }
+        }
+
+        // these types are coerced, rather than rejected, when parsing leniently
+        assert_eq!(
+            parse_function_parameters("test(uint7,bytes33,adress)"),
+            Some(vec![ParamType::Uint(7), ParamType::FixedBytes(33), ParamType::Bytes])
+        );
+    }
+
+    #[test]
+    fn test_function_and_fixed_signature() {
+        assert_eq!(
+            parse_function_parameters("test(function,fixed,ufixed,fixed64x10,ufixed256x80[])"),
+            Some(vec![
+                ParamType::FixedBytes(24),
+                ParamType::Int(128),
+                ParamType::Uint(128),
+                ParamType::Int(64),
+                ParamType::Array(Box::new(ParamType::Uint(256))),
+            ])
+        );
+        assert_eq!(
+            parse_human_readable_signature("test(function callback, ufixed128x18 rate)"),
+            Ok((
+                String::from("test"),
+                vec![
+                    (ParamType::FixedBytes(24), String::from("callback")),
+                    (ParamType::Uint(128), String::from("rate")),
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parameter_names() {
+        let names = parse_parameter_names(
+            "function fill(tuple(address maker, (uint96 amount, bytes32) terms)[] calldata orders, uint256)",
+        );
+
+        assert_eq!(
+            names,
+            vec![
+                ParameterNames {
+                    name: String::from("orders"),
+                    components: vec![
+                        ParameterNames { name: String::from("maker"), components: Vec::new() },
+                        ParameterNames {
+                            name: String::from("terms"),
+                            components: vec![
+                                ParameterNames {
+                                    name: String::from("amount"),
+                                    components: Vec::new()
+                                },
+                                ParameterNames::default(),
+                            ],
+                        },
+                    ],
+                },
+                ParameterNames::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict_signature_invalid_syntax() {
+        assert_eq!(
+            parse_human_readable_signature("test((uint256,address)"),
+            Err(SignatureError::Unbalanced(String::from("test((uint256,address)")))
+        );
+        assert_eq!(
+            parse_human_readable_signature("test(uint256[)]"),
+            Err(SignatureError::Unbalanced(String::from("test(uint256[)]")))
+        );
+        assert_eq!(
+            parse_human_readable_signature("test"),
+            Err(SignatureError::MissingParameters(String::from("test")))
+        );
+        assert_eq!(
+            parse_human_readable_signature("1test(uint256)"),
+            Err(SignatureError::InvalidName(String::from("1test")))
+        );
+        assert_eq!(
+            parse_human_readable_signature("test(uint256,,address)"),
+            Err(SignatureError::EmptyParameter(1))
+        );
+        assert!(matches!(
+            parse_human_readable_signature("test(uint256 memory amount extra)"),
+            Err(SignatureError::InvalidParameter(..))
+        ));
+
+        // empty parameters are skipped when parsing leniently
+        assert_eq!(
+            parse_function_parameters("test(uint256,,address)"),
+            Some(vec![ParamType::Uint(256), ParamType::Address])
+        );
+    }
+
     #[test]
     fn test_seaport_fulfill_advanced_order() {
         let solidity_type = "fulfillAdvancedOrder(((address,address,(uint8,address,uint256,uint256,uint256)[],(uint8,address,uint256,uint256,uint256,address)[],uint8,uint256,uint256,bytes32,uint256,bytes32,uint256),uint120,uint120,bytes,bytes),(uint256,uint8,uint256,uint256,bytes32[])[],bytes32,address)";
@@ -809,6 +1218,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_to_type_fixed_array_of_tuples() {
+        let input = "(uint256,address)[5]";
+        assert_eq!(
+            super::to_type(input),
+            ParamType::FixedArray(
+                Box::new(ParamType::Tuple(vec![ParamType::Uint(256), ParamType::Address])),
+                5
+            )
+        );
+    }
+
     #[test]
     fn test_to_type_nested_fixed_array_ordering() {
         let input = "uint8[2][3][2]";
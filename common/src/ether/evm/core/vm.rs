@@ -1,18 +1,47 @@
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ops::{Div, Rem, Shl, Shr},
     str::FromStr,
     time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
-use ethers::{abi::AbiEncode, prelude::U256, types::I256, utils::keccak256};
+use ethers::{
+    abi::AbiEncode,
+    prelude::U256,
+    types::{Address, BigEndianHash, H256, I256},
+    utils::{get_contract_address, get_create2_address, keccak256},
+};
 
 use crate::{
-    ether::evm::core::opcodes::{Opcode, WrappedInput, WrappedOpcode},
-    utils::strings::{decode_hex, sign_uint},
+    ether::evm::{
+        core::{
+            fault::{memory_operands, Fault, FaultKind, MemorySize},
+            fork::Fork,
+            hardfork::Hardfork,
+            opcodes::{Opcode, WrappedInput, WrappedOpcode},
+            precompiles::{execute_precompile, is_precompile},
+            preimages::Preimages,
+            taint::TaintTracker,
+            trace::Tracer,
+        },
+        ext::exec::cache::TraceCache,
+    },
+    utils::{
+        determinism::{deterministic, DETERMINISTIC_TIMESTAMP},
+        strings::{decode_hex, encode_hex, sign_uint},
+    },
+};
+
+use super::{
+    log::Log,
+    memory::{Memory, MemorySource, MEMORY_LIMIT},
+    stack::Stack,
+    storage::Storage,
 };
 
-use super::{log::Log, memory::Memory, stack::Stack, storage::Storage};
+// child contexts are executed recursively, so the call depth is limited well below the EVM's
+// limit of 1024 to avoid overflowing the stack
+const MAX_CALL_DEPTH: usize = 16;
 
 /// The [`VM`] struct represents an EVM instance. \
 /// It contains the EVM's [`Stack`], [`Memory`], [`Storage`], and other state variables needed to
@@ -22,6 +51,7 @@ pub struct VM {
     pub stack: Stack,
     pub memory: Memory,
     pub storage: Storage,
+    pub transient_storage: Storage,
     pub instruction: u128,
     pub bytecode: Vec<u8>,
     pub calldata: Vec<u8>,
@@ -31,11 +61,23 @@ pub struct VM {
     pub value: u128,
     pub gas_remaining: u128,
     pub gas_used: u128,
+    pub gas_refund: i128,
     pub events: Vec<Log>,
     pub returndata: Vec<u8>,
     pub exitcode: u128,
     pub timestamp: Instant,
     pub address_access_set: HashSet<U256>,
+    pub call_returndata: Option<Vec<u8>>,
+    pub contracts: HashMap<U256, VM>,
+    pub nonce: u64,
+    pub depth: usize,
+    pub fork: Option<Fork>,
+    pub trace_cache: TraceCache,
+    pub tracer: Option<Tracer>,
+    pub taint: Option<TaintTracker>,
+    pub hardfork: Hardfork,
+    pub fault: Option<Fault>,
+    pub preimages: Preimages,
 }
 
 /// [`ExecutionResult`] is the result of a single contract execution.
@@ -48,6 +90,7 @@ pub struct ExecutionResult {
     pub events: Vec<Log>,
     pub runtime: f64,
     pub instruction: u128,
+    pub fault: Option<Fault>,
 }
 
 /// [`State`] is the state of the EVM after executing a single instruction. It is returned by the
@@ -61,6 +104,7 @@ pub struct State {
     pub memory: Memory,
     pub storage: Storage,
     pub events: Vec<Log>,
+    pub fault: Option<Fault>,
 }
 
 /// [`Instruction`] is a single EVM instruction. It is returned by the [`VM::step`] function, and
@@ -108,6 +152,7 @@ impl VM {
             stack: Stack::new(),
             memory: Memory::new(),
             storage: Storage::new(),
+            transient_storage: Storage::new(),
             instruction: 1,
             bytecode: decode_hex(&bytecode.replacen("0x", "", 1)).unwrap(),
             calldata: decode_hex(&calldata.replacen("0x", "", 1)).unwrap(),
@@ -117,11 +162,23 @@ impl VM {
             value,
             gas_remaining: gas_limit.max(21000) - 21000,
             gas_used: 21000,
+            gas_refund: 0,
             events: Vec::new(),
             returndata: Vec::new(),
             exitcode: 255,
             timestamp: Instant::now(),
             address_access_set: HashSet::new(),
+            call_returndata: None,
+            contracts: HashMap::new(),
+            nonce: 1,
+            depth: 0,
+            fork: None,
+            trace_cache: TraceCache::default(),
+            tracer: None,
+            taint: None,
+            hardfork: Hardfork::default(),
+            fault: None,
+            preimages: Preimages::default(),
         }
     }
 
@@ -149,6 +206,29 @@ impl VM {
         self.returndata = returndata;
     }
 
+    /// Halts execution exceptionally, recording the fault along with the instruction and call
+    /// context it happened in.
+    fn fault(&mut self, kind: FaultKind, instruction: u128, opcode: u8) {
+        let exitcode = match kind {
+            FaultKind::InvalidJump(_) => 790,
+            _ => 2,
+        };
+        self.fault = Some(Fault {
+            kind,
+            pc: instruction - 1,
+            opcode,
+            address: format!("0x{}", encode_hex(self.address.clone())),
+            depth: self.depth,
+        });
+        self.exit(exitcode, Vec::new());
+    }
+
+    /// Whether the given destination is a JUMPDEST within the code.
+    fn is_jumpdest(&self, destination: U256) -> bool {
+        destination < U256::from(self.bytecode.len()) &&
+            self.bytecode[destination.as_usize()] == 0x5b
+    }
+
     /// Consume gas units, halting execution if out of gas
     ///
     /// ```
@@ -227,12 +307,44 @@ impl VM {
         self.instruction += 1;
 
         // add the opcode to the trace
-        let opcode_details = Opcode::new(opcode);
+        let opcode_details = self.hardfork.opcode(opcode);
         let input_frames = self.stack.peek_n(opcode_details.inputs as usize);
         let input_operations =
             input_frames.iter().map(|x| x.operation.clone()).collect::<Vec<WrappedOpcode>>();
         let inputs = input_frames.iter().map(|x| x.value).collect::<Vec<U256>>();
 
+        // the stack must hold every input, and memory must only be accessed within the range
+        // that is modeled
+        let fault = if self.stack.size() < opcode_details.inputs as usize {
+            Some(FaultKind::StackUnderflow {
+                required: opcode_details.inputs as usize,
+                available: self.stack.size(),
+            })
+        } else {
+            memory_operands(opcode).iter().find_map(|(offset, size)| {
+                let offset = inputs.get(*offset).copied().unwrap_or_default();
+                let size = match size {
+                    MemorySize::Input(index) => inputs.get(*index).copied().unwrap_or_default(),
+                    MemorySize::Fixed(size) => U256::from(*size),
+                };
+                let limit = U256::from(MEMORY_LIMIT);
+                (!size.is_zero() && (offset > limit || size > limit))
+                    .then_some(FaultKind::MemoryOutOfBounds { offset, size })
+            })
+        };
+        if let Some(kind) = fault {
+            self.fault(kind, last_instruction, opcode);
+            return Instruction {
+                instruction: last_instruction,
+                opcode,
+                opcode_details: Some(opcode_details),
+                inputs,
+                outputs: Vec::new(),
+                input_operations,
+                output_operations: Vec::new(),
+            }
+        }
+
         // Consume the minimum gas for the opcode
         let gas_cost = opcode_details.mingas;
         self.consume_gas(gas_cost.into());
@@ -246,6 +358,11 @@ impl VM {
 
         // execute the operation
         match opcode {
+            // opcodes which haven't been introduced in this hardfork are invalid
+            _ if opcode_details.name == "unknown" => {
+                self.exit(1, Vec::new());
+            }
+
             // STOP
             0x00 => {
                 self.exit(10, Vec::new());
@@ -686,26 +803,12 @@ impl VM {
                 let a = self.stack.pop();
                 let b = self.stack.pop();
 
-                // convert a to usize
-                let usize_a: usize = match a.value.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
+                // shifting by 255 or more leaves only the sign
+                let usize_a: usize = a.value.try_into().unwrap_or(usize::MAX).min(255);
 
                 let mut result = I256::zero();
                 if !b.value.is_zero() {
-                    result = sign_uint(b.value).shr(usize_a);
+                    result = sign_uint(b.value).asr(usize_a);
                 }
 
                 // if both inputs are PUSH instructions, simplify the operation
@@ -758,7 +861,8 @@ impl VM {
                 };
 
                 let data = self.memory.read(offset, size);
-                let result = keccak256(data);
+                let result = keccak256(&data);
+                self.preimages.insert(U256::from(result), &data);
 
                 // consume dynamic gas
                 let minimum_word_size = ((size + 31) / 32) as u128;
@@ -783,15 +887,10 @@ impl VM {
                 let address = self.stack.pop().value;
 
                 // consume dynamic gas
-                if !self.address_access_set.contains(&address) {
-                    self.consume_gas(2600);
-                    self.address_access_set.insert(address);
-                } else {
-                    self.consume_gas(100);
-                }
+                self.access_account(address);
 
-                // balance is set to 1 wei because we won't run into div by 0 errors
-                self.stack.push(U256::from(1), operation);
+                let balance = self.balance(address);
+                self.stack.push(balance, operation);
             }
 
             // ORIGIN
@@ -823,24 +922,10 @@ impl VM {
             0x35 => {
                 let i = self.stack.pop().value;
 
-                // Safely convert U256 to usize
-                let i: usize = match i.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
+                // reading past the end of calldata yields zeros
+                let i: usize = i.try_into().unwrap_or(usize::MAX);
 
-                let result = if i + 32 > self.calldata.len() {
+                let result = if i.saturating_add(32) > self.calldata.len() {
                     let mut value = [0u8; 32];
 
                     if i <= self.calldata.len() {
@@ -884,21 +969,8 @@ impl VM {
                         }
                     }
                 };
-                let offset: usize = match offset.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
+                // reading past the end of the source yields zeros
+                let offset: usize = offset.try_into().unwrap_or(usize::MAX);
                 let size: usize = match size.try_into() {
                     Ok(x) => x,
                     Err(_) => {
@@ -915,7 +987,7 @@ impl VM {
                     }
                 };
 
-                let value_offset_safe = (offset + size).min(self.calldata.len());
+                let value_offset_safe = offset.saturating_add(size).min(self.calldata.len());
                 let mut value =
                     self.calldata.get(offset..value_offset_safe).unwrap_or(&[]).to_owned();
 
@@ -930,6 +1002,7 @@ impl VM {
                 self.consume_gas(gas_cost);
 
                 self.memory.store(dest_offset, size, &value);
+                self.memory.set_source(dest_offset, size, MemorySource::Calldata(offset));
             }
 
             // CODESIZE
@@ -961,21 +1034,8 @@ impl VM {
                         }
                     }
                 };
-                let offset: usize = match offset.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
+                // reading past the end of the source yields zeros
+                let offset: usize = offset.try_into().unwrap_or(usize::MAX);
                 let size: usize = match size.try_into() {
                     Ok(x) => x,
                     Err(_) => {
@@ -992,7 +1052,7 @@ impl VM {
                     }
                 };
 
-                let value_offset_safe = (offset + size).min(self.bytecode.len());
+                let value_offset_safe = offset.saturating_add(size).min(self.bytecode.len());
                 let mut value =
                     self.bytecode.get(offset..value_offset_safe).unwrap_or(&[]).to_owned();
 
@@ -1007,6 +1067,7 @@ impl VM {
                 self.consume_gas(gas_cost);
 
                 self.memory.store(dest_offset, size, &value);
+                self.memory.set_source(dest_offset, size, MemorySource::Code(offset));
             }
 
             // GASPRICE
@@ -1019,21 +1080,21 @@ impl VM {
                 let address = self.stack.pop().value;
 
                 // consume dynamic gas
-                if !self.address_access_set.contains(&address) {
-                    self.consume_gas(2600);
-                    self.address_access_set.insert(address);
-                } else {
-                    self.consume_gas(100);
-                }
+                self.access_account(address);
 
-                self.stack.push(U256::from(1), operation);
+                // the size of contracts created during execution or on the fork is known
+                let size = match self.code(address) {
+                    Some(code) => U256::from(code.len()),
+                    None => U256::from(1),
+                };
+                self.stack.push(size, operation);
             }
 
             // EXTCODECOPY
             0x3C => {
                 let address = self.stack.pop().value;
                 let dest_offset = self.stack.pop().value;
-                self.stack.pop();
+                let offset = self.stack.pop().value;
                 let size = self.stack.pop().value;
 
                 // Safely convert U256 to usize
@@ -1068,33 +1129,40 @@ impl VM {
                     }
                 };
 
-                let mut value = Vec::with_capacity(size);
-                value.resize(size, 0xff);
+                // the code of contracts created during execution or on the fork is known
+                let value = match self.code(address) {
+                    Some(code) => {
+                        let offset: usize = offset.try_into().unwrap_or(usize::MAX);
+                        let mut value = code
+                            .get(offset..)
+                            .map(|code| code[..code.len().min(size)].to_vec())
+                            .unwrap_or_default();
+                        value.resize(size, 0);
+                        value
+                    }
+                    None => vec![0xff; size],
+                };
 
                 // consume dynamic gas
                 let minimum_word_size = ((size + 31) / 32) as u128;
                 let gas_cost =
                     3 * minimum_word_size + self.memory.expansion_cost(dest_offset, size);
                 self.consume_gas(gas_cost);
-                if !self.address_access_set.contains(&address) {
-                    self.consume_gas(2600);
-                    self.address_access_set.insert(address);
-                } else {
-                    self.consume_gas(100);
-                }
+                self.access_account(address);
 
                 self.memory.store(dest_offset, size, &value);
             }
 
             // RETURNDATASIZE
             0x3D => {
-                self.stack.push(U256::from(1u8), operation);
+                let size = self.call_returndata.as_ref().map(|data| data.len()).unwrap_or(1);
+                self.stack.push(U256::from(size), operation);
             }
 
             // RETURNDATACOPY
             0x3E => {
                 let dest_offset = self.stack.pop().value;
-                self.stack.pop();
+                let offset = self.stack.pop().value;
                 let size = self.stack.pop().value;
 
                 // Safely convert U256 to usize
@@ -1129,8 +1197,19 @@ impl VM {
                     }
                 };
 
-                let mut value = Vec::with_capacity(size);
-                value.resize(size, 0xff);
+                // returndata is only known after calls to precompiles
+                let offset: usize = offset.try_into().unwrap_or(usize::MAX);
+                let value = match &self.call_returndata {
+                    Some(returndata) => {
+                        let mut value = returndata
+                            .get(offset..)
+                            .map(|data| data[..data.len().min(size)].to_vec())
+                            .unwrap_or_default();
+                        value.resize(size, 0);
+                        value
+                    }
+                    None => vec![0xff; size],
+                };
 
                 // consume dynamic gas
                 let minimum_word_size = ((size + 31) / 32) as u128;
@@ -1139,6 +1218,7 @@ impl VM {
                 self.consume_gas(gas_cost);
 
                 self.memory.store(dest_offset, size, &value);
+                self.memory.set_source(dest_offset, size, MemorySource::ReturnData(offset));
             }
 
             // EXTCODEHASH and BLOCKHASH
@@ -1146,14 +1226,16 @@ impl VM {
                 let address = self.stack.pop().value;
 
                 // consume dynamic gas
-                if opcode == 0x3f && !self.address_access_set.contains(&address) {
-                    self.consume_gas(2600);
-                    self.address_access_set.insert(address);
-                } else if opcode == 0x3f {
-                    self.consume_gas(100);
+                if opcode == 0x3f {
+                    self.access_account(address);
                 }
 
-                self.stack.push(U256::zero(), operation);
+                let code = if opcode == 0x3f { self.code(address) } else { None };
+                let hash = match code {
+                    Some(code) if !code.is_empty() => U256::from_big_endian(&keccak256(code)),
+                    _ => U256::zero(),
+                };
+                self.stack.push(hash, operation);
             }
 
             // COINBASE
@@ -1166,16 +1248,38 @@ impl VM {
 
             // TIMESTAMP
             0x42 => {
-                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                let timestamp = match deterministic() {
+                    true => DETERMINISTIC_TIMESTAMP,
+                    false => SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                };
 
                 self.stack.push(U256::from(timestamp), operation);
             }
 
+            // SELFBALANCE
+            0x47 => {
+                let balance = self.balance(U256::from_big_endian(&self.address));
+                self.stack.push(balance, operation);
+            }
+
             // NUMBER -> BASEFEE
             (0x43..=0x48) => {
                 self.stack.push(U256::from(1u8), operation);
             }
 
+            // BLOBHASH
+            0x49 => {
+                self.stack.pop();
+
+                // there are no blobs in the emulated transaction
+                self.stack.push(U256::zero(), operation);
+            }
+
+            // BLOBBASEFEE
+            0x4a => {
+                self.stack.push(U256::from(1u8), operation);
+            }
+
             // POP
             0x50 => {
                 self.stack.pop();
@@ -1208,13 +1312,29 @@ impl VM {
                 let gas_cost = self.memory.expansion_cost(i, 32);
                 self.consume_gas(gas_cost);
 
+                // words copied into memory are loaded as whatever they were copied from. words
+                // stored with MSTORE are left as memory reads, unless MCOPY has moved them
+                let operation = match self.memory.source(i, 32) {
+                    Some(MemorySource::Calldata(offset)) => {
+                        WrappedOpcode::new(0x35, vec![WrappedInput::Raw(U256::from(offset))])
+                    }
+                    Some(MemorySource::Code(_)) => {
+                        WrappedOpcode::new(0x7f, vec![WrappedInput::Raw(result)])
+                    }
+                    Some(MemorySource::ReturnData(offset)) => {
+                        WrappedOpcode::new(0x3e, vec![WrappedInput::Raw(U256::from(offset))])
+                    }
+                    Some(MemorySource::Copied(operation)) => operation,
+                    Some(MemorySource::Stored(_)) | None => operation,
+                };
+
                 self.stack.push(result, operation);
             }
 
             // MSTORE
             0x52 => {
                 let offset = self.stack.pop().value;
-                let value = self.stack.pop().value;
+                let value = self.stack.pop();
 
                 // Safely convert U256 to usize
                 let offset: usize = match offset.try_into() {
@@ -1237,7 +1357,8 @@ impl VM {
                 let gas_cost = self.memory.expansion_cost(offset, 32);
                 self.consume_gas(gas_cost);
 
-                self.memory.store(offset, 32, value.encode().as_slice());
+                self.memory.store(offset, 32, value.value.encode().as_slice());
+                self.memory.set_source(offset, 32, MemorySource::Stored(value.operation));
             }
 
             // MSTORE8
@@ -1274,10 +1395,11 @@ impl VM {
                 let key = self.stack.pop().value;
 
                 // consume dynamic gas
-                let gas_cost = self.storage.access_cost(key.into());
+                let gas_cost = self.hardfork.sload_cost(self.storage.is_warm(key.into()));
                 self.consume_gas(gas_cost);
 
-                self.stack.push(U256::from(self.storage.load(key.into())), operation)
+                let value = self.sload(key);
+                self.stack.push(value, operation)
             }
 
             // SSTORE
@@ -1285,9 +1407,20 @@ impl VM {
                 let key = self.stack.pop().value;
                 let value = self.stack.pop().value;
 
-                // consume dynamic gas
-                let gas_cost = self.storage.storage_cost(key.into(), value.into());
+                // SSTORE fails when only the call stipend remains, per EIP-2200
+                if self.gas_remaining <= 2300 {
+                    self.consume_gas(self.gas_remaining + 1);
+                }
+
+                // consume dynamic gas, which depends on the slot's value at the start of the
+                // transaction and before this write
+                let warm = self.storage.is_warm(key.into());
+                let current = self.sload(key);
+                self.storage.set_original(key.into(), current.into());
+                let original = self.storage.original(key.into()).map(U256::from).unwrap_or(current);
+                let (gas_cost, refund) = self.hardfork.sstore_cost(original, current, value, warm);
                 self.consume_gas(gas_cost);
+                self.gas_refund += refund;
 
                 self.storage.store(key.into(), value.into());
             }
@@ -1296,28 +1429,9 @@ impl VM {
             0x56 => {
                 let pc = self.stack.pop().value;
 
-                // Safely convert U256 to u128
-                let pc: u128 = match pc.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
-
-                // Check if JUMPDEST is valid and throw with 790 if not (invalid jump destination)
-                if (pc <= self.bytecode.len().try_into().unwrap()) &&
-                    (self.bytecode[pc as usize] != 0x5b)
-                {
-                    self.exit(790, Vec::new());
+                // the destination must be a JUMPDEST within the code
+                if !self.is_jumpdest(pc) {
+                    self.fault(FaultKind::InvalidJump(pc), last_instruction, opcode);
                     return Instruction {
                         instruction: last_instruction,
                         opcode,
@@ -1327,9 +1441,8 @@ impl VM {
                         input_operations,
                         output_operations: Vec::new(),
                     }
-                } else {
-                    self.instruction = pc + 1;
                 }
+                self.instruction = pc.as_u128() + 1;
             }
 
             // JUMPI
@@ -1337,30 +1450,10 @@ impl VM {
                 let pc = self.stack.pop().value;
                 let condition = self.stack.pop().value;
 
-                // Safely convert U256 to u128
-                let pc: u128 = match pc.try_into() {
-                    Ok(x) => x,
-                    Err(_) => {
-                        self.exit(2, Vec::new());
-                        return Instruction {
-                            instruction: last_instruction,
-                            opcode,
-                            opcode_details: Some(opcode_details),
-                            inputs,
-                            outputs: Vec::new(),
-                            input_operations,
-                            output_operations: Vec::new(),
-                        }
-                    }
-                };
-
                 if !condition.eq(&U256::from(0u8)) {
-                    // Check if JUMPDEST is valid and throw with 790 if not (invalid jump
-                    // destination)
-                    if (pc <= self.bytecode.len().try_into().unwrap()) &&
-                        (self.bytecode[pc as usize] != 0x5b)
-                    {
-                        self.exit(790, Vec::new());
+                    // the destination must be a JUMPDEST within the code
+                    if !self.is_jumpdest(pc) {
+                        self.fault(FaultKind::InvalidJump(pc), last_instruction, opcode);
                         return Instruction {
                             instruction: last_instruction,
                             opcode,
@@ -1370,9 +1463,8 @@ impl VM {
                             input_operations,
                             output_operations: Vec::new(),
                         }
-                    } else {
-                        self.instruction = pc + 1;
                     }
+                    self.instruction = pc.as_u128() + 1;
                 }
             }
 
@@ -1394,6 +1486,83 @@ impl VM {
                 self.stack.push(U256::from(self.gas_remaining), operation);
             }
 
+            // TLOAD
+            0x5c => {
+                let key = self.stack.pop().value;
+
+                self.stack.push(U256::from(self.transient_storage.load(key.into())), operation)
+            }
+
+            // TSTORE
+            0x5d => {
+                let key = self.stack.pop().value;
+                let value = self.stack.pop().value;
+
+                self.transient_storage.store(key.into(), value.into());
+            }
+
+            // MCOPY
+            0x5e => {
+                let dest_offset = self.stack.pop().value;
+                let offset = self.stack.pop().value;
+                let size = self.stack.pop().value;
+
+                // Safely convert U256 to usize
+                let dest_offset: usize = match dest_offset.try_into() {
+                    Ok(x) => x,
+                    Err(_) => {
+                        self.exit(2, Vec::new());
+                        return Instruction {
+                            instruction: last_instruction,
+                            opcode,
+                            opcode_details: Some(opcode_details),
+                            inputs,
+                            outputs: Vec::new(),
+                            input_operations,
+                            output_operations: Vec::new(),
+                        }
+                    }
+                };
+                let offset: usize = match offset.try_into() {
+                    Ok(x) => x,
+                    Err(_) => {
+                        self.exit(2, Vec::new());
+                        return Instruction {
+                            instruction: last_instruction,
+                            opcode,
+                            opcode_details: Some(opcode_details),
+                            inputs,
+                            outputs: Vec::new(),
+                            input_operations,
+                            output_operations: Vec::new(),
+                        }
+                    }
+                };
+                let size: usize = match size.try_into() {
+                    Ok(x) => x,
+                    Err(_) => {
+                        self.exit(2, Vec::new());
+                        return Instruction {
+                            instruction: last_instruction,
+                            opcode,
+                            opcode_details: Some(opcode_details),
+                            inputs,
+                            outputs: Vec::new(),
+                            input_operations,
+                            output_operations: Vec::new(),
+                        }
+                    }
+                };
+
+                // consume dynamic gas
+                let minimum_word_size = size.div_ceil(32) as u128;
+                let gas_cost = 3 * minimum_word_size +
+                    self.memory.expansion_cost(std::cmp::max(offset, dest_offset), size);
+                self.consume_gas(gas_cost);
+
+                self.memory.copy(offset, dest_offset, size);
+            }
+
             // PUSH0
             0x5f => {
                 self.stack.push(U256::zero(), operation);
@@ -1491,28 +1660,37 @@ impl VM {
 
             // CREATE
             0xF0 => {
-                self.stack.pop_n(3);
+                let value = self.stack.pop().value;
+                let offset = self.stack.pop().value;
+                let size = self.stack.pop().value;
 
-                self.stack.push(
-                    U256::from_str("0x6865696d64616c6c000000000000637265617465").unwrap(),
-                    operation,
-                );
+                let address = self.create(value, offset, size, None);
+                self.stack.push(address, operation);
             }
 
             // CALL, CALLCODE
             0xF1 | 0xF2 => {
+                self.stack.pop();
                 let address = self.stack.pop().value;
-                self.stack.pop_n(6);
+                let value = self.stack.pop().value;
+                let args_offset = self.stack.pop().value;
+                let args_size = self.stack.pop().value;
+                let ret_offset = self.stack.pop().value;
+                let ret_size = self.stack.pop().value;
 
                 // consume dynamic gas
-                if !self.address_access_set.contains(&address) {
-                    self.consume_gas(2600);
-                    self.address_access_set.insert(address);
-                } else {
-                    self.consume_gas(100);
-                }
+                self.access_account(address);
 
-                self.stack.push(U256::from(1u8), operation);
+                let success = self.execute_call(
+                    opcode,
+                    address,
+                    value,
+                    args_offset,
+                    args_size,
+                    ret_offset,
+                    ret_size,
+                );
+                self.stack.push(U256::from(success as u8), operation);
             }
 
             // RETURN
@@ -1561,28 +1739,37 @@ impl VM {
 
             // DELEGATECALL, STATICCALL
             0xF4 | 0xFA => {
+                self.stack.pop();
                 let address = self.stack.pop().value;
-                self.stack.pop_n(5);
+                let args_offset = self.stack.pop().value;
+                let args_size = self.stack.pop().value;
+                let ret_offset = self.stack.pop().value;
+                let ret_size = self.stack.pop().value;
 
                 // consume dynamic gas
-                if !self.address_access_set.contains(&address) {
-                    self.consume_gas(2600);
-                    self.address_access_set.insert(address);
-                } else {
-                    self.consume_gas(100);
-                }
+                self.access_account(address);
 
-                self.stack.push(U256::from(1u8), operation);
+                let success = self.execute_call(
+                    opcode,
+                    address,
+                    U256::zero(),
+                    args_offset,
+                    args_size,
+                    ret_offset,
+                    ret_size,
+                );
+                self.stack.push(U256::from(success as u8), operation);
             }
 
             // CREATE2
             0xF5 => {
-                self.stack.pop_n(4);
+                let value = self.stack.pop().value;
+                let offset = self.stack.pop().value;
+                let size = self.stack.pop().value;
+                let salt = self.stack.pop().value;
 
-                self.stack.push(
-                    U256::from_str("0x6865696d64616c6c000000000063726561746532").unwrap(),
-                    operation,
-                );
+                let address = self.create(value, offset, size, Some(salt));
+                self.stack.push(address, operation);
             }
 
             // REVERT
@@ -1625,7 +1812,22 @@ impl VM {
                 self.exit(1, self.memory.read(offset, size));
             }
 
-            // INVALID & SELFDESTRUCT
+            // SELFDESTRUCT
+            0xff => {
+                let recipient = self.stack.pop().value;
+
+                // sending the balance to a cold account costs extra since EIP-2929
+                if self.address_access_set.insert(recipient) && self.hardfork >= Hardfork::Berlin {
+                    self.consume_gas(2600);
+                }
+                self.gas_refund += self.hardfork.selfdestruct_refund();
+
+                // accounts are only removed at the end of the transaction, and since EIP-6780 only
+                // if they were created in the same transaction, so execution halts as with STOP
+                self.exit(10, Vec::new());
+            }
+
+            // INVALID
             _ => {
                 self.exit(1, Vec::new());
             }
@@ -1669,7 +1871,14 @@ impl VM {
     /// assert_eq!(vm.exitcode, 10);
     /// ```
     pub fn step(&mut self) -> State {
+        let traced_step = self.tracer.clone().map(|tracer| (tracer.before_step(self), tracer));
         let instruction = self._step();
+        if let Some((index, tracer)) = traced_step {
+            tracer.after_step(index, self);
+        }
+        if let Some(taint) = self.taint.as_mut() {
+            taint.apply(&instruction);
+        }
 
         State {
             last_instruction: instruction,
@@ -1679,6 +1888,7 @@ impl VM {
             memory: self.memory.clone(),
             storage: self.storage.clone(),
             events: self.events.clone(),
+            fault: self.fault.clone(),
         }
     }
 
@@ -1718,7 +1928,8 @@ impl VM {
         states
     }
 
-    /// Resets the VM state for a new execution
+    /// Resets the VM state for a new execution. Storage persists between executions, while
+    /// transient storage is cleared, since it only lasts for a single transaction.
     ///
     /// ```
     /// use heimdall_common::ether::evm::core::vm::VM;
@@ -1743,13 +1954,328 @@ impl VM {
     pub fn reset(&mut self) {
         self.stack = Stack::new();
         self.memory = Memory::new();
+        self.transient_storage = Storage::new();
+        for contract in self.contracts.values_mut() {
+            contract.transient_storage = Storage::new();
+        }
+        self.storage.reset_access();
+        self.address_access_set.clear();
         self.instruction = 1;
         self.gas_remaining = u128::max_value();
         self.gas_used = 21000;
+        self.gas_refund = 0;
         self.events = Vec::new();
         self.returndata = Vec::new();
         self.exitcode = 255;
+        self.fault = None;
         self.timestamp = Instant::now();
+        self.call_returndata = None;
+    }
+
+    /// Creates a child [`VM`] for a call or contract creation made from this context, with its own
+    /// stack, memory, and storage.
+    fn child(&self, bytecode: Vec<u8>, calldata: Vec<u8>, address: Vec<u8>, value: u128) -> VM {
+        VM {
+            stack: Stack::new(),
+            memory: Memory::new(),
+            storage: Storage::new(),
+            transient_storage: Storage::new(),
+            instruction: 1,
+            bytecode,
+            calldata,
+            address,
+            origin: self.origin.clone(),
+            caller: self.address.clone(),
+            value,
+            // all but one 64th of the remaining gas is passed to the child, per EIP-150
+            gas_remaining: self.gas_remaining - self.gas_remaining / 64,
+            gas_used: 0,
+            gas_refund: 0,
+            events: Vec::new(),
+            returndata: Vec::new(),
+            exitcode: 255,
+            timestamp: Instant::now(),
+            address_access_set: self.address_access_set.clone(),
+            call_returndata: None,
+            contracts: HashMap::new(),
+            nonce: 1,
+            depth: self.depth + 1,
+            fork: self.fork.clone(),
+            trace_cache: TraceCache::default(),
+            tracer: self.tracer.clone(),
+            // taint isn't tracked into child contexts
+            taint: None,
+            hardfork: self.hardfork,
+            fault: None,
+            // hashes computed by the child are part of the same transaction
+            preimages: self.preimages.clone(),
+        }
+    }
+
+    /// Executes the init code in memory in a child context, returning the address of the created
+    /// contract, or zero if creation failed. The deployed code is kept in
+    /// [`VM::contracts`], so the contract can be called later in the same execution.
+    fn create(&mut self, value: U256, offset: U256, size: U256, salt: Option<U256>) -> U256 {
+        self.call_returndata = Some(Vec::new());
+
+        // offsets or sizes which don't fit in a usize can never be paid for
+        let (offset, size): (usize, usize) = match (offset.try_into(), size.try_into()) {
+            (Ok(offset), Ok(size)) => (offset, size),
+            _ => return U256::zero(),
+        };
+
+        // consume dynamic gas, including hashing the init code for CREATE2
+        let words = size.div_ceil(32) as u128;
+        let hash_cost = if salt.is_some() { 6 * words } else { 0 };
+        let init_code_cost = self.hardfork.initcode_word_cost() * words;
+        if !self.consume_gas(self.memory.expansion_cost(offset, size) + init_code_cost + hash_cost) ||
+            self.depth >= MAX_CALL_DEPTH
+        {
+            return U256::zero()
+        }
+
+        let init_code = self.memory.read(offset, size);
+        let sender = Address::from(H256::from_uint(&U256::from_big_endian(&self.address)));
+        let address = match salt {
+            Some(salt) => {
+                get_create2_address(sender, H256::from_uint(&salt).as_bytes(), init_code.clone())
+            }
+            None => get_contract_address(sender, self.nonce),
+        };
+        self.nonce += 1;
+
+        let mut child = self.child(
+            init_code,
+            Vec::new(),
+            address.as_bytes().to_vec(),
+            value.try_into().unwrap_or(u128::MAX),
+        );
+        child.execute();
+        self.consume_gas(child.gas_used);
+
+        // reverted init code returns its revert data
+        let code = std::mem::take(&mut child.returndata);
+        if !matches!(child.exitcode, 0 | 10) {
+            self.call_returndata = Some(code);
+            return U256::zero()
+        }
+        self.gas_refund += child.gas_refund;
+
+        // deployed code can't exceed 24576 bytes or start with 0xEF, per EIP-170 and EIP-3541
+        if code.len() > 24576 ||
+            code.first() == Some(&0xef) ||
+            !self.consume_gas(200 * code.len() as u128)
+        {
+            return U256::zero()
+        }
+
+        let address = U256::from_big_endian(address.as_bytes());
+        child.bytecode = code;
+        child.instruction = 1;
+        child.exitcode = 255;
+        self.address_access_set.insert(address);
+        self.contracts.insert(address, child);
+        address
+    }
+
+    /// Executes a call to the given address, returning whether it succeeded. Calls to precompiled
+    /// contracts, contracts created during execution, and contracts on the fork are executed, with
+    /// their output written to memory and kept for RETURNDATACOPY. Calls to other contracts aren't
+    /// executed, and are assumed to succeed.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_call(
+        &mut self,
+        opcode: u8,
+        address: U256,
+        value: U256,
+        args_offset: U256,
+        args_size: U256,
+        ret_offset: U256,
+        ret_size: U256,
+    ) -> bool {
+        if !is_precompile(address) && !self.contracts.contains_key(&address) {
+            match self.code(address) {
+                // contracts on the fork are loaded the first time they're called
+                Some(code) if !code.is_empty() => {
+                    let contract = self.child(
+                        code,
+                        Vec::new(),
+                        Address::from(H256::from_uint(&address)).as_bytes().to_vec(),
+                        0,
+                    );
+                    self.contracts.insert(address, contract);
+                }
+
+                // calls to accounts on the fork without code always succeed
+                Some(_) => {
+                    self.call_returndata = Some(Vec::new());
+                    return self.exitcode == 255
+                }
+                None => {
+                    self.call_returndata = None;
+                    return true
+                }
+            }
+        }
+        self.call_returndata = Some(Vec::new());
+
+        // offsets or sizes which don't fit in a usize can never be paid for
+        let (args_offset, args_size, ret_offset, ret_size): (usize, usize, usize, usize) = match (
+            args_offset.try_into(),
+            args_size.try_into(),
+            ret_offset.try_into(),
+            ret_size.try_into(),
+        ) {
+            (Ok(args_offset), Ok(args_size), Ok(ret_offset), Ok(ret_size)) => {
+                (args_offset, args_size, ret_offset, ret_size)
+            }
+            _ => return false,
+        };
+
+        // consume dynamic gas
+        let gas_cost = self.memory.expansion_cost(args_offset, args_size) +
+            self.memory.expansion_cost(ret_offset, ret_size);
+        if !self.consume_gas(gas_cost) {
+            return false
+        }
+
+        let input = self.memory.read(args_offset, args_size);
+        let (success, output) = match execute_precompile(address, &input) {
+            Some((gas_used, output)) => (self.consume_gas(gas_used), output),
+            None if is_precompile(address) => return false,
+            None => self.execute_contract(opcode, address, value, input),
+        };
+
+        if ret_size > 0 {
+            self.memory.store(ret_offset, ret_size.min(output.len()), &output);
+            self.memory.set_source(
+                ret_offset,
+                ret_size.min(output.len()),
+                MemorySource::ReturnData(0),
+            );
+        }
+        self.call_returndata = Some(output);
+        success
+    }
+
+    /// Executes a call to a contract in [`VM::contracts`] in a child context, returning whether it
+    /// succeeded and its returndata. State changes are only kept if the call succeeds.
+    fn execute_contract(
+        &mut self,
+        opcode: u8,
+        address: U256,
+        value: U256,
+        calldata: Vec<u8>,
+    ) -> (bool, Vec<u8>) {
+        if self.depth >= MAX_CALL_DEPTH {
+            return (false, Vec::new())
+        }
+
+        let contract = &self.contracts[&address];
+        let value = value.try_into().unwrap_or(u128::MAX);
+        let mut child = match opcode {
+            // CALLCODE and DELEGATECALL run the contract's code in this context
+            0xF2 | 0xF4 => {
+                let mut child =
+                    self.child(contract.bytecode.clone(), calldata, self.address.clone(), value);
+                child.storage = self.storage.clone();
+                child.transient_storage = self.transient_storage.clone();
+                if opcode == 0xF4 {
+                    child.caller = self.caller.clone();
+                    child.value = self.value;
+                }
+                child
+            }
+            _ => {
+                let mut child = self.child(
+                    contract.bytecode.clone(),
+                    calldata,
+                    contract.address.clone(),
+                    value,
+                );
+                child.storage = contract.storage.clone();
+                child.transient_storage = contract.transient_storage.clone();
+                child.contracts = contract.contracts.clone();
+                child.nonce = contract.nonce;
+                child
+            }
+        };
+        child.execute();
+        self.consume_gas(child.gas_used);
+
+        let success = matches!(child.exitcode, 0 | 10);
+        if success {
+            self.gas_refund += child.gas_refund;
+            match opcode {
+                0xF2 | 0xF4 => {
+                    self.storage = child.storage;
+                    self.transient_storage = child.transient_storage;
+                }
+                _ => {
+                    let contract = self.contracts.get_mut(&address).unwrap();
+                    contract.storage = child.storage;
+                    contract.transient_storage = child.transient_storage;
+                    contract.contracts = child.contracts;
+                    contract.nonce = child.nonce;
+                }
+            }
+        }
+
+        (success, child.returndata)
+    }
+
+    /// Consumes the gas for accessing the given account, and marks it as warm. The cost of a warm
+    /// access is already charged as the opcode's minimum gas.
+    fn access_account(&mut self, address: U256) {
+        let warm = !self.address_access_set.insert(address);
+        self.consume_gas(self.hardfork.account_access_cost(warm) - 100);
+    }
+
+    /// Loads the given storage slot, falling back to the fork for slots which haven't been written
+    /// during execution. Execution halts if the node can't be queried.
+    fn sload(&mut self, key: U256) -> U256 {
+        let value = U256::from(self.storage.load(key.into()));
+        let fork = match &self.fork {
+            Some(fork) if !self.storage.storage.contains_key(&<[u8; 32]>::from(key)) => fork,
+            _ => return value,
+        };
+
+        match fork.storage(U256::from_big_endian(&self.address), key) {
+            Ok(value) => value,
+            Err(_) => {
+                self.exit(2, Vec::new());
+                U256::zero()
+            }
+        }
+    }
+
+    /// Gets the balance of the given account from the fork. Without a fork, balance is set to 1
+    /// wei because we won't run into div by 0 errors.
+    fn balance(&mut self, address: U256) -> U256 {
+        match self.fork.as_ref().map(|fork| fork.balance(address)) {
+            Some(Ok(balance)) => balance,
+            Some(Err(_)) => {
+                self.exit(2, Vec::new());
+                U256::zero()
+            }
+            None => U256::from(1),
+        }
+    }
+
+    /// Gets the code of the given account, if it was created during execution or is on the fork.
+    fn code(&mut self, address: U256) -> Option<Vec<u8>> {
+        if let Some(contract) = self.contracts.get(&address) {
+            return Some(contract.bytecode.clone())
+        }
+
+        match self.fork.as_ref().map(|fork| fork.code(address)) {
+            Some(Ok(code)) => Some(code),
+            Some(Err(_)) => {
+                self.exit(2, Vec::new());
+                None
+            }
+            None => None,
+        }
     }
 
     /// Executes the code until finished
@@ -1780,6 +2306,15 @@ impl VM {
             }
         }
 
+        // refunds are applied once the transaction succeeds, up to the hardfork's limit
+        if self.depth == 0 && matches!(self.exitcode, 0 | 10 | 255) {
+            let refund =
+                (self.gas_refund.max(0) as u128).min(self.hardfork.max_refund(self.gas_used));
+            self.gas_used -= refund;
+            self.gas_remaining += refund;
+            self.gas_refund = 0;
+        }
+
         ExecutionResult {
             gas_used: self.gas_used,
             gas_remaining: self.gas_remaining,
@@ -1788,6 +2323,7 @@ impl VM {
             events: self.events.clone(),
             runtime: self.timestamp.elapsed().as_secs_f64(),
             instruction: self.instruction,
+            fault: self.fault.clone(),
         }
     }
 
@@ -1820,31 +2356,47 @@ impl VM {
     }
 }
 
+/// Creates a new [`VM`] for tests, with the given bytecode and calldata.
+#[cfg(test)]
+pub(crate) fn new_test_vm(bytecode: &str, calldata: &str) -> VM {
+    VM::new(
+        String::from(bytecode),
+        String::from(calldata),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        9999999999,
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
     use std::str::FromStr;
 
-    use ethers::prelude::U256;
-
-    use crate::{ether::evm::core::vm::VM, utils::strings::decode_hex};
-
-    // creates a new test VM with calldata.
-    fn new_test_vm(bytecode: &str) -> VM {
-        VM::new(
-            String::from(bytecode),
-            String::from("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"),
-            String::from("0x6865696d64616c6c000000000061646472657373"),
-            String::from("0x6865696d64616c6c0000000000006f726967696e"),
-            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
-            0,
-            9999999999,
-        )
-    }
+    use ethers::{
+        prelude::U256,
+        types::{Address, H256},
+        utils::{get_contract_address, get_create2_address},
+    };
+
+    use crate::{
+        ether::evm::core::{
+            fault::FaultKind,
+            fork::Fork,
+            hardfork::Hardfork,
+            vm::{new_test_vm, VM},
+        },
+        utils::strings::decode_hex,
+    };
+
+    // the calldata the test VMs are created with
+    const CALLDATA: &str = "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF";
 
     #[test]
     fn test_stop_vm() {
-        let mut vm = new_test_vm("0x00");
+        let mut vm = new_test_vm("0x00", CALLDATA);
         vm.execute();
 
         assert!(vm.returndata.is_empty());
@@ -1853,7 +2405,7 @@ mod tests {
 
     #[test]
     fn test_pc_out_of_range() {
-        let mut vm = new_test_vm("0x");
+        let mut vm = new_test_vm("0x", CALLDATA);
         vm.execute();
 
         assert!(vm.returndata.is_empty());
@@ -1864,6 +2416,7 @@ mod tests {
     fn test_add() {
         let mut vm = new_test_vm(
             "0x600a600a017fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff600101",
+            CALLDATA,
         );
         vm.execute();
 
@@ -1875,6 +2428,7 @@ mod tests {
     fn test_mul() {
         let mut vm = new_test_vm(
             "0x600a600a027fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff600202",
+            CALLDATA,
         );
         vm.execute();
 
@@ -1888,7 +2442,7 @@ mod tests {
 
     #[test]
     fn test_sub() {
-        let mut vm = new_test_vm("0x600a600a036001600003");
+        let mut vm = new_test_vm("0x600a600a036001600003", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x00").unwrap());
@@ -1901,7 +2455,7 @@ mod tests {
 
     #[test]
     fn test_div() {
-        let mut vm = new_test_vm("0x600a600a046002600104");
+        let mut vm = new_test_vm("0x600a600a046002600104", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -1910,7 +2464,7 @@ mod tests {
 
     #[test]
     fn test_div_by_zero() {
-        let mut vm = new_test_vm("0x6002600004");
+        let mut vm = new_test_vm("0x6002600004", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -1918,7 +2472,7 @@ mod tests {
 
     #[test]
     fn test_sdiv() {
-        let mut vm = new_test_vm("0x600a600a057fFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF7fFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE05");
+        let mut vm = new_test_vm("0x600a600a057fFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF7fFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFE05", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -1927,7 +2481,7 @@ mod tests {
 
     #[test]
     fn test_sdiv_by_zero() {
-        let mut vm = new_test_vm("0x6002600005");
+        let mut vm = new_test_vm("0x6002600005", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -1935,7 +2489,7 @@ mod tests {
 
     #[test]
     fn test_mod() {
-        let mut vm = new_test_vm("0x6003600a066005601106");
+        let mut vm = new_test_vm("0x6003600a066005601106", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -1944,7 +2498,7 @@ mod tests {
 
     #[test]
     fn test_mod_by_zero() {
-        let mut vm = new_test_vm("0x6002600006");
+        let mut vm = new_test_vm("0x6002600006", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -1952,7 +2506,7 @@ mod tests {
 
     #[test]
     fn test_smod() {
-        let mut vm = new_test_vm("0x6003600a077ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff807");
+        let mut vm = new_test_vm("0x6003600a077ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffd7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff807", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -1965,7 +2519,7 @@ mod tests {
 
     #[test]
     fn test_smod_by_zero() {
-        let mut vm = new_test_vm("0x6002600007");
+        let mut vm = new_test_vm("0x6002600007", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -1973,7 +2527,7 @@ mod tests {
 
     #[test]
     fn test_addmod() {
-        let mut vm = new_test_vm("0x6008600a600a08600260027fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff08");
+        let mut vm = new_test_vm("0x6008600a600a08600260027fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff08", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x04").unwrap());
@@ -1982,7 +2536,7 @@ mod tests {
 
     #[test]
     fn test_addmod_by_zero() {
-        let mut vm = new_test_vm("0x60026000600008");
+        let mut vm = new_test_vm("0x60026000600008", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -1990,7 +2544,7 @@ mod tests {
 
     #[test]
     fn test_mulmod() {
-        let mut vm = new_test_vm("0x6008600a600a09600c7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff09");
+        let mut vm = new_test_vm("0x6008600a600a09600c7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff09", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x04").unwrap());
@@ -1999,7 +2553,7 @@ mod tests {
 
     #[test]
     fn test_mulmod_by_zero() {
-        let mut vm = new_test_vm("0x60026000600009");
+        let mut vm = new_test_vm("0x60026000600009", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -2007,7 +2561,7 @@ mod tests {
 
     #[test]
     fn test_exp() {
-        let mut vm = new_test_vm("0x6002600a0a600260020a");
+        let mut vm = new_test_vm("0x6002600a0a600260020a", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x64").unwrap());
@@ -2016,7 +2570,7 @@ mod tests {
 
     #[test]
     fn test_signextend() {
-        let mut vm = new_test_vm("0x60ff60000b607f60000b");
+        let mut vm = new_test_vm("0x60ff60000b607f60000b", CALLDATA);
         vm.execute();
 
         assert_eq!(
@@ -2029,7 +2583,7 @@ mod tests {
 
     #[test]
     fn test_lt() {
-        let mut vm = new_test_vm("0x600a600910600a600a10");
+        let mut vm = new_test_vm("0x600a600910600a600a10", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -2038,7 +2592,7 @@ mod tests {
 
     #[test]
     fn test_gt() {
-        let mut vm = new_test_vm("0x6009600a11600a600a10");
+        let mut vm = new_test_vm("0x6009600a11600a600a10", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -2049,6 +2603,7 @@ mod tests {
     fn test_slt() {
         let mut vm = new_test_vm(
             "0x60097fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff12600a600a12",
+            CALLDATA,
         );
         vm.execute();
 
@@ -2060,6 +2615,7 @@ mod tests {
     fn test_sgt() {
         let mut vm = new_test_vm(
             "0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff600913600a600a13",
+            CALLDATA,
         );
         vm.execute();
 
@@ -2069,7 +2625,7 @@ mod tests {
 
     #[test]
     fn test_eq() {
-        let mut vm = new_test_vm("0x600a600a14600a600514");
+        let mut vm = new_test_vm("0x600a600a14600a600514", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -2078,7 +2634,7 @@ mod tests {
 
     #[test]
     fn test_iszero() {
-        let mut vm = new_test_vm("0x600015600a15");
+        let mut vm = new_test_vm("0x600015600a15", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -2087,7 +2643,7 @@ mod tests {
 
     #[test]
     fn test_and() {
-        let mut vm = new_test_vm("0x600f600f16600060ff1600");
+        let mut vm = new_test_vm("0x600f600f16600060ff1600", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x0F").unwrap());
@@ -2096,7 +2652,7 @@ mod tests {
 
     #[test]
     fn test_or() {
-        let mut vm = new_test_vm("0x600f60f01760ff60ff17");
+        let mut vm = new_test_vm("0x600f60f01760ff60ff17", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0xff").unwrap());
@@ -2105,7 +2661,7 @@ mod tests {
 
     #[test]
     fn test_xor() {
-        let mut vm = new_test_vm("0x600f60f01860ff60ff18");
+        let mut vm = new_test_vm("0x600f60f01860ff60ff18", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0xff").unwrap());
@@ -2114,7 +2670,7 @@ mod tests {
 
     #[test]
     fn test_not() {
-        let mut vm = new_test_vm("0x600019");
+        let mut vm = new_test_vm("0x600019", CALLDATA);
         vm.execute();
 
         assert_eq!(
@@ -2126,7 +2682,7 @@ mod tests {
 
     #[test]
     fn test_byte() {
-        let mut vm = new_test_vm("0x60ff601f1a61ff00601e1a");
+        let mut vm = new_test_vm("0x60ff601f1a61ff00601e1a", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0xff").unwrap());
@@ -2137,6 +2693,7 @@ mod tests {
     fn test_shl() {
         let mut vm = new_test_vm(
             "600160011b7fFF0000000000000000000000000000000000000000000000000000000000000060041b",
+            CALLDATA,
         );
         vm.execute();
 
@@ -2152,6 +2709,7 @@ mod tests {
     fn test_shl_gt_255() {
         let mut vm = new_test_vm(
             "600161ffff1b7fFF0000000000000000000000000000000000000000000000000000000000000060041b",
+            CALLDATA,
         );
         vm.execute();
 
@@ -2165,7 +2723,7 @@ mod tests {
 
     #[test]
     fn test_shr() {
-        let mut vm = new_test_vm("600260011c60ff60041c");
+        let mut vm = new_test_vm("600260011c60ff60041c", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x01").unwrap());
@@ -2174,7 +2732,7 @@ mod tests {
 
     #[test]
     fn test_shr_gt_256() {
-        let mut vm = new_test_vm("600261ffff1c61ffff60041c");
+        let mut vm = new_test_vm("600261ffff1c61ffff60041c", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x00").unwrap());
@@ -2183,7 +2741,7 @@ mod tests {
 
     #[test]
     fn test_shr_zero() {
-        let mut vm = new_test_vm("0x600060011c");
+        let mut vm = new_test_vm("0x600060011c", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -2191,7 +2749,7 @@ mod tests {
 
     #[test]
     fn test_sar() {
-        let mut vm = new_test_vm("600260011d");
+        let mut vm = new_test_vm("600260011d", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x01").unwrap());
@@ -2199,7 +2757,7 @@ mod tests {
 
     #[test]
     fn test_sar_zero() {
-        let mut vm = new_test_vm("0x600060011d");
+        let mut vm = new_test_vm("0x600060011d", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
@@ -2209,6 +2767,7 @@ mod tests {
     fn test_sha3() {
         let mut vm = new_test_vm(
             "0x7fffffffff000000000000000000000000000000000000000000000000000000006000526004600020",
+            CALLDATA,
         );
         vm.execute();
 
@@ -2221,7 +2780,7 @@ mod tests {
 
     #[test]
     fn test_address() {
-        let mut vm = new_test_vm("0x30");
+        let mut vm = new_test_vm("0x30", CALLDATA);
         vm.execute();
 
         assert_eq!(
@@ -2232,7 +2791,7 @@ mod tests {
 
     #[test]
     fn test_calldataload() {
-        let mut vm = new_test_vm("600035601f35");
+        let mut vm = new_test_vm("600035601f35", CALLDATA);
         vm.execute();
 
         assert_eq!(
@@ -2249,7 +2808,7 @@ mod tests {
 
     #[test]
     fn test_calldatasize() {
-        let mut vm = new_test_vm("0x36");
+        let mut vm = new_test_vm("0x36", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x20").unwrap());
@@ -2258,7 +2817,7 @@ mod tests {
     #[test]
     fn test_xdatacopy() {
         // returndatacopy, calldatacopy, etc share same code.
-        let mut vm = new_test_vm("0x60ff6000600037");
+        let mut vm = new_test_vm("0x60ff6000600037", CALLDATA);
         vm.execute();
         assert_eq!(
             vm.memory.read(0, 32),
@@ -2268,7 +2827,7 @@ mod tests {
 
     #[test]
     fn test_codesize() {
-        let mut vm = new_test_vm("0x60ff60ff60ff60ff60ff38");
+        let mut vm = new_test_vm("0x60ff60ff60ff60ff60ff38", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x0B").unwrap());
@@ -2276,7 +2835,7 @@ mod tests {
 
     #[test]
     fn test_mload_mstore() {
-        let mut vm = new_test_vm("0x7f00000000000000000000000000000000000000000000000000000000000000FF600052600051600151");
+        let mut vm = new_test_vm("0x7f00000000000000000000000000000000000000000000000000000000000000FF600052600051600151", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0xff").unwrap());
@@ -2285,7 +2844,7 @@ mod tests {
 
     #[test]
     fn test_mstore8() {
-        let mut vm = new_test_vm("0x60ff600053");
+        let mut vm = new_test_vm("0x60ff600053", CALLDATA);
         vm.execute();
 
         assert_eq!(
@@ -2296,7 +2855,7 @@ mod tests {
 
     #[test]
     fn test_msize() {
-        let mut vm = new_test_vm("0x60ff60005359");
+        let mut vm = new_test_vm("0x60ff60005359", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x20").unwrap());
@@ -2304,29 +2863,72 @@ mod tests {
 
     #[test]
     fn test_sload_sstore() {
-        let mut vm = new_test_vm("0x602e600055600054600154");
+        let mut vm = new_test_vm("0x602e600055600054600154", CALLDATA);
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(1).value, U256::from_str("0x2e").unwrap());
+        assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
+    }
+
+    #[test]
+    fn test_tload_tstore() {
+        let mut vm = new_test_vm("0x602e60005d60005c60015c", CALLDATA);
         vm.execute();
 
         assert_eq!(vm.stack.peek(1).value, U256::from_str("0x2e").unwrap());
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x00").unwrap());
+        assert!(vm.storage.storage.is_empty());
+    }
+
+    #[test]
+    fn test_mcopy() {
+        let mut vm = new_test_vm("0x602e6000526020600060205e", CALLDATA);
+        vm.execute();
+
+        assert_eq!(
+            vm.memory.read(32, 32),
+            decode_hex("000000000000000000000000000000000000000000000000000000000000002e").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_blobhash_blobbasefee() {
+        let mut vm = new_test_vm("0x5f494a", CALLDATA);
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(1).value, U256::zero());
+        assert_eq!(vm.stack.peek(0).value, U256::from(1u8));
     }
 
     #[test]
     fn test_jump() {
-        let mut vm = new_test_vm("0x60fe56");
+        let mut vm = new_test_vm("0x60fe56", CALLDATA);
         vm.execute();
 
-        assert_eq!(U256::from(vm.instruction), U256::from_str("0xff").unwrap());
+        // 0xfe is past the end of the code, so isn't a JUMPDEST
+        assert_eq!(vm.exitcode, 790);
+        assert_eq!(U256::from(vm.instruction), U256::from_str("0x04").unwrap());
+
+        let mut vm = new_test_vm("0x6003565b", CALLDATA);
+        vm.execute();
+
+        assert_eq!(U256::from(vm.instruction), U256::from_str("0x05").unwrap());
     }
 
     #[test]
     fn test_jumpi() {
-        let mut vm = new_test_vm("0x600160fe57");
+        let mut vm = new_test_vm("0x600160fe57", CALLDATA);
         vm.execute();
 
-        assert_eq!(U256::from(vm.instruction), U256::from_str("0xff").unwrap());
+        assert_eq!(vm.exitcode, 790);
+        assert_eq!(U256::from(vm.instruction), U256::from_str("0x06").unwrap());
 
-        let mut vm = new_test_vm("0x600060fe5758");
+        let mut vm = new_test_vm("0x60016005575b", CALLDATA);
+        vm.execute();
+
+        assert_eq!(U256::from(vm.instruction), U256::from_str("0x07").unwrap());
+
+        let mut vm = new_test_vm("0x600060fe5758", CALLDATA);
         vm.execute();
 
         assert_eq!(U256::from(vm.instruction), U256::from_str("0x07").unwrap());
@@ -2335,6 +2937,191 @@ mod tests {
         assert_eq!(vm.stack.peek(0).value, U256::from_str("0x07").unwrap());
     }
 
+    #[test]
+    fn test_transient_storage_cleared_between_calls() {
+        // tstore(0, add(tload(0), 1)), mstore(0, tload(0)), return(0, 32)
+        let mut vm = new_test_vm("0x60005c60010160005d60005c60005260206000f3", CALLDATA);
+
+        for _ in 0..2 {
+            let result = vm.call("0x", 0);
+            assert_eq!(U256::from_big_endian(&result.returndata), U256::from(1));
+        }
+    }
+
+    #[test]
+    fn test_staticcall_precompile() {
+        // staticcall(gas(), 0x02, 0, 0, 0x20, 0x20), returndatasize(), mload(0x20)
+        let mut vm = new_test_vm("0x602060206000600060025afa3d60205100", CALLDATA);
+        vm.execute();
+
+        assert_eq!(
+            vm.stack.peek(0).value,
+            U256::from_str("0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+                .unwrap()
+        );
+        assert_eq!(vm.stack.peek(1).value, U256::from(32));
+        assert_eq!(vm.stack.peek(2).value, U256::from(1));
+    }
+
+    #[test]
+    fn test_create() {
+        // create a contract whose runtime code returns 42, then staticcall it and get its size
+        let mut vm = new_test_vm("0x75600a600c600039600a6000f3602a60005260206000f36000526016600a6000f06020600060006000845afa600051823b00", CALLDATA);
+        vm.execute();
+
+        let address = get_contract_address(
+            Address::from_str("0x6865696d64616c6c000000000061646472657373").unwrap(),
+            1,
+        );
+        assert_eq!(vm.stack.peek(3).value, U256::from_big_endian(address.as_bytes()));
+        assert_eq!(vm.stack.peek(2).value, U256::from(1));
+        assert_eq!(vm.stack.peek(1).value, U256::from(42));
+        assert_eq!(vm.stack.peek(0).value, U256::from(10));
+        assert_eq!(vm.nonce, 2);
+    }
+
+    #[test]
+    fn test_create2() {
+        let mut vm = new_test_vm("0x75600a600c600039600a6000f3602a60005260206000f360005260016016600a6000f56020600060006000845afa600051823b00", CALLDATA);
+        vm.execute();
+
+        let address = get_create2_address(
+            Address::from_str("0x6865696d64616c6c000000000061646472657373").unwrap(),
+            H256::from_low_u64_be(1).as_bytes(),
+            decode_hex("600a600c600039600a6000f3602a60005260206000f3").unwrap(),
+        );
+        assert_eq!(vm.stack.peek(3).value, U256::from_big_endian(address.as_bytes()));
+        assert_eq!(vm.stack.peek(1).value, U256::from(42));
+    }
+
+    #[test]
+    fn test_fork_state() {
+        // sload(0), selfbalance(), sstore(0, 1), sload(0)
+        let mut vm = new_test_vm("0x600054476001600055600054", CALLDATA);
+        let address = U256::from_big_endian(&vm.address);
+        let fork = Fork::new("http://localhost:1", None);
+        fork.set_storage(address, U256::zero(), U256::from(42));
+        fork.set_balance(address, U256::from(1000));
+        vm.fork = Some(fork);
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(2).value, U256::from(42));
+        assert_eq!(vm.stack.peek(1).value, U256::from(1000));
+        assert_eq!(vm.stack.peek(0).value, U256::from(1));
+    }
+
+    #[test]
+    fn test_fork_call() {
+        // staticcall(gas(), 0x1234, 0, 0, 0, 0x20), extcodesize(0x1234), mload(0)
+        let mut vm = new_test_vm("0x60206000600060006112345afa6112343b60005100", CALLDATA);
+        let fork = Fork::new("http://localhost:1", None);
+        fork.set_code(U256::from(0x1234), decode_hex("602a60005260206000f3").unwrap());
+        vm.fork = Some(fork);
+        vm.execute();
+
+        assert_eq!(vm.stack.peek(2).value, U256::from(1));
+        assert_eq!(vm.stack.peek(1).value, U256::from(10));
+        assert_eq!(vm.stack.peek(0).value, U256::from(42));
+    }
+
+    #[test]
+    fn test_hardfork_gas() {
+        // sstore(0, 1), sstore(0, 0)
+        let bytecode = "0x60016000556000600055";
+
+        // EIP-2929 access costs and EIP-3529 refunds
+        let mut vm = new_test_vm(bytecode, CALLDATA);
+        vm.execute();
+        assert_eq!(vm.gas_used, 43212 - 43212 / 5);
+
+        // EIP-2200 costs and refunds
+        let mut vm = new_test_vm(bytecode, CALLDATA);
+        vm.hardfork = Hardfork::Istanbul;
+        vm.execute();
+        assert_eq!(vm.gas_used, 41812 - 19200);
+    }
+
+    #[test]
+    fn test_hardfork_opcodes() {
+        // PUSH0 is invalid before Shanghai
+        let mut vm = new_test_vm("0x5f00", CALLDATA);
+        vm.hardfork = Hardfork::London;
+        vm.execute();
+        assert_eq!(vm.exitcode, 1);
+
+        let mut vm = new_test_vm("0x5f00", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.exitcode, 10);
+
+        // PUSH0 costs the base gas of 2 (EIP-3855), on top of the intrinsic 21000
+        assert_eq!(vm.gas_used, 21002);
+
+        // selfdestruct(caller) halts successfully, refunding gas before London
+        let mut vm = new_test_vm("0x33ff", CALLDATA);
+        vm.hardfork = Hardfork::Berlin;
+        vm.execute();
+        assert_eq!(vm.exitcode, 10);
+        assert_eq!(vm.gas_used, 28602 - 28602 / 2);
+
+        let mut vm = new_test_vm("0x33ff", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.exitcode, 10);
+        assert_eq!(vm.gas_used, 28602);
+    }
+
+    #[test]
+    fn test_faults() {
+        // ADD with a single item on the stack
+        let mut vm = new_test_vm("0x600101", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.exitcode, 2);
+        assert_eq!(vm.fault.unwrap().kind, FaultKind::StackUnderflow { required: 2, available: 1 });
+
+        // jump to the end of the code
+        let mut vm = new_test_vm("0x600356", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.exitcode, 790);
+        let fault = vm.fault.unwrap();
+        assert_eq!(fault.kind, FaultKind::InvalidJump(U256::from(3)));
+        assert_eq!((fault.pc, fault.opcode, fault.depth), (2, 0x56, 0));
+
+        // mload(2**64)
+        let mut vm = new_test_vm("0x680100000000000000005100", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.exitcode, 2);
+        assert_eq!(
+            vm.fault.unwrap().kind,
+            FaultKind::MemoryOutOfBounds {
+                offset: U256::from(2).pow(U256::from(64)),
+                size: U256::from(32)
+            }
+        );
+
+        // calldataload(2**255) reads zeros past the end of calldata
+        let mut vm = new_test_vm(
+            "0x7f80000000000000000000000000000000000000000000000000000000000000003500",
+            CALLDATA,
+        );
+        vm.execute();
+        assert_eq!(vm.exitcode, 10);
+        assert_eq!(vm.stack.peek(0).value, U256::zero());
+        assert!(vm.fault.is_none());
+    }
+
+    #[test]
+    fn test_memory_provenance() {
+        // calldatacopy(0, 4, 64), mcopy(64, 32, 32), mload(64)
+        let mut vm = new_test_vm("0x604060046000376020602060405e60405100", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.stack.peek(0).operation.solidify(), "arg1");
+
+        // mstore(0, caller), mcopy(32, 0, 32), mload(32), mload(0)
+        let mut vm = new_test_vm("0x336000526020600060205e60205160005100", CALLDATA);
+        vm.execute();
+        assert_eq!(vm.stack.peek(0).operation.opcode.name, "MLOAD");
+        assert_eq!(vm.stack.peek(1).operation.solidify(), "msg.sender");
+    }
+
     #[test]
     fn test_usdt_sim() {
         // this execution should return the name of the USDT contract
@@ -0,0 +1,386 @@
+use std::{
+    fmt::{Debug, Formatter},
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use ethers::types::U256;
+use hashlink::LruCache;
+
+use crate::ether::evm::core::{
+    stack::{Stack, StackFrame},
+    storage::Storage,
+    vm::{State, VM},
+};
+
+use super::{Path, VMTrace};
+
+// the most states whose branches are cached, and the most sets of branches cached for each of
+// them, such as when a routine is reached with different stacks
+const TRACE_CACHE_CAPACITY: usize = 4096;
+const MAX_BRANCHES_PER_STATE: usize = 16;
+
+/// The key of the cached branches of a JUMPI: the state before it, and the conditions of the path
+/// which reached that state.
+pub(super) type TraceKey = (StateKey, Path);
+
+/// The branches explored from a JUMPI, along with the parts of the state before it which they
+/// read and which aren't part of its [`TraceKey`].
+struct CachedBranches {
+    // the top of the stack, as deep as the branches read it. if they read past its bottom, the
+    // whole stack must match
+    stack: Vec<StackFrame>,
+    exact_stack: bool,
+
+    // the calldata the branches read, and the offset of each range of it
+    calldata: Vec<(usize, Vec<u8>)>,
+    children: Vec<Arc<VMTrace>>,
+}
+
+impl CachedBranches {
+    /// Whether the branches can be reused from the given VM, which is at a state with the same
+    /// [`TraceKey`] as the one they were explored from.
+    fn matches(&self, vm: &VM) -> bool {
+        let stack = &vm.stack.stack;
+        let size_matches = match self.exact_stack {
+            true => stack.len() == self.stack.len(),
+            false => stack.len() >= self.stack.len(),
+        };
+
+        size_matches &&
+            stack.iter().zip(&self.stack).all(|(frame, cached)| frame == cached) &&
+            self.calldata.iter().all(|(offset, bytes)| {
+                vm.calldata.get(*offset..offset + bytes.len()) == Some(bytes.as_slice())
+            })
+    }
+}
+
+/// The [`TraceCache`] memoizes symbolic execution, mapping the state before a JUMPI to the
+/// branches explored from it. Branches only depend on the stack as deep as they read it, and on
+/// the calldata they load, so a routine shared by many functions, such as a modifier's revert or
+/// a helper which ends the call, is only explored once, even when each function reaches it with
+/// its own selector and return addresses below it on the stack. Routines which return to the
+/// function which called them read its return address, so they're only shared between callers
+/// which return to the same place. \
+/// \
+/// The least recently used states are evicted once the cache is full. Clones share the same
+/// cache, so a cache attached to a [`VM`] is reused by every function executed on a clone of it.
+#[derive(Clone)]
+pub struct TraceCache {
+    traces: Arc<Mutex<LruCache<TraceKey, Vec<CachedBranches>>>>,
+    hits: Arc<AtomicUsize>,
+}
+
+impl Default for TraceCache {
+    fn default() -> Self {
+        TraceCache {
+            traces: Arc::new(Mutex::new(LruCache::new(TRACE_CACHE_CAPACITY))),
+            hits: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+impl TraceCache {
+    /// Gets the branches explored from the JUMPI the given VM is about to execute, if any were
+    /// explored from a matching state.
+    pub(super) fn get(&self, key: &TraceKey, vm: &VM) -> Option<Vec<Arc<VMTrace>>> {
+        let mut traces = self.traces.lock().expect("trace cache lock poisoned");
+        let children = traces
+            .get(key)?
+            .iter()
+            .find(|branches| branches.matches(vm))
+            .map(|branches| branches.children.clone())?;
+
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(children)
+    }
+
+    /// Stores the branches explored from a JUMPI, given the key and stack of the state before it,
+    /// the calldata it was explored with, and the JUMPI's own state.
+    pub(super) fn insert(
+        &self,
+        key: TraceKey,
+        stack: &Stack,
+        calldata: &[u8],
+        jump: &State,
+        children: &[Arc<VMTrace>],
+    ) {
+        let (depth, ranges) = reads(jump, children, calldata.len());
+        let branches = CachedBranches {
+            stack: stack.stack.iter().take(depth).cloned().collect(),
+            exact_stack: depth > stack.size(),
+            calldata: ranges
+                .into_iter()
+                .map(|range| (range.start, calldata[range].to_vec()))
+                .collect(),
+            children: children.to_vec(),
+        };
+
+        let mut traces = self.traces.lock().expect("trace cache lock poisoned");
+        match traces.get_mut(&key) {
+            Some(cached) => {
+                if cached.len() >= MAX_BRANCHES_PER_STATE {
+                    cached.remove(0);
+                }
+                cached.push(branches);
+            }
+            None => {
+                traces.insert(key, vec![branches]);
+            }
+        }
+    }
+
+    /// The number of cached sets of branches.
+    pub fn len(&self) -> usize {
+        self.traces
+            .lock()
+            .expect("trace cache lock poisoned")
+            .iter()
+            .map(|(_, branches)| branches.len())
+            .sum()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of times cached branches were reused.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Removes every cached set of branches.
+    pub fn clear(&self) {
+        self.traces.lock().expect("trace cache lock poisoned").clear();
+    }
+}
+
+impl Debug for TraceCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TraceCache {{ {} traces, {} hits }}", self.len(), self.hits())
+    }
+}
+
+/// How deep into the stack a JUMPI and the branches explored from it read, and the ranges of the
+/// given length of calldata they load. Heights are relative to the stack before the JUMPI, so they
+/// hold for branches which were themselves reused from the cache.
+fn reads(
+    jump: &State,
+    children: &[Arc<VMTrace>],
+    calldata_len: usize,
+) -> (usize, Vec<Range<usize>>) {
+    let mut depth = 0;
+    let mut ranges = Vec::new();
+
+    let height = read(jump, 0, &mut depth, &mut ranges, calldata_len);
+    let mut traces = children.iter().map(|child| (child.as_ref(), height)).collect::<Vec<_>>();
+    while let Some((trace, mut height)) = traces.pop() {
+        for state in &trace.operations {
+            height = read(state, height, &mut depth, &mut ranges, calldata_len);
+        }
+        traces.extend(trace.children.iter().map(|child| (child.as_ref(), height)));
+    }
+
+    (depth, ranges)
+}
+
+/// Records how deep into the stack the given operation reads, and the calldata it loads, returning
+/// the height of the stack after it.
+fn read(
+    state: &State,
+    height: isize,
+    depth: &mut usize,
+    ranges: &mut Vec<Range<usize>>,
+    calldata_len: usize,
+) -> isize {
+    let instruction = &state.last_instruction;
+    let (inputs, outputs) = instruction
+        .opcode_details
+        .as_ref()
+        .map(|opcode| (opcode.inputs as isize, opcode.outputs as isize))
+        .unwrap_or((0, 0));
+    *depth = (*depth).max((inputs - height).max(0) as usize);
+
+    // reads past the end of calldata are zeroes, whatever it holds
+    let range = match instruction.opcode {
+        0x35 => instruction.inputs.first().map(|offset| (*offset, U256::from(32))),
+        0x37 => instruction.inputs.get(1).zip(instruction.inputs.get(2)).map(|(a, b)| (*a, *b)),
+        _ => None,
+    };
+    if let Some((offset, size)) = range.filter(|(offset, _)| *offset < U256::from(calldata_len)) {
+        let start = offset.as_usize();
+        let end = start + size.min(U256::from(calldata_len - start)).as_usize();
+        ranges.push(start..end);
+    }
+
+    height - inputs + outputs
+}
+
+/// The normalized state of a VM, which, along with the parts of its stack and calldata which are
+/// read, determines everything that symbolic execution from its current instruction will
+/// discover. Gas is left out, since symbolic execution never runs out of it, as is the storage
+/// access set, which only affects gas, and the events emitted so far, which no instruction reads.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StateKey {
+    instruction: u128,
+    memory: Vec<u8>,
+    storage: Vec<([u8; 32], [u8; 32])>,
+    transient_storage: Vec<([u8; 32], [u8; 32])>,
+    calldata_size: usize,
+    address: Vec<u8>,
+    origin: Vec<u8>,
+    caller: Vec<u8>,
+    value: u128,
+    returndata: Vec<u8>,
+    call_returndata: Option<Vec<u8>>,
+    nonce: u64,
+}
+
+/// Gets the normalized state of the given VM, or `None` if the VM has contracts of its own, whose
+/// state isn't part of it.
+pub fn state_key(vm: &VM) -> Option<StateKey> {
+    if !vm.contracts.is_empty() {
+        return None
+    }
+
+    Some(StateKey {
+        instruction: vm.instruction,
+        memory: vm.memory.memory.clone(),
+        storage: sorted_slots(&vm.storage),
+        transient_storage: sorted_slots(&vm.transient_storage),
+        calldata_size: vm.calldata.len(),
+        address: vm.address.clone(),
+        origin: vm.origin.clone(),
+        caller: vm.caller.clone(),
+        value: vm.value,
+        returndata: vm.returndata.clone(),
+        call_returndata: vm.call_returndata.clone(),
+        nonce: vm.nonce,
+    })
+}
+
+/// The exact state a path begins from, which identifies the node explored from it during a single
+/// execution, where the calldata never changes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(super) struct NodeKey {
+    key: TraceKey,
+    stack: Vec<StackFrame>,
+
+    // events don't affect execution, but they're part of every traced state
+    events: Vec<(u128, Vec<U256>, Vec<u8>)>,
+}
+
+impl NodeKey {
+    pub(super) fn new(key: TraceKey, vm: &VM) -> NodeKey {
+        NodeKey {
+            key,
+            stack: vm.stack.stack.iter().cloned().collect(),
+            events: vm
+                .events
+                .iter()
+                .map(|event| (event.index, event.topics.clone(), event.data.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// The slots of the given storage, in a fixed order.
+fn sorted_slots(storage: &Storage) -> Vec<([u8; 32], [u8; 32])> {
+    let mut slots = storage.storage.iter().map(|(slot, value)| (*slot, *value)).collect::<Vec<_>>();
+    slots.sort();
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::prelude::U256;
+
+    use crate::ether::evm::{
+        core::{opcodes::WrappedOpcode, vm::new_test_vm},
+        ext::exec::{cache::state_key, ExecutionLimits, LimitReached},
+    };
+
+    #[test]
+    fn test_state_key_ignores_gas_and_stack() {
+        let vm = new_test_vm("0x00", "0x");
+        let mut other = vm.clone();
+        other.gas_used += 100;
+        other.gas_remaining -= 100;
+        assert_eq!(state_key(&vm), state_key(&other));
+
+        // the stack is matched as deep as it's read, rather than as part of the key
+        let mut pushed = vm.clone();
+        pushed.stack.push(U256::from(1), WrappedOpcode::default());
+        assert_eq!(state_key(&vm), state_key(&pushed));
+    }
+
+    #[test]
+    fn test_state_key_includes_storage() {
+        let vm = new_test_vm("0x00", "0x");
+
+        let mut stored = vm.clone();
+        stored.storage.store([1; 32], [2; 32]);
+        assert_ne!(state_key(&vm), state_key(&stored));
+    }
+
+    #[test]
+    fn test_cache_is_shared_between_clones() {
+        // if calldataload(0) { stop } else { stop }
+        let vm = new_test_vm("0x600035600757005b00", "0x");
+
+        let (trace, branches, _) = vm.clone().symbolic_exec(&ExecutionLimits::default());
+        assert_eq!(branches, 1);
+        assert!(!vm.trace_cache.is_empty());
+
+        // the branches are reused, so none are explored again
+        let (cached, branches, _) = vm.clone().symbolic_exec(&ExecutionLimits::default());
+        assert_eq!(branches, 0);
+        assert_eq!(vm.trace_cache.hits(), 1);
+        assert_eq!(cached.children.len(), trace.children.len());
+    }
+
+    #[test]
+    fn test_cache_keeps_complete_subtrees() {
+        // if calldataload(0) { if calldataload(32) { stop } else { stop } }
+        // else { if calldataload(64) { stop } else { stop } }
+        let vm = new_test_vm("0x600035600f57604035600d57005b005b602035601757005b00", "0x");
+
+        // the branch limit stops one of the inner JUMPIs, but the other is explored in full
+        let limits = ExecutionLimits { max_branches: Some(2), ..Default::default() };
+        let (_, branches, limit_reached) = vm.clone().symbolic_exec(&limits);
+        assert_eq!(branches, 2);
+        assert_eq!(limit_reached, Some(LimitReached::Branches));
+
+        // only the branches which the limit didn't cut short are cached
+        assert_eq!(vm.trace_cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_is_shared_between_selectors() {
+        // two functions push their own value, then jump to a shared helper, which reverts unless
+        // calldataload(4) is set
+        let vm = new_test_vm(
+            "0x60003560e01c8063aaaaaaaa14601b578063bbbbbbbb14602157005b60016027565b60026027565b60043560335760006000fd5b00",
+            "0x",
+        );
+
+        let (first, branches, _) =
+            vm.clone().symbolic_exec_selector("aaaaaaaa", 0x1b, &ExecutionLimits::default());
+        assert_eq!(branches, 1);
+        assert_eq!(vm.trace_cache.hits(), 0);
+
+        // the helper doesn't read the selector or the value below its stack, so its branches
+        // are only explored once
+        let (second, branches, _) =
+            vm.clone().symbolic_exec_selector("bbbbbbbb", 0x21, &ExecutionLimits::default());
+        assert_eq!(branches, 0);
+        assert_eq!(vm.trace_cache.hits(), 1);
+        assert_eq!(vm.trace_cache.len(), 1);
+        assert_eq!(second.children.len(), 2);
+        assert!(std::sync::Arc::ptr_eq(&first.children[0], &second.children[0]));
+    }
+}
@@ -0,0 +1,275 @@
+use std::{collections::HashSet, sync::Arc};
+
+use ethers::types::U256;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::ether::evm::core::{
+    opcodes::Opcode,
+    vm::{State, VM},
+};
+
+use super::VMTrace;
+
+// fuzzed calldata can drive loops for a very long time, so each execution is cut off after this
+// many steps
+const MAX_STEPS: usize = 10_000;
+
+// the number of argument words fuzzed calldata starts with, and the most it can grow to
+const INITIAL_WORDS: usize = 4;
+const MAX_WORDS: usize = 16;
+
+/// The basic blocks reached during execution, identified by the instruction each starts at. A
+/// block starts at the first instruction executed, and wherever a JUMP or JUMPI lands.
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    pub blocks: HashSet<u128>,
+}
+
+impl Coverage {
+    /// The blocks reached anywhere in the given execution tree.
+    pub fn from_trace(trace: &VMTrace) -> Coverage {
+        let mut coverage = Coverage::default();
+        let mut traces = vec![trace];
+        while let Some(trace) = traces.pop() {
+            coverage.record(&trace.operations);
+            traces.extend(trace.children.iter().map(|child| child.as_ref()));
+        }
+        coverage
+    }
+
+    /// Records the blocks reached by the given steps, returning how many hadn't been reached
+    /// before.
+    pub fn record(&mut self, states: &[State]) -> usize {
+        let mut new_blocks = 0;
+        let mut block_ended = true;
+        for state in states {
+            if block_ended && self.blocks.insert(state.last_instruction.instruction) {
+                new_blocks += 1;
+            }
+            block_ended = matches!(state.last_instruction.opcode, 0x56 | 0x57);
+        }
+        new_blocks
+    }
+}
+
+/// Executes the function with the given selector concretely, with fuzzed calldata. Calldata
+/// which reaches blocks missing from `coverage` is kept and mutated further, with arguments drawn
+/// from the constants in the bytecode as well as random values. \
+/// \
+/// Returns the steps of every execution which reached new blocks, starting from the given entry
+/// point.
+pub fn fuzz_selector(
+    vm: &VM,
+    selector: &[u8],
+    entry_point: u128,
+    runs: u32,
+    coverage: &mut Coverage,
+) -> Vec<Vec<State>> {
+    // the same function is always fuzzed with the same calldata
+    let mut rng =
+        StdRng::seed_from_u64(selector.iter().fold(0, |seed, byte| seed << 8 | *byte as u64));
+    let dictionary = dictionary(vm);
+
+    let mut corpus = vec![vec![U256::zero(); INITIAL_WORDS]];
+    let mut paths = Vec::new();
+    for run in 0..runs {
+        let words = match run {
+            0 => corpus[0].clone(),
+            _ => mutate(&corpus[rng.gen_range(0..corpus.len())], &dictionary, &mut rng),
+        };
+
+        let mut calldata = selector.to_vec();
+        for word in &words {
+            let mut bytes = [0u8; 32];
+            word.to_big_endian(&mut bytes);
+            calldata.extend_from_slice(&bytes);
+        }
+
+        let states = execute(vm, calldata, entry_point);
+        if coverage.record(&states) > 0 {
+            corpus.push(words);
+            paths.push(states);
+        }
+    }
+
+    paths
+}
+
+/// Executes the given VM with the given calldata, returning the steps executed from the entry
+/// point onwards.
+fn execute(vm: &VM, calldata: Vec<u8>, entry_point: u128) -> Vec<State> {
+    let mut vm = vm.clone();
+    vm.calldata = calldata;
+
+    let mut states = Vec::new();
+    let mut steps = 0;
+    while vm.bytecode.len() >= vm.instruction as usize && steps < MAX_STEPS {
+        let state = vm.step();
+        steps += 1;
+
+        if !states.is_empty() || state.last_instruction.instruction == entry_point {
+            states.push(state);
+        }
+        if vm.exitcode != 255 || !vm.returndata.is_empty() {
+            break
+        }
+    }
+
+    states
+}
+
+/// The values fuzzed arguments are drawn from, which are the constants pushed by the bytecode,
+/// the addresses of the execution context, and a few edge cases.
+fn dictionary(vm: &VM) -> Vec<U256> {
+    let mut values: HashSet<U256> = [U256::zero(), U256::one(), U256::from(32), U256::MAX]
+        .into_iter()
+        .chain([&vm.address, &vm.origin, &vm.caller].map(|address| U256::from_big_endian(address)))
+        .collect();
+
+    let mut program_counter = 0;
+    while program_counter < vm.bytecode.len() {
+        let opcode = Opcode::new(vm.bytecode[program_counter]);
+        if let Some(byte_count) = opcode.name.strip_prefix("PUSH") {
+            let byte_count: usize = byte_count.parse().unwrap_or(0);
+            let end = std::cmp::min(program_counter + 1 + byte_count, vm.bytecode.len());
+            values.insert(U256::from_big_endian(&vm.bytecode[program_counter + 1..end]));
+            program_counter += byte_count;
+        }
+        program_counter += 1;
+    }
+
+    // sorted, so the same bytecode is always fuzzed the same way
+    let mut values = values.into_iter().collect::<Vec<U256>>();
+    values.sort();
+    values
+}
+
+/// Returns a copy of the given arguments with a single random mutation applied.
+fn mutate(words: &[U256], dictionary: &[U256], rng: &mut StdRng) -> Vec<U256> {
+    let mut words = words.to_vec();
+    let index = rng.gen_range(0..words.len().max(1));
+
+    // most mutations draw from the dictionary, since that's where magic values come from
+    match rng.gen_range(0..8) {
+        // add an argument
+        0 if words.len() < MAX_WORDS => {
+            words.push(dictionary[rng.gen_range(0..dictionary.len())]);
+        }
+
+        // remove the last argument
+        1 if !words.is_empty() => {
+            words.pop();
+        }
+
+        // replace an argument with a random value
+        2 if !words.is_empty() => {
+            words[index] = U256::from_big_endian(&rng.gen::<[u8; 32]>());
+        }
+
+        // flip a single bit of an argument
+        3 if !words.is_empty() => {
+            words[index] ^= U256::one() << rng.gen_range(0..256);
+        }
+
+        // nudge an argument up or down
+        4 if !words.is_empty() => {
+            let delta = U256::from(rng.gen_range(1..=16));
+            words[index] = match rng.gen::<bool>() {
+                true => words[index].overflowing_add(delta).0,
+                false => words[index].overflowing_sub(delta).0,
+            };
+        }
+
+        // replace an argument with a value from the dictionary
+        _ if !words.is_empty() => {
+            words[index] = dictionary[rng.gen_range(0..dictionary.len())];
+        }
+        _ => words.push(dictionary[rng.gen_range(0..dictionary.len())]),
+    }
+
+    words
+}
+
+/// Adds the given steps to the execution tree, branching off wherever they leave the paths it
+/// already contains. Returns whether the tree changed, which it won't if the steps don't start at
+/// the root of the tree or follow a path already in it.
+pub(super) fn graft(trace: &mut VMTrace, states: &[State]) -> bool {
+    let shared = trace
+        .operations
+        .iter()
+        .zip(states)
+        .take_while(|(operation, state)| {
+            operation.last_instruction.instruction == state.last_instruction.instruction
+        })
+        .count();
+    if shared == 0 || shared == states.len() {
+        return false
+    }
+
+    // the steps leave this node partway through, so the node is split where they do
+    if shared < trace.operations.len() {
+        let operations = trace.operations.split_off(shared);
+        let tail = VMTrace {
+            instruction: operations[0].last_instruction.instruction,
+            gas_used: trace.gas_used,
+            operations,
+            children: std::mem::take(&mut trace.children),
+        };
+        trace.gas_used = trace.operations[shared - 1].gas_used;
+        trace.children.push(Arc::new(tail));
+    }
+
+    let rest = &states[shared..];
+    let instruction = rest[0].last_instruction.instruction;
+    match trace.children.iter_mut().find(|child| child.instruction == instruction) {
+        Some(child) => graft(Arc::make_mut(child), rest),
+        None => {
+            trace.children.push(Arc::new(VMTrace {
+                instruction,
+                gas_used: rest[rest.len() - 1].gas_used,
+                operations: rest.to_vec(),
+                children: Vec::new(),
+            }));
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::{
+        core::vm::new_test_vm,
+        ext::exec::{
+            fuzz::{fuzz_selector, Coverage},
+            ExecutionLimits,
+        },
+    };
+
+    #[test]
+    fn test_fuzzing_finds_magic_values() {
+        // if calldataload(4) == 0x1337 { stop } else { revert }
+        let vm = new_test_vm("0x61133760043514600f5760006000fd5b00", "0x");
+
+        let mut coverage = Coverage::default();
+        let paths = fuzz_selector(&vm, &[0; 4], 1, 100, &mut coverage);
+
+        // the magic value is drawn from the bytecode, reaching the JUMPDEST at pc 0x0f
+        assert!(coverage.blocks.contains(&0x10));
+        assert_eq!(paths.len(), 2);
+    }
+
+    #[test]
+    fn test_fuzzing_supplements_symbolic_execution() {
+        // jump(calldataload(4)), with a JUMPDEST at pc 5 that only a concrete argument reaches
+        let mut vm = new_test_vm("0x60043556005b60055000", "0x");
+
+        let (trace, ..) =
+            vm.clone().symbolic_exec_selector("00000000", 0, &ExecutionLimits::default());
+        assert!(trace.children.is_empty());
+
+        let limits = ExecutionLimits { fuzz_runs: Some(100), ..Default::default() };
+        let (trace, ..) = vm.symbolic_exec_selector("00000000", 0, &limits);
+        assert_eq!(trace.children.len(), 1);
+        assert_eq!(trace.children[0].instruction, 6);
+    }
+}
@@ -1,81 +1,354 @@
+pub mod cache;
+pub mod fuzz;
+#[cfg(feature = "z3")]
+pub mod solver;
 mod util;
 
-use self::util::{
-    jump_condition_appears_recursive, jump_condition_contains_mutated_memory_access,
-    jump_condition_contains_mutated_storage_access,
-    jump_condition_historical_diffs_approximately_equal, stack_contains_too_many_of_the_same_item,
-    stack_diff, stack_item_source_depth_too_deep,
+use self::{
+    cache::{state_key, NodeKey, TraceKey},
+    fuzz::{fuzz_selector, graft, Coverage},
+    util::{
+        jump_condition_appears_recursive, jump_condition_contains_mutated_memory_access,
+        jump_condition_contains_mutated_storage_access,
+        jump_condition_historical_diffs_approximately_equal,
+        stack_contains_too_many_of_the_same_item, stack_diff, stack_item_source_depth_too_deep,
+    },
 };
 use crate::{
     ether::evm::core::{
+        fault::Fault,
+        opcodes::WrappedOpcode,
         stack::Stack,
         vm::{State, VM},
     },
-    utils::{io::logging::Logger, strings::decode_hex},
+    utils::strings::decode_hex,
 };
+use clap::Args;
 use ethers::types::U256;
-use std::collections::HashMap;
+use lazy_static::lazy_static;
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::{instrument, trace};
 
 #[derive(Clone, Debug)]
 pub struct VMTrace {
     pub instruction: u128,
     pub gas_used: u128,
     pub operations: Vec<State>,
-    pub children: Vec<VMTrace>,
+    pub children: Vec<Arc<VMTrace>>,
 }
 
-impl VM {
-    /// Run symbolic execution on a given function selector within a contract
-    pub fn symbolic_exec_selector(&mut self, selector: &str, entry_point: u128) -> (VMTrace, u32) {
-        self.calldata = decode_hex(selector).unwrap();
+impl VMTrace {
+    /// The faults which ended any path through the execution tree, without duplicates.
+    pub fn faults(&self) -> Vec<Fault> {
+        let mut faults = Vec::new();
+        let mut traces = vec![self];
+        while let Some(trace) = traces.pop() {
+            if let Some(fault) = trace.operations.last().and_then(|state| state.fault.as_ref()) {
+                if !faults.contains(fault) {
+                    faults.push(fault.clone());
+                }
+            }
+            traces.extend(trace.children.iter().rev().map(|child| child.as_ref()));
+        }
+        faults
+    }
+}
 
-        // step through the bytecode until we reach the entry point
-        while self.bytecode.len() >= self.instruction as usize && (self.instruction <= entry_point)
-        {
-            self.step();
+// the limits on each function's symbolic execution when heimdall isn't interactive, if none are set
+const NON_INTERACTIVE_MAX_BRANCHES: u32 = 10_000;
+const NON_INTERACTIVE_TIMEOUT_MS: u64 = 60_000;
+
+/// Limits on how much of a contract symbolic execution will explore. Once a limit is reached,
+/// exploration stops and the paths discovered so far are returned. Every limit is unset by
+/// default.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Args, Serialize, Deserialize)]
+pub struct ExecutionLimits {
+    /// The maximum number of branches to explore per function.
+    #[clap(long = "max-branches")]
+    pub max_branches: Option<u32>,
+
+    /// The maximum number of nested branches to follow along a single path.
+    #[clap(long = "max-depth")]
+    pub max_depth: Option<usize>,
+
+    /// The maximum number of times a single JUMPI is revisited along a path before it's
+    /// treated as a loop.
+    #[clap(long = "max-loop-iterations")]
+    pub max_loop_iterations: Option<usize>,
+
+    /// The maximum time to spend executing each function, in milliseconds.
+    #[clap(long = "exec-timeout", value_name = "MS")]
+    pub timeout: Option<u64>,
+
+    /// The number of concrete executions with fuzzed calldata to run per function after symbolic
+    /// execution. Paths which reach code symbolic execution missed, such as code behind hash or
+    /// signature checks, are added to its execution tree.
+    #[clap(long = "fuzz-runs")]
+    pub fuzz_runs: Option<u32>,
+}
 
-            // this shouldn't be necessary, but it's safer to have it
-            if self.exitcode != 255 || !self.returndata.is_empty() {
-                break
-            }
+impl ExecutionLimits {
+    /// Fill any unset limits from `defaults`, such as the limits in the configuration file.
+    pub fn with_defaults(self, defaults: &ExecutionLimits) -> ExecutionLimits {
+        ExecutionLimits {
+            max_branches: self.max_branches.or(defaults.max_branches),
+            max_depth: self.max_depth.or(defaults.max_depth),
+            max_loop_iterations: self.max_loop_iterations.or(defaults.max_loop_iterations),
+            timeout: self.timeout.or(defaults.timeout),
+            fuzz_runs: self.fuzz_runs.or(defaults.fuzz_runs),
         }
+    }
 
-        // get a new logger
-        let logger = Logger::default();
+    /// The limits used when heimdall isn't interactive, such as with `--ci`, if none are set, so
+    /// symbolic execution always finishes with partial results rather than running indefinitely.
+    ///
+    /// ```
+    /// use heimdall_common::ether::evm::ext::exec::ExecutionLimits;
+    ///
+    /// let limits = ExecutionLimits { timeout: Some(5_000), ..Default::default() }
+    ///     .with_defaults(&ExecutionLimits::non_interactive());
+    /// assert_eq!(limits.timeout, Some(5_000));
+    /// assert!(limits.max_branches.is_some());
+    /// ```
+    pub fn non_interactive() -> ExecutionLimits {
+        ExecutionLimits {
+            max_branches: Some(NON_INTERACTIVE_MAX_BRANCHES),
+            timeout: Some(NON_INTERACTIVE_TIMEOUT_MS),
+            ..Default::default()
+        }
+    }
+}
 
-        logger.debug_max(&format!("beginning symbolic execution for selector 0x{}", selector));
+/// The [`ExecutionLimits`] limit which stopped symbolic execution early.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitReached {
+    Branches,
+    Depth,
+    LoopIterations,
+    Timeout,
+}
 
-        // the VM is at the function entry point, begin tracing
-        let mut branch_count = 0;
-        (self.recursive_map(&mut branch_count, &mut HashMap::new(), &logger), branch_count)
+impl Display for LimitReached {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitReached::Branches => write!(f, "branch limit"),
+            LimitReached::Depth => write!(f, "depth limit"),
+            LimitReached::LoopIterations => write!(f, "loop iteration limit"),
+            LimitReached::Timeout => write!(f, "timeout"),
+        }
     }
+}
 
-    // build a map of function jump possibilities from the EVM bytecode
-    pub fn symbolic_exec(&self) -> (VMTrace, u32) {
-        let mut vm = self.clone();
+lazy_static! {
+    // execution trees are linked recursively, so worker threads need a larger stack than the
+    // default
+    static ref THREAD_POOL: ThreadPool = ThreadPoolBuilder::new()
+        .stack_size(64 * 1024 * 1024)
+        .build()
+        .expect("failed to build symbolic execution thread pool");
+}
 
-        // get a new logger
-        let logger = Logger::default();
+// the instruction of a JUMPI, its destination, the stack size, and whether its condition is zero
+type JumpFrame = (u128, U256, usize, bool);
+
+/// A path which is yet to be explored, starting from one of the branches of a JUMPI.
+struct Pending {
+    vm: VM,
+    path: Path,
+    // the jump condition of the branch, and whether it's taken
+    branch: Option<(WrappedOpcode, bool)>,
+    // the node of the JUMPI this path branches from
+    parent: Option<usize>,
+    depth: usize,
+}
 
-        logger.debug_max("beginning contract-wide symbolic execution");
+/// A [`Pending`] path, after it's traced up to its next JUMPI.
+enum Traced {
+    /// The branch can never be taken.
+    Infeasible,
+
+    /// The path was traced until it reached a JUMPI, which is the last of its operations, or
+    /// until the VM exited.
+    Path {
+        vm: Box<VM>,
+        path: Path,
+        key: Option<Box<NodeKey>>,
+        trace: VMTrace,
+        jump: Option<Box<Jump>>,
+    },
+}
 
-        // the VM is at the function entry point, begin tracing
-        let mut branch_count = 0;
-        (vm.recursive_map(&mut branch_count, &mut HashMap::new(), &logger), branch_count)
+/// The JUMPI a traced path stopped at.
+struct Jump {
+    state: State,
+
+    // the key and stack of the state before the JUMPI, if its branches can be cached
+    key: Option<(TraceKey, Stack)>,
+
+    // the branches explored from a matching state before, if any
+    cached: Option<Vec<Arc<VMTrace>>>,
+}
+
+/// A node of the execution tree, which are linked together once exploration finishes. Each node
+/// is complete unless a limit stopped it, or any node below it, from being explored in full.
+enum Node {
+    Traced {
+        trace: VMTrace,
+        children: Vec<usize>,
+        key: Option<Box<(TraceKey, Stack)>>,
+        complete: bool,
+    },
+    Linked(Arc<VMTrace>, bool),
+    Linking,
+}
+
+/// The state of a single symbolic execution. Paths are explored a level at a time: every pending
+/// path is traced up to its next JUMPI in parallel, and then whether to follow each of their
+/// branches is decided in order, so the loop history is the same on every run.
+struct Exploration<'a> {
+    branch_count: u32,
+    handled_jumps: HashMap<JumpFrame, Vec<Stack>>,
+    limits: &'a ExecutionLimits,
+    start: Instant,
+    limit_reached: Option<LimitReached>,
+}
+
+impl<'a> Exploration<'a> {
+    fn new(limits: &'a ExecutionLimits) -> Self {
+        Exploration {
+            branch_count: 0,
+            handled_jumps: HashMap::new(),
+            limits,
+            start: Instant::now(),
+            limit_reached: None,
+        }
     }
 
-    fn recursive_map(
-        &mut self,
-        branch_count: &mut u32,
-        handled_jumps: &mut HashMap<(u128, U256, usize, bool), Vec<Stack>>,
-        logger: &Logger,
-    ) -> VMTrace {
-        let mut vm = self.clone();
+    /// Runs symbolic execution from the given VM on the thread pool, returning the execution
+    /// tree, the number of branches explored, and the limit which stopped execution early, if
+    /// any.
+    fn run(mut self, vm: &VM) -> (VMTrace, u32, Option<LimitReached>) {
+        let vm_trace = THREAD_POOL.install(|| {
+            let mut nodes: Vec<Node> = Vec::new();
+
+            // paths which reach the same state share the node explored from it
+            let mut explored: HashMap<NodeKey, usize> = HashMap::new();
+
+            let mut pending = vec![Pending {
+                vm: vm.clone(),
+                path: Path::default(),
+                branch: None,
+                parent: None,
+                depth: 0,
+            }];
+            while !pending.is_empty() {
+                // trace every pending path up to its next JUMPI in parallel
+                let traced = pending
+                    .into_par_iter()
+                    .map(|pending| (pending.parent, pending.depth, self.trace(pending)))
+                    .collect::<Vec<_>>();
+
+                // then decide their branches in order, linking each to the node it branched from
+                pending = Vec::new();
+                for (parent, depth, traced) in traced {
+                    let node = match traced {
+                        Traced::Infeasible => continue,
+                        Traced::Path { vm, path, key, trace, jump } => {
+                            match key.as_deref().and_then(|key| explored.get(key)) {
+                                Some(node) => *node,
+                                None => {
+                                    let node = nodes.len();
+                                    if let Some(key) = key {
+                                        explored.insert(*key, node);
+                                    }
+
+                                    // reuse the branches explored from a matching state, if any,
+                                    // or else decide whether to explore them
+                                    let mut complete = true;
+                                    let mut cache_key = None;
+                                    let mut cached = Vec::new();
+                                    if let Some(jump) = jump {
+                                        let Jump { state, key, cached: children } = *jump;
+                                        match children {
+                                            Some(children) => cached = children,
+                                            None => {
+                                                cache_key = key.map(Box::new);
+                                                match self.branch(*vm, path, &state, node, depth) {
+                                                    Ok(branches) => pending.extend(branches),
+                                                    Err(_) => complete = false,
+                                                }
+                                            }
+                                        }
+                                    }
+                                    nodes.push(Node::Traced {
+                                        trace,
+                                        children: (node + 1..node + 1 + cached.len()).collect(),
+                                        key: cache_key,
+                                        complete,
+                                    });
+                                    nodes.extend(
+                                        cached.into_iter().map(|child| Node::Linked(child, true)),
+                                    );
+                                    node
+                                }
+                            }
+                        }
+                    };
+
+                    if let Some(Node::Traced { children, .. }) =
+                        parent.map(|parent| &mut nodes[parent])
+                    {
+                        children.push(node);
+                    }
+                }
+            }
+
+            self.link(&mut nodes, 0, vm)
+        });
+
+        (
+            vm_trace.map(|(trace, _)| Arc::unwrap_or_clone(trace)).unwrap_or_else(|| VMTrace {
+                instruction: vm.instruction,
+                gas_used: 21000,
+                operations: Vec::new(),
+                children: Vec::new(),
+            }),
+            self.branch_count,
+            self.limit_reached,
+        )
+    }
+
+    /// Traces the given path until it reaches a JUMPI or the VM exits, unless its branch can
+    /// never be taken. The branches of the JUMPI are taken from the cache if they were explored
+    /// from a matching state before.
+    fn trace(&self, pending: Pending) -> Traced {
+        let Pending { mut vm, path, branch, .. } = pending;
+
+        let path = match branch {
+            Some((condition, taken)) => match path.branch(&condition, taken) {
+                Some(path) => path,
+                None => {
+                    trace!("pruned infeasible branch at instruction {}", vm.instruction);
+                    return Traced::Infeasible
+                }
+            },
+            None => path,
+        };
+
+        let key = self.cache_key(&vm, &path).map(|key| Box::new(NodeKey::new(key, &vm)));
 
         // create a new VMTrace object
         // this will essentially be a tree of executions, with each branch being a different path
         // that symbolic execution discovered
-        let mut vm_trace = VMTrace {
+        let mut trace = VMTrace {
             instruction: vm.instruction,
             gas_used: 21000,
             operations: Vec::new(),
@@ -84,190 +357,417 @@ impl VM {
 
         // step through the bytecode until we find a JUMPI instruction
         while vm.bytecode.len() >= vm.instruction as usize {
+            // the branches of a JUMPI are cached by the state before it
+            let jump_key = match vm.instruction.checked_sub(1).map(|i| vm.bytecode[i as usize]) {
+                Some(0x57) => self.cache_key(&vm, &path),
+                _ => None,
+            };
+            let cached = jump_key.as_ref().and_then(|key| vm.trace_cache.get(key, &vm));
+            let jump_key = jump_key.map(|key| (key, vm.stack.clone()));
+
             let state = vm.step();
 
             // update vm_trace
-            vm_trace.operations.push(state.clone());
-            vm_trace.gas_used = vm.gas_used;
+            trace.operations.push(state.clone());
+            trace.gas_used = vm.gas_used;
 
-            // if we encounter a JUMPI, create children taking both paths and break
             if state.last_instruction.opcode == 0x57 {
-                logger.debug_max(&format!(
+                trace!(
                     "found branch due to JUMPI instruction at {}",
                     state.last_instruction.instruction
-                ));
-
-                // jump frame contains:
-                //  1. the instruction (PC) of the JUMPI
-                //  2. the jump destination
-                //  3. the stack size at the time of the JUMPI
-                //  4. whether the jump condition is zero
-                let jump_frame: (u128, U256, usize, bool) = (
-                    state.last_instruction.instruction,
-                    state.last_instruction.inputs[0],
-                    vm.stack.size(),
-                    state.last_instruction.inputs[1].is_zero(),
                 );
-
-                // if the stack has over 16 items of the same source, it's probably a loop
-                if stack_contains_too_many_of_the_same_item(&vm.stack) {
-                    return vm_trace
+                if cached.is_some() {
+                    trace!("reusing cached branches at instruction {}", vm.instruction);
                 }
-
-                // if any item on the stack has a depth > 16, it's probably a loop (because of stack
-                // too deep)
-                if stack_item_source_depth_too_deep(&vm.stack) {
-                    return vm_trace
+                return Traced::Path {
+                    vm: Box::new(vm),
+                    path,
+                    key,
+                    trace,
+                    jump: Some(Box::new(Jump { state, key: jump_key, cached })),
                 }
+            }
 
-                // break out of loops
-                match handled_jumps.get_mut(&jump_frame) {
-                    Some(historical_stacks) => {
-                        // for every stack that we have encountered for this jump, perform some
-                        // heuristic checks to determine if this might be a loop
-                        if historical_stacks.iter().any(|hist_stack| {
-                            // get a solidity repr of the jump condition
-                            let jump_condition =
-                                state.last_instruction.input_operations[1].solidify();
-
-                            // check if any historical stack is the same as the current stack
-                            if hist_stack == &vm.stack {
-                                logger.debug_max(
-                                    "jump matches loop-detection heuristic: 'jump_path_already_handled'"
-                                );
-                                return true
-                            }
+            // when the vm exits, this path is complete
+            if vm.exitcode != 255 || !vm.returndata.is_empty() {
+                break
+            }
+        }
 
-                            // calculate the difference of the current stack and the historical stack
-                            let stack_diff = stack_diff(&vm.stack, hist_stack);
-                            if stack_diff.is_empty() {
-                                // the stack_diff is empty (the stacks are the same), so we've
-                                // already handled this path
-                                logger.debug_max(
-                                    "jump matches loop-detection heuristic: 'stack_diff_is_empty'"
-                                );
-                                return true
-                            }
+        Traced::Path { vm: Box::new(vm), path, key, trace, jump: None }
+    }
 
-                            logger.debug_max(&format!("stack diff: [{}]", stack_diff.iter().map(|frame| format!("{}", frame.value)).collect::<Vec<String>>().join(", ")));
+    /// Decides whether to follow the branches of the JUMPI the given VM stopped at, which is the
+    /// last operation of the given node, returning both branches if so, or the limit which
+    /// stopped them from being explored.
+    fn branch(
+        &mut self,
+        vm: VM,
+        path: Path,
+        state: &State,
+        node: usize,
+        depth: usize,
+    ) -> Result<Vec<Pending>, LimitReached> {
+        // jump frame contains:
+        //  1. the instruction (PC) of the JUMPI
+        //  2. the jump destination
+        //  3. the stack size at the time of the JUMPI
+        //  4. whether the jump condition is zero
+        let jump_frame: JumpFrame = (
+            state.last_instruction.instruction,
+            state.last_instruction.inputs[0],
+            vm.stack.size(),
+            state.last_instruction.inputs[1].is_zero(),
+        );
+
+        // stop exploring once any of the configured limits are reached
+        if let Some(limit) = self.check_limits(depth) {
+            trace!("jump terminated: reached the {}.", limit);
+            return Err(limit)
+        }
 
-                            // check if the jump condition appears to be recursive
-                            if jump_condition_appears_recursive(&stack_diff, &jump_condition) {
-                                return true
-                            }
+        // if the stack has over 16 items of the same source, it's probably a loop
+        if stack_contains_too_many_of_the_same_item(&vm.stack) {
+            return Ok(Vec::new())
+        }
 
-                            // check for mutated memory accesses in the jump condition
-                            if jump_condition_contains_mutated_memory_access(
-                                &stack_diff,
-                                &jump_condition,
-                            ) {
-                                return true
-                            }
+        // if any item on the stack has a depth > 16, it's probably a loop (because of stack
+        // too deep)
+        if stack_item_source_depth_too_deep(&vm.stack) {
+            return Ok(Vec::new())
+        }
 
-                            // check for mutated memory accesses in the jump condition
-                            if jump_condition_contains_mutated_storage_access(
-                                &stack_diff,
-                                &jump_condition,
-                            ) {
-                                return true
-                            }
+        // break out of loops
+        match self.handled_jumps.get_mut(&jump_frame) {
+            Some(historical_stacks) => {
+                // every historical stack is a previous visit to this jump
+                if self.limits.max_loop_iterations.is_some_and(|max| historical_stacks.len() >= max)
+                {
+                    trace!("jump terminated: reached the {}.", LimitReached::LoopIterations);
+                    self.set_limit_reached(LimitReached::LoopIterations);
+                    return Err(LimitReached::LoopIterations)
+                }
 
-                            false
-                        }) {
-                            logger.debug_max("jump terminated.");
-                            logger.debug_max(&format!(
-                                "adding historical stack {} to jump frame {:?}",
-                                &format!("{:#016x?}", vm.stack.hash()),
-                                jump_frame
-                            ));
-
-                            // this key exists, but the stack is different, so the jump is new
-                            historical_stacks.push(vm.stack.clone());
-                            return vm_trace
-                        }
+                // for every stack that we have encountered for this jump, perform some
+                // heuristic checks to determine if this might be a loop
+                if historical_stacks.iter().any(|hist_stack| {
+                    // get a solidity repr of the jump condition
+                    let jump_condition = state.last_instruction.input_operations[1].solidify();
+
+                    // check if any historical stack is the same as the current stack
+                    if hist_stack == &vm.stack {
+                        trace!(
+                            "jump matches loop-detection heuristic: 'jump_path_already_handled'"
+                        );
+                        return true
+                    }
 
-                        if jump_condition_historical_diffs_approximately_equal(
-                            &vm.stack,
-                            historical_stacks,
-                        ) {
-                            logger.debug_max("jump terminated.");
-                            logger.debug_max(&format!(
-                                "adding historical stack {} to jump frame {:?}",
-                                &format!("{:#016x?}", vm.stack.hash()),
-                                jump_frame
-                            ));
-
-                            // this key exists, but the stack is different, so the jump is new
-                            historical_stacks.push(vm.stack.clone());
-                            return vm_trace
-                        } else {
-                            logger.debug_max(&format!(
-                                "adding historical stack {} to jump frame {:?}",
-                                &format!("{:#016x?}", vm.stack.hash()),
-                                jump_frame
-                            ));
-                            logger.debug_max(&format!(
-                                " - jump condition: {}\n        - stack: {}\n        - historical stacks: {}",
-                                state.last_instruction.input_operations[1].solidify(),
-                                vm.stack,
-                                historical_stacks.iter().map(|stack| format!("{}", stack)).collect::<Vec<String>>().join("\n            - ")
-                            ));
-
-                            // this key exists, but the stack is different, so the jump is new
-                            historical_stacks.push(vm.stack.clone());
-                        }
+                    // calculate the difference of the current stack and the historical stack
+                    let stack_diff = stack_diff(&vm.stack, hist_stack);
+                    if stack_diff.is_empty() {
+                        // the stack_diff is empty (the stacks are the same), so we've
+                        // already handled this path
+                        trace!("jump matches loop-detection heuristic: 'stack_diff_is_empty'");
+                        return true
+                    }
+
+                    trace!(
+                        "stack diff: [{}]",
+                        stack_diff
+                            .iter()
+                            .map(|frame| format!("{}", frame.value))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    );
+
+                    // check if the jump condition appears to be recursive
+                    if jump_condition_appears_recursive(&stack_diff, &jump_condition) {
+                        return true
                     }
-                    None => {
-                        // this key doesnt exist, so the jump is new
-                        logger.debug_max(&format!("added new jump frame: {:?}", jump_frame));
-                        handled_jumps.insert(jump_frame, vec![vm.stack.clone()]);
+
+                    // check for mutated memory accesses in the jump condition
+                    if jump_condition_contains_mutated_memory_access(&stack_diff, &jump_condition) {
+                        return true
+                    }
+
+                    // check for mutated memory accesses in the jump condition
+                    if jump_condition_contains_mutated_storage_access(&stack_diff, &jump_condition)
+                    {
+                        return true
                     }
+
+                    false
+                }) {
+                    trace!("jump terminated.");
+                    trace!(
+                        "adding historical stack {} to jump frame {:?}",
+                        &format!("{:#016x?}", vm.stack.hash()),
+                        jump_frame
+                    );
+
+                    // this key exists, but the stack is different, so the jump is new
+                    historical_stacks.push(vm.stack.clone());
+                    return Ok(Vec::new())
                 }
 
-                // we didnt break out, so now we crate branching paths to cover all possibilities
-                *branch_count += 1;
-                logger.debug_max(&format!(
-                    "creating branching paths at instructions {} (JUMPDEST) and {} (CONTINUE)",
-                    state.last_instruction.inputs[0],
-                    state.last_instruction.instruction + 1
-                ));
-
-                // we need to create a trace for the path that wasn't taken.
-                if state.last_instruction.inputs[1].is_zero() {
-                    // push a new vm trace to the children
-                    let mut trace_vm = vm.clone();
-                    trace_vm.instruction = state.last_instruction.inputs[0].as_u128() + 1;
-                    vm_trace.children.push(trace_vm.recursive_map(
-                        branch_count,
-                        handled_jumps,
-                        logger,
-                    ));
-
-                    // push the current path onto the stack
-                    vm_trace.children.push(vm.recursive_map(branch_count, handled_jumps, logger));
-                    break
+                if jump_condition_historical_diffs_approximately_equal(&vm.stack, historical_stacks)
+                {
+                    trace!("jump terminated.");
+                    trace!(
+                        "adding historical stack {} to jump frame {:?}",
+                        &format!("{:#016x?}", vm.stack.hash()),
+                        jump_frame
+                    );
+
+                    // this key exists, but the stack is different, so the jump is new
+                    historical_stacks.push(vm.stack.clone());
+                    return Ok(Vec::new())
                 } else {
-                    // push a new vm trace to the children
-                    let mut trace_vm = vm.clone();
-                    trace_vm.instruction = state.last_instruction.instruction + 1;
-                    vm_trace.children.push(trace_vm.recursive_map(
-                        branch_count,
-                        handled_jumps,
-                        logger,
-                    ));
-
-                    // push the current path onto the stack
-                    vm_trace.children.push(vm.recursive_map(branch_count, handled_jumps, logger));
-                    break
+                    trace!(
+                        "adding historical stack {} to jump frame {:?}",
+                        &format!("{:#016x?}", vm.stack.hash()),
+                        jump_frame
+                    );
+                    trace!(
+                        " - jump condition: {}\n        - stack: {}\n        - historical stacks: {}",
+                        state.last_instruction.input_operations[1].solidify(),
+                        vm.stack,
+                        historical_stacks.iter().map(|stack| format!("{}", stack)).collect::<Vec<String>>().join("\n            - ")
+                    );
+
+                    // this key exists, but the stack is different, so the jump is new
+                    historical_stacks.push(vm.stack.clone());
                 }
             }
+            None => {
+                // this key doesnt exist, so the jump is new
+                trace!("added new jump frame: {:?}", jump_frame);
+                self.handled_jumps.insert(jump_frame, vec![vm.stack.clone()]);
+            }
+        }
 
-            // when the vm exits, this path is complete
-            if vm.exitcode != 255 || !vm.returndata.is_empty() {
+        // we didnt break out, so now we crate branching paths to cover all possibilities
+        self.branch_count += 1;
+        trace!(
+            "creating branching paths at instructions {} (JUMPDEST) and {} (CONTINUE)",
+            state.last_instruction.inputs[0],
+            state.last_instruction.instruction + 1
+        );
+
+        // we need to create a trace for the path that wasn't taken, as well as the
+        // current path, skipping either if it can never be reached
+        let condition = &state.last_instruction.input_operations[1];
+        let jumped = !state.last_instruction.inputs[1].is_zero();
+        let mut trace_vm = vm.clone();
+        trace_vm.instruction = if jumped {
+            state.last_instruction.instruction + 1
+        } else {
+            state.last_instruction.inputs[0].as_u128() + 1
+        };
+
+        Ok([(trace_vm, !jumped), (vm, jumped)]
+            .into_iter()
+            .map(|(vm, taken)| Pending {
+                vm,
+                path: path.clone(),
+                branch: Some((condition.clone(), taken)),
+                parent: Some(node),
+                depth: depth + 1,
+            })
+            .collect())
+    }
+
+    /// Links the node with the given index and every node below it into an execution tree,
+    /// returning it and whether it's complete, and caching its branches if so. A link back to a
+    /// node which is still being linked would make a cycle, so it's dropped, leaving the tree
+    /// incomplete.
+    fn link(&self, nodes: &mut [Node], index: usize, vm: &VM) -> Option<(Arc<VMTrace>, bool)> {
+        let (trace, complete) = match std::mem::replace(&mut nodes[index], Node::Linking) {
+            Node::Traced { mut trace, children, key, mut complete } => {
+                for child in children {
+                    match self.link(nodes, child, vm) {
+                        Some((child, child_complete)) => {
+                            trace.children.push(child);
+                            complete &= child_complete;
+                        }
+                        None => complete = false,
+                    }
+                }
+                // branches cut short by a limit are incomplete, so they can't be reused
+                if let (Some(key), Some(jump), true) = (key, trace.operations.last(), complete) {
+                    let (key, stack) = *key;
+                    vm.trace_cache.insert(key, &stack, &vm.calldata, jump, &trace.children);
+                }
+                let trace = Arc::new(trace);
+                (trace, complete)
+            }
+            Node::Linked(trace, complete) => (trace, complete),
+            Node::Linking => return None,
+        };
+
+        nodes[index] = Node::Linked(trace.clone(), complete);
+        Some((trace, complete))
+    }
+
+    /// Records that the given limit was reached. The branch limit and timeout take precedence,
+    /// since they stop every remaining path.
+    fn set_limit_reached(&mut self, limit: LimitReached) {
+        match limit {
+            LimitReached::Branches | LimitReached::Timeout => self.limit_reached = Some(limit),
+            _ => {
+                self.limit_reached.get_or_insert(limit);
+            }
+        }
+    }
+
+    /// Checks the limits which apply to every path, returning the one reached, if any. The
+    /// branch limit and timeout stop all remaining paths, while the depth limit only stops the
+    /// current one.
+    fn check_limits(&mut self, depth: usize) -> Option<LimitReached> {
+        if let Some(limit @ (LimitReached::Branches | LimitReached::Timeout)) = self.limit_reached {
+            return Some(limit)
+        }
+
+        let limit = if self
+            .limits
+            .timeout
+            .is_some_and(|timeout| self.start.elapsed() >= Duration::from_millis(timeout))
+        {
+            LimitReached::Timeout
+        } else if self.limits.max_branches.is_some_and(|max| self.branch_count >= max) {
+            LimitReached::Branches
+        } else if self.limits.max_depth.is_some_and(|max| depth >= max) {
+            LimitReached::Depth
+        } else {
+            return None
+        };
+
+        self.set_limit_reached(limit);
+        Some(limit)
+    }
+
+    /// The key of the given VM's state along the given path, or `None` if what's explored from
+    /// it can't be cached or shared. Trees explored under a depth limit depend on how deep they
+    /// start, so they're never cached, and neither are trees explored while tracking taint, whose
+    /// sinks are only recorded when they're explored.
+    fn cache_key(&self, vm: &VM, path: &Path) -> Option<TraceKey> {
+        if self.limits.max_depth.is_some() || vm.taint.is_some() {
+            return None
+        }
+
+        Some((state_key(vm)?, path.clone()))
+    }
+}
+
+/// The conditions of the jumps along a single path, and whether each was taken.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct Path {
+    #[cfg(feature = "z3")]
+    conditions: Vec<(WrappedOpcode, bool)>,
+}
+
+impl Path {
+    /// Returns the path which continues through the branch where the given jump condition is
+    /// `taken`, or `None` if that branch can never be reached.
+    #[cfg(feature = "z3")]
+    fn branch(&self, condition: &WrappedOpcode, taken: bool) -> Option<Path> {
+        let mut conditions = self.conditions.clone();
+        conditions.push((condition.clone(), taken));
+        solver::is_satisfiable(&conditions).then_some(Path { conditions })
+    }
+
+    /// Without the `z3` feature, every branch is assumed to be reachable.
+    #[cfg(not(feature = "z3"))]
+    fn branch(&self, _condition: &WrappedOpcode, _taken: bool) -> Option<Path> {
+        Some(Path {})
+    }
+}
+
+impl VM {
+    /// Run symbolic execution on a given function selector within a contract, returning the
+    /// execution tree, the number of branches explored, and the limit which stopped execution
+    /// early, if any.
+    #[instrument(level = "debug", skip(self, limits))]
+    pub fn symbolic_exec_selector(
+        &mut self,
+        selector: &str,
+        entry_point: u128,
+        limits: &ExecutionLimits,
+    ) -> (VMTrace, u32, Option<LimitReached>) {
+        self.calldata = decode_hex(selector).unwrap();
+        let fuzz_vm = limits.fuzz_runs.map(|_| self.clone());
+
+        // step through the bytecode until we reach the entry point
+        while self.bytecode.len() >= self.instruction as usize && (self.instruction <= entry_point)
+        {
+            self.step();
+
+            // this shouldn't be necessary, but it's safer to have it
+            if self.exitcode != 255 || !self.returndata.is_empty() {
                 break
             }
         }
 
-        vm_trace
+        trace!("beginning symbolic execution for selector 0x{}", selector);
+
+        // the VM is at the function entry point, begin tracing
+        let (mut vm_trace, branch_count, limit_reached) = Exploration::new(limits).run(self);
+
+        // supplement the execution tree with any paths concrete execution finds
+        if let (Some(runs), Some(vm)) = (limits.fuzz_runs, fuzz_vm) {
+            let mut coverage = Coverage::from_trace(&vm_trace);
+            let paths = fuzz_selector(&vm, &vm.calldata, vm_trace.instruction, runs, &mut coverage);
+            let grafted = paths.iter().filter(|path| graft(&mut vm_trace, path)).count();
+            trace!("concrete execution of selector 0x{} found {} new paths", selector, grafted);
+        }
+
+        (vm_trace, branch_count, limit_reached)
+    }
+
+    // build a map of function jump possibilities from the EVM bytecode
+    #[instrument(level = "debug", skip_all)]
+    pub fn symbolic_exec(&self, limits: &ExecutionLimits) -> (VMTrace, u32, Option<LimitReached>) {
+        trace!("beginning contract-wide symbolic execution");
+
+        // the VM is at the function entry point, begin tracing
+        Exploration::new(limits).run(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::{core::vm::new_test_vm, ext::exec::ExecutionLimits};
+
+    #[test]
+    fn test_exploration_is_deterministic() {
+        // if calldataload(0) { sstore(0, 1) }, then both paths reach if calldataload(32) { stop }
+        // with the same stack, but different storage
+        for _ in 0..16 {
+            let vm = new_test_vm("0x600035600b5760016000555b602035601357005b00", "0x");
+            let (trace, branches, _) = vm.symbolic_exec(&ExecutionLimits::default());
+
+            // the branches are decided in order, so the first path to reach the second JUMPI
+            // always explores it, and the other always stops there
+            assert_eq!(branches, 2);
+            assert_eq!(trace.children.len(), 2);
+            assert_eq!(trace.children[0].children.len(), 2);
+            assert!(trace.children[1].children.is_empty());
+        }
+    }
+
+    // with the `z3` feature, the paths' jump conditions differ, so their states aren't identical
+    #[cfg(not(feature = "z3"))]
+    #[test]
+    fn test_exploration_shares_identical_states() {
+        // if calldataload(0) {}, then both paths reach if calldataload(32) { stop } in the same
+        // state
+        let vm = new_test_vm("0x6000356006575b602035600e57005b00", "0x");
+        let (trace, branches, _) = vm.symbolic_exec(&ExecutionLimits::default());
+
+        // the state is only explored once, and both paths share its tree
+        assert_eq!(branches, 2);
+        assert_eq!(trace.children.len(), 2);
+        assert!(std::sync::Arc::ptr_eq(&trace.children[0], &trace.children[1]));
+        assert_eq!(trace.children[0].children.len(), 2);
     }
 }
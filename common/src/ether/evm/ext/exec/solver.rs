@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+
+use ethers::types::U256;
+use z3::{Config, Context, SatResult, Solver};
+
+use crate::ether::evm::core::opcodes::{WrappedInput, WrappedOpcode};
+
+// conditions larger than this are assumed to be satisfiable rather than sent to the solver
+const MAX_CONDITION_LENGTH: usize = 1 << 16;
+
+// the solver gives up, and the path is assumed to be feasible, after this many milliseconds
+const SOLVER_TIMEOUT_MS: u64 = 250;
+
+/// Whether the given jump conditions can all hold at once, where each condition is paired with
+/// whether the jump was taken. Conditions the solver can't decide are assumed to be satisfiable,
+/// so paths are only pruned when they're provably unreachable.
+///
+/// ```
+/// use heimdall_common::ether::evm::{
+///     core::opcodes::{Opcode, WrappedInput, WrappedOpcode},
+///     ext::exec::solver::is_satisfiable,
+/// };
+///
+/// // the jump condition `calldatasize() < 4`
+/// let condition = WrappedOpcode::new(
+///     0x10,
+///     vec![
+///         WrappedInput::Opcode(WrappedOpcode::new(0x36, vec![])),
+///         WrappedInput::Opcode(WrappedOpcode::new(0x60, vec![WrappedInput::Raw(4.into())])),
+///     ],
+/// );
+///
+/// assert!(is_satisfiable(&[(condition.clone(), true)]));
+/// assert!(!is_satisfiable(&[(condition.clone(), true), (condition, false)]));
+/// ```
+pub fn is_satisfiable(conditions: &[(WrappedOpcode, bool)]) -> bool {
+    let mut variables = Variables::default();
+    let assertions = conditions
+        .iter()
+        .map(|(condition, taken)| {
+            let comparison = if *taken { "distinct" } else { "=" };
+            format!(
+                "(assert ({} {} {}))",
+                comparison,
+                to_smtlib(condition, &mut variables),
+                word(0)
+            )
+        })
+        .collect::<Vec<String>>();
+
+    let mut source = (0..variables.count)
+        .map(|i| format!("(declare-const v{} (_ BitVec 256))", i))
+        .collect::<Vec<String>>();
+    source.extend(assertions);
+    let source = source.join("\n");
+    if source.len() > MAX_CONDITION_LENGTH {
+        return true
+    }
+
+    check(&source).unwrap_or(true)
+}
+
+/// Checks the given SMT-LIB2 assertions with Z3, returning `None` if it can't decide.
+fn check(source: &str) -> Option<bool> {
+    let mut config = Config::new();
+    config.set_timeout_msec(SOLVER_TIMEOUT_MS);
+    let context = Context::new(&config);
+
+    let solver = Solver::new(&context);
+    solver.from_string(source);
+    match solver.check() {
+        SatResult::Sat => Some(true),
+        SatResult::Unsat => Some(false),
+        SatResult::Unknown => None,
+    }
+}
+
+/// A 256-bit bitvector constant.
+fn word(value: impl Into<U256>) -> String {
+    format!("(_ bv{} 256)", value.into())
+}
+
+/// Converts a boolean SMT-LIB2 expression to a word, as EVM comparisons push 1 or 0.
+fn from_bool(expression: String) -> String {
+    format!("(ite {} {} {})", expression, word(1), word(0))
+}
+
+/// The variables of the operations which can't be translated.
+#[derive(Default)]
+struct Variables {
+    // the variable of each operation which reads the same value wherever it appears
+    shared: HashMap<String, usize>,
+    count: usize,
+}
+
+impl Variables {
+    /// The variable of the given operation. Operations which only read state that's fixed for
+    /// the whole call, such as calldata, share a variable wherever they appear. Reads of state
+    /// which can change, such as storage, memory, or the return data of calls, may differ between
+    /// occurrences, such as before and after an SSTORE, so each occurrence gets its own variable.
+    fn get(&mut self, operation: &WrappedOpcode) -> String {
+        let index = match is_fixed(operation) {
+            true => *self.shared.entry(operation.solidify()).or_insert(self.count),
+            false => self.count,
+        };
+        if index == self.count {
+            self.count += 1;
+        }
+        format!("v{}", index)
+    }
+}
+
+/// Whether the given operation reads the same value wherever it appears in a call, which is when
+/// it only reads the call's environment, or is pure, and so are all of its inputs.
+fn is_fixed(operation: &WrappedOpcode) -> bool {
+    let fixed = matches!(
+        operation.opcode.code,
+        // arithmetic, comparison, bitwise, and PUSH operations
+        0x01..=0x0b | 0x10..=0x1d | 0x5f..=0x7f |
+        // ADDRESS, ORIGIN, CALLER, CALLVALUE, CALLDATALOAD, CALLDATASIZE, CODESIZE, and GASPRICE
+        0x30 | 0x32..=0x36 | 0x38 | 0x3a |
+        // BLOCKHASH through BLOBBASEFEE, except for SELFBALANCE
+        0x40..=0x46 | 0x48..=0x4a
+    );
+
+    fixed &&
+        operation.inputs.iter().all(|input| match input {
+            WrappedInput::Raw(_) => true,
+            WrappedInput::Opcode(operation) => is_fixed(operation),
+        })
+}
+
+/// Translates an operation into an SMT-LIB2 bitvector expression. Operations which can't be
+/// translated, such as calldata or storage reads, become variables.
+fn to_smtlib(operation: &WrappedOpcode, variables: &mut Variables) -> String {
+    let code = operation.opcode.code;
+
+    // PUSH0 through PUSH32 are constants
+    if (0x5f..=0x7f).contains(&code) {
+        return match operation.inputs.first() {
+            Some(WrappedInput::Raw(value)) => word(*value),
+            _ => word(0),
+        }
+    }
+
+    let expected_inputs = match code {
+        0x15 | 0x19 => 1,
+        0x01..=0x07 | 0x10..=0x14 | 0x16..=0x18 | 0x1b..=0x1d => 2,
+        _ => usize::MAX,
+    };
+    if operation.inputs.len() != expected_inputs {
+        return variables.get(operation)
+    }
+
+    let inputs = operation
+        .inputs
+        .iter()
+        .map(|input| match input {
+            WrappedInput::Raw(value) => word(*value),
+            WrappedInput::Opcode(operation) => to_smtlib(operation, variables),
+        })
+        .collect::<Vec<String>>();
+    let binary = |function: &str| format!("({} {} {})", function, inputs[0], inputs[1]);
+    // division and modulo by zero are zero in the EVM
+    let nonzero = |function: &str| {
+        format!("(ite (= {} {}) {} {})", inputs[1], word(0), word(0), binary(function))
+    };
+    // shifts take the shift amount first
+    let shift = |function: &str| format!("({} {} {})", function, inputs[1], inputs[0]);
+
+    match code {
+        0x01 => binary("bvadd"),
+        0x02 => binary("bvmul"),
+        0x03 => binary("bvsub"),
+        0x04 => nonzero("bvudiv"),
+        0x05 => nonzero("bvsdiv"),
+        0x06 => nonzero("bvurem"),
+        0x07 => nonzero("bvsrem"),
+        0x10 => from_bool(binary("bvult")),
+        0x11 => from_bool(binary("bvugt")),
+        0x12 => from_bool(binary("bvslt")),
+        0x13 => from_bool(binary("bvsgt")),
+        0x14 => from_bool(binary("=")),
+        0x15 => from_bool(format!("(= {} {})", inputs[0], word(0))),
+        0x16 => binary("bvand"),
+        0x17 => binary("bvor"),
+        0x18 => binary("bvxor"),
+        0x19 => format!("(bvnot {})", inputs[0]),
+        0x1b => shift("bvshl"),
+        0x1c => shift("bvlshr"),
+        _ => shift("bvashr"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ether::evm::{
+        core::{
+            opcodes::{WrappedInput, WrappedOpcode},
+            vm::new_test_vm,
+        },
+        ext::exec::{solver::is_satisfiable, ExecutionLimits},
+    };
+
+    fn push(value: u64) -> WrappedInput {
+        WrappedInput::Opcode(WrappedOpcode::new(0x60, vec![WrappedInput::Raw(value.into())]))
+    }
+
+    fn calldata(offset: u64) -> WrappedInput {
+        WrappedInput::Opcode(WrappedOpcode::new(0x35, vec![push(offset)]))
+    }
+
+    #[test]
+    fn test_contradictory_conditions() {
+        // calldataload(4) > 10, then calldataload(4) < 5
+        let greater = WrappedOpcode::new(0x11, vec![calldata(4), push(10)]);
+        let less = WrappedOpcode::new(0x10, vec![calldata(4), push(5)]);
+
+        assert!(is_satisfiable(&[(greater.clone(), true), (less.clone(), false)]));
+        assert!(!is_satisfiable(&[(greater, true), (less, true)]));
+    }
+
+    #[test]
+    fn test_independent_conditions() {
+        // calldataload(4) and calldataload(36) are different variables
+        let first = WrappedOpcode::new(0x14, vec![calldata(4), push(1)]);
+        let second = WrappedOpcode::new(0x14, vec![calldata(36), push(2)]);
+
+        assert!(is_satisfiable(&[(first, true), (second, true)]));
+    }
+
+    #[test]
+    fn test_storage_reads_are_distinct() {
+        // sload(0) == 0, and then sload(0) != 0, which holds if there's an sstore between them
+        let condition = WrappedOpcode::new(
+            0x14,
+            vec![WrappedInput::Opcode(WrappedOpcode::new(0x54, vec![push(0)])), push(0)],
+        );
+        assert!(is_satisfiable(&[(condition.clone(), true), (condition.clone(), false)]));
+
+        // while calldata can't change within a call
+        let condition = WrappedOpcode::new(0x14, vec![calldata(4), push(0)]);
+        assert!(!is_satisfiable(&[(condition.clone(), true), (condition, false)]));
+    }
+
+    #[test]
+    fn test_sstore_between_sloads_keeps_both_branches() {
+        // if sload(0) == 0 { sstore(0, 1); if sload(0) { stop } else { stop } }
+        let vm = new_test_vm("0x6000546014576001600055600054601257005b005b00", "0x");
+        let (trace, branches, _) = vm.symbolic_exec(&ExecutionLimits::default());
+
+        // the second sload isn't the same value as the first, so neither branch is pruned
+        assert_eq!(branches, 2);
+        assert_eq!(trace.children[1].children.len(), 2);
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        // calldataload(4) / 0 is always zero
+        let division = WrappedOpcode::new(0x04, vec![calldata(4), push(0)]);
+
+        assert!(!is_satisfiable(&[(division.clone(), true)]));
+        assert!(is_satisfiable(&[(division, false)]));
+    }
+}
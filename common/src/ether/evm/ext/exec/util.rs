@@ -1,9 +1,9 @@
 use ethers::types::U256;
+use tracing::trace;
 
 use crate::{
     constants::{MEMORY_REGEX, STORAGE_REGEX},
     ether::evm::core::stack::{Stack, StackFrame},
-    utils::io::logging::Logger,
 };
 
 /// Given two stacks A and B, return A - B, i.e. the items in A that are not in B.
@@ -30,12 +30,7 @@ pub fn stack_contains_too_many_of_the_same_item(stack: &Stack) -> bool {
         stack.stack.iter().filter(|f| f.operation.solidify() == solidified_frame_source).count() >=
             16
     }) {
-        // get a new logger
-        let logger = Logger::default();
-
-        logger.debug_max(
-            "jump matches loop-detection heuristic: 'stack_contains_too_many_of_the_same_item'",
-        );
+        trace!("jump matches loop-detection heuristic: 'stack_contains_too_many_of_the_same_item'",);
         return true
     }
 
@@ -47,11 +42,7 @@ pub fn stack_contains_too_many_of_the_same_item(stack: &Stack) -> bool {
 /// `SWAP16` operation limitations.
 pub fn stack_item_source_depth_too_deep(stack: &Stack) -> bool {
     if stack.stack.iter().any(|frame| frame.operation.depth() > 16) {
-        // get a new logger
-        let logger = Logger::default();
-
-        logger
-            .debug_max("jump matches loop-detection heuristic: 'stack_item_source_depth_too_deep'");
+        trace!("jump matches loop-detection heuristic: 'stack_item_source_depth_too_deep'");
         return true
     }
 
@@ -67,11 +58,7 @@ pub fn jump_condition_appears_recursive(stack_diff: &[StackFrame], jump_conditio
         .map(|frame| frame.operation.solidify())
         .any(|solidified| jump_condition.contains(&solidified))
     {
-        // get a new logger
-        let logger = Logger::default();
-
-        logger
-            .debug_max("jump matches loop-detection heuristic: 'jump_condition_appears_recursive'");
+        trace!("jump matches loop-detection heuristic: 'jump_condition_appears_recursive'");
         return true
     }
 
@@ -94,10 +81,7 @@ pub fn jump_condition_contains_mutated_memory_access(
             frame.operation.solidify().contains(slice)
         })
     }) {
-        // get a new logger
-        let logger = Logger::default();
-
-        logger.debug_max("jump matches loop-detection heuristic: 'jump_condition_contains_mutated_memory_access'");
+        trace!("jump matches loop-detection heuristic: 'jump_condition_contains_mutated_memory_access'");
         return true
     }
 
@@ -120,10 +104,7 @@ pub fn jump_condition_contains_mutated_storage_access(
             frame.operation.solidify().contains(slice)
         })
     }) {
-        // get a new logger
-        let logger = Logger::default();
-
-        logger.debug_max("jump matches loop-detection heuristic: 'jump_condition_contains_mutated_storage_access'");
+        trace!("jump matches loop-detection heuristic: 'jump_condition_contains_mutated_storage_access'");
         return true
     }
 
@@ -162,9 +143,7 @@ pub fn jump_condition_historical_diffs_approximately_equal(
         return false
     }
 
-    // get a new logger
-    let logger = Logger::default();
-    logger.debug_max("jump matches loop-detection heuristic: 'jump_condition_historical_diffs_approximately_equal'");
+    trace!("jump matches loop-detection heuristic: 'jump_condition_historical_diffs_approximately_equal'");
 
     true
 }
@@ -0,0 +1,464 @@
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+
+use crate::{ether::rpc::batch_request, utils::io::logging::Logger};
+
+// the most blocks searched by a single `trace_filter` request, which most nodes limit
+const TRACE_FILTER_RANGE: u128 = 10_000;
+
+// the most blocks searched by a single `eth_getLogs` request
+const LOG_FILTER_RANGE: u128 = 10_000;
+
+// the most blocks traced at once when searching for contract creations
+const CREATION_BATCH_SIZE: u128 = 100;
+
+/// sends a single request to the node, returning its result
+async fn request(
+    rpc_url: &str,
+    method: &str,
+    params: Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    batch_request(rpc_url, &[(method, params)])
+        .await?
+        .pop()
+        .ok_or("no response")?
+        .map_err(|e| format!("{method} failed: {e}").into())
+}
+
+/// Get the number of the latest block.
+pub async fn get_block_number(rpc_url: &str) -> Result<u128, Box<dyn std::error::Error>> {
+    let block_number = request(rpc_url, "eth_blockNumber", json!([])).await?;
+    let block_number = block_number.as_str().ok_or("malformed eth_blockNumber response")?;
+    Ok(u128::from_str_radix(block_number.trim_start_matches("0x"), 16)?)
+}
+
+/// Get the block number and hash of the transaction which created the given contract, using only
+/// an archive node, rather than an indexer such as Transpose. The creation block is found by
+/// searching for the first block the contract has code at, and the transaction by tracing that
+/// block, so the node must support `trace_block`.
+///
+/// ```no_run
+/// use heimdall_common::ether::history::get_contract_creation;
+///
+/// // let (block_number, transaction_hash) = get_contract_creation(
+/// //     "https://eth.llamarpc.com",
+/// //     "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+/// // ).await.unwrap();
+/// ```
+pub async fn get_contract_creation(
+    rpc_url: &str,
+    address: &str,
+) -> Result<(u128, String), Box<dyn std::error::Error>> {
+    let logger = Logger::default();
+
+    // the contract has code from its creation block onwards
+    let (mut low, mut high) = (0, get_block_number(rpc_url).await?);
+    if !has_code(rpc_url, address, high).await? {
+        return Err(format!("'{address}' isn't a contract").into())
+    }
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if has_code(rpc_url, address, middle).await? {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+    logger.debug_max(&format!("'{address}' was created in block {low} ."));
+
+    let traces = request(rpc_url, "trace_block", json!([format!("{low:#x}")])).await?;
+    let transaction_hash = find_creation(&traces, address)
+        .ok_or_else(|| format!("failed to find the transaction which created '{address}'"))?;
+    Ok((low, transaction_hash))
+}
+
+/// whether the given address has code at the given block
+async fn has_code(
+    rpc_url: &str,
+    address: &str,
+    block: u128,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let code = request(rpc_url, "eth_getCode", json!([address, format!("{block:#x}")])).await?;
+    Ok(!matches!(code.as_str(), None | Some("0x") | Some("")))
+}
+
+/// gets every contract created in a block's traces which was created successfully
+fn parse_creations(traces: &Value) -> Vec<ContractCreation> {
+    traces
+        .as_array()
+        .map(|traces| {
+            traces
+                .iter()
+                .filter(|trace| trace.get("type").and_then(Value::as_str) == Some("create"))
+                .filter_map(|trace| {
+                    Some(ContractCreation {
+                        block_number: trace.get("blockNumber")?.as_u64()? as u128,
+                        transaction_hash: trace.get("transactionHash")?.as_str()?.to_string(),
+                        address: trace.get("result")?.get("address")?.as_str()?.to_lowercase(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// finds the hash of the transaction which created the given address in a block's traces
+fn find_creation(traces: &Value, address: &str) -> Option<String> {
+    traces.as_array()?.iter().find_map(|trace| {
+        let created = trace.get("result")?.get("address")?.as_str()?;
+        match trace.get("type")?.as_str()? == "create" && created.eq_ignore_ascii_case(address) {
+            true => Some(trace.get("transactionHash")?.as_str()?.to_string()),
+            false => None,
+        }
+    })
+}
+
+/// A contract created within a block range, with the transaction which created it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractCreation {
+    pub block_number: u128,
+    pub transaction_hash: String,
+    pub address: String,
+}
+
+/// Get every contract created within the given block range, including contracts created by other
+/// contracts. Each block is traced with `trace_block`, so the node must support it, and blocks
+/// are traced in batches of `CREATION_BATCH_SIZE` so only one batch of traces is held at a time.
+///
+/// ```no_run
+/// use heimdall_common::ether::history::get_contract_creations;
+///
+/// // let creations = get_contract_creations(
+/// //     "https://eth.llamarpc.com",
+/// //     (&17000000, &17000100),
+/// // ).await.unwrap();
+/// ```
+pub async fn get_contract_creations(
+    rpc_url: &str,
+    bounds: (&u128, &u128),
+) -> Result<Vec<ContractCreation>, Box<dyn std::error::Error>> {
+    let to_block = (*bounds.1).min(get_block_number(rpc_url).await?);
+
+    let mut creations = Vec::new();
+    for (from, to) in block_ranges(*bounds.0, to_block, CREATION_BATCH_SIZE) {
+        let requests = (from..=to)
+            .map(|block| ("trace_block", json!([format!("{block:#x}")])))
+            .collect::<Vec<_>>();
+        for result in batch_request(rpc_url, &requests).await? {
+            let traces = result.map_err(|e| format!("trace_block failed: {e}"))?;
+            creations.extend(parse_creations(&traces));
+        }
+    }
+    Ok(creations)
+}
+
+/// Get the block number and hash of every transaction which called the given address, directly
+/// or internally, within the given block range. This uses the node's `trace_filter`, which
+/// archive nodes such as Erigon, Reth, and Nethermind support, rather than an indexer such as
+/// Transpose.
+///
+/// ```no_run
+/// use heimdall_common::ether::history::get_transaction_list;
+///
+/// // let transactions = get_transaction_list(
+/// //     "https://eth.llamarpc.com",
+/// //     "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+/// //     (&17000000, &17001000),
+/// // ).await.unwrap();
+/// ```
+pub async fn get_transaction_list(
+    rpc_url: &str,
+    address: &str,
+    bounds: (&u128, &u128),
+) -> Result<Vec<(u128, String)>, Box<dyn std::error::Error>> {
+    let to_block = (*bounds.1).min(get_block_number(rpc_url).await?);
+    let ranges = block_ranges(*bounds.0, to_block, TRACE_FILTER_RANGE);
+    let requests = ranges
+        .iter()
+        .map(|(from, to)| {
+            (
+                "trace_filter",
+                json!([{
+                    "fromBlock": format!("{from:#x}"),
+                    "toBlock": format!("{to:#x}"),
+                    "toAddress": [address],
+                }]),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut transactions = Vec::new();
+    let mut seen = HashSet::new();
+    for result in batch_request(rpc_url, &requests).await? {
+        let traces = result.map_err(|e| format!("trace_filter failed: {e}"))?;
+        for transaction in parse_traces(&traces) {
+            if seen.insert(transaction.1.clone()) {
+                transactions.push(transaction);
+            }
+        }
+    }
+
+    transactions.sort();
+    Ok(transactions)
+}
+
+/// Get the topics of every event the given address emitted within the given block range, whose
+/// signature is one of the given topics. Each event's topics are returned in order, starting with
+/// its signature.
+///
+/// ```no_run
+/// use heimdall_common::ether::history::get_event_topics;
+///
+/// // let topics = get_event_topics(
+/// //     "https://eth.llamarpc.com",
+/// //     "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+/// //     &["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+/// //     (&17000000, &17001000),
+/// // ).await.unwrap();
+/// ```
+pub async fn get_event_topics(
+    rpc_url: &str,
+    address: &str,
+    signatures: &[&str],
+    bounds: (&u128, &u128),
+) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+    let mut topics = Vec::new();
+    for logs in request_logs(rpc_url, address, signatures, bounds).await? {
+        topics.extend(parse_topics(&logs));
+    }
+    Ok(topics)
+}
+
+/// An event emitted by a contract, with the transaction which emitted it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventLog {
+    pub block_number: u128,
+    pub transaction_hash: String,
+    pub topics: Vec<String>,
+    pub data: String,
+}
+
+/// Get every event the given address emitted within the given block range, whose signature is one
+/// of the given topics, in the order they were emitted.
+///
+/// ```no_run
+/// use heimdall_common::ether::history::get_event_logs;
+///
+/// // let logs = get_event_logs(
+/// //     "https://eth.llamarpc.com",
+/// //     "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+/// //     &["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+/// //     (&17000000, &17001000),
+/// // ).await.unwrap();
+/// ```
+pub async fn get_event_logs(
+    rpc_url: &str,
+    address: &str,
+    signatures: &[&str],
+    bounds: (&u128, &u128),
+) -> Result<Vec<EventLog>, Box<dyn std::error::Error>> {
+    let mut event_logs = Vec::new();
+    for logs in request_logs(rpc_url, address, signatures, bounds).await? {
+        event_logs.extend(parse_logs(&logs));
+    }
+    Ok(event_logs)
+}
+
+/// requests the logs matching the given filter with `eth_getLogs`, split into ranges of at most
+/// `LOG_FILTER_RANGE` blocks
+async fn request_logs(
+    rpc_url: &str,
+    address: &str,
+    signatures: &[&str],
+    bounds: (&u128, &u128),
+) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let to_block = (*bounds.1).min(get_block_number(rpc_url).await?);
+    let requests = block_ranges(*bounds.0, to_block, LOG_FILTER_RANGE)
+        .iter()
+        .map(|(from, to)| {
+            (
+                "eth_getLogs",
+                json!([{
+                    "fromBlock": format!("{from:#x}"),
+                    "toBlock": format!("{to:#x}"),
+                    "address": address,
+                    "topics": [signatures],
+                }]),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    batch_request(rpc_url, &requests)
+        .await?
+        .into_iter()
+        .map(|result| result.map_err(|e| format!("eth_getLogs failed: {e}").into()))
+        .collect()
+}
+
+/// gets the topics of each log
+fn parse_topics(logs: &Value) -> Vec<Vec<String>> {
+    logs.as_array()
+        .map(|logs| {
+            logs.iter()
+                .filter_map(|log| {
+                    log.get("topics")?
+                        .as_array()?
+                        .iter()
+                        .map(|topic| topic.as_str().map(|topic| topic.to_lowercase()))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// parses each log, skipping those which are pending, and so have no block number
+fn parse_logs(logs: &Value) -> Vec<EventLog> {
+    logs.as_array()
+        .map(|logs| {
+            logs.iter()
+                .filter_map(|log| {
+                    let block_number = log.get("blockNumber")?.as_str()?.trim_start_matches("0x");
+                    Some(EventLog {
+                        block_number: u128::from_str_radix(block_number, 16).ok()?,
+                        transaction_hash: log.get("transactionHash")?.as_str()?.to_lowercase(),
+                        topics: log
+                            .get("topics")?
+                            .as_array()?
+                            .iter()
+                            .map(|topic| topic.as_str().map(|topic| topic.to_lowercase()))
+                            .collect::<Option<Vec<_>>>()?,
+                        data: log.get("data")?.as_str()?.to_lowercase(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// splits the given block range into inclusive ranges of at most `size` blocks
+fn block_ranges(from_block: u128, to_block: u128, size: u128) -> Vec<(u128, u128)> {
+    let mut ranges = Vec::new();
+    let mut from = from_block;
+    while from <= to_block {
+        let to = (from + size - 1).min(to_block);
+        ranges.push((from, to));
+        from = to + 1;
+    }
+    ranges
+}
+
+/// gets the block number and transaction hash of each trace, skipping those which aren't part of
+/// a transaction, such as block rewards
+fn parse_traces(traces: &Value) -> Vec<(u128, String)> {
+    traces
+        .as_array()
+        .map(|traces| {
+            traces
+                .iter()
+                .filter_map(|trace| {
+                    Some((
+                        trace.get("blockNumber")?.as_u64()? as u128,
+                        trace.get("transactionHash")?.as_str()?.to_string(),
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::ether::history::{
+        block_ranges, find_creation, parse_creations, parse_logs, parse_topics, parse_traces,
+        ContractCreation, EventLog,
+    };
+
+    #[test]
+    fn test_block_ranges() {
+        assert_eq!(block_ranges(0, 25, 10), [(0, 9), (10, 19), (20, 25)]);
+        assert_eq!(block_ranges(5, 5, 10), [(5, 5)]);
+        assert!(block_ranges(10, 5, 10).is_empty());
+    }
+
+    #[test]
+    fn test_parse_traces() {
+        let traces = json!([
+            { "type": "call", "blockNumber": 100, "transactionHash": "0xaa" },
+            { "type": "reward", "blockNumber": 100, "transactionHash": null },
+            { "type": "call", "blockNumber": 101, "transactionHash": "0xbb" },
+        ]);
+        assert_eq!(
+            parse_traces(&traces),
+            [(100, String::from("0xaa")), (101, String::from("0xbb"))]
+        );
+    }
+
+    #[test]
+    fn test_parse_topics() {
+        let logs = json!([
+            { "topics": ["0xDDF2", "0xaa", "0xbb"], "data": "0x01" },
+            { "topics": [], "data": "0x" },
+            { "data": "0x" },
+        ]);
+        assert_eq!(
+            parse_topics(&logs),
+            [vec![String::from("0xddf2"), String::from("0xaa"), String::from("0xbb")], Vec::new()]
+        );
+    }
+
+    #[test]
+    fn test_parse_logs() {
+        let logs = json!([
+            {
+                "blockNumber": "0x64",
+                "transactionHash": "0xAA",
+                "topics": ["0xDDF2", "0xbb"],
+                "data": "0x01",
+            },
+            { "blockNumber": null, "transactionHash": null, "topics": ["0xddf2"], "data": "0x" },
+        ]);
+        assert_eq!(
+            parse_logs(&logs),
+            [EventLog {
+                block_number: 100,
+                transaction_hash: String::from("0xaa"),
+                topics: vec![String::from("0xddf2"), String::from("0xbb")],
+                data: String::from("0x01"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_creations() {
+        let traces = json!([
+            { "type": "call", "blockNumber": 100, "result": { "output": "0x" }, "transactionHash": "0xaa" },
+            { "type": "create", "blockNumber": 100, "result": { "address": "0x00000000000000000000000000000000000000FF" }, "transactionHash": "0xbb" },
+            { "type": "create", "blockNumber": 100, "result": null, "error": "Reverted", "transactionHash": "0xcc" },
+        ]);
+        assert_eq!(
+            parse_creations(&traces),
+            [ContractCreation {
+                block_number: 100,
+                transaction_hash: String::from("0xbb"),
+                address: String::from("0x00000000000000000000000000000000000000ff"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_creation() {
+        let traces = json!([
+            { "type": "call", "result": { "output": "0x" }, "transactionHash": "0xaa" },
+            { "type": "create", "result": { "address": "0x00000000000000000000000000000000000000ff" }, "transactionHash": "0xbb" },
+        ]);
+        assert_eq!(
+            find_creation(&traces, "0x00000000000000000000000000000000000000FF"),
+            Some(String::from("0xbb"))
+        );
+        assert_eq!(find_creation(&traces, "0x00000000000000000000000000000000000000ee"), None);
+    }
+}
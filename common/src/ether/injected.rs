@@ -0,0 +1,117 @@
+use std::{
+    fmt::{Debug, Formatter},
+    future::Future,
+    pin::Pin,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, ProviderError};
+use lazy_static::lazy_static;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// A future returned by an injected callback.
+pub type CallbackFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Sends a JSON-RPC request, given its method and params, returning its result or an error
+/// message.
+pub type RpcCallback =
+    Arc<dyn Fn(String, Value) -> CallbackFuture<Result<Value, String>> + Send + Sync>;
+
+/// Fetches a URL, returning its body as JSON, or `None` if it couldn't be fetched.
+pub type HttpCallback = Arc<dyn Fn(String) -> CallbackFuture<Option<Value>> + Send + Sync>;
+
+lazy_static! {
+    static ref RPC_CALLBACK: RwLock<Option<RpcCallback>> = RwLock::new(None);
+    static ref HTTP_CALLBACK: RwLock<Option<HttpCallback>> = RwLock::new(None);
+}
+
+/// Sets the callback which JSON-RPC requests are sent through when targeting wasm32, where the
+/// host, such as a browser, does the networking instead.
+///
+/// ```
+/// use heimdall_common::ether::injected::set_rpc_callback;
+/// use serde_json::json;
+/// use std::sync::Arc;
+///
+/// set_rpc_callback(Arc::new(|method, _params| {
+///     Box::pin(async move {
+///         match method.as_str() {
+///             "eth_chainId" => Ok(json!("0x1")),
+///             _ => Err(format!("unsupported method '{method}'")),
+///         }
+///     })
+/// }));
+/// ```
+pub fn set_rpc_callback(callback: RpcCallback) {
+    *RPC_CALLBACK.write().expect("rpc callback lock poisoned") = Some(callback);
+}
+
+/// Sets the callback which HTTP requests, such as signature lookups, are sent through when
+/// targeting wasm32.
+pub fn set_http_callback(callback: HttpCallback) {
+    *HTTP_CALLBACK.write().expect("http callback lock poisoned") = Some(callback);
+}
+
+/// Fetches a URL through the injected HTTP callback, returning `None` if none is set.
+pub async fn http_request(url: &str) -> Option<Value> {
+    let callback = HTTP_CALLBACK.read().expect("http callback lock poisoned").clone()?;
+    callback(url.to_string()).await
+}
+
+/// A JSON-RPC client which sends every request through the injected RPC callback.
+#[derive(Clone, Default)]
+pub struct InjectedClient;
+
+impl Debug for InjectedClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InjectedClient")
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcClient for InjectedClient {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, ProviderError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send, {
+        let callback =
+            RPC_CALLBACK.read().expect("rpc callback lock poisoned").clone().ok_or_else(|| {
+                ProviderError::CustomError(String::from("no rpc callback has been set"))
+            })?;
+        let params = serde_json::to_value(params)?;
+        let result =
+            callback(method.to_string(), params).await.map_err(ProviderError::CustomError)?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ethers::providers::JsonRpcClient;
+    use serde_json::{json, Value};
+
+    use crate::ether::injected::{set_rpc_callback, InjectedClient};
+
+    #[tokio::test]
+    async fn test_injected_client() {
+        set_rpc_callback(Arc::new(|method, params| {
+            Box::pin(async move {
+                match method.as_str() {
+                    "eth_getCode" => Ok(json!(format!("0x{}", params[0].as_str().unwrap_or("")))),
+                    _ => Err(format!("unsupported method '{method}'")),
+                }
+            })
+        }));
+
+        let code: String = InjectedClient.request("eth_getCode", ["6080", "latest"]).await.unwrap();
+        assert_eq!(code, "0x6080");
+        assert!(InjectedClient.request::<_, Value>("eth_chainId", ()).await.is_err());
+    }
+}
@@ -177,6 +177,17 @@ impl WrappedOpcode {
                         .as_str(),
                 );
             }
+            "SIGNEXTEND" => {
+                // sign extending from a constant size is a cast to a signed integer of that size
+                let value = self.inputs[1]._solidify();
+                match &self.inputs[0] {
+                    WrappedInput::Raw(b) if *b < U256::from(31) => {
+                        solidified_wrapped_opcode
+                            .push_str(format!("int{}({value})", (b.as_usize() + 1) * 8).as_str());
+                    }
+                    _ => solidified_wrapped_opcode.push_str(value.as_str()),
+                }
+            }
             "BYTE" => {
                 solidified_wrapped_opcode.push_str(self.inputs[1]._solidify().as_str());
             }
@@ -207,10 +218,14 @@ impl WrappedOpcode {
                 if WORD_REGEX.is_match(&solidified_slot).unwrap() {
                     // convert to usize
                     match usize::from_str_radix(&solidified_slot.replacen("0x", "", 1), 16) {
-                        Ok(slot) => {
+                        Ok(slot) if slot >= 4 => {
                             solidified_wrapped_opcode
                                 .push_str(format!("arg{}", (slot - 4) / 32).as_str());
                         }
+                        Ok(_) => {
+                            solidified_wrapped_opcode
+                                .push_str(format!("msg.data[{solidified_slot}]").as_str());
+                        }
                         Err(_) => {
                             if solidified_slot.contains("0x04 + ") ||
                                 solidified_slot.contains("+ 0x04")
@@ -275,6 +290,13 @@ impl WrappedOpcode {
             "BASEFEE" => {
                 solidified_wrapped_opcode.push_str("block.basefee");
             }
+            "BLOBHASH" => {
+                solidified_wrapped_opcode
+                    .push_str(format!("blobhash({})", self.inputs[0]._solidify()).as_str());
+            }
+            "BLOBBASEFEE" => {
+                solidified_wrapped_opcode.push_str("block.blobbasefee");
+            }
             "GAS" => {
                 solidified_wrapped_opcode.push_str("gasleft()");
             }
@@ -285,6 +307,10 @@ impl WrappedOpcode {
                 solidified_wrapped_opcode
                     .push_str(format!("storage[{}]", self.inputs[0]._solidify()).as_str());
             }
+            "TLOAD" => {
+                solidified_wrapped_opcode
+                    .push_str(format!("transient[{}]", self.inputs[0]._solidify()).as_str());
+            }
             "MLOAD" => {
                 let memloc = self.inputs[0]._solidify();
                 if memloc.contains("memory") {
@@ -367,6 +393,11 @@ impl WrappedOpcode {
             "RETURNDATASIZE" => {
                 solidified_wrapped_opcode.push_str("ret0.length");
             }
+            "RETURNDATACOPY" => {
+                // the VM loads words copied out of returndata as RETURNDATACOPY of their offset
+                solidified_wrapped_opcode
+                    .push_str(format!("ret0[{}]", self.inputs[0]._solidify()).as_str());
+            }
             "PUSH0" => {
                 solidified_wrapped_opcode.push('0');
             }
@@ -656,6 +687,16 @@ mod tests {
         assert_eq!(wrapped_opcode.solidify(), "0x01 >> 0x06");
     }
 
+    #[test]
+    fn test_wrapped_opcode_solidify_signextend() {
+        let opcode = Opcode { code: 0x0b, name: "SIGNEXTEND", mingas: 5, inputs: 2, outputs: 1 };
+        let inputs =
+            vec![WrappedInput::Raw(U256::from(1u8)), WrappedInput::Raw(U256::from(0xffu8))];
+        let wrapped_opcode = WrappedOpcode { opcode, inputs };
+
+        assert_eq!(wrapped_opcode.solidify(), "int16(0xff)");
+    }
+
     #[test]
     fn test_wrapped_opcode_solidify_byte() {
         let opcode = Opcode { code: 0x1d, name: "BYTE", mingas: 1, inputs: 2, outputs: 1 };
@@ -846,6 +887,15 @@ mod tests {
         assert_eq!(wrapped_opcode.solidify(), "block.basefee");
     }
 
+    #[test]
+    fn test_wrapped_opcode_solidify_blobbasefee() {
+        let opcode = Opcode { code: 0x4a, name: "BLOBBASEFEE", mingas: 2, inputs: 0, outputs: 1 };
+        let inputs = vec![];
+        let wrapped_opcode = WrappedOpcode { opcode, inputs };
+
+        assert_eq!(wrapped_opcode.solidify(), "block.blobbasefee");
+    }
+
     #[test]
     fn test_wrapped_opcode_solidify_gas() {
         let opcode = Opcode { code: 0x5a, name: "GAS", mingas: 1, inputs: 0, outputs: 1 };
@@ -873,6 +923,15 @@ mod tests {
         assert_eq!(wrapped_opcode.solidify(), "storage[0x1234]");
     }
 
+    #[test]
+    fn test_wrapped_opcode_solidify_tload() {
+        let opcode = Opcode { code: 0x5c, name: "TLOAD", mingas: 100, inputs: 1, outputs: 1 };
+        let inputs = vec![WrappedInput::Raw(U256::from(0x1234u16))];
+        let wrapped_opcode = WrappedOpcode { opcode, inputs };
+
+        assert_eq!(wrapped_opcode.solidify(), "transient[0x1234]");
+    }
+
     #[test]
     fn test_wrapped_opcode_solidify_mload() {
         let opcode = Opcode { code: 0x51, name: "MLOAD", mingas: 1, inputs: 1, outputs: 1 };
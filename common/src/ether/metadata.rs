@@ -0,0 +1,193 @@
+use crate::utils::strings::encode_hex;
+
+/// Find the offset of the CBOR-encoded compiler metadata appended to the end of the given
+/// bytecode, if any. Both solc and vyper append a CBOR map followed by its length as a big-endian
+/// u16, so the metadata spans from the returned offset to the end of the bytecode.
+///
+/// ```
+/// use heimdall_common::ether::metadata::find_metadata_offset;
+///
+/// // STOP, followed by {"solc": 0x000814}
+/// let bytecode = [0x00, 0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63, 0x43, 0x00, 0x08, 0x14, 0x00, 0x0a];
+/// assert_eq!(find_metadata_offset(&bytecode), Some(1));
+/// ```
+pub fn find_metadata_offset(bytecode: &[u8]) -> Option<usize> {
+    let length_offset = bytecode.len().checked_sub(2)?;
+    let length = u16::from_be_bytes([bytecode[length_offset], bytecode[length_offset + 1]]);
+    let offset = length_offset.checked_sub(length as usize)?;
+
+    // the metadata must be a non-empty map (0xa1..=0xb7, or 0xbf if indefinite), and its first
+    // key must be a text string (0x60..=0x77)
+    match (bytecode.get(offset), bytecode.get(offset + 1)) {
+        (Some(0xa1..=0xb7 | 0xbf), Some(0x60..=0x77)) if length > 1 => Some(offset),
+        _ => None,
+    }
+}
+
+/// Remove the CBOR-encoded compiler metadata from the end of the given bytecode, if any.
+pub fn strip_metadata(bytecode: &[u8]) -> &[u8] {
+    match find_metadata_offset(bytecode) {
+        Some(offset) => &bytecode[..offset],
+        None => bytecode,
+    }
+}
+
+/// Read a CBOR item header at `offset`, returning its major type, argument, and the offset of the
+/// item's contents. Indefinite lengths are returned as `None`.
+fn read_header(bytes: &[u8], offset: usize) -> Option<(u8, Option<u64>, usize)> {
+    let header = *bytes.get(offset)?;
+    let (major, info) = (header >> 5, header & 0x1f);
+
+    let (argument, size) = match info {
+        0..=23 => (Some(info as u64), 0),
+        24..=27 => {
+            let size = 1 << (info - 24);
+            let argument = bytes
+                .get(offset + 1..offset + 1 + size)?
+                .iter()
+                .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+            (Some(argument), size)
+        }
+        31 => (None, 0),
+        _ => return None,
+    };
+
+    Some((major, argument, offset + 1 + size))
+}
+
+/// Format a metadata value for display. IPFS hashes are base58 encoded, solc versions are
+/// formatted as `major.minor.patch`, and other byte strings are hex encoded.
+fn format_value(key: &str, bytes: &[u8], offset: usize) -> Option<(String, usize)> {
+    let (major, argument, start) = read_header(bytes, offset)?;
+
+    match (major, argument) {
+        (0, Some(value)) => Some((value.to_string(), start)),
+        (2 | 3, Some(length)) => {
+            let end = start.checked_add(length as usize)?;
+            let value = bytes.get(start..end)?;
+            let formatted = match (major, key) {
+                (3, _) => String::from_utf8(value.to_vec()).ok()?,
+                (_, "ipfs") => bs58::encode(value).into_string(),
+                (_, "solc") if value.len() == 3 => {
+                    format!("{}.{}.{}", value[0], value[1], value[2])
+                }
+                _ => format!("0x{}", encode_hex(value.to_vec())),
+            };
+            Some((formatted, end))
+        }
+        (7, Some(20)) => Some((String::from("false"), start)),
+        (7, Some(21)) => Some((String::from("true"), start)),
+        _ => None,
+    }
+}
+
+/// Decode the CBOR-encoded compiler metadata found at [`find_metadata_offset`] into its key-value
+/// pairs, such as `("solc", "0.8.20")`. Returns `None` if the metadata isn't a valid CBOR map.
+///
+/// ```
+/// use heimdall_common::ether::metadata::decode_metadata;
+///
+/// // {"solc": 0x000814}
+/// let metadata = [0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63, 0x43, 0x00, 0x08, 0x14, 0x00, 0x0a];
+/// assert_eq!(
+///     decode_metadata(&metadata),
+///     Some(vec![(String::from("solc"), String::from("0.8.20"))])
+/// );
+/// ```
+pub fn decode_metadata(metadata: &[u8]) -> Option<Vec<(String, String)>> {
+    // ignore the trailing length
+    let metadata = metadata.get(..metadata.len().checked_sub(2)?)?;
+
+    let (major, count, mut offset) = read_header(metadata, 0)?;
+    if major != 5 {
+        return None
+    }
+
+    let mut fields = Vec::new();
+    while count.map(|count| (fields.len() as u64) < count).unwrap_or(true) {
+        // indefinite length maps end with a break
+        if count.is_none() && metadata.get(offset) == Some(&0xff) {
+            break
+        }
+
+        let (major, length, start) = read_header(metadata, offset)?;
+        if major != 3 {
+            return None
+        }
+        let key_end = start.checked_add(length? as usize)?;
+        let key = String::from_utf8(metadata.get(start..key_end)?.to_vec()).ok()?;
+
+        let (value, end) = format_value(&key, metadata, key_end)?;
+        fields.push((key, value));
+        offset = end;
+    }
+
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ether::metadata::*, utils::strings::decode_hex};
+
+    #[test]
+    fn test_strip_solc_metadata() {
+        let bytecode = decode_hex("6080604052600080fdfea2646970667358221220aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e64736f6c63430008140033").unwrap();
+
+        assert_eq!(find_metadata_offset(&bytecode), Some(10));
+        assert_eq!(strip_metadata(&bytecode), &bytecode[..10]);
+    }
+
+    #[test]
+    fn test_strip_metadata_without_metadata() {
+        let bytecode = [0x60, 0x80, 0x60, 0x40, 0x52, 0x00, 0x02];
+
+        assert_eq!(find_metadata_offset(&bytecode), None);
+        assert_eq!(strip_metadata(&bytecode), &bytecode);
+        assert_eq!(find_metadata_offset(&[]), None);
+    }
+
+    #[test]
+    fn test_decode_metadata() {
+        let bytecode = decode_hex("6080604052600080fdfea2646970667358221220aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e64736f6c63430008140033").unwrap();
+        let fields =
+            decode_metadata(&bytecode[find_metadata_offset(&bytecode).unwrap()..]).unwrap();
+
+        assert_eq!(
+            fields,
+            vec![
+                (
+                    String::from("ipfs"),
+                    String::from("QmZqRxGenqp39eEpBmVi6gxGGrjc1bk5Cx2Z9K125cNqcy")
+                ),
+                (String::from("solc"), String::from("0.8.20")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_metadata_bzzr() {
+        let bytecode = decode_hex("00a165627a7a72305820aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e0029").unwrap();
+        let fields =
+            decode_metadata(&bytecode[find_metadata_offset(&bytecode).unwrap()..]).unwrap();
+
+        assert_eq!(
+            fields,
+            vec![(
+                String::from("bzzr0"),
+                String::from("0xaacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e")
+            )]
+        );
+        assert_eq!(decode_metadata(&[0x01, 0x00, 0x01]), None);
+    }
+
+    #[test]
+    fn test_decode_metadata_huge_length() {
+        // a key, then a value, whose text string lengths overflow the offset
+        let key = [0xa1, 0x7b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x0a];
+        assert_eq!(decode_metadata(&key), None);
+
+        let value =
+            [0xa1, 0x61, 0x61, 0x7b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x0c];
+        assert_eq!(decode_metadata(&value), None);
+    }
+}
@@ -1,6 +1,14 @@
+pub mod chains;
 pub mod compiler;
+pub mod eof;
 pub mod evm;
+pub mod history;
+pub mod injected;
 pub mod lexers;
+pub mod metadata;
+pub mod pool;
+pub mod proof;
 pub mod rpc;
 pub mod selectors;
 pub mod signatures;
+pub mod transport;
@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ethers::providers::{Provider, ProviderError};
+use lazy_static::lazy_static;
+
+use super::transport::{self, Transport};
+
+// an endpoint which fails this many times in a row is skipped until its cooldown ends
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(30);
+
+lazy_static! {
+    // the health of every endpoint used during this run, shared by every pool that uses it so
+    // failures in one request are remembered by the next
+    static ref HEALTH: Mutex<HashMap<String, EndpointHealth>> = Mutex::new(HashMap::new());
+
+    // the number of requests made through each list of endpoints, used to round-robin between them
+    static ref REQUESTS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+
+    // the open WebSocket and IPC connection to each endpoint, reused by every request to it
+    static ref CONNECTIONS: Mutex<HashMap<String, Provider<Transport>>> = Mutex::new(HashMap::new());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+lazy_static! {
+    // WebSocket and IPC connections are driven by a task on the runtime they're opened on, so
+    // they're opened on one which lives as long as the process, rather than on the short-lived
+    // runtimes some callers block on, such as the VM's when forking state
+    static ref CONNECTION_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("heimdall-rpc")
+        .enable_all()
+        .build()
+        .expect("failed to build rpc connection runtime");
+}
+
+/// The health of a single RPC endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EndpointHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+
+    // when the endpoint can be used again after failing too many times in a row
+    pub unhealthy_until: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_until.is_none_or(|until| now >= until)
+    }
+}
+
+/// A pool of RPC endpoints, given as a comma-separated list of URLs. Requests are spread across
+/// the endpoints round-robin, and endpoints which keep failing, such as when they're down or
+/// rate limiting, are skipped for a while so requests fail over to the rest.
+///
+/// ```
+/// use heimdall_common::ether::pool::RpcPool;
+///
+/// let pool = RpcPool::new("https://eth.llamarpc.com, https://rpc.ankr.com/eth");
+/// assert_eq!(pool.endpoints(), ["https://eth.llamarpc.com", "https://rpc.ankr.com/eth"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RpcPool {
+    key: String,
+    endpoints: Vec<String>,
+}
+
+impl RpcPool {
+    pub fn new(rpc_url: &str) -> RpcPool {
+        let endpoints: Vec<String> = rpc_url
+            .split(',')
+            .map(|endpoint| endpoint.trim().to_string())
+            .filter(|endpoint| !endpoint.is_empty())
+            .collect();
+        RpcPool { key: endpoints.join(","), endpoints }
+    }
+
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    /// Picks the endpoint the next request should be sent to. Healthy endpoints are taken in
+    /// turn, and if every endpoint is unhealthy, the one which will recover soonest is used.
+    pub fn next_endpoint(&self) -> Option<String> {
+        if self.endpoints.is_empty() {
+            return None
+        }
+
+        let start = {
+            let mut requests = REQUESTS.lock().expect("rpc pool lock poisoned");
+            let count = requests.entry(self.key.clone()).or_insert(0);
+            *count += 1;
+            *count - 1
+        };
+
+        let health = HEALTH.lock().expect("rpc pool lock poisoned");
+        let now = Instant::now();
+        let endpoints = (0..self.endpoints.len())
+            .map(|i| &self.endpoints[(start + i) % self.endpoints.len()])
+            .collect::<Vec<&String>>();
+
+        endpoints
+            .iter()
+            .find(|endpoint| health.get(endpoint.as_str()).is_none_or(|h| h.is_healthy(now)))
+            .or_else(|| {
+                endpoints.iter().min_by_key(|endpoint| {
+                    health.get(endpoint.as_str()).and_then(|h| h.unhealthy_until)
+                })
+            })
+            .map(|endpoint| endpoint.to_string())
+    }
+
+    /// Picks the next endpoint, as in [`RpcPool::next_endpoint`], and connects to it over the
+    /// [`Transport`] its URL calls for. Endpoints which can't be connected to are marked as failed
+    /// and skipped.
+    pub async fn provider(&self) -> Option<(String, Provider<Transport>)> {
+        for _ in 0..self.endpoints.len() {
+            let endpoint = self.next_endpoint()?;
+            match self.connect(&endpoint).await {
+                Ok(provider) => return Some((endpoint, provider)),
+                Err(_) => self.report_failure(&endpoint),
+            }
+        }
+        None
+    }
+
+    /// Connects to the given endpoint over the [`Transport`] its URL calls for. WebSocket and IPC
+    /// connections stay open and are reused by later requests to the endpoint, from any pool,
+    /// until a request to it fails. HTTP providers are cheap to build, so a new one is used for
+    /// each request.
+    pub async fn connect(&self, endpoint: &str) -> Result<Provider<Transport>, ProviderError> {
+        if !transport::is_websocket(endpoint) && !transport::is_ipc(endpoint) {
+            return transport::connect(endpoint).await
+        }
+        if let Some(provider) =
+            CONNECTIONS.lock().expect("rpc pool lock poisoned").get(endpoint).cloned()
+        {
+            return Ok(provider)
+        }
+
+        let provider = open(endpoint).await?;
+        CONNECTIONS
+            .lock()
+            .expect("rpc pool lock poisoned")
+            .insert(endpoint.to_string(), provider.clone());
+        Ok(provider)
+    }
+
+    /// Connects to the next endpoint which supports subscriptions, such as `newHeads`, `logs`, and
+    /// `newPendingTransactions`. Only WebSocket and IPC endpoints do, so HTTP endpoints are
+    /// skipped.
+    ///
+    /// ```no_run
+    /// use ethers::providers::Middleware;
+    /// use heimdall_common::ether::pool::RpcPool;
+    ///
+    /// // let (_, provider) = RpcPool::new("wss://eth.example.com").pubsub_provider().await?;
+    /// // let mut blocks = provider.subscribe_blocks().await?;
+    /// ```
+    pub async fn pubsub_provider(&self) -> Option<(String, Provider<Transport>)> {
+        let pubsub = RpcPool {
+            key: self.key.clone(),
+            endpoints: self
+                .endpoints
+                .iter()
+                .filter(|endpoint| transport::is_websocket(endpoint) || transport::is_ipc(endpoint))
+                .cloned()
+                .collect(),
+        };
+        pubsub.provider().await
+    }
+
+    /// Records a successful request to the given endpoint.
+    pub fn report_success(&self, endpoint: &str) {
+        let mut health = HEALTH.lock().expect("rpc pool lock poisoned");
+        let health = health.entry(endpoint.to_string()).or_default();
+        health.successes += 1;
+        health.consecutive_failures = 0;
+        health.unhealthy_until = None;
+    }
+
+    /// Records a failed request to the given endpoint, which is skipped for a while if it has
+    /// failed too many times in a row. Its open connection, if any, is closed, so the next request
+    /// to it reconnects.
+    pub fn report_failure(&self, endpoint: &str) {
+        CONNECTIONS.lock().expect("rpc pool lock poisoned").remove(endpoint);
+
+        let mut health = HEALTH.lock().expect("rpc pool lock poisoned");
+        let health = health.entry(endpoint.to_string()).or_default();
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            health.unhealthy_until = Some(Instant::now() + FAILURE_COOLDOWN);
+        }
+    }
+
+    /// The health of each endpoint in the pool.
+    pub fn health(&self) -> Vec<(String, EndpointHealth)> {
+        let health = HEALTH.lock().expect("rpc pool lock poisoned");
+        self.endpoints
+            .iter()
+            .map(|endpoint| (endpoint.clone(), health.get(endpoint).cloned().unwrap_or_default()))
+            .collect()
+    }
+}
+
+/// Opens a WebSocket or IPC connection to the given endpoint, on the connection runtime.
+#[cfg(not(target_arch = "wasm32"))]
+async fn open(endpoint: &str) -> Result<Provider<Transport>, ProviderError> {
+    let endpoint = endpoint.to_string();
+    CONNECTION_RUNTIME
+        .spawn(async move { transport::connect(&endpoint).await })
+        .await
+        .map_err(|e| ProviderError::CustomError(e.to_string()))?
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn open(endpoint: &str) -> Result<Provider<Transport>, ProviderError> {
+    transport::connect(endpoint).await
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use std::{
+        io::Write,
+        os::unix::net::UnixListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    #[cfg(unix)]
+    use ethers::providers::Middleware;
+    #[cfg(unix)]
+    use serde_json::{json, Deserializer, Value};
+
+    use crate::ether::pool::RpcPool;
+
+    #[test]
+    fn test_round_robin() {
+        let pool = RpcPool::new("http://round-robin-a.invalid,http://round-robin-b.invalid");
+        let first = pool.next_endpoint().unwrap();
+        let second = pool.next_endpoint().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(pool.next_endpoint().unwrap(), first);
+    }
+
+    #[test]
+    fn test_failover() {
+        let pool = RpcPool::new("http://failover-a.invalid,http://failover-b.invalid");
+        for _ in 0..3 {
+            pool.report_failure("http://failover-a.invalid");
+        }
+
+        // the failing endpoint is skipped until its cooldown ends
+        for _ in 0..4 {
+            assert_eq!(pool.next_endpoint().unwrap(), "http://failover-b.invalid");
+        }
+
+        pool.report_success("http://failover-a.invalid");
+        let health = pool.health();
+        assert_eq!(health[0].1.failures, 3);
+        assert_eq!(health[0].1.consecutive_failures, 0);
+        assert!(pool.health().iter().all(|(_, health)| health.unhealthy_until.is_none()));
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        assert!(RpcPool::new("").next_endpoint().is_none());
+        assert!(RpcPool::new(" , ").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pubsub_provider_skips_http() {
+        assert!(RpcPool::new("http://pubsub-a.invalid").pubsub_provider().await.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_connections_are_reused() {
+        let path = std::env::temp_dir().join("heimdall_test_connections_are_reused.ipc");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let endpoint = path.to_str().unwrap().to_string();
+
+        // a node which answers every request with chain id 1, counting the connections made to it
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = connections.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let mut writer = stream.unwrap();
+                let reader = writer.try_clone().unwrap();
+                std::thread::spawn(move || {
+                    for request in Deserializer::from_reader(reader).into_iter::<Value>() {
+                        let Ok(request) = request else { return };
+                        let response =
+                            json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x1" });
+                        writer.write_all(response.to_string().as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        // each request is made on its own short-lived runtime, as the VM's are when forking
+        let pool = RpcPool::new(&endpoint);
+        let chain_id = || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async {
+                pool.connect(&endpoint).await.unwrap().get_chainid().await.unwrap().as_u64()
+            })
+        };
+        assert_eq!(chain_id(), 1);
+        assert_eq!(chain_id(), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // a failure closes the connection, so the next request reconnects
+        pool.report_failure(&endpoint);
+        assert_eq!(chain_id(), 1);
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+}
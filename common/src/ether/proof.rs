@@ -0,0 +1,279 @@
+use ethers::{
+    types::{Bytes, H160, H256},
+    utils::{keccak256, rlp::Rlp},
+};
+
+// the root of an empty trie, which is the hash of the RLP encoding of an empty string
+const EMPTY_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// A reference to a trie node from its parent, which is the node's hash, unless the node is
+/// shorter than a hash and embedded in its parent.
+enum NodeReference {
+    Hash(Vec<u8>),
+    Embedded(Vec<u8>),
+}
+
+/// Verify a Merkle-Patricia proof, such as those returned by `eth_getProof`, that the given key
+/// is or isn't in the trie with the given root. The key is hashed, as it is in Ethereum's state and
+/// storage tries. Returns the value at the key, or `None` if the proof shows it isn't in the trie,
+/// or an error if the proof is invalid.
+///
+/// ```
+/// use ethers::types::H256;
+/// use heimdall_common::ether::proof::verify_proof;
+///
+/// // nothing is in an empty trie
+/// let empty_root = ethers::utils::keccak256([0x80]);
+/// assert_eq!(verify_proof(H256::from(empty_root), &[0u8; 32], &[]), Ok(None));
+/// ```
+pub fn verify_proof(root: H256, key: &[u8], proof: &[Bytes]) -> Result<Option<Vec<u8>>, String> {
+    if root.as_bytes() == EMPTY_ROOT {
+        return Ok(None)
+    }
+
+    let path = nibbles(&keccak256(key));
+    let mut position = 0;
+    let mut nodes = proof.iter();
+    let mut reference = NodeReference::Hash(root.as_bytes().to_vec());
+    loop {
+        let node = match reference {
+            NodeReference::Hash(hash) => {
+                let node = nodes.next().ok_or("the proof is missing nodes")?;
+                if keccak256(node).as_slice() != hash {
+                    return Err(String::from("a proof node doesn't match its hash"))
+                }
+                node.to_vec()
+            }
+            NodeReference::Embedded(node) => node,
+        };
+
+        let node = Rlp::new(&node);
+        let child = match node.item_count().map_err(|e| format!("malformed trie node: {e}"))? {
+            // a branch node, with a child for each nibble
+            17 => {
+                let nibble = *path.get(position).ok_or("the key is shorter than the trie")?;
+                position += 1;
+                node.at(nibble as usize).map_err(|e| format!("malformed branch node: {e}"))?
+            }
+
+            // a leaf or extension node, which holds part of the path
+            2 => {
+                let encoded_path = node
+                    .at(0)
+                    .and_then(|path| path.data().map(|path| path.to_vec()))
+                    .map_err(|e| format!("malformed trie node: {e}"))?;
+                let (partial_path, is_leaf) = decode_path(&encoded_path)?;
+                if !path[position..].starts_with(&partial_path) {
+                    return Ok(None)
+                }
+                position += partial_path.len();
+
+                let child = node.at(1).map_err(|e| format!("malformed trie node: {e}"))?;
+                if is_leaf {
+                    return match position == path.len() {
+                        true => Ok(Some(
+                            child.data().map_err(|e| format!("malformed leaf: {e}"))?.to_vec(),
+                        )),
+                        false => Ok(None),
+                    }
+                }
+                child
+            }
+            count => return Err(format!("trie nodes have 2 or 17 items, not {count}")),
+        };
+
+        reference = match child.is_list() {
+            true => NodeReference::Embedded(child.as_raw().to_vec()),
+            false => match child.data().map_err(|e| format!("malformed trie node: {e}"))? {
+                [] => return Ok(None),
+                hash if hash.len() == 32 => NodeReference::Hash(hash.to_vec()),
+                _ => return Err(String::from("trie node references are 32 byte hashes")),
+            },
+        };
+    }
+}
+
+/// Verify the proof of an account against a block's state root, returning the root of the
+/// account's storage trie, which is the empty trie's root if the account doesn't exist.
+pub fn verify_account_proof(
+    state_root: H256,
+    address: H160,
+    proof: &[Bytes],
+) -> Result<H256, String> {
+    match verify_proof(state_root, address.as_bytes(), proof)? {
+        Some(account) => {
+            // accounts are encoded as [nonce, balance, storage root, code hash]
+            let storage_root = Rlp::new(&account)
+                .at(2)
+                .and_then(|storage_root| storage_root.data().map(|root| root.to_vec()))
+                .map_err(|e| format!("malformed account: {e}"))?;
+            match storage_root.len() {
+                32 => Ok(H256::from_slice(&storage_root)),
+                _ => Err(String::from("malformed account: storage roots are 32 bytes")),
+            }
+        }
+        None => Ok(H256::from(EMPTY_ROOT)),
+    }
+}
+
+/// Verify the proof of a storage slot against the root of the account's storage trie, returning
+/// the slot's value, which is zero if it isn't in the trie.
+pub fn verify_storage_proof(
+    storage_root: H256,
+    slot: H256,
+    proof: &[Bytes],
+) -> Result<H256, String> {
+    match verify_proof(storage_root, slot.as_bytes(), proof)? {
+        Some(value) => {
+            // values are stored as RLP encoded integers, without leading zeros
+            let value = Rlp::new(&value)
+                .data()
+                .map(|value| value.to_vec())
+                .map_err(|e| format!("malformed storage value: {e}"))?;
+            match value.len() {
+                0..=32 => {
+                    let mut padded = [0u8; 32];
+                    padded[32 - value.len()..].copy_from_slice(&value);
+                    Ok(H256::from(padded))
+                }
+                _ => Err(String::from("malformed storage value: values are at most 32 bytes")),
+            }
+        }
+        None => Ok(H256::zero()),
+    }
+}
+
+/// splits bytes into nibbles, high nibble first
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+/// decodes the hex-prefix encoded path of a leaf or extension node, returning its nibbles and
+/// whether the node is a leaf
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), String> {
+    let flag = encoded.first().ok_or("trie node paths can't be empty")? >> 4;
+    let mut path = nibbles(encoded);
+    match flag {
+        // even paths are padded with a zero nibble after the flag, and odd paths aren't
+        0 | 2 => path.drain(..2),
+        1 | 3 => path.drain(..1),
+        _ => return Err(format!("invalid trie node path flag {flag}")),
+    };
+    Ok((path, flag >= 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        types::{Bytes, H160, H256},
+        utils::{keccak256, rlp::RlpStream},
+    };
+
+    use crate::ether::proof::{
+        decode_path, verify_account_proof, verify_proof, verify_storage_proof,
+    };
+
+    /// a leaf node holding the given nibbles of its path, hex-prefix encoded, and value
+    fn leaf(path: &[u8], value: &[u8]) -> Vec<u8> {
+        let mut encoded = match path.len() % 2 {
+            0 => vec![0x20],
+            _ => vec![0x30 | path[0]],
+        };
+        let rest = &path[path.len() % 2..];
+        encoded.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&encoded);
+        stream.append(&value.to_vec());
+        stream.out().to_vec()
+    }
+
+    fn nibbles(key: &[u8]) -> Vec<u8> {
+        keccak256(key).iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+    }
+
+    fn storage_value(value: u8) -> Vec<u8> {
+        ethers::utils::rlp::encode(&vec![value]).to_vec()
+    }
+
+    #[test]
+    fn test_decode_path() {
+        assert_eq!(decode_path(&[0x20, 0x12]), Ok((vec![1, 2], true)));
+        assert_eq!(decode_path(&[0x31, 0x23]), Ok((vec![1, 2, 3], true)));
+        assert_eq!(decode_path(&[0x00, 0x12]), Ok((vec![1, 2], false)));
+        assert_eq!(decode_path(&[0x11]), Ok((vec![1], false)));
+        assert!(decode_path(&[0x40]).is_err());
+    }
+
+    #[test]
+    fn test_verify_storage_proof() {
+        // a trie with a single slot is a single leaf
+        let slot = H256::zero();
+        let node = leaf(&nibbles(slot.as_bytes()), &storage_value(42));
+        let root = H256::from(keccak256(&node));
+        let proof = [Bytes::from(node.clone())];
+
+        assert_eq!(verify_storage_proof(root, slot, &proof), Ok(H256::from_low_u64_be(42)));
+
+        // other slots aren't in the trie, so they're zero
+        assert_eq!(verify_storage_proof(root, H256::from_low_u64_be(1), &proof), Ok(H256::zero()));
+
+        // a proof of a different value doesn't match the root
+        let forged = [Bytes::from(leaf(&nibbles(slot.as_bytes()), &storage_value(43)))];
+        assert!(verify_storage_proof(root, slot, &forged).is_err());
+        assert!(verify_storage_proof(root, slot, &[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_branch_proof() {
+        // two slots whose hashes start with different nibbles are leaves of a branch
+        let (slot_a, slot_b) = (H256::zero(), H256::from_low_u64_be(1));
+        let (path_a, path_b) = (nibbles(slot_a.as_bytes()), nibbles(slot_b.as_bytes()));
+        assert_ne!(path_a[0], path_b[0]);
+
+        let leaf_a = leaf(&path_a[1..], &storage_value(1));
+        let leaf_b = leaf(&path_b[1..], &storage_value(2));
+        let mut branch = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            match nibble {
+                n if n == path_a[0] => branch.append(&keccak256(&leaf_a).to_vec()),
+                n if n == path_b[0] => branch.append(&keccak256(&leaf_b).to_vec()),
+                _ => branch.append_empty_data(),
+            };
+        }
+        branch.append_empty_data();
+        let branch = branch.out().to_vec();
+        let root = H256::from(keccak256(&branch));
+
+        let proof = [Bytes::from(branch.clone()), Bytes::from(leaf_b)];
+        assert_eq!(verify_storage_proof(root, slot_b, &proof), Ok(H256::from_low_u64_be(2)));
+
+        // a proof that a slot isn't in the trie ends at the branch's empty child
+        let empty_slot = (2..)
+            .map(H256::from_low_u64_be)
+            .find(|slot| ![path_a[0], path_b[0]].contains(&nibbles(slot.as_bytes())[0]))
+            .unwrap();
+        assert_eq!(verify_proof(root, empty_slot.as_bytes(), &[Bytes::from(branch)]), Ok(None));
+    }
+
+    #[test]
+    fn test_verify_account_proof() {
+        let address = H160::repeat_byte(0xaa);
+        let storage_root = H256::repeat_byte(0x11);
+        let mut account = RlpStream::new_list(4);
+        account.append(&1u64);
+        account.append(&0u64);
+        account.append(&storage_root.as_bytes().to_vec());
+        account.append(&keccak256([]).to_vec());
+
+        let node = leaf(&nibbles(address.as_bytes()), &account.out());
+        let state_root = H256::from(keccak256(&node));
+        assert_eq!(
+            verify_account_proof(state_root, address, &[Bytes::from(node)]),
+            Ok(storage_root)
+        );
+    }
+}
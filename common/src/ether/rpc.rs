@@ -1,13 +1,197 @@
-use std::{str::FromStr, time::Duration};
+use std::{
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::utils::io::logging::Logger;
-use backoff::ExponentialBackoff;
+use crate::{
+    ether::{
+        pool::RpcPool,
+        transport::{is_ipc, is_websocket},
+    },
+    resources::quota::TokenBucket,
+    utils::{
+        failure::{Failure, FailureClass},
+        http::client_builder,
+        io::logging::Logger,
+        strings::encode_hex,
+    },
+};
+use backoff::{backoff::Backoff, ExponentialBackoff};
 use ethers::{
     core::types::Address,
-    providers::{Http, Middleware, Provider},
-    types::{Transaction, H256},
+    providers::{Middleware, RpcError},
+    types::{Transaction, TransactionReceipt, H256, U256},
+    utils::keccak256,
 };
 use heimdall_cache::{read_cache, store_cache};
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use tracing::{debug, instrument, trace};
+
+// the most requests sent to the node in a single batch
+const MAX_BATCH_SIZE: usize = 100;
+
+// whether responses which can never change, such as mined transactions and state at a fixed
+// block, are kept in heimdall's cache
+static RPC_CACHE: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables caching RPC responses on disk. When disabled, every request is sent to the
+/// node, and nothing is written to the cache.
+pub fn set_rpc_cache(enabled: bool) {
+    RPC_CACHE.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether RPC responses are cached on disk.
+pub fn rpc_cache_enabled() -> bool {
+    RPC_CACHE.load(Ordering::Relaxed)
+}
+
+lazy_static! {
+    // the token bucket every batched request takes from, if their rate is limited
+    static ref RPC_RATE_LIMIT: Mutex<Option<TokenBucket>> = Mutex::new(None);
+}
+
+/// Limits the requests sent with [`batch_request`] to the given number per second, shared by
+/// every batch, so large jobs stay within a public provider's limits. A limit of 0 removes it.
+pub fn set_rpc_rate_limit(requests_per_second: f64) {
+    *RPC_RATE_LIMIT.lock().expect("rate limit lock poisoned") =
+        (requests_per_second > 0.0).then(|| TokenBucket::new(requests_per_second));
+}
+
+// the most time a single request to the node may take, in milliseconds
+static RPC_TIMEOUT: AtomicU64 = AtomicU64::new(30_000);
+
+// how many times a failed request to the node is retried before giving up
+static RPC_RETRIES: AtomicU32 = AtomicU32::new(5);
+
+/// Set the most time a single request to the node may take before it fails, and is retried.
+/// Slow archive nodes may need a longer timeout for historical state. The default is 30 seconds.
+pub fn set_rpc_timeout(timeout: Duration) {
+    RPC_TIMEOUT.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// The most time a single request to the node may take.
+pub fn rpc_timeout() -> Duration {
+    Duration::from_millis(RPC_TIMEOUT.load(Ordering::Relaxed))
+}
+
+/// Set how many times a failed request to the node is retried, failing over to the next provider
+/// each time, before giving up. The default is 5.
+pub fn set_rpc_retries(retries: u32) {
+    RPC_RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// How many times a failed request to the node is retried.
+pub fn rpc_retries() -> u32 {
+    RPC_RETRIES.load(Ordering::Relaxed)
+}
+
+/// An exponential backoff between attempts of a request, which gives up after a number of
+/// retries rather than after a fixed time, so slow requests aren't abandoned early.
+///
+/// ```
+/// use backoff::backoff::Backoff;
+/// use heimdall_common::ether::rpc::RetryBackoff;
+///
+/// let mut backoff = RetryBackoff::new(2);
+/// assert!(backoff.next_backoff().is_some());
+/// assert!(backoff.next_backoff().is_some());
+/// assert!(backoff.next_backoff().is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryBackoff {
+    backoff: ExponentialBackoff,
+    retries: u32,
+    retries_remaining: u32,
+}
+
+impl RetryBackoff {
+    /// A backoff which allows the given number of retries, waiting a second before the first, and
+    /// up to 10 seconds between later ones.
+    pub fn new(retries: u32) -> Self {
+        Self {
+            backoff: ExponentialBackoff {
+                initial_interval: Duration::from_secs(1),
+                max_interval: Duration::from_secs(10),
+                max_elapsed_time: None,
+                ..ExponentialBackoff::default()
+            },
+            retries,
+            retries_remaining: retries,
+        }
+    }
+}
+
+impl Backoff for RetryBackoff {
+    fn reset(&mut self) {
+        self.backoff.reset();
+        self.retries_remaining = self.retries;
+    }
+
+    fn next_backoff(&mut self) -> Option<Duration> {
+        if self.retries_remaining == 0 {
+            return None
+        }
+        self.retries_remaining -= 1;
+        self.backoff.next_backoff()
+    }
+}
+
+/// The backoff between attempts of a request to the node, which allows [`rpc_retries`] retries.
+pub fn rpc_backoff() -> RetryBackoff {
+    RetryBackoff::new(rpc_retries())
+}
+
+/// waits until the rate limit set with [`set_rpc_rate_limit`] allows another request
+async fn throttle() {
+    loop {
+        let wait = match RPC_RATE_LIMIT.lock().expect("rate limit lock poisoned").as_mut() {
+            Some(bucket) => bucket.take(Instant::now()),
+            None => return,
+        };
+        match wait {
+            Ok(()) => return,
+            Err(wait) => tokio::time::sleep(wait).await,
+        }
+    }
+}
+
+/// The key an RPC response is cached under, which is the hash of the chain the request is made
+/// on, its method, and its parameters.
+///
+/// ```
+/// use heimdall_common::ether::rpc::rpc_cache_key;
+///
+/// let key = rpc_cache_key(1, "eth_getTransactionByHash", &["0x01"]);
+/// assert!(key.starts_with("rpc."));
+/// assert_ne!(key, rpc_cache_key(5, "eth_getTransactionByHash", &["0x01"]));
+/// ```
+pub fn rpc_cache_key(chain_id: u64, method: &str, params: &[&str]) -> String {
+    let request = format!("{chain_id}:{method}:{}", params.join(","));
+    format!("rpc.{}", encode_hex(keccak256(request).to_vec()))
+}
+
+/// Reads a cached RPC response, if caching is enabled.
+fn read_response<T: serde::de::DeserializeOwned>(key: &str) -> Option<T> {
+    if !rpc_cache_enabled() {
+        return None
+    }
+    read_cache::<String>(key).and_then(|response| serde_json::from_str(&response).ok())
+}
+
+/// Caches an RPC response, if caching is enabled.
+fn store_response<T: serde::Serialize>(key: &str, response: &T) {
+    if !rpc_cache_enabled() {
+        return
+    }
+    if let Ok(response) = serde_json::to_string(response) {
+        store_cache(key, response, None);
+    }
+}
 
 /// Get the chainId of the provided RPC URL
 ///
@@ -17,37 +201,35 @@ use heimdall_cache::{read_cache, store_cache};
 /// // let chain_id = chain_id("https://eth.llamarpc.com").await.unwrap();
 /// //assert_eq!(chain_id, 1);
 /// ```
+#[instrument(level = "debug", skip_all)]
 pub async fn chain_id(rpc_url: &str) -> Result<u64, Box<dyn std::error::Error>> {
+    // the pool is built once, so each attempt fails over to the next endpoint in it
+    let pool = RpcPool::new(rpc_url);
     backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
+        rpc_backoff(),
     || async {
         // get a new logger
         let logger = Logger::default();
 
-        logger.debug_max(&format!("checking chain id for rpc url: '{}'", &rpc_url));
+        trace!("checking chain id");
 
         // check the cache for a matching rpc url
         let cache_key = format!("chain_id.{}", &rpc_url.replace('/', "").replace(['.', ':'], "-"));
-        if let Some(chain_id) = read_cache(&cache_key) {
-            logger.debug(&format!("found cached chain id for rpc url: {:?}", &rpc_url));
+        if let Some(chain_id) = read_cache(&cache_key).filter(|_| rpc_cache_enabled()) {
+            debug!("found cached chain id");
             return Ok(chain_id)
         }
 
         // make sure the RPC provider isn't empty
-        if rpc_url.is_empty() {
-            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
-            std::process::exit(1);
+        if pool.is_empty() {
+            return Err(backoff::Error::Permanent(Failure::new(FailureClass::InvalidInput, "reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.")))
         }
 
-        // create new provider
-        let provider = match Provider::<Http>::try_from(rpc_url) {
-            Ok(provider) => provider,
-            Err(_) => {
-                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
-                std::process::exit(1)
+        // each attempt is made with the next provider in the pool, failing over between them
+        let (endpoint, provider) = match pool.provider().await {
+            Some(provider) => provider,
+            None => {
+                return Err(backoff::Error::Permanent(Failure::new(FailureClass::Rpc, format!("failed to connect to RPC provider '{}' .", &rpc_url))))
             }
         };
 
@@ -55,20 +237,29 @@ pub async fn chain_id(rpc_url: &str) -> Result<u64, Box<dyn std::error::Error>>
         let chain_id = match provider.get_chainid().await {
             Ok(chain_id) => chain_id,
             Err(_) => {
-                logger.error(&format!("failed to fetch chain id from '{}' .", &rpc_url));
-                return Err(backoff::Error::Transient { err: (), retry_after: Some(Duration::from_secs(1)) })
+                pool.report_failure(&endpoint);
+                let message = format!("failed to fetch chain id from '{}' .", &rpc_url);
+                logger.error(&message);
+                return Err(backoff::Error::Transient {
+                    err: Failure::new(FailureClass::Rpc, message),
+                    retry_after: None,
+                })
             }
         };
 
+        pool.report_success(&endpoint);
+
         // cache the results
-        store_cache(&cache_key, chain_id.as_u64(), None);
+        if rpc_cache_enabled() {
+            store_cache(&cache_key, chain_id.as_u64(), None);
+        }
 
-        logger.debug_max(&format!("chain_id is '{}'", &chain_id));
+        trace!(chain_id = %chain_id, "fetched chain id");
 
         Ok(chain_id.as_u64())
     })
     .await
-    .map_err(|_| Box::from("failed to fetch chain id"))
+    .map_err(Box::from)
 }
 
 /// Get the bytecode of the provided contract address
@@ -79,15 +270,15 @@ pub async fn chain_id(rpc_url: &str) -> Result<u64, Box<dyn std::error::Error>>
 /// // let bytecode = get_code("0x0", "https://eth.llamarpc.com").await;
 /// // assert!(bytecode.is_ok());
 /// ```
+#[instrument(level = "debug", skip(rpc_url))]
 pub async fn get_code(
     contract_address: &str,
     rpc_url: &str,
 ) -> Result<String, Box<dyn std::error::Error>> {
+    // the pool is built once, so each attempt fails over to the next endpoint in it
+    let pool = RpcPool::new(rpc_url);
     backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
+        rpc_backoff(),
     || async {
         // get a new logger
         let logger = Logger::default();
@@ -95,27 +286,26 @@ pub async fn get_code(
         // get chain_id
         let _chain_id = chain_id(rpc_url).await.unwrap_or(1);
 
-        logger
-            .debug_max(&format!("fetching bytecode from node for contract: '{}' .", &contract_address));
+        trace!("fetching bytecode from node");
 
         // check the cache for a matching address
-        if let Some(bytecode) = read_cache(&format!("contract.{}.{}", &_chain_id, &contract_address)) {
-            logger.debug(&format!("found cached bytecode for '{}' .", &contract_address));
+        if let Some(bytecode) = read_cache(&format!("contract.{}.{}", &_chain_id, &contract_address))
+            .filter(|_| rpc_cache_enabled())
+        {
+            debug!("found cached bytecode");
             return Ok(bytecode)
         }
 
         // make sure the RPC provider isn't empty
-        if rpc_url.is_empty() {
-            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
-            std::process::exit(1);
+        if pool.is_empty() {
+            return Err(backoff::Error::Permanent(Failure::new(FailureClass::InvalidInput, "reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.")))
         }
 
-        // create new provider
-        let provider = match Provider::<Http>::try_from(rpc_url) {
-            Ok(provider) => provider,
-            Err(_) => {
-                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
-                std::process::exit(1)
+        // each attempt is made with the next provider in the pool, failing over between them
+        let (endpoint, provider) = match pool.provider().await {
+            Some(provider) => provider,
+            None => {
+                return Err(backoff::Error::Permanent(Failure::new(FailureClass::Rpc, format!("failed to connect to RPC provider '{}' .", &rpc_url))))
             }
         };
 
@@ -123,8 +313,7 @@ pub async fn get_code(
         let address = match contract_address.parse::<Address>() {
             Ok(address) => address,
             Err(_) => {
-                logger.error(&format!("failed to parse address '{}' .", &contract_address));
-                std::process::exit(1)
+                return Err(backoff::Error::Permanent(Failure::new(FailureClass::UnresolvedTarget, format!("failed to parse address '{}' .", &contract_address))))
             }
         };
 
@@ -132,22 +321,31 @@ pub async fn get_code(
         let bytecode_as_bytes = match provider.get_code(address, None).await {
             Ok(bytecode) => bytecode,
             Err(_) => {
-                logger.error(&format!("failed to fetch bytecode from '{}' .", &contract_address));
-                return Err(backoff::Error::Transient { err: (), retry_after: Some(Duration::from_secs(1)) })
+                pool.report_failure(&endpoint);
+                let message = format!("failed to fetch bytecode from '{}' .", &contract_address);
+                logger.error(&message);
+                return Err(backoff::Error::Transient {
+                    err: Failure::new(FailureClass::Rpc, message),
+                    retry_after: None,
+                })
             }
         };
 
+        pool.report_success(&endpoint);
+
         // cache the results
-        store_cache(
-            &format!("contract.{}.{}", &_chain_id, &contract_address),
-            bytecode_as_bytes.to_string().replacen("0x", "", 1),
-            None,
-        );
+        if rpc_cache_enabled() {
+            store_cache(
+                &format!("contract.{}.{}", &_chain_id, &contract_address),
+                bytecode_as_bytes.to_string().replacen("0x", "", 1),
+                None,
+            );
+        }
 
         Ok(bytecode_as_bytes.to_string())
     })
     .await
-    .map_err(|_| Box::from("failed to fetch bytecode"))
+    .map_err(Box::from)
 }
 
 /// Get the raw transaction data of the provided transaction hash
@@ -158,36 +356,42 @@ pub async fn get_code(
 /// // let bytecode = get_code("0x0", "https://eth.llamarpc.com").await;
 /// // assert!(bytecode.is_ok());
 /// ```
+#[instrument(level = "debug", skip(rpc_url))]
 pub async fn get_transaction(
     transaction_hash: &str,
     rpc_url: &str,
 ) -> Result<Transaction, Box<dyn std::error::Error>> {
+    // the pool is built once, so each attempt fails over to the next endpoint in it
+    let pool = RpcPool::new(rpc_url);
     backoff::future::retry(
-        ExponentialBackoff {
-            max_elapsed_time: Some(Duration::from_secs(10)),
-            ..ExponentialBackoff::default()
-        },
+        rpc_backoff(),
     || async {
         // get a new logger
         let logger = Logger::default();
 
-        logger.debug_max(&format!(
-            "fetching calldata from node for transaction: '{}' .",
-            &transaction_hash
-        ));
+        trace!("fetching transaction from node");
+
+        // mined transactions never change, so they're cached
+        let cache_key = rpc_cache_key(
+            chain_id(rpc_url).await.unwrap_or(1),
+            "eth_getTransactionByHash",
+            &[transaction_hash],
+        );
+        if let Some(tx) = read_response::<Transaction>(&cache_key) {
+            debug!("found cached transaction");
+            return Ok(tx)
+        }
 
         // make sure the RPC provider isn't empty
-        if rpc_url.is_empty() {
-            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
-            std::process::exit(1);
+        if pool.is_empty() {
+            return Err(backoff::Error::Permanent(Failure::new(FailureClass::InvalidInput, "reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.")))
         }
 
-        // create new provider
-        let provider = match Provider::<Http>::try_from(rpc_url) {
-            Ok(provider) => provider,
-            Err(_) => {
-                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
-                std::process::exit(1)
+        // each attempt is made with the next provider in the pool, failing over between them
+        let (endpoint, provider) = match pool.provider().await {
+            Some(provider) => provider,
+            None => {
+                return Err(backoff::Error::Permanent(Failure::new(FailureClass::Rpc, format!("failed to connect to RPC provider '{}' .", &rpc_url))))
             }
         };
 
@@ -195,26 +399,452 @@ pub async fn get_transaction(
         let transaction_hash = match H256::from_str(transaction_hash) {
             Ok(transaction_hash) => transaction_hash,
             Err(_) => {
-                logger.error(&format!("failed to parse transaction hash '{}' .", &transaction_hash));
-                std::process::exit(1)
+                return Err(backoff::Error::Permanent(Failure::new(FailureClass::UnresolvedTarget, format!("failed to parse transaction hash '{}' .", &transaction_hash))))
             }
         };
 
         // fetch the transaction from the node
         Ok(match provider.get_transaction(transaction_hash).await {
             Ok(tx) => match tx {
-                Some(tx) => tx,
+                Some(tx) => {
+                    pool.report_success(&endpoint);
+                    if tx.block_number.is_some() {
+                        store_response(&cache_key, &tx);
+                    }
+                    tx
+                }
                 None => {
-                    logger.error(&format!("transaction '{}' doesn't exist.", &transaction_hash));
-                    std::process::exit(1)
+                    return Err(backoff::Error::Permanent(Failure::new(FailureClass::UnresolvedTarget, format!("transaction '{}' doesn't exist.", &transaction_hash))))
                 }
             },
             Err(_) => {
-                logger.error(&format!("failed to fetch calldata from '{}' .", &transaction_hash));
-                return Err(backoff::Error::Transient { err: (), retry_after: Some(Duration::from_secs(1)) })
+                pool.report_failure(&endpoint);
+                let message = format!("failed to fetch calldata from '{}' .", &transaction_hash);
+                logger.error(&message);
+                return Err(backoff::Error::Transient {
+                    err: Failure::new(FailureClass::Rpc, message),
+                    retry_after: None,
+                })
             }
         })
     })
     .await
-    .map_err(|_| Box::from("failed to fetch calldata"))
+    .map_err(Box::from)
+}
+
+/// Get the receipt of the provided transaction hash
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::get_transaction_receipt;
+///
+/// // let receipt = get_transaction_receipt("0x0", "https://eth.llamarpc.com").await;
+/// // assert!(receipt.is_ok());
+/// ```
+#[instrument(level = "debug", skip(rpc_url))]
+pub async fn get_transaction_receipt(
+    transaction_hash: &str,
+    rpc_url: &str,
+) -> Result<TransactionReceipt, Box<dyn std::error::Error>> {
+    // the pool is built once, so each attempt fails over to the next endpoint in it
+    let pool = RpcPool::new(rpc_url);
+    backoff::future::retry(
+        rpc_backoff(),
+    || async {
+        // get a new logger
+        let logger = Logger::default();
+
+        trace!("fetching receipt from node");
+
+        // receipts only exist once a transaction is mined, after which they never change
+        let cache_key = rpc_cache_key(
+            chain_id(rpc_url).await.unwrap_or(1),
+            "eth_getTransactionReceipt",
+            &[transaction_hash],
+        );
+        if let Some(receipt) = read_response::<TransactionReceipt>(&cache_key) {
+            debug!("found cached receipt");
+            return Ok(receipt)
+        }
+
+        // make sure the RPC provider isn't empty
+        if pool.is_empty() {
+            logger.error("reading on-chain data requires an RPC provider. Use `heimdall --help` for more information.");
+            return Err(backoff::Error::Permanent(()))
+        }
+
+        // each attempt is made with the next provider in the pool, failing over between them
+        let (endpoint, provider) = match pool.provider().await {
+            Some(provider) => provider,
+            None => {
+                logger.error(&format!("failed to connect to RPC provider '{}' .", &rpc_url));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // safely unwrap the transaction hash
+        let transaction_hash = match H256::from_str(transaction_hash) {
+            Ok(transaction_hash) => transaction_hash,
+            Err(_) => {
+                logger.error(&format!("failed to parse transaction hash '{}' .", &transaction_hash));
+                return Err(backoff::Error::Permanent(()))
+            }
+        };
+
+        // fetch the receipt from the node
+        match provider.get_transaction_receipt(transaction_hash).await {
+            Ok(Some(receipt)) => {
+                pool.report_success(&endpoint);
+                store_response(&cache_key, &receipt);
+                Ok(receipt)
+            }
+            Ok(None) => {
+                pool.report_success(&endpoint);
+                logger.error(&format!("transaction '{}' hasn't been mined.", &transaction_hash));
+                Err(backoff::Error::Permanent(()))
+            }
+            Err(_) => {
+                pool.report_failure(&endpoint);
+                logger.error(&format!("failed to fetch receipt for '{}' .", &transaction_hash));
+                Err(backoff::Error::Transient { err: (), retry_after: None })
+            }
+        }
+    })
+    .await
+    .map_err(|_| Box::from("failed to fetch receipt"))
+}
+
+/// Sends the given JSON-RPC requests, as `(method, params)` pairs, to the node in batches, which
+/// takes far fewer round trips than sending them one at a time. Providers which don't support
+/// batching are sent each request individually instead. \
+/// \
+/// Returns the result of each request in the order they were given, or the error the node
+/// returned for it.
+///
+/// ```no_run
+/// use heimdall_common::ether::rpc::batch_request;
+/// use serde_json::json;
+///
+/// // let results = batch_request(
+/// //     "https://eth.llamarpc.com",
+/// //     &[("eth_blockNumber", json!([])), ("eth_chainId", json!([]))],
+/// // ).await;
+/// ```
+#[instrument(level = "debug", skip_all, fields(requests = requests.len()))]
+pub async fn batch_request(
+    rpc_url: &str,
+    requests: &[(&str, Value)],
+) -> Result<Vec<Result<Value, String>>, Box<dyn std::error::Error>> {
+    let pool = RpcPool::new(rpc_url);
+    if pool.is_empty() {
+        return Err(Box::from("reading on-chain data requires an RPC provider"))
+    }
+
+    let client = client_builder(rpc_timeout()).build()?;
+    let mut results = Vec::with_capacity(requests.len());
+    for batch in requests.chunks(MAX_BATCH_SIZE) {
+        // each request in the batch counts towards the rate limit
+        for _ in batch {
+            throttle().await;
+        }
+        debug!(size = batch.len(), "sending batch");
+
+        let body = Value::Array(
+            batch
+                .iter()
+                .enumerate()
+                .map(|(id, (method, params))| {
+                    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+                })
+                .collect(),
+        );
+
+        // each attempt is made with the next provider in the pool, failing over between them
+        let responses = backoff::future::retry(rpc_backoff(), || async {
+            let endpoint = pool.next_endpoint().ok_or(backoff::Error::Permanent(()))?;
+            // WebSocket and IPC endpoints can't be posted to, so the batch is sent over the
+            // socket instead, as it is through the injected callback when targeting wasm32
+            if cfg!(target_arch = "wasm32") || is_websocket(&endpoint) || is_ipc(&endpoint) {
+                return match send_over_socket(&pool, &endpoint, batch).await {
+                    Ok(responses) => {
+                        pool.report_success(&endpoint);
+                        Ok(responses)
+                    }
+                    Err(e) => {
+                        Logger::default()
+                            .debug_max(&format!("batch request to '{endpoint}' failed: {e}"));
+                        pool.report_failure(&endpoint);
+                        Err(backoff::Error::Transient { err: (), retry_after: None })
+                    }
+                }
+            }
+
+            let responses = match post(&client, &endpoint, &body).await {
+                Ok(Value::Array(responses)) => Ok(responses),
+
+                // the provider doesn't support batching, so each request is sent on its own
+                Ok(_) => {
+                    let mut responses = Vec::with_capacity(batch.len());
+                    for request in body.as_array().expect("batch body is an array") {
+                        match post(&client, &endpoint, request).await {
+                            Ok(response) => responses.push(response),
+                            Err(e) => {
+                                responses.clear();
+                                Logger::default()
+                                    .debug_max(&format!("request to '{endpoint}' failed: {e}"));
+                                break
+                            }
+                        }
+                    }
+                    match responses.len() == batch.len() {
+                        true => Ok(responses),
+                        false => Err(()),
+                    }
+                }
+                Err(e) => {
+                    Logger::default()
+                        .debug_max(&format!("batch request to '{endpoint}' failed: {e}"));
+                    Err(())
+                }
+            };
+
+            match responses {
+                Ok(responses) => {
+                    pool.report_success(&endpoint);
+                    Ok(responses)
+                }
+                Err(_) => {
+                    pool.report_failure(&endpoint);
+                    Err(backoff::Error::Transient { err: (), retry_after: None })
+                }
+            }
+        })
+        .await
+        .map_err(|_| Box::<dyn std::error::Error>::from("failed to send batch request"))?;
+
+        // responses may come back in any order, so they're matched to requests by id
+        let mut batch_results = vec![Err(String::from("no response")); batch.len()];
+        for response in responses {
+            let id = match response.get("id").and_then(Value::as_u64) {
+                Some(id) if (id as usize) < batch.len() => id as usize,
+                _ => continue,
+            };
+            batch_results[id] = match (response.get("result"), response.get("error")) {
+                (_, Some(error)) if !error.is_null() => Err(error.to_string()),
+                (Some(result), _) => Ok(result.clone()),
+                _ => Err(String::from("malformed response")),
+            };
+        }
+        results.extend(batch_results);
+    }
+
+    Ok(results)
+}
+
+/// Posts the given JSON-RPC request body to the endpoint, returning the decoded response.
+async fn post(
+    client: &reqwest::Client,
+    endpoint: &str,
+    body: &Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let response = client
+        .post(endpoint)
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(serde_json::from_str(&response.text().await?)?)
+}
+
+/// Sends each of the given requests over a WebSocket or IPC connection to the endpoint, returning
+/// responses in the same shape a batch posted over HTTP would get.
+async fn send_over_socket(
+    pool: &RpcPool,
+    endpoint: &str,
+    batch: &[(&str, Value)],
+) -> Result<Vec<Value>, Box<dyn std::error::Error>> {
+    let provider = pool.connect(endpoint).await?;
+    let mut responses = Vec::with_capacity(batch.len());
+    for (id, (method, params)) in batch.iter().enumerate() {
+        let response = match provider.request::<_, Value>(method, params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => match e.as_error_response() {
+                Some(error) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": error.code, "message": error.message, "data": error.data },
+                }),
+                None => return Err(Box::new(e)),
+            },
+        };
+        responses.push(response);
+    }
+    Ok(responses)
+}
+
+/// Get the bytecode of each of the provided contract addresses, in a single batch of requests.
+/// Bytecode is returned without a `0x` prefix, and shares [`get_code`]'s cache.
+#[instrument(level = "debug", skip_all, fields(contracts = contract_addresses.len()))]
+pub async fn get_code_batch(
+    contract_addresses: &[String],
+    rpc_url: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+    let cache_keys = contract_addresses
+        .iter()
+        .map(|address| format!("contract.{}.{}", &chain_id, address))
+        .collect::<Vec<String>>();
+
+    let mut bytecode: Vec<Option<String>> = cache_keys
+        .iter()
+        .map(|key| read_cache::<String>(key).filter(|_| rpc_cache_enabled()))
+        .collect();
+    let missing = (0..bytecode.len()).filter(|i| bytecode[*i].is_none()).collect::<Vec<usize>>();
+    let requests = missing
+        .iter()
+        .map(|i| ("eth_getCode", json!([contract_addresses[*i], "latest"])))
+        .collect::<Vec<_>>();
+
+    for (i, result) in missing.into_iter().zip(batch_request(rpc_url, &requests).await?) {
+        let code = result?.as_str().ok_or("malformed eth_getCode response")?.replacen("0x", "", 1);
+        if rpc_cache_enabled() {
+            store_cache(&cache_keys[i], code.clone(), None);
+        }
+        bytecode[i] = Some(code);
+    }
+
+    Ok(bytecode.into_iter().map(|code| code.unwrap_or_default()).collect())
+}
+
+/// Get the value of each of the provided storage slots of a contract, in a single batch of
+/// requests, such as when reading a range of slots. When a block is given, the values are read
+/// at that block, and cached.
+#[instrument(level = "debug", skip(slots, rpc_url), fields(slots = slots.len()))]
+pub async fn get_storage_batch(
+    contract_address: &str,
+    slots: &[U256],
+    block: Option<u64>,
+    rpc_url: &str,
+) -> Result<Vec<H256>, Box<dyn std::error::Error>> {
+    let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+    let block_tag = match block {
+        Some(block) => format!("{block:#x}"),
+        None => String::from("latest"),
+    };
+
+    let slot_params = slots.iter().map(|slot| format!("{slot:#x}")).collect::<Vec<String>>();
+    let cache_keys = slot_params
+        .iter()
+        .map(|slot| {
+            rpc_cache_key(chain_id, "eth_getStorageAt", &[contract_address, slot, &block_tag])
+        })
+        .collect::<Vec<String>>();
+
+    // only storage at a fixed block is immutable, so storage at the latest block isn't cached
+    let mut values: Vec<Option<H256>> =
+        cache_keys.iter().map(|key| block.and_then(|_| read_response::<H256>(key))).collect();
+    let missing = (0..values.len()).filter(|i| values[*i].is_none()).collect::<Vec<usize>>();
+    let requests = missing
+        .iter()
+        .map(|i| ("eth_getStorageAt", json!([contract_address, slot_params[*i], block_tag])))
+        .collect::<Vec<_>>();
+
+    for (i, result) in missing.into_iter().zip(batch_request(rpc_url, &requests).await?) {
+        let value: H256 = serde_json::from_value(result?)?;
+        if block.is_some() {
+            store_response(&cache_keys[i], &value);
+        }
+        values[i] = Some(value);
+    }
+
+    Ok(values.into_iter().map(|value| value.unwrap_or_default()).collect())
+}
+
+/// Get the receipt of each of the provided transaction hashes, in a single batch of requests.
+/// Receipts share [`get_transaction_receipt`]'s cache.
+#[instrument(level = "debug", skip_all, fields(transactions = transaction_hashes.len()))]
+pub async fn get_transaction_receipts(
+    transaction_hashes: &[String],
+    rpc_url: &str,
+) -> Result<Vec<TransactionReceipt>, Box<dyn std::error::Error>> {
+    let chain_id = chain_id(rpc_url).await.unwrap_or(1);
+    let cache_keys = transaction_hashes
+        .iter()
+        .map(|hash| rpc_cache_key(chain_id, "eth_getTransactionReceipt", &[hash]))
+        .collect::<Vec<String>>();
+
+    let mut receipts: Vec<Option<TransactionReceipt>> =
+        cache_keys.iter().map(|key| read_response(key)).collect();
+    let missing = (0..receipts.len()).filter(|i| receipts[*i].is_none()).collect::<Vec<usize>>();
+    let requests = missing
+        .iter()
+        .map(|i| ("eth_getTransactionReceipt", json!([transaction_hashes[*i]])))
+        .collect::<Vec<_>>();
+
+    for (i, result) in missing.into_iter().zip(batch_request(rpc_url, &requests).await?) {
+        let receipt: Option<TransactionReceipt> = serde_json::from_value(result?)?;
+        let receipt = receipt
+            .ok_or_else(|| format!("transaction '{}' hasn't been mined", transaction_hashes[i]))?;
+        store_response(&cache_keys[i], &receipt);
+        receipts[i] = Some(receipt);
+    }
+
+    Ok(receipts.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use serde_json::{json, Value};
+
+    use crate::ether::rpc::batch_request;
+
+    #[tokio::test]
+    async fn test_batch_request() {
+        // a node which answers a batch in reverse order, with an error for the second request
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            while !request.ends_with(b"]") {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+
+            let body = json!([
+                { "jsonrpc": "2.0", "id": 2, "result": "0x2" },
+                { "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "not found" } },
+                { "jsonrpc": "2.0", "id": 0, "result": "0x0" },
+            ])
+            .to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        let requests = [
+            ("eth_blockNumber", json!([])),
+            ("eth_unknown", json!([])),
+            ("eth_chainId", json!([])),
+        ];
+        let results = batch_request(&rpc_url, &requests).await.unwrap();
+        assert_eq!(results[0], Ok(Value::from("0x0")));
+        assert!(results[1].as_ref().unwrap_err().contains("not found"));
+        assert_eq!(results[2], Ok(Value::from("0x2")));
+    }
+
+    #[tokio::test]
+    async fn test_batch_request_without_provider() {
+        assert!(batch_request("", &[("eth_chainId", json!([]))]).await.is_err());
+    }
 }
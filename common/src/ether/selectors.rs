@@ -1,17 +1,20 @@
 use std::{
     collections::{HashMap, HashSet},
     sync::{Arc, Mutex},
-    time::Duration,
 };
 
-use indicatif::ProgressBar;
 use tokio::task;
+use tracing::{error, instrument};
 
-use crate::utils::{io::logging::Logger, strings::decode_hex};
+use crate::utils::{
+    io::{logging::Logger, progress::Progress},
+    strings::decode_hex,
+};
 
 use super::{evm::core::vm::VM, signatures::ResolveSelector};
 
 /// find all function selectors in the given EVM assembly.
+#[instrument(level = "debug", skip_all)]
 pub fn find_function_selectors(evm: &VM, assembly: &str) -> HashMap<String, u128> {
     let mut function_selectors = HashMap::new();
     let mut handled_selectors = HashSet::new();
@@ -105,6 +108,7 @@ pub fn resolve_entry_point(evm: &VM, selector: &str) -> u128 {
 }
 
 /// Resolve a list of selectors to their function signatures.
+#[instrument(level = "debug", skip_all, fields(selectors = selectors.len()))]
 pub async fn resolve_selectors<T>(selectors: Vec<String>) -> HashMap<String, Vec<T>>
 where
     T: ResolveSelector + Send + Clone + 'static, {
@@ -114,24 +118,11 @@ where
     let resolved_functions: Arc<Mutex<HashMap<String, Vec<T>>>> =
         Arc::new(Mutex::new(HashMap::new()));
 
-    let resolve_progress: Arc<Mutex<ProgressBar>> =
-        Arc::new(Mutex::new(ProgressBar::new_spinner()));
+    let resolve_progress = Progress::new("resolve_selectors", selectors.len() as u64, &logger);
+    resolve_progress.set_message("resolving selectors");
 
     let mut threads = Vec::new();
 
-    resolve_progress
-        .lock()
-        .expect("Could not obtain lock on resolve_progress.")
-        .enable_steady_tick(Duration::from_millis(100));
-    resolve_progress
-        .lock()
-        .expect("Could not obtain lock on resolve_progress.")
-        .set_style(logger.info_spinner());
-    resolve_progress
-        .lock()
-        .expect("Could not obtain lock on resolve_progress.")
-        .set_message("resolving selectors");
-
     for selector in selectors {
         let function_clone = resolved_functions.clone();
         let resolve_progress = resolve_progress.clone();
@@ -141,24 +132,22 @@ where
             if let Some(function) = T::resolve(&selector).await {
                 let mut _resolved_functions =
                     function_clone.lock().expect("Could not obtain lock on function_clone.");
-                let mut _resolve_progress =
-                    resolve_progress.lock().expect("Could not obtain lock on resolve_progress.");
-                _resolve_progress
-                    .set_message(format!("resolved {} selectors", _resolved_functions.len()));
                 _resolved_functions.insert(selector, function);
+                resolve_progress
+                    .set_message(format!("resolved {} selectors", _resolved_functions.len()));
             }
+            resolve_progress.inc(1);
         }));
     }
 
     // wait for all threads to finish
     for thread in threads {
         if let Err(e) = thread.await {
-            // Handle error
-            eprintln!("Task failed: {:?}", e);
+            error!(error = %e, "selector resolution task failed");
         }
     }
 
-    resolve_progress.lock().unwrap().finish_and_clear();
+    resolve_progress.finish_and_clear();
 
     let x =
         resolved_functions.lock().expect("Could not obtain lock on resolved_functions.").clone();
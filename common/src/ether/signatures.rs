@@ -1,8 +1,18 @@
 use async_trait::async_trait;
-use ethers::abi::Token;
+use ethers::{abi::Token, utils::id};
 use heimdall_cache::{read_cache, store_cache};
 
-use crate::utils::{http::get_json_from_url, io::logging::Logger, strings::replace_last};
+use crate::{
+    ether::evm::core::{
+        grammar::parse_signature,
+        types::{ParameterNames, SignatureError},
+    },
+    utils::{
+        http::{get_json_from_url, lookup_timeout},
+        io::logging::Logger,
+        strings::{encode_hex, replace_last},
+    },
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -13,6 +23,34 @@ pub struct ResolvedFunction {
     pub decoded_inputs: Option<Vec<Token>>,
 }
 
+impl ResolvedFunction {
+    /// Build a function from a human-readable signature, such as `transfer(address to, uint256
+    /// amount)`, which is parsed strictly. Returns its selector without a `0x` prefix, the
+    /// function, and the names of its parameters and their tuple components, which are empty if
+    /// unnamed.
+    pub fn from_human_readable(
+        signature: &str,
+    ) -> Result<(String, Self, Vec<ParameterNames>), SignatureError> {
+        let signature = parse_signature(signature, true)?;
+        let canonical = signature.canonical();
+
+        Ok((
+            encode_hex(id(&canonical)[..4].to_vec()),
+            ResolvedFunction {
+                name: signature.name,
+                signature: canonical,
+                inputs: signature
+                    .parameters
+                    .iter()
+                    .map(|parameter| parameter.kind.to_string())
+                    .collect(),
+                decoded_inputs: None,
+            },
+            signature.parameters.iter().map(ParameterNames::from).collect(),
+        ))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ResolvedError {
     pub name: String,
@@ -61,7 +99,7 @@ impl ResolveSelector for ResolvedError {
                 "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&function=0x{}",
                 &selector
             ),
-            10,
+            lookup_timeout().as_secs(),
         )
         .await
         .unwrap()
@@ -146,7 +184,7 @@ impl ResolveSelector for ResolvedLog {
                 "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&event=0x{}",
                 &selector
             ),
-            10,
+            lookup_timeout().as_secs(),
         )
         .await
         .unwrap()
@@ -231,7 +269,7 @@ impl ResolveSelector for ResolvedFunction {
                 "https://api.openchain.xyz/signature-database/v1/lookup?filter=true&function=0x{}",
                 &selector
             ),
-            10,
+            lookup_timeout().as_secs(),
         )
         .await
         .unwrap()
@@ -312,6 +350,29 @@ mod tests {
         score_signature, ResolveSelector, ResolvedError, ResolvedFunction, ResolvedLog,
     };
 
+    #[test]
+    fn test_function_from_human_readable() {
+        let (selector, function, names) =
+            ResolvedFunction::from_human_readable("transfer(address to, uint256)").unwrap();
+        assert_eq!(selector, "a9059cbb");
+        assert_eq!(function.signature, "transfer(address,uint256)");
+        assert_eq!(function.inputs, ["address", "uint256"]);
+        assert_eq!(
+            names.iter().map(|parameter| parameter.name.as_str()).collect::<Vec<_>>(),
+            ["to", ""]
+        );
+
+        let (_, function, names) = ResolvedFunction::from_human_readable(
+            "swap((address tokenIn, uint256 amountIn) params)",
+        )
+        .unwrap();
+        assert_eq!(function.signature, "swap((address,uint256))");
+        assert_eq!(names[0].name, "params");
+        assert_eq!(names[0].components[1].name, "amountIn");
+
+        assert!(ResolvedFunction::from_human_readable("transfer(address to, bytes33)").is_err());
+    }
+
     #[tokio::test]
     async fn resolve_function_signature_nominal() {
         let signature = String::from("095ea7b3");
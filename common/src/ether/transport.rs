@@ -0,0 +1,175 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use ethers::providers::{Http, JsonRpcClient, Provider, ProviderError};
+#[cfg(not(target_arch = "wasm32"))]
+use ethers::{
+    providers::{Ipc, PubsubClient, Ws},
+    types::U256,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{injected::InjectedClient, rpc::rpc_timeout};
+
+/// The transport used to reach an RPC endpoint, which is picked from the endpoint's URL.
+/// `ws://` and `wss://` endpoints are reached over a WebSocket, paths to a node's IPC socket,
+/// such as `/path/to/geth.ipc`, over IPC, and everything else over HTTP. WebSocket and IPC
+/// transports also support subscriptions. When targeting wasm32, where WebSocket and IPC
+/// transports aren't available, every endpoint is reached through the injected RPC callback
+/// instead.
+#[derive(Clone, Debug)]
+pub enum Transport {
+    Http(Http),
+    #[cfg(not(target_arch = "wasm32"))]
+    Ws(Ws),
+    #[cfg(not(target_arch = "wasm32"))]
+    Ipc(Ipc),
+    Injected(InjectedClient),
+}
+
+impl Transport {
+    /// Connects to the given endpoint.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn connect(endpoint: &str) -> Result<Transport, ProviderError> {
+        if is_websocket(endpoint) {
+            return Ok(Transport::Ws(Ws::connect(endpoint).await?))
+        }
+        if is_ipc(endpoint) {
+            return Ok(Transport::Ipc(Ipc::connect(endpoint).await?))
+        }
+
+        endpoint
+            .parse::<Http>()
+            .map(Transport::Http)
+            .map_err(|e| ProviderError::CustomError(e.to_string()))
+    }
+
+    /// Connects to the given endpoint, through the injected RPC callback.
+    #[cfg(target_arch = "wasm32")]
+    pub async fn connect(_endpoint: &str) -> Result<Transport, ProviderError> {
+        Ok(Transport::Injected(InjectedClient))
+    }
+
+    /// Whether the transport supports subscriptions.
+    pub fn is_pubsub(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        if matches!(self, Transport::Ws(_) | Transport::Ipc(_)) {
+            return true
+        }
+        false
+    }
+}
+
+/// Whether the given endpoint is reached over a WebSocket.
+pub fn is_websocket(endpoint: &str) -> bool {
+    endpoint.starts_with("ws://") || endpoint.starts_with("wss://")
+}
+
+/// Whether the given endpoint is the path to a node's IPC socket, such as `/path/to/geth.ipc` or
+/// `\\.\pipe\geth.ipc` on windows.
+pub fn is_ipc(endpoint: &str) -> bool {
+    !endpoint.contains("://") &&
+        (endpoint.ends_with(".ipc") ||
+            endpoint.starts_with('/') ||
+            endpoint.starts_with(r"\\.\pipe\"))
+}
+
+/// Connects a [`Provider`] to the given endpoint, over the [`Transport`] its URL calls for.
+///
+/// ```
+/// use heimdall_common::ether::transport::{connect, Transport};
+///
+/// let rt = tokio::runtime::Runtime::new().unwrap();
+/// let provider = rt.block_on(connect("http://localhost:8545")).unwrap();
+/// assert!(matches!(provider.as_ref(), Transport::Http(_)));
+/// ```
+pub async fn connect(endpoint: &str) -> Result<Provider<Transport>, ProviderError> {
+    Transport::connect(endpoint).await.map(Provider::new)
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcClient for Transport {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, ProviderError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send, {
+        // requests which take longer than the timeout fail, so they can be retried
+        let request = async {
+            match self {
+                Transport::Http(http) => http.request(method, params).await.map_err(Into::into),
+                #[cfg(not(target_arch = "wasm32"))]
+                Transport::Ws(ws) => ws.request(method, params).await.map_err(Into::into),
+                #[cfg(not(target_arch = "wasm32"))]
+                Transport::Ipc(ipc) => ipc.request(method, params).await.map_err(Into::into),
+                Transport::Injected(injected) => injected.request(method, params).await,
+            }
+        };
+        if matches!(self, Transport::Injected(_)) {
+            return request.await
+        }
+
+        let timeout = rpc_timeout();
+        tokio::time::timeout(timeout, request).await.unwrap_or_else(|_| {
+            Err(ProviderError::CustomError(format!(
+                "request timed out after {}s",
+                timeout.as_secs_f64()
+            )))
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PubsubClient for Transport {
+    type NotificationStream = <Ws as PubsubClient>::NotificationStream;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, ProviderError> {
+        match self {
+            Transport::Ws(ws) => ws.subscribe(id).map_err(Into::into),
+            Transport::Ipc(ipc) => ipc.subscribe(id).map_err(Into::into),
+            Transport::Http(_) | Transport::Injected(_) => Err(ProviderError::UnsupportedRPC),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), ProviderError> {
+        match self {
+            Transport::Ws(ws) => ws.unsubscribe(id).map_err(Into::into),
+            Transport::Ipc(ipc) => ipc.unsubscribe(id).map_err(Into::into),
+            Transport::Http(_) | Transport::Injected(_) => Err(ProviderError::UnsupportedRPC),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::providers::PubsubClient;
+
+    use crate::ether::transport::{is_ipc, is_websocket, Transport};
+
+    #[tokio::test]
+    async fn test_transport_selection() {
+        assert!(is_websocket("wss://eth.example.com"));
+        assert!(!is_websocket("https://eth.example.com"));
+
+        // native builds reach endpoints over the network, even with the `wasm` feature enabled
+        let http = Transport::connect("https://eth.example.com").await.unwrap();
+        assert!(matches!(http, Transport::Http(_)));
+        assert!(!http.is_pubsub());
+        assert!(http.subscribe(1).is_err());
+
+        // nothing is listening, so the WebSocket can't connect
+        assert!(Transport::connect("ws://127.0.0.1:1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ipc_transport() {
+        assert!(is_ipc("/root/.ethereum/geth.ipc"));
+        assert!(is_ipc(r"\\.\pipe\geth.ipc"));
+        assert!(!is_ipc("http://localhost:8545/geth.ipc"));
+
+        // there's no socket at this path, so the node can't be reached
+        assert!(Transport::connect("/tmp/heimdall-missing-node.ipc").await.is_err());
+    }
+}
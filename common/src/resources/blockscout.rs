@@ -0,0 +1,71 @@
+pub use super::explorer::ContractSource;
+use super::explorer::{call_explorer, parse_source};
+
+/// Get the ABI of the given verified contract from a Blockscout instance's API, such as
+/// `https://eth.blockscout.com/api`.
+///
+/// ```
+/// use heimdall_common::resources::blockscout::get_abi;
+///
+/// let api = "https://eth.blockscout.com/api";
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+///
+/// // let abi = get_abi(api, address).await;
+/// ```
+pub async fn get_abi(api: &str, address: &str) -> Option<String> {
+    call_explorer(api, &[("module", "contract"), ("action", "getabi"), ("address", address)], None)
+        .await?
+        .as_str()
+        .map(String::from)
+}
+
+/// Get the verified source of the given contract from a Blockscout instance's API.
+///
+/// ```
+/// use heimdall_common::resources::blockscout::get_source;
+///
+/// let api = "https://eth.blockscout.com/api";
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+///
+/// // let source = get_source(api, address).await;
+/// ```
+pub async fn get_source(api: &str, address: &str) -> Option<ContractSource> {
+    let result = call_explorer(
+        api,
+        &[("module", "contract"), ("action", "getsourcecode"), ("address", address)],
+        None,
+    )
+    .await?;
+
+    parse_source(&result)
+}
+
+/// Get the creator and creation transaction hash of the given contract from a Blockscout
+/// instance's API.
+///
+/// ```
+/// use heimdall_common::resources::blockscout::get_contract_creation;
+///
+/// let api = "https://eth.blockscout.com/api";
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+///
+/// // let (creator, transaction_hash) = get_contract_creation(api, address).await.unwrap();
+/// ```
+pub async fn get_contract_creation(api: &str, address: &str) -> Option<(String, String)> {
+    let result = call_explorer(
+        api,
+        &[
+            ("module", "contract"),
+            ("action", "getcontractcreation"),
+            ("contractaddresses", address),
+        ],
+        None,
+    )
+    .await?;
+
+    let creation = result.as_array()?.first()?;
+    Some((
+        creation.get("contractCreator")?.as_str()?.to_string(),
+        creation.get("txHash")?.as_str()?.to_string(),
+    ))
+}
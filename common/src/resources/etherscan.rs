@@ -0,0 +1,53 @@
+use ethers::abi::Abi;
+
+pub use super::explorer::ContractSource;
+use super::{
+    explorer::{call_explorer, parse_source},
+    quota::ApiKeys,
+};
+
+/// Get the ABI of the given verified contract from an Etherscan API, such as
+/// `https://api.etherscan.io/api`, or the API of the chain's etherscan-compatible explorer.
+/// Multiple API keys may be given, separated by commas, to rotate between them.
+///
+/// ```
+/// use heimdall_common::resources::etherscan::get_abi;
+///
+/// let api = "https://api.etherscan.io/api";
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+/// let api_key = "YOUR_API_KEY";
+///
+/// // let abi = get_abi(api, address, api_key).await;
+/// ```
+pub async fn get_abi(api: &str, address: &str, api_key: &str) -> Option<Abi> {
+    let result = call_explorer(
+        api,
+        &[("module", "contract"), ("action", "getabi"), ("address", address)],
+        Some(&ApiKeys::new("etherscan", api_key)),
+    )
+    .await?;
+
+    serde_json::from_str(result.as_str()?).ok()
+}
+
+/// Get the verified source of the given contract from an Etherscan API.
+///
+/// ```
+/// use heimdall_common::resources::etherscan::get_source;
+///
+/// let api = "https://api.etherscan.io/api";
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+/// let api_key = "YOUR_API_KEY";
+///
+/// // let source = get_source(api, address, api_key).await;
+/// ```
+pub async fn get_source(api: &str, address: &str, api_key: &str) -> Option<ContractSource> {
+    let result = call_explorer(
+        api,
+        &[("module", "contract"), ("action", "getsourcecode"), ("address", address)],
+        Some(&ApiKeys::new("etherscan", api_key)),
+    )
+    .await?;
+
+    parse_source(&result)
+}
@@ -0,0 +1,157 @@
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+use super::quota::{ApiKeys, MAX_RATE_LIMITED_ATTEMPTS};
+use crate::utils::{
+    http::{client_builder, lookup_timeout, send_with_retries},
+    io::logging::Logger,
+};
+
+/// The verified source of a contract, as returned by an explorer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractSource {
+    pub name: String,
+    pub compiler_version: String,
+    pub optimization_used: bool,
+    pub runs: u64,
+    pub source_code: String,
+    pub abi: String,
+}
+
+/// calls the given etherscan-compatible explorer API, such as Etherscan's or Blockscout's, with the
+/// given query parameters, returning the `result` field of the response. if API keys are given,
+/// requests rotate between them and are retried with another key when rate limited
+pub(crate) async fn call_explorer(
+    api: &str,
+    params: &[(&str, &str)],
+    keys: Option<&ApiKeys>,
+) -> Option<Value> {
+    // get a new logger
+    let logger = Logger::default();
+
+    let client = client_builder(lookup_timeout()).build().ok()?;
+    for _ in 0..MAX_RATE_LIMITED_ATTEMPTS {
+        let key = match keys {
+            Some(keys) => Some(keys.acquire().await?),
+            None => None,
+        };
+        let mut params = params.to_vec();
+        if let Some(key) = &key {
+            params.push(("apikey", key));
+        }
+
+        let url = match Url::parse_with_params(api, &params) {
+            Ok(url) => url,
+            Err(e) => {
+                logger.debug(&format!("invalid explorer API url '{api}': {e}"));
+                return None
+            }
+        };
+
+        // make the request
+        let (status, retry_after, body) = match send_with_retries(|| client.get(url.clone())).await
+        {
+            Ok(response) => {
+                let status = response.status();
+                let retry_after = response
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                match response.text().await {
+                    Ok(body) => (status, retry_after, body),
+                    Err(e) => {
+                        logger.debug(&format!("failed to read explorer response body: {e}"));
+                        return None
+                    }
+                }
+            }
+            Err(e) => {
+                logger.debug(&format!("failed to call explorer: {e}"));
+                return None
+            }
+        };
+
+        // parse body, where unverified contracts and failed lookups have a status of "0"
+        let response: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+        let rate_limited = status == StatusCode::TOO_MANY_REQUESTS ||
+            response
+                .get("result")
+                .and_then(Value::as_str)
+                .is_some_and(|result| result.to_lowercase().contains("rate limit"));
+        if rate_limited {
+            logger.debug(&format!("explorer request to '{api}' was rate limited."));
+            if let (Some(keys), Some(key)) = (keys, &key) {
+                keys.report_rate_limited(key, retry_after);
+            } else {
+                tokio::time::sleep(retry_after.unwrap_or(Duration::from_secs(1))).await;
+            }
+            continue
+        }
+
+        if response.is_null() {
+            logger.debug(&format!("failed to parse explorer response from '{api}'"));
+            return None
+        }
+        if response.get("status").and_then(Value::as_str) != Some("1") {
+            logger.debug(&format!("explorer request to '{api}' unsuccessful: {response}"));
+            return None
+        }
+
+        return response.get("result").cloned()
+    }
+
+    logger.debug(&format!("explorer request to '{api}' kept being rate limited."));
+    None
+}
+
+/// parses the result of a `getsourcecode` request, which is `None` if the contract isn't verified
+pub(crate) fn parse_source(result: &Value) -> Option<ContractSource> {
+    let source = match result {
+        Value::Array(sources) => sources.first()?,
+        source => source,
+    };
+    let field = |name: &str| source.get(name).and_then(Value::as_str).unwrap_or_default();
+
+    let source_code = field("SourceCode");
+    if source_code.is_empty() {
+        return None
+    }
+
+    Some(ContractSource {
+        name: field("ContractName").to_string(),
+        compiler_version: field("CompilerVersion").to_string(),
+        optimization_used: matches!(field("OptimizationUsed"), "1" | "true"),
+        runs: field("OptimizationRuns").parse().or_else(|_| field("Runs").parse()).unwrap_or(0),
+        source_code: source_code.to_string(),
+        abi: field("ABI").to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::resources::explorer::parse_source;
+
+    #[test]
+    fn test_parse_source() {
+        let source = parse_source(&json!([{
+            "ContractName": "WETH9",
+            "CompilerVersion": "v0.4.19+commit.c4cbbb05",
+            "OptimizationUsed": "false",
+            "OptimizationRuns": "200",
+            "SourceCode": "contract WETH9 {}",
+            "ABI": "[]",
+        }]))
+        .unwrap();
+        assert_eq!(source.name, "WETH9");
+        assert!(!source.optimization_used);
+        assert_eq!(source.runs, 200);
+
+        // unverified contracts have no source code
+        assert!(parse_source(&json!([{ "SourceCode": "", "ABI": "" }])).is_none());
+    }
+}
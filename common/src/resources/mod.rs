@@ -1,2 +1,7 @@
+pub mod blockscout;
+pub mod etherscan;
+pub mod explorer;
 pub mod openai;
+pub mod quota;
+pub mod sourcify;
 pub mod transpose;
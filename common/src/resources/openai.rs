@@ -1,4 +1,6 @@
+#[cfg(not(target_arch = "wasm32"))]
 use crate::utils::io::logging::Logger;
+#[cfg(not(target_arch = "wasm32"))]
 use async_openai::{types::CreateCompletionRequestArgs, Client};
 
 /// Complete the given prompt using the OpenAI API.
@@ -9,6 +11,7 @@ use async_openai::{types::CreateCompletionRequestArgs, Client};
 /// let prompt = "what is love?";
 /// let api_key = "your-api-key";
 /// // complete(prompt, api_key).await;
+#[cfg(not(target_arch = "wasm32"))]
 pub async fn complete(prompt: &str, api_key: &str) -> Option<String> {
     let client = Client::new().with_api_key(api_key);
 
@@ -44,3 +47,9 @@ pub async fn complete(prompt: &str, api_key: &str) -> Option<String> {
         }
     }
 }
+
+/// The OpenAI client isn't available when targeting wasm32, so prompts are never completed.
+#[cfg(target_arch = "wasm32")]
+pub async fn complete(_prompt: &str, _api_key: &str) -> Option<String> {
+    None
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+
+// how many requests each key may make per second, which is the free tier limit of most explorer
+// APIs. a service's requests are limited to this rate for each of its keys
+const REQUESTS_PER_SECOND_PER_KEY: f64 = 5.0;
+
+// the most attempts made at a request which keeps being rate limited
+pub(crate) const MAX_RATE_LIMITED_ATTEMPTS: usize = 5;
+
+// how long a key which was rate limited is skipped for, unless the service says otherwise
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    // the token bucket of every service used during this run, shared by every request to it
+    static ref BUCKETS: Mutex<HashMap<String, TokenBucket>> = Mutex::new(HashMap::new());
+
+    // when each rate limited key can be used again
+    static ref COOLDOWNS: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+
+    // the number of requests made with each service's keys, used to rotate between them
+    static ref REQUESTS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+}
+
+/// A token bucket, which allows bursts of up to `capacity` requests and refills at a steady rate.
+#[derive(Clone, Debug)]
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_second: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(requests_per_second: f64) -> TokenBucket {
+        // a burst must fit at least one request, or slower rates would never allow any
+        TokenBucket {
+            capacity: requests_per_second.max(1.0),
+            tokens: requests_per_second.max(1.0),
+            refill_per_second: requests_per_second,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    pub(crate) fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(())
+        }
+        Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_second))
+    }
+}
+
+/// The API keys for a service, such as Etherscan or Transpose, given as a comma-separated list.
+/// Requests rotate between the keys, skipping keys which were recently rate limited, and every
+/// request to the service shares a token bucket so large jobs stay within the keys' quota.
+///
+/// ```
+/// use heimdall_common::resources::quota::ApiKeys;
+///
+/// let keys = ApiKeys::new("etherscan", "KEY_A, KEY_B");
+/// assert_eq!(keys.keys(), ["KEY_A", "KEY_B"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ApiKeys {
+    service: String,
+    keys: Vec<String>,
+}
+
+impl ApiKeys {
+    pub fn new(service: &str, api_keys: &str) -> ApiKeys {
+        ApiKeys {
+            service: service.to_string(),
+            keys: api_keys
+                .split(',')
+                .map(|key| key.trim().to_string())
+                .filter(|key| !key.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Picks the key the next request should use, without waiting for the rate limit. Keys which
+    /// aren't cooling down are taken in turn, and if every key is, the one which will be usable
+    /// soonest is used.
+    pub fn next_key(&self) -> Option<String> {
+        if self.keys.is_empty() {
+            return None
+        }
+
+        let start = {
+            let mut requests = REQUESTS.lock().expect("api key lock poisoned");
+            let count = requests.entry(self.service.clone()).or_insert(0);
+            *count += 1;
+            *count - 1
+        };
+
+        let cooldowns = COOLDOWNS.lock().expect("api key lock poisoned");
+        let now = Instant::now();
+        let keys = (0..self.keys.len())
+            .map(|i| &self.keys[(start + i) % self.keys.len()])
+            .collect::<Vec<&String>>();
+
+        keys.iter()
+            .find(|key| cooldowns.get(key.as_str()).is_none_or(|until| now >= *until))
+            .or_else(|| keys.iter().min_by_key(|key| cooldowns.get(key.as_str())))
+            .map(|key| key.to_string())
+    }
+
+    /// Waits until the service's rate limit allows another request, then picks the key it should
+    /// use, as in [`ApiKeys::next_key`]. If every key is cooling down, this also waits until the
+    /// picked key can be used again.
+    pub async fn acquire(&self) -> Option<String> {
+        if self.keys.is_empty() {
+            return None
+        }
+
+        let key = loop {
+            let wait = {
+                let mut buckets = BUCKETS.lock().expect("api key lock poisoned");
+                buckets
+                    .entry(self.service.clone())
+                    .or_insert_with(|| {
+                        TokenBucket::new(REQUESTS_PER_SECOND_PER_KEY * self.keys.len() as f64)
+                    })
+                    .take(Instant::now())
+            };
+
+            match wait {
+                Ok(()) => break self.next_key()?,
+                Err(wait) => tokio::time::sleep(wait).await,
+            }
+        };
+
+        let cooldown = COOLDOWNS.lock().expect("api key lock poisoned").get(&key).copied();
+        if let Some(until) = cooldown {
+            tokio::time::sleep(until.saturating_duration_since(Instant::now())).await;
+        }
+        Some(key)
+    }
+
+    /// Records that a request with the given key was rate limited, so it's skipped until
+    /// `retry_after` has passed, or briefly if the service didn't say.
+    pub fn report_rate_limited(&self, key: &str, retry_after: Option<Duration>) {
+        COOLDOWNS
+            .lock()
+            .expect("api key lock poisoned")
+            .insert(key.to_string(), Instant::now() + retry_after.unwrap_or(RATE_LIMIT_COOLDOWN));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::resources::quota::{ApiKeys, TokenBucket};
+
+    #[test]
+    fn test_rotation_skips_rate_limited_keys() {
+        let keys = ApiKeys::new("test_rotation", "KEY_A,KEY_B");
+        let first = keys.next_key().unwrap();
+        assert_ne!(keys.next_key().unwrap(), first);
+
+        keys.report_rate_limited("KEY_A", Some(Duration::from_secs(60)));
+        for _ in 0..4 {
+            assert_eq!(keys.next_key().unwrap(), "KEY_B");
+        }
+
+        // when every key is rate limited, the one which recovers soonest is used
+        keys.report_rate_limited("KEY_B", Some(Duration::from_secs(120)));
+        assert_eq!(keys.next_key().unwrap(), "KEY_A");
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(2.0);
+        assert!(bucket.take(now).is_ok());
+        assert!(bucket.take(now).is_ok());
+
+        let wait = bucket.take(now).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(500));
+        assert!(bucket.take(now + Duration::from_millis(500)).is_ok());
+
+        // slower rates than one request per second still allow requests
+        let mut bucket = TokenBucket::new(0.5);
+        assert!(bucket.take(now).is_ok());
+        assert!(bucket.take(now + Duration::from_secs(2)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_without_keys() {
+        assert!(ApiKeys::new("test_empty", " , ").acquire().await.is_none());
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use ethers::{
+    abi::{Abi, ParamType},
+    types::U256,
+};
+use serde_json::Value;
+
+use crate::{
+    ether::signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
+    utils::{
+        http::{client_builder, lookup_timeout, send_with_retries},
+        io::logging::Logger,
+        strings::encode_hex,
+    },
+};
+
+const SOURCIFY_SERVER: &str = "https://sourcify.dev/server";
+
+/// A contract verified on Sourcify, along with its metadata, ABI, and sources.
+#[derive(Debug, Clone)]
+pub struct VerifiedContract {
+    // whether the contract is a full match, meaning its metadata matches too, rather than only its
+    // bytecode
+    pub full_match: bool,
+    pub metadata: Value,
+    pub abi: Abi,
+
+    // the contract's source files, by path
+    pub sources: HashMap<String, String>,
+}
+
+/// Get the verified contract at the given address from Sourcify, checking for a full match and
+/// then a partial match. Returns `None` if the contract isn't verified.
+///
+/// ```
+/// use heimdall_common::resources::sourcify::get_verified_contract;
+///
+/// let chain_id = 1;
+/// let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+///
+/// // let contract = get_verified_contract(chain_id, address).await;
+/// ```
+pub async fn get_verified_contract(chain_id: u64, address: &str) -> Option<VerifiedContract> {
+    // get a new logger
+    let logger = Logger::default();
+
+    let url = format!("{SOURCIFY_SERVER}/files/any/{chain_id}/{address}");
+    let client = client_builder(lookup_timeout()).build().ok()?;
+    let body = match send_with_retries(|| client.get(&url)).await {
+        Ok(response) if response.status().is_success() => response.text().await.ok()?,
+        Ok(response) => {
+            logger
+                .debug(&format!("'{address}' isn't verified on Sourcify ({}).", response.status()));
+            return None
+        }
+        Err(e) => {
+            logger.debug(&format!("failed to call Sourcify: {e}"));
+            return None
+        }
+    };
+
+    match serde_json::from_str::<Value>(&body) {
+        Ok(response) => parse_verified_contract(&response),
+        Err(e) => {
+            logger.debug(&format!("failed to parse Sourcify response: {e}"));
+            None
+        }
+    }
+}
+
+/// parses a Sourcify `files/any` response into a [`VerifiedContract`]
+fn parse_verified_contract(response: &Value) -> Option<VerifiedContract> {
+    let full_match = match response.get("status")?.as_str()? {
+        "full" => true,
+        "partial" => false,
+        _ => return None,
+    };
+
+    let mut metadata = None;
+    let mut sources = HashMap::new();
+    for file in response.get("files")?.as_array()? {
+        let name = file.get("name").and_then(Value::as_str).unwrap_or_default();
+        let content = file.get("content").and_then(Value::as_str).unwrap_or_default();
+        if name == "metadata.json" {
+            metadata = serde_json::from_str::<Value>(content).ok();
+        } else {
+            // paths are prefixed with the match type, chain id, and address, so only the path
+            // from the metadata's sources is kept
+            let path = file.get("path").and_then(Value::as_str).unwrap_or(name);
+            let path = path.split_once("/sources/").map(|(_, path)| path).unwrap_or(path);
+            sources.insert(path.to_string(), content.to_string());
+        }
+    }
+
+    let metadata = metadata?;
+    let abi = serde_json::from_value(metadata.get("output")?.get("abi")?.clone()).ok()?;
+    Some(VerifiedContract { full_match, metadata, abi, sources })
+}
+
+/// formats the signature of the given name and parameters, such as `transfer(address,uint256)`
+fn signature(name: &str, params: &[ParamType]) -> String {
+    format!("{name}({})", params.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(","))
+}
+
+impl From<Abi> for VerifiedContract {
+    /// A contract whose ABI is verified elsewhere, such as on Etherscan, but whose metadata and
+    /// sources aren't known.
+    fn from(abi: Abi) -> Self {
+        VerifiedContract { full_match: false, metadata: Value::Null, abi, sources: HashMap::new() }
+    }
+}
+
+impl VerifiedContract {
+    /// The contract's source files, sorted by path and joined into one, each under a comment with
+    /// its path.
+    pub fn source(&self) -> String {
+        let mut paths = self.sources.keys().collect::<Vec<&String>>();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| format!("// {path}\n{}", self.sources[path]))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    /// The contract's functions, by selector without a `0x` prefix.
+    pub fn functions(&self) -> HashMap<String, ResolvedFunction> {
+        self.abi
+            .functions()
+            .map(|function| {
+                let inputs =
+                    function.inputs.iter().map(|input| input.kind.clone()).collect::<Vec<_>>();
+                (
+                    encode_hex(function.short_signature().to_vec()),
+                    ResolvedFunction {
+                        name: function.name.clone(),
+                        signature: signature(&function.name, &inputs),
+                        inputs: inputs.iter().map(|input| input.to_string()).collect(),
+                        decoded_inputs: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The names of each function's parameters, by selector without a `0x` prefix. Unnamed
+    /// parameters have an empty name.
+    pub fn parameter_names(&self) -> HashMap<String, Vec<String>> {
+        self.abi
+            .functions()
+            .map(|function| {
+                (
+                    encode_hex(function.short_signature().to_vec()),
+                    function.inputs.iter().map(|input| input.name.clone()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// The contract's custom errors, by selector.
+    pub fn errors(&self) -> HashMap<U256, ResolvedError> {
+        self.abi
+            .errors()
+            .map(|error| {
+                let inputs =
+                    error.inputs.iter().map(|input| input.kind.clone()).collect::<Vec<_>>();
+                (
+                    U256::from_big_endian(&error.signature()[0..4]),
+                    ResolvedError {
+                        name: error.name.clone(),
+                        signature: signature(&error.name, &inputs),
+                        inputs: inputs.iter().map(|input| input.to_string()).collect(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// The contract's events, by topic.
+    pub fn events(&self) -> HashMap<U256, ResolvedLog> {
+        self.abi
+            .events()
+            .map(|event| {
+                let inputs =
+                    event.inputs.iter().map(|input| input.kind.clone()).collect::<Vec<_>>();
+                (
+                    U256::from_big_endian(event.signature().as_bytes()),
+                    ResolvedLog {
+                        name: event.name.clone(),
+                        signature: signature(&event.name, &inputs),
+                        inputs: inputs.iter().map(|input| input.to_string()).collect(),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+    use serde_json::json;
+
+    use crate::resources::sourcify::parse_verified_contract;
+
+    #[test]
+    fn test_parse_verified_contract() {
+        let metadata = json!({
+            "output": { "abi": [
+                { "type": "function", "name": "transfer", "stateMutability": "nonpayable",
+                  "inputs": [{ "name": "to", "type": "address" }, { "name": "amount", "type": "uint256" }],
+                  "outputs": [{ "name": "", "type": "bool" }] },
+                { "type": "event", "name": "Transfer", "anonymous": false,
+                  "inputs": [{ "name": "from", "type": "address", "indexed": true },
+                             { "name": "to", "type": "address", "indexed": true },
+                             { "name": "value", "type": "uint256", "indexed": false }] },
+                { "type": "error", "name": "Unauthorized", "inputs": [] },
+            ]}
+        });
+        let contract = parse_verified_contract(&json!({
+            "status": "partial",
+            "files": [
+                { "name": "metadata.json", "path": "partial_match/1/0x00/metadata.json", "content": metadata.to_string() },
+                { "name": "Token.sol", "path": "partial_match/1/0x00/sources/src/Token.sol", "content": "contract Token {}" },
+            ]
+        }))
+        .unwrap();
+
+        assert!(!contract.full_match);
+        assert_eq!(contract.sources["src/Token.sol"], "contract Token {}");
+        assert_eq!(contract.functions()["a9059cbb"].signature, "transfer(address,uint256)");
+        assert_eq!(contract.parameter_names()["a9059cbb"], ["to", "amount"]);
+        assert_eq!(contract.errors()[&U256::from(0x82b42900u32)].name, "Unauthorized");
+        assert_eq!(
+            contract.events()[&U256::from_str_radix(
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                16
+            )
+            .unwrap()]
+                .signature,
+            "Transfer(address,address,uint256)"
+        );
+
+        // contracts which aren't verified have no match
+        assert!(parse_verified_contract(&json!({ "status": "false", "files": [] })).is_none());
+    }
+}
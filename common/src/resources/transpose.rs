@@ -1,9 +1,13 @@
-use indicatif::ProgressBar;
-use reqwest::header::HeaderMap;
+use reqwest::{header::HeaderMap, StatusCode};
 use serde_json::Value;
 use std::time::{Duration, Instant};
 
-use crate::utils::io::logging::Logger;
+use super::quota::{ApiKeys, MAX_RATE_LIMITED_ATTEMPTS};
+use crate::utils::{
+    failure::{Failure, FailureClass},
+    http::{client_builder, lookup_timeout},
+    io::{logging::Logger, progress::spinner},
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,58 +24,82 @@ struct TransposeResponse {
     results: Vec<Value>,
 }
 
-/// executes a transpose SQL query and returns the response
-async fn _call_transpose(query: &str, api_key: &str) -> Option<TransposeResponse> {
+/// executes a transpose SQL query and returns the response. multiple API keys may be given,
+/// separated by commas, which requests rotate between when one is rate limited
+async fn _call_transpose(query: &str, api_key: &str) -> Result<TransposeResponse, Failure> {
     // get a new logger
     let logger = Logger::default();
 
-    // build the headers
-    let mut headers = HeaderMap::new();
-    headers.insert("Content-Type", "application/json".parse().unwrap());
-    headers.insert("X-API-KEY", api_key.parse().unwrap());
-
     // clone the query
     let query = query.to_owned();
 
     // make the request
-    let client = reqwest::Client::builder()
-        .redirect(reqwest::redirect::Policy::none())
-        .timeout(Duration::from_secs(999999999))
-        .build()
-        .unwrap();
-
-    let response = match client
-        .post("https://api.transpose.io/sql")
-        .body(query.clone())
-        .headers(headers)
-        .send()
-        .await
-    {
-        Ok(res) => res,
-        Err(e) => {
-            logger.error("failed to call Transpose .");
-            logger.error(&format!("error: {e}"));
-            std::process::exit(1)
+    let client = client_builder(lookup_timeout());
+    // browsers follow redirects themselves, so they can't be disabled when targeting wasm32
+    #[cfg(not(target_arch = "wasm32"))]
+    let client = client.redirect(reqwest::redirect::Policy::none());
+    let client = client.build().unwrap();
+
+    let keys = ApiKeys::new("transpose", api_key);
+    let mut attempts = 0;
+    let response = loop {
+        let api_key = keys.acquire().await.ok_or_else(|| {
+            Failure::new(FailureClass::InvalidInput, "querying Transpose requires an API key")
+        })?;
+        attempts += 1;
+
+        // build the headers
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/json".parse().unwrap());
+        headers.insert("X-API-KEY", api_key.parse().unwrap());
+
+        let response = match client
+            .post("https://api.transpose.io/sql")
+            .body(query.clone())
+            .headers(headers)
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(e) => {
+                return Err(Failure::new(
+                    FailureClass::Rpc,
+                    format!("failed to call Transpose: {e}"),
+                ))
+            }
+        };
+
+        // when rate limited, retry with the next key once the limit allows
+        if response.status() == StatusCode::TOO_MANY_REQUESTS &&
+            attempts < MAX_RATE_LIMITED_ATTEMPTS
+        {
+            logger.debug("Transpose request was rate limited, retrying with the next API key.");
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            keys.report_rate_limited(&api_key, retry_after);
+            continue
         }
+
+        break response
     };
 
     // parse body
     match response.text().await {
-        Ok(body) => Some(match serde_json::from_str(&body) {
-            Ok(json) => json,
+        Ok(body) => match serde_json::from_str(&body) {
+            Ok(json) => Ok(json),
             Err(e) => {
-                logger.error("Transpose request unsucessful.");
                 logger.debug(&format!("curl: curl -X GET \"https://api.transpose.io/sql\" -H \"accept: application/json\" -H \"Content-Type: application/json\" -H \"X-API-KEY: {api_key}\" -d {query}"));
-                logger.error(&format!("error: {e}"));
                 logger.debug(&format!("response body: {body:?}"));
-                std::process::exit(1)
+                Err(Failure::new(FailureClass::Rpc, format!("Transpose request unsucessful: {e}")))
             }
-        }),
-        Err(e) => {
-            logger.error("failed to parse Transpose response body.");
-            logger.error(&format!("error: {e}"));
-            std::process::exit(1)
-        }
+        },
+        Err(e) => Err(Failure::new(
+            FailureClass::Rpc,
+            format!("failed to parse Transpose response body: {e}"),
+        )),
     }
 }
 
@@ -93,14 +121,12 @@ pub async fn get_transaction_list(
     address: &str,
     api_key: &str,
     bounds: (&u128, &u128),
-) -> Vec<(u128, String)> {
+) -> Result<Vec<(u128, String)>, Failure> {
     // get a new logger
     let logger = Logger::default();
 
     // get a new progress bar
-    let transaction_list_progress = ProgressBar::new_spinner();
-    transaction_list_progress.enable_steady_tick(Duration::from_millis(100));
-    transaction_list_progress.set_style(logger.info_spinner());
+    let transaction_list_progress = spinner(logger.info_spinner());
     transaction_list_progress.set_message(format!("fetching transactions from '{address}' ."));
     let start_time = Instant::now();
 
@@ -115,13 +141,9 @@ pub async fn get_transaction_list(
         bounds.1
     );
 
-    let response = match _call_transpose(&query, api_key).await {
-        Some(response) => response,
-        None => {
-            logger.error("failed to get transaction list from Transpose");
-            std::process::exit(1)
-        }
-    };
+    let response = _call_transpose(&query, api_key).await.map_err(|e| {
+        Failure::new(e.class, format!("failed to get transaction list from Transpose: {e}"))
+    })?;
 
     transaction_list_progress.finish_and_clear();
     logger.debug(&format!("fetching transactions took {:?}", start_time.elapsed()));
@@ -134,26 +156,34 @@ pub async fn get_transaction_list(
             Some(block_number) => match block_number.as_u64() {
                 Some(block_number) => block_number as u128,
                 None => {
-                    logger.error("failed to parse block_number from Transpose");
-                    std::process::exit(1)
+                    return Err(Failure::new(
+                        FailureClass::Rpc,
+                        "failed to parse block_number from Transpose",
+                    ))
                 }
             },
             None => {
-                logger.error("failed to fetch block_number from Transpose response");
-                std::process::exit(1)
+                return Err(Failure::new(
+                    FailureClass::Rpc,
+                    "failed to fetch block_number from Transpose response",
+                ))
             }
         };
         let transaction_hash: String = match result.get("transaction_hash") {
             Some(transaction_hash) => match transaction_hash.as_str() {
                 Some(transaction_hash) => transaction_hash.to_string(),
                 None => {
-                    logger.error("failed to parse transaction_hash from Transpose");
-                    std::process::exit(1)
+                    return Err(Failure::new(
+                        FailureClass::Rpc,
+                        "failed to parse transaction_hash from Transpose",
+                    ))
                 }
             },
             None => {
-                logger.error("failed to fetch transaction_hash from Transpose response");
-                std::process::exit(1)
+                return Err(Failure::new(
+                    FailureClass::Rpc,
+                    "failed to fetch transaction_hash from Transpose response",
+                ))
             }
         };
 
@@ -163,7 +193,7 @@ pub async fn get_transaction_list(
     // sort the transactions by block number
     transactions.sort_by(|a, b| a.0.cmp(&b.0));
 
-    transactions
+    Ok(transactions)
 }
 
 /// Get the contrct creation block and transaction hash for the given address.
@@ -181,14 +211,12 @@ pub async fn get_contract_creation(
     chain: &str,
     address: &str,
     api_key: &str,
-) -> Option<(u128, String)> {
+) -> Result<Option<(u128, String)>, Failure> {
     // get a new logger
     let logger = Logger::default();
 
     // get a new progress bar
-    let transaction_list_progress = ProgressBar::new_spinner();
-    transaction_list_progress.enable_steady_tick(Duration::from_millis(100));
-    transaction_list_progress.set_style(logger.info_spinner());
+    let transaction_list_progress = spinner(logger.info_spinner());
     transaction_list_progress.set_message(format!("fetching '{address}''s creation tx ."));
     let start_time = Instant::now();
 
@@ -197,13 +225,9 @@ pub async fn get_contract_creation(
         "{{\"sql\":\"SELECT block_number, transaction_hash FROM {chain}.transactions WHERE TIMESTAMP = ( SELECT created_timestamp FROM {chain}.accounts WHERE address = '{address}' ) AND contract_address = '{address}'\",\"parameters\":{{}},\"options\":{{\"timeout\": 999999999}}}}",
     );
 
-    let response = match _call_transpose(&query, api_key).await {
-        Some(response) => response,
-        None => {
-            logger.error("failed to get creation tx from Transpose");
-            std::process::exit(1)
-        }
-    };
+    let response = _call_transpose(&query, api_key).await.map_err(|e| {
+        Failure::new(e.class, format!("failed to get creation tx from Transpose: {e}"))
+    })?;
 
     transaction_list_progress.finish_and_clear();
     logger.debug(&format!("fetching contract creation took {:?}", start_time.elapsed()));
@@ -214,31 +238,39 @@ pub async fn get_contract_creation(
             Some(block_number) => match block_number.as_u64() {
                 Some(block_number) => block_number as u128,
                 None => {
-                    logger.error("failed to parse block_number from Transpose");
-                    std::process::exit(1)
+                    return Err(Failure::new(
+                        FailureClass::Rpc,
+                        "failed to parse block_number from Transpose",
+                    ))
                 }
             },
             None => {
-                logger.error("failed to fetch block_number from Transpose response");
-                std::process::exit(1)
+                return Err(Failure::new(
+                    FailureClass::Rpc,
+                    "failed to fetch block_number from Transpose response",
+                ))
             }
         };
         let transaction_hash: String = match result.get("transaction_hash") {
             Some(transaction_hash) => match transaction_hash.as_str() {
                 Some(transaction_hash) => transaction_hash.to_string(),
                 None => {
-                    logger.error("failed to parse transaction_hash from Transpose");
-                    std::process::exit(1)
+                    return Err(Failure::new(
+                        FailureClass::Rpc,
+                        "failed to parse transaction_hash from Transpose",
+                    ))
                 }
             },
             None => {
-                logger.error("failed to fetch transaction_hash from Transpose response");
-                std::process::exit(1)
+                return Err(Failure::new(
+                    FailureClass::Rpc,
+                    "failed to fetch transaction_hash from Transpose response",
+                ))
             }
         };
 
-        return Some((block_number, transaction_hash))
+        return Ok(Some((block_number, transaction_hash)))
     };
 
-    None
+    Ok(None)
 }
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// whether output should be identical between runs on the same input
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+
+/// The block timestamp the VM's `TIMESTAMP` opcode returns in deterministic mode, rather than the
+/// current time.
+pub const DETERMINISTIC_TIMESTAMP: u64 = 1_700_000_000;
+
+/// Set whether output should be identical between runs on the same input, so it can be committed
+/// and diffed. Functions are analyzed in selector order, timestamps are omitted from logs, and
+/// `TIMESTAMP` returns [`DETERMINISTIC_TIMESTAMP`]. This is set by `--deterministic`. \
+/// \
+/// Symbolic execution decides which branches to follow in the same order on every run, however
+/// many threads explore them, so only `--exec-timeout` can stop it at a different point.
+pub fn set_deterministic(enabled: bool) {
+    DETERMINISTIC.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether output should be identical between runs on the same input.
+pub fn deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::Relaxed)
+}
@@ -0,0 +1,161 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use serde_json::{json, Value};
+
+use crate::utils::io::logging::Logger;
+
+// whether failures are reported as JSON on stderr, rather than as log messages
+static JSON_FAILURES: AtomicBool = AtomicBool::new(false);
+
+/// The classes of failure heimdall distinguishes, each with a stable exit code, so orchestration
+/// systems can tell failures worth retrying apart from permanent ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureClass {
+    /// An unexpected failure, or one which doesn't fit another class. Exits with `1`.
+    Internal,
+
+    /// The arguments or input were invalid, such as malformed hex or an unsupported format.
+    /// Exits with `2`, as invalid command lines do.
+    InvalidInput,
+
+    /// The RPC provider, or another API, couldn't be reached or returned an error. Exits with
+    /// `3`, and is worth retrying.
+    Rpc,
+
+    /// The target couldn't be resolved to bytecode, calldata, or a transaction. Exits with `4`.
+    UnresolvedTarget,
+
+    /// Analysis stopped at its time limit, so its output is incomplete. Exits with `5`, and is
+    /// worth retrying with a longer limit.
+    AnalysisTimeout,
+
+    /// Analysis finished and its output was written, but some of it stopped early, such as at a
+    /// branch or depth limit. Exits with `6`.
+    PartialSuccess,
+}
+
+impl FailureClass {
+    /// The process exit code for this class of failure.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            FailureClass::Internal => 1,
+            FailureClass::InvalidInput => 2,
+            FailureClass::Rpc => 3,
+            FailureClass::UnresolvedTarget => 4,
+            FailureClass::AnalysisTimeout => 5,
+            FailureClass::PartialSuccess => 6,
+        }
+    }
+
+    /// Whether the same command may succeed if it's run again.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, FailureClass::Rpc | FailureClass::AnalysisTimeout)
+    }
+}
+
+impl Display for FailureClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureClass::Internal => write!(f, "internal"),
+            FailureClass::InvalidInput => write!(f, "invalid_input"),
+            FailureClass::Rpc => write!(f, "rpc"),
+            FailureClass::UnresolvedTarget => write!(f, "unresolved_target"),
+            FailureClass::AnalysisTimeout => write!(f, "analysis_timeout"),
+            FailureClass::PartialSuccess => write!(f, "partial_success"),
+        }
+    }
+}
+
+/// A classified failure, returned by library functions so their callers decide whether to exit,
+/// retry, or report it.
+///
+/// ```
+/// use heimdall_common::utils::failure::{Failure, FailureClass};
+///
+/// let failure = Failure::new(FailureClass::Rpc, "failed to fetch bytecode");
+/// assert_eq!(failure.to_string(), "failed to fetch bytecode");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub class: FailureClass,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(class: FailureClass, message: impl Into<String>) -> Self {
+        Self { class, message: message.into() }
+    }
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Enables or disables reporting failures as JSON objects on stderr.
+pub fn set_json_failures(enabled: bool) {
+    JSON_FAILURES.store(enabled, Ordering::Relaxed);
+}
+
+/// Build the JSON object a failure is reported as.
+///
+/// ```
+/// use heimdall_common::utils::failure::{failure_json, FailureClass};
+///
+/// let failure = failure_json(FailureClass::Rpc, "failed to fetch bytecode");
+/// assert_eq!(failure["error"]["exit_code"], 3);
+/// assert_eq!(failure["error"]["retryable"], true);
+/// ```
+pub fn failure_json(class: FailureClass, message: &str) -> Value {
+    json!({
+        "error": {
+            "class": class.to_string(),
+            "exit_code": class.exit_code(),
+            "retryable": class.is_retryable(),
+            "message": message,
+        }
+    })
+}
+
+/// Report a failure, either as a JSON object on stderr or as an error message, without exiting.
+pub fn report_failure(class: FailureClass, message: &str) {
+    match JSON_FAILURES.load(Ordering::Relaxed) {
+        true => eprintln!("{}", failure_json(class, message)),
+        false => Logger::default().error(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::failure::{failure_json, FailureClass};
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let classes = [
+            FailureClass::Internal,
+            FailureClass::InvalidInput,
+            FailureClass::Rpc,
+            FailureClass::UnresolvedTarget,
+            FailureClass::AnalysisTimeout,
+            FailureClass::PartialSuccess,
+        ];
+        let mut codes = classes.iter().map(FailureClass::exit_code).collect::<Vec<_>>();
+        codes.dedup();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_failure_json() {
+        let failure = failure_json(FailureClass::UnresolvedTarget, "transaction doesn't exist");
+        assert_eq!(failure["error"]["class"], "unresolved_target");
+        assert_eq!(failure["error"]["exit_code"], 4);
+        assert_eq!(failure["error"]["retryable"], false);
+        assert_eq!(failure["error"]["message"], "transaction doesn't exist");
+    }
+}
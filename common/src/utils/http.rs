@@ -1,13 +1,85 @@
-use crate::utils::io::logging::Logger;
+#[cfg(target_arch = "wasm32")]
+use crate::ether::injected::http_request;
+use crate::{ether::rpc::RetryBackoff, utils::io::logging::Logger};
+#[cfg(not(target_arch = "wasm32"))]
 use async_recursion::async_recursion;
-use reqwest::Client;
+use backoff::backoff::Backoff;
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response};
 use serde_json::Value;
-use std::time::Duration;
+use std::{
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    time::Duration,
+};
 use tokio::time::sleep as async_sleep;
 
+#[cfg(not(target_arch = "wasm32"))]
 static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
-/// Make a GET request to the target URL and return the response body as JSON
+// the most time a single lookup, such as of a signature or a verified contract, may take, in
+// milliseconds
+static LOOKUP_TIMEOUT: AtomicU64 = AtomicU64::new(15_000);
+
+// how many times a lookup which fails to send is retried before giving up
+static LOOKUP_RETRIES: AtomicU32 = AtomicU32::new(3);
+
+/// Set the most time a single lookup to an external service, such as a signature database,
+/// Sourcify, or a block explorer, may take before it fails. The default is 15 seconds.
+pub fn set_lookup_timeout(timeout: Duration) {
+    LOOKUP_TIMEOUT.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// The most time a single lookup to an external service may take.
+pub fn lookup_timeout() -> Duration {
+    Duration::from_millis(LOOKUP_TIMEOUT.load(Ordering::Relaxed))
+}
+
+/// Set how many times a lookup to an external service which fails to send, such as when it times
+/// out, is retried before giving up. The default is 3.
+pub fn set_lookup_retries(retries: u32) {
+    LOOKUP_RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// How many times a lookup to an external service is retried.
+pub fn lookup_retries() -> u32 {
+    LOOKUP_RETRIES.load(Ordering::Relaxed)
+}
+
+/// Builds an HTTP client whose requests fail after the given timeout.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn client_builder(timeout: Duration) -> ClientBuilder {
+    Client::builder().timeout(timeout)
+}
+
+/// Builds an HTTP client. When targeting wasm32, requests are made by the browser, which enforces
+/// its own timeout.
+#[cfg(target_arch = "wasm32")]
+pub fn client_builder(_timeout: Duration) -> ClientBuilder {
+    Client::builder()
+}
+
+/// Send the request made by `request`, retrying it up to [`lookup_retries`] times, with an
+/// exponential backoff, if it fails to send.
+pub async fn send_with_retries(
+    request: impl Fn() -> RequestBuilder,
+) -> Result<Response, reqwest::Error> {
+    let mut backoff = RetryBackoff::new(lookup_retries());
+    loop {
+        match request().send().await {
+            Ok(response) => return Ok(response),
+            Err(e) => match backoff.next_backoff() {
+                Some(wait) => {
+                    Logger::default().debug_max(&format!("request failed, retrying: {e}"));
+                    async_sleep(wait).await
+                }
+                None => return Err(e),
+            },
+        }
+    }
+}
+
+/// Make a GET request to the target URL and return the response body as JSON, retrying up to
+/// [`lookup_retries`] times if it fails, with the given timeout in seconds. When targeting
+/// wasm32, the request is always made through the injected HTTP callback.
 ///
 /// ```no_run
 /// use heimdall_common::utils::http::get_json_from_url;
@@ -16,17 +88,27 @@ static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_P
 /// let timeout = 5;
 /// // get_json_from_url(url, timeout).await;
 /// ```
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
 pub async fn get_json_from_url(url: &str, timeout: u64) -> Result<Option<Value>, reqwest::Error> {
-    _get_json_from_url(url, 0, 5, timeout).await
+    // when targeting wasm32, the host makes the request through the injected callback
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        _get_json_from_url(url, 0, lookup_retries(), timeout).await
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Ok(http_request(url).await)
+    }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 #[async_recursion]
 /// Internal function for making a GET request to the target URL and returning the response body as
 /// JSON
 async fn _get_json_from_url(
     url: &str,
-    retry_count: u8,
-    retries_remaining: u8,
+    retry_count: u32,
+    retries_remaining: u32,
     timeout: u64,
 ) -> Result<Option<Value>, reqwest::Error> {
     // get a new logger
@@ -34,10 +116,9 @@ async fn _get_json_from_url(
 
     logger.debug_max(&format!("GET {}", &url));
 
-    let client = Client::builder()
+    let client = client_builder(Duration::from_secs(timeout))
         .danger_accept_invalid_certs(true)
         .user_agent(APP_USER_AGENT)
-        .timeout(Duration::from_secs(timeout))
         .build()?;
 
     let res = match client.get(url).send().await {
@@ -54,7 +135,7 @@ async fn _get_json_from_url(
             // exponential backoff
             let retry_count = retry_count + 1;
             let retries_remaining = retries_remaining - 1;
-            let sleep_time = 2u64.pow(retry_count as u32) * 250;
+            let sleep_time = 2u64.pow(retry_count.min(8)) * 250;
             async_sleep(Duration::from_millis(sleep_time)).await;
             return _get_json_from_url(url, retry_count, retries_remaining, timeout).await
         }
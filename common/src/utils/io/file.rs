@@ -1,13 +1,16 @@
-use super::logging::Logger;
+use crate::utils::strings::encode_hex;
+
+use serde_json::Value;
 
 use std::{
     env,
     fs::File,
-    io::{Read, Write},
+    io::{Error, ErrorKind, Read, Write},
     process::Command,
 };
 
-/// Convert a long path to a short path.
+/// Convert a long path to a short path. If the current directory can't be read, the path is
+/// returned unchanged.
 ///
 /// ```no_run
 /// use heimdall_common::utils::io::file::short_path;
@@ -17,11 +20,10 @@ use std::{
 /// assert_eq!(short_path, "./something.json");
 /// ```
 pub fn short_path(path: &str) -> String {
-    let current_dir = match env::current_dir() {
-        Ok(dir) => dir.into_os_string().into_string().unwrap(),
-        Err(_) => std::process::exit(1),
-    };
-    path.replace(&current_dir, ".")
+    match env::current_dir() {
+        Ok(dir) => path.replace(&dir.to_string_lossy().to_string(), "."),
+        Err(_) => path.to_string(),
+    }
 }
 
 /// Write contents to a file on the disc
@@ -33,29 +35,18 @@ pub fn short_path(path: &str) -> String {
 /// let contents = "Hello, World!";
 /// let result = write_file(path, contents);
 /// ```
-pub fn write_file(_path: &str, contents: &str) -> String {
+pub fn write_file(_path: &str, contents: &str) -> Result<String, Error> {
     let path = std::path::Path::new(_path);
-    let prefix = path.parent().unwrap();
-    std::fs::create_dir_all(prefix).unwrap();
-
-    let mut file = match File::create(path) {
-        Ok(file) => file,
-        Err(_) => {
-            let (logger, _) = Logger::new("");
-            logger.error(&format!("failed to create file \"{_path}\" ."));
-            std::process::exit(1)
-        }
-    };
-    match file.write_all(contents.as_bytes()) {
-        Ok(_) => {}
-        Err(_) => {
-            let (logger, _) = Logger::new("");
-            logger.error(&format!("failed to write to file \"{_path}\" ."));
-            std::process::exit(1)
-        }
+    if let Some(prefix) = path.parent() {
+        std::fs::create_dir_all(prefix)?;
     }
 
-    _path.to_string()
+    let mut file = File::create(path)
+        .map_err(|e| Error::new(e.kind(), format!("failed to create file \"{_path}\": {e}")))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| Error::new(e.kind(), format!("failed to write to file \"{_path}\": {e}")))?;
+
+    Ok(_path.to_string())
 }
 
 /// Write contents to a file on the disc
@@ -67,8 +58,33 @@ pub fn write_file(_path: &str, contents: &str) -> String {
 /// let contents = vec![String::from("Hello"), String::from("World!")];
 /// let result = write_lines_to_file(path, contents);
 /// ```
-pub fn write_lines_to_file(_path: &str, contents: Vec<String>) {
-    write_file(_path, &contents.join("\n"));
+pub fn write_lines_to_file(_path: &str, contents: Vec<String>) -> Result<(), Error> {
+    write_file(_path, &contents.join("\n")).map(|_| ())
+}
+
+/// Append lines to a file on the disc, creating it if it doesn't exist
+///
+/// ```no_run
+/// use heimdall_common::utils::io::file::append_lines_to_file;
+///
+/// let path = "/tmp/test.txt";
+/// let contents = vec![String::from("Hello"), String::from("World!")];
+/// append_lines_to_file(path, contents);
+/// ```
+pub fn append_lines_to_file(_path: &str, contents: Vec<String>) -> Result<(), Error> {
+    let path = std::path::Path::new(_path);
+    if let Some(prefix) = path.parent() {
+        std::fs::create_dir_all(prefix)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| Error::new(e.kind(), format!("failed to open file \"{_path}\": {e}")))?;
+    let contents = contents.into_iter().map(|line| line + "\n").collect::<String>();
+    file.write_all(contents.as_bytes())
+        .map_err(|e| Error::new(e.kind(), format!("failed to write to file \"{_path}\": {e}")))
 }
 
 /// Read contents from a file on the disc
@@ -79,26 +95,173 @@ pub fn write_lines_to_file(_path: &str, contents: Vec<String>) {
 /// let path = "/tmp/test.txt";
 /// let contents = read_file(path);
 /// ```
-pub fn read_file(_path: &str) -> String {
+pub fn read_file(_path: &str) -> Result<String, Error> {
     let path = std::path::Path::new(_path);
-    let mut file = match File::open(path) {
-        Ok(file) => file,
-        Err(_) => {
-            let (logger, _) = Logger::new("");
-            logger.error(&format!("failed to open file \"{_path}\" ."));
-            std::process::exit(1)
-        }
-    };
+    let mut file = File::open(path)
+        .map_err(|e| Error::new(e.kind(), format!("failed to open file \"{_path}\": {e}")))?;
     let mut contents = String::new();
-    match file.read_to_string(&mut contents) {
-        Ok(_) => {}
-        Err(_) => {
-            let (logger, _) = Logger::new("");
-            logger.error(&format!("failed to read file \"{_path}\" ."));
-            std::process::exit(1)
+    file.read_to_string(&mut contents)
+        .map_err(|e| Error::new(e.kind(), format!("failed to read file \"{_path}\": {e}")))?;
+    Ok(contents)
+}
+
+/// Read bytecode from a file, or from stdin if `path` is `-`. Hex-encoded bytecode (as produced
+/// by `solc --bin`) may be prefixed with `0x` and contain whitespace, and files which aren't
+/// hex-encoded are treated as raw binary bytecode, unless they have a `.hex` extension.
+///
+/// Compiler artifacts are also accepted, in which case the contract's deployed bytecode is read
+/// from them. This includes forge and hardhat artifacts, `solc --combined-json bin-runtime`
+/// output, and solc's standard JSON output. When an artifact contains more than one contract, the
+/// contract is selected with a `:Name` suffix, such as `out.json:Token`.
+///
+/// Returns the hex-encoded bytecode, without a `0x` prefix.
+///
+/// ```no_run
+/// use heimdall_common::utils::io::file::read_bytecode;
+///
+/// let bytecode = read_bytecode("/tmp/contract.bin");
+/// let bytecode = read_bytecode("out/Token.sol/Token.json");
+/// let bytecode = read_bytecode("combined.json:Token");
+/// ```
+pub fn read_bytecode(path: &str) -> Result<String, Error> {
+    // `artifact.json:Name` selects a contract from an artifact. the name may itself contain `:`,
+    // as in `combined.json:src/Token.sol:Token`
+    let (path, contract) = match std::path::Path::new(path).exists() {
+        true => (path, None),
+        false => path
+            .match_indices(':')
+            .map(|(index, _)| (&path[..index], &path[index + 1..]))
+            .find(|(file, _)| std::path::Path::new(file).is_file())
+            .map_or((path, None), |(file, name)| (file, Some(name))),
+    };
+
+    let mut contents = Vec::new();
+    if path == "-" {
+        std::io::stdin().read_to_end(&mut contents)?;
+    } else {
+        File::open(path)?.read_to_end(&mut contents)?;
+    }
+
+    if let Ok(artifact) = serde_json::from_slice::<Value>(&contents) {
+        if artifact.is_object() {
+            return bytecode_from_artifact(&artifact, contract)
+        }
+    }
+    if contract.is_some() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "contracts can only be selected from compiler artifacts",
+        ))
+    }
+
+    if let Ok(text) = std::str::from_utf8(&contents) {
+        let hex: String = text.split_whitespace().collect();
+        let hex = hex.strip_prefix("0x").unwrap_or(&hex);
+        if hex.len().is_multiple_of(2) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(hex.to_string())
         }
     }
-    contents
+
+    if path.ends_with(".hex") || path == "-" {
+        return Err(Error::new(ErrorKind::InvalidData, "input doesn't contain valid bytecode"))
+    }
+
+    Ok(encode_hex(contents))
+}
+
+/// Read the deployed bytecode of a contract from a compiler artifact, returning it hex-encoded,
+/// without a `0x` prefix. If `contract` is `None`, the artifact must contain exactly one contract
+/// with deployed bytecode, so interfaces and abstract contracts in the same build are skipped.
+///
+/// ```
+/// use heimdall_common::utils::io::file::bytecode_from_artifact;
+/// use serde_json::json;
+///
+/// let artifact = json!({ "deployedBytecode": { "object": "0x6080604052" } });
+/// assert_eq!(bytecode_from_artifact(&artifact, None).unwrap(), "6080604052");
+/// ```
+pub fn bytecode_from_artifact(artifact: &Value, contract: Option<&str>) -> Result<String, Error> {
+    let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+
+    // forge and hardhat artifacts, which hold a single contract
+    if let Some(bytecode) = artifact.get("deployedBytecode") {
+        let bytecode = bytecode.get("object").unwrap_or(bytecode);
+        return match bytecode.as_str() {
+            Some(bytecode) => artifact_hex(bytecode, contract.unwrap_or("artifact")),
+            None => Err(invalid("artifact's 'deployedBytecode' isn't a string".to_string())),
+        }
+    }
+
+    // `solc --combined-json` output is keyed by `path:Name`, and solc's standard JSON output by
+    // path, then name
+    let contracts = artifact
+        .get("contracts")
+        .and_then(Value::as_object)
+        .ok_or_else(|| invalid("input isn't a recognized compiler artifact".to_string()))?;
+    let mut candidates = Vec::new();
+    for (key, value) in contracts {
+        if let Some(bytecode) = value.get("bin-runtime").and_then(Value::as_str) {
+            candidates.push((key.clone(), bytecode));
+        } else if let Some(file) = value.as_object() {
+            for (name, value) in file {
+                if let Some(bytecode) =
+                    value.pointer("/evm/deployedBytecode/object").and_then(Value::as_str)
+                {
+                    candidates.push((format!("{key}:{name}"), bytecode));
+                }
+            }
+        }
+    }
+
+    let matches = |key: &str, name: &str| {
+        key == name || key.rsplit_once(':').is_some_and(|(_, contract)| contract == name)
+    };
+    let selected = match contract {
+        Some(name) => candidates.iter().filter(|(key, _)| matches(key, name)).collect::<Vec<_>>(),
+        None => candidates
+            .iter()
+            .filter(|(_, bytecode)| !bytecode.trim_start_matches("0x").is_empty())
+            .collect::<Vec<_>>(),
+    };
+
+    match selected.as_slice() {
+        [(key, bytecode)] => artifact_hex(bytecode, key),
+        [] => match contract {
+            Some(name) => {
+                Err(invalid(format!("artifact doesn't contain a contract named '{name}'")))
+            }
+            None => Err(invalid("artifact doesn't contain any deployed bytecode".to_string())),
+        },
+        _ => Err(invalid(format!(
+            "artifact contains multiple contracts, select one with ':Name'. found: {}",
+            selected.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+// validate a contract's deployed bytecode from an artifact, stripping its `0x` prefix
+fn artifact_hex(bytecode: &str, contract: &str) -> Result<String, Error> {
+    let hex = bytecode.trim().trim_start_matches("0x");
+    if hex.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("'{contract}' has no deployed bytecode, it may be abstract or an interface"),
+        ))
+    }
+    if hex.contains("__") {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("'{contract}' has unlinked library references, link them before analyzing it"),
+        ))
+    }
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("'{contract}' doesn't contain valid bytecode"),
+        ))
+    }
+
+    Ok(hex.to_string())
 }
 
 /// Delete a file from the disc
@@ -113,3 +276,78 @@ pub fn delete_path(_path: &str) -> bool {
     let path = std::path::Path::new(_path);
     Command::new("rm").args(["-rf", path.to_str().unwrap()]).output().is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::io::file::*;
+
+    #[test]
+    fn test_read_bytecode_hex() {
+        let path = env::temp_dir().join("heimdall_test_read_bytecode.hex");
+        write_file(path.to_str().unwrap(), "0x6080\n6040\n").unwrap();
+
+        assert_eq!(read_bytecode(path.to_str().unwrap()).unwrap(), "60806040");
+
+        write_file(path.to_str().unwrap(), "not bytecode").unwrap();
+        assert!(read_bytecode(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_read_bytecode_binary() {
+        let path = env::temp_dir().join("heimdall_test_read_bytecode.bin");
+        std::fs::write(&path, [0x60, 0x80, 0x60, 0x40, 0xfe]).unwrap();
+
+        assert_eq!(read_bytecode(path.to_str().unwrap()).unwrap(), "60806040fe");
+    }
+
+    #[test]
+    fn test_read_bytecode_artifact() {
+        let path = env::temp_dir().join("heimdall_test_read_bytecode_forge.json");
+        write_file(
+            path.to_str().unwrap(),
+            r#"{"abi":[],"bytecode":{"object":"0x00"},"deployedBytecode":{"object":"0x6080fe"}}"#,
+        )
+        .unwrap();
+        assert_eq!(read_bytecode(path.to_str().unwrap()).unwrap(), "6080fe");
+
+        let path = env::temp_dir().join("heimdall_test_read_bytecode_hardhat.json");
+        write_file(path.to_str().unwrap(), r#"{"deployedBytecode":"0x6080fe"}"#).unwrap();
+        assert_eq!(read_bytecode(path.to_str().unwrap()).unwrap(), "6080fe");
+    }
+
+    #[test]
+    fn test_read_bytecode_combined_json() {
+        let path = env::temp_dir().join("heimdall_test_read_bytecode_combined.json");
+        let path = path.to_str().unwrap();
+        write_file(
+            path,
+            r#"{"contracts":{"src/Token.sol:IToken":{"bin-runtime":""},"src/Token.sol:Token":{"bin-runtime":"6080fe"}},"version":"0.8.24"}"#,
+        )
+        .unwrap();
+
+        // interfaces are skipped when no contract is selected
+        assert_eq!(read_bytecode(path).unwrap(), "6080fe");
+        assert_eq!(read_bytecode(&format!("{path}:Token")).unwrap(), "6080fe");
+        assert_eq!(read_bytecode(&format!("{path}:src/Token.sol:Token")).unwrap(), "6080fe");
+        assert!(read_bytecode(&format!("{path}:IToken")).is_err());
+        assert!(read_bytecode(&format!("{path}:Missing")).is_err());
+    }
+
+    #[test]
+    fn test_bytecode_from_standard_json() {
+        let artifact = serde_json::json!({
+            "contracts": {
+                "src/A.sol": {
+                    "A": { "evm": { "deployedBytecode": { "object": "6001" } } },
+                    "B": { "evm": { "deployedBytecode": { "object": "6002" } } },
+                }
+            }
+        });
+
+        assert!(bytecode_from_artifact(&artifact, None).is_err());
+        assert_eq!(bytecode_from_artifact(&artifact, Some("B")).unwrap(), "6002");
+
+        let unlinked = serde_json::json!({ "deployedBytecode": "0x73__$abc$__fe" });
+        assert!(bytecode_from_artifact(&unlinked, None).is_err());
+    }
+}
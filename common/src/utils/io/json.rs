@@ -0,0 +1,26 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::utils::version::current_version;
+
+/// The version of the schemas of heimdall's JSON output. It's incremented whenever a module's JSON
+/// result changes in a way which isn't backwards compatible, such as a field being renamed.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Wrap a module's result in the document heimdall prints for `--json`, which names the schema of
+/// the result, such as `heimdall.decode.v1`, and the version of heimdall which produced it.
+///
+/// ```
+/// use heimdall_common::utils::io::json::json_document;
+///
+/// let document = json_document("encode", &serde_json::json!({ "calldata": "0x" })).unwrap();
+/// assert_eq!(document["schema"], "heimdall.encode.v1");
+/// assert_eq!(document["result"]["calldata"], "0x");
+/// ```
+pub fn json_document<T: Serialize>(module: &str, result: &T) -> Result<Value, serde_json::Error> {
+    Ok(json!({
+        "schema": format!("heimdall.{module}.v{JSON_SCHEMA_VERSION}"),
+        "version": current_version().to_string(),
+        "result": serde_json::to_value(result)?,
+    }))
+}
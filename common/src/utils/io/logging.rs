@@ -1,12 +1,74 @@
 use indicatif::ProgressStyle;
-use std::io::{stdin, stdout};
+use std::{
+    io::{stderr, stdin, stdout},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-use colored::*;
-
-use crate::utils::time::pretty_timestamp;
+use crate::utils::{
+    determinism::deterministic,
+    io::theme::{tick_chars, Paint, Role},
+    time::pretty_timestamp,
+};
 
 use super::super::strings::replace_last;
 
+// whether heimdall may prompt the user, and draw spinners
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+/// Set whether heimdall is interactive. When it isn't, such as with `--ci`, prompts select their
+/// default option rather than reading stdin, and spinners are hidden.
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+/// Whether heimdall may prompt the user, and draw spinners.
+pub fn interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+// whether the logger writes to stderr, rather than stdout
+static STDERR_LOGS: AtomicBool = AtomicBool::new(false);
+
+/// Set whether the logger's messages and prompts are written to stderr rather than stdout, so
+/// stdout only holds a command's result, such as with `--json`.
+pub fn set_stderr_logs(enabled: bool) {
+    STDERR_LOGS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the logger's messages and prompts are written to stderr.
+pub fn stderr_logs() -> bool {
+    STDERR_LOGS.load(Ordering::Relaxed)
+}
+
+/// The timestamp which prefixes each logged line, followed by its separator, which is empty in
+/// deterministic mode so logs are identical between runs.
+pub fn log_timestamp() -> String {
+    match deterministic() {
+        true => String::new(),
+        false => format!("{}  ", pretty_timestamp().paint(Role::Muted)),
+    }
+}
+
+// write to the log, which is stdout unless `set_stderr_logs` is enabled
+macro_rules! log {
+    ($($arg:tt)*) => {
+        match stderr_logs() {
+            true => eprint!($($arg)*),
+            false => print!($($arg)*),
+        }
+    };
+}
+
+// write a line to the log, which is stdout unless `set_stderr_logs` is enabled
+macro_rules! log_line {
+    ($($arg:tt)*) => {
+        match stderr_logs() {
+            true => eprintln!($($arg)*),
+            false => println!($($arg)*),
+        }
+    };
+}
+
 /// A logger which can be used to log messages to the console
 /// in a standardized format.
 #[derive(Clone)]
@@ -80,7 +142,7 @@ impl TraceFactory {
     /// display the trace to the console if the verbosity is high enough
     pub fn display(&self) {
         if self.level >= 3 {
-            println!("{}:", "trace".bright_blue().bold());
+            println!("{}:", "trace".paint(Role::Trace));
             for index in 0..self.traces.len() {
                 // safe to unwrap because we just iterated over the traces
                 let trace = self.traces.get(index).expect("Failed to build trace.");
@@ -106,36 +168,34 @@ impl TraceFactory {
                 // print the trace title
                 println!(
                     "{} {} {}",
-                    replace_last(prefix, "│ ", " ├─").bold().bright_white(),
-                    format!("[{}]", trace.instruction).bold().bright_white(),
+                    replace_last(prefix, "│ ", " ├─").paint(Role::Emphasis),
+                    format!("[{}]", trace.instruction).paint(Role::Emphasis),
                     trace.message.first().expect("Failed to build trace.")
                 );
 
                 // print the children
                 for child in &trace.children {
-                    self.print_trace(
-                        &format!("{prefix}   │").bold().bright_white(),
-                        *child as usize - 1,
-                    );
+                    self.print_trace(&format!("{prefix}   │"), *child as usize - 1);
                 }
 
                 // print the return value
                 println!(
-                    "{} ← {}",
-                    format!("{prefix}   └─").bold().bright_white(),
+                    "{} {} {}",
+                    format!("{prefix}   └─").paint(Role::Emphasis),
+                    "←".paint(Role::Text),
                     match trace.message.get(1) {
-                        Some(message) => format!(
-                            "{}",
-                            if message == "()" { message.dimmed() } else { message.green() }
-                        ),
-                        None => "()".dimmed().to_string(),
+                        Some(message) => match message.as_str() {
+                            "()" => message.paint(Role::Muted),
+                            _ => message.paint(Role::Value),
+                        },
+                        None => "()".paint(Role::Muted),
                     }
                 )
             }
             TraceCategory::Log => {
                 println!(
                     "{} emit {}",
-                    replace_last(prefix, "│ ", " ├─").bold().bright_white(),
+                    replace_last(prefix, "│ ", " ├─").paint(Role::Emphasis),
                     trace.message.first().expect("Failed to build trace.")
                 );
             }
@@ -148,26 +208,26 @@ impl TraceFactory {
                         println!(
                             "{} {} {}: {}",
                             if message_index == 0 {
-                                replace_last(prefix, "│ ", " ├─").bold().bright_white()
+                                replace_last(prefix, "│ ", " ├─").paint(Role::Emphasis)
                             } else {
-                                replace_last(prefix, "│ ", " │ ").bold().bright_white()
+                                replace_last(prefix, "│ ", " │ ").paint(Role::Emphasis)
                             },
                             if message_index == 0 { "emit" } else { "    " },
-                            format!("topic {message_index}").purple(),
+                            format!("topic {message_index}").paint(Role::Log),
                             message
                         );
                     }
                     println!(
                         "{}         {}: {}",
-                        replace_last(prefix, "│ ", " │ ").bold().blue(),
-                        "data".purple(),
+                        replace_last(prefix, "│ ", " │ ").paint(Role::Type),
+                        "data".paint(Role::Log),
                         trace.message.last().expect("Failed to build trace.")
                     );
                 } else {
                     println!(
                         "{} emit {}: {}",
-                        replace_last(prefix, "│ ", " ├─").bold().bright_white(),
-                        "data".purple(),
+                        replace_last(prefix, "│ ", " ├─").paint(Role::Emphasis),
+                        "data".paint(Role::Log),
                         trace.message.last().expect("Failed to build trace.")
                     );
                 }
@@ -178,11 +238,11 @@ impl TraceFactory {
                     println!(
                         "{} {}",
                         if prefix.ends_with("└─") {
-                            prefix.to_string().bold().bright_white()
+                            prefix.to_string().paint(Role::Emphasis)
                         } else if message_index == 0 {
-                            replace_last(prefix, "│ ", " ├─").bold().bright_white()
+                            replace_last(prefix, "│ ", " ├─").paint(Role::Emphasis)
                         } else {
-                            replace_last(prefix, "│ ", " │ ").bold().bright_white()
+                            replace_last(prefix, "│ ", " │ ").paint(Role::Emphasis)
                         },
                         message
                     );
@@ -191,42 +251,35 @@ impl TraceFactory {
                 // print the children
                 for (i, child) in trace.children.iter().enumerate() {
                     if i == trace.children.len() - 1 {
-                        self.print_trace(
-                            &format!("{prefix}   └─").bold().bright_white(),
-                            *child as usize - 1,
-                        );
+                        self.print_trace(&format!("{prefix}   └─"), *child as usize - 1);
                     } else {
-                        self.print_trace(
-                            &format!("{prefix}   │").bold().bright_white(),
-                            *child as usize - 1,
-                        );
+                        self.print_trace(&format!("{prefix}   │"), *child as usize - 1);
                     }
                 }
             }
             TraceCategory::Empty => {
-                println!("{}", replace_last(prefix, "│ ", " │ ").bold().bright_white());
+                println!("{}", replace_last(prefix, "│ ", " │ ").paint(Role::Emphasis));
             }
             TraceCategory::Create => {
                 println!(
-                    "{} {} create → {}",
-                    replace_last(prefix, "│ ", " ├─").bold().bright_white(),
-                    format!("[{}]", trace.instruction).bold().bright_white(),
+                    "{} {} create {} {}",
+                    replace_last(prefix, "│ ", " ├─").paint(Role::Emphasis),
+                    format!("[{}]", trace.instruction).paint(Role::Emphasis),
+                    "→".paint(Role::Text),
                     trace.message.first().expect("Failed to build trace.")
                 );
 
                 // print the children
                 for child in &trace.children {
-                    self.print_trace(
-                        &format!("{prefix}   │").bold().bright_white(),
-                        *child as usize - 1,
-                    );
+                    self.print_trace(&format!("{prefix}   │"), *child as usize - 1);
                 }
 
                 // print the return value
                 println!(
-                    "{} ← {}",
-                    format!("{prefix}   └─").bold().bright_white(),
-                    trace.message.get(1).expect("Failed to build trace.").bold().green()
+                    "{} {} {}",
+                    format!("{prefix}   └─").paint(Role::Emphasis),
+                    "←".paint(Role::Text),
+                    trace.message.get(1).expect("Failed to build trace.").paint(Role::Value)
                 )
             }
         }
@@ -248,8 +301,8 @@ impl TraceFactory {
     ) -> u32 {
         let title = format!(
             "{}::{}({})",
-            origin.bright_cyan(),
-            function_name.bright_cyan(),
+            origin.paint(Role::Call),
+            function_name.paint(Role::Call),
             args.join(", ")
         );
         self.add("call", parent_index, instruction, vec![title, returns])
@@ -264,7 +317,7 @@ impl TraceFactory {
         pointer: String,
         size: u128,
     ) -> u32 {
-        let contract = format!("{}@{}", name.green(), pointer.green(),);
+        let contract = format!("{}@{}", name.paint(Role::Value), pointer.paint(Role::Value),);
         self.add("create", parent_index, instruction, vec![contract, format!("{size} bytes")])
     }
 
@@ -276,7 +329,7 @@ impl TraceFactory {
         name: String,
         args: Vec<String>,
     ) -> u32 {
-        let log = format!("{}({})", name.purple(), args.join(", "));
+        let log = format!("{}({})", name.paint(Role::Log), args.join(", "));
         self.add("log", parent_index, instruction, vec![log])
     }
 
@@ -294,25 +347,25 @@ impl TraceFactory {
 
     /// add info message to the trace
     pub fn add_info(&mut self, parent_index: u32, instruction: u32, message: &str) -> u32 {
-        let message = format!("{} {}", "info:".bright_cyan().bold(), message);
+        let message = format!("{} {}", "info:".paint(Role::Info), message);
         self.add("message", parent_index, instruction, vec![message])
     }
 
     /// add debug message to the trace
     pub fn add_debug(&mut self, parent_index: u32, instruction: u32, message: &str) -> u32 {
-        let message = format!("{} {}", "debug:".bright_magenta().bold(), message);
+        let message = format!("{} {}", "debug:".paint(Role::Debug), message);
         self.add("message", parent_index, instruction, vec![message])
     }
 
     /// add error message to the trace
     pub fn add_error(&mut self, parent_index: u32, instruction: u32, message: &str) -> u32 {
-        let message = format!("{} {}", "error:".bright_red().bold(), message);
+        let message = format!("{} {}", "error:".paint(Role::Error), message);
         self.add("message", parent_index, instruction, vec![message])
     }
 
     /// add warn message to the trace
     pub fn add_warn(&mut self, parent_index: u32, instruction: u32, message: &str) -> u32 {
-        let message = format!("{} {}", "warn:".bright_yellow().bold(), message);
+        let message = format!("{} {}", "warn:".paint(Role::Warn), message);
         self.add("message", parent_index, instruction, vec![message])
     }
 
@@ -394,92 +447,57 @@ impl Logger {
     /// log an error message
     pub fn error(&self, message: &str) {
         if self.level >= 0 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "error".bright_red().bold(),
-                message
-            );
+            log_line!("{}{}: {}", log_timestamp(), "error".paint(Role::Error), message);
         }
     }
 
     /// log a fatal error, typically an unhanded exception which causes the program to exit
     pub fn fatal(&self, message: &str) {
-        println!(
-            "{}  {}: {}",
-            pretty_timestamp().dimmed(),
-            "fatal".bright_white().on_bright_red().bold(),
-            message
-        );
+        log_line!("{}{}: {}", log_timestamp(), "fatal".paint(Role::Fatal), message);
     }
 
     /// log a success message
     pub fn success(&self, message: &str) {
         if self.level >= 0 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "success".bright_green().bold(),
-                message
-            );
+            log_line!("{}{}: {}", log_timestamp(), "success".paint(Role::Success), message);
         }
     }
 
     /// log an info message
     pub fn info(&self, message: &str) {
         if self.level >= 1 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "info".bright_cyan().bold(),
-                message
-            );
+            log_line!("{}{}: {}", log_timestamp(), "info".paint(Role::Info), message);
         }
     }
 
     /// log a warning message
     pub fn warn(&self, message: &str) {
         if self.level >= 0 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "warn".bright_yellow().bold(),
-                message
-            );
+            log_line!("{}{}: {}", log_timestamp(), "warn".paint(Role::Warn), message);
         }
     }
 
     /// log a debug message
     pub fn debug(&self, message: &str) {
         if self.level >= 2 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "debug".bright_magenta().bold(),
-                message
-            );
+            log_line!("{}{}: {}", log_timestamp(), "debug".paint(Role::Debug), message);
         }
     }
 
     /// log a trace message
     pub fn trace(&self, message: &str) {
         if self.level >= 4 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "trace".bright_blue().bold(),
-                message
-            );
+            log_line!("{}{}: {}", log_timestamp(), "trace".paint(Role::Trace), message);
         }
     }
 
     /// log a max message
     pub fn debug_max(&self, message: &str) {
         if self.level >= 6 {
-            println!(
-                "{}  {}: {}",
-                pretty_timestamp().dimmed(),
-                "debug".bright_white().bold(),
+            log_line!(
+                "{}{}: {}",
+                log_timestamp(),
+                "debug".paint(Role::Emphasis),
                 message.replace('\n', &("\n".to_owned() + &" ".repeat(31)))
             );
         }
@@ -488,28 +506,41 @@ impl Logger {
     /// get a formatted spinner for the given function
     pub fn info_spinner(&self) -> ProgressStyle {
         ProgressStyle::with_template(&format!(
-            "{}  {}: {}",
-            pretty_timestamp().dimmed(),
-            "info".bright_cyan().bold(),
+            "{}{}: {}",
+            log_timestamp(),
+            "info".paint(Role::Info),
             "{spinner} {msg}"
         ))
         .expect("Failed to create spinner.")
-        .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
+        .tick_chars(tick_chars())
+    }
+
+    /// get a formatted progress bar, with an ETA, for the given function
+    pub fn info_progress_bar(&self) -> ProgressStyle {
+        ProgressStyle::with_template(&format!(
+            "{}{}: {}",
+            log_timestamp(),
+            "info".paint(Role::Info),
+            "{msg} [{bar:30}] {pos}/{len} (eta {eta})"
+        ))
+        .expect("Failed to create progress bar.")
+        .progress_chars("=> ")
     }
 
     /// get a formatted spinner for the given function
     pub fn debug_spinner(&self) -> ProgressStyle {
         ProgressStyle::with_template(&format!(
-            "{}  {}: {}",
-            pretty_timestamp().dimmed(),
-            "debug".bright_magenta().bold(),
+            "{}{}: {}",
+            log_timestamp(),
+            "debug".paint(Role::Debug),
             "{spinner} {msg}"
         ))
         .expect("Failed to create spinner.")
-        .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
+        .tick_chars(tick_chars())
     }
 
-    /// prompt the user to select an option from the given list, or return the default
+    /// prompt the user to select an option from the given list, or return the default. the default,
+    /// or the first option if there's none, is always returned when heimdall isn't interactive.
     pub fn option(
         &self,
         function: &str,
@@ -534,12 +565,12 @@ impl Logger {
 
         // print the option tree
         for (i, option) in options.iter().enumerate() {
-            println!(
+            log_line!(
                 "                                      {} {}: {}",
                 if i == options.len() - 1 {
-                    "└─".bold().bright_white()
+                    "└─".paint(Role::Emphasis)
                 } else {
-                    "├─".bold().bright_white()
+                    "├─".paint(Role::Emphasis)
                 },
                 i,
                 option
@@ -548,7 +579,7 @@ impl Logger {
 
         // flush output print prompt
         let mut selection = String::new();
-        print!(
+        log!(
             "\n                                      Select an option {}: ",
             if default.is_some() {
                 format!("(default: {})", default.expect("Failed to get default option."))
@@ -556,15 +587,15 @@ impl Logger {
                 "".to_string()
             }
         );
-        let _ = std::io::Write::flush(&mut stdout());
+        let _ = match stderr_logs() {
+            true => std::io::Write::flush(&mut stderr()),
+            false => std::io::Write::flush(&mut stdout()),
+        };
 
-        if skip {
-            if default.is_some() {
-                println!("{}", default.expect("Failed to get default option."));
-            } else {
-                println!();
-            }
-            return default.expect("Failed to get default option.")
+        if skip || !interactive() {
+            let selection = default.unwrap_or(0);
+            log_line!("{selection}");
+            return selection
         }
 
         // get input
@@ -725,6 +756,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_option_non_interactive() {
+        let (logger, _) = Logger::new("TRACE");
+
+        // the prompt isn't skipped, but stdin is never read
+        set_interactive(false);
+        let selection = logger.option(
+            "warn",
+            "multiple possibilities",
+            vec!["option 1".to_string(), "option 2".to_string()],
+            Some(1),
+            false,
+        );
+        let first = logger.option(
+            "warn",
+            "multiple possibilities",
+            vec!["option 1".to_string(), "option 2".to_string()],
+            None,
+            false,
+        );
+        set_interactive(true);
+
+        assert_eq!(selection, 1);
+        assert_eq!(first, 0);
+    }
+
     #[test]
     fn test_warn() {
         let (logger, _) = Logger::new("SILENT");
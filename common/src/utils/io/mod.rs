@@ -1,4 +1,12 @@
 pub mod file;
+pub mod json;
 pub mod logging;
 pub mod macros;
+pub mod parquet;
+pub mod progress;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sqlite;
+pub mod subscriber;
+pub mod table;
+pub mod theme;
 pub mod types;
@@ -0,0 +1,175 @@
+use std::{
+    fs::File,
+    io::{Error, ErrorKind, Write},
+    sync::Arc,
+};
+
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    data_type::{ByteArray, ByteArrayType, Int64Type},
+    errors::ParquetError,
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::types::Type,
+};
+
+// the most rows written in each row group, so readers can stream large files
+const ROW_GROUP_SIZE: usize = 1_000_000;
+
+/// The values of a column in a [`ParquetTable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnData {
+    Int64(Vec<i64>),
+    Text(Vec<String>),
+}
+
+impl ColumnData {
+    fn len(&self) -> usize {
+        match self {
+            ColumnData::Int64(values) => values.len(),
+            ColumnData::Text(values) => values.len(),
+        }
+    }
+
+    /// the column's type in the file's schema, where every column is required
+    fn schema(&self, name: &str) -> Result<Arc<Type>, ParquetError> {
+        let column = match self {
+            ColumnData::Int64(_) => Type::primitive_type_builder(name, PhysicalType::INT64),
+            ColumnData::Text(_) => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+                .with_logical_type(Some(LogicalType::String)),
+        };
+        Ok(Arc::new(column.with_repetition(Repetition::REQUIRED).build()?))
+    }
+}
+
+/// A table of required, flat columns which is written as a Parquet file, so results can be
+/// loaded by pandas, duckdb, or Spark without a conversion step.
+///
+/// ```no_run
+/// use heimdall_common::utils::io::parquet::{ColumnData, ParquetTable};
+///
+/// let mut table = ParquetTable::new();
+/// table.add_column("slot", ColumnData::Text(vec![String::from("0x00")]));
+/// table.add_column("block", ColumnData::Int64(vec![17000000]));
+/// table.write("/tmp/heimdall.parquet").unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParquetTable {
+    pub columns: Vec<(String, ColumnData)>,
+}
+
+impl ParquetTable {
+    pub fn new() -> ParquetTable {
+        ParquetTable { columns: Vec::new() }
+    }
+
+    pub fn add_column(&mut self, name: &str, data: ColumnData) {
+        self.columns.push((name.to_string(), data));
+    }
+
+    /// Writes the table in the Parquet file format to the given writer, one row group at a time.
+    pub fn write_to<W: Write + Send>(&self, writer: W) -> Result<(), Error> {
+        let num_rows = self.columns.first().map(|(_, data)| data.len()).unwrap_or(0);
+        if self.columns.iter().any(|(_, data)| data.len() != num_rows) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "every column must have the same length",
+            ))
+        }
+
+        self.write_row_groups(writer, num_rows).map_err(Error::other)
+    }
+
+    fn write_row_groups<W: Write + Send>(
+        &self,
+        writer: W,
+        num_rows: usize,
+    ) -> Result<(), ParquetError> {
+        let fields = self
+            .columns
+            .iter()
+            .map(|(name, data)| data.schema(name))
+            .collect::<Result<Vec<Arc<Type>>, ParquetError>>()?;
+        let schema = Type::group_type_builder("schema").with_fields(fields).build()?;
+        let properties = WriterProperties::builder().set_max_row_group_size(ROW_GROUP_SIZE).build();
+        let mut writer = SerializedFileWriter::new(writer, Arc::new(schema), Arc::new(properties))?;
+
+        for start in (0..num_rows).step_by(ROW_GROUP_SIZE) {
+            let rows = start..num_rows.min(start + ROW_GROUP_SIZE);
+            let mut row_group = writer.next_row_group()?;
+
+            for (_, data) in &self.columns {
+                let mut column = row_group
+                    .next_column()?
+                    .ok_or_else(|| ParquetError::General(String::from("missing column writer")))?;
+                match data {
+                    ColumnData::Int64(values) => {
+                        column.typed::<Int64Type>().write_batch(
+                            &values[rows.clone()],
+                            None,
+                            None,
+                        )?;
+                    }
+                    ColumnData::Text(values) => {
+                        let values = values[rows.clone()]
+                            .iter()
+                            .map(|value| ByteArray::from(value.as_str()))
+                            .collect::<Vec<ByteArray>>();
+                        column.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+                    }
+                }
+                column.close()?;
+            }
+            row_group.close()?;
+        }
+
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Writes the table to the given path, replacing any file already there.
+    pub fn write(&self, path: &str) -> Result<(), Error> {
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.write_to(File::create(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::RowAccessor,
+    };
+
+    use crate::utils::io::parquet::{ColumnData, ParquetTable};
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let mut table = ParquetTable::new();
+        table
+            .add_column("slot", ColumnData::Text(vec![String::from("0x00"), String::from("0x01")]));
+        table.add_column("block", ColumnData::Int64(vec![1, 2]));
+        let path = std::env::temp_dir().join("heimdall_test_parquet_round_trip.parquet");
+        table.write(path.to_str().unwrap()).unwrap();
+
+        let reader = SerializedFileReader::new(std::fs::File::open(path).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        assert_eq!(schema.column(0).name(), "slot");
+        assert_eq!(schema.column(1).name(), "block");
+
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                let row = row.unwrap();
+                (row.get_string(0).unwrap().clone(), row.get_long(1).unwrap())
+            })
+            .collect::<Vec<(String, i64)>>();
+        assert_eq!(rows, vec![(String::from("0x00"), 1), (String::from("0x01"), 2)]);
+
+        // columns of different lengths are rejected
+        table.add_column("value", ColumnData::Int64(vec![1]));
+        assert!(table.write_to(Vec::new()).is_err());
+    }
+}
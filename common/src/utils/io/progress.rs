@@ -0,0 +1,155 @@
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde_json::{json, Value};
+
+use super::logging::{interactive, Logger};
+
+// whether progress is reported as lines of JSON, rather than drawn as progress bars
+static PROGRESS_LINES: AtomicBool = AtomicBool::new(false);
+
+// how often a line is written for each operation, when progress is reported as lines
+const PROGRESS_LINE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Set whether the progress of long-running operations is written to stderr as periodic lines of
+/// JSON, for logs, rather than drawn as progress bars. This is set by `--no-progress`.
+pub fn set_progress_lines(enabled: bool) {
+    PROGRESS_LINES.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the progress of long-running operations is written as lines of JSON.
+pub fn progress_lines() -> bool {
+    PROGRESS_LINES.load(Ordering::Relaxed)
+}
+
+/// Start a spinner with the given style, which is hidden when heimdall isn't interactive, or
+/// progress is reported as lines.
+pub fn spinner(style: ProgressStyle) -> ProgressBar {
+    if !interactive() || progress_lines() {
+        return ProgressBar::hidden()
+    }
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner.set_style(style);
+    spinner
+}
+
+/// The progress of a long-running operation over a known number of items, such as the selectors
+/// being decompiled, drawn as a progress bar with an ETA. With `--no-progress`, the bar is hidden,
+/// and a line of JSON with the operation's progress is written to stderr every few seconds, and
+/// when it finishes.
+///
+/// `Progress` dereferences to its [`ProgressBar`], so its message can be set, or the bar suspended,
+/// as usual.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    operation: String,
+    bar: ProgressBar,
+    last_line: Arc<Mutex<Instant>>,
+}
+
+impl Progress {
+    /// Start the progress of `operation` over `length` items.
+    pub fn new(operation: &str, length: u64, logger: &Logger) -> Self {
+        let bar = match progress_lines() {
+            true => ProgressBar::with_draw_target(Some(length), ProgressDrawTarget::hidden()),
+            false => {
+                let bar = ProgressBar::new(length);
+                bar.set_style(logger.info_progress_bar());
+                bar.enable_steady_tick(Duration::from_millis(100));
+                bar
+            }
+        };
+
+        Self {
+            operation: operation.to_string(),
+            bar,
+            last_line: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Advance the progress by `delta` items.
+    pub fn inc(&self, delta: u64) {
+        self.bar.inc(delta);
+        self.write_line(false);
+    }
+
+    /// Stop drawing the bar, such as while another spinner is shown, without losing its progress.
+    pub fn hide(&self) {
+        self.bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    /// Draw the bar again after [`Progress::hide`].
+    pub fn show(&self) {
+        if !progress_lines() {
+            self.bar.set_draw_target(ProgressDrawTarget::stderr());
+        }
+    }
+
+    /// Finish the operation, clearing its bar, or writing its final line.
+    pub fn finish_and_clear(&self) {
+        self.bar.finish_and_clear();
+        self.write_line(true);
+    }
+
+    /// The operation's progress, as written to stderr with `--no-progress`.
+    ///
+    /// ```
+    /// use heimdall_common::utils::io::{logging::Logger, progress::Progress};
+    ///
+    /// let progress = Progress::new("decompile", 4, &Logger::default());
+    /// progress.inc(1);
+    ///
+    /// let line = progress.line();
+    /// assert_eq!(line["progress"], "decompile");
+    /// assert_eq!(line["position"], 1);
+    /// assert_eq!(line["length"], 4);
+    /// assert_eq!(line["percent"], 25.0);
+    /// ```
+    pub fn line(&self) -> Value {
+        let position = self.bar.position();
+        let length = self.bar.length().unwrap_or_default();
+
+        json!({
+            "progress": self.operation,
+            "position": position,
+            "length": length,
+            "percent": match length {
+                0 => 100.0,
+                _ => (position as f64 / length as f64 * 10000.0).round() / 100.0,
+            },
+            "elapsed_seconds": self.bar.elapsed().as_secs(),
+            "eta_seconds": self.bar.eta().as_secs(),
+        })
+    }
+
+    // write the operation's progress to stderr, at most once per interval unless forced. empty
+    // operations, such as resolving a function without any errors, aren't written
+    fn write_line(&self, force: bool) {
+        if !progress_lines() || self.bar.length() == Some(0) {
+            return
+        }
+
+        let mut last_line = self.last_line.lock().expect("failed to lock last progress line");
+        if force || last_line.elapsed() >= PROGRESS_LINE_INTERVAL {
+            eprintln!("{}", self.line());
+            *last_line = Instant::now();
+        }
+    }
+}
+
+impl Deref for Progress {
+    type Target = ProgressBar;
+
+    fn deref(&self) -> &Self::Target {
+        &self.bar
+    }
+}
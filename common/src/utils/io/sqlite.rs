@@ -0,0 +1,192 @@
+use std::io::{Error, ErrorKind};
+
+use rusqlite::{
+    params_from_iter,
+    types::{ToSqlOutput, ValueRef},
+    Connection, ToSql,
+};
+
+/// A value in a SQLite row.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Text(String),
+}
+
+impl ToSql for SqlValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Borrowed(match self {
+            SqlValue::Null => ValueRef::Null,
+            SqlValue::Integer(value) => ValueRef::Integer(*value),
+            SqlValue::Text(value) => ValueRef::Text(value.as_bytes()),
+        }))
+    }
+}
+
+/// A table in a [`Database`], along with its rows and the indexes on it.
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub name: String,
+
+    // the name and type of each column, such as `("slot", "TEXT")`
+    pub columns: Vec<(String, String)>,
+    pub rows: Vec<Vec<SqlValue>>,
+
+    // the name and columns of each index on the table
+    pub indexes: Vec<(String, Vec<String>)>,
+}
+
+/// A SQLite database, for exporting results which are too large to be queried as CSV.
+///
+/// ```no_run
+/// use heimdall_common::utils::io::sqlite::{Database, SqlValue, Table};
+///
+/// let mut database = Database::new();
+/// database.add_table(Table {
+///     name: String::from("slots"),
+///     columns: vec![(String::from("slot"), String::from("TEXT"))],
+///     rows: vec![vec![SqlValue::Text(String::from("0x00"))]],
+///     indexes: vec![(String::from("slots_slot"), vec![String::from("slot")])],
+/// });
+/// database.write("/tmp/heimdall.db").unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    pub tables: Vec<Table>,
+}
+
+impl Database {
+    pub fn new() -> Database {
+        Database { tables: Vec::new() }
+    }
+
+    pub fn add_table(&mut self, table: Table) {
+        self.tables.push(table);
+    }
+
+    /// Writes the database to the given path, replacing any file already there. Rows are inserted
+    /// one at a time within a single transaction, and indexes are built once every row is in.
+    pub fn write(&self, path: &str) -> Result<(), Error> {
+        for table in &self.tables {
+            for (name, columns) in &table.indexes {
+                if let Some(column) =
+                    columns.iter().find(|column| !table.columns.iter().any(|(c, _)| c == *column))
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("index '{name}' is on unknown column '{column}'"),
+                    ))
+                }
+            }
+        }
+
+        if let Some(parent) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        self.write_to(&mut Connection::open(path).map_err(Error::other)?).map_err(Error::other)
+    }
+
+    fn write_to(&self, connection: &mut Connection) -> rusqlite::Result<()> {
+        let transaction = connection.transaction()?;
+        for table in &self.tables {
+            let columns = table
+                .columns
+                .iter()
+                .map(|(name, kind)| format!("{name} {kind}"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            transaction.execute(&format!("CREATE TABLE {} ({columns})", table.name), [])?;
+
+            let placeholders = vec!["?"; table.columns.len()].join(", ");
+            let mut insert = transaction
+                .prepare(&format!("INSERT INTO {} VALUES ({placeholders})", table.name))?;
+            for row in &table.rows {
+                insert.execute(params_from_iter(
+                    (0..table.columns.len()).map(|i| row.get(i).unwrap_or(&SqlValue::Null)),
+                ))?;
+            }
+
+            for (name, columns) in &table.indexes {
+                transaction.execute(
+                    &format!("CREATE INDEX {name} ON {} ({})", table.name, columns.join(", ")),
+                    [],
+                )?;
+            }
+        }
+        transaction.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::utils::io::sqlite::{Database, SqlValue, Table};
+
+    #[test]
+    fn test_database_round_trip() {
+        let path = std::env::temp_dir().join("heimdall_test_database_round_trip.db");
+        let path = path.to_str().unwrap();
+
+        let mut database = Database::new();
+        database.add_table(Table {
+            name: String::from("slots"),
+            columns: vec![
+                (String::from("slot"), String::from("TEXT")),
+                (String::from("block"), String::from("INTEGER")),
+                (String::from("value"), String::from("TEXT")),
+            ],
+            rows: (0..5000)
+                .map(|i| {
+                    vec![
+                        SqlValue::Text(format!("{:064x}", i * 7919 % 5000)),
+                        SqlValue::Integer(i),
+                        // rows larger than a page spill into overflow pages
+                        if i % 1000 == 0 {
+                            SqlValue::Text("ff".repeat(8192))
+                        } else {
+                            SqlValue::Null
+                        },
+                    ]
+                })
+                .collect(),
+            indexes: vec![(String::from("slots_slot"), vec![String::from("slot")])],
+        });
+        database.write(path).unwrap();
+
+        // writing again replaces the file rather than failing on the existing tables
+        database.write(path).unwrap();
+
+        let connection = Connection::open(path).unwrap();
+        let count: i64 =
+            connection.query_row("SELECT COUNT(*) FROM slots", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 5000);
+
+        let (block, value): (i64, String) = connection
+            .query_row(
+                "SELECT block, value FROM slots WHERE slot = ?",
+                [format!("{:064x}", 0)],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(block, 0);
+        assert_eq!(value, "ff".repeat(8192));
+
+        let plan: String = connection
+            .query_row("EXPLAIN QUERY PLAN SELECT * FROM slots WHERE slot = '00'", [], |row| {
+                row.get(3)
+            })
+            .unwrap();
+        assert!(plan.contains("slots_slot"));
+
+        // indexes on unknown columns are rejected
+        database.tables[0].indexes.push((String::from("bad"), vec![String::from("missing")]));
+        assert!(database.write(path).is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Debug,
+    io::Write,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use serde_json::{json, Map, Value};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    subscriber::Interest,
+    Event, Level, Metadata, Subscriber,
+};
+
+use crate::utils::{
+    determinism::deterministic,
+    io::{
+        logging::{log_timestamp, Logger},
+        theme::{Paint, Role},
+    },
+};
+
+thread_local! {
+    // the spans entered on this thread, innermost last
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The format in which spans and events are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines, in the same style as the [`Logger`]'s messages.
+    Text,
+
+    /// One JSON object per line, for log aggregators and scripts.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    /// Parses a log format from its name, either `text` or `json`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format '{s}', expected 'text' or 'json'")),
+        }
+    }
+}
+
+struct SpanData {
+    name: &'static str,
+    level: Level,
+    target: &'static str,
+    fields: Map<String, Value>,
+    parent: Option<u64>,
+    references: usize,
+    created: Instant,
+    entered: Option<Instant>,
+    busy: Duration,
+}
+
+/// A tracing subscriber which writes heimdall's spans and events to stderr, filtered by the same
+/// `RUST_LOG` verbosity as the [`Logger`]. When a span closes, the time spent inside it is written
+/// too, so slow analysis passes and RPC requests stand out.
+pub struct HeimdallSubscriber {
+    format: LogFormat,
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, SpanData>>,
+}
+
+impl HeimdallSubscriber {
+    pub fn new(format: LogFormat) -> Self {
+        HeimdallSubscriber { format, next_id: AtomicU64::new(1), spans: Mutex::new(HashMap::new()) }
+    }
+
+    /// formats a span or event, including the spans it's nested in, as a single line
+    fn format(
+        &self,
+        level: &Level,
+        target: &str,
+        fields: Map<String, Value>,
+        parent: Option<u64>,
+    ) -> String {
+        let spans = self.spans.lock().expect("failed to lock spans");
+
+        // walk the span tree from the innermost span outwards
+        let mut scope = Vec::new();
+        let mut next = parent;
+        while let Some(span) = next.and_then(|id| spans.get(&id)) {
+            scope.push(span);
+            next = span.parent;
+        }
+        scope.reverse();
+
+        match self.format {
+            LogFormat::Text => {
+                let path = scope
+                    .iter()
+                    .map(|span| match span.fields.is_empty() {
+                        true => span.name.to_string(),
+                        false => format!("{}{{{}}}", span.name, format_fields(&span.fields)),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(":");
+                let mut fields = fields;
+                let message = match fields.remove("message") {
+                    Some(Value::String(message)) => message,
+                    Some(message) => message.to_string(),
+                    None => String::new(),
+                };
+                let level = match *level {
+                    Level::ERROR => "error".paint(Role::Error),
+                    Level::WARN => "warn".paint(Role::Warn),
+                    Level::INFO => "info".paint(Role::Info),
+                    Level::DEBUG => "debug".paint(Role::Debug),
+                    Level::TRACE => "trace".paint(Role::Trace),
+                };
+
+                [
+                    format!("{}{}:", log_timestamp(), level),
+                    match path.is_empty() {
+                        true => String::new(),
+                        false => format!("{}:", path.paint(Role::Muted)),
+                    },
+                    message,
+                    format_fields(&fields),
+                ]
+                .iter()
+                .filter(|part| !part.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ")
+            }
+            LogFormat::Json => {
+                let mut line = json!({
+                    "timestamp": Local::now().to_rfc3339(),
+                    "level": level.as_str(),
+                    "target": target,
+                    "spans": scope
+                        .iter()
+                        .map(|span| {
+                            let mut fields = span.fields.clone();
+                            fields.insert(String::from("name"), Value::from(span.name));
+                            fields
+                        })
+                        .collect::<Vec<_>>(),
+                    "fields": fields,
+                });
+
+                // timestamps are omitted so logs are identical between runs
+                if deterministic() {
+                    if let Some(line) = line.as_object_mut() {
+                        line.remove("timestamp");
+                    }
+                }
+                line.to_string()
+            }
+        }
+    }
+
+    fn write(&self, line: &str) {
+        let _ = writeln!(std::io::stderr().lock(), "{line}");
+    }
+}
+
+impl Subscriber for HeimdallSubscriber {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // the verbosity is set by each module after the subscriber is installed, so callsites
+        // are filtered each time they're hit
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // dependencies' spans and events, such as hyper's, are too noisy to be useful
+        metadata.target().starts_with("heimdall") &&
+            max_level().map(|max| *metadata.level() <= max).unwrap_or(false)
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let mut visitor = FieldVisitor::default();
+        attributes.record(&mut visitor);
+
+        let parent = match attributes.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if attributes.is_contextual() => current_span(),
+            None => None,
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.spans.lock().expect("failed to lock spans").insert(
+            id,
+            SpanData {
+                name: attributes.metadata().name(),
+                level: *attributes.metadata().level(),
+                target: attributes.metadata().target(),
+                fields: visitor.fields,
+                parent,
+                references: 1,
+                created: Instant::now(),
+                entered: None,
+                busy: Duration::ZERO,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(span) =
+            self.spans.lock().expect("failed to lock spans").get_mut(&span.into_u64())
+        {
+            span.fields.extend(visitor.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let parent = match event.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if event.is_contextual() => current_span(),
+            None => None,
+        };
+        self.write(&self.format(
+            event.metadata().level(),
+            event.metadata().target(),
+            visitor.fields,
+            parent,
+        ));
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+        if let Some(span) =
+            self.spans.lock().expect("failed to lock spans").get_mut(&span.into_u64())
+        {
+            span.entered = Some(Instant::now());
+        }
+    }
+
+    fn exit(&self, span: &Id) {
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(index) = entered.iter().rposition(|id| *id == span.into_u64()) {
+                entered.remove(index);
+            }
+        });
+        if let Some(span) =
+            self.spans.lock().expect("failed to lock spans").get_mut(&span.into_u64())
+        {
+            if let Some(entered) = span.entered.take() {
+                span.busy += entered.elapsed();
+            }
+        }
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) =
+            self.spans.lock().expect("failed to lock spans").get_mut(&span.into_u64())
+        {
+            span.references += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let closed = {
+            let mut spans = self.spans.lock().expect("failed to lock spans");
+            match spans.get_mut(&span.into_u64()) {
+                Some(data) if data.references > 1 => {
+                    data.references -= 1;
+                    return false
+                }
+                Some(data) => {
+                    let total = data.created.elapsed();
+                    let closed = (data.level, data.target, total.saturating_sub(data.busy));
+                    (closed, data.busy)
+                }
+                None => return false,
+            }
+        };
+
+        // write the time spent in the span before it's removed, so it's included in the scope
+        let ((level, target, idle), busy) = closed;
+        let mut fields = Map::new();
+        fields.insert(String::from("message"), Value::from("close"));
+        fields.insert(String::from("time.busy"), Value::from(format!("{busy:.2?}")));
+        fields.insert(String::from("time.idle"), Value::from(format!("{idle:.2?}")));
+        self.write(&self.format(&level, target, fields, Some(span.into_u64())));
+
+        self.spans.lock().expect("failed to lock spans").remove(&span.into_u64());
+        true
+    }
+}
+
+/// Install a [`HeimdallSubscriber`] as the global default, writing spans and events in the given
+/// format. Does nothing if a subscriber is already installed.
+pub fn init_tracing(format: LogFormat) {
+    let _ = tracing::subscriber::set_global_default(HeimdallSubscriber::new(format));
+}
+
+/// the most verbose level to write, from the `RUST_LOG` verbosity, or `None` when silent
+fn max_level() -> Option<Level> {
+    match Logger::default().level {
+        -1 => None,
+        0 => Some(Level::ERROR),
+        1 => Some(Level::WARN),
+        2 => Some(Level::INFO),
+        3 => Some(Level::DEBUG),
+        _ => Some(Level::TRACE),
+    }
+}
+
+fn current_span() -> Option<u64> {
+    ENTERED.with(|entered| entered.borrow().last().copied())
+}
+
+fn format_fields(fields: &Map<String, Value>) -> String {
+    fields
+        .iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => format!("{key}={value}"),
+            value => format!("{key}={value}"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// collects a span or event's fields as JSON values
+#[derive(Default)]
+struct FieldVisitor {
+    fields: Map<String, Value>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields.insert(field.name().to_string(), Value::from(format!("{value:?}")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Map, Value};
+    use tracing::Level;
+
+    use crate::utils::io::subscriber::{HeimdallSubscriber, LogFormat, SpanData};
+
+    fn subscriber(format: LogFormat) -> HeimdallSubscriber {
+        let subscriber = HeimdallSubscriber::new(format);
+        let mut fields = Map::new();
+        fields.insert(String::from("selector"), Value::from("a9059cbb"));
+        subscriber.spans.lock().unwrap().insert(
+            1,
+            SpanData {
+                name: "analyze",
+                level: Level::DEBUG,
+                target: "heimdall_core::decompile",
+                fields,
+                parent: None,
+                references: 1,
+                created: std::time::Instant::now(),
+                entered: None,
+                busy: std::time::Duration::ZERO,
+            },
+        );
+        subscriber
+    }
+
+    fn event_fields() -> Map<String, Value> {
+        let mut fields = Map::new();
+        fields.insert(String::from("message"), Value::from("found jump table"));
+        fields.insert(String::from("entries"), Value::from(4));
+        fields
+    }
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("json".parse::<LogFormat>(), Ok(LogFormat::Json));
+        assert_eq!("TEXT".parse::<LogFormat>(), Ok(LogFormat::Text));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_format_json() {
+        let line = subscriber(LogFormat::Json).format(
+            &Level::DEBUG,
+            "heimdall_core::decompile",
+            event_fields(),
+            Some(1),
+        );
+        let line: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(line["level"], "DEBUG");
+        assert_eq!(line["spans"], json!([{ "name": "analyze", "selector": "a9059cbb" }]));
+        assert_eq!(line["fields"], json!({ "message": "found jump table", "entries": 4 }));
+    }
+
+    #[test]
+    fn test_format_text() {
+        colored::control::set_override(false);
+        let line = subscriber(LogFormat::Text).format(
+            &Level::DEBUG,
+            "heimdall_core::decompile",
+            event_fields(),
+            Some(1),
+        );
+        assert!(line.ends_with("debug: analyze{selector=a9059cbb}: found jump table entries=4"));
+    }
+}
@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+/// A delimited text format for tabular output, which spreadsheets and tools like `awk` and `cut`
+/// can read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Tab-separated values. Tabs and newlines within fields are replaced by spaces, so every row
+    /// is a single line which can be split on tabs.
+    Tsv,
+
+    /// Comma-separated values, as described by RFC 4180. Fields containing commas, quotes, or
+    /// newlines are quoted.
+    Csv,
+}
+
+impl FromStr for TableFormat {
+    type Err = String;
+
+    /// Parses a table format from its name, either `tsv` or `csv`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "tsv" => Ok(TableFormat::Tsv),
+            "csv" => Ok(TableFormat::Csv),
+            _ => Err(format!("unknown table format '{s}', expected 'tsv' or 'csv'")),
+        }
+    }
+}
+
+impl TableFormat {
+    /// The file extension of the format, such as `tsv`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TableFormat::Tsv => "tsv",
+            TableFormat::Csv => "csv",
+        }
+    }
+
+    /// Format the given fields as a row, without a trailing newline.
+    ///
+    /// ```
+    /// use heimdall_common::utils::io::table::TableFormat;
+    ///
+    /// assert_eq!(TableFormat::Tsv.row(&["0xa9059cbb", "transfer(address,uint256)"]), "0xa9059cbb\ttransfer(address,uint256)");
+    /// assert_eq!(TableFormat::Csv.row(&["0xa9059cbb", "transfer(address,uint256)"]), "0xa9059cbb,\"transfer(address,uint256)\"");
+    /// ```
+    pub fn row<S: AsRef<str>>(&self, fields: &[S]) -> String {
+        match self {
+            TableFormat::Tsv => fields
+                .iter()
+                .map(|field| field.as_ref().replace(['\t', '\r', '\n'], " "))
+                .collect::<Vec<_>>()
+                .join("\t"),
+            TableFormat::Csv => fields
+                .iter()
+                .map(|field| {
+                    let field = field.as_ref();
+                    match field.contains([',', '"', '\r', '\n']) {
+                        true => format!("\"{}\"", field.replace('"', "\"\"")),
+                        false => field.to_string(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_format_from_str() {
+        assert_eq!("tsv".parse::<TableFormat>(), Ok(TableFormat::Tsv));
+        assert_eq!(" CSV ".parse::<TableFormat>(), Ok(TableFormat::Csv));
+        assert!("xlsx".parse::<TableFormat>().is_err());
+    }
+
+    #[test]
+    fn test_table_row_escaping() {
+        assert_eq!(TableFormat::Tsv.row(&["a\tb", "c\nd"]), "a b\tc d");
+        assert_eq!(TableFormat::Csv.row(&["say \"hi\"", "plain", ""]), "\"say \"\"hi\"\"\",plain,");
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use colored::Colorize;
+
+// the theme heimdall's output is rendered with, as a `Theme` discriminant
+static THEME: AtomicU8 = AtomicU8::new(Theme::Default as u8);
+
+/// How heimdall's logs, traces, and reports are styled in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// Bright colors, for dark terminals.
+    Default = 0,
+
+    /// Darker colors, without white text, for light terminals.
+    Light = 1,
+
+    /// Bold and dimmed text, without any colors.
+    Mono = 2,
+
+    /// ASCII text without any escape codes, for terminals and logs which mangle ANSI codes or
+    /// box-drawing characters.
+    Plain = 3,
+}
+
+impl Theme {
+    /// The theme used when none is selected, which is [`Theme::Mono`] if the `NO_COLOR`
+    /// environment variable is set, and [`Theme::Default`] otherwise.
+    pub fn from_env() -> Theme {
+        match std::env::var("NO_COLOR") {
+            Ok(value) if !value.is_empty() => Theme::Mono,
+            _ => Theme::Default,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    /// Parses a theme from its name, either `default`, `light`, `mono`, or `plain`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "default" => Ok(Theme::Default),
+            "light" => Ok(Theme::Light),
+            "mono" => Ok(Theme::Mono),
+            "plain" => Ok(Theme::Plain),
+            _ => {
+                Err(format!("unknown theme '{s}', expected 'default', 'light', 'mono', or 'plain'"))
+            }
+        }
+    }
+}
+
+/// Set the theme heimdall's output is rendered with.
+pub fn set_theme(theme: Theme) {
+    THEME.store(theme as u8, Ordering::Relaxed);
+}
+
+/// The theme heimdall's output is rendered with.
+pub fn theme() -> Theme {
+    match THEME.load(Ordering::Relaxed) {
+        1 => Theme::Light,
+        2 => Theme::Mono,
+        3 => Theme::Plain,
+        _ => Theme::Default,
+    }
+}
+
+/// What a piece of styled text is, which decides how each [`Theme`] renders it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Info,
+    Success,
+    Warn,
+    Error,
+    Fatal,
+    Debug,
+    Trace,
+
+    /// Secondary text, such as timestamps and paths.
+    Muted,
+
+    /// Structural text, such as the branches of a trace.
+    Emphasis,
+
+    /// Values, such as the return value of a call.
+    Value,
+
+    /// The origin and name of a call.
+    Call,
+
+    /// Events and their topics.
+    Log,
+
+    /// The types of decoded values.
+    Type,
+
+    /// Unstyled text, which is only made ASCII by [`Theme::Plain`].
+    Text,
+}
+
+/// Styles text with a [`Role`], according to the current [`Theme`].
+pub trait Paint {
+    /// Render the text in the given role, such as `"warn".paint(Role::Warn)`.
+    fn paint(&self, role: Role) -> String;
+}
+
+impl<T: AsRef<str> + ?Sized> Paint for T {
+    fn paint(&self, role: Role) -> String {
+        paint_with(self.as_ref(), role, theme())
+    }
+}
+
+/// Render text in the given role with the given theme, rather than the current one.
+///
+/// ```
+/// use heimdall_common::utils::io::theme::{paint_with, Role, Theme};
+///
+/// assert_eq!(paint_with("warn", Role::Warn, Theme::Plain), "warn");
+/// ```
+pub fn paint_with(text: &str, role: Role, theme: Theme) -> String {
+    match theme {
+        Theme::Default => match role {
+            Role::Info => text.bright_cyan().bold(),
+            Role::Success => text.bright_green().bold(),
+            Role::Warn => text.bright_yellow().bold(),
+            Role::Error => text.bright_red().bold(),
+            Role::Fatal => text.bright_white().on_bright_red().bold(),
+            Role::Debug => text.bright_magenta().bold(),
+            Role::Trace => text.bright_blue().bold(),
+            Role::Muted => text.dimmed(),
+            Role::Emphasis => text.bright_white().bold(),
+            Role::Value => text.green(),
+            Role::Call => text.bright_cyan(),
+            Role::Log => text.purple(),
+            Role::Type => text.blue(),
+            Role::Text => text.normal(),
+        }
+        .to_string(),
+        Theme::Light => match role {
+            Role::Info => text.cyan().bold(),
+            Role::Success => text.green().bold(),
+            Role::Warn => text.yellow().bold(),
+            Role::Error => text.red().bold(),
+            Role::Fatal => text.white().on_red().bold(),
+            Role::Debug => text.magenta().bold(),
+            Role::Trace => text.blue().bold(),
+            Role::Muted => text.dimmed(),
+            Role::Emphasis => text.bold(),
+            Role::Value => text.green(),
+            Role::Call => text.cyan(),
+            Role::Log => text.purple(),
+            Role::Type => text.blue(),
+            Role::Text => text.normal(),
+        }
+        .to_string(),
+        Theme::Mono => match role {
+            Role::Muted => text.dimmed(),
+            Role::Value | Role::Call | Role::Log | Role::Type | Role::Text => text.normal(),
+            _ => text.bold(),
+        }
+        .to_string(),
+        Theme::Plain => ascii(text),
+    }
+}
+
+/// Replace the box-drawing characters and arrows heimdall draws trees with by ASCII, as the
+/// [`Theme::Plain`] theme does.
+///
+/// ```
+/// use heimdall_common::utils::io::theme::ascii;
+///
+/// assert_eq!(ascii(" ├─ call"), " |- call");
+/// assert_eq!(ascii("   │  └─ ()"), "   |  `- ()");
+/// ```
+pub fn ascii(text: &str) -> String {
+    text.replace('├', "|")
+        .replace('└', "`")
+        .replace('│', "|")
+        .replace('─', "-")
+        .replace('←', "<-")
+        .replace('→', "->")
+}
+
+/// The characters spinners are drawn with in the current [`Theme`].
+pub fn tick_chars() -> &'static str {
+    match theme() {
+        Theme::Plain => "|/-\\ ",
+        _ => "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_from_str() {
+        assert_eq!("default".parse::<Theme>(), Ok(Theme::Default));
+        assert_eq!(" Light ".parse::<Theme>(), Ok(Theme::Light));
+        assert_eq!("mono".parse::<Theme>(), Ok(Theme::Mono));
+        assert_eq!("PLAIN".parse::<Theme>(), Ok(Theme::Plain));
+        assert!("solarized".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn test_paint_plain() {
+        assert_eq!(paint_with(" ├─ transfer", Role::Emphasis, Theme::Plain), " |- transfer");
+        assert_eq!(paint_with("fatal", Role::Fatal, Theme::Plain), "fatal");
+    }
+}
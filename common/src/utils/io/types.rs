@@ -1,61 +1,247 @@
-use colored::Colorize;
 use ethers::abi::Token;
+use serde::{Deserialize, Serialize};
 
-/// A helper function used by the decode module to pretty format decoded tokens.
-pub fn display(inputs: Vec<Token>, prefix: &str) -> Vec<String> {
+use crate::{
+    ether::evm::core::types::ParameterNames,
+    utils::io::theme::{Paint, Role},
+};
+
+/// A decoded token as a tree, which can be serialized, or rendered as text with [`render`].
+/// Arrays and tuples hold their elements as children.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenNode {
+    /// The name of the parameter this token was decoded from, if it's known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The kind of the token, such as `address`, `uint`, `bytes32`, `array`, or `tuple`.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// The token's value, or `None` for arrays and tuples.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TokenNode>,
+}
+
+impl From<&Token> for TokenNode {
+    fn from(token: &Token) -> Self {
+        let (type_, value, children) = match token {
+            Token::Address(_) => (String::from("address"), Some(format!("0x{token}")), Vec::new()),
+            Token::Int(val) => (String::from("int"), Some(val.to_string()), Vec::new()),
+            Token::Uint(val) => (String::from("uint"), Some(val.to_string()), Vec::new()),
+            Token::String(val) => (String::from("string"), Some(val.clone()), Vec::new()),
+            Token::Bool(val) => (String::from("bool"), Some(val.to_string()), Vec::new()),
+            Token::FixedBytes(bytes) => {
+                (format!("bytes{}", bytes.len()), Some(format!("0x{token}")), Vec::new())
+            }
+            Token::Bytes(_) => (String::from("bytes"), Some(format!("0x{token}")), Vec::new()),
+            Token::FixedArray(val) | Token::Array(val) => {
+                (String::from("array"), None, token_tree(val))
+            }
+            Token::Tuple(val) => (String::from("tuple"), None, token_tree(val)),
+        };
+
+        TokenNode { name: None, type_, value, children }
+    }
+}
+
+impl TokenNode {
+    /// Name this node after the given parameter, along with its components if it's a tuple or an
+    /// array of tuples. Components are named by their path from the parameter, such as
+    /// `params.amountIn` or `orders[0].maker`.
+    pub fn label(&mut self, parameter: &ParameterNames) {
+        self.label_at(&parameter.name, parameter)
+    }
+
+    fn label_at(&mut self, path: &str, parameter: &ParameterNames) {
+        self.name = Some(path.to_string()).filter(|path| !path.is_empty());
+        if path.is_empty() || parameter.components.is_empty() {
+            return
+        }
+
+        match self.type_.as_str() {
+            "tuple" => {
+                for (child, component) in self.children.iter_mut().zip(&parameter.components) {
+                    match component.name.is_empty() {
+                        true => child.name = None,
+                        false => child.label_at(&format!("{path}.{}", component.name), component),
+                    }
+                }
+            }
+            "array" => {
+                for (i, child) in self.children.iter_mut().enumerate() {
+                    child.label_at(&format!("{path}[{i}]"), parameter);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Build the tree of each of the given decoded tokens.
+///
+/// ```
+/// use ethers::abi::Token;
+/// use heimdall_common::utils::io::types::token_tree;
+///
+/// let tree = token_tree(&[Token::Array(vec![Token::Bool(true)])]);
+/// assert_eq!(tree[0].type_, "array");
+/// assert_eq!(tree[0].children[0].value, Some(String::from("true")));
+/// ```
+pub fn token_tree(inputs: &[Token]) -> Vec<TokenNode> {
+    inputs.iter().map(TokenNode::from).collect()
+}
+
+/// Render token trees as pretty formatted lines of text, each starting with the given prefix.
+/// Nested arrays and tuples are indented further, and named tokens are labeled with their name.
+pub fn render(nodes: &[TokenNode], prefix: &str) -> Vec<String> {
     let mut output = Vec::new();
-    let prefix = prefix.to_string();
-
-    for input in inputs {
-        match input {
-            Token::Address(_) => output.push(format!("{prefix}{} 0x{input}", "address".blue())),
-            Token::Int(val) => output.push(format!("{prefix}{} {}", "int    ".blue(), val)),
-            Token::Uint(val) => output.push(format!("{prefix}{} {}", "uint   ".blue(), val)),
-            Token::String(val) => output.push(format!("{prefix}{} {val}", "string ".blue())),
-            Token::Bool(val) => {
-                if val {
-                    output.push(format!("{prefix}{} true", "bool   ".blue()));
+
+    for node in nodes {
+        let value = node.value.clone().unwrap_or_default();
+        let label = node.name.as_ref().map(|name| format!("{name}: ")).unwrap_or_default();
+        match node.type_.as_str() {
+            "array" | "tuple" => {
+                let (open, close) = if node.type_ == "array" { ("[", "]") } else { ("(", ")") };
+                if node.children.is_empty() {
+                    output.push(format!("{prefix}{label}{open}{close}"));
                 } else {
-                    output.push(format!("{prefix}{} false", "bool   ".blue()));
+                    output.push(format!("{prefix}{label}{open}"));
+                    output.extend(render(&node.children, &format!("{prefix}   ")));
+                    output.push(format!("{prefix}{close}"));
                 }
             }
-            Token::FixedBytes(_) | Token::Bytes(_) => {
-                let bytes = input
-                    .to_string()
+            bytes if bytes.starts_with("bytes") => {
+                let chunks = value
+                    .trim_start_matches("0x")
                     .chars()
                     .collect::<Vec<char>>()
                     .chunks(64)
                     .map(|c| c.iter().collect::<String>())
                     .collect::<Vec<String>>();
 
-                for (i, byte) in bytes.iter().enumerate() {
+                for (i, chunk) in chunks.iter().enumerate() {
                     if i == 0 {
-                        output.push(format!("{prefix}{} 0x{}", "bytes  ".blue(), byte));
+                        output.push(format!(
+                            "{prefix}{label}{} 0x{}",
+                            "bytes  ".paint(Role::Type),
+                            chunk
+                        ));
                     } else {
-                        output.push(format!("{prefix}{}   {}", "       ".blue(), byte));
+                        output.push(format!(
+                            "{prefix}{}{}   {}",
+                            " ".repeat(label.len()),
+                            "       ".paint(Role::Type),
+                            chunk
+                        ));
                     }
                 }
             }
-            Token::FixedArray(val) | Token::Array(val) => {
-                if val.is_empty() {
-                    output.push(format!("{prefix}[]"));
-                } else {
-                    output.push(format!("{prefix}["));
-                    output.extend(display(val.to_vec(), &format!("{prefix}   ")));
-                    output.push(format!("{prefix}]"));
-                }
-            }
-            Token::Tuple(val) => {
-                if val.is_empty() {
-                    output.push(format!("{prefix}()"));
-                } else {
-                    output.push(format!("{prefix}("));
-                    output.extend(display(val.to_vec(), &format!("{prefix}   ")));
-                    output.push(format!("{prefix})"));
-                }
-            }
-        };
+            type_ => output.push(format!(
+                "{prefix}{label}{} {value}",
+                format!("{type_:<7}").paint(Role::Type)
+            )),
+        }
     }
 
     output
 }
+
+/// A helper function used by the decode module to pretty format decoded tokens.
+pub fn display(inputs: Vec<Token>, prefix: &str) -> Vec<String> {
+    render(&token_tree(&inputs), prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use colored::control::set_override;
+    use ethers::{
+        abi::Token,
+        types::{H160, U256},
+    };
+    use serde_json::json;
+
+    use crate::{
+        ether::evm::core::types::parse_parameter_names,
+        utils::io::types::{display, render, token_tree},
+    };
+
+    #[test]
+    fn test_token_tree_serializes() {
+        let tree = token_tree(&[Token::Tuple(vec![
+            Token::Address(H160::repeat_byte(0xaa)),
+            Token::FixedBytes(vec![0x01, 0x02]),
+        ])]);
+
+        assert_eq!(
+            serde_json::to_value(tree).unwrap(),
+            json!([{
+                "type": "tuple",
+                "children": [
+                    { "type": "address", "value": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa" },
+                    { "type": "bytes2", "value": "0x0102" }
+                ]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_display_renders_tree() {
+        set_override(false);
+        let lines = display(
+            vec![
+                Token::Uint(U256::from(1000)),
+                Token::Array(vec![Token::Bool(true), Token::Tuple(vec![])]),
+                Token::Bytes(vec![0xff; 33]),
+            ],
+            "  ",
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                "  uint    1000",
+                "  [",
+                "     bool    true",
+                "     ()",
+                "  ]",
+                &format!("  bytes   0x{}", "ff".repeat(32)),
+                "            ff",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_label_tuple_components() {
+        set_override(false);
+        let order = |amount: u64| {
+            Token::Tuple(vec![Token::Address(H160::zero()), Token::Uint(U256::from(amount))])
+        };
+        let mut tree = token_tree(&[Token::Array(vec![order(1), order(2)]), Token::Bool(true)]);
+        let names = parse_parameter_names("fill((address, uint256 amount)[] orders, bool)");
+        for (node, parameter) in tree.iter_mut().zip(&names) {
+            node.label(parameter);
+        }
+
+        assert_eq!(tree[0].children[1].children[1].name, Some(String::from("orders[1].amount")));
+        assert_eq!(
+            render(&tree, ""),
+            vec![
+                "orders: [",
+                "   orders[0]: (",
+                "      address 0x0000000000000000000000000000000000000000",
+                "      orders[0].amount: uint    1",
+                "   )",
+                "   orders[1]: (",
+                "      address 0x0000000000000000000000000000000000000000",
+                "      orders[1].amount: uint    2",
+                "   )",
+                "]",
+                "bool    true",
+            ]
+        );
+    }
+}
@@ -1,8 +1,11 @@
+pub mod determinism;
+pub mod failure;
 pub mod http;
 pub mod integers;
 pub mod io;
 pub mod iter;
 pub mod strings;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod sync;
 pub mod testing;
 pub mod threading;
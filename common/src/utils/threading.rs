@@ -2,7 +2,8 @@ use crossbeam_channel::unbounded;
 use std::{sync::Arc, thread};
 
 /// A simple thread pool implementation that takes a vector of items, splits them into chunks, and
-/// processes each chunk in a separate thread. The results are collected and returned.
+/// processes each chunk in a separate thread. The results are collected and returned in the same
+/// order as the items, regardless of which thread finishes first.
 ///
 /// ```
 /// use heimdall_common::utils::threading::task_pool;
@@ -32,28 +33,30 @@ pub fn task_pool<
     // Split items into chunks for each thread to process
     let chunk_size = (items.len() + num_threads - 1) / num_threads;
     let chunks = items.chunks(chunk_size);
+    let chunk_count = chunks.len();
 
     // Share ownership of f across threads with Arc
     let shared_f = Arc::new(f);
 
-    for chunk in chunks {
+    for (index, chunk) in chunks.enumerate() {
         let chunk = chunk.to_owned();
         let tx = tx.clone();
         // Share ownership of shared_f with each thread with Arc
         let shared_f = Arc::clone(&shared_f);
         let handle = thread::spawn(move || {
             let chunk_results: Vec<R> = chunk.into_iter().map(|item| shared_f(item)).collect();
-            tx.send(chunk_results).unwrap();
+            tx.send((index, chunk_results)).unwrap();
         });
         handles.push(handle);
     }
 
-    // Wait for all threads to finish and collect the results
-    let mut results = Vec::new();
-    for _ in 0..num_threads {
-        let chunk_results = rx.recv().unwrap();
-        results.extend(chunk_results);
+    // Wait for all threads to finish and collect the results, in the order of their chunks
+    let mut chunk_results = Vec::with_capacity(chunk_count);
+    for _ in 0..chunk_count {
+        chunk_results.push(rx.recv().unwrap());
     }
+    chunk_results.sort_by_key(|(index, _)| *index);
+    let results = chunk_results.into_iter().flat_map(|(_, results)| results).collect();
 
     // Wait for all threads to finish
     for handle in handles {
@@ -97,6 +100,22 @@ mod tests {
         assert_eq!(results, expected_results);
     }
 
+    #[test]
+    fn test_task_pool_preserves_order() {
+        // Test case where later chunks finish first, and there are fewer chunks than threads
+        let items = vec![4, 3, 2, 1, 0];
+        let num_threads = 4;
+
+        // Define a function which sleeps longer for earlier items
+        let f = |x: u64| {
+            std::thread::sleep(std::time::Duration::from_millis(x * 10));
+            x
+        };
+
+        let results = task_pool(items, num_threads, f);
+        assert_eq!(results, vec![4, 3, 2, 1, 0]);
+    }
+
     #[test]
     fn test_task_pool_with_empty_items() {
         // Test case with empty items vector
@@ -0,0 +1,13 @@
+/// The errors returned when reading or updating the configuration, so hosts embedding heimdall,
+/// such as the FFI bindings, can handle a bad configuration rather than being exited.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("ReadError: {0}")]
+    ReadError(#[from] std::io::Error),
+    #[error("HomeDirError: couldn't resolve the bifrost directory. Is your $HOME variable set correctly?")]
+    HomeDirError,
+    #[error("ParseError: {0}")]
+    ParseError(String),
+    #[error("UnknownKeyError: unknown configuration key '{0}'")]
+    UnknownKeyError(String),
+}
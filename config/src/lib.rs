@@ -1,11 +1,19 @@
+pub mod error;
+
 use clap::{AppSettings, Parser};
-use heimdall_common::utils::io::{
-    file::{delete_path, read_file, write_file},
-    logging::*,
+use heimdall_common::{
+    ether::{chains::Chain, evm::ext::exec::ExecutionLimits},
+    utils::io::{
+        file::{delete_path, read_file, write_file},
+        logging::*,
+    },
 };
 use serde::{Deserialize, Serialize};
 #[allow(deprecated)]
 use std::env::home_dir;
+use std::{collections::BTreeMap, env, path::PathBuf};
+
+use crate::error::Error;
 
 pub static DEFAULT_CONFIG: &str = "rpc_url = \"\"
 local_rpc_url = \"http://localhost:8545\"
@@ -33,104 +41,201 @@ pub struct ConfigArgs {
 
 /// The [`Configuration`] struct represents the configuration of the CLI. All heimdall core modules
 /// will attempt to read from this configuration when possible.
-#[derive(Deserialize, Serialize, Debug)]
+///
+/// The configuration is read from `$HOME/.bifrost/config.toml`, which `heimdall config` edits,
+/// overridden by a user-level `$HOME/.bifrost/heimdall.toml`, then by the project-level
+/// `heimdall.toml` nearest the working directory. Flags given on the command line override all of
+/// them. Keys missing from every file take their default values.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
 pub struct Configuration {
+    // one or more RPC providers, separated by commas, which requests fail over between. each may
+    // be an http(s) or ws(s) URL, or the path to a local node's IPC socket
     pub rpc_url: String,
     pub local_rpc_url: String,
+
+    // api keys may also be comma-separated lists, which requests rotate between when rate limited
     pub etherscan_api_key: String,
     pub transpose_api_key: String,
     pub openai_api_key: String,
+
+    // the RPC providers used when `--chain` is given, keyed by the chain's name, alias, or id
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rpc_urls: BTreeMap<String, String>,
+
+    // the directory output is written to when `--output` isn't given, which may be a template
+    // such as `out/{chain}/{address}/{module}`
+    pub output: String,
+
+    // whether responses which can never change are cached, unless `--no-rpc-cache` is given
+    pub rpc_cache: bool,
+
+    // the most seconds a single RPC request or lookup to an external service may take, and how
+    // many times either is retried when it fails, unless the equivalent flags are given
+    pub rpc_timeout: u64,
+    pub rpc_retries: u32,
+    pub lookup_timeout: u64,
+    pub lookup_retries: u32,
+
+    // the symbolic execution limits used when the equivalent flags aren't given
+    #[serde(skip_serializing_if = "is_unset")]
+    pub limits: ExecutionLimits,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Configuration {
+            rpc_url: String::new(),
+            local_rpc_url: String::from("http://localhost:8545"),
+            etherscan_api_key: String::new(),
+            transpose_api_key: String::new(),
+            openai_api_key: String::new(),
+            rpc_urls: BTreeMap::new(),
+            output: String::from("output"),
+            rpc_cache: true,
+            rpc_timeout: 30,
+            rpc_retries: 5,
+            lookup_timeout: 15,
+            lookup_retries: 3,
+            limits: ExecutionLimits::default(),
+        }
+    }
+}
+
+impl Configuration {
+    /// Get the configured RPC provider for the given chain, looked up by its name, any of its
+    /// aliases, or its id.
+    pub fn chain_rpc_url(&self, chain: &Chain) -> Option<String> {
+        let id = chain.id.to_string();
+        std::iter::once(chain.name)
+            .chain(chain.aliases.iter().copied())
+            .chain(std::iter::once(id.as_str()))
+            .find_map(|key| self.rpc_urls.get(key))
+            .cloned()
+    }
+}
+
+fn is_unset(limits: &ExecutionLimits) -> bool {
+    limits == &ExecutionLimits::default()
 }
 
 #[allow(deprecated)]
 /// Writes the given configuration to the disc at `$HOME/.bifrost/config.toml`.
-pub fn write_config(contents: &str) {
+pub fn write_config(contents: &str) -> Result<(), Error> {
     match home_dir() {
         Some(mut home) => {
             home.push(".bifrost");
             home.push("config.toml");
 
             let _ = write_file(home.into_os_string().to_str().unwrap(), contents);
+            Ok(())
         }
-        None => {
-            let (logger, _) = Logger::new("");
-            logger.error(
-                "couldn't resolve the bifrost directory. Is your $HOME variable set correctly?",
-            );
-            std::process::exit(1)
-        }
+        None => Err(Error::HomeDirError),
     }
 }
 
 #[allow(deprecated)]
 /// Deletes the configuration file at `$HOME/.bifrost/config.toml`.
-pub fn delete_config() {
+pub fn delete_config() -> Result<(), Error> {
     match home_dir() {
         Some(mut home) => {
             home.push(".bifrost");
             home.push("config.toml");
 
             let _ = delete_path(home.into_os_string().to_str().unwrap());
+            Ok(())
         }
-        None => {
-            let (logger, _) = Logger::new("");
-            logger.error(
-                "couldn't resolve the bifrost directory. Is your $HOME variable set correctly?",
-            );
-            std::process::exit(1)
-        }
+        None => Err(Error::HomeDirError),
     }
 }
 
 #[allow(deprecated)]
 /// Reads the configuration file at `$HOME/.bifrost/config.toml`.
-pub fn read_config() -> String {
-    match home_dir() {
-        Some(mut home) => {
-            home.push(".bifrost");
-            home.push("config.toml");
+pub fn read_config() -> Result<String, Error> {
+    let mut home = home_dir().ok_or(Error::HomeDirError)?;
+    home.push(".bifrost");
+    home.push("config.toml");
 
-            if home.as_path().exists() {
-                // the file exists, read it
-                return read_file(home.into_os_string().to_str().unwrap())
-            } else {
-                // the file does not exist, create it
-                write_config(DEFAULT_CONFIG);
-                return read_file(home.into_os_string().to_str().unwrap())
-            }
-        }
-        None => {
-            let (logger, _) = Logger::new("");
-            logger.error(
-                "couldn't resolve the bifrost directory. Is your $HOME variable set correctly?",
-            );
-            std::process::exit(1)
-        }
+    if !home.as_path().exists() {
+        // the file does not exist, create it
+        write_config(DEFAULT_CONFIG)?;
     }
+    read_config_file(home.to_str().unwrap())
+}
+
+// reads a configuration file
+fn read_config_file(path: &str) -> Result<String, Error> {
+    Ok(read_file(path)?)
 }
 
 /// Returns the [`Configuration`] struct after parsing the configuration file at
-/// `$HOME/.bifrost/config.toml`.
-pub fn get_config() -> Configuration {
-    let contents = read_config();
+/// `$HOME/.bifrost/config.toml`, overridden by any user-level and project-level `heimdall.toml`.
+pub fn get_config() -> Result<Configuration, Error> {
+    let mut merged = read_user_config()?;
+    for path in [user_heimdall_toml(), project_heimdall_toml()].into_iter().flatten() {
+        let overrides = read_config_file(path.to_str().unwrap())?
+            .parse::<toml::Table>()
+            .map_err(|e| Error::ParseError(format!("failed to parse '{}': {e}", path.display())))?;
+        merge_tables(&mut merged, overrides);
+    }
+
+    toml::Value::Table(merged)
+        .try_into()
+        .map_err(|e| Error::ParseError(format!("invalid configuration: {e}")))
+}
+
+/// parses `$HOME/.bifrost/config.toml`, regenerating it if it can't be parsed
+fn read_user_config() -> Result<toml::Table, Error> {
+    let contents = read_config()?;
 
     // toml parse from contents into Configuration
-    let config: Configuration = match toml::from_str(&contents) {
-        Ok(config) => config,
-        Err(e) => {
+    match contents.parse::<toml::Table>() {
+        Ok(config) if toml::Value::Table(config.clone()).try_into::<Configuration>().is_ok() => {
+            Ok(config)
+        }
+        Ok(_) | Err(_) => {
             let (logger, _) = Logger::new("");
-            logger.error(&format!("failed to parse config file: {e}"));
+            logger.error("failed to parse config file.");
             logger.info("regenerating config file...");
-            delete_config();
-            return get_config()
+            delete_config()?;
+            read_user_config()
         }
-    };
-    config
+    }
+}
+
+#[allow(deprecated)]
+/// the user-level `$HOME/.bifrost/heimdall.toml`, if it exists
+fn user_heimdall_toml() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".bifrost").join("heimdall.toml")).filter(|path| path.exists())
+}
+
+/// the `heimdall.toml` in the working directory or its nearest ancestor, if any
+fn project_heimdall_toml() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors().map(|dir| dir.join("heimdall.toml")).find(|path| path.exists())
+}
+
+/// merges `overrides` into `base`, replacing values and merging nested tables key by key
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_tables(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
 }
 
 /// update a single key/value pair in the configuration file
-pub fn update_config(key: &str, value: &str) {
-    let mut contents = get_config();
+pub fn update_config(key: &str, value: &str) -> Result<(), Error> {
+    // only the user's config file is updated, so settings from `heimdall.toml` aren't copied into
+    // it
+    let mut contents: Configuration = toml::Value::Table(read_user_config()?)
+        .try_into()
+        .expect("config file was validated when it was read");
 
     // update the key in the struct and ensure it's the correct type
     match key {
@@ -149,25 +254,42 @@ pub fn update_config(key: &str, value: &str) {
         "openai_api_key" => {
             contents.openai_api_key = value.to_string();
         }
-        _ => {
-            let (logger, _) = Logger::new("");
-            logger.error(&format!("unknown configuration key \'{key}\' ."));
-            std::process::exit(1)
+        "output" => {
+            contents.output = value.to_string();
         }
+        "rpc_cache" => match value.parse() {
+            Ok(rpc_cache) => contents.rpc_cache = rpc_cache,
+            Err(_) => return Err(Error::ParseError(format!("'{key}' must be 'true' or 'false'"))),
+        },
+        "rpc_timeout" | "lookup_timeout" => match value.parse() {
+            Ok(timeout) if key == "rpc_timeout" => contents.rpc_timeout = timeout,
+            Ok(timeout) => contents.lookup_timeout = timeout,
+            Err(_) => {
+                return Err(Error::ParseError(format!("'{key}' must be a whole number of seconds")))
+            }
+        },
+        "rpc_retries" | "lookup_retries" => match value.parse() {
+            Ok(retries) if key == "rpc_retries" => contents.rpc_retries = retries,
+            Ok(retries) => contents.lookup_retries = retries,
+            Err(_) => {
+                return Err(Error::ParseError(format!("'{key}' must be a non-negative number")))
+            }
+        },
+        _ => return Err(Error::UnknownKeyError(key.to_string())),
     }
 
     // write the updated config to disk
     let serialized_config = toml::to_string(&contents).unwrap();
-    write_config(&serialized_config);
+    write_config(&serialized_config)
 }
 
 /// The `config` command is used to display and edit the current configuration.
-pub fn config(args: ConfigArgs) {
+pub fn config(args: ConfigArgs) -> Result<(), Error> {
     let (logger, _) = Logger::new("");
     if !args.key.is_empty() {
         if !args.value.is_empty() {
             // read the config file and update the key/value pair
-            update_config(&args.key, &args.value);
+            update_config(&args.key, &args.value)?;
             logger.success(&format!(
                 "updated configuration! Set \'{}\' = \'{}\' .",
                 &args.key, &args.value
@@ -179,7 +301,60 @@ pub fn config(args: ConfigArgs) {
         }
     } else {
         // no key is set, print the config file
-        println!("{:#?}", get_config());
+        println!("{:#?}", get_config()?);
         logger.info("use `heimdall config <KEY> <VALUE>` to set a key/value pair.");
     }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::chains::Chain;
+
+    use crate::{error::Error, merge_tables, read_config_file, Configuration};
+
+    #[test]
+    fn test_merge_tables() {
+        let mut base =
+            "rpc_url = \"https://user.rpc\"\noutput = \"output\"\n[limits]\nmax_depth = 10"
+                .parse::<toml::Table>()
+                .unwrap();
+        let project = "output = \"build/heimdall\"\n[limits]\nmax_branches = 100"
+            .parse::<toml::Table>()
+            .unwrap();
+        merge_tables(&mut base, project);
+
+        let config: Configuration = toml::Value::Table(base).try_into().unwrap();
+        assert_eq!(config.rpc_url, "https://user.rpc");
+        assert_eq!(config.output, "build/heimdall");
+        assert_eq!(config.limits.max_depth, Some(10));
+        assert_eq!(config.limits.max_branches, Some(100));
+        assert!(config.rpc_cache);
+        assert_eq!(config.rpc_timeout, 30);
+        assert_eq!(config.lookup_retries, 3);
+    }
+
+    #[test]
+    fn test_chain_rpc_url() {
+        let config: Configuration = toml::from_str(
+            "[rpc_urls]\nbase = \"https://base.rpc\"\n137 = \"https://polygon.rpc\"",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.chain_rpc_url(&"base".parse::<Chain>().unwrap()),
+            Some(String::from("https://base.rpc"))
+        );
+        assert_eq!(
+            config.chain_rpc_url(&"polygon".parse::<Chain>().unwrap()),
+            Some(String::from("https://polygon.rpc"))
+        );
+        assert_eq!(config.chain_rpc_url(&"mainnet".parse::<Chain>().unwrap()), None);
+    }
+
+    #[test]
+    fn test_read_missing_config_file() {
+        let path = std::env::temp_dir().join("heimdall_test_missing_config.toml");
+        assert!(matches!(read_config_file(path.to_str().unwrap()), Err(Error::ReadError(_))));
+    }
 }
@@ -0,0 +1,201 @@
+use ethers::types::U256;
+use heimdall_common::{ether::evm::core::opcodes::Opcode, utils::strings::encode_hex_reduced};
+
+/// A basic block of EVM instructions. Execution always begins at the first instruction of the
+/// block, and always leaves the block at its last instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    // the instruction (program counter + 1) of the first instruction in the block
+    pub start: u128,
+
+    // the instruction (program counter + 1) of the last instruction in the block
+    pub end: u128,
+
+    // the opcode and pushed value, if any, of each instruction in the block
+    pub instructions: Vec<(u8, Option<U256>)>,
+
+    // the assembly of the block, with one instruction per line
+    pub assembly: String,
+}
+
+/// Whether or not execution of a basic block ends after the given opcode.
+pub(crate) fn is_terminator(opcode: &Opcode) -> bool {
+    matches!(
+        opcode.name,
+        "STOP" | "JUMP" | "JUMPI" | "RETURN" | "REVERT" | "INVALID" | "SELFDESTRUCT" | "unknown"
+    )
+}
+
+/// Split the given bytecode into [`BasicBlock`]s. A new block is started at every JUMPDEST, as
+/// well as after every instruction which halts or jumps.
+///
+/// The assembly of each block uses the same format as the nodes of the generated CFG, so blocks
+/// can be added to the graph directly.
+///
+/// ```
+/// use heimdall_core::analysis::blocks::find_basic_blocks;
+///
+/// // PUSH1 0x04 JUMP JUMPDEST STOP
+/// let blocks = find_basic_blocks(&[0x60, 0x04, 0x56, 0x5b, 0x00]);
+/// assert_eq!(blocks.len(), 2);
+/// assert_eq!(blocks[1].start, 4);
+/// ```
+pub fn find_basic_blocks(bytecode: &[u8]) -> Vec<BasicBlock> {
+    let mut blocks: Vec<BasicBlock> = Vec::new();
+    let mut current: Option<BasicBlock> = None;
+    let mut program_counter = 0;
+
+    while program_counter < bytecode.len() {
+        let opcode = Opcode::new(bytecode[program_counter]);
+        let instruction = program_counter as u128 + 1;
+
+        // JUMPDESTs always begin a new block
+        if opcode.name == "JUMPDEST" {
+            if let Some(block) = current.take() {
+                blocks.push(block);
+            }
+        }
+
+        // read the pushed value, if any
+        let mut pushed_value = None;
+        if let Some(byte_count) = opcode.name.strip_prefix("PUSH") {
+            let byte_count: usize = byte_count.parse().unwrap_or(0);
+            let end = std::cmp::min(program_counter + 1 + byte_count, bytecode.len());
+            pushed_value = Some(U256::from_big_endian(&bytecode[program_counter + 1..end]));
+            program_counter += byte_count;
+        }
+
+        let block = current.get_or_insert(BasicBlock {
+            start: instruction,
+            end: instruction,
+            instructions: Vec::new(),
+            assembly: String::new(),
+        });
+        block.end = instruction;
+        block.instructions.push((opcode.code, pushed_value));
+        block.assembly.push_str(&format!(
+            "{} {} {}\n",
+            encode_hex_reduced(U256::from(instruction)),
+            opcode.name,
+            pushed_value.map(encode_hex_reduced).unwrap_or_default()
+        ));
+
+        if is_terminator(&opcode) {
+            if let Some(block) = current.take() {
+                blocks.push(block);
+            }
+        }
+
+        program_counter += 1;
+    }
+
+    if let Some(block) = current.take() {
+        blocks.push(block);
+    }
+
+    blocks
+}
+
+/// Find all statically resolvable edges between the given [`BasicBlock`]s, as returned by
+/// [`find_basic_blocks`]. Each edge is a tuple of `(from, to, jump_taken)`, where `from` and `to`
+/// are indices into `blocks`.
+///
+/// Jumps are only resolved when their destination is pushed immediately before the JUMP or JUMPI,
+/// so computed jumps (such as internal function returns) will not have edges.
+pub fn find_block_edges(blocks: &[BasicBlock]) -> Vec<(usize, usize, bool)> {
+    let mut edges = Vec::new();
+
+    for (index, block) in blocks.iter().enumerate() {
+        let (opcode, _) = match block.instructions.last() {
+            Some(instruction) => *instruction,
+            None => continue,
+        };
+
+        // find the constant jump destination, if any
+        if opcode == 0x56 || opcode == 0x57 {
+            let destination = match block.instructions.iter().rev().nth(1) {
+                Some((_, Some(destination))) => destination,
+                _ => continue,
+            };
+
+            if let Some(target) = blocks.iter().position(|target| {
+                U256::from(target.start - 1) == *destination &&
+                    target.instructions.first().map(|(opcode, _)| *opcode) == Some(0x5b)
+            }) {
+                edges.push((index, target, true));
+            }
+        }
+
+        // every block which doesn't halt or JUMP falls through to the next block
+        if (!is_terminator(&Opcode::new(opcode)) || opcode == 0x57) && index + 1 < blocks.len() {
+            edges.push((index, index + 1, false));
+        }
+    }
+
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::blocks::*;
+
+    #[test]
+    fn test_find_basic_blocks_empty() {
+        assert!(find_basic_blocks(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_find_basic_blocks_splits_on_terminators() {
+        // PUSH1 0x01 PUSH1 0x06 JUMPI STOP JUMPDEST STOP
+        let blocks = find_basic_blocks(&[0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x5b, 0x00]);
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!((blocks[0].start, blocks[0].end), (1, 5));
+        assert_eq!((blocks[1].start, blocks[1].end), (6, 6));
+        assert_eq!((blocks[2].start, blocks[2].end), (7, 8));
+    }
+
+    #[test]
+    fn test_find_basic_blocks_assembly() {
+        // PUSH1 0x00 JUMPDEST ADD
+        let blocks = find_basic_blocks(&[0x60, 0x00, 0x5b, 0x01]);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].assembly, "0x01 PUSH1 0\n");
+        assert_eq!(blocks[1].assembly, "0x03 JUMPDEST \n0x04 ADD \n");
+    }
+
+    #[test]
+    fn test_find_basic_blocks_truncated_push() {
+        // PUSH2 0x01 (truncated)
+        let blocks = find_basic_blocks(&[0x61, 0x01]);
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].assembly, "0x01 PUSH2 0x01\n");
+    }
+
+    #[test]
+    fn test_find_block_edges() {
+        // PUSH1 0x01 PUSH1 0x06 JUMPI STOP JUMPDEST PUSH1 0x06 JUMP
+        let blocks =
+            find_basic_blocks(&[0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x5b, 0x60, 0x06, 0x56]);
+
+        assert_eq!(find_block_edges(&blocks), vec![(0, 2, true), (0, 1, false), (2, 2, true)]);
+    }
+
+    #[test]
+    fn test_find_block_edges_fallthrough() {
+        // PUSH1 0x00 JUMPDEST STOP
+        let blocks = find_basic_blocks(&[0x60, 0x00, 0x5b, 0x00]);
+
+        assert_eq!(find_block_edges(&blocks), vec![(0, 1, false)]);
+    }
+
+    #[test]
+    fn test_find_block_edges_ignores_computed_jumps() {
+        // CALLDATASIZE JUMP JUMPDEST STOP
+        let blocks = find_basic_blocks(&[0x36, 0x56, 0x5b, 0x00]);
+
+        assert!(find_block_edges(&blocks).is_empty());
+    }
+}
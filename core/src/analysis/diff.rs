@@ -0,0 +1,169 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+};
+
+use ethers::types::U256;
+use serde::Serialize;
+
+use super::blocks::BasicBlock;
+
+/// The status of a [`BasicBlock`] after aligning two versions of a contract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffStatus {
+    // the block exists in both versions with identical instructions
+    Unchanged,
+
+    // the block exists in both versions with identical opcodes, but different constants
+    Changed,
+
+    // the block only exists in the new version
+    Added,
+
+    // the block only exists in the old version
+    Removed,
+}
+
+/// A single aligned pair of [`BasicBlock`]s. `old` and `new` are indices into the old and new
+/// blocks respectively, and at least one of them is always set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockDiff {
+    pub status: DiffStatus,
+    pub old: Option<usize>,
+    pub new: Option<usize>,
+}
+
+/// The normalized shape of a block, which is its sequence of opcodes with all constants removed.
+fn shape_key(block: &BasicBlock) -> Vec<u8> {
+    block.instructions.iter().map(|(opcode, _)| *opcode).collect()
+}
+
+/// The normalized contents of a block. Jump destinations are removed, since inserting or removing
+/// code will shift the destination of every jump which follows it.
+fn content_key(block: &BasicBlock) -> Vec<(u8, Option<U256>)> {
+    block
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(index, (opcode, value))| match block.instructions.get(index + 1) {
+            Some((0x56, _)) | Some((0x57, _)) => (*opcode, None),
+            _ => (*opcode, *value),
+        })
+        .collect()
+}
+
+/// Pair each unmatched block in `new` with the first unmatched block in `old` which has the same
+/// key, marking the pair with the given status.
+fn pair_blocks<K: Hash + Eq>(
+    old: &[BasicBlock],
+    new: &[BasicBlock],
+    matches: &mut [Option<(DiffStatus, usize)>],
+    matched_old: &mut [bool],
+    status: DiffStatus,
+    key: impl Fn(&BasicBlock) -> K,
+) {
+    let mut candidates: HashMap<K, VecDeque<usize>> = HashMap::new();
+    for (index, block) in old.iter().enumerate() {
+        if !matched_old[index] {
+            candidates.entry(key(block)).or_default().push_back(index);
+        }
+    }
+
+    for (index, block) in new.iter().enumerate() {
+        if matches[index].is_some() {
+            continue
+        }
+
+        if let Some(old_index) =
+            candidates.get_mut(&key(block)).and_then(|candidates| candidates.pop_front())
+        {
+            matches[index] = Some((status, old_index));
+            matched_old[old_index] = true;
+        }
+    }
+}
+
+/// Align the basic blocks of two versions of a contract by their normalized instructions.
+///
+/// Blocks with identical normalized contents are paired first and marked unchanged. The remaining
+/// blocks are then paired by their opcodes alone and marked changed. Any blocks left over are
+/// marked as added or removed.
+///
+/// The returned diffs are ordered by their position in the new version, followed by all removed
+/// blocks in their order in the old version.
+pub fn diff_blocks(old: &[BasicBlock], new: &[BasicBlock]) -> Vec<BlockDiff> {
+    let mut matches: Vec<Option<(DiffStatus, usize)>> = vec![None; new.len()];
+    let mut matched_old = vec![false; old.len()];
+
+    pair_blocks(old, new, &mut matches, &mut matched_old, DiffStatus::Unchanged, content_key);
+    pair_blocks(old, new, &mut matches, &mut matched_old, DiffStatus::Changed, shape_key);
+
+    let mut diffs = matches
+        .into_iter()
+        .enumerate()
+        .map(|(index, matched)| match matched {
+            Some((status, old_index)) => {
+                BlockDiff { status, old: Some(old_index), new: Some(index) }
+            }
+            None => BlockDiff { status: DiffStatus::Added, old: None, new: Some(index) },
+        })
+        .collect::<Vec<BlockDiff>>();
+
+    diffs.extend(
+        matched_old.into_iter().enumerate().filter(|(_, matched)| !matched).map(|(index, _)| {
+            BlockDiff { status: DiffStatus::Removed, old: Some(index), new: None }
+        }),
+    );
+
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::{blocks::find_basic_blocks, diff::*};
+
+    #[test]
+    fn test_diff_blocks_identical() {
+        let blocks = find_basic_blocks(&[0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x5b, 0x00]);
+
+        assert!(diff_blocks(&blocks, &blocks)
+            .iter()
+            .all(|diff| diff.status == DiffStatus::Unchanged && diff.old == diff.new));
+    }
+
+    #[test]
+    fn test_diff_blocks_shifted_jump_destination_is_unchanged() {
+        // PUSH1 0x03 JUMP JUMPDEST STOP
+        let old = find_basic_blocks(&[0x60, 0x03, 0x56, 0x5b, 0x00]);
+
+        // PUSH1 0x04 JUMP INVALID JUMPDEST STOP
+        let new = find_basic_blocks(&[0x60, 0x04, 0x56, 0xfe, 0x5b, 0x00]);
+
+        assert_eq!(
+            diff_blocks(&old, &new),
+            vec![
+                BlockDiff { status: DiffStatus::Unchanged, old: Some(0), new: Some(0) },
+                BlockDiff { status: DiffStatus::Added, old: None, new: Some(1) },
+                BlockDiff { status: DiffStatus::Unchanged, old: Some(1), new: Some(2) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_blocks_changed_and_removed() {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE STOP JUMPDEST CALLER SELFDESTRUCT
+        let old = find_basic_blocks(&[0x60, 0x01, 0x60, 0x00, 0x55, 0x00, 0x5b, 0x33, 0xff]);
+
+        // PUSH1 0x02 PUSH1 0x00 SSTORE STOP
+        let new = find_basic_blocks(&[0x60, 0x02, 0x60, 0x00, 0x55, 0x00]);
+
+        assert_eq!(
+            diff_blocks(&old, &new),
+            vec![
+                BlockDiff { status: DiffStatus::Changed, old: Some(0), new: Some(0) },
+                BlockDiff { status: DiffStatus::Removed, old: Some(1), new: None },
+            ]
+        );
+    }
+}
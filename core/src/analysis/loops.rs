@@ -0,0 +1,242 @@
+use std::collections::{HashSet, VecDeque};
+
+use petgraph::{
+    algo::dominators::{simple_fast, Dominators},
+    graph::NodeIndex,
+    visit::EdgeRef,
+    Direction, Graph,
+};
+
+/// A natural loop within a control flow graph. Every node within the loop's body is dominated by
+/// the loop header, and each latch has a back-edge which jumps to the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NaturalLoop {
+    // the single entry point of the loop, which dominates every node in the body
+    pub header: NodeIndex<u32>,
+
+    // the nodes which jump back to the header, sorted ascending
+    pub latches: Vec<NodeIndex<u32>>,
+
+    // every node which belongs to the loop, including the header, sorted ascending
+    pub body: Vec<NodeIndex<u32>>,
+}
+
+impl NaturalLoop {
+    /// Whether or not the given node belongs to this loop.
+    pub fn contains(&self, node: NodeIndex<u32>) -> bool {
+        self.body.binary_search(&node).is_ok()
+    }
+}
+
+/// Build the dominator tree of the given [`Graph`], rooted at `entry`.
+pub fn dominator_tree<N, E>(graph: &Graph<N, E>, entry: NodeIndex<u32>) -> Dominators<NodeIndex> {
+    simple_fast(graph, entry)
+}
+
+/// Whether or not `a` dominates `b`. Every node dominates itself, and nodes which are not
+/// reachable from the root are not dominated by anything.
+pub fn dominates(dominators: &Dominators<NodeIndex>, a: NodeIndex<u32>, b: NodeIndex<u32>) -> bool {
+    match dominators.dominators(b) {
+        Some(mut iter) => iter.any(|node| node == a),
+        None => false,
+    }
+}
+
+/// Find all natural loops within the given [`Graph`], starting from `entry`.
+///
+/// An edge `latch -> header` is a back-edge when `header` dominates `latch`. The body of the loop
+/// is the header, plus every node which can reach the latch without passing through the header.
+/// Back-edges which share a header are merged into a single loop.
+pub fn find_natural_loops<N, E>(graph: &Graph<N, E>, entry: NodeIndex<u32>) -> Vec<NaturalLoop> {
+    let mut loops: Vec<NaturalLoop> = Vec::new();
+
+    if graph.node_count() == 0 {
+        return loops
+    }
+
+    let dominators = dominator_tree(graph, entry);
+
+    for edge in graph.edge_references() {
+        let (latch, header) = (edge.source(), edge.target());
+        if !dominates(&dominators, header, latch) {
+            continue
+        }
+
+        // walk backwards from the latch until we reach the header
+        let mut body: HashSet<NodeIndex<u32>> = HashSet::from([header, latch]);
+        let mut worklist: VecDeque<NodeIndex<u32>> = VecDeque::from([latch]);
+        while let Some(node) = worklist.pop_front() {
+            if node == header {
+                continue
+            }
+
+            for predecessor in graph.neighbors_directed(node, Direction::Incoming) {
+                // unreachable predecessors cannot be part of the loop
+                if dominators.dominators(predecessor).is_none() {
+                    continue
+                }
+
+                if body.insert(predecessor) {
+                    worklist.push_back(predecessor);
+                }
+            }
+        }
+
+        // merge loops which share the same header
+        match loops.iter_mut().find(|natural_loop| natural_loop.header == header) {
+            Some(natural_loop) => {
+                natural_loop.latches.push(latch);
+                natural_loop.body.extend(body);
+            }
+            None => loops.push(NaturalLoop {
+                header,
+                latches: vec![latch],
+                body: body.into_iter().collect(),
+            }),
+        }
+    }
+
+    for natural_loop in loops.iter_mut() {
+        natural_loop.latches.sort();
+        natural_loop.latches.dedup();
+        natural_loop.body.sort();
+        natural_loop.body.dedup();
+    }
+    loops.sort_by_key(|natural_loop| natural_loop.header);
+
+    loops
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::{graph::NodeIndex, Graph};
+
+    use crate::analysis::loops::*;
+
+    #[test]
+    fn test_dominates() {
+        let mut graph = Graph::<(), ()>::new();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        let d = graph.add_node(());
+        let unreachable = graph.add_node(());
+        graph.extend_with_edges([(a, b), (a, c), (b, d), (c, d), (unreachable, d)]);
+
+        let dominators = dominator_tree(&graph, a);
+        assert!(dominates(&dominators, a, d));
+        assert!(dominates(&dominators, d, d));
+        assert!(!dominates(&dominators, b, d));
+        assert!(!dominates(&dominators, a, unreachable));
+    }
+
+    #[test]
+    fn test_find_natural_loops_acyclic() {
+        let mut graph = Graph::<(), ()>::new();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        graph.extend_with_edges([(a, b), (a, c), (b, c)]);
+
+        assert!(find_natural_loops(&graph, a).is_empty());
+    }
+
+    #[test]
+    fn test_find_natural_loops_empty_graph() {
+        let graph = Graph::<(), ()>::new();
+
+        assert!(find_natural_loops(&graph, NodeIndex::new(0)).is_empty());
+    }
+
+    #[test]
+    fn test_find_natural_loops_simple() {
+        // entry -> header -> body -> header
+        //            \-> exit
+        let mut graph = Graph::<(), ()>::new();
+        let entry = graph.add_node(());
+        let header = graph.add_node(());
+        let body = graph.add_node(());
+        let exit = graph.add_node(());
+        graph.extend_with_edges([(entry, header), (header, body), (body, header), (header, exit)]);
+
+        let loops = find_natural_loops(&graph, entry);
+        assert_eq!(
+            loops,
+            vec![NaturalLoop { header, latches: vec![body], body: vec![header, body] }]
+        );
+        assert!(loops[0].contains(body));
+        assert!(!loops[0].contains(exit));
+    }
+
+    #[test]
+    fn test_find_natural_loops_self_loop() {
+        let mut graph = Graph::<(), ()>::new();
+        let entry = graph.add_node(());
+        let header = graph.add_node(());
+        graph.extend_with_edges([(entry, header), (header, header)]);
+
+        let loops = find_natural_loops(&graph, entry);
+        assert_eq!(loops, vec![NaturalLoop { header, latches: vec![header], body: vec![header] }]);
+    }
+
+    #[test]
+    fn test_find_natural_loops_nested() {
+        // entry -> outer -> inner -> inner_latch -> inner
+        //                               \-> outer_latch -> outer
+        let mut graph = Graph::<(), ()>::new();
+        let entry = graph.add_node(());
+        let outer = graph.add_node(());
+        let inner = graph.add_node(());
+        let inner_latch = graph.add_node(());
+        let outer_latch = graph.add_node(());
+        graph.extend_with_edges([
+            (entry, outer),
+            (outer, inner),
+            (inner, inner_latch),
+            (inner_latch, inner),
+            (inner_latch, outer_latch),
+            (outer_latch, outer),
+        ]);
+
+        let loops = find_natural_loops(&graph, entry);
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].header, outer);
+        assert_eq!(loops[0].body, vec![outer, inner, inner_latch, outer_latch]);
+        assert_eq!(loops[1].header, inner);
+        assert_eq!(loops[1].body, vec![inner, inner_latch]);
+    }
+
+    #[test]
+    fn test_find_natural_loops_irreducible() {
+        // neither a nor b dominates the other, so there are no natural loops
+        let mut graph = Graph::<(), ()>::new();
+        let entry = graph.add_node(());
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.extend_with_edges([(entry, a), (entry, b), (a, b), (b, a)]);
+
+        assert!(find_natural_loops(&graph, entry).is_empty());
+    }
+
+    #[test]
+    fn test_find_natural_loops_merges_shared_header() {
+        let mut graph = Graph::<(), ()>::new();
+        let entry = graph.add_node(());
+        let header = graph.add_node(());
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        graph.extend_with_edges([
+            (entry, header),
+            (header, a),
+            (header, b),
+            (a, header),
+            (b, header),
+        ]);
+
+        let loops = find_natural_loops(&graph, entry);
+        assert_eq!(
+            loops,
+            vec![NaturalLoop { header, latches: vec![a, b], body: vec![header, a, b] }]
+        );
+    }
+}
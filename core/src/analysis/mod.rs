@@ -0,0 +1,4 @@
+pub mod blocks;
+pub mod diff;
+pub mod loops;
+pub mod reachability;
@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use petgraph::{graph::NodeIndex, visit::Dfs, Graph};
+
+/// Find all nodes within the given [`Graph`] which cannot be reached from `entry`, sorted
+/// ascending.
+///
+/// ```
+/// use heimdall_core::analysis::reachability::find_unreachable_nodes;
+/// use petgraph::Graph;
+///
+/// let mut graph = Graph::<(), ()>::new();
+/// let entry = graph.add_node(());
+/// let reachable = graph.add_node(());
+/// let unreachable = graph.add_node(());
+/// graph.add_edge(entry, reachable, ());
+///
+/// assert_eq!(find_unreachable_nodes(&graph, entry), vec![unreachable]);
+/// ```
+pub fn find_unreachable_nodes<N, E>(
+    graph: &Graph<N, E>,
+    entry: NodeIndex<u32>,
+) -> Vec<NodeIndex<u32>> {
+    if graph.node_count() == 0 {
+        return Vec::new()
+    }
+
+    let mut reachable: HashSet<NodeIndex<u32>> = HashSet::new();
+    let mut dfs = Dfs::new(graph, entry);
+    while let Some(node) = dfs.next(graph) {
+        reachable.insert(node);
+    }
+
+    graph.node_indices().filter(|node| !reachable.contains(node)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::{graph::NodeIndex, Graph};
+
+    use crate::analysis::reachability::*;
+
+    #[test]
+    fn test_find_unreachable_nodes_empty_graph() {
+        let graph = Graph::<(), ()>::new();
+
+        assert!(find_unreachable_nodes(&graph, NodeIndex::new(0)).is_empty());
+    }
+
+    #[test]
+    fn test_find_unreachable_nodes_ignores_edges_into_reachable_nodes() {
+        // an unreachable node with an edge into reachable code is still unreachable
+        let mut graph = Graph::<(), ()>::new();
+        let entry = graph.add_node(());
+        let a = graph.add_node(());
+        let hidden = graph.add_node(());
+        let hidden_child = graph.add_node(());
+        graph.extend_with_edges([(entry, a), (hidden, a), (hidden, hidden_child)]);
+
+        assert_eq!(find_unreachable_nodes(&graph, entry), vec![hidden, hidden_child]);
+    }
+}
@@ -0,0 +1,275 @@
+use std::{collections::HashMap, fs};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::U256;
+use heimdall_common::{
+    ether::evm::core::opcodes::Opcode,
+    utils::{io::logging::Logger, strings::encode_hex},
+};
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(about = "Assemble EVM assembly back into bytecode",
+       after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+       global_setting = AppSettings::DeriveDisplayOrder,
+       override_usage = "heimdall assemble <TARGET> [OPTIONS]")]
+pub struct AssemblerArgs {
+    /// The assembly to assemble, either a file containing the output of `heimdall disassemble`, or
+    /// the assembly itself.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl AssemblerArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A single parsed line of assembly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Label(String),
+    Instruction { line: usize, opcode: u8, operand: Option<String> },
+}
+
+/// Find the opcode with the given name, ignoring case.
+fn find_opcode(name: &str) -> Option<u8> {
+    (0..=255u8).find(|code| {
+        let opcode = Opcode::new(*code);
+        opcode.name != "unknown" && opcode.name.eq_ignore_ascii_case(name)
+    })
+}
+
+/// Parse a single line of assembly. Program counters, comments (starting with `;`), and empty
+/// lines are ignored.
+fn parse_line(line_number: usize, line: &str) -> Result<Option<Line>, Error> {
+    let line = line.split(';').next().unwrap_or_default().trim();
+    if line.is_empty() {
+        return Ok(None)
+    }
+
+    if let Some(label) = line.strip_suffix(':') {
+        return Ok(Some(Line::Label(label.trim().to_string())))
+    }
+
+    // skip the program counter, if any
+    let mut tokens = line.split_whitespace().peekable();
+    if let Some(token) = tokens.peek() {
+        if find_opcode(token).is_none() && !token.eq_ignore_ascii_case("unknown") {
+            tokens.next();
+        }
+    }
+
+    let name = tokens.next().ok_or_else(|| {
+        Error::AssembleError(line_number, String::from("expected an instruction"))
+    })?;
+    let operand = tokens.next().map(|operand| operand.to_string());
+    if let Some(extra) = tokens.next() {
+        return Err(Error::AssembleError(line_number, format!("unexpected token '{extra}'")))
+    }
+
+    // unknown opcodes are assembled from their raw byte, if it was included in the disassembly
+    if name.eq_ignore_ascii_case("unknown") {
+        let byte = operand
+            .and_then(|operand| u8::from_str_radix(operand.trim_start_matches("0x"), 16).ok())
+            .ok_or_else(|| {
+                Error::AssembleError(
+                    line_number,
+                    String::from("unknown opcodes must include their raw byte, i.e. 'unknown 0c'"),
+                )
+            })?;
+        return Ok(Some(Line::Instruction { line: line_number, opcode: byte, operand: None }))
+    }
+
+    let opcode = find_opcode(name).ok_or_else(|| {
+        Error::AssembleError(line_number, format!("unknown instruction '{name}'"))
+    })?;
+
+    match (push_size(opcode), &operand) {
+        (0, Some(operand)) => {
+            Err(Error::AssembleError(line_number, format!("unexpected operand '{operand}'")))
+        }
+        (size, None) if size > 0 => {
+            Err(Error::AssembleError(line_number, format!("{name} requires an operand")))
+        }
+        _ => Ok(Some(Line::Instruction { line: line_number, opcode, operand })),
+    }
+}
+
+/// The number of bytes pushed by the given opcode.
+fn push_size(opcode: u8) -> usize {
+    match opcode {
+        0x60..=0x7f => (opcode - 0x5f) as usize,
+        _ => 0,
+    }
+}
+
+/// Encode a PUSH operand, which is either a hex value or a label, as exactly `size` bytes.
+fn encode_operand(
+    line: usize,
+    operand: &str,
+    size: usize,
+    labels: &HashMap<String, usize>,
+) -> Result<Vec<u8>, Error> {
+    let value = match labels.get(operand) {
+        Some(offset) => U256::from(*offset),
+        None => U256::from_str_radix(operand.trim_start_matches("0x"), 16).map_err(|_| {
+            Error::AssembleError(line, format!("'{operand}' is not a hex value or label"))
+        })?,
+    };
+
+    if value.bits() > size * 8 {
+        return Err(Error::AssembleError(line, format!("'{operand}' doesn't fit in {size} bytes")))
+    }
+
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    Ok(word[32 - size..].to_vec())
+}
+
+/// Assemble the given assembly into bytecode. The assembly may be in the format produced by
+/// `heimdall disassemble`, with or without program counters and labels.
+///
+/// Labels are defined with `name:` and may be used as PUSH operands, in which case they are
+/// replaced with the offset of the label. Offsets are recalculated when assembling, so
+/// instructions may be freely added or removed.
+///
+/// ```
+/// use heimdall_core::assemble::assemble_instructions;
+///
+/// let bytecode = assemble_instructions("PUSH1 label_0x4\nJUMP\nINVALID\nlabel_0x4:\nJUMPDEST\n");
+/// assert_eq!(bytecode.unwrap(), vec![0x60, 0x04, 0x56, 0xfe, 0x5b]);
+/// ```
+pub fn assemble_instructions(assembly: &str) -> Result<Vec<u8>, Error> {
+    let mut lines = Vec::new();
+    for (index, line) in assembly.lines().enumerate() {
+        if let Some(line) = parse_line(index + 1, line)? {
+            lines.push(line);
+        }
+    }
+
+    // find the offset of each label
+    let mut labels = HashMap::new();
+    let mut offset = 0;
+    for line in &lines {
+        match line {
+            Line::Label(label) => {
+                labels.insert(label.clone(), offset);
+            }
+            Line::Instruction { opcode, .. } => offset += 1 + push_size(*opcode),
+        }
+    }
+
+    let mut bytecode = Vec::with_capacity(offset);
+    for line in &lines {
+        if let Line::Instruction { line, opcode, operand } = line {
+            bytecode.push(*opcode);
+            if let Some(operand) = operand {
+                bytecode.extend(encode_operand(*line, operand, push_size(*opcode), &labels)?);
+            }
+        }
+    }
+
+    Ok(bytecode)
+}
+
+/// Assemble the given target's assembly to bytecode.
+pub async fn assemble(args: AssemblerArgs) -> Result<String, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // the target is either a file containing assembly, or the assembly itself
+    let assembly = match fs::read_to_string(&args.target) {
+        Ok(contents) => contents,
+        Err(_) => {
+            logger.debug_max("target is not a file, assembling it directly.");
+            args.target.clone()
+        }
+    };
+
+    let bytecode = assemble_instructions(&assembly)?;
+
+    logger.info(&format!("assembled {} bytes successfully.", bytecode.len()));
+    logger.debug(&format!("assembly completed in {} ms.", now.elapsed().as_millis()));
+
+    Ok(format!("0x{}", encode_hex(bytecode)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{assemble::*, error::Error};
+
+    #[test]
+    fn test_assemble_disassembler_output() {
+        let assembly = "000001 PUSH1 01\n000003 PUSH1 label_0x6\n000004 JUMPI \n\n000005 STOP \n\nlabel_0x6:\n000006 JUMPDEST \n000008 PUSH1 label_0x6\n000009 JUMP \n";
+
+        assert_eq!(
+            assemble_instructions(assembly).unwrap(),
+            vec![0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x5b, 0x60, 0x06, 0x56]
+        );
+    }
+
+    #[test]
+    fn test_assemble_relocates_labels() {
+        // an extra instruction was inserted before the label
+        let assembly = "PUSH1 label_0x6\nJUMP\nCALLER\nPOP\nlabel_0x6:\nJUMPDEST\n";
+
+        assert_eq!(
+            assemble_instructions(assembly).unwrap(),
+            vec![0x60, 0x05, 0x56, 0x33, 0x50, 0x5b]
+        );
+    }
+
+    #[test]
+    fn test_assemble_pads_operands() {
+        assert_eq!(
+            assemble_instructions("PUSH4 0x01 ; comment").unwrap(),
+            vec![0x63, 0x00, 0x00, 0x00, 0x01]
+        );
+        assert_eq!(assemble_instructions("0 unknown 0c").unwrap(), vec![0x0c]);
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert!(matches!(assemble_instructions("PUSH1 0100"), Err(Error::AssembleError(1, _))));
+        assert!(matches!(assemble_instructions("ADD\nPUSH2"), Err(Error::AssembleError(2, _))));
+        assert!(matches!(
+            assemble_instructions("PUSH1 missing_label"),
+            Err(Error::AssembleError(1, _))
+        ));
+        assert!(matches!(assemble_instructions("FOO"), Err(Error::AssembleError(1, _))));
+        assert!(matches!(assemble_instructions("unknown"), Err(Error::AssembleError(1, _))));
+    }
+}
@@ -0,0 +1,422 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{Display, Formatter},
+};
+
+use ethers::types::{H160, U256};
+use heimdall_common::{
+    ether::evm::{
+        core::{
+            opcodes::{WrappedInput, WrappedOpcode},
+            vm::State,
+        },
+        ext::exec::VMTrace,
+    },
+    utils::strings::encode_hex_reduced,
+};
+use serde::Serialize;
+
+/// A check which restricts who can call a function.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Gate {
+    /// msg.sender is compared with an address read from a storage slot, such as Ownable's owner.
+    Owner { slot: U256 },
+
+    /// msg.sender must hold a role, such as in OpenZeppelin's AccessControl, whose members are
+    /// stored in a mapping of roles at `slot`.
+    Role { role: U256, slot: U256 },
+
+    /// msg.sender is looked up in a mapping at `slot`, such as a list of admins. Mappings which
+    /// hash msg.sender alone are reported at slot zero.
+    Allowlist { slot: U256 },
+
+    /// msg.sender is compared with a hardcoded address.
+    Address { address: H160 },
+
+    /// msg.sender must be the contract itself.
+    SelfCall,
+}
+
+impl Display for Gate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Gate::Owner { slot } => write!(f, "owner at storage[{}]", encode_hex_reduced(*slot)),
+            Gate::Role { role, slot } => write!(
+                f,
+                "role {} of the roles at storage[{}]",
+                encode_hex_reduced(*role),
+                encode_hex_reduced(*slot)
+            ),
+            Gate::Allowlist { slot } => {
+                write!(f, "allowlist at storage[{}]", encode_hex_reduced(*slot))
+            }
+            Gate::Address { address } => write!(f, "address {address:#x}"),
+            Gate::SelfCall => write!(f, "the contract itself"),
+        }
+    }
+}
+
+/// Who can call a function successfully.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    // at least one path succeeds without checking msg.sender
+    Open,
+
+    // every path which succeeds checks msg.sender against a gate
+    Gated,
+
+    // every path which succeeds requires msg.sender to be the contract itself
+    Internal,
+
+    // every path reverts
+    Unreachable,
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Access::Open => write!(f, "open"),
+            Access::Gated => write!(f, "gated"),
+            Access::Internal => write!(f, "internal"),
+            Access::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
+
+/// Who can call one of the contract's functions, and the gates which restrict it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionAccess {
+    // the function's 4byte selector, and its signature if it was resolved
+    pub selector: String,
+    pub function: Option<String>,
+
+    pub access: Access,
+
+    // every gate checked on a path through the function which succeeds
+    pub gates: Vec<Gate>,
+}
+
+impl Display for FunctionAccess {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.function {
+            Some(function) => write!(f, "{function} (0x{}): {}", self.selector, self.access)?,
+            None => write!(f, "0x{}: {}", self.selector, self.access)?,
+        }
+        if !self.gates.is_empty() {
+            let gates = self.gates.iter().map(|gate| gate.to_string()).collect::<Vec<_>>();
+            write!(f, ", checks {}", gates.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Tracks the checks a path through a function makes on msg.sender, one operation at a time.
+#[derive(Clone, Debug, Default)]
+pub struct CallerGuard {
+    // the memory hashed to produce each hash, so nested mapping keys can be recovered
+    preimages: HashMap<U256, Vec<u8>>,
+
+    // the slots of mappings keyed by msg.sender, and the gate reading each implies
+    caller_slots: HashMap<U256, Gate>,
+
+    // the gates implied by the slots keyed by msg.sender which have been read
+    read_gates: BTreeSet<Gate>,
+
+    // the gates the path has branched on
+    gates: BTreeSet<Gate>,
+}
+
+impl CallerGuard {
+    /// Update the guard with an operation of the path, which was executed with `caller` as
+    /// msg.sender.
+    pub fn step(&mut self, operation: &State, caller: &[u8]) {
+        let instruction = &operation.last_instruction;
+        let word = |index: usize| instruction.inputs.get(index).copied().unwrap_or_default();
+
+        match instruction.opcode {
+            // SHA3, which may compute the slot of a mapping keyed by msg.sender
+            0x20 => {
+                let Some(hash) = instruction.outputs.first() else { return };
+                let data = operation.memory.read(
+                    word(0).try_into().unwrap_or(usize::MAX),
+                    word(1).try_into().unwrap_or(0),
+                );
+
+                // a set of addresses keyed by msg.sender alone, with no slot
+                if data.len() == 32 && !caller.is_empty() && is_word_of(&data, caller) {
+                    self.caller_slots.insert(*hash, Gate::Allowlist { slot: U256::zero() });
+                }
+
+                // solidity hashes the key before the slot, and vyper after it
+                if data.len() == 64 && !caller.is_empty() {
+                    let key =
+                        match (is_word_of(&data[..32], caller), is_word_of(&data[32..], caller)) {
+                            (true, _) => Some(U256::from_big_endian(&data[32..])),
+                            (_, true) => Some(U256::from_big_endian(&data[..32])),
+                            _ => None,
+                        };
+                    if let Some(key) = key {
+                        let gate = match self.preimages.get(&key) {
+                            Some(preimage) if preimage.len() == 64 => Gate::Role {
+                                role: U256::from_big_endian(&preimage[..32]),
+                                slot: U256::from_big_endian(&preimage[32..]),
+                            },
+                            _ => Gate::Allowlist { slot: key },
+                        };
+                        self.caller_slots.insert(*hash, gate);
+                    }
+                }
+                self.preimages.insert(*hash, data);
+            }
+
+            // SLOAD
+            0x54 => {
+                if let Some(gate) = self.caller_slots.get(&word(0)) {
+                    self.read_gates.insert(gate.clone());
+                }
+            }
+
+            // JUMPI
+            0x57 => {
+                if let Some(condition) = instruction.input_operations.get(1) {
+                    let gates = self.condition_gates(condition);
+                    self.gates.extend(gates);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    /// Whether the path has branched on a check of msg.sender.
+    pub fn checked(&self) -> bool {
+        !self.gates.is_empty()
+    }
+
+    // the gates a branch's condition checks. comparisons of amounts, such as a balance keyed by
+    // msg.sender, aren't access control
+    fn condition_gates(&self, condition: &WrappedOpcode) -> Vec<Gate> {
+        let has = |code: u8| condition.any(&|operation| operation.opcode.code == code);
+        if condition.any(&|operation| (0x10..=0x13).contains(&operation.opcode.code)) {
+            return Vec::new()
+        }
+
+        // slots keyed by msg.sender
+        if has(0x54) && !self.read_gates.is_empty() && !has(0x33) {
+            return self.read_gates.iter().cloned().collect()
+        }
+        if !has(0x33) {
+            return Vec::new()
+        }
+
+        // msg.sender == address(this)
+        if has(0x30) {
+            return vec![Gate::SelfCall]
+        }
+
+        // msg.sender == storage[slot]
+        let mut gates = Vec::new();
+        collect_loaded_slots(condition, &mut gates);
+        if !gates.is_empty() {
+            return gates
+        }
+
+        // msg.sender == 0x..., ignoring the masks which clean addresses
+        let mut constants = Vec::new();
+        collect_constants(condition, &mut constants);
+        constants
+            .into_iter()
+            .filter(|value| {
+                *value > U256::from(10) &&
+                    value.bits() <= 160 &&
+                    *value != (U256::one() << 160) - U256::one()
+            })
+            .map(|value| Gate::Address { address: H160::from_slice(&encode_word(value)[12..]) })
+            .collect()
+    }
+}
+
+/// Determine who can call a function, from its [`VMTrace`] executed with `caller` as
+/// msg.sender.
+///
+/// A path through the function is gated if it branches on a comparison of msg.sender with an
+/// owner in storage, a hardcoded address, or the contract itself, or on a mapping keyed by
+/// msg.sender, such as a role. Paths which revert aren't considered.
+pub fn analyze_access(vm_trace: &VMTrace, caller: &[u8]) -> (Access, Vec<Gate>) {
+    let mut paths = Vec::new();
+    collect_paths(vm_trace, CallerGuard::default(), caller, &mut paths);
+
+    let gates = paths.iter().flatten().cloned().collect::<BTreeSet<Gate>>().into_iter().collect();
+    let access = if paths.is_empty() {
+        Access::Unreachable
+    } else if paths.iter().all(|gates| gates.contains(&Gate::SelfCall)) {
+        Access::Internal
+    } else if paths.iter().all(|gates| !gates.is_empty()) {
+        Access::Gated
+    } else {
+        Access::Open
+    };
+
+    (access, gates)
+}
+
+// collect the gates of each path through the trace which doesn't revert
+fn collect_paths(
+    vm_trace: &VMTrace,
+    mut guard: CallerGuard,
+    caller: &[u8],
+    paths: &mut Vec<BTreeSet<Gate>>,
+) {
+    for operation in &vm_trace.operations {
+        guard.step(operation, caller);
+    }
+
+    if vm_trace.children.is_empty() {
+        if !reverts(vm_trace) {
+            paths.push(guard.gates);
+        }
+        return
+    }
+    for child in &vm_trace.children {
+        collect_paths(child, guard.clone(), caller, paths);
+    }
+}
+
+/// Whether the last operation of a branch of the trace reverts, or halts exceptionally.
+pub fn reverts(vm_trace: &VMTrace) -> bool {
+    vm_trace.operations.last().is_some_and(|operation| {
+        operation.fault.is_some() || [0xfd, 0xfe].contains(&operation.last_instruction.opcode)
+    })
+}
+
+// whether a 32 byte word holds the given address, padded with zeros
+fn is_word_of(word: &[u8], address: &[u8]) -> bool {
+    address.len() <= word.len() &&
+        word[word.len() - address.len()..] == *address &&
+        word[..word.len() - address.len()].iter().all(|byte| *byte == 0)
+}
+
+// the owner gates of the constant slots read within an operation
+fn collect_loaded_slots(operation: &WrappedOpcode, gates: &mut Vec<Gate>) {
+    if operation.opcode.code == 0x54 {
+        if let Some(slot) = operation.inputs.first().and_then(constant) {
+            gates.push(Gate::Owner { slot });
+        }
+        return
+    }
+    for input in &operation.inputs {
+        if let WrappedInput::Opcode(input) = input {
+            collect_loaded_slots(input, gates);
+        }
+    }
+}
+
+// the constants pushed within an operation
+fn collect_constants(operation: &WrappedOpcode, constants: &mut Vec<U256>) {
+    for input in &operation.inputs {
+        match input {
+            WrappedInput::Raw(value) if (0x5f..=0x7f).contains(&operation.opcode.code) => {
+                constants.push(*value)
+            }
+            WrappedInput::Raw(_) => {}
+            WrappedInput::Opcode(input) => collect_constants(input, constants),
+        }
+    }
+}
+
+// the value of an input, if it's a constant
+fn constant(input: &WrappedInput) -> Option<U256> {
+    match input {
+        WrappedInput::Raw(value) => Some(*value),
+        WrappedInput::Opcode(operation) if (0x5f..=0x7f).contains(&operation.opcode.code) => {
+            match operation.inputs.first() {
+                Some(WrappedInput::Raw(value)) => Some(*value),
+                _ => Some(U256::zero()),
+            }
+        }
+        WrappedInput::Opcode(_) => None,
+    }
+}
+
+// the big-endian bytes of a word
+fn encode_word(value: U256) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    value.to_big_endian(&mut word);
+    word
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::{core::vm::VM, ext::exec::ExecutionLimits};
+
+    use super::*;
+
+    // analyze the access control of bytecode as though it were a single function
+    fn access_of(bytecode: &str) -> (Access, Vec<Gate>) {
+        let vm = VM::new(
+            bytecode.to_string(),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        );
+        let (vm_trace, ..) = vm.symbolic_exec(&ExecutionLimits::default());
+        analyze_access(&vm_trace, &vm.caller)
+    }
+
+    #[test]
+    fn test_owner_gate() {
+        // require(msg.sender == storage[0])
+        assert_eq!(
+            access_of("3360005414600957fe5b33ff"),
+            (Access::Gated, vec![Gate::Owner { slot: U256::zero() }])
+        );
+
+        // if (msg.sender == storage[0]) { .. }, which does nothing for anyone else
+        assert_eq!(
+            access_of("3360005414600957005b33ff"),
+            (Access::Gated, vec![Gate::Owner { slot: U256::zero() }])
+        );
+
+        // if (storage[keccak256(msg.sender)] != 0) { .. }
+        assert_eq!(
+            access_of("33600052602060002054600e57005b33ff"),
+            (Access::Gated, vec![Gate::Allowlist { slot: U256::zero() }])
+        );
+    }
+
+    #[test]
+    fn test_role_gate() {
+        // require(storage[keccak256(msg.sender, keccak256(0x42, 1))])
+        assert_eq!(
+            access_of("60426000526001602052604060002060205233600052604060002054602057fe5b00"),
+            (Access::Gated, vec![Gate::Role { role: U256::from(0x42), slot: U256::one() }])
+        );
+    }
+
+    #[test]
+    fn test_address_and_self_gates() {
+        // require(msg.sender == 0x1111111111111111111111111111111111111111)
+        assert_eq!(
+            access_of("3373111111111111111111111111111111111111111114601b57fe5b00"),
+            (Access::Gated, vec![Gate::Address { address: H160::from([0x11; 20]) }])
+        );
+
+        // require(msg.sender == address(this))
+        assert_eq!(access_of("333014600757fe5b00"), (Access::Internal, vec![Gate::SelfCall]));
+    }
+
+    #[test]
+    fn test_balance_check_is_not_gate() {
+        // require(storage[keccak256(msg.sender, 0)] > 5)
+        assert_eq!(
+            access_of("336000526000602052604060002054600510601657fe5b00"),
+            (Access::Open, vec![])
+        );
+        assert_eq!(access_of("fe"), (Access::Unreachable, vec![]));
+    }
+}
@@ -0,0 +1,444 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use ethers::types::U256;
+use heimdall_common::ether::{
+    evm::{
+        core::{
+            opcodes::WrappedOpcode,
+            taint::{Sink, TaintedSink},
+        },
+        ext::exec::VMTrace,
+    },
+    lexers::cleanup::Cleanup,
+};
+use serde::Serialize;
+
+use crate::audit::access::{reverts, CallerGuard};
+
+// the gas stipend forwarded by `transfer` and `send`, which is too little to re-enter the caller
+const CALL_STIPEND: u64 = 2300;
+
+/// How serious a [`Finding`] is, from the most to the least severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    High,
+    Medium,
+    Low,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::High => write!(f, "high"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::Low => write!(f, "low"),
+        }
+    }
+}
+
+/// A pattern which is likely to be a vulnerability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    // SELFDESTRUCT reachable without checking msg.sender
+    UnprotectedSelfdestruct,
+
+    // DELEGATECALL or CALLCODE to a computed address, reachable without checking msg.sender
+    UnprotectedDelegatecall,
+
+    // a branch on tx.origin, which authenticates whoever started the transaction rather than the
+    // caller
+    TxOriginAuth,
+
+    // a low-level call whose success is never checked
+    UncheckedCall,
+
+    // a call to an address taken from calldata, reachable without checking msg.sender
+    ArbitraryCall,
+
+    // storage written after an external call, without a lock held across the call
+    MissingReentrancyGuard,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Check::UnprotectedSelfdestruct => write!(f, "unprotected-selfdestruct"),
+            Check::UnprotectedDelegatecall => write!(f, "unprotected-delegatecall"),
+            Check::TxOriginAuth => write!(f, "tx-origin-auth"),
+            Check::UncheckedCall => write!(f, "unchecked-call"),
+            Check::ArbitraryCall => write!(f, "arbitrary-call"),
+            Check::MissingReentrancyGuard => write!(f, "missing-reentrancy-guard"),
+        }
+    }
+}
+
+/// A likely vulnerability found in one of the contract's functions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub check: Check,
+
+    // the function's 4byte selector, and its signature if it was resolved
+    pub selector: String,
+    pub function: Option<String>,
+
+    // the instruction the finding is at
+    pub instruction: u128,
+    pub description: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}] {} in {} at instruction {}: {}",
+            self.severity,
+            self.check,
+            match &self.function {
+                Some(function) => format!("{function} (0x{})", self.selector),
+                None => format!("0x{}", self.selector),
+            },
+            self.instruction,
+            self.description
+        )
+    }
+}
+
+// a call made on the current path, along with the slots written before and after it
+#[derive(Clone, Debug)]
+struct PathCall {
+    instruction: u128,
+    writes_before: HashSet<(u8, U256)>,
+    writes_after: HashSet<(u8, U256)>,
+}
+
+// what's known about the path from the function's entry point to the current instruction
+#[derive(Clone, Debug, Default)]
+struct Path {
+    // the checks the path has made on msg.sender
+    guard: CallerGuard,
+
+    // the storage and transient storage slots written, keyed by SSTORE or TSTORE
+    writes: HashSet<(u8, U256)>,
+
+    // the external calls which could re-enter the contract
+    calls: Vec<PathCall>,
+}
+
+/// Audit a function's [`VMTrace`] for likely vulnerabilities, such as unprotected SELFDESTRUCTs,
+/// authentication with tx.origin, and unchecked calls.
+///
+/// A path is considered access controlled once it branches on msg.sender, or on storage keyed by
+/// msg.sender, so `caller` must be the address of the caller the trace was executed with. Calls
+/// are only flagged as arbitrary if their target appears among the `tainted_sinks`.
+pub fn audit_trace(
+    vm_trace: &VMTrace,
+    selector: &str,
+    caller: &[u8],
+    tainted_sinks: &[TaintedSink],
+) -> Vec<Finding> {
+    let tainted_targets = tainted_sinks
+        .iter()
+        .filter(|sink| sink.sink == Sink::CallTarget)
+        .map(|sink| sink.instruction)
+        .collect::<HashSet<u128>>();
+
+    // the same instruction is usually reached by many paths, but is only reported once
+    let mut findings = BTreeMap::new();
+    audit_path(vm_trace, Path::default(), caller, &tainted_targets, &mut findings);
+
+    findings
+        .into_iter()
+        .map(|((check, instruction), (severity, description))| Finding {
+            severity,
+            check,
+            selector: selector.to_string(),
+            function: None,
+            instruction,
+            description,
+        })
+        .collect()
+}
+
+// audit the operations of a branch of the trace, then each of its children
+fn audit_path(
+    vm_trace: &VMTrace,
+    mut path: Path,
+    caller: &[u8],
+    tainted_targets: &HashSet<u128>,
+    findings: &mut BTreeMap<(Check, u128), (Severity, String)>,
+) {
+    for (index, operation) in vm_trace.operations.iter().enumerate() {
+        path.guard.step(operation, caller);
+
+        let instruction = &operation.last_instruction;
+        let word = |index: usize| instruction.inputs.get(index).copied().unwrap_or_default();
+
+        match instruction.opcode {
+            // JUMPI
+            0x57 => {
+                let Some(condition) = instruction.input_operations.get(1) else { continue };
+
+                // comparing tx.origin with msg.sender only rejects calls from contracts
+                if condition.any(&|operation| operation.opcode.code == 0x32) &&
+                    !condition.any(&|operation| operation.opcode.code == 0x33)
+                {
+                    report(
+                        findings,
+                        Check::TxOriginAuth,
+                        instruction.instruction,
+                        Severity::Medium,
+                        format!(
+                            "branches on tx.origin in `{}`, which a contract the origin calls can satisfy.",
+                            condition.solidify().cleanup()
+                        ),
+                    );
+                }
+            }
+
+            // SSTORE and TSTORE
+            0x55 | 0x5d => {
+                let write = (instruction.opcode, word(0));
+                for call in &mut path.calls {
+                    call.writes_after.insert(write);
+                }
+                path.writes.insert(write);
+            }
+
+            // CALL, CALLCODE, DELEGATECALL, and STATICCALL
+            0xf1 | 0xf2 | 0xf4 | 0xfa => {
+                let Some(target) = instruction.input_operations.get(1) else { continue };
+                let name = instruction.opcode_details.as_ref().map(|details| details.name);
+                let name = name.unwrap_or_default();
+
+                // calls to constant addresses, such as precompiles, are trusted
+                let constant = (0x5f..=0x7f).contains(&target.opcode.code);
+                if constant && !word(1).is_zero() && word(1) <= U256::from(10) {
+                    continue
+                }
+                let delegates = [0xf2, 0xf4].contains(&instruction.opcode);
+
+                if tainted_targets.contains(&instruction.instruction) && !path.guard.checked() {
+                    if instruction.opcode != 0xfa {
+                        report(
+                            findings,
+                            Check::ArbitraryCall,
+                            instruction.instruction,
+                            if delegates { Severity::High } else { Severity::Medium },
+                            format!(
+                                "{name} to `{}`, an address taken from calldata, without checking msg.sender.",
+                                target.solidify().cleanup()
+                            ),
+                        );
+                    }
+                } else if delegates &&
+                    !path.guard.checked() &&
+                    !constant &&
+                    !target.any(&|operation| operation.opcode.code == 0x54)
+                {
+                    // proxies delegate to an implementation read from storage, which is expected
+                    report(
+                        findings,
+                        Check::UnprotectedDelegatecall,
+                        instruction.instruction,
+                        Severity::High,
+                        format!(
+                            "{name} to `{}` without checking msg.sender.",
+                            target.solidify().cleanup()
+                        ),
+                    );
+                }
+
+                if instruction.opcode != 0xfa {
+                    let checked = instruction
+                        .output_operations
+                        .first()
+                        .is_none_or(|success| is_used(vm_trace, index + 1, success));
+                    if !checked {
+                        report(
+                            findings,
+                            Check::UncheckedCall,
+                            instruction.instruction,
+                            Severity::Medium,
+                            format!(
+                                "the success of the {name} to `{}` is never checked.",
+                                target.solidify().cleanup()
+                            ),
+                        );
+                    }
+                }
+
+                // calls forwarding only the stipend can't re-enter the contract
+                if instruction.opcode == 0xf1 && word(0) > U256::from(CALL_STIPEND) {
+                    path.calls.push(PathCall {
+                        instruction: instruction.instruction,
+                        writes_before: path.writes.clone(),
+                        writes_after: HashSet::new(),
+                    });
+                }
+            }
+
+            // SELFDESTRUCT
+            0xff if !path.guard.checked() => {
+                report(
+                    findings,
+                    Check::UnprotectedSelfdestruct,
+                    instruction.instruction,
+                    Severity::High,
+                    String::from("SELFDESTRUCT is reachable without checking msg.sender."),
+                );
+            }
+
+            _ => {}
+        }
+    }
+
+    if !vm_trace.children.is_empty() {
+        for child in &vm_trace.children {
+            audit_path(child, path.clone(), caller, tainted_targets, findings);
+        }
+        return
+    }
+
+    // the writes of paths which revert are undone, so they can't be re-entered
+    if reverts(vm_trace) {
+        return
+    }
+
+    // a lock is a slot written both before and after the call, such as OpenZeppelin's
+    // `nonReentrant` status
+    for call in &path.calls {
+        let locked = call.writes_before.intersection(&call.writes_after).next().is_some();
+        let writes_storage = call.writes_after.iter().any(|(opcode, _)| *opcode == 0x55);
+        if !locked && writes_storage {
+            report(
+                findings,
+                Check::MissingReentrancyGuard,
+                call.instruction,
+                Severity::Medium,
+                String::from(
+                    "storage is written after this external call, without a reentrancy lock held across it.",
+                ),
+            );
+        }
+    }
+}
+
+// record a finding, unless the check was already reported at the instruction by another path
+fn report(
+    findings: &mut BTreeMap<(Check, u128), (Severity, String)>,
+    check: Check,
+    instruction: u128,
+    severity: Severity,
+    description: String,
+) {
+    findings.entry((check, instruction)).or_insert((severity, description));
+}
+
+// whether an instruction after `start`, on any path, uses the given operation's result, other
+// than by discarding it with POP
+fn is_used(vm_trace: &VMTrace, start: usize, operation: &WrappedOpcode) -> bool {
+    vm_trace.operations.iter().skip(start).any(|state| {
+        state.last_instruction.opcode != 0x50 &&
+            state
+                .last_instruction
+                .input_operations
+                .iter()
+                .any(|input| input.any(&|input| input == operation))
+    }) || vm_trace.children.iter().any(|child| is_used(child, 0, operation))
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::{
+        core::{taint::TaintTracker, vm::VM},
+        ext::exec::ExecutionLimits,
+    };
+
+    use super::*;
+
+    // audit bytecode as though it were a single function
+    fn audit_bytecode(bytecode: &str) -> Vec<(Check, u128)> {
+        let mut vm = VM::new(
+            bytecode.to_string(),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        );
+        let taint = TaintTracker::default();
+        vm.taint = Some(taint.clone());
+        let (vm_trace, ..) = vm.symbolic_exec(&ExecutionLimits::default());
+
+        audit_trace(&vm_trace, "00000000", &vm.caller, &taint.sinks())
+            .into_iter()
+            .map(|finding| (finding.check, finding.instruction))
+            .collect()
+    }
+
+    #[test]
+    fn test_unprotected_selfdestruct() {
+        // selfdestruct(msg.sender)
+        assert_eq!(audit_bytecode("33ff"), vec![(Check::UnprotectedSelfdestruct, 2)]);
+
+        // if (msg.sender == storage[0]) selfdestruct(msg.sender)
+        assert_eq!(audit_bytecode("3360005414600957005b33ff"), vec![]);
+
+        // if (storage[keccak256(msg.sender)] != 0) selfdestruct(msg.sender)
+        assert_eq!(audit_bytecode("33600052602060002054600e57005b33ff"), vec![]);
+    }
+
+    #[test]
+    fn test_tx_origin_auth() {
+        // if (tx.origin == storage[0]) selfdestruct(msg.sender)
+        assert_eq!(
+            audit_bytecode("3260005414600957005b33ff"),
+            vec![(Check::UnprotectedSelfdestruct, 12), (Check::TxOriginAuth, 8)]
+        );
+    }
+
+    #[test]
+    fn test_unchecked_call() {
+        // the success of the call is popped
+        assert_eq!(
+            audit_bytecode("600060006000600060006100aa5af15000"),
+            vec![(Check::UncheckedCall, 15)]
+        );
+
+        // the call reverts if it fails
+        assert_eq!(audit_bytecode("600060006000600060006100aa5af1601357fe5b00"), vec![]);
+    }
+
+    #[test]
+    fn test_arbitrary_delegatecall() {
+        // delegatecall(gas(), calldataload(4), 0, 0, 0, 0)
+        assert_eq!(
+            audit_bytecode("60006000600060006004355af4601157fe5b00"),
+            vec![(Check::ArbitraryCall, 13)]
+        );
+    }
+
+    #[test]
+    fn test_missing_reentrancy_guard() {
+        // storage[0] = 1 after the call
+        assert_eq!(
+            audit_bytecode("600060006000600060006100aa5af1601357fe5b600160005500"),
+            vec![(Check::MissingReentrancyGuard, 15)]
+        );
+
+        // storage[1] = 1 after the call, while storage[0] is held as a lock
+        assert_eq!(
+            audit_bytecode(
+                "6002600055600060006000600060006100aa5af1601857fe5b6001600155600160005500"
+            ),
+            vec![]
+        );
+    }
+}
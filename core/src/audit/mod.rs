@@ -0,0 +1,308 @@
+pub mod access;
+pub mod heuristics;
+
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        compiler::detect_compiler,
+        eof::is_eof,
+        evm::{
+            core::{hardfork::Hardfork, taint::TaintTracker, vm::VM},
+            ext::exec::ExecutionLimits,
+        },
+        selectors::{find_function_selectors, resolve_selectors},
+        signatures::{score_signature, ResolvedFunction},
+    },
+    utils::{
+        io::{logging::*, progress::spinner},
+        strings::decode_hex,
+    },
+};
+
+use serde::Serialize;
+
+use crate::{
+    audit::{
+        access::{analyze_access, Access, FunctionAccess},
+        heuristics::{audit_trace, Finding},
+    },
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    utils::get_contract_bytecode,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Flag likely vulnerabilities in EVM bytecode, such as unprotected selfdestructs and unchecked calls",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall audit <TARGET> [OPTIONS]"
+)]
+pub struct AuditArgs {
+    /// The target to audit, either a file, bytecode, contract address, or `-` to read bytecode
+    /// from stdin.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// Limits on how much of each function symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl AuditArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            limits: Some(ExecutionLimits::default()),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// The result of an audit: who can call each function, and the likely vulnerabilities found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditReport {
+    // the access control of each function, ordered by selector
+    pub functions: Vec<FunctionAccess>,
+
+    // the findings, ordered by severity, then by selector and instruction
+    pub findings: Vec<Finding>,
+}
+
+impl Display for AuditReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "access control:")?;
+        if self.functions.is_empty() {
+            writeln!(f, "  no functions found.")?;
+        }
+        for function in &self.functions {
+            writeln!(f, "  {function}")?;
+        }
+
+        writeln!(f, "\nfindings:")?;
+        if self.findings.is_empty() {
+            writeln!(f, "  no potential vulnerabilities found.")?;
+        }
+        for finding in &self.findings {
+            writeln!(f, "  {finding}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The main entry point for the audit module. Will symbolically execute each function found in
+/// the target bytecode, determine who can call it, and flag the patterns in its execution tree
+/// which are likely to be vulnerabilities.
+pub async fn audit(args: AuditArgs) -> Result<AuditReport, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, mut trace) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // truncate target for prettier display
+    let mut shortened_target = args.target.clone();
+    if shortened_target.len() > 66 {
+        shortened_target = shortened_target.chars().take(66).collect::<String>() +
+            "..." +
+            &shortened_target.chars().skip(shortened_target.len() - 16).collect::<String>();
+    }
+
+    // add the call to the trace
+    let audit_call = trace.add_call(
+        0,
+        line!(),
+        "heimdall".to_string(),
+        "audit".to_string(),
+        vec![shortened_target.clone()],
+        "()".to_string(),
+    );
+
+    // fetch bytecode
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // symbolic execution only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by audit. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
+    }
+
+    // disassemble the bytecode
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        output: String::new(),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: Hardfork::default(),
+    })
+    .await?;
+    trace.add_call(
+        audit_call,
+        line!(),
+        "heimdall".to_string(),
+        "disassemble".to_string(),
+        vec![format!("{} bytes", contract_bytecode.len() / 2usize)],
+        "()".to_string(),
+    );
+
+    // perform versioning and compiler heuristics
+    let (compiler, version) = detect_compiler(&contract_bytecode);
+    trace.add_call(
+        audit_call,
+        line!(),
+        "heimdall".to_string(),
+        "detect_compiler".to_string(),
+        vec![format!("{} bytes", contract_bytecode.len() / 2usize)],
+        format!("({compiler}, {version})"),
+    );
+
+    if compiler == "solc" {
+        logger.debug(&format!("detected compiler {compiler} {version}."));
+    } else {
+        logger
+            .warn(&format!("detected compiler {compiler} {version} is not supported by heimdall."));
+    }
+
+    // create a new EVM instance
+    let evm = VM::new(
+        contract_bytecode.clone(),
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    // find and resolve all selectors in the bytecode
+    let selectors = find_function_selectors(&evm, &disassembled_bytecode);
+    let mut resolved_selectors: HashMap<String, Vec<ResolvedFunction>> = HashMap::new();
+    if !args.skip_resolving {
+        resolved_selectors =
+            resolve_selectors::<ResolvedFunction>(selectors.keys().cloned().collect()).await;
+        logger.info(&format!(
+            "resolved {} possible functions from {} detected selectors.",
+            resolved_selectors.len(),
+            selectors.len()
+        ));
+    } else {
+        logger.info(&format!("found {} possible function selectors.", selectors.len()));
+    }
+
+    logger.info(&format!("performing symbolic execution on '{shortened_target}' ."));
+
+    // create a new progress bar
+    let progress = spinner(logger.info_spinner());
+
+    // sort selectors so findings are reported in a deterministic order
+    let mut selectors = selectors.into_iter().collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    // symbolically execute each function, tracking which calldata reaches each sink
+    let mut functions = Vec::new();
+    let mut findings = Vec::new();
+    for (selector, entry_point) in selectors {
+        progress.set_message(format!("auditing '0x{selector}'"));
+
+        let mut function_evm = evm.clone();
+        let taint = TaintTracker::default();
+        function_evm.taint = Some(taint.clone());
+        let (map, jumpdest_count, limit_reached) =
+            &function_evm.symbolic_exec_selector(&selector, entry_point, &args.limits);
+        if let Some(limit) = limit_reached {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' stopped early after reaching the {limit}, so it may have findings which weren't reported."
+            ));
+        }
+        for fault in map.faults() {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' faulted: {}",
+                Error::from(fault)
+            ));
+        }
+        trace.add_info(
+            audit_call,
+            line!(),
+            &format!("traced and executed {jumpdest_count} possible paths for '0x{selector}'."),
+        );
+
+        let function = resolved_selectors.get(&selector).and_then(|resolved_functions| {
+            resolved_functions
+                .iter()
+                .max_by_key(|resolved_function| score_signature(&resolved_function.signature))
+                .map(|resolved_function| resolved_function.signature.clone())
+        });
+        let (access, gates) = analyze_access(map, &evm.caller);
+        functions.push(FunctionAccess {
+            selector: selector.clone(),
+            function: function.clone(),
+            access,
+            gates,
+        });
+        findings.extend(
+            audit_trace(map, &selector, &evm.caller, &taint.sinks())
+                .into_iter()
+                .map(|finding| Finding { function: function.clone(), ..finding }),
+        );
+    }
+    progress.finish_and_clear();
+
+    findings.sort_by(|a, b| {
+        (a.severity, &a.selector, a.instruction).cmp(&(b.severity, &b.selector, b.instruction))
+    });
+
+    logger.info("symbolic execution completed.");
+    logger.info(&format!(
+        "found {} open functions and {} potential vulnerabilities.",
+        functions.iter().filter(|function| function.access == Access::Open).count(),
+        findings.len()
+    ));
+    logger.debug(&format!("audit completed in {:?}.", now.elapsed()));
+    trace.display();
+
+    Ok(AuditReport { functions, findings })
+}
@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+
+use ethers::types::U256;
+use heimdall_common::{
+    ether::{evm::ext::exec::VMTrace, lexers::cleanup::Cleanup},
+    utils::strings::encode_hex,
+};
+
+/// An external call made by a function, along with the address it targets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExternalCall {
+    // the opcode used to make the call, i.e. CALL, CALLCODE, DELEGATECALL, or STATICCALL
+    pub opcode: String,
+
+    // the target address of the call. if the address is a constant, this will be the address
+    // itself. otherwise, this will be the solidified expression which produces the address.
+    pub target: String,
+
+    // whether or not the target could be resolved to a constant address
+    pub constant: bool,
+}
+
+/// All calls and events found while analyzing a function's symbolic execution trace.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionCalls {
+    // every instruction executed by the function
+    pub instructions: HashSet<u128>,
+
+    // every JUMP made by the function, as the instruction of the JUMP and of its destination
+    pub jumps: HashSet<(u128, u128)>,
+
+    // every external call made by the function
+    pub external_calls: HashSet<ExternalCall>,
+
+    // the topic0 of every event emitted by the function
+    pub events: HashSet<U256>,
+}
+
+/// Analyze a function's [`VMTrace`], collecting every executed instruction, jump, external call,
+/// and emitted event into the given [`FunctionCalls`].
+pub fn analyze_calls(vm_trace: &VMTrace, calls: &mut FunctionCalls) {
+    for operation in &vm_trace.operations {
+        let instruction = &operation.last_instruction;
+        calls.instructions.insert(instruction.instruction);
+
+        let opcode_name = match &instruction.opcode_details {
+            Some(opcode) => opcode.name,
+            None => continue,
+        };
+
+        if instruction.opcode == 0x56 {
+            // instructions are counted from 1, so the destination's instruction is one past it
+            if let Some(destination) =
+                instruction.inputs.first().and_then(|pc| u128::try_from(*pc).ok())
+            {
+                calls.jumps.insert((instruction.instruction, destination.saturating_add(1)));
+            }
+        } else if ["CALL", "CALLCODE", "DELEGATECALL", "STATICCALL"].contains(&opcode_name) {
+            let address_operation = match instruction.input_operations.get(1) {
+                Some(operation) => operation,
+                None => continue,
+            };
+
+            // if the address was pushed, even if it was then masked or shifted, it's a constant
+            let constant = !address_operation.any(&|operation| {
+                !(0x5f..=0x7f).contains(&operation.opcode.code) &&
+                    !(0x16..=0x1d).contains(&operation.opcode.code)
+            });
+            let target = if constant {
+                let mut address = [0u8; 32];
+                instruction.inputs[1].to_big_endian(&mut address);
+                format!("0x{}", encode_hex(address[12..].to_vec()))
+            } else {
+                address_operation.solidify().cleanup()
+            };
+
+            calls.external_calls.insert(ExternalCall {
+                opcode: opcode_name.to_string(),
+                target,
+                constant,
+            });
+        } else if (0xA0..=0xA4).contains(&instruction.opcode) {
+            // LOG0 has no topics, so there is no event selector to record
+            if let Some(topic) = operation.events.last().and_then(|event| event.topics.first()) {
+                calls.events.insert(*topic);
+            }
+        }
+    }
+
+    for child in &vm_trace.children {
+        analyze_calls(child, calls);
+    }
+}
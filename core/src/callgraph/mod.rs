@@ -0,0 +1,346 @@
+pub mod analyze;
+pub mod output;
+
+use std::collections::HashMap;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::U256;
+use heimdall_common::{
+    ether::{
+        compiler::detect_compiler,
+        eof::is_eof,
+        evm::{
+            core::{hardfork::Hardfork, vm::VM},
+            ext::exec::ExecutionLimits,
+        },
+        selectors::{find_function_selectors, resolve_selectors},
+        signatures::{score_signature, ResolvedFunction, ResolvedLog},
+    },
+    utils::{
+        io::{logging::*, progress::spinner},
+        strings::{decode_hex, encode_hex_reduced},
+    },
+};
+use petgraph::{graph::NodeIndex, Graph};
+
+use crate::{
+    callgraph::analyze::{analyze_calls, FunctionCalls},
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    utils::get_contract_bytecode,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Generate a function-level call graph for EVM bytecode",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall callgraph <TARGET> [OPTIONS]"
+)]
+pub struct CallGraphArgs {
+    /// The target to generate a call graph for, either a file, bytecode, contract address,
+    /// or `-` to read bytecode from stdin.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors and event signatures.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// Limits on how much of each function symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl CallGraphArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            limits: Some(ExecutionLimits::default()),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// The main entry point for the callgraph module. Will symbolically execute each function found
+/// in the target bytecode, and build a graph of the internal calls, external calls, and events
+/// made by each function.
+pub async fn callgraph(args: CallGraphArgs) -> Result<Graph<String, String>, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, mut trace) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // truncate target for prettier display
+    let mut shortened_target = args.target.clone();
+    if shortened_target.len() > 66 {
+        shortened_target = shortened_target.chars().take(66).collect::<String>() +
+            "..." +
+            &shortened_target.chars().skip(shortened_target.len() - 16).collect::<String>();
+    }
+
+    // add the call to the trace
+    let callgraph_call = trace.add_call(
+        0,
+        line!(),
+        "heimdall".to_string(),
+        "callgraph".to_string(),
+        vec![shortened_target.clone()],
+        "()".to_string(),
+    );
+
+    // fetch bytecode
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // symbolic execution only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by callgraph. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
+    }
+
+    // disassemble the bytecode
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        output: String::new(),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: Hardfork::default(),
+    })
+    .await?;
+    trace.add_call(
+        callgraph_call,
+        line!(),
+        "heimdall".to_string(),
+        "disassemble".to_string(),
+        vec![format!("{} bytes", contract_bytecode.len() / 2usize)],
+        "()".to_string(),
+    );
+
+    // perform versioning and compiler heuristics
+    let (compiler, version) = detect_compiler(&contract_bytecode);
+    trace.add_call(
+        callgraph_call,
+        line!(),
+        "heimdall".to_string(),
+        "detect_compiler".to_string(),
+        vec![format!("{} bytes", contract_bytecode.len() / 2usize)],
+        format!("({compiler}, {version})"),
+    );
+
+    if compiler == "solc" {
+        logger.debug(&format!("detected compiler {compiler} {version}."));
+    } else {
+        logger
+            .warn(&format!("detected compiler {compiler} {version} is not supported by heimdall."));
+    }
+
+    // create a new EVM instance
+    let evm = VM::new(
+        contract_bytecode.clone(),
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    // find and resolve all selectors in the bytecode
+    let selectors = find_function_selectors(&evm, &disassembled_bytecode);
+    let mut resolved_selectors: HashMap<String, Vec<ResolvedFunction>> = HashMap::new();
+    if !args.skip_resolving {
+        resolved_selectors =
+            resolve_selectors::<ResolvedFunction>(selectors.keys().cloned().collect()).await;
+        logger.info(&format!(
+            "resolved {} possible functions from {} detected selectors.",
+            resolved_selectors.len(),
+            selectors.len()
+        ));
+    } else {
+        logger.info(&format!("found {} possible function selectors.", selectors.len()));
+    }
+
+    logger.info(&format!("performing symbolic execution on '{shortened_target}' ."));
+
+    // create a new progress bar
+    let progress = spinner(logger.info_spinner());
+
+    // sort selectors so the graph is built in a deterministic order
+    let mut selectors = selectors.into_iter().collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    // symbolically execute each function, collecting its calls and events
+    let mut function_calls: Vec<(String, FunctionCalls)> = Vec::new();
+    for (selector, entry_point) in selectors {
+        progress.set_message(format!("executing '0x{selector}'"));
+
+        let (map, jumpdest_count, limit_reached) =
+            &evm.clone().symbolic_exec_selector(&selector, entry_point, &args.limits);
+        if let Some(limit) = limit_reached {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' stopped early after reaching the {limit}."
+            ));
+        }
+        for fault in map.faults() {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' faulted: {}",
+                Error::from(fault)
+            ));
+        }
+        trace.add_info(
+            callgraph_call,
+            line!(),
+            &format!("traced and executed {jumpdest_count} possible paths for '0x{selector}'."),
+        );
+
+        let mut calls = FunctionCalls::default();
+        analyze_calls(map, &mut calls);
+        function_calls.push((selector, calls));
+    }
+    progress.finish_and_clear();
+
+    // resolve event signatures
+    let mut resolved_events: HashMap<String, Vec<ResolvedLog>> = HashMap::new();
+    if !args.skip_resolving {
+        let mut event_selectors = function_calls
+            .iter()
+            .flat_map(|(_, calls)| calls.events.iter())
+            .map(|topic| encode_hex_reduced(*topic).replacen("0x", "", 1))
+            .collect::<Vec<String>>();
+        event_selectors.sort();
+        event_selectors.dedup();
+        resolved_events = resolve_selectors::<ResolvedLog>(event_selectors).await;
+    }
+
+    // build the call graph
+    let mut call_graph = Graph::<String, String>::new();
+    let mut nodes: HashMap<String, NodeIndex<u32>> = HashMap::new();
+    let mut get_or_add_node = |graph: &mut Graph<String, String>, label: String| {
+        *nodes.entry(label.clone()).or_insert_with(|| graph.add_node(label))
+    };
+
+    // add a node for each function
+    let function_nodes = function_calls
+        .iter()
+        .map(|(selector, ..)| {
+            let label = match resolved_selectors.get(selector) {
+                Some(resolved_functions) => match resolved_functions
+                    .iter()
+                    .max_by_key(|resolved_function| score_signature(&resolved_function.signature))
+                {
+                    Some(resolved_function) => {
+                        format!("function {}", resolved_function.signature)
+                    }
+                    None => format!("function Unresolved_{selector}()"),
+                },
+                None => format!("function Unresolved_{selector}()"),
+            };
+            get_or_add_node(&mut call_graph, label)
+        })
+        .collect::<Vec<NodeIndex<u32>>>();
+
+    // count how many functions reach each instruction, so code shared between functions can be
+    // told apart from code belonging to a single function
+    let mut reached_by: HashMap<u128, usize> = HashMap::new();
+    for (_, calls) in &function_calls {
+        for instruction in &calls.instructions {
+            *reached_by.entry(*instruction).or_default() += 1;
+        }
+    }
+    let shared = |instruction: &u128| reached_by.get(instruction).copied().unwrap_or_default() > 1;
+
+    for (i, (_, calls)) in function_calls.iter().enumerate() {
+        // a function makes an internal call when it jumps from its own code into the body of an
+        // internal function, which is code reached from several functions
+        let mut internal_calls = calls
+            .jumps
+            .iter()
+            .filter(|(instruction, destination)| !shared(instruction) && shared(destination))
+            .map(|(_, destination)| *destination)
+            .collect::<Vec<u128>>();
+        internal_calls.sort();
+        internal_calls.dedup();
+        for destination in internal_calls {
+            let internal_node = get_or_add_node(
+                &mut call_graph,
+                format!("internal Internal_{:x}()", destination - 1),
+            );
+            call_graph.add_edge(function_nodes[i], internal_node, "internal".to_string());
+        }
+
+        // add edges for each external call
+        let mut external_calls = calls.external_calls.iter().collect::<Vec<_>>();
+        external_calls.sort_by(|a, b| (&a.target, &a.opcode).cmp(&(&b.target, &b.opcode)));
+        for external_call in external_calls {
+            let target_node = get_or_add_node(&mut call_graph, external_call.target.clone());
+            call_graph.add_edge(function_nodes[i], target_node, external_call.opcode.clone());
+        }
+
+        // add edges for each emitted event
+        let mut events = calls.events.iter().collect::<Vec<&U256>>();
+        events.sort();
+        for topic in events {
+            let event_selector = encode_hex_reduced(*topic).replacen("0x", "", 1);
+            let label = match resolved_events.get(&event_selector).and_then(|resolved_events| {
+                resolved_events
+                    .iter()
+                    .max_by_key(|resolved_event| score_signature(&resolved_event.signature))
+            }) {
+                Some(resolved_event) => format!("event {}", resolved_event.signature),
+                None => {
+                    format!("event Event_{}()", &event_selector[0..8.min(event_selector.len())])
+                }
+            };
+
+            let event_node = get_or_add_node(&mut call_graph, label);
+            call_graph.add_edge(function_nodes[i], event_node, "emits".to_string());
+        }
+    }
+
+    logger.info("symbolic execution completed.");
+    logger.info(&format!(
+        "built call graph with {} nodes and {} edges.",
+        call_graph.node_count(),
+        call_graph.edge_count()
+    ));
+    logger.debug(&format!("Call graph generated in {:?}.", now.elapsed()));
+    trace.display();
+
+    Ok(call_graph)
+}
@@ -0,0 +1,34 @@
+use petgraph::{dot::Dot, graph::Graph, visit::EdgeRef};
+use serde_json::{json, Value};
+
+/// Write the generated call graph to a file in the `dot` graphviz format.
+pub fn build_callgraph(call_graph: &Graph<String, String>) -> String {
+    let output = format!("{}", Dot::with_config(&call_graph, &[]));
+
+    // find regex matches and replace
+    output.replace(
+        "digraph {",
+        "digraph G {\n    node [shape=box, style=\"rounded\", fontname=\"Helvetica\"];\n    edge [fontname=\"Helvetica\"];"
+    )
+}
+
+/// Build the generated call graph as JSON, with each function as a node and each call as an edge
+/// between the indices of its caller and callee.
+pub fn build_callgraph_json(call_graph: &Graph<String, String>) -> Value {
+    json!({
+        "nodes": call_graph
+            .node_indices()
+            .map(|node| json!({ "id": node.index(), "name": call_graph[node] }))
+            .collect::<Vec<_>>(),
+        "edges": call_graph
+            .edge_references()
+            .map(|edge| {
+                json!({
+                    "source": edge.source().index(),
+                    "target": edge.target().index(),
+                    "label": edge.weight(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
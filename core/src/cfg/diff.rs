@@ -0,0 +1,153 @@
+use std::fmt::{Display, Formatter};
+
+use heimdall_common::{
+    ether::eof::is_eof,
+    utils::{io::logging::*, strings::decode_hex},
+};
+use petgraph::{graph::NodeIndex, Graph};
+
+use crate::{
+    analysis::{
+        blocks::{find_basic_blocks, find_block_edges},
+        diff::{diff_blocks, DiffStatus},
+    },
+    error::Error,
+    utils::get_contract_bytecode,
+};
+
+use super::CFGArgs;
+
+/// A single basic block within a CFG diff.
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    // whether the block was added, removed, changed, or left unchanged
+    pub status: DiffStatus,
+
+    // the assembly of the block. changed blocks will use the assembly of the new version
+    pub assembly: String,
+}
+
+impl Display for DiffNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.assembly)
+    }
+}
+
+/// Build a CFG which contains the basic blocks of both the old and new bytecode. Blocks are aligned
+/// by their normalized instructions, and each node is marked with its [`DiffStatus`].
+pub fn build_diff_cfg(old_bytecode: &[u8], new_bytecode: &[u8]) -> Graph<DiffNode, String> {
+    let old_blocks = find_basic_blocks(old_bytecode);
+    let new_blocks = find_basic_blocks(new_bytecode);
+
+    let mut diff_cfg = Graph::<DiffNode, String>::new();
+    let mut old_nodes: Vec<Option<NodeIndex<u32>>> = vec![None; old_blocks.len()];
+    let mut new_nodes: Vec<Option<NodeIndex<u32>>> = vec![None; new_blocks.len()];
+
+    // add a node for each aligned pair of blocks
+    for block_diff in diff_blocks(&old_blocks, &new_blocks) {
+        let assembly = match (block_diff.new, block_diff.old) {
+            (Some(index), _) => new_blocks[index].assembly.clone(),
+            (None, Some(index)) => old_blocks[index].assembly.clone(),
+            (None, None) => continue,
+        };
+
+        let node = diff_cfg.add_node(DiffNode { status: block_diff.status, assembly });
+        if let Some(index) = block_diff.old {
+            old_nodes[index] = Some(node);
+        }
+        if let Some(index) = block_diff.new {
+            new_nodes[index] = Some(node);
+        }
+    }
+
+    // add the edges of both versions, skipping duplicates
+    for (blocks, nodes) in [(&old_blocks, &old_nodes), (&new_blocks, &new_nodes)] {
+        for (from, to, jump_taken) in find_block_edges(blocks) {
+            if let (Some(from), Some(to)) = (nodes[from], nodes[to]) {
+                if !diff_cfg.contains_edge(from, to) {
+                    diff_cfg.add_edge(from, to, jump_taken.to_string());
+                }
+            }
+        }
+    }
+
+    diff_cfg
+}
+
+/// Generate a CFG diff between `args.target` and `args.diff`, where `args.target` is treated as
+/// the old version of the contract.
+pub async fn cfg_diff(args: CFGArgs) -> Result<Graph<DiffNode, String>, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // make sure the output format is supported
+    if !["dot", "json", "html"].contains(&args.format.as_str()) {
+        return Err(Error::ParseError(format!("unsupported output format '{}' .", &args.format)))
+    }
+
+    // fetch the bytecode of both versions
+    let old_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+    let new_bytecode = get_contract_bytecode(&args.diff, &args.rpc_url, &logger).await?;
+
+    let (old_bytecode, new_bytecode) = (decode_hex(&old_bytecode)?, decode_hex(&new_bytecode)?);
+
+    // basic block alignment relies on legacy JUMPDESTs, which EOF code sections don't have
+    if is_eof(&old_bytecode) || is_eof(&new_bytecode) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by `cfg --diff`.",
+        )))
+    }
+
+    let diff_cfg = build_diff_cfg(&old_bytecode, &new_bytecode);
+
+    // summarize the diff
+    let count =
+        |status: DiffStatus| diff_cfg.node_weights().filter(|node| node.status == status).count();
+    logger.info(&format!(
+        "aligned {} basic blocks: {} unchanged, {} changed, {} added, {} removed.",
+        diff_cfg.node_count(),
+        count(DiffStatus::Unchanged),
+        count(DiffStatus::Changed),
+        count(DiffStatus::Added),
+        count(DiffStatus::Removed)
+    ));
+    logger.debug(&format!("Control flow graph diff generated in {:?}.", now.elapsed()));
+
+    Ok(diff_cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analysis::diff::DiffStatus, cfg::diff::*};
+
+    #[test]
+    fn test_build_diff_cfg() {
+        // PUSH1 0x01 PUSH1 0x06 JUMPI STOP JUMPDEST STOP
+        let old = [0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x5b, 0x00];
+
+        // PUSH1 0x01 PUSH1 0x06 JUMPI STOP JUMPDEST CALLER SELFDESTRUCT
+        let new = [0x60, 0x01, 0x60, 0x06, 0x57, 0x00, 0x5b, 0x33, 0xff];
+
+        let diff_cfg = build_diff_cfg(&old, &new);
+        let statuses = diff_cfg.node_weights().map(|node| node.status).collect::<Vec<_>>();
+
+        assert_eq!(
+            statuses,
+            vec![
+                DiffStatus::Unchanged,
+                DiffStatus::Unchanged,
+                DiffStatus::Added,
+                DiffStatus::Removed
+            ]
+        );
+
+        // the JUMPI targets the removed block in the old version and the added block in the new
+        // version, and both versions fall through to the same STOP
+        assert_eq!(diff_cfg.edge_count(), 3);
+    }
+}
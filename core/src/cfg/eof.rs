@@ -0,0 +1,139 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ethers::types::U256;
+use heimdall_common::{
+    ether::eof::{decode_eof_instructions, is_eof_terminator, EofContainer, EofError},
+    utils::strings::encode_hex_reduced,
+};
+use petgraph::{graph::NodeIndex, Graph};
+
+/// Build a control flow graph of an EOF container. Since EOF code only uses static relative
+/// jumps, the graph is built directly from the bytecode rather than through symbolic execution.
+///
+/// Each code section's first block is connected to the blocks which CALLF or JUMPF into it, and
+/// node labels use the same format as legacy CFGs, with instructions numbered by their offset
+/// within the container.
+pub fn build_eof_cfg(container: &EofContainer) -> Result<Graph<String, String>, EofError> {
+    let mut contract_cfg = Graph::<String, String>::new();
+
+    // maps (section, offset of the block's first instruction) to the block's node
+    let mut block_nodes: HashMap<(usize, usize), NodeIndex<u32>> = HashMap::new();
+
+    // edges are added once every block exists, as (from, (section, offset), label)
+    let mut edges: Vec<(NodeIndex<u32>, (usize, usize), String)> = Vec::new();
+
+    for (section, code_section) in container.code_sections.iter().enumerate() {
+        let instructions = decode_eof_instructions(&code_section.code, section)?;
+
+        // blocks start at the beginning of the section, at every jump target, and after every
+        // instruction which ends a block
+        let mut leaders = BTreeSet::from([0usize]);
+        for (index, instruction) in instructions.iter().enumerate() {
+            leaders.extend(instruction.jump_targets().into_iter().map(|target| target as usize));
+            if is_eof_terminator(&instruction.opcode) {
+                if let Some(next) = instructions.get(index + 1) {
+                    leaders.insert(next.pc);
+                }
+            }
+        }
+
+        let mut block = String::new();
+        let mut block_start = 0;
+        for (index, instruction) in instructions.iter().enumerate() {
+            if leaders.contains(&instruction.pc) {
+                block_start = instruction.pc;
+            }
+
+            block.push_str(&format!(
+                "{} {} {}\n",
+                encode_hex_reduced(U256::from(code_section.offset + instruction.pc + 1)),
+                instruction.opcode.name,
+                if instruction.immediate.is_empty() {
+                    String::new()
+                } else {
+                    encode_hex_reduced(U256::from_big_endian(&instruction.immediate))
+                }
+            ));
+
+            // the block ends before the next leader, or at the end of the section
+            let next = instructions.get(index + 1);
+            if !next.map(|next| leaders.contains(&next.pc)).unwrap_or(true) {
+                // CALLF returns to the following instruction, so it doesn't end the block
+                if let Some(target) = instruction.section_target() {
+                    edges.push((
+                        NodeIndex::new(contract_cfg.node_count()),
+                        (target, 0),
+                        String::from("CALLF"),
+                    ));
+                }
+                continue
+            }
+
+            let node = contract_cfg.add_node(std::mem::take(&mut block));
+            block_nodes.insert((section, block_start), node);
+
+            for target in instruction.jump_targets() {
+                edges.push((node, (section, target as usize), String::from("true")));
+            }
+            match instruction.opcode.name {
+                "JUMPF" | "CALLF" => edges.push((
+                    node,
+                    (instruction.section_target().unwrap_or_default(), 0),
+                    String::from(instruction.opcode.name),
+                )),
+                _ => {}
+            }
+
+            // fall through to the next block, unless execution halts or jumps unconditionally
+            if let Some(next) = next {
+                if !matches!(
+                    instruction.opcode.name,
+                    "STOP" | "RETURN" | "REVERT" | "INVALID" | "RJUMP" | "RETF" | "JUMPF"
+                ) {
+                    edges.push((node, (section, next.pc), String::from("false")));
+                }
+            }
+        }
+    }
+
+    for (from, target, label) in edges {
+        if let Some(to) = block_nodes.get(&target) {
+            if !contract_cfg.contains_edge(from, *to) {
+                contract_cfg.add_edge(from, *to, label);
+            }
+        }
+    }
+
+    Ok(contract_cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::eof::EofContainer;
+
+    use crate::cfg::eof::*;
+
+    #[test]
+    fn test_build_eof_cfg() {
+        // section 0: PUSH0 RJUMPI +1 STOP CALLF 1 STOP
+        // section 1: RETF
+        let bytecode = [
+            0xef, 0x00, 0x01, 0x01, 0x00, 0x08, 0x02, 0x00, 0x02, 0x00, 0x09, 0x00, 0x01, 0x04,
+            0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x5f, 0xe1, 0x00,
+            0x01, 0x00, 0xe3, 0x00, 0x01, 0x00, 0xe4,
+        ];
+        let container = EofContainer::parse(&bytecode).unwrap();
+        let contract_cfg = build_eof_cfg(&container).unwrap();
+
+        // PUSH0 RJUMPI | STOP | CALLF STOP | RETF
+        assert_eq!(contract_cfg.node_count(), 4);
+        assert_eq!(contract_cfg[NodeIndex::new(0)], "0x1a PUSH0 \n0x1b RJUMPI 0x01\n");
+
+        let edges = contract_cfg
+            .raw_edges()
+            .iter()
+            .map(|edge| (edge.source().index(), edge.target().index(), edge.weight.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(edges, vec![(0, 2, "true"), (0, 1, "false"), (2, 3, "CALLF")]);
+    }
+}
@@ -1,10 +1,19 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
 
 use ethers::prelude::U256;
-use heimdall_common::{ether::evm::ext::exec::VMTrace, utils::strings::encode_hex_reduced};
+use heimdall_common::{
+    ether::{evm::ext::exec::VMTrace, metadata::strip_metadata},
+    utils::strings::encode_hex_reduced,
+};
 use petgraph::{matrix_graph::NodeIndex, Graph};
 
 use lazy_static::lazy_static;
+
+use crate::analysis::blocks::{find_basic_blocks, BasicBlock};
+
 lazy_static! {
     static ref INSTRUCTION_NODE_MAP: Mutex<HashMap<u128, NodeIndex<u32>>> =
         Mutex::new(HashMap::new());
@@ -96,3 +105,37 @@ pub fn build_cfg(
         );
     }
 }
+
+/// collect the instructions executed anywhere within the given [`VMTrace`].
+fn collect_executed_instructions(vm_trace: &VMTrace, executed: &mut HashSet<u128>) {
+    for operation in &vm_trace.operations {
+        executed.insert(operation.last_instruction.instruction);
+    }
+
+    for child in vm_trace.children.iter() {
+        collect_executed_instructions(child, executed);
+    }
+}
+
+/// add every basic block of the bytecode which was never reached by the symbolic execution
+/// [`VMTrace`] to the graph as a disconnected node, returning the blocks which were added. The
+/// compiler's metadata isn't code, so it's never added.
+pub fn add_unreachable_blocks(
+    vm_trace: &VMTrace,
+    contract_cfg: &mut Graph<String, String>,
+    bytecode: &[u8],
+) -> Vec<BasicBlock> {
+    let mut executed = HashSet::new();
+    collect_executed_instructions(vm_trace, &mut executed);
+
+    let unreachable_blocks = find_basic_blocks(strip_metadata(bytecode))
+        .into_iter()
+        .filter(|block| !executed.contains(&block.start))
+        .collect::<Vec<BasicBlock>>();
+
+    for block in &unreachable_blocks {
+        contract_cfg.add_node(block.assembly.clone());
+    }
+
+    unreachable_blocks
+}
@@ -1,23 +1,39 @@
+pub mod diff;
+pub mod eof;
 pub mod graph;
 pub mod output;
 use derive_builder::Builder;
-use heimdall_common::ether::{
-    compiler::detect_compiler, rpc::get_code, selectors::find_function_selectors,
+use ethers::types::U256;
+use heimdall_common::{
+    ether::{
+        compiler::detect_compiler,
+        eof::{is_eof, EofContainer},
+        selectors::find_function_selectors,
+    },
+    utils::io::progress::spinner,
 };
-use indicatif::ProgressBar;
-use std::{fs, time::Duration};
 
 use clap::{AppSettings, Parser};
 use heimdall_common::{
-    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
-    ether::evm::core::vm::VM,
-    utils::io::logging::*,
+    ether::evm::{
+        core::{hardfork::Hardfork, vm::VM},
+        ext::exec::ExecutionLimits,
+    },
+    utils::{
+        io::logging::*,
+        strings::{decode_hex, encode_hex_reduced},
+    },
 };
 use petgraph::Graph;
 
 use crate::{
-    cfg::graph::build_cfg,
+    cfg::{
+        eof::build_eof_cfg,
+        graph::{add_unreachable_blocks, build_cfg},
+    },
     disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    utils::get_contract_bytecode,
 };
 
 #[derive(Debug, Clone, Parser, Builder)]
@@ -28,7 +44,10 @@ use crate::{
     override_usage = "heimdall cfg <TARGET> [OPTIONS]"
 )]
 pub struct CFGArgs {
-    /// The target to generate a CFG for, either a file, bytecode, contract address, or ENS name.
+    /// The target to generate a CFG for, either a file, bytecode, contract address, ENS
+    /// name, or `-` to read bytecode from stdin. Files may be forge, hardhat, or solc artifacts,
+    /// whose deployed bytecode is used, with `artifact.json:Name` selecting one of several
+    /// contracts.
     #[clap(required = true)]
     pub target: String,
 
@@ -36,7 +55,8 @@ pub struct CFGArgs {
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 
-    /// The RPC provider to use for fetching target bytecode.
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
     #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
     pub rpc_url: String,
 
@@ -49,6 +69,23 @@ pub struct CFGArgs {
     #[clap(long = "color-edges", short)]
     pub color_edges: bool,
 
+    /// A second target to compare against the first, either a file, bytecode, or contract
+    /// address. When set, a diff of the two targets' basic blocks will be generated instead.
+    #[clap(long = "diff", default_value = "", hide_default_value = true)]
+    pub diff: String,
+
+    /// The format to write the graph in, either 'dot', 'json', or 'html'.
+    #[clap(long = "format", short = 'f', default_value = "dot")]
+    pub format: String,
+
+    /// The hardfork whose opcodes are valid during disassembly and symbolic execution.
+    #[clap(long = "evm-version", default_value = "cancun")]
+    pub evm_version: Hardfork,
+
+    /// Limits on how much of the contract symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
     /// The output directory to write the output to or 'print' to print to the console
     #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
     pub output: String,
@@ -62,6 +99,10 @@ impl CFGArgsBuilder {
             rpc_url: Some(String::new()),
             default: Some(true),
             color_edges: Some(false),
+            diff: Some(String::new()),
+            format: Some(String::from("dot")),
+            evm_version: Some(Hardfork::default()),
+            limits: Some(ExecutionLimits::default()),
             output: Some(String::new()),
         }
     }
@@ -69,7 +110,7 @@ impl CFGArgsBuilder {
 
 /// The main entry point for the CFG module. Will generate a control flow graph of the target
 /// bytecode, after performing symbolic execution and discovering all possible execution paths.
-pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::error::Error>> {
+pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Error> {
     use std::time::Instant;
     let now = Instant::now();
 
@@ -89,6 +130,11 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
         None => "SILENT",
     });
 
+    // make sure the output format is supported
+    if !["dot", "json", "html"].contains(&args.format.as_str()) {
+        return Err(Error::ParseError(format!("unsupported output format '{}' .", &args.format)))
+    }
+
     // truncate target for prettier display
     let mut shortened_target = args.target.clone();
     if shortened_target.len() > 66 {
@@ -108,34 +154,27 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     );
 
     // fetch bytecode
-    let contract_bytecode: String;
-    if ADDRESS_REGEX.is_match(&args.target).unwrap() {
-        // We are working with a contract address, so we need to fetch the bytecode from the RPC
-        // provider
-        contract_bytecode = get_code(&args.target, &args.rpc_url).await?;
-    } else if BYTECODE_REGEX.is_match(&args.target).unwrap() {
-        logger.debug_max("using provided bytecode for cfg generation");
-        contract_bytecode = args.target.replacen("0x", "", 1);
-    } else {
-        logger.debug_max("using provided file for cfg generation.");
-
-        // We are analyzing a file, so we need to read the bytecode from the file.
-        contract_bytecode = match fs::read_to_string(&args.target) {
-            Ok(contents) => {
-                let _contents = contents.replace('\n', "");
-                if BYTECODE_REGEX.is_match(&_contents).unwrap() && _contents.len() % 2 == 0 {
-                    _contents.replacen("0x", "", 1)
-                } else {
-                    logger
-                        .error(&format!("file '{}' doesn't contain valid bytecode.", &args.target));
-                    std::process::exit(1)
-                }
-            }
-            Err(_) => {
-                logger.error(&format!("failed to open file '{}' .", &args.target));
-                std::process::exit(1)
-            }
-        };
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // EOF containers only use static jumps, so their CFG can be built without symbolic execution
+    let bytecode = decode_hex(&contract_bytecode)?;
+    if is_eof(&bytecode) {
+        let container = EofContainer::parse(&bytecode)?;
+        logger.info(&format!(
+            "found EOF container with {} code sections.",
+            container.code_sections.len()
+        ));
+
+        let contract_cfg = build_eof_cfg(&container)?;
+        trace.add_info(
+            cfg_call,
+            line!(),
+            &format!("built {} blocks from EOF code sections.", contract_cfg.node_count()),
+        );
+        logger.debug(&format!("Control flow graph generated in {:?}.", now.elapsed()));
+        trace.display();
+
+        return Ok(contract_cfg)
     }
 
     // disassemble the bytecode
@@ -145,6 +184,10 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
         rpc_url: args.rpc_url.clone(),
         decimal_counter: false,
         output: String::from(""),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: args.evm_version,
     })
     .await?;
 
@@ -177,7 +220,7 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     }
 
     // create a new EVM instance
-    let evm = VM::new(
+    let mut evm = VM::new(
         contract_bytecode.clone(),
         String::from("0x"),
         String::from("0x6865696d64616c6c000000000061646472657373"),
@@ -186,6 +229,7 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
         0,
         u128::max_value(),
     );
+    evm.hardfork = args.evm_version;
     let mut shortened_target = contract_bytecode.clone();
     if shortened_target.len() > 66 {
         shortened_target = shortened_target.chars().take(66).collect::<String>() +
@@ -208,9 +252,7 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     logger.info(&format!("performing symbolic execution on '{}' .", &shortened_target));
 
     // create a new progress bar
-    let progress = ProgressBar::new_spinner();
-    progress.enable_steady_tick(Duration::from_millis(100));
-    progress.set_style(logger.info_spinner());
+    let progress = spinner(logger.info_spinner());
 
     // create a new petgraph StableGraph
     let mut contract_cfg = Graph::<String, String>::new();
@@ -226,7 +268,13 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     );
 
     // get a map of possible jump destinations
-    let (map, jumpdest_count) = &evm.symbolic_exec();
+    let (map, jumpdest_count, limit_reached) = &evm.symbolic_exec(&args.limits);
+    if let Some(limit) = limit_reached {
+        logger.warn(&format!("symbolic execution stopped early after reaching the {limit}."));
+    }
+    for fault in map.faults() {
+        logger.warn(&format!("symbolic execution faulted: {}", Error::from(fault)));
+    }
 
     // add jumpdests to the trace
     trace.add_info(
@@ -238,8 +286,29 @@ pub async fn cfg(args: CFGArgs) -> Result<Graph<String, String>, Box<dyn std::er
     logger.debug_max("building control flow graph from symbolic execution trace");
     build_cfg(map, &mut contract_cfg, None, false);
 
+    // find any code which was never reached by symbolic execution
+    let unreachable_blocks = add_unreachable_blocks(map, &mut contract_cfg, &bytecode);
+    trace.add_info(
+        map_trace,
+        line!(),
+        &format!("found {} unreachable blocks.", unreachable_blocks.len()),
+    );
+
     progress.finish_and_clear();
     logger.info("symbolic execution completed.");
+    if !unreachable_blocks.is_empty() {
+        logger.warn(&format!(
+            "found {} basic blocks which are unreachable from the dispatcher:",
+            unreachable_blocks.len()
+        ));
+        for block in &unreachable_blocks {
+            logger.warn(&format!(
+                "  - unreachable block {} - {}",
+                encode_hex_reduced(U256::from(block.start)),
+                encode_hex_reduced(U256::from(block.end))
+            ));
+        }
+    }
     logger.debug(&format!("Control flow graph generated in {:?}.", now.elapsed()));
     trace.display();
 
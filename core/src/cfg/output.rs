@@ -1,11 +1,123 @@
-use petgraph::{dot::Dot, graph::Graph};
+use petgraph::{
+    dot::Dot,
+    graph::{Graph, NodeIndex},
+    visit::EdgeRef,
+};
+use serde::Serialize;
 
-use super::CFGArgs;
+use crate::analysis::{
+    diff::DiffStatus,
+    loops::{find_natural_loops, NaturalLoop},
+    reachability::find_unreachable_nodes,
+};
+
+use super::{diff::DiffNode, CFGArgs};
+
+/// A serializable representation of a single basic block within the CFG.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonNode {
+    pub id: usize,
+    pub instructions: Vec<String>,
+    pub loop_header: bool,
+    pub unreachable: bool,
+}
+
+/// A serializable representation of a single basic block within a CFG diff.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiffNode {
+    pub id: usize,
+    pub status: DiffStatus,
+    pub instructions: Vec<String>,
+}
+
+/// A serializable representation of a single edge within the CFG.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonEdge {
+    pub source: usize,
+    pub target: usize,
+    pub jump_taken: bool,
+}
+
+/// A serializable representation of a natural loop within the CFG.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonLoop {
+    pub header: usize,
+    pub latches: Vec<usize>,
+    pub body: Vec<usize>,
+}
+
+/// A serializable representation of the entire CFG.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonCFG {
+    pub nodes: Vec<JsonNode>,
+    pub edges: Vec<JsonEdge>,
+    pub loops: Vec<JsonLoop>,
+    pub unreachable: Vec<usize>,
+}
+
+/// A serializable representation of an entire CFG diff.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiffCFG {
+    pub nodes: Vec<JsonDiffNode>,
+    pub edges: Vec<JsonEdge>,
+}
+
+/// Find all natural loops in the generated CFG. The dispatcher is always the first node added to
+/// the graph, so it is used as the entry point.
+fn find_loops(contract_cfg: &Graph<String, String>) -> Vec<NaturalLoop> {
+    find_natural_loops(contract_cfg, NodeIndex::new(0))
+}
+
+/// Find all nodes in the generated CFG which cannot be reached from the dispatcher.
+fn find_unreachable(contract_cfg: &Graph<String, String>) -> Vec<NodeIndex<u32>> {
+    find_unreachable_nodes(contract_cfg, NodeIndex::new(0))
+}
 
 /// Write the generated CFG to a file in the `dot` graphviz format.
 pub fn build_cfg(contract_cfg: &Graph<String, String>, args: &CFGArgs) -> String {
-    let output = format!("{}", Dot::with_config(&contract_cfg, &[]));
+    let loops = find_loops(contract_cfg);
+    let unreachable = find_unreachable(contract_cfg);
+
+    // mark unreachable nodes, loop headers, and the loops each node belongs to
+    let get_node_attributes = |_, (node, _): (NodeIndex<u32>, &String)| {
+        let mut attributes = Vec::new();
+        let loop_ids = loops
+            .iter()
+            .enumerate()
+            .filter(|(_, natural_loop)| natural_loop.contains(node))
+            .map(|(i, _)| i.to_string())
+            .collect::<Vec<String>>();
 
+        if unreachable.contains(&node) {
+            attributes.push(String::from(
+                "style = \"rounded,dashed\", color = \"gray\", fontcolor = \"gray\", unreachable = \"true\"",
+            ));
+        }
+        if loops.iter().any(|natural_loop| natural_loop.header == node) {
+            attributes.push(String::from("peripheries = 2, loop_header = \"true\""));
+        }
+        if !loop_ids.is_empty() {
+            attributes.push(format!("loops = \"{}\"", loop_ids.join(",")));
+        }
+
+        if attributes.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", attributes.join(", "))
+        }
+    };
+    let get_edge_attributes = |_, _| String::new();
+
+    let output = format!(
+        "{}",
+        Dot::with_attr_getters(&contract_cfg, &[], &get_edge_attributes, &get_node_attributes)
+    );
+
+    format_dot(output, args)
+}
+
+/// Apply the default styling to a `dot` graph, and replace edge labels with colors if enabled.
+fn format_dot(output: String, args: &CFGArgs) -> String {
     // find regex matches and replace
     let mut output = output.replace(
         "digraph {",
@@ -26,3 +138,151 @@ pub fn build_cfg(contract_cfg: &Graph<String, String>, args: &CFGArgs) -> String
 
     output
 }
+
+/// Write the generated CFG to a JSON string, including all nodes, edges, natural loops, and
+/// unreachable nodes.
+pub fn build_cfg_json(contract_cfg: &Graph<String, String>) -> Result<String, serde_json::Error> {
+    let loops = find_loops(contract_cfg);
+    let unreachable = find_unreachable(contract_cfg);
+
+    let nodes = contract_cfg
+        .node_indices()
+        .map(|node| JsonNode {
+            id: node.index(),
+            instructions: contract_cfg[node].lines().map(|line| line.trim().to_string()).collect(),
+            loop_header: loops.iter().any(|natural_loop| natural_loop.header == node),
+            unreachable: unreachable.contains(&node),
+        })
+        .collect();
+    let edges = contract_cfg
+        .edge_references()
+        .map(|edge| JsonEdge {
+            source: edge.source().index(),
+            target: edge.target().index(),
+            jump_taken: edge.weight() == "true",
+        })
+        .collect();
+    let loops = loops
+        .iter()
+        .map(|natural_loop| JsonLoop {
+            header: natural_loop.header.index(),
+            latches: natural_loop.latches.iter().map(|node| node.index()).collect(),
+            body: natural_loop.body.iter().map(|node| node.index()).collect(),
+        })
+        .collect();
+
+    let unreachable = unreachable.iter().map(|node| node.index()).collect();
+
+    serde_json::to_string_pretty(&JsonCFG { nodes, edges, loops, unreachable })
+}
+
+/// Write a generated CFG diff to a file in the `dot` graphviz format, highlighting added,
+/// removed, and changed blocks.
+pub fn build_cfg_diff(diff_cfg: &Graph<DiffNode, String>, args: &CFGArgs) -> String {
+    let get_node_attributes = |_, (_, node): (NodeIndex<u32>, &DiffNode)| match node.status {
+        DiffStatus::Unchanged => String::new(),
+        DiffStatus::Changed => String::from(
+            "style = \"rounded,filled\", fillcolor = \"lightgoldenrod\", diff = \"changed\" ",
+        ),
+        DiffStatus::Added => {
+            String::from("style = \"rounded,filled\", fillcolor = \"palegreen\", diff = \"added\" ")
+        }
+        DiffStatus::Removed => String::from(
+            "style = \"rounded,filled\", fillcolor = \"lightpink\", diff = \"removed\" ",
+        ),
+    };
+    let get_edge_attributes = |_, _| String::new();
+
+    let output = format!(
+        "{}",
+        Dot::with_attr_getters(&diff_cfg, &[], &get_edge_attributes, &get_node_attributes)
+    );
+
+    format_dot(output, args)
+}
+
+/// Write a generated CFG diff to a JSON string, including the diff status of each node.
+pub fn build_cfg_diff_json(
+    diff_cfg: &Graph<DiffNode, String>,
+) -> Result<String, serde_json::Error> {
+    let nodes = diff_cfg
+        .node_indices()
+        .map(|node| JsonDiffNode {
+            id: node.index(),
+            status: diff_cfg[node].status,
+            instructions: diff_cfg[node]
+                .assembly
+                .lines()
+                .map(|line| line.trim().to_string())
+                .collect(),
+        })
+        .collect();
+    let edges = diff_cfg
+        .edge_references()
+        .map(|edge| JsonEdge {
+            source: edge.source().index(),
+            target: edge.target().index(),
+            jump_taken: edge.weight() == "true",
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&JsonDiffCFG { nodes, edges })
+}
+
+/// Embed the given serialized CFG into the self-contained HTML viewer.
+fn embed_in_html(json: &str) -> String {
+    // escape closing tags so the JSON can't terminate the script early
+    include_str!("templates/cfg.html").replace("/*CFG_JSON*/", &json.replace("</", "<\\/"))
+}
+
+/// Write the generated CFG to a self-contained, interactive HTML viewer.
+pub fn build_cfg_html(contract_cfg: &Graph<String, String>) -> Result<String, serde_json::Error> {
+    Ok(embed_in_html(&build_cfg_json(contract_cfg)?))
+}
+
+/// Write a generated CFG diff to a self-contained, interactive HTML viewer.
+pub fn build_cfg_diff_html(
+    diff_cfg: &Graph<DiffNode, String>,
+) -> Result<String, serde_json::Error> {
+    Ok(embed_in_html(&build_cfg_diff_json(diff_cfg)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::Graph;
+
+    use crate::cfg::output::*;
+
+    fn simple_cfg() -> Graph<String, String> {
+        let mut contract_cfg = Graph::<String, String>::new();
+        let entry = contract_cfg.add_node(String::from("0x01 PUSH1 0x01\n0x03 JUMPI \n"));
+        let body = contract_cfg.add_node(String::from("0x04 JUMPDEST \n0x05 JUMP \n"));
+        contract_cfg.add_node(String::from("0x06 STOP \n"));
+        contract_cfg.add_edge(entry, body, String::from("true"));
+        contract_cfg.add_edge(body, body, String::from("true"));
+        contract_cfg
+    }
+
+    #[test]
+    fn test_build_cfg_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&build_cfg_json(&simple_cfg()).unwrap()).unwrap();
+
+        assert_eq!(json["nodes"][0]["instructions"][0], "0x01 PUSH1 0x01");
+        assert_eq!(json["nodes"][1]["loop_header"], true);
+        assert_eq!(json["loops"][0]["header"], 1);
+        assert_eq!(json["unreachable"], serde_json::json!([2]));
+    }
+
+    #[test]
+    fn test_build_cfg_html() {
+        let mut contract_cfg = simple_cfg();
+        contract_cfg.add_node(String::from("</script><script>alert(1)</script>"));
+        let html = build_cfg_html(&contract_cfg).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("/*CFG_JSON*/"));
+        assert!(html.contains("<\\/script><script>alert(1)<\\/script>"));
+        assert_eq!(html.matches("</script>").count(), 1);
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{Display, Formatter},
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::{
+    abi::{decode as decode_abi, ParamType},
+    types::H160,
+};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        eof::is_eof,
+        evm::core::{hardfork::Hardfork, vm::VM},
+        rpc::get_code,
+        selectors::{find_function_selectors, resolve_selectors},
+        signatures::{score_signature, ResolvedFunction},
+    },
+    utils::{io::logging::*, strings::decode_hex},
+};
+use serde::Serialize;
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    proxy::{call, constants::FACET_ADDRESS_SELECTOR, proxy, target_address, ProxyArgs},
+    utils::get_contract_bytecode,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Detect function selectors which collide across a proxy, its implementation, and a diamond's facets",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall collisions <TARGET> [OPTIONS]"
+)]
+pub struct CollisionsArgs {
+    /// The target to check, either a file, bytecode, contract address, or `-` to read bytecode
+    /// from stdin. Only contract addresses are checked against their implementation or facets.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to read the target, its implementation, and its facets from. Separate
+    /// multiple providers with commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors, which finds selectors shared by more than
+    /// one known signature.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl CollisionsArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A selector implemented by more than one of the target, its implementation, and its facets. A
+/// proxy's own functions shadow its implementation's, and a diamond delegates each selector to a
+/// single facet, so only one of the functions can be called.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Collision {
+    pub selector: String,
+    pub signature: Option<String>,
+
+    // the contracts which implement the selector, starting with the target
+    pub contracts: Vec<String>,
+
+    // the facet a diamond delegates the selector to, if the target is a diamond
+    pub dispatched_to: Option<String>,
+}
+
+/// A selector of the target which more than one known signature hashes to, so it's ambiguous
+/// which function it dispatches to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AmbiguousSelector {
+    pub selector: String,
+
+    // the signatures, ordered from most to least likely
+    pub signatures: Vec<String>,
+}
+
+/// The selector collisions found in the target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CollisionReport {
+    pub collisions: Vec<Collision>,
+    pub ambiguous: Vec<AmbiguousSelector>,
+}
+
+impl Display for CollisionReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.collisions.is_empty() && self.ambiguous.is_empty() {
+            writeln!(f, "no selector collisions found.")?;
+        }
+
+        if !self.collisions.is_empty() {
+            writeln!(f, "collisions:")?;
+        }
+        for collision in &self.collisions {
+            write!(f, "  0x{}", collision.selector)?;
+            if let Some(signature) = &collision.signature {
+                write!(f, " {signature}")?;
+            }
+            write!(f, " in {}", collision.contracts.join(", "))?;
+            if let Some(facet) = &collision.dispatched_to {
+                write!(f, ", dispatched to {facet}")?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.ambiguous.is_empty() {
+            writeln!(f, "ambiguous selectors:")?;
+        }
+        for ambiguous in &self.ambiguous {
+            writeln!(f, "  0x{}: {}", ambiguous.selector, ambiguous.signatures.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the selectors implemented by more than one contract, given each contract's selectors.
+/// Colliding selectors are mapped to the contracts which implement them, in the order given.
+///
+/// ```
+/// use std::collections::HashSet;
+///
+/// use heimdall_core::collisions::find_collisions;
+///
+/// let proxy = (String::from("proxy"), HashSet::from([String::from("3659cfe6")]));
+/// let implementation = (
+///     String::from("implementation"),
+///     HashSet::from([String::from("3659cfe6"), String::from("a9059cbb")]),
+/// );
+///
+/// let collisions = find_collisions(&[proxy, implementation]);
+/// assert_eq!(collisions.len(), 1);
+/// assert_eq!(collisions["3659cfe6"], vec!["proxy", "implementation"]);
+/// ```
+pub fn find_collisions(contracts: &[(String, HashSet<String>)]) -> BTreeMap<String, Vec<String>> {
+    let mut implementers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (contract, selectors) in contracts {
+        for selector in selectors {
+            implementers.entry(selector.clone()).or_default().push(contract.clone());
+        }
+    }
+
+    implementers.retain(|_, contracts| contracts.len() > 1);
+    implementers
+}
+
+// find the function selectors in a contract's bytecode
+async fn selectors_of(bytecode: &str, args: &CollisionsArgs) -> Result<HashSet<String>, Error> {
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: bytecode.to_string(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        output: String::new(),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: Hardfork::default(),
+    })
+    .await?;
+
+    let evm = VM::new(
+        bytecode.to_string(),
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+    Ok(find_function_selectors(&evm, &disassembled_bytecode).into_keys().collect())
+}
+
+/// The main entry point for the collisions module. Will find the function selectors of the
+/// target, and if it's a proxy or diamond, of its implementation or facets, and report selectors
+/// which more than one of them implement, or which more than one known signature hashes to.
+pub async fn collisions(args: CollisionsArgs) -> Result<CollisionReport, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // fetch bytecode
+    let is_address = ADDRESS_REGEX.is_match(&args.target)?;
+    let contract_bytecode =
+        get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?.replacen("0x", "", 1);
+
+    // selector detection only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by collisions. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
+    }
+
+    let target_name = match is_address {
+        true => args.target.to_lowercase(),
+        false => String::from("target"),
+    };
+    let mut contracts = vec![(target_name.clone(), selectors_of(&contract_bytecode, &args).await?)];
+
+    // a proxy's implementation, or a diamond's facets, are called through the target's
+    // dispatcher, so their selectors collide with the target's and each other's
+    let mut facets = Vec::new();
+    if is_address {
+        let report = proxy(ProxyArgs {
+            target: args.target.clone(),
+            verbose: args.verbose.clone(),
+            rpc_url: args.rpc_url.clone(),
+            skip_history: true,
+            output: String::new(),
+        })
+        .await?;
+        facets = report.facets.clone();
+
+        for address in report.implementation.into_iter().chain(report.facets) {
+            let address = format!("{address:?}");
+            let bytecode = get_code(&address, &args.rpc_url)
+                .await
+                .map_err(Error::from_rpc)?
+                .replacen("0x", "", 1);
+            if is_eof(&decode_hex(&bytecode)?) {
+                logger.warn(&format!("skipping '{address}', which is an EOF container."));
+                continue
+            }
+            contracts.push((address, selectors_of(&bytecode, &args).await?));
+        }
+        logger.info(&format!(
+            "found {} contracts called through '{}' .",
+            contracts.len() - 1,
+            &args.target
+        ));
+    }
+
+    // resolve every selector, which names collisions and finds ambiguous selectors
+    let mut selectors = contracts
+        .iter()
+        .flat_map(|(_, selectors)| selectors.iter().cloned())
+        .collect::<Vec<String>>();
+    selectors.sort();
+    selectors.dedup();
+    let mut resolved_selectors: HashMap<String, Vec<ResolvedFunction>> = HashMap::new();
+    if !args.skip_resolving {
+        resolved_selectors = resolve_selectors::<ResolvedFunction>(selectors.clone()).await;
+        logger.info(&format!(
+            "resolved {} possible functions from {} detected selectors.",
+            resolved_selectors.len(),
+            selectors.len()
+        ));
+    } else {
+        logger.info(&format!("found {} possible function selectors.", selectors.len()));
+    }
+    let signatures = |selector: &str| {
+        let mut signatures = resolved_selectors
+            .get(selector)
+            .map(|resolved_functions| {
+                resolved_functions
+                    .iter()
+                    .map(|resolved_function| resolved_function.signature.clone())
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        signatures.sort_by(|a, b| score_signature(b).cmp(&score_signature(a)).then(a.cmp(b)));
+        signatures.dedup();
+        signatures
+    };
+
+    let mut collisions = Vec::new();
+    for (selector, implementers) in find_collisions(&contracts) {
+        // a diamond's loupe knows which facet it delegates the selector to
+        let mut dispatched_to = None;
+        if !facets.is_empty() {
+            dispatched_to = call(
+                &args.rpc_url,
+                target_address(&args.target)?,
+                &format!("{FACET_ADDRESS_SELECTOR}{selector:0<64}"),
+            )
+            .await
+            .and_then(|output| decode_abi(&[ParamType::Address], &output).ok())
+            .and_then(|tokens| tokens.into_iter().next()?.into_address())
+            .filter(|facet| !facet.is_zero())
+            .map(|facet: H160| format!("{facet:?}"));
+        }
+
+        let collision = Collision {
+            signature: signatures(&selector).into_iter().next(),
+            selector,
+            contracts: implementers,
+            dispatched_to,
+        };
+        logger.warn(&format!(
+            "selector '0x{}' is implemented by {}, so only one of them can be called.",
+            collision.selector,
+            collision.contracts.join(", ")
+        ));
+        collisions.push(collision);
+    }
+
+    let mut ambiguous = Vec::new();
+    for selector in selectors {
+        let signatures = signatures(&selector);
+        if signatures.len() > 1 {
+            logger.warn(&format!(
+                "selector '0x{selector}' is shared by {} known signatures, such as '{}'.",
+                signatures.len(),
+                signatures[0]
+            ));
+            ambiguous.push(AmbiguousSelector { selector, signatures });
+        }
+    }
+
+    logger.info(&format!(
+        "found {} colliding and {} ambiguous selectors.",
+        collisions.len(),
+        ambiguous.len()
+    ));
+    logger.debug(&format!("collision detection completed in {:?}.", now.elapsed()));
+
+    Ok(CollisionReport { collisions, ambiguous })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_collisions_across_facets() {
+        let facet = |name: &str, selectors: &[&str]| {
+            (name.to_string(), selectors.iter().map(|selector| selector.to_string()).collect())
+        };
+        let collisions = find_collisions(&[
+            facet("diamond", &["1f931c1c"]),
+            facet("loupe", &["52ef6b2c", "cdffacc6"]),
+            facet("token", &["a9059cbb", "cdffacc6"]),
+            facet("vault", &["a9059cbb", "6e553f65"]),
+        ]);
+
+        assert_eq!(
+            collisions,
+            BTreeMap::from([
+                (String::from("a9059cbb"), vec![String::from("token"), String::from("vault")]),
+                (String::from("cdffacc6"), vec![String::from("loupe"), String::from("token")]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report = CollisionReport {
+            collisions: vec![Collision {
+                selector: String::from("3659cfe6"),
+                signature: Some(String::from("upgradeTo(address)")),
+                contracts: vec![String::from("proxy"), String::from("implementation")],
+                dispatched_to: None,
+            }],
+            ambiguous: Vec::new(),
+        };
+        assert_eq!(
+            report.to_string(),
+            "collisions:\n  0x3659cfe6 upgradeTo(address) in proxy, implementation\n"
+        );
+    }
+}
@@ -0,0 +1,515 @@
+pub mod standards;
+
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::U256;
+use heimdall_common::{
+    ether::evm::{core::hardfork::Hardfork, ext::exec::ExecutionLimits},
+    utils::{io::logging::*, strings::encode_hex},
+};
+use serde::Serialize;
+
+use crate::{
+    conformance::standards::{FunctionSpec, Standard},
+    error::Error,
+    snapshot::{snapshot, SnapshotArgs, SnapshotResult},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Check EVM bytecode against the ERC-20, ERC-721, ERC-1155, and ERC-4626 token standards",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall conformance <TARGET> [OPTIONS]"
+)]
+pub struct ConformanceArgs {
+    /// The target to check, either a file, bytecode, contract address, or `-` to read bytecode
+    /// from stdin.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving the signatures of functions and events which aren't part of the
+    /// standards.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// A standard to check against, one of `erc20`, `erc721`, `erc1155`, or `erc4626`. May be
+    /// given more than once. By default, every standard the target implements most of is
+    /// checked.
+    #[clap(long = "standard", short = 's')]
+    pub standards: Vec<Standard>,
+
+    /// Limits on how much of each function symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl ConformanceArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            standards: Some(Vec::new()),
+            limits: Some(ExecutionLimits::default()),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A function recovered from the target's bytecode.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoveredFunction {
+    // the function's return type, or `None` if it never returns data
+    pub returns: Option<String>,
+
+    // the function's resolved signature, if any
+    pub signature: Option<String>,
+}
+
+/// The functions and events recovered from the target's bytecode, which are compared against
+/// each standard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    // the recovered functions, keyed by selector
+    pub functions: BTreeMap<String, RecoveredFunction>,
+
+    // the resolved signature of each event the target emits, keyed by topic0
+    pub events: BTreeMap<U256, Option<String>>,
+}
+
+impl From<&SnapshotResult> for Interface {
+    fn from(result: &SnapshotResult) -> Self {
+        let mut interface = Interface::default();
+        for snapshot in &result.snapshots {
+            interface.functions.insert(
+                snapshot.selector.clone(),
+                RecoveredFunction {
+                    returns: snapshot.returns.clone(),
+                    signature: snapshot
+                        .resolved_function
+                        .as_ref()
+                        .map(|resolved_function| resolved_function.signature.clone()),
+                },
+            );
+            for (topic, (resolved_event, _)) in &snapshot.events {
+                let signature =
+                    resolved_event.as_ref().map(|resolved_event| resolved_event.signature.clone());
+                let entry = interface.events.entry(*topic).or_default();
+                if entry.is_none() {
+                    *entry = signature;
+                }
+            }
+        }
+        interface
+    }
+}
+
+/// Whether a function or event of a standard is implemented.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Status {
+    Present,
+    Missing,
+
+    /// The function is implemented, but doesn't return what the standard requires.
+    Mismatched {
+        reason: String,
+    },
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Present => write!(f, "present"),
+            Status::Missing => write!(f, "missing"),
+            Status::Mismatched { .. } => write!(f, "mismatched"),
+        }
+    }
+}
+
+/// A function or event of a standard, along with whether the target implements it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Member {
+    pub signature: String,
+
+    // the function's selector, or the event's topic0
+    pub selector: String,
+    pub optional: bool,
+
+    #[serde(flatten)]
+    pub status: Status,
+}
+
+impl Display for Member {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<10} {}", self.status.to_string(), self.signature)?;
+        if self.optional {
+            write!(f, " (optional)")?;
+        }
+        if let Status::Mismatched { reason } = &self.status {
+            write!(f, ": {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How closely the target conforms to a standard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StandardConformance {
+    pub standard: Standard,
+
+    // whether every required function and event is present, and returns what it should
+    pub conforms: bool,
+    pub functions: Vec<Member>,
+    pub events: Vec<Member>,
+}
+
+/// A function or event of the target which isn't part of any of the standards checked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Extra {
+    // the function's selector, or the event's topic0
+    pub selector: String,
+    pub signature: Option<String>,
+}
+
+impl Display for Extra {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.signature {
+            Some(signature) => write!(f, "0x{} {signature}", self.selector),
+            None => write!(f, "0x{}", self.selector),
+        }
+    }
+}
+
+/// The result of checking the target against each standard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConformanceReport {
+    pub standards: Vec<StandardConformance>,
+    pub extra_functions: Vec<Extra>,
+    pub extra_events: Vec<Extra>,
+}
+
+impl Display for ConformanceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.standards.is_empty() {
+            writeln!(f, "the target doesn't implement most of any standard.")?;
+        }
+        for standard in &self.standards {
+            writeln!(
+                f,
+                "{}: {}",
+                standard.standard,
+                if standard.conforms { "conforms" } else { "does not conform" }
+            )?;
+            for member in standard.functions.iter().chain(standard.events.iter()) {
+                writeln!(f, "  {member}")?;
+            }
+            writeln!(f)?;
+        }
+
+        for (name, extras) in [("functions", &self.extra_functions), ("events", &self.extra_events)]
+        {
+            if extras.is_empty() {
+                continue
+            }
+            writeln!(f, "extra {name}:")?;
+            for extra in extras {
+                writeln!(f, "  {extra}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// whether the recovered return type can't hold what the standard returns. symbolic execution
+// can't always tell a bool from a uint, so only returning nothing, returning data where none is
+// expected, or returning static data where dynamic data is expected, are mismatches
+fn return_mismatch(expected: Option<&str>, recovered: Option<&str>) -> Option<String> {
+    let is_dynamic = |type_: &str| {
+        type_.ends_with("[]") || type_.ends_with(" memory") || ["string", "bytes"].contains(&type_)
+    };
+
+    match (expected, recovered) {
+        (Some(expected), None) => Some(format!("returns nothing, but should return {expected}")),
+        (None, Some(recovered)) => Some(format!("returns {recovered}, but should return nothing")),
+        (Some(expected), Some(recovered)) if is_dynamic(expected) != is_dynamic(recovered) => {
+            Some(format!("returns {recovered}, but should return {expected}"))
+        }
+        _ => None,
+    }
+}
+
+/// Whether the interface implements more than half of the standard's required functions.
+pub fn implements_most_of(standard: Standard, interface: &Interface) -> bool {
+    let required = standard.functions().iter().filter(|function| !function.optional);
+    let (count, present) = required.fold((0, 0), |(count, present), function| {
+        (count + 1, present + interface.functions.contains_key(&function.selector()) as usize)
+    });
+    present * 2 > count
+}
+
+/// Compare the interface against each function and event of the standard.
+pub fn check_standard(standard: Standard, interface: &Interface) -> StandardConformance {
+    let function_status = |function: &FunctionSpec| match interface
+        .functions
+        .get(&function.selector())
+    {
+        None => Status::Missing,
+        Some(recovered) => match return_mismatch(function.returns, recovered.returns.as_deref()) {
+            Some(reason) => Status::Mismatched { reason },
+            None => Status::Present,
+        },
+    };
+    let functions = standard
+        .functions()
+        .iter()
+        .map(|function| Member {
+            signature: function.signature.to_string(),
+            selector: function.selector(),
+            optional: function.optional,
+            status: function_status(function),
+        })
+        .collect::<Vec<_>>();
+
+    let events = standard
+        .events()
+        .iter()
+        .map(|event| Member {
+            signature: event.signature.to_string(),
+            selector: encode_topic(event.topic()),
+            optional: event.optional,
+            status: match interface.events.contains_key(&event.topic()) {
+                true => Status::Present,
+                false => Status::Missing,
+            },
+        })
+        .collect::<Vec<_>>();
+
+    let conforms = functions
+        .iter()
+        .chain(events.iter())
+        .all(|member| member.optional || member.status == Status::Present);
+    StandardConformance { standard, conforms, functions, events }
+}
+
+// a topic as 32 bytes of hex, without a `0x` prefix
+fn encode_topic(topic: U256) -> String {
+    let mut bytes = [0u8; 32];
+    topic.to_big_endian(&mut bytes);
+    encode_hex(bytes.to_vec())
+}
+
+/// Check an interface against the given standards, or every standard it implements most of if
+/// none are given.
+pub fn check_interface(interface: &Interface, standards: &[Standard]) -> ConformanceReport {
+    let mut standards = match standards.is_empty() {
+        true => Standard::ALL
+            .into_iter()
+            .filter(|standard| implements_most_of(*standard, interface))
+            .collect(),
+        false => standards.to_vec(),
+    };
+    standards.sort();
+    standards.dedup();
+
+    let standards = standards
+        .into_iter()
+        .map(|standard| check_standard(standard, interface))
+        .collect::<Vec<_>>();
+
+    // functions and events which no checked standard defines
+    let extra_functions = interface
+        .functions
+        .iter()
+        .filter(|(selector, _)| {
+            !standards.iter().any(|standard| {
+                standard.functions.iter().any(|function| &&function.selector == selector)
+            })
+        })
+        .map(|(selector, function)| Extra {
+            selector: selector.clone(),
+            signature: function.signature.clone(),
+        })
+        .collect();
+    let extra_events = interface
+        .events
+        .iter()
+        .map(|(topic, signature)| (encode_topic(*topic), signature))
+        .filter(|(topic, _)| {
+            !standards
+                .iter()
+                .any(|standard| standard.events.iter().any(|event| &event.selector == topic))
+        })
+        .map(|(selector, signature)| Extra { selector, signature: signature.clone() })
+        .collect();
+
+    ConformanceReport { standards, extra_functions, extra_events }
+}
+
+/// The main entry point for the conformance module. Will snapshot the target to recover its
+/// functions, their return types, and the events it emits, and compare them against each token
+/// standard.
+pub async fn conformance(args: ConformanceArgs) -> Result<ConformanceReport, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // recover the target's interface
+    let result = snapshot(SnapshotArgs {
+        target: args.target.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        default: true,
+        skip_resolving: args.skip_resolving,
+        no_tui: true,
+        evm_version: Hardfork::default(),
+        limits: args.limits.clone(),
+        output: String::new(),
+    })
+    .await?;
+    let interface = Interface::from(&result);
+
+    let report = check_interface(&interface, &args.standards);
+    logger.info(&format!(
+        "checked {} standards, {} of which the target conforms to.",
+        report.standards.len(),
+        report.standards.iter().filter(|standard| standard.conforms).count()
+    ));
+    logger.debug(&format!("conformance check completed in {:?}.", now.elapsed()));
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an interface with the given selectors and return types, emitting the given events
+    fn interface(functions: &[(&str, Option<&str>)], events: &[&str]) -> Interface {
+        Interface {
+            functions: functions
+                .iter()
+                .map(|(selector, returns)| {
+                    (
+                        selector.to_string(),
+                        RecoveredFunction { returns: returns.map(String::from), signature: None },
+                    )
+                })
+                .collect(),
+            events: events
+                .iter()
+                .map(|topic| (U256::from_str_radix(topic, 16).unwrap(), None))
+                .collect(),
+        }
+    }
+
+    const TRANSFER: &str = "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+    const APPROVAL: &str = "8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+
+    #[test]
+    fn test_erc20_conforms() {
+        let report = check_interface(
+            &interface(
+                &[
+                    ("18160ddd", Some("uint256")),
+                    ("70a08231", Some("uint256")),
+                    ("a9059cbb", Some("bool")),
+                    ("23b872dd", Some("bool")),
+                    ("095ea7b3", Some("bool")),
+                    ("dd62ed3e", Some("uint256")),
+                    ("12345678", None),
+                ],
+                &[TRANSFER, APPROVAL],
+            ),
+            &[],
+        );
+
+        assert_eq!(report.standards.len(), 1);
+        assert_eq!(report.standards[0].standard, Standard::Erc20);
+        assert!(report.standards[0].conforms);
+        assert_eq!(
+            report.extra_functions,
+            vec![Extra { selector: String::from("12345678"), signature: None }]
+        );
+        assert!(report.extra_events.is_empty());
+    }
+
+    #[test]
+    fn test_erc20_mismatched_and_missing() {
+        // transfer returns nothing, and approve and the Approval event are missing
+        let report = check_interface(
+            &interface(
+                &[
+                    ("18160ddd", Some("uint256")),
+                    ("70a08231", Some("uint256")),
+                    ("a9059cbb", None),
+                    ("23b872dd", Some("bool")),
+                    ("dd62ed3e", Some("uint256")),
+                ],
+                &[TRANSFER],
+            ),
+            &[Standard::Erc20],
+        );
+
+        let erc20 = &report.standards[0];
+        assert!(!erc20.conforms);
+        assert_eq!(
+            erc20.functions[2].status,
+            Status::Mismatched { reason: String::from("returns nothing, but should return bool") }
+        );
+        assert_eq!(erc20.functions[4].status, Status::Missing);
+        assert_eq!(erc20.functions[6].status, Status::Missing);
+        assert!(erc20.functions[6].optional);
+        assert_eq!(erc20.events[0].status, Status::Present);
+        assert_eq!(erc20.events[1].status, Status::Missing);
+    }
+
+    #[test]
+    fn test_return_mismatch() {
+        assert_eq!(return_mismatch(Some("bool"), Some("uint256")), None);
+        assert_eq!(return_mismatch(Some("string"), Some("bytes memory")), None);
+        assert_eq!(
+            return_mismatch(Some("string"), Some("uint256")),
+            Some(String::from("returns uint256, but should return string"))
+        );
+        assert_eq!(
+            return_mismatch(None, Some("bool")),
+            Some(String::from("returns bool, but should return nothing"))
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use ethers::{types::U256, utils::keccak256};
+use heimdall_common::utils::strings::encode_hex;
+use serde::Serialize;
+
+/// A token standard which a contract's interface can be checked against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Standard {
+    Erc20,
+    Erc721,
+    Erc1155,
+    Erc4626,
+}
+
+/// A function a standard defines, along with what it returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionSpec {
+    pub signature: &'static str,
+    pub returns: Option<&'static str>,
+
+    // whether the function is part of an optional extension, such as ERC-20's metadata
+    pub optional: bool,
+}
+
+/// An event a standard defines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventSpec {
+    pub signature: &'static str,
+    pub optional: bool,
+}
+
+const fn required(signature: &'static str, returns: Option<&'static str>) -> FunctionSpec {
+    FunctionSpec { signature, returns, optional: false }
+}
+
+const fn optional(signature: &'static str, returns: Option<&'static str>) -> FunctionSpec {
+    FunctionSpec { signature, returns, optional: true }
+}
+
+const ERC20_FUNCTIONS: &[FunctionSpec] = &[
+    required("totalSupply()", Some("uint256")),
+    required("balanceOf(address)", Some("uint256")),
+    required("transfer(address,uint256)", Some("bool")),
+    required("transferFrom(address,address,uint256)", Some("bool")),
+    required("approve(address,uint256)", Some("bool")),
+    required("allowance(address,address)", Some("uint256")),
+    optional("name()", Some("string")),
+    optional("symbol()", Some("string")),
+    optional("decimals()", Some("uint8")),
+];
+
+const ERC20_EVENTS: &[EventSpec] = &[
+    EventSpec { signature: "Transfer(address,address,uint256)", optional: false },
+    EventSpec { signature: "Approval(address,address,uint256)", optional: false },
+];
+
+const ERC721_FUNCTIONS: &[FunctionSpec] = &[
+    required("balanceOf(address)", Some("uint256")),
+    required("ownerOf(uint256)", Some("address")),
+    required("safeTransferFrom(address,address,uint256,bytes)", None),
+    required("safeTransferFrom(address,address,uint256)", None),
+    required("transferFrom(address,address,uint256)", None),
+    required("approve(address,uint256)", None),
+    required("setApprovalForAll(address,bool)", None),
+    required("getApproved(uint256)", Some("address")),
+    required("isApprovedForAll(address,address)", Some("bool")),
+    required("supportsInterface(bytes4)", Some("bool")),
+    optional("name()", Some("string")),
+    optional("symbol()", Some("string")),
+    optional("tokenURI(uint256)", Some("string")),
+];
+
+const ERC721_EVENTS: &[EventSpec] = &[
+    EventSpec { signature: "Transfer(address,address,uint256)", optional: false },
+    EventSpec { signature: "Approval(address,address,uint256)", optional: false },
+    EventSpec { signature: "ApprovalForAll(address,address,bool)", optional: false },
+];
+
+const ERC1155_FUNCTIONS: &[FunctionSpec] = &[
+    required("safeTransferFrom(address,address,uint256,uint256,bytes)", None),
+    required("safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)", None),
+    required("balanceOf(address,uint256)", Some("uint256")),
+    required("balanceOfBatch(address[],uint256[])", Some("uint256[]")),
+    required("setApprovalForAll(address,bool)", None),
+    required("isApprovedForAll(address,address)", Some("bool")),
+    required("supportsInterface(bytes4)", Some("bool")),
+    optional("uri(uint256)", Some("string")),
+];
+
+const ERC1155_EVENTS: &[EventSpec] = &[
+    EventSpec {
+        signature: "TransferSingle(address,address,address,uint256,uint256)",
+        optional: false,
+    },
+    EventSpec {
+        signature: "TransferBatch(address,address,address,uint256[],uint256[])",
+        optional: false,
+    },
+    EventSpec { signature: "ApprovalForAll(address,address,bool)", optional: false },
+    EventSpec { signature: "URI(string,uint256)", optional: true },
+];
+
+// ERC-4626 vaults must also be ERC-20 tokens, which is checked separately
+const ERC4626_FUNCTIONS: &[FunctionSpec] = &[
+    required("asset()", Some("address")),
+    required("totalAssets()", Some("uint256")),
+    required("convertToShares(uint256)", Some("uint256")),
+    required("convertToAssets(uint256)", Some("uint256")),
+    required("maxDeposit(address)", Some("uint256")),
+    required("previewDeposit(uint256)", Some("uint256")),
+    required("deposit(uint256,address)", Some("uint256")),
+    required("maxMint(address)", Some("uint256")),
+    required("previewMint(uint256)", Some("uint256")),
+    required("mint(uint256,address)", Some("uint256")),
+    required("maxWithdraw(address)", Some("uint256")),
+    required("previewWithdraw(uint256)", Some("uint256")),
+    required("withdraw(uint256,address,address)", Some("uint256")),
+    required("maxRedeem(address)", Some("uint256")),
+    required("previewRedeem(uint256)", Some("uint256")),
+    required("redeem(uint256,address,address)", Some("uint256")),
+];
+
+const ERC4626_EVENTS: &[EventSpec] = &[
+    EventSpec { signature: "Deposit(address,address,uint256,uint256)", optional: false },
+    EventSpec { signature: "Withdraw(address,address,address,uint256,uint256)", optional: false },
+];
+
+impl Standard {
+    /// Every standard which can be checked.
+    pub const ALL: [Standard; 4] =
+        [Standard::Erc20, Standard::Erc721, Standard::Erc1155, Standard::Erc4626];
+
+    /// The functions the standard defines, including those of its optional extensions.
+    pub fn functions(&self) -> &'static [FunctionSpec] {
+        match self {
+            Standard::Erc20 => ERC20_FUNCTIONS,
+            Standard::Erc721 => ERC721_FUNCTIONS,
+            Standard::Erc1155 => ERC1155_FUNCTIONS,
+            Standard::Erc4626 => ERC4626_FUNCTIONS,
+        }
+    }
+
+    /// The events the standard defines.
+    pub fn events(&self) -> &'static [EventSpec] {
+        match self {
+            Standard::Erc20 => ERC20_EVENTS,
+            Standard::Erc721 => ERC721_EVENTS,
+            Standard::Erc1155 => ERC1155_EVENTS,
+            Standard::Erc4626 => ERC4626_EVENTS,
+        }
+    }
+}
+
+impl FromStr for Standard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "erc20" => Ok(Standard::Erc20),
+            "erc721" => Ok(Standard::Erc721),
+            "erc1155" => Ok(Standard::Erc1155),
+            "erc4626" => Ok(Standard::Erc4626),
+            _ => Err(format!(
+                "unknown standard '{}', expected one of: {}",
+                s,
+                Standard::ALL.map(|standard| standard.to_string()).join(", ")
+            )),
+        }
+    }
+}
+
+impl Display for Standard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Standard::Erc20 => "erc20",
+            Standard::Erc721 => "erc721",
+            Standard::Erc1155 => "erc1155",
+            Standard::Erc4626 => "erc4626",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FunctionSpec {
+    /// The function's 4byte selector, without a `0x` prefix.
+    ///
+    /// ```
+    /// use heimdall_core::conformance::standards::Standard;
+    ///
+    /// let transfer = Standard::Erc20.functions()[2];
+    /// assert_eq!(transfer.selector(), "a9059cbb");
+    /// ```
+    pub fn selector(&self) -> String {
+        encode_hex(keccak256(self.signature)[..4].to_vec())
+    }
+}
+
+impl EventSpec {
+    /// The event's topic0, the hash of its signature.
+    pub fn topic(&self) -> U256 {
+        U256::from_big_endian(&keccak256(self.signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_from_str() {
+        assert_eq!("ERC-20".parse::<Standard>().unwrap(), Standard::Erc20);
+        assert_eq!("erc4626".parse::<Standard>().unwrap(), Standard::Erc4626);
+        assert!("erc777".parse::<Standard>().is_err());
+    }
+
+    #[test]
+    fn test_selectors_and_topics() {
+        assert_eq!(ERC721_FUNCTIONS[3].selector(), "42842e0e");
+        assert_eq!(ERC1155_FUNCTIONS[1].selector(), "2eb2c2d6");
+        assert_eq!(
+            ERC20_EVENTS[0].topic(),
+            U256::from_str_radix(
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                16
+            )
+            .unwrap()
+        );
+    }
+}
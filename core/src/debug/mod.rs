@@ -0,0 +1,425 @@
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, Write},
+    str::FromStr,
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::types::U256;
+use heimdall_common::{
+    ether::evm::core::{hardfork::Hardfork, vm::VM},
+    utils::{io::logging::*, strings::encode_hex},
+};
+
+use crate::{error::Error, exec::build_vm};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Step through the execution of EVM bytecode interactively",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall debug <TARGET> [OPTIONS]"
+)]
+pub struct DebugArgs {
+    /// The target to debug, either a file, bytecode, or contract address.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. When the target is a contract
+    /// address, its state is also read from this provider. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The calldata to execute the target with.
+    #[clap(long = "calldata", short, default_value = "0x", hide_default_value = true)]
+    pub calldata: String,
+
+    /// The value, in wei, to send with the call.
+    #[clap(long = "value", default_value = "0", hide_default_value = true)]
+    pub value: u128,
+
+    /// The gas limit of the call.
+    #[clap(long = "gas-limit", default_value = "30000000")]
+    pub gas_limit: u128,
+
+    /// The hardfork whose gas schedule the VM follows.
+    #[clap(long = "evm-version", default_value = "cancun")]
+    pub evm_version: Hardfork,
+}
+
+impl DebugArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            calldata: Some(String::from("0x")),
+            value: Some(0),
+            gas_limit: Some(30_000_000),
+            evm_version: Some(Hardfork::default()),
+        }
+    }
+}
+
+const HELP: &str = "commands:
+  step, s [n]        execute the next n instructions, default 1
+  continue, c        execute until a breakpoint is reached or execution ends
+  break, b <pc>      set a breakpoint at the given program counter
+  delete, d <pc>     remove the breakpoint at the given program counter
+  breakpoints        list every breakpoint
+  where, w           show the next instruction
+  stack              show the stack, top first
+  memory, mem        show memory, one word per line
+  storage            show every storage slot which has been written
+  help, h            show this message
+  quit, q            exit the debugger";
+
+/// A single command entered into the debugger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DebugCommand {
+    Step(usize),
+    Continue,
+    Break(u128),
+    Delete(u128),
+    Breakpoints,
+    Where,
+    Stack,
+    Memory,
+    Storage,
+    Help,
+    Quit,
+}
+
+impl FromStr for DebugCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.next();
+
+        // program counters may be given in hex or decimal
+        let pc = || match argument {
+            Some(pc) => match pc.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16),
+                None => pc.parse(),
+            }
+            .map_err(|_| format!("invalid program counter '{pc}'.")),
+            None => Err(format!("'{command}' requires a program counter.")),
+        };
+
+        match command {
+            "step" | "s" => match argument {
+                Some(count) => count
+                    .parse()
+                    .map(DebugCommand::Step)
+                    .map_err(|_| format!("invalid count '{count}'.")),
+                None => Ok(DebugCommand::Step(1)),
+            },
+            "continue" | "c" => Ok(DebugCommand::Continue),
+            "break" | "b" => pc().map(DebugCommand::Break),
+            "delete" | "d" => pc().map(DebugCommand::Delete),
+            "breakpoints" => Ok(DebugCommand::Breakpoints),
+            "where" | "w" => Ok(DebugCommand::Where),
+            "stack" => Ok(DebugCommand::Stack),
+            "memory" | "mem" => Ok(DebugCommand::Memory),
+            "storage" => Ok(DebugCommand::Storage),
+            "help" | "h" => Ok(DebugCommand::Help),
+            "quit" | "q" | "exit" => Ok(DebugCommand::Quit),
+            _ => Err(format!("unknown command '{command}'. type 'help' for a list of commands.")),
+        }
+    }
+}
+
+/// The [`Debugger`] steps a [`VM`] through execution, stopping at breakpoints and reporting its
+/// state on request.
+pub struct Debugger {
+    pub vm: VM,
+    pub breakpoints: BTreeSet<u128>,
+}
+
+impl Debugger {
+    /// Creates a new [`Debugger`] for the given VM, which is yet to execute.
+    pub fn new(vm: VM) -> Self {
+        Debugger { vm, breakpoints: BTreeSet::new() }
+    }
+
+    /// The program counter of the next instruction.
+    pub fn pc(&self) -> u128 {
+        self.vm.instruction - 1
+    }
+
+    /// Whether execution has ended.
+    pub fn finished(&self) -> bool {
+        self.vm.exitcode != 255 || self.vm.bytecode.len() < self.vm.instruction as usize
+    }
+
+    /// Handles the given command, returning the output to show.
+    pub fn handle(&mut self, command: &DebugCommand) -> String {
+        match command {
+            DebugCommand::Step(count) => {
+                for _ in 0..*count {
+                    if self.finished() {
+                        break
+                    }
+                    self.vm.step();
+                }
+                self.location()
+            }
+            DebugCommand::Continue => {
+                // always execute at least one instruction, so we can continue past a breakpoint
+                while !self.finished() {
+                    self.vm.step();
+                    if self.breakpoints.contains(&self.pc()) {
+                        return format!("hit breakpoint at {:#x}.\n{}", self.pc(), self.location())
+                    }
+                }
+                self.location()
+            }
+            DebugCommand::Break(pc) => {
+                self.breakpoints.insert(*pc);
+                format!("breakpoint set at {pc:#x}.")
+            }
+            DebugCommand::Delete(pc) => match self.breakpoints.remove(pc) {
+                true => format!("breakpoint at {pc:#x} removed."),
+                false => format!("no breakpoint at {pc:#x}."),
+            },
+            DebugCommand::Breakpoints => match self.breakpoints.is_empty() {
+                true => String::from("no breakpoints set."),
+                false => self
+                    .breakpoints
+                    .iter()
+                    .map(|pc| format!("{pc:#x}"))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            },
+            DebugCommand::Where => self.location(),
+            DebugCommand::Stack => match self.vm.stack.size() {
+                0 => String::from("the stack is empty."),
+                _ => self
+                    .vm
+                    .stack
+                    .stack
+                    .iter()
+                    .enumerate()
+                    .map(|(i, frame)| format!("{i:>4}: {:#066x}", frame.value))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            },
+            DebugCommand::Memory => match self.vm.memory.memory.is_empty() {
+                true => String::from("memory is empty."),
+                false => self
+                    .vm
+                    .memory
+                    .memory
+                    .chunks(32)
+                    .enumerate()
+                    .map(|(i, word)| format!("{:#06x}: {}", i * 32, encode_hex(word.to_vec())))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            },
+            DebugCommand::Storage => {
+                let mut slots = self.vm.storage.storage.iter().collect::<Vec<_>>();
+                slots.sort();
+                match slots.is_empty() {
+                    true => String::from("no storage slots have been written."),
+                    false => slots
+                        .iter()
+                        .map(|(slot, value)| {
+                            // slots computed from mapping keys are labeled with the access
+                            let label = match self.vm.preimages.resolve(U256::from(*slot)) {
+                                Some(mapping) => format!(" ({mapping})"),
+                                None => String::new(),
+                            };
+                            format!(
+                                "{}{label}: {}",
+                                encode_hex(slot.to_vec()),
+                                encode_hex(value.to_vec())
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                }
+            }
+            DebugCommand::Help => HELP.to_string(),
+            DebugCommand::Quit => String::new(),
+        }
+    }
+
+    /// Describes the next instruction, or how execution ended.
+    pub fn location(&self) -> String {
+        if self.finished() {
+            return format!(
+                "execution finished with exit code {} after using {} gas.{}{}",
+                self.vm.exitcode,
+                self.vm.gas_used,
+                match self.vm.returndata.is_empty() {
+                    true => String::new(),
+                    false => format!("\nreturndata: 0x{}", encode_hex(self.vm.returndata.clone())),
+                },
+                match &self.vm.fault {
+                    Some(fault) => format!("\n{}", Error::from(fault.clone())),
+                    None => String::new(),
+                }
+            )
+        }
+
+        let pc = self.pc() as usize;
+        let opcode = self.vm.hardfork.opcode(self.vm.bytecode[pc]);
+        let mut instruction = format!("{:#06x}: {}", pc, opcode.name);
+
+        // show the data pushed by PUSH1 through PUSH32
+        if (0x60..=0x7f).contains(&opcode.code) {
+            let size = (opcode.code - 0x5f) as usize;
+            let end = (pc + 1 + size).min(self.vm.bytecode.len());
+            instruction
+                .push_str(&format!(" 0x{}", encode_hex(self.vm.bytecode[pc + 1..end].to_vec())));
+        }
+
+        instruction
+    }
+
+    /// Reads commands from the given input until it ends or the user quits, writing the output of
+    /// each to the given output.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> Result<(), Error> {
+        writeln!(output, "{}", self.location())?;
+        write!(output, "(heimdall) ")?;
+        output.flush()?;
+
+        let mut last_command = DebugCommand::Step(1);
+        for line in input.lines() {
+            let line = line?;
+
+            // an empty line repeats the last command
+            let command = match line.trim() {
+                "" => Ok(last_command.clone()),
+                line => line.parse::<DebugCommand>(),
+            };
+
+            match command {
+                Ok(DebugCommand::Quit) => return Ok(()),
+                Ok(command) => {
+                    writeln!(output, "{}", self.handle(&command))?;
+                    last_command = command;
+                }
+                Err(e) => writeln!(output, "{e}")?,
+            }
+
+            write!(output, "(heimdall) ")?;
+            output.flush()?;
+        }
+
+        writeln!(output)?;
+        Ok(())
+    }
+}
+
+/// The main entry point for the debug module. Will start an interactive debugger, reading
+/// commands from stdin, for the execution of the target with the given calldata.
+pub async fn debug(args: DebugArgs) -> Result<(), Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let mut evm =
+        build_vm(&args.target, &args.rpc_url, &args.calldata, args.value, args.gas_limit, &logger)
+            .await?;
+    evm.hardfork = args.evm_version;
+
+    println!("type 'help' for a list of commands.");
+    Debugger::new(evm).run(std::io::stdin().lock(), std::io::stdout())
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::core::vm::VM;
+
+    use crate::debug::*;
+
+    fn new_debugger(bytecode: &str) -> Debugger {
+        Debugger::new(VM::new(
+            String::from(bytecode),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            100000,
+        ))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!("s".parse::<DebugCommand>(), Ok(DebugCommand::Step(1)));
+        assert_eq!("step 5".parse::<DebugCommand>(), Ok(DebugCommand::Step(5)));
+        assert_eq!("b 0x1a".parse::<DebugCommand>(), Ok(DebugCommand::Break(0x1a)));
+        assert_eq!("break 26".parse::<DebugCommand>(), Ok(DebugCommand::Break(26)));
+        assert!("break".parse::<DebugCommand>().is_err());
+        assert!("jump".parse::<DebugCommand>().is_err());
+    }
+
+    #[test]
+    fn test_step_and_breakpoints() {
+        // PUSH1 0x2a PUSH1 0x00 SSTORE STOP
+        let mut debugger = new_debugger("0x602a60005500");
+        assert_eq!(debugger.location(), "0x0000: PUSH1 0x2a");
+
+        assert_eq!(debugger.handle(&DebugCommand::Step(1)), "0x0002: PUSH1 0x00");
+        assert_eq!(debugger.handle(&DebugCommand::Stack), format!("   0: {:#066x}", 0x2a));
+
+        debugger.handle(&DebugCommand::Break(5));
+        assert_eq!(
+            debugger.handle(&DebugCommand::Continue),
+            "hit breakpoint at 0x5.\n0x0005: STOP"
+        );
+        assert_eq!(
+            debugger.handle(&DebugCommand::Storage),
+            format!("{}: {}2a", "00".repeat(32), "00".repeat(31))
+        );
+
+        assert!(debugger.handle(&DebugCommand::Continue).starts_with("execution finished"));
+        assert!(debugger.finished());
+    }
+
+    #[test]
+    fn test_storage_mapping_labels() {
+        // balances[caller] = 0x2a, where balances is declared at slot 1
+        let mut debugger = new_debugger("0x336000526001602052602a60406000205500");
+        debugger.handle(&DebugCommand::Continue);
+
+        assert!(debugger
+            .handle(&DebugCommand::Storage)
+            .contains(" (mapping[0x1][0x6865696d64616c6c00000000000063616c6c6572]): "));
+    }
+
+    #[test]
+    fn test_run() {
+        let mut debugger = new_debugger("0x602a60005500");
+        let mut output = Vec::new();
+        debugger.run("s\n\nfoo\nq\ns\n".as_bytes(), &mut output).unwrap();
+
+        // the empty line repeats the step, and nothing runs after quitting
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("0x0004: SSTORE"));
+        assert!(output.contains("unknown command 'foo'"));
+        assert_eq!(debugger.pc(), 4);
+    }
+}
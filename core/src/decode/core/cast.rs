@@ -0,0 +1,155 @@
+use ethers::{
+    abi::Token,
+    types::{Transaction, I256},
+    utils::to_checksum,
+};
+use heimdall_common::ether::signatures::ResolvedFunction;
+
+/// Format decoded calldata as `cast calldata-decode` does, with each input on its own line. If
+/// `numbered` is set, the inputs follow the function's signature, as `cast 4byte-decode` formats
+/// them.
+pub fn build_cast_output(function: &ResolvedFunction, numbered: bool) -> String {
+    let mut lines = Vec::new();
+    if numbered {
+        lines.push(format!("1) \"{}\"", function.signature));
+    }
+    lines.extend(function.decoded_inputs.iter().flatten().map(format_token));
+    lines.join("\n")
+}
+
+/// Build the `cast send` or `cast call` command line which makes the decoded call. The call's
+/// target and value are taken from the transaction, if the calldata came from one. Functions
+/// whose signatures weren't resolved are called with their raw calldata.
+pub fn build_cast_command(
+    function: &ResolvedFunction,
+    calldata: &str,
+    transaction: &Transaction,
+    command: &str,
+) -> String {
+    let mut parts = vec![
+        String::from("cast"),
+        command.to_string(),
+        transaction.to.map(|to| to_checksum(&to, None)).unwrap_or_else(|| String::from("<TO>")),
+    ];
+
+    match (function.name.starts_with("Unresolved_"), &function.decoded_inputs) {
+        (false, Some(inputs)) => {
+            parts.push(shell_quote(&function.signature));
+            parts.extend(inputs.iter().map(|input| {
+                // top-level strings are passed as they are, rather than quoted
+                shell_quote(&match input {
+                    Token::String(string) => string.clone(),
+                    input => format_token(input),
+                })
+            }));
+        }
+        _ => parts.push(format!("0x{}", calldata.trim_start_matches("0x"))),
+    }
+
+    if !transaction.value.is_zero() {
+        parts.push(format!("--value {}", transaction.value));
+    }
+    if command == "call" && !transaction.from.is_zero() {
+        parts.push(format!("--from {}", to_checksum(&transaction.from, None)));
+    }
+
+    parts.join(" ")
+}
+
+/// formats a decoded value as cast does
+pub(crate) fn format_token(token: &Token) -> String {
+    match token {
+        Token::Address(address) => to_checksum(address, None),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            format!("0x{}", bytes.iter().map(|byte| format!("{byte:02x}")).collect::<String>())
+        }
+        Token::Int(int) => I256::from_raw(*int).to_string(),
+        Token::Uint(uint) => uint.to_string(),
+        Token::Bool(boolean) => boolean.to_string(),
+        Token::String(string) => format!("{string:?}"),
+        Token::FixedArray(tokens) | Token::Array(tokens) => {
+            format!("[{}]", tokens.iter().map(format_token).collect::<Vec<_>>().join(", "))
+        }
+        Token::Tuple(tokens) => {
+            format!("({})", tokens.iter().map(format_token).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+/// quotes an argument for a POSIX shell, unless it's safe to pass as it is
+fn shell_quote(argument: &str) -> String {
+    match !argument.is_empty() &&
+        argument.chars().all(|c| c.is_ascii_alphanumeric() || ['_', '-', '.'].contains(&c))
+    {
+        true => argument.to_string(),
+        false => format!("'{}'", argument.replace('\'', r"'\''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::Token,
+        types::{Transaction, H160, U256},
+    };
+    use heimdall_common::ether::signatures::ResolvedFunction;
+
+    use crate::decode::core::cast::{build_cast_command, build_cast_output};
+
+    fn transfer() -> ResolvedFunction {
+        ResolvedFunction {
+            name: String::from("transfer"),
+            signature: String::from("transfer(address,uint256)"),
+            inputs: vec![String::from("address"), String::from("uint256")],
+            decoded_inputs: Some(vec![
+                Token::Address(H160::repeat_byte(0xaa)),
+                Token::Uint(U256::from(1000)),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_build_cast_output() {
+        assert_eq!(
+            build_cast_output(&transfer(), false),
+            "0xaAaAaAaaAaAaAaaAaAAAAAAAAaaaAaAaAaaAaaAa\n1000"
+        );
+        assert_eq!(
+            build_cast_output(&transfer(), true),
+            "1) \"transfer(address,uint256)\"\n0xaAaAaAaaAaAaAaaAaAAAAAAAAaaaAaAaAaaAaaAa\n1000"
+        );
+
+        let function = ResolvedFunction {
+            decoded_inputs: Some(vec![
+                Token::Int(U256::MAX),
+                Token::Array(vec![Token::String(String::from("a")), Token::Bool(true)]),
+                Token::Bytes(vec![0x01, 0x02]),
+            ]),
+            ..transfer()
+        };
+        assert_eq!(build_cast_output(&function, false), "-1\n[\"a\", true]\n0x0102");
+    }
+
+    #[test]
+    fn test_build_cast_command() {
+        let transaction = Transaction {
+            to: Some(H160::repeat_byte(0xbb)),
+            value: U256::from(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            build_cast_command(&transfer(), "a9059cbb", &transaction, "send"),
+            "cast send 0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB 'transfer(address,uint256)' 0xaAaAaAaaAaAaAaaAaAAAAAAAAaaaAaAaAaaAaaAa 1000 --value 5"
+        );
+
+        let unresolved = ResolvedFunction {
+            name: String::from("Unresolved_a9059cbb"),
+            signature: String::from("Unresolved_a9059cbb(address, uint256)"),
+            ..transfer()
+        };
+        assert_eq!(
+            build_cast_command(&unresolved, "a9059cbb00", &Transaction::default(), "call"),
+            "cast call <TO> 0xa9059cbb00"
+        );
+    }
+}
@@ -1 +1,3 @@
 pub mod abi;
+pub mod cast;
+pub mod table;
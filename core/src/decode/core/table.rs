@@ -0,0 +1,97 @@
+use ethers::abi::Token;
+use heimdall_common::{
+    ether::signatures::ResolvedFunction,
+    utils::io::{table::TableFormat, types::TokenNode},
+};
+
+use super::cast::format_token;
+
+/// The columns of decoded calldata as a table.
+pub const DECODE_TABLE_HEADER: [&str; 7] =
+    ["target", "selector", "signature", "index", "name", "type", "value"];
+
+/// Format decoded calldata as rows of a table, one per input, each with the target, selector, and
+/// signature it was decoded from, so many decoded targets can be filtered and joined as one table.
+/// Functions without inputs have a single row with empty input columns. Nested arrays and tuples
+/// are formatted as cast does, such as `[1, 2]`.
+pub fn build_table_rows(
+    target: &str,
+    selector: &str,
+    function: &ResolvedFunction,
+    inputs: &[TokenNode],
+    format: TableFormat,
+) -> Vec<String> {
+    let row = |index: String, name: &str, type_: &str, value: String| {
+        format.row(&[target, selector, &function.signature, &index, name, type_, &value])
+    };
+
+    let decoded_inputs = function.decoded_inputs.as_deref().unwrap_or_default();
+    if decoded_inputs.is_empty() {
+        return vec![row(String::new(), "", "", String::new())]
+    }
+
+    decoded_inputs
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            row(
+                i.to_string(),
+                inputs.get(i).and_then(|input| input.name.as_deref()).unwrap_or_default(),
+                function.inputs.get(i).map(String::as_str).unwrap_or_default(),
+                // top-level strings are written as they are, rather than quoted
+                match token {
+                    Token::String(string) => string.clone(),
+                    token => format_token(token),
+                },
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::Token,
+        types::{H160, U256},
+    };
+    use heimdall_common::{
+        ether::signatures::ResolvedFunction,
+        utils::io::{table::TableFormat, types::token_tree},
+    };
+
+    use crate::decode::core::table::build_table_rows;
+
+    #[test]
+    fn test_build_table_rows() {
+        let function = ResolvedFunction {
+            name: String::from("transfer"),
+            signature: String::from("transfer(address,uint256)"),
+            inputs: vec![String::from("address"), String::from("uint256")],
+            decoded_inputs: Some(vec![
+                Token::Address(H160::repeat_byte(0xaa)),
+                Token::Uint(U256::from(1000)),
+            ]),
+        };
+        let mut inputs = token_tree(function.decoded_inputs.as_deref().unwrap());
+        inputs[0].name = Some(String::from("to"));
+
+        assert_eq!(
+            build_table_rows("0xa9059cbb", "0xa9059cbb", &function, &inputs, TableFormat::Tsv),
+            vec![
+                "0xa9059cbb\t0xa9059cbb\ttransfer(address,uint256)\t0\tto\taddress\t0xaAaAaAaaAaAaAaaAaAAAAAAAAaaaAaAaAaaAaaAa",
+                "0xa9059cbb\t0xa9059cbb\ttransfer(address,uint256)\t1\t\tuint256\t1000",
+            ]
+        );
+
+        let function = ResolvedFunction {
+            signature: String::from("pause()"),
+            inputs: Vec::new(),
+            decoded_inputs: Some(Vec::new()),
+            ..function
+        };
+        assert_eq!(
+            build_table_rows("0x8456cb59", "0x8456cb59", &function, &[], TableFormat::Csv),
+            vec!["0x8456cb59,0x8456cb59,pause(),,,,"]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 mod core;
 mod util;
 
-use std::{collections::HashSet, time::Duration};
+use std::{collections::HashSet, path::Path};
 
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
@@ -17,21 +17,36 @@ use heimdall_common::{
         evm::core::types::{
             get_padding, get_potential_types_for_word, parse_function_parameters, to_type, Padding,
         },
-        rpc::get_transaction,
+        rpc::{chain_id, get_transaction},
         signatures::{score_signature, ResolveSelector, ResolvedFunction},
     },
+    resources::sourcify::get_verified_contract,
     utils::{
-        io::{logging::Logger, types::display},
+        io::{
+            json::json_document,
+            logging::Logger,
+            table::TableFormat,
+            types::{render, token_tree, TokenNode},
+        },
         strings::decode_hex,
     },
 };
 
-use indicatif::ProgressBar;
+use heimdall_common::utils::io::progress::spinner;
+use serde_json::json;
 use strsim::normalized_damerau_levenshtein as similarity;
 
 use crate::{
-    decode::{core::abi::is_parameter_abi_encoded, util::get_explanation},
+    decode::{
+        core::{
+            abi::is_parameter_abi_encoded,
+            cast::{build_cast_command, build_cast_output},
+            table::{build_table_rows, DECODE_TABLE_HEADER},
+        },
+        util::get_explanation,
+    },
     error::Error,
+    inspect::tenderly::TenderlyTrace,
 };
 
 #[derive(Debug, Clone, Parser, Builder)]
@@ -42,7 +57,9 @@ use crate::{
     override_usage = "heimdall decode <TARGET> [OPTIONS]"
 )]
 pub struct DecodeArgs {
-    /// The target to decode, either a transaction hash or string of bytes.
+    /// The target to decode, either a transaction hash, string of bytes, or a trace exported from
+    /// Tenderly. From the CLI, `-` decodes each line of stdin as a target, printing a line of JSON
+    /// for each.
     #[clap(required = true)]
     pub target: String,
 
@@ -50,7 +67,8 @@ pub struct DecodeArgs {
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 
-    /// The RPC provider to use for fetching target bytecode.
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
     #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
     pub rpc_url: String,
 
@@ -69,6 +87,29 @@ pub struct DecodeArgs {
     /// Whether to truncate nonstandard sized calldata.
     #[clap(long, short)]
     pub truncate_calldata: bool,
+
+    /// The format to display decoded calldata in, either `trace`, `json` for a tree of the decoded
+    /// inputs, `ndjson` for the same tree on a single line, `cast` for the layout of
+    /// `cast calldata-decode`, `cast-4byte` for the layout of `cast 4byte-decode`, or `tsv` or
+    /// `csv` for a table with a row for each input, with the target, selector, signature, and the
+    /// input's index, name, type, and value as columns.
+    #[clap(long = "format", default_value = "trace")]
+    pub format: String,
+
+    /// Whether to omit the header row of the `tsv` and `csv` formats, such as when appending to an
+    /// existing table.
+    #[clap(long = "no-header")]
+    pub no_header: bool,
+
+    /// Also print the equivalent cast command line, either `send` for `cast send`, or `call` for
+    /// `cast call`.
+    #[clap(long = "cast-command", default_value = "", hide_default_value = true)]
+    pub cast_command: String,
+
+    /// A human-readable signature to decode the calldata with rather than resolving its selector,
+    /// such as `transfer(address to, uint256 amount)`. Named parameters are labeled in the output.
+    #[clap(long = "signature", default_value = "", hide_default_value = true)]
+    pub signature: String,
 }
 
 impl DecodeArgsBuilder {
@@ -81,6 +122,10 @@ impl DecodeArgsBuilder {
             explain: Some(false),
             default: Some(true),
             truncate_calldata: Some(false),
+            format: Some(String::from("trace")),
+            no_header: Some(false),
+            cast_command: Some(String::new()),
+            signature: Some(String::new()),
         }
     }
 }
@@ -108,8 +153,16 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
 
     // check if we require an OpenAI API key
     if args.explain && args.openai_api_key.is_empty() {
-        logger.error("OpenAI API key is required for explaining calldata. Use `heimdall decode --help` for more information.");
-        std::process::exit(1);
+        return Err(Error::ParseError(String::from("OpenAI API key is required for explaining calldata. Use `heimdall decode --help` for more information.")))
+    }
+
+    if !["trace", "json", "ndjson", "cast", "cast-4byte", "tsv", "csv"]
+        .contains(&args.format.as_str())
+    {
+        return Err(Error::ParseError(format!("unknown format '{}' .", &args.format)))
+    }
+    if !["", "send", "call"].contains(&args.cast_command.as_str()) {
+        return Err(Error::ParseError(format!("unknown cast command '{}' .", &args.cast_command)))
     }
 
     // init variables
@@ -117,24 +170,30 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
     let mut calldata;
 
     // determine whether or not the target is a transaction hash
-    if TRANSACTION_HASH_REGEX.is_match(&args.target).unwrap() {
+    if TRANSACTION_HASH_REGEX.is_match(&args.target)? {
         // We are decoding a transaction hash, so we need to fetch the calldata from the RPC
         // provider.
-        raw_transaction = get_transaction(&args.target, &args.rpc_url).await.unwrap();
+        raw_transaction =
+            get_transaction(&args.target, &args.rpc_url).await.map_err(Error::from_rpc)?;
 
         calldata = raw_transaction.input.to_string().replacen("0x", "", 1);
-    } else if CALLDATA_REGEX.is_match(&args.target).unwrap() {
+    } else if Path::new(&args.target).is_file() {
+        // We are decoding an exported Tenderly trace, whose top-level call holds the calldata.
+        raw_transaction = TenderlyTrace::from_file(&args.target)?.transaction();
+
+        calldata = raw_transaction.input.to_string().replacen("0x", "", 1);
+    } else if CALLDATA_REGEX.is_match(&args.target)? {
         // We are decoding raw calldata, so we can just use the provided calldata.
         calldata = args.target.to_string().replacen("0x", "", 1);
     } else {
-        logger.error("invalid target. must be a transaction hash or calldata (bytes).");
-        std::process::exit(1);
+        return Err(Error::ResolutionError(String::from(
+            "invalid target. must be a transaction hash, calldata (bytes), or Tenderly trace.",
+        )))
     }
 
     // check if the calldata length is a standard length
     if calldata.len() % 2 != 0 || calldata.len() < 8 {
-        logger.error("calldata is not a valid hex string.");
-        std::process::exit(1);
+        return Err(Error::ParseError(String::from("calldata is not a valid hex string.")))
     }
 
     // if calldata isn't a multiple of 64, it may be harder to decode.
@@ -152,19 +211,40 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
 
     // parse the two parts of calldata, inputs and selector
     let function_selector = calldata[0..8].to_owned();
-    let byte_args = match decode_hex(&calldata[8..]) {
-        Ok(byte_args) => byte_args,
-        Err(_) => {
-            logger.error("failed to parse bytearray from calldata.");
-            std::process::exit(1)
+    let byte_args = decode_hex(&calldata[8..])?;
+
+    // get the function signature possibilities, unless a signature was given
+    let mut potential_matches = Vec::new();
+    let mut parameter_names = Vec::new();
+    if !args.signature.is_empty() {
+        let (selector, function, names) = ResolvedFunction::from_human_readable(&args.signature)?;
+        if !selector.eq_ignore_ascii_case(&function_selector) {
+            return Err(Error::ParseError(format!(
+                "signature '{}' doesn't match the selector 0x{function_selector} .",
+                function.signature
+            )))
         }
-    };
 
-    // get the function signature possibilities
-    let potential_matches = match ResolvedFunction::resolve(&function_selector).await {
-        Some(signatures) => signatures,
-        None => Vec::new(),
-    };
+        potential_matches.push(function);
+        parameter_names = names;
+    } else if let Some(signatures) = ResolvedFunction::resolve(&function_selector).await {
+        potential_matches = signatures;
+    }
+
+    // if the transaction's target is verified on Sourcify, its verified signature is used instead
+    if let (Some(to), true) = (raw_transaction.to, args.signature.is_empty()) {
+        let chain_id = chain_id(&args.rpc_url).await.unwrap_or(1);
+        if let Some(verified_contract) = get_verified_contract(chain_id, &format!("{to:#x}")).await
+        {
+            if let Some(function) = verified_contract.functions().get(&function_selector) {
+                logger.debug(&format!(
+                    "using signature '{}' verified on Sourcify.",
+                    &function.signature
+                ));
+                potential_matches = vec![function.clone()];
+            }
+        }
+    }
     if potential_matches.is_empty() {
         logger.warn("couldn't resolve potential matches for the given function selector.");
     }
@@ -248,7 +328,7 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
     }
 
     // truncate target for prettier display
-    let mut shortened_target = args.target;
+    let mut shortened_target = args.target.clone();
     if shortened_target.len() > 66 {
         shortened_target = shortened_target.chars().take(66).collect::<String>() +
             "..." +
@@ -357,10 +437,7 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
 
     let selected_match = match matches.get(selection as usize) {
         Some(selected_match) => selected_match,
-        None => {
-            logger.error("invalid selection.");
-            std::process::exit(1)
-        }
+        None => return Err(Error::ParseError(String::from("invalid selection."))),
     };
 
     let decode_call = trace.add_call(
@@ -395,25 +472,36 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
         format!("calldata: {} bytes", calldata.len() / 2usize)
     );
 
+    // each input is labeled with its parameter's name, and its components' names, if they're known
+    let mut inputs = token_tree(selected_match.decoded_inputs.as_deref().unwrap_or_default());
+    for (input, parameter) in inputs.iter_mut().zip(&parameter_names) {
+        input.label(parameter);
+    }
+
     // build inputs
-    for (i, input) in selected_match.decoded_inputs.as_ref().unwrap().iter().enumerate() {
-        let mut decoded_inputs_as_message = display(vec![input.to_owned()], "           ");
+    for (i, input) in inputs.iter().enumerate() {
+        // the parameter's name leads the input's first line, rather than being rendered with it
+        let label = input.name.as_ref().map(|name| format!("{name}: ")).unwrap_or_default();
+        let mut decoded_inputs_as_message =
+            render(&[TokenNode { name: None, ..input.clone() }], "           ");
         if decoded_inputs_as_message.is_empty() {
             break;
         }
 
         if i == 0 {
             decoded_inputs_as_message[0] = format!(
-                "input {}:{}{}",
+                "input {}:{}{}{}",
                 i,
                 " ".repeat(4 - i.to_string().len()),
+                label,
                 decoded_inputs_as_message[0].replacen("           ", "", 1)
             )
         } else {
             decoded_inputs_as_message[0] = format!(
-                "      {}:{}{}",
+                "      {}:{}{}{}",
                 i,
                 " ".repeat(4 - i.to_string().len()),
+                label,
                 decoded_inputs_as_message[0].replacen("           ", "", 1)
             )
         }
@@ -423,14 +511,56 @@ pub async fn decode(args: DecodeArgs) -> Result<Vec<ResolvedFunction>, Error> {
         decoded_string.push_str(&format!("\n{}", decoded_inputs_as_message.clone().join("\n")));
     }
 
-    // display trace (pretty print decoded calldata)
-    trace.display();
+    // display trace (pretty print decoded calldata), or the layout cast uses
+    match args.format.as_str() {
+        "cast" => println!("{}", build_cast_output(selected_match, false)),
+        "cast-4byte" => println!("{}", build_cast_output(selected_match, true)),
+        "tsv" | "csv" => {
+            let format = args.format.parse::<TableFormat>().map_err(Error::ParseError)?;
+            if !args.no_header {
+                println!("{}", format.row(&DECODE_TABLE_HEADER));
+            }
+            for row in build_table_rows(
+                &args.target,
+                &format!("0x{function_selector}"),
+                selected_match,
+                &inputs,
+                format,
+            ) {
+                println!("{row}");
+            }
+        }
+        "json" | "ndjson" => {
+            let mut document = json_document(
+                "decode",
+                &json!({
+                    "name": selected_match.name,
+                    "signature": selected_match.signature,
+                    "selector": format!("0x{function_selector}"),
+                    "inputs": inputs,
+                }),
+            )?;
+            match args.format.as_str() {
+                // lines are printed for a stream of targets, so each names its target
+                "ndjson" => {
+                    document["target"] = json!(args.target);
+                    println!("{document}")
+                }
+                _ => println!("{}", serde_json::to_string_pretty(&document)?),
+            }
+        }
+        _ => trace.display(),
+    }
+    if !args.cast_command.is_empty() {
+        println!(
+            "{}",
+            build_cast_command(selected_match, &calldata, &raw_transaction, &args.cast_command)
+        );
+    }
 
     if args.explain {
         // get a new progress bar
-        let explain_progress = ProgressBar::new_spinner();
-        explain_progress.enable_steady_tick(Duration::from_millis(100));
-        explain_progress.set_style(logger.info_spinner());
+        let explain_progress = spinner(logger.info_spinner());
         explain_progress.set_message("attempting to explain calldata...");
 
         match get_explanation(decoded_string.to_string(), raw_transaction, &args.openai_api_key)
@@ -6,7 +6,7 @@ use heimdall_common::{
     ether::evm::{
         core::{
             opcodes::WrappedOpcode,
-            types::{byte_size_to_type, convert_bitmask},
+            types::{byte_size_to_type, convert_bitmask, signed_operands},
         },
         ext::exec::VMTrace,
     },
@@ -58,6 +58,9 @@ pub fn analyze_sol(
         let opcode_name = instruction.opcode_details.clone().unwrap().name;
         let opcode_number = instruction.opcode;
 
+        // record calls to other contracts, which are reported alongside the decompiled output
+        function.add_external_call(&instruction);
+
         // if the instruction is a state-accessing instruction, the function is no longer pure
         if function.pure &&
             vec![
@@ -76,8 +79,12 @@ pub fn analyze_sol(
                 "CHAINID",
                 "SELFBALANCE",
                 "BASEFEE",
+                "BLOBHASH",
+                "BLOBBASEFEE",
                 "SLOAD",
                 "SSTORE",
+                "TLOAD",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -103,6 +110,7 @@ pub fn analyze_sol(
         if function.view &&
             [
                 "SSTORE",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -393,6 +401,17 @@ pub fn analyze_sol(
                 instruction.input_operations[0].solidify(),
                 instruction.input_operations[1].solidify(),
             ));
+        } else if opcode_name == "TSTORE" {
+            let key = instruction.inputs[0];
+            let value = instruction.inputs[1];
+
+            // add the tstore to the function's transient storage map
+            function.transient_storage.entry(key).or_default().push(value);
+            function.logic.push(format!(
+                "transient[{}] = {};",
+                instruction.input_operations[0].solidify(),
+                instruction.input_operations[1].solidify(),
+            ));
         } else if opcode_name.contains("MSTORE") {
             let key = instruction.inputs[0];
             let value = instruction.inputs[1];
@@ -639,11 +658,13 @@ pub fn analyze_sol(
                         frame.operation == calldata_slot_operation.inputs[0].to_string()
                     })
                 {
-                    // append the current potential types to the new vector and remove
-                    // duplicates
-                    potential_types.append(&mut arg.1.clone());
-                    potential_types.sort();
-                    potential_types.dedup();
+                    // keep the mask's candidates first, as they're ordered by likelihood, followed
+                    // by any other types the argument could be
+                    for potential_type in &arg.1 {
+                        if !potential_types.contains(potential_type) {
+                            potential_types.push(potential_type.clone());
+                        }
+                    }
 
                     // replace mask size and potential types
                     function.arguments.insert(
@@ -662,6 +683,24 @@ pub fn analyze_sol(
             };
         }
 
+        // signed operations imply a signed integer type for the arguments they operate on
+        for (operand, byte_size) in signed_operands(&instruction) {
+            if operand.opcode.code != 0x35 {
+                continue
+            }
+            let slot = operand.inputs.first().map(|input| input.to_string());
+            for (frame, potential_types) in function.arguments.values_mut() {
+                if slot.as_ref() != Some(&frame.operation) {
+                    continue
+                }
+
+                let signed_type = format!("int{}", byte_size * 8);
+                potential_types.retain(|potential_type| potential_type != &signed_type);
+                potential_types.insert(0, signed_type);
+                frame.mask_size = byte_size;
+            }
+        }
+
         // handle type heuristics
         if [
             "MUL",
@@ -9,6 +9,9 @@ lazy_static! {
     /// The following regex is used as a detector for AND bitmasks
     pub static ref AND_BITMASK_REGEX_2: Regex = Regex::new(r" & \(0x([a-fA-F0-9]{2}){1,32}\)").unwrap();
 
+    /// used to detect a function's arguments, which are named by their calldata index
+    pub static ref ARGUMENT_REGEX: Regex = Regex::new(r"\barg(\d+)\b").unwrap();
+
     /// used to detect constant values
     pub static ref CONSTANT_REGEX: Regex = Regex::new(r"^(?:(?![memorystorage\[\]]).)*$").unwrap();
 
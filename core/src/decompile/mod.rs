@@ -8,29 +8,56 @@ pub mod util;
 use crate::{
     decompile::{
         analyzers::{solidity::analyze_sol, yul::analyze_yul},
-        out::{abi::build_abi, solidity::build_solidity_output, yul::build_yul_output},
+        out::{
+            abi::{build_abi, build_verified_abi, label_abi},
+            artifact::build_hardhat_artifact,
+            slither::build_slither_output,
+            solidity::{build_solidity_output, label_parameters},
+            storage::{build_storage_layout, StorageVariable},
+            yul::build_yul_output,
+        },
         resolve::*,
         util::*,
     },
     disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    utils::get_contract_bytecode,
 };
-
 use derive_builder::Builder;
 use heimdall_common::{
     ether::{
+        chains::chain_by_id,
         compiler::detect_compiler,
-        rpc::get_code,
+        eof::is_eof,
+        evm::ext::exec::LimitReached,
+        rpc::chain_id,
         selectors::{find_function_selectors, resolve_selectors},
     },
-    utils::strings::encode_hex_reduced,
+    resources::{
+        etherscan::get_abi,
+        sourcify::{get_verified_contract, VerifiedContract},
+    },
+    utils::{
+        determinism::deterministic,
+        io::progress::Progress,
+        strings::{decode_hex, encode_hex_reduced},
+    },
 };
-use indicatif::ProgressBar;
-use std::{collections::HashMap, fs, time::Duration};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::debug_span;
 
 use clap::{AppSettings, Parser};
 use heimdall_common::{
-    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
-    ether::{evm::core::vm::VM, signatures::*},
+    constants::ADDRESS_REGEX,
+    ether::{
+        evm::{
+            core::{hardfork::Hardfork, vm::VM},
+            ext::exec::ExecutionLimits,
+        },
+        signatures::*,
+    },
     utils::io::logging::*,
 };
 
@@ -44,7 +71,11 @@ use self::out::abi::ABIStructure;
     override_usage = "heimdall decompile <TARGET> [OPTIONS]"
 )]
 pub struct DecompilerArgs {
-    /// The target to decompile, either a file, bytecode, contract address, or ENS name.
+    /// The target to decompile, either a file, bytecode, contract address, ENS name, or
+    /// `-` to read bytecode from stdin. From the CLI, `-` decompiles each line of stdin as a
+    /// target instead, printing a line of JSON for each. Files may be forge, hardhat, or solc
+    /// artifacts, whose deployed bytecode is used, with `artifact.json:Name` selecting one of
+    /// several contracts.
     #[clap(required = true)]
     pub target: String,
 
@@ -52,7 +83,8 @@ pub struct DecompilerArgs {
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 
-    /// The RPC provider to use for fetching target bytecode.
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
     #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
     pub rpc_url: String,
 
@@ -72,6 +104,36 @@ pub struct DecompilerArgs {
     #[clap(long = "include-yul")]
     pub include_yul: bool,
 
+    /// Your Etherscan API key, used to label the output with the target's verified ABI. Separate
+    /// multiple keys with commas to rotate between them when one is rate limited.
+    #[clap(long = "etherscan-api-key", default_value = "", hide_default_value = true)]
+    pub etherscan_api_key: String,
+
+    /// Whether to decompile contracts which are verified on Sourcify with a full match, rather
+    /// than using their verified ABI and source.
+    #[clap(long = "force-decompile")]
+    pub force_decompile: bool,
+
+    /// Whether to also output the decompiled functions, storage variables, external calls, and
+    /// warnings in slither's JSON format.
+    #[clap(long = "slither")]
+    pub slither: bool,
+
+    /// Whether to also output a Hardhat artifact with the decompiled ABI and the contract's
+    /// bytecode, which Hardhat and Foundry can deploy.
+    #[clap(long = "include-artifact")]
+    pub include_artifact: bool,
+
+    /// A human-readable signature to name a function with, such as `transfer(address to, uint256
+    /// amount)`, taking precedence over resolved and verified signatures. Named parameters are
+    /// labeled in the decompiled source. May be given more than once.
+    #[clap(long = "signature")]
+    pub signatures: Vec<String>,
+
+    /// Limits on how much of each function symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
     /// The output directory to write the output to or 'print' to print to the console
     #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
     pub output: String,
@@ -87,20 +149,37 @@ impl DecompilerArgsBuilder {
             skip_resolving: Some(false),
             include_solidity: Some(false),
             include_yul: Some(false),
+            etherscan_api_key: Some(String::new()),
+            force_decompile: Some(false),
+            slither: Some(false),
+            include_artifact: Some(false),
+            signatures: Some(Vec::new()),
+            limits: Some(ExecutionLimits::default()),
             output: Some(String::new()),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DecompileResult {
     pub source: Option<String>,
     pub abi: Option<Vec<ABIStructure>>,
+
+    // the storage variables recovered from the slots the contract writes to
+    pub storage_layout: Vec<StorageVariable>,
+
+    // the analysis results in slither's JSON format, if requested
+    pub slither: Option<Value>,
+
+    // the contract's ABI and bytecode as a Hardhat artifact, if requested
+    pub artifact: Option<Value>,
+
+    // the selectors of functions whose symbolic execution stopped early, and the limit each
+    // reached
+    pub limits_reached: Vec<(String, LimitReached)>,
 }
 
-pub async fn decompile(
-    args: DecompilerArgs,
-) -> Result<DecompileResult, Box<dyn std::error::Error>> {
+pub async fn decompile(args: DecompilerArgs) -> Result<DecompileResult, Error> {
     use std::time::Instant;
     let now = Instant::now();
 
@@ -123,11 +202,21 @@ pub async fn decompile(
 
     let mut all_resolved_events: HashMap<String, ResolvedLog> = HashMap::new();
     let mut all_resolved_errors: HashMap<String, ResolvedError> = HashMap::new();
+    let mut limits_reached = Vec::new();
 
     // ensure both --include-sol and --include-yul aren't set
     if args.include_solidity && args.include_yul {
-        logger.error("arguments '--include-sol' and '--include-yul' are mutually exclusive.");
-        std::process::exit(1);
+        return Err(Error::ParseError(String::from(
+            "arguments '--include-sol' and '--include-yul' are mutually exclusive.",
+        )))
+    }
+
+    // functions given by their human-readable signatures, by selector, along with the names of
+    // their parameters
+    let mut given_functions = HashMap::new();
+    for signature in &args.signatures {
+        let (selector, function, names) = ResolvedFunction::from_human_readable(signature)?;
+        given_functions.insert(selector, (function, names));
     }
 
     // truncate target for prettier display
@@ -146,36 +235,52 @@ pub async fn decompile(
         "()".to_string(),
     );
 
+    // contracts verified on Sourcify or Etherscan have a known ABI, which is used to name their
+    // functions, errors, events, and parameters. if the contract is a full match on Sourcify, its
+    // verified source is used instead
+    let mut verified_contract = None;
+    if ADDRESS_REGEX.is_match(&args.target)? && !args.skip_resolving {
+        let chain_id = chain_id(&args.rpc_url).await.unwrap_or(1);
+        verified_contract = get_verified_contract(chain_id, &args.target).await;
+
+        if verified_contract.is_none() && !args.etherscan_api_key.is_empty() {
+            let api = chain_by_id(chain_id)
+                .map(|chain| chain.explorer_api)
+                .unwrap_or("https://api.etherscan.io/api");
+            verified_contract = get_abi(api, &args.target, &args.etherscan_api_key)
+                .await
+                .map(VerifiedContract::from);
+        }
+    }
+    if let Some(verified_contract) = &verified_contract {
+        if verified_contract.full_match && !args.force_decompile {
+            logger.info(&format!(
+                "'{}' is verified on Sourcify, so its verified ABI and source are used. use `--force-decompile` to decompile it anyway.",
+                &args.target
+            ));
+            return Ok(DecompileResult {
+                source: args.include_solidity.then(|| verified_contract.source()),
+                abi: Some(build_verified_abi(&verified_contract.abi)),
+                storage_layout: Vec::new(),
+                slither: None,
+                artifact: None,
+                limits_reached: Vec::new(),
+            })
+        }
+        logger.info(
+            "using the target's verified ABI to name functions, errors, events, and parameters.",
+        );
+    }
+
     // parse the various formats that are accepted as targets
     // i.e, file, bytecode, contract address
-    let contract_bytecode: String;
-    if ADDRESS_REGEX.is_match(&args.target)? {
-        // We are decompiling a contract address, so we need to fetch the bytecode from the RPC
-        // provider
-        contract_bytecode = get_code(&args.target, &args.rpc_url).await?;
-    } else if BYTECODE_REGEX.is_match(&args.target)? {
-        logger.debug_max("using provided bytecode for decompilation");
-        contract_bytecode = args.target.clone().replacen("0x", "", 1);
-    } else {
-        logger.debug_max("using provided file for decompilation.");
-
-        // We are decompiling a file, so we need to read the bytecode from the file.
-        contract_bytecode = match fs::read_to_string(&args.target) {
-            Ok(contents) => {
-                let _contents = contents.replace('\n', "");
-                if BYTECODE_REGEX.is_match(&_contents)? && _contents.len() % 2 == 0 {
-                    _contents.replacen("0x", "", 1)
-                } else {
-                    logger
-                        .error(&format!("file '{}' doesn't contain valid bytecode.", &args.target));
-                    std::process::exit(1)
-                }
-            }
-            Err(_) => {
-                logger.error(&format!("failed to open file '{}' .", &args.target));
-                std::process::exit(1)
-            }
-        };
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // symbolic execution only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by the decompiler. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
     }
 
     // disassemble the bytecode
@@ -185,6 +290,10 @@ pub async fn decompile(
         rpc_url: args.rpc_url.clone(),
         decimal_counter: false,
         output: String::from(""),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: Hardfork::default(),
     })
     .await?;
     trace.add_call(
@@ -245,6 +354,23 @@ pub async fn decompile(
     if !args.skip_resolving {
         resolved_selectors = resolve_selectors(selectors.keys().cloned().collect()).await;
 
+        // verified signatures take precedence over any resolved ones
+        if let Some(verified_contract) = &verified_contract {
+            let verified_functions = verified_contract.functions();
+            for selector in selectors.keys() {
+                if let Some(function) = verified_functions.get(selector) {
+                    resolved_selectors.insert(selector.clone(), vec![function.clone()]);
+                }
+            }
+        }
+
+        // as do the signatures which were given
+        for (selector, (function, _)) in &given_functions {
+            if selectors.contains_key(selector) {
+                resolved_selectors.insert(selector.clone(), vec![function.clone()]);
+            }
+        }
+
         // if resolved selectors are empty, we can't perform symbolic execution
         if resolved_selectors.is_empty() {
             logger.error(&format!(
@@ -265,13 +391,20 @@ pub async fn decompile(
     logger.info(&format!("performing symbolic execution on '{shortened_target}' ."));
 
     // get a new progress bar
-    let mut decompilation_progress = ProgressBar::new_spinner();
-    decompilation_progress.enable_steady_tick(Duration::from_millis(100));
-    decompilation_progress.set_style(logger.info_spinner());
+    let decompilation_progress = Progress::new("decompile", selectors.len() as u64, &logger);
+
+    // in deterministic mode, functions are analyzed in selector order, which also fixes the
+    // order their variables are named in
+    let mut selectors = selectors.into_iter().collect::<Vec<_>>();
+    if deterministic() {
+        selectors.sort();
+    }
 
     // perform EVM analysis
     let mut analyzed_functions = Vec::new();
     for (selector, function_entry_point) in selectors {
+        // the bar is hidden while a function's errors and events are resolved
+        decompilation_progress.show();
         decompilation_progress.set_message(format!("executing '0x{selector}'"));
 
         let func_analysis_trace = trace.add_call(
@@ -290,8 +423,20 @@ pub async fn decompile(
         );
 
         // get a map of possible jump destinations
-        let (map, jumpdest_count) =
-            &evm.clone().symbolic_exec_selector(&selector, function_entry_point);
+        let (map, jumpdest_count, limit_reached) =
+            &evm.clone().symbolic_exec_selector(&selector, function_entry_point, &args.limits);
+        if let Some(limit) = limit_reached {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' stopped early after reaching the {limit}."
+            ));
+            limits_reached.push((selector.clone(), *limit));
+        }
+        for fault in map.faults() {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' faulted: {}",
+                Error::from(fault)
+            ));
+        }
 
         trace.add_debug(
             func_analysis_trace,
@@ -312,62 +457,76 @@ pub async fn decompile(
         // analyze execution tree
         let mut analyzed_function;
         if args.include_yul {
-            logger.debug_max(&format!(
-                "analyzing symbolic execution trace '0x{}' with yul analyzer",
-                selector
-            ));
-            analyzed_function = analyze_yul(
-                map,
-                Function {
-                    selector: selector.clone(),
-                    entry_point: function_entry_point,
-                    arguments: HashMap::new(),
-                    storage: HashMap::new(),
-                    memory: HashMap::new(),
-                    returns: None,
-                    logic: Vec::new(),
-                    events: HashMap::new(),
-                    errors: HashMap::new(),
-                    resolved_function: None,
-                    indent_depth: 0,
-                    notices: Vec::new(),
-                    pure: true,
-                    view: true,
-                    payable: true,
-                },
-                &mut trace,
-                func_analysis_trace,
-                &mut Vec::new(),
-            );
+            analyzed_function = debug_span!("analyze_yul", selector = %selector).in_scope(|| {
+                analyze_yul(
+                    map,
+                    Function {
+                        selector: selector.clone(),
+                        entry_point: function_entry_point,
+                        arguments: HashMap::new(),
+                        storage: HashMap::new(),
+                        transient_storage: HashMap::new(),
+                        memory: HashMap::new(),
+                        returns: None,
+                        logic: Vec::new(),
+                        events: HashMap::new(),
+                        errors: HashMap::new(),
+                        resolved_function: None,
+                        indent_depth: 0,
+                        notices: Vec::new(),
+                        external_calls: Vec::new(),
+                        pure: true,
+                        view: true,
+                        payable: true,
+                    },
+                    &mut trace,
+                    func_analysis_trace,
+                    &mut Vec::new(),
+                )
+            });
         } else {
-            logger.debug_max(&format!(
-                "analyzing symbolic execution trace '0x{}' with sol analyzer",
-                selector
+            analyzed_function = debug_span!("analyze_sol", selector = %selector).in_scope(|| {
+                analyze_sol(
+                    map,
+                    Function {
+                        selector: selector.clone(),
+                        entry_point: function_entry_point,
+                        arguments: HashMap::new(),
+                        storage: HashMap::new(),
+                        transient_storage: HashMap::new(),
+                        memory: HashMap::new(),
+                        returns: None,
+                        logic: Vec::new(),
+                        events: HashMap::new(),
+                        errors: HashMap::new(),
+                        resolved_function: None,
+                        indent_depth: 0,
+                        notices: Vec::new(),
+                        external_calls: Vec::new(),
+                        pure: true,
+                        view: true,
+                        payable: true,
+                    },
+                    &mut trace,
+                    func_analysis_trace,
+                    &mut Vec::new(),
+                    (0, 0),
+                )
+            });
+        }
+
+        if let Some(limit) = limit_reached {
+            analyzed_function.notices.push(format!(
+                "symbolic execution stopped early after reaching the {limit}, so some paths may be missing"
+            ));
+        }
+
+        // transient storage slots which are set and later cleared are likely reentrancy locks
+        for slot in analyzed_function.get_transient_locks() {
+            analyzed_function.notices.push(format!(
+                "transient storage slot {} is used as a reentrancy lock",
+                encode_hex_reduced(slot)
             ));
-            analyzed_function = analyze_sol(
-                map,
-                Function {
-                    selector: selector.clone(),
-                    entry_point: function_entry_point,
-                    arguments: HashMap::new(),
-                    storage: HashMap::new(),
-                    memory: HashMap::new(),
-                    returns: None,
-                    logic: Vec::new(),
-                    events: HashMap::new(),
-                    errors: HashMap::new(),
-                    resolved_function: None,
-                    indent_depth: 0,
-                    notices: Vec::new(),
-                    pure: true,
-                    view: true,
-                    payable: true,
-                },
-                &mut trace,
-                func_analysis_trace,
-                &mut Vec::new(),
-                (0, 0),
-            );
         }
 
         let argument_count = analyzed_function.arguments.len();
@@ -478,11 +637,11 @@ pub async fn decompile(
                 }
             }
 
-            decompilation_progress.finish_and_clear();
+            decompilation_progress.hide();
 
             // resolve custom error signatures
             let mut resolved_counter = 0;
-            let resolved_errors: HashMap<String, Vec<ResolvedError>> = resolve_selectors(
+            let mut resolved_errors: HashMap<String, Vec<ResolvedError>> = resolve_selectors(
                 analyzed_function
                     .errors
                     .keys()
@@ -490,6 +649,16 @@ pub async fn decompile(
                     .collect(),
             )
             .await;
+            if let Some(verified_contract) = &verified_contract {
+                for (error_selector, error) in verified_contract.errors() {
+                    if analyzed_function.errors.contains_key(&error_selector) {
+                        resolved_errors.insert(
+                            encode_hex_reduced(error_selector).replacen("0x", "", 1),
+                            vec![error],
+                        );
+                    }
+                }
+            }
             for (error_selector, _) in analyzed_function.errors.clone() {
                 let error_selector_str = encode_hex_reduced(error_selector).replacen("0x", "", 1);
                 let mut selected_error_index: u8 = 0;
@@ -548,7 +717,7 @@ pub async fn decompile(
 
             // resolve custom event signatures
             resolved_counter = 0;
-            let resolved_events: HashMap<String, Vec<ResolvedLog>> = resolve_selectors(
+            let mut resolved_events: HashMap<String, Vec<ResolvedLog>> = resolve_selectors(
                 analyzed_function
                     .events
                     .keys()
@@ -556,6 +725,16 @@ pub async fn decompile(
                     .collect(),
             )
             .await;
+            if let Some(verified_contract) = &verified_contract {
+                for (event_selector, event) in verified_contract.events() {
+                    if analyzed_function.events.contains_key(&event_selector) {
+                        resolved_events.insert(
+                            encode_hex_reduced(event_selector).replacen("0x", "", 1),
+                            vec![event],
+                        );
+                    }
+                }
+            }
             for (event_selector, (_, raw_event)) in analyzed_function.events.clone() {
                 let mut selected_event_index: u8 = 0;
                 let event_selector_str = encode_hex_reduced(event_selector).replacen("0x", "", 1);
@@ -613,24 +792,28 @@ pub async fn decompile(
             }
         }
 
-        // get a new progress bar
-        decompilation_progress = ProgressBar::new_spinner();
-        decompilation_progress.enable_steady_tick(Duration::from_millis(100));
-        decompilation_progress.set_style(logger.info_spinner());
-
         analyzed_functions.push(analyzed_function.clone());
+        decompilation_progress.inc(1);
     }
     decompilation_progress.finish_and_clear();
     logger.info("symbolic execution completed.");
     logger.info("building decompilation output.");
 
-    let abi = build_abi(&args, analyzed_functions.clone(), &mut trace, decompile_call)?;
+    let mut abi = build_abi(&args, analyzed_functions.clone(), &mut trace, decompile_call)?;
+    if let Some(verified_contract) = &verified_contract {
+        label_abi(&mut abi, &verified_contract.abi);
+    }
+    let storage_layout = build_storage_layout(&analyzed_functions, &evm.preimages);
+    let slither = args
+        .slither
+        .then(|| build_slither_output(&args.target, &analyzed_functions, &storage_layout));
+    let artifact = args.include_artifact.then(|| build_hardhat_artifact(&abi, &contract_bytecode));
     trace.display();
     logger.debug(&format!("decompilation completed in {:?}.", now.elapsed()));
 
     Ok(DecompileResult {
         source: if args.include_solidity {
-            Some(build_solidity_output(
+            let source = build_solidity_output(
                 &args,
                 &abi,
                 analyzed_functions,
@@ -638,7 +821,16 @@ pub async fn decompile(
                 all_resolved_events,
                 &mut trace,
                 decompile_call,
-            )?)
+            )?;
+            // parameters are labeled with their verified names, or the names they were given
+            let mut parameter_names = verified_contract
+                .as_ref()
+                .map(VerifiedContract::parameter_names)
+                .unwrap_or_default();
+            parameter_names.extend(given_functions.into_iter().map(|(selector, (_, names))| {
+                (selector, names.into_iter().map(|parameter| parameter.name).collect())
+            }));
+            Some(label_parameters(&source, &parameter_names))
         } else if args.include_yul {
             Some(build_yul_output(
                 &args,
@@ -651,5 +843,9 @@ pub async fn decompile(
             None
         },
         abi: Some(abi),
+        storage_layout,
+        slither,
+        artifact,
+        limits_reached,
     })
 }
@@ -1,14 +1,19 @@
-use std::time::Duration;
-
-use ethers::abi::AbiEncode;
-use heimdall_common::utils::io::{
-    file::short_path,
-    logging::{Logger, TraceFactory},
+use ethers::abi::{Abi, AbiEncode, StateMutability};
+use heimdall_common::utils::{
+    determinism::deterministic,
+    io::{
+        file::short_path,
+        logging::{Logger, TraceFactory},
+        progress::spinner,
+    },
 };
-use indicatif::ProgressBar;
 use serde::{Deserialize, Serialize};
+use tracing::instrument;
 
-use crate::decompile::{util::Function, DecompilerArgs};
+use crate::{
+    decompile::{util::Function, DecompilerArgs},
+    error::Error,
+};
 
 /// A single named ABI token.
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -69,19 +74,18 @@ pub enum ABIStructure {
 ///
 /// # Returns
 /// A [`Vec`] of [`ABIStructure`]s representing the contract's ABI.
+#[instrument(level = "debug", skip_all)]
 pub fn build_abi(
     args: &DecompilerArgs,
     functions: Vec<Function>,
     trace: &mut TraceFactory,
     trace_parent: u32,
-) -> Result<Vec<ABIStructure>, Box<dyn std::error::Error>> {
+) -> Result<Vec<ABIStructure>, Error> {
     // get a new logger
     let logger = Logger::default();
 
     // get a new progress bar
-    let progress_bar = ProgressBar::new_spinner();
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-    progress_bar.set_style(logger.info_spinner());
+    let progress_bar = spinner(logger.info_spinner());
 
     // truncate target for prettier display
     let mut shortened_target = args.target.clone();
@@ -141,9 +145,11 @@ pub fn build_abi(
                 let mut inputs = Vec::new();
                 let mut outputs = Vec::new();
 
-                for (index, (_, (_, potential_types))) in
-                    function.arguments.clone().iter().enumerate()
-                {
+                // sort arguments by their calldata index
+                let mut sorted_arguments: Vec<_> = function.arguments.iter().collect();
+                sorted_arguments.sort_by_key(|(index, _)| **index);
+
+                for (index, (_, (_, potential_types))) in sorted_arguments.into_iter().enumerate() {
                     inputs.push(ABIToken {
                         name: format!("arg{index}"),
                         internal_type: potential_types[0].to_owned(),
@@ -190,8 +196,12 @@ pub fn build_abi(
             constant,
         }));
 
-        // build the function's custom errors
-        for (error_selector, resolved_error) in &function.errors {
+        // build the function's custom errors, in selector order in deterministic mode
+        let mut errors: Vec<_> = function.errors.iter().collect();
+        if deterministic() {
+            errors.sort_by_key(|(error_selector, _)| **error_selector);
+        }
+        for (error_selector, resolved_error) in errors {
             progress_bar.set_message(format!("building ABI for '0x{error_selector}'"));
 
             match resolved_error {
@@ -249,8 +259,12 @@ pub fn build_abi(
             }
         }
 
-        // build the function's events
-        for (event_selector, (resolved_event, _)) in &function.events {
+        // build the function's events, in selector order in deterministic mode
+        let mut events: Vec<_> = function.events.iter().collect();
+        if deterministic() {
+            events.sort_by_key(|(event_selector, _)| **event_selector);
+        }
+        for (event_selector, (resolved_event, _)) in events {
             progress_bar.set_message(format!("building ABI for '0x{event_selector}'"));
 
             match resolved_event {
@@ -311,3 +325,112 @@ pub fn build_abi(
 
     Ok(abi)
 }
+
+/// Convert a verified contract's ABI, such as one fetched from Sourcify, into [`ABIStructure`]s,
+/// keeping its real parameter names.
+pub fn build_verified_abi(verified_abi: &Abi) -> Vec<ABIStructure> {
+    let token = |name: &str, internal_type: Option<&String>, type_: String| ABIToken {
+        name: name.to_string(),
+        internal_type: internal_type.cloned().unwrap_or_else(|| type_.clone()),
+        type_,
+    };
+
+    let mut abi: Vec<ABIStructure> = Vec::new();
+    for function in verified_abi.functions() {
+        let state_mutability = match function.state_mutability {
+            StateMutability::Pure => "pure",
+            StateMutability::View => "view",
+            StateMutability::NonPayable => "nonpayable",
+            StateMutability::Payable => "payable",
+        };
+
+        abi.push(ABIStructure::Function(FunctionABI {
+            type_: "function".to_string(),
+            name: function.name.clone(),
+            inputs: function
+                .inputs
+                .iter()
+                .map(|p| token(&p.name, p.internal_type.as_ref(), p.kind.to_string()))
+                .collect(),
+            outputs: function
+                .outputs
+                .iter()
+                .map(|p| token(&p.name, p.internal_type.as_ref(), p.kind.to_string()))
+                .collect(),
+            state_mutability: state_mutability.to_string(),
+            constant: matches!(state_mutability, "pure" | "view"),
+        }));
+    }
+    for error in verified_abi.errors() {
+        abi.push(ABIStructure::Error(ErrorABI {
+            type_: "error".to_string(),
+            name: error.name.clone(),
+            inputs: error
+                .inputs
+                .iter()
+                .map(|p| token(&p.name, p.internal_type.as_ref(), p.kind.to_string()))
+                .collect(),
+        }));
+    }
+    for event in verified_abi.events() {
+        abi.push(ABIStructure::Event(EventABI {
+            type_: "event".to_string(),
+            name: event.name.clone(),
+            inputs: event.inputs.iter().map(|p| token(&p.name, None, p.kind.to_string())).collect(),
+        }));
+    }
+
+    abi
+}
+
+/// Label a decompiled contract's ABI with the parameter names of its verified ABI, such as one
+/// fetched from Etherscan. Functions, errors, and events are matched by name and parameter types,
+/// and those which aren't in the verified ABI are left as-is.
+pub fn label_abi(abi: &mut [ABIStructure], verified_abi: &Abi) {
+    let types = |tokens: &[ABIToken]| tokens.iter().map(|t| t.type_.clone()).collect::<Vec<_>>();
+    let label = |tokens: &mut [ABIToken], names: Vec<&String>| {
+        for (token, name) in tokens.iter_mut().zip(names) {
+            if !name.is_empty() {
+                token.name = name.clone();
+            }
+        }
+    };
+
+    for item in abi.iter_mut() {
+        match item {
+            ABIStructure::Function(function) => {
+                if let Some(verified) = verified_abi.functions().find(|f| {
+                    f.name == function.name &&
+                        f.inputs.iter().map(|p| p.kind.to_string()).collect::<Vec<_>>() ==
+                            types(&function.inputs)
+                }) {
+                    label(&mut function.inputs, verified.inputs.iter().map(|p| &p.name).collect());
+                    if verified.outputs.len() == function.outputs.len() {
+                        label(
+                            &mut function.outputs,
+                            verified.outputs.iter().map(|p| &p.name).collect(),
+                        );
+                    }
+                }
+            }
+            ABIStructure::Error(error) => {
+                if let Some(verified) = verified_abi.errors().find(|e| {
+                    e.name == error.name &&
+                        e.inputs.iter().map(|p| p.kind.to_string()).collect::<Vec<_>>() ==
+                            types(&error.inputs)
+                }) {
+                    label(&mut error.inputs, verified.inputs.iter().map(|p| &p.name).collect());
+                }
+            }
+            ABIStructure::Event(event) => {
+                if let Some(verified) = verified_abi.events().find(|e| {
+                    e.name == event.name &&
+                        e.inputs.iter().map(|p| p.kind.to_string()).collect::<Vec<_>>() ==
+                            types(&event.inputs)
+                }) {
+                    label(&mut event.inputs, verified.inputs.iter().map(|p| &p.name).collect());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use serde_json::{json, Value};
+
+use crate::decompile::out::abi::ABIStructure;
+
+// decompiled contracts are named as they are in the solidity output
+const CONTRACT_NAME: &str = "DecompiledContract";
+
+/// Build a Hardhat artifact for the decompiled contract, so it can be deployed and called from
+/// existing test harnesses and deployment scripts. The original creation code isn't known, so the
+/// artifact's bytecode is a minimal constructor which deploys the runtime bytecode as it is.
+pub fn build_hardhat_artifact(abi: &[ABIStructure], runtime_bytecode: &str) -> Value {
+    let runtime_bytecode = runtime_bytecode.trim_start_matches("0x");
+    json!({
+        "_format": "hh-sol-artifact-1",
+        "contractName": CONTRACT_NAME,
+        "sourceName": format!("contracts/{CONTRACT_NAME}.sol"),
+        "abi": abi
+            .iter()
+            .map(|entry| match entry {
+                ABIStructure::Function(function) => json!(function),
+                ABIStructure::Error(error) => json!(error),
+                ABIStructure::Event(event) => json!(event),
+            })
+            .collect::<Vec<_>>(),
+        "bytecode": format!("0x{}{runtime_bytecode}", deployer(runtime_bytecode.len() / 2)),
+        "deployedBytecode": format!("0x{runtime_bytecode}"),
+        "linkReferences": {},
+        "deployedLinkReferences": {},
+
+        // there's no compiler metadata for decompiled contracts
+        "metadata": "{}",
+    })
+}
+
+/// builds init code which returns the `size` bytes of runtime bytecode which follow it
+fn deployer(size: usize) -> String {
+    // PUSH2 size, DUP1, PUSH1 0x0c, PUSH1 0x00, CODECOPY, PUSH1 0x00, RETURN
+    format!("61{:04x}80600c6000396000f3", size.min(0xffff))
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::ether::evm::core::vm::VM;
+
+    use crate::decompile::out::{
+        abi::{ABIStructure, FunctionABI},
+        artifact::build_hardhat_artifact,
+    };
+
+    #[test]
+    fn test_build_hardhat_artifact() {
+        let abi = vec![ABIStructure::Function(FunctionABI {
+            type_: String::from("function"),
+            name: String::from("Unresolved_a9059cbb"),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            state_mutability: String::from("nonpayable"),
+            constant: false,
+        })];
+        let artifact = build_hardhat_artifact(&abi, "0x6001600055");
+
+        assert_eq!(artifact["_format"], "hh-sol-artifact-1");
+        assert_eq!(artifact["abi"][0]["name"], "Unresolved_a9059cbb");
+        assert_eq!(artifact["deployedBytecode"], "0x6001600055");
+
+        // running the bytecode returns the runtime bytecode
+        let bytecode = artifact["bytecode"].as_str().unwrap().to_string();
+        let mut vm = VM::new(
+            bytecode,
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        );
+        let result = vm.execute();
+        assert_eq!(result.returndata, vec![0x60, 0x01, 0x60, 0x00, 0x55]);
+    }
+}
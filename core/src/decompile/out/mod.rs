@@ -1,4 +1,7 @@
 pub mod abi;
+pub mod artifact;
 pub mod postprocessers;
+pub mod slither;
 pub mod solidity;
+pub mod storage;
 pub mod yul;
@@ -9,9 +9,12 @@ use heimdall_common::{
         evm::core::types::{byte_size_to_type, find_cast},
         signatures::{ResolvedError, ResolvedLog},
     },
-    utils::strings::{
-        base26_encode, classify_token, find_balanced_encapsulator,
-        find_balanced_encapsulator_backwards, tokenize, TokenType,
+    utils::{
+        determinism::deterministic,
+        strings::{
+            base26_encode, classify_token, find_balanced_encapsulator,
+            find_balanced_encapsulator_backwards, tokenize, TokenType,
+        },
     },
 };
 use indicatif::ProgressBar;
@@ -421,8 +424,8 @@ fn contains_unnecessary_assignment(line: &str, lines: &Vec<&str>) -> bool {
     let var_name = line.split(" = ").collect::<Vec<&str>>()[0].split(' ').collect::<Vec<&str>>()
         [line.split(" = ").collect::<Vec<&str>>()[0].split(' ').collect::<Vec<&str>>().len() - 1];
 
-    // skip lines that contain assignments to storage
-    if var_name.contains("stor_") {
+    // skip lines that contain assignments to storage or transient storage
+    if var_name.contains("stor_") || var_name.starts_with("transient[") {
         return false
     }
 
@@ -920,6 +923,13 @@ pub fn postprocess(
     let mut function_count = 0;
     let mut cleaned_lines: Vec<String> = Vec::new();
 
+    // storage variables are named in the order they're found, so in deterministic mode the names
+    // left over from previous contracts are forgotten
+    if deterministic() {
+        STORAGE_LOOKUP_MAP.lock().unwrap().clear();
+        STORAGE_TYPE_MAP.lock().unwrap().clear();
+    }
+
     // clean up each line using postprocessing techniques
     for line in lines {
         // update progress bar
@@ -0,0 +1,257 @@
+use ethers::utils::keccak256;
+use heimdall_common::utils::strings::{encode_hex, encode_hex_reduced};
+use serde_json::{json, Value};
+
+use crate::decompile::{out::storage::StorageVariable, util::Function};
+
+// decompiled contracts are named as they are in the solidity output
+const CONTRACT_NAME: &str = "DecompiledContract";
+
+/// Build the decompiler's results in slither's JSON output format, so tools which consume
+/// slither's findings can consume heimdall's. External calls and decompiler notices are reported
+/// as detector findings, and the contract's functions and storage variables as printer output.
+/// Since there's no source, each element's source mapping points at its offset in the bytecode.
+pub fn build_slither_output(
+    target: &str,
+    functions: &[Function],
+    storage_layout: &[StorageVariable],
+) -> Value {
+    // bytecode targets are too long to be used as a file name
+    let filename = match target.len() > 66 {
+        true => "bytecode",
+        false => target,
+    };
+
+    let mut functions = functions.iter().collect::<Vec<_>>();
+    functions.sort_by(|a, b| a.selector.cmp(&b.selector));
+
+    let mut detectors = Vec::new();
+    for function in &functions {
+        for call in &function.external_calls {
+            let (check, impact, confidence, description) = match call.opcode.as_str() {
+                "DELEGATECALL" | "CALLCODE" => (
+                    "heimdall-delegatecall",
+                    "Medium",
+                    "Medium",
+                    format!(
+                        "{} delegates to address({}) at instruction {}, which runs another contract's code against this contract's storage",
+                        function_signature(function),
+                        call.target,
+                        call.instruction
+                    ),
+                ),
+                _ => (
+                    "heimdall-external-call",
+                    "Informational",
+                    "High",
+                    format!(
+                        "{} makes a {} to address({}) at instruction {}",
+                        function_signature(function),
+                        call.opcode.to_lowercase(),
+                        call.target,
+                        call.instruction
+                    ),
+                ),
+            };
+            detectors.push(detector(
+                check,
+                impact,
+                confidence,
+                &description,
+                vec![function_element(filename, function, call.instruction, json!({}))],
+            ));
+        }
+
+        for notice in &function.notices {
+            detectors.push(detector(
+                "heimdall-decompiler-notice",
+                "Informational",
+                "Medium",
+                &format!("{}: {notice}", function_signature(function)),
+                vec![function_element(filename, function, function.entry_point, json!({}))],
+            ));
+        }
+    }
+
+    let function_elements = functions
+        .iter()
+        .map(|function| {
+            function_element(
+                filename,
+                function,
+                function.entry_point,
+                json!({
+                    "selector": format!("0x{}", function.selector),
+                    "state_mutability": match (function.pure, function.view, function.payable) {
+                        (true, _, _) => "pure",
+                        (_, true, _) => "view",
+                        (_, _, true) => "payable",
+                        _ => "nonpayable",
+                    },
+                    "external_calls": function.external_calls.len(),
+                }),
+            )
+        })
+        .collect::<Vec<_>>();
+    let variable_elements = storage_layout
+        .iter()
+        .map(|variable| {
+            json!({
+                "type": "variable",
+                "name": variable.name,
+                "source_mapping": source_mapping(filename, 0),
+                "type_specific_fields": { "parent": contract_element(filename) },
+                "additional_fields": {
+                    "slot": encode_hex_reduced(variable.slot),
+                    "type": variable.value_type,
+                    "mapping_depth": variable.depth,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "success": true,
+        "error": null,
+        "results": {
+            "detectors": detectors,
+            "printers": [
+                printer("function-summary", "the contract's decompiled functions", function_elements),
+                printer("variable-order", "the contract's recovered storage variables", variable_elements),
+            ],
+        },
+    })
+}
+
+/// gets a function's resolved signature, or its placeholder name if it wasn't resolved
+fn function_signature(function: &Function) -> String {
+    match &function.resolved_function {
+        Some(resolved_function) => resolved_function.signature.clone(),
+        None => format!("Unresolved_{}()", function.selector),
+    }
+}
+
+/// builds a detector finding, identified by a hash of its check and description
+fn detector(
+    check: &str,
+    impact: &str,
+    confidence: &str,
+    description: &str,
+    elements: Vec<Value>,
+) -> Value {
+    json!({
+        "check": check,
+        "impact": impact,
+        "confidence": confidence,
+        "description": format!("{description}\n"),
+        "markdown": format!("{description}\n"),
+        "first_markdown_element": "",
+        "id": encode_hex(keccak256(format!("{check}{description}")).to_vec()),
+        "elements": elements,
+    })
+}
+
+fn printer(name: &str, description: &str, elements: Vec<Value>) -> Value {
+    json!({
+        "printer": name,
+        "description": description,
+        "elements": elements,
+    })
+}
+
+fn function_element(filename: &str, function: &Function, start: u128, fields: Value) -> Value {
+    let signature = function_signature(function);
+    json!({
+        "type": "function",
+        "name": signature.split('(').next().unwrap_or_default(),
+        "source_mapping": source_mapping(filename, start),
+        "type_specific_fields": {
+            "parent": contract_element(filename),
+            "signature": signature,
+        },
+        "additional_fields": fields,
+    })
+}
+
+fn contract_element(filename: &str) -> Value {
+    json!({
+        "type": "contract",
+        "name": CONTRACT_NAME,
+        "source_mapping": source_mapping(filename, 0),
+    })
+}
+
+/// builds a source mapping for an offset in the bytecode, which has no lines or columns
+fn source_mapping(filename: &str, start: u128) -> Value {
+    json!({
+        "start": start,
+        "length": 0,
+        "filename_used": filename,
+        "filename_relative": filename,
+        "filename_absolute": filename,
+        "filename_short": filename,
+        "is_dependency": false,
+        "lines": [],
+        "starting_column": 0,
+        "ending_column": 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use ethers::types::U256;
+
+    use crate::decompile::{
+        out::{slither::build_slither_output, storage::StorageVariable},
+        util::{ExternalCall, Function},
+    };
+
+    #[test]
+    fn test_build_slither_output() {
+        let function = Function {
+            selector: String::from("a9059cbb"),
+            entry_point: 42,
+            arguments: HashMap::new(),
+            storage: HashMap::new(),
+            transient_storage: HashMap::new(),
+            memory: HashMap::new(),
+            returns: None,
+            logic: Vec::new(),
+            events: HashMap::new(),
+            errors: HashMap::new(),
+            resolved_function: None,
+            indent_depth: 0,
+            notices: vec![String::from("symbolic execution stopped early")],
+            external_calls: vec![ExternalCall {
+                instruction: 100,
+                opcode: String::from("DELEGATECALL"),
+                target: String::from("storage[0x00]"),
+            }],
+            pure: false,
+            view: false,
+            payable: false,
+        };
+        let storage_layout = vec![StorageVariable {
+            name: String::from("stor_a"),
+            slot: U256::zero(),
+            depth: 0,
+            offsets: BTreeSet::new(),
+            value_type: String::from("address"),
+        }];
+
+        let output = build_slither_output("0xaaaa", &[function], &storage_layout);
+        let detectors = output["results"]["detectors"].as_array().unwrap();
+        assert_eq!(detectors.len(), 2);
+        assert_eq!(detectors[0]["check"], "heimdall-delegatecall");
+        assert_eq!(detectors[0]["impact"], "Medium");
+        assert_eq!(detectors[0]["elements"][0]["name"], "Unresolved_a9059cbb");
+        assert_eq!(detectors[0]["elements"][0]["source_mapping"]["start"], 100);
+        assert_eq!(detectors[1]["check"], "heimdall-decompiler-notice");
+
+        let printers = output["results"]["printers"].as_array().unwrap();
+        assert_eq!(printers[0]["elements"][0]["additional_fields"]["selector"], "0xa9059cbb");
+        assert_eq!(printers[1]["elements"][0]["additional_fields"]["type"], "address");
+    }
+}
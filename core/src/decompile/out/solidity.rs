@@ -1,30 +1,34 @@
-use std::{collections::HashMap, time::Duration};
+use std::collections::HashMap;
 
+use fancy_regex::Captures;
 use heimdall_common::{
     ether::signatures::{ResolvedError, ResolvedLog},
     utils::{
         io::{
             file::short_path,
             logging::{Logger, TraceFactory},
+            progress::spinner,
         },
         strings::find_balanced_encapsulator,
     },
 };
-use indicatif::ProgressBar;
+use tracing::instrument;
 
 use super::{
     super::{
-        constants::{DECOMPILED_SOURCE_HEADER_SOL, STORAGE_ACCESS_REGEX},
+        constants::{ARGUMENT_REGEX, DECOMPILED_SOURCE_HEADER_SOL, STORAGE_ACCESS_REGEX},
         util::Function,
         DecompilerArgs,
     },
     abi::ABIStructure,
     postprocessers::solidity::postprocess,
 };
+use crate::error::Error;
 
 /// Build the decompiled Solidity source code from the given functions. Will piece together
 /// decompiled [`Function`]s, [`ResolvedError`]s, [`ResolvedLog`]s, and [`ABIStructure`]s into a
 /// Solidity contract.
+#[instrument(level = "debug", skip_all)]
 pub fn build_solidity_output(
     args: &DecompilerArgs,
     abi: &[ABIStructure],
@@ -33,7 +37,7 @@ pub fn build_solidity_output(
     all_resolved_events: HashMap<String, ResolvedLog>,
     trace: &mut TraceFactory,
     trace_parent: u32,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<String, Error> {
     // get a new logger
     let logger = Logger::default();
 
@@ -41,9 +45,7 @@ pub fn build_solidity_output(
     let mut functions = functions;
 
     // get a new progress bar
-    let progress_bar = ProgressBar::new_spinner();
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-    progress_bar.set_style(logger.info_spinner());
+    let progress_bar = spinner(logger.info_spinner());
 
     // write the decompiled source to file
     let mut decompiled_output: Vec<String> = Vec::new();
@@ -175,7 +177,7 @@ pub fn build_solidity_output(
                             format!(
                                 "{} {}arg{}",
                                 solidity_type,
-                                if solidity_type.contains("[]") ||
+                                if solidity_type.ends_with(']') ||
                                     solidity_type.contains('(') ||
                                     ["string", "bytes"].contains(&solidity_type.as_str())
                                 {
@@ -206,7 +208,7 @@ pub fn build_solidity_output(
                             format!(
                                 "{} {}arg{}",
                                 potential_types[0],
-                                if potential_types[0].contains("[]") ||
+                                if potential_types[0].ends_with(']') ||
                                     potential_types[0].contains('(') ||
                                     ["string", "bytes"].contains(&potential_types[0].as_str())
                                 {
@@ -261,3 +263,69 @@ pub fn build_solidity_output(
     Ok(postprocess(decompiled_output, all_resolved_errors, all_resolved_events, &progress_bar)
         .join("\n"))
 }
+
+/// Label the arguments of each function in the decompiled Solidity source with the parameter names
+/// of the contract's verified ABI, by selector. Arguments without a verified name are left as-is.
+pub fn label_parameters(source: &str, parameter_names: &HashMap<String, Vec<String>>) -> String {
+    let mut names: Option<&Vec<String>> = None;
+    source
+        .lines()
+        .map(|line| {
+            // each function starts with its selector in its natspec header
+            if let Some(selector) = line.trim().strip_prefix("/// @custom:selector    0x") {
+                names = parameter_names.get(selector.trim());
+            }
+
+            match names {
+                Some(names) => ARGUMENT_REGEX
+                    .replace_all(line, |captures: &Captures| {
+                        captures[1]
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|index| names.get(index))
+                            .filter(|name| !name.is_empty())
+                            .cloned()
+                            .unwrap_or_else(|| captures[0].to_string())
+                    })
+                    .to_string(),
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::decompile::out::solidity::label_parameters;
+
+    #[test]
+    fn test_label_parameters() {
+        let source = [
+            "/// @custom:selector    0xa9059cbb",
+            "/// @param              arg0 \"address\"",
+            "function transfer(address arg0, uint256 arg1) public {",
+            "    balances[arg0] += arg1;",
+            "}",
+            "/// @custom:selector    0x70a08231",
+            "function balanceOf(address arg0) public view {",
+        ]
+        .join("\n");
+        let names =
+            HashMap::from([(String::from("a9059cbb"), vec![String::from("to"), String::from("")])]);
+
+        assert_eq!(
+            label_parameters(&source, &names).lines().collect::<Vec<&str>>()[1..],
+            [
+                "/// @param              to \"address\"",
+                "function transfer(address to, uint256 arg1) public {",
+                "    balances[to] += arg1;",
+                "}",
+                "/// @custom:selector    0x70a08231",
+                "function balanceOf(address arg0) public view {",
+            ]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ethers::types::U256;
+use heimdall_common::{
+    ether::evm::core::{
+        opcodes::{WrappedInput, WrappedOpcode},
+        preimages::Preimages,
+        types::bitmask_to_type,
+    },
+    utils::strings::base26_encode,
+};
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::decompile::util::Function;
+
+/// A storage variable recovered from the slots the contract writes to. Mappings are recognized
+/// by slots computed as `keccak(key . slot)` during symbolic execution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StorageVariable {
+    // the variable's name, such as `stor_a` or `stor_map_b`, numbered in order of slot
+    pub name: String,
+
+    // the slot the variable is declared at
+    pub slot: U256,
+
+    // the number of keys the variable is indexed by, or 0 if it isn't a mapping
+    pub depth: usize,
+
+    // for mappings of structs or fixed-size arrays, how far past each entry's slot the members
+    // written to are
+    pub offsets: BTreeSet<u64>,
+
+    // the type of the values written to the variable
+    pub value_type: String,
+}
+
+/// Recover the storage layout of the contract from the slots its functions write to, using the
+/// preimages of the hashes computed during symbolic execution to recognize mappings.
+#[instrument(level = "debug", skip_all)]
+pub fn build_storage_layout(functions: &[Function], preimages: &Preimages) -> Vec<StorageVariable> {
+    // variables are keyed by their slot and depth, since a mapping and a value can't share a slot
+    let mut variables: BTreeMap<(U256, usize), (BTreeSet<u64>, String)> = BTreeMap::new();
+    for function in functions {
+        for (slot, frame) in &function.storage {
+            let (key, offset) = match preimages.resolve(*slot) {
+                Some(mapping) => ((mapping.slot, mapping.keys.len()), mapping.offset),
+                None => ((*slot, 0), 0),
+            };
+
+            let (offsets, value_type) =
+                variables.entry(key).or_insert_with(|| (BTreeSet::new(), String::from("uint256")));
+            offsets.insert(offset);
+
+            // the most specific type any function writes wins
+            let written_type = infer_value_type(&frame.operations);
+            if written_type != "uint256" {
+                *value_type = written_type;
+            }
+        }
+    }
+
+    variables
+        .into_iter()
+        .enumerate()
+        .map(|(i, ((slot, depth), (offsets, value_type)))| StorageVariable {
+            name: match depth {
+                0 => format!("stor_{}", base26_encode(i + 1)),
+                _ => format!("stor_map_{}", base26_encode(i + 1)),
+            },
+            slot,
+            depth,
+            offsets,
+            value_type,
+        })
+        .collect()
+}
+
+/// the constant value of the given input, if it's pushed rather than computed
+fn constant(input: &WrappedInput) -> Option<U256> {
+    match input {
+        WrappedInput::Raw(value) => Some(*value),
+        WrappedInput::Opcode(operation) if operation.opcode.name.starts_with("PUSH") => {
+            match operation.inputs.first() {
+                Some(WrappedInput::Raw(value)) => Some(*value),
+                _ => Some(U256::zero()),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// infers the type of a value from the operation it was computed by
+fn infer_value_type(operation: &WrappedOpcode) -> String {
+    match operation.opcode.name {
+        "ISZERO" | "LT" | "GT" | "SLT" | "SGT" | "EQ" => String::from("bool"),
+        "CALLER" | "ORIGIN" | "ADDRESS" | "COINBASE" => String::from("address"),
+
+        // a value masked before it's written is narrowed to the mask's most likely type
+        "AND" => operation
+            .inputs
+            .iter()
+            .find_map(constant)
+            .and_then(|mask| bitmask_to_type(mask, "AND").1.into_iter().next())
+            .unwrap_or_else(|| String::from("uint256")),
+        _ => String::from("uint256"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::{types::U256, utils::keccak256};
+    use heimdall_common::ether::evm::core::{
+        opcodes::{Opcode, WrappedInput, WrappedOpcode},
+        vm::VM,
+    };
+
+    use crate::decompile::{
+        out::storage::build_storage_layout,
+        util::{Function, StorageFrame},
+    };
+
+    fn function(storage: HashMap<U256, StorageFrame>) -> Function {
+        Function {
+            selector: String::from("00000000"),
+            entry_point: 0,
+            arguments: HashMap::new(),
+            storage,
+            transient_storage: HashMap::new(),
+            memory: HashMap::new(),
+            returns: None,
+            logic: Vec::new(),
+            events: HashMap::new(),
+            errors: HashMap::new(),
+            resolved_function: None,
+            indent_depth: 0,
+            notices: Vec::new(),
+            external_calls: Vec::new(),
+            pure: false,
+            view: false,
+            payable: false,
+        }
+    }
+
+    #[test]
+    fn test_build_storage_layout() {
+        // balances[caller] = 1, where balances is declared at slot 1
+        // mstore(0, caller), mstore(32, 1), sstore(sha3(0, 64), 1)
+        let mut vm = VM::new(
+            String::from("0x336000526001602052600160406000205500"),
+            String::from("0x"),
+            String::from("0x6865696d64616c6c000000000061646472657373"),
+            String::from("0x6865696d64616c6c0000000000006f726967696e"),
+            String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+            0,
+            u128::MAX,
+        );
+        vm.execute();
+
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(&vm.caller);
+        preimage[63] = 1;
+        let balance_slot = U256::from_big_endian(&keccak256(preimage));
+
+        let caller = WrappedOpcode { opcode: Opcode::new(0x33), inputs: Vec::new() };
+        let one =
+            WrappedOpcode { opcode: Opcode::new(0x60), inputs: vec![WrappedInput::Raw(1.into())] };
+        let layout = build_storage_layout(
+            &[
+                function(HashMap::from([(
+                    balance_slot,
+                    StorageFrame { value: U256::one(), operations: one },
+                )])),
+                function(HashMap::from([(
+                    U256::zero(),
+                    StorageFrame { value: U256::one(), operations: caller },
+                )])),
+            ],
+            &vm.preimages,
+        );
+
+        assert_eq!(layout.len(), 2);
+        assert_eq!((layout[0].name.as_str(), layout[0].depth), ("stor_a", 0));
+        assert_eq!(layout[0].value_type, "address");
+        assert_eq!(
+            (layout[1].name.as_str(), layout[1].slot, layout[1].depth),
+            ("stor_map_b", U256::one(), 1)
+        );
+        assert_eq!(layout[1].value_type, "uint256");
+    }
+
+    #[test]
+    fn test_build_storage_layout_masked_types() {
+        let mask = |mask: U256| WrappedOpcode {
+            opcode: Opcode::new(0x16),
+            inputs: vec![
+                WrappedInput::Raw(mask),
+                WrappedInput::Opcode(WrappedOpcode {
+                    opcode: Opcode::new(0x35),
+                    inputs: vec![WrappedInput::Raw(4.into())],
+                }),
+            ],
+        };
+        let layout = build_storage_layout(
+            &[function(HashMap::from([
+                (U256::zero(), StorageFrame { value: U256::one(), operations: mask(0xff.into()) }),
+                (
+                    U256::one(),
+                    StorageFrame { value: U256::one(), operations: mask((U256::one() << 160) - 1) },
+                ),
+                (
+                    U256::from(2),
+                    StorageFrame { value: U256::one(), operations: mask((U256::one() << 128) - 1) },
+                ),
+            ]))],
+            &Default::default(),
+        );
+
+        assert_eq!(
+            layout.iter().map(|variable| variable.value_type.as_str()).collect::<Vec<_>>(),
+            vec!["uint8", "address", "uint128"]
+        );
+    }
+}
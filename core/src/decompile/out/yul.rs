@@ -1,33 +1,36 @@
-use std::{collections::HashMap, time::Duration};
+use std::collections::HashMap;
 
-use crate::decompile::{constants::DECOMPILED_SOURCE_HEADER_YUL, util::Function, DecompilerArgs};
+use crate::{
+    decompile::{constants::DECOMPILED_SOURCE_HEADER_YUL, util::Function, DecompilerArgs},
+    error::Error,
+};
 use heimdall_common::{
     ether::signatures::ResolvedLog,
     utils::io::{
         file::short_path,
         logging::{Logger, TraceFactory},
+        progress::spinner,
     },
 };
-use indicatif::ProgressBar;
+use tracing::instrument;
 
 use super::postprocessers::yul::postprocess;
 
 /// Build the decompiled Yul source code from the given functions. Will piece together decompiled
 /// [`Function`]s and [`ResolvedLog`]s into a Yul contract.
+#[instrument(level = "debug", skip_all)]
 pub fn build_yul_output(
     args: &DecompilerArgs,
     functions: Vec<Function>,
     all_resolved_events: HashMap<String, ResolvedLog>,
     trace: &mut TraceFactory,
     trace_parent: u32,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<String, Error> {
     // get a new logger
     let logger = Logger::default();
 
     // get a new progress bar
-    let progress_bar = ProgressBar::new_spinner();
-    progress_bar.enable_steady_tick(Duration::from_millis(100));
-    progress_bar.set_style(logger.info_spinner());
+    let progress_bar = spinner(logger.info_spinner());
 
     // build the decompiled source
     let mut decompiled_output: Vec<String> = Vec::new();
@@ -75,7 +78,7 @@ pub fn build_yul_output(
                             format!(
                                 "{}{}",
                                 solidity_type,
-                                if solidity_type.contains("[]") ||
+                                if solidity_type.ends_with(']') ||
                                     solidity_type.contains('(') ||
                                     ["string", "bytes"].contains(&solidity_type.as_str())
                                 {
@@ -103,7 +106,7 @@ pub fn build_yul_output(
                             format!(
                                 "{}{}",
                                 potential_types[0],
-                                if potential_types[0].contains("[]") ||
+                                if potential_types[0].ends_with(']') ||
                                     potential_types[0].contains('(') ||
                                     ["string", "bytes"].contains(&potential_types[0].as_str())
                                 {
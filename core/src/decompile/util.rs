@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use ethers::prelude::U256;
 use heimdall_common::ether::{
-    evm::core::{log::Log, opcodes::WrappedOpcode},
+    evm::core::{log::Log, opcodes::WrappedOpcode, vm::Instruction},
     signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
 };
 
@@ -28,6 +28,11 @@ pub struct Function {
     //   - value : tuple of ({value: U256, operation: WrappedOpcode})
     pub storage: HashMap<U256, StorageFrame>,
 
+    // transient storage structure:
+    //   - key : slot of the transient storage.
+    //   - value : the values stored to the slot, in execution order.
+    pub transient_storage: HashMap<U256, Vec<U256>>,
+
     // memory structure:
     //   - key : slot of the argument. I.E: slot 0 is CALLDATALOAD(4).
     //   - value : tuple of ({value: U256, operation: WrappedOpcode})
@@ -57,6 +62,9 @@ pub struct Function {
     // stores decompiler notices
     pub notices: Vec<String>,
 
+    // the calls the function makes to other contracts, in the order they were found
+    pub external_calls: Vec<ExternalCall>,
+
     // modifiers
     pub pure: bool,
     pub view: bool,
@@ -70,6 +78,19 @@ pub struct StorageFrame {
     pub operations: WrappedOpcode,
 }
 
+/// A call a function makes to another contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalCall {
+    // the instruction the call is made at
+    pub instruction: u128,
+
+    // the opcode used to make the call, such as `DELEGATECALL`
+    pub opcode: String,
+
+    // the address called, as a solidity expression
+    pub target: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct CalldataFrame {
     pub slot: usize,
@@ -98,4 +119,48 @@ impl Function {
 
         memory_slice
     }
+
+    // record a call to another contract, ignoring calls to precompiles, and calls which were
+    // already recorded on another path
+    pub fn add_external_call(&mut self, instruction: &Instruction) {
+        let opcode = match &instruction.opcode_details {
+            Some(details) => details.name,
+            None => return,
+        };
+        if !["CALL", "CALLCODE", "DELEGATECALL", "STATICCALL"].contains(&opcode) ||
+            instruction
+                .inputs
+                .get(1)
+                .is_some_and(|address| !address.is_zero() && *address <= U256::from(10)) ||
+            self.external_calls.iter().any(|call| call.instruction == instruction.instruction)
+        {
+            return
+        }
+
+        self.external_calls.push(ExternalCall {
+            instruction: instruction.instruction,
+            opcode: opcode.to_string(),
+            target: instruction
+                .input_operations
+                .get(1)
+                .map(|address| address.solidify())
+                .unwrap_or_default(),
+        });
+    }
+
+    // get the transient storage slots which are set and later cleared, such as reentrancy locks
+    pub fn get_transient_locks(&self) -> Vec<U256> {
+        let mut locks = self
+            .transient_storage
+            .iter()
+            .filter(|(_, values)| match values.iter().position(|value| !value.is_zero()) {
+                Some(set) => values[set..].iter().any(|value| value.is_zero()),
+                None => false,
+            })
+            .map(|(slot, _)| *slot)
+            .collect::<Vec<U256>>();
+
+        locks.sort();
+        locks
+    }
 }
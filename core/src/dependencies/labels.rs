@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use heimdall_common::{
+    ether::chains::chain_by_id,
+    resources::{blockscout, etherscan},
+};
+use serde::Serialize;
+
+use crate::error::Error;
+
+/// Where the label of a dependency came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelSource {
+    /// The address is one of the EVM's precompiled contracts.
+    Precompile,
+
+    /// The address was labeled in the labels file given with `--labels`.
+    File,
+
+    /// The address is a verified contract, named by the chain's explorer.
+    Explorer,
+}
+
+/// The name of the precompiled contract at `address`, if there is one.
+///
+/// ```
+/// use heimdall_core::dependencies::labels::precompile_label;
+///
+/// assert_eq!(precompile_label("0x0000000000000000000000000000000000000001"), Some("ecrecover"));
+/// assert_eq!(precompile_label("0x1111111111111111111111111111111111111111"), None);
+/// ```
+pub fn precompile_label(address: &str) -> Option<&'static str> {
+    let address = address.trim_start_matches("0x");
+    if address.len() != 40 || !address[..38].chars().all(|c| c == '0') {
+        return None
+    }
+
+    match u8::from_str_radix(&address[38..], 16).ok()? {
+        0x01 => Some("ecrecover"),
+        0x02 => Some("sha256"),
+        0x03 => Some("ripemd160"),
+        0x04 => Some("identity"),
+        0x05 => Some("modexp"),
+        0x06 => Some("ecadd"),
+        0x07 => Some("ecmul"),
+        0x08 => Some("ecpairing"),
+        0x09 => Some("blake2f"),
+        0x0a => Some("point_evaluation"),
+        _ => None,
+    }
+}
+
+/// Parse a labels file, which is a JSON object mapping addresses to their labels, such as
+/// `{ "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2": "WETH" }`. Addresses are lowercased, so
+/// checksummed addresses match.
+pub fn parse_labels(contents: &str) -> Result<HashMap<String, String>, Error> {
+    let labels: HashMap<String, String> = serde_json::from_str(contents).map_err(|e| {
+        Error::ParseError(format!("labels must be a JSON object of addresses to labels: {e}"))
+    })?;
+
+    Ok(labels.into_iter().map(|(address, label)| (address.to_lowercase(), label)).collect())
+}
+
+/// Read and parse the labels file at `path`. See [`parse_labels`] for its format.
+pub fn read_labels(path: &str) -> Result<HashMap<String, String>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::ResolutionError(format!("failed to read labels from '{path}': {e}")))?;
+    parse_labels(&contents)
+}
+
+/// Name the verified contract at `address` on the given chain, using its etherscan-compatible
+/// explorer if an API key is given, and falling back to its Blockscout instance.
+pub async fn explorer_label(
+    chain_id: u64,
+    address: &str,
+    etherscan_api_key: &str,
+) -> Option<String> {
+    let chain = chain_by_id(chain_id);
+
+    if !etherscan_api_key.is_empty() {
+        let api = chain.map(|chain| chain.explorer_api).unwrap_or("https://api.etherscan.io/api");
+        if let Some(source) = etherscan::get_source(api, address, etherscan_api_key).await {
+            if !source.name.is_empty() {
+                return Some(source.name)
+            }
+        }
+    }
+
+    let api = chain.and_then(|chain| chain.blockscout_api)?;
+    blockscout::get_source(api, address)
+        .await
+        .map(|source| source.name)
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_labels() {
+        let labels = parse_labels(
+            r#"{ "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2": "WETH", "0x1111111111111111111111111111111111111111": "Router" }"#,
+        )
+        .unwrap();
+        assert_eq!(labels.get("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").unwrap(), "WETH");
+        assert_eq!(labels.get("0x1111111111111111111111111111111111111111").unwrap(), "Router");
+
+        assert!(parse_labels("[\"WETH\"]").is_err());
+    }
+
+    #[test]
+    fn test_precompile_label() {
+        assert_eq!(precompile_label("0x0000000000000000000000000000000000000009"), Some("blake2f"));
+        assert_eq!(precompile_label("0x000000000000000000000000000000000000000b"), None);
+        assert_eq!(precompile_label("0x01"), None);
+    }
+}
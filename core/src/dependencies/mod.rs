@@ -0,0 +1,376 @@
+pub mod labels;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::{Display, Formatter},
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        compiler::detect_compiler,
+        eof::is_eof,
+        evm::{
+            core::{hardfork::Hardfork, vm::VM},
+            ext::exec::ExecutionLimits,
+        },
+        rpc::chain_id,
+        selectors::{find_function_selectors, resolve_selectors},
+        signatures::{score_signature, ResolvedFunction},
+    },
+    utils::{
+        io::{logging::*, progress::spinner},
+        strings::decode_hex,
+    },
+};
+use serde::Serialize;
+
+use crate::{
+    callgraph::analyze::{analyze_calls, FunctionCalls},
+    dependencies::labels::{explorer_label, precompile_label, read_labels, LabelSource},
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    utils::get_contract_bytecode,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "List the contracts which EVM bytecode calls at constant addresses, with their labels",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall dependencies <TARGET> [OPTIONS]"
+)]
+pub struct DependenciesArgs {
+    /// The target to find the dependencies of, either a file, bytecode, contract address, or `-`
+    /// to read bytecode from stdin.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode and the chain's id. Separate multiple
+    /// providers with commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors and labeling dependencies with the
+    /// explorer.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// A JSON file mapping addresses to labels, which take precedence over the explorer's.
+    #[clap(long = "labels", short = 'l', default_value = "", hide_default_value = true)]
+    pub labels: String,
+
+    /// Your Etherscan API key, used to name verified dependencies. Without one, the chain's
+    /// Blockscout instance is used if it has one.
+    #[clap(long = "etherscan-api-key", default_value = "", hide_default_value = true)]
+    pub etherscan_api_key: String,
+
+    /// Limits on how much of each function symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl DependenciesArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            labels: Some(String::new()),
+            etherscan_api_key: Some(String::new()),
+            limits: Some(ExecutionLimits::default()),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A call to a dependency, made by one of the target's functions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct DependencyCall {
+    // the selector of the function making the call
+    pub selector: String,
+
+    // the resolved signature of the function making the call, if any
+    pub function: Option<String>,
+
+    // the opcode used to make the call, i.e. CALL, CALLCODE, DELEGATECALL, or STATICCALL
+    pub opcode: String,
+}
+
+/// A contract which the target calls at a constant address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    pub address: String,
+    pub label: Option<String>,
+    pub label_source: Option<LabelSource>,
+
+    // every call to the dependency, ordered by selector
+    pub calls: Vec<DependencyCall>,
+}
+
+/// The contracts the target interacts with, along with how many of its calls target addresses
+/// which aren't constant, and so can't be listed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyReport {
+    // the dependencies, ordered by address
+    pub dependencies: Vec<Dependency>,
+    pub dynamic_calls: usize,
+}
+
+impl Display for DependencyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.dependencies.is_empty() {
+            writeln!(f, "no calls to constant addresses found.")?;
+        }
+        for dependency in &self.dependencies {
+            match &dependency.label {
+                Some(label) => writeln!(f, "{} ({label})", dependency.address)?,
+                None => writeln!(f, "{}", dependency.address)?,
+            }
+            for call in &dependency.calls {
+                match &call.function {
+                    Some(function) => {
+                        writeln!(f, "  {} from 0x{} {function}", call.opcode, call.selector)?
+                    }
+                    None => writeln!(f, "  {} from 0x{}", call.opcode, call.selector)?,
+                }
+            }
+        }
+        if self.dynamic_calls > 0 {
+            writeln!(
+                f,
+                "\n{} calls target addresses which aren't constant, and aren't listed.",
+                self.dynamic_calls
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The main entry point for the dependencies module. Will symbolically execute each function
+/// found in the target bytecode, collect the constant addresses it calls, and label them with
+/// the precompiles, the labels file, or the chain's explorer.
+pub async fn dependencies(args: DependenciesArgs) -> Result<DependencyReport, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, mut trace) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // truncate target for prettier display
+    let mut shortened_target = args.target.clone();
+    if shortened_target.len() > 66 {
+        shortened_target = shortened_target.chars().take(66).collect::<String>() +
+            "..." +
+            &shortened_target.chars().skip(shortened_target.len() - 16).collect::<String>();
+    }
+
+    // add the call to the trace
+    let dependencies_call = trace.add_call(
+        0,
+        line!(),
+        "heimdall".to_string(),
+        "dependencies".to_string(),
+        vec![shortened_target.clone()],
+        "()".to_string(),
+    );
+
+    // read the labels file first, so a bad path fails before symbolic execution
+    let file_labels = match args.labels.is_empty() {
+        true => HashMap::new(),
+        false => read_labels(&args.labels)?,
+    };
+
+    // fetch bytecode
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // symbolic execution only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by dependencies. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
+    }
+
+    // disassemble the bytecode
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        output: String::new(),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: Hardfork::default(),
+    })
+    .await?;
+    trace.add_call(
+        dependencies_call,
+        line!(),
+        "heimdall".to_string(),
+        "disassemble".to_string(),
+        vec![format!("{} bytes", contract_bytecode.len() / 2usize)],
+        "()".to_string(),
+    );
+
+    // perform versioning and compiler heuristics
+    let (compiler, version) = detect_compiler(&contract_bytecode);
+    trace.add_call(
+        dependencies_call,
+        line!(),
+        "heimdall".to_string(),
+        "detect_compiler".to_string(),
+        vec![format!("{} bytes", contract_bytecode.len() / 2usize)],
+        format!("({compiler}, {version})"),
+    );
+
+    if compiler == "solc" {
+        logger.debug(&format!("detected compiler {compiler} {version}."));
+    } else {
+        logger
+            .warn(&format!("detected compiler {compiler} {version} is not supported by heimdall."));
+    }
+
+    // create a new EVM instance
+    let evm = VM::new(
+        contract_bytecode.clone(),
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    // find and resolve all selectors in the bytecode
+    let selectors = find_function_selectors(&evm, &disassembled_bytecode);
+    let mut resolved_selectors: HashMap<String, Vec<ResolvedFunction>> = HashMap::new();
+    if !args.skip_resolving {
+        resolved_selectors =
+            resolve_selectors::<ResolvedFunction>(selectors.keys().cloned().collect()).await;
+        logger.info(&format!(
+            "resolved {} possible functions from {} detected selectors.",
+            resolved_selectors.len(),
+            selectors.len()
+        ));
+    } else {
+        logger.info(&format!("found {} possible function selectors.", selectors.len()));
+    }
+
+    logger.info(&format!("performing symbolic execution on '{shortened_target}' ."));
+
+    // create a new progress bar
+    let progress = spinner(logger.info_spinner());
+
+    // sort selectors so calls are reported in a deterministic order
+    let mut selectors = selectors.into_iter().collect::<Vec<(String, u128)>>();
+    selectors.sort();
+
+    // symbolically execute each function, grouping its constant calls by address
+    let mut calls_by_address: BTreeMap<String, Vec<DependencyCall>> = BTreeMap::new();
+    let mut dynamic_calls = 0;
+    for (selector, entry_point) in selectors {
+        progress.set_message(format!("executing '0x{selector}'"));
+
+        let (map, jumpdest_count, limit_reached) =
+            &evm.clone().symbolic_exec_selector(&selector, entry_point, &args.limits);
+        if let Some(limit) = limit_reached {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' stopped early after reaching the {limit}, so it may have dependencies which weren't found."
+            ));
+        }
+        for fault in map.faults() {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' faulted: {}",
+                Error::from(fault)
+            ));
+        }
+        trace.add_info(
+            dependencies_call,
+            line!(),
+            &format!("traced and executed {jumpdest_count} possible paths for '0x{selector}'."),
+        );
+
+        let function = resolved_selectors.get(&selector).and_then(|resolved_functions| {
+            resolved_functions
+                .iter()
+                .max_by_key(|resolved_function| score_signature(&resolved_function.signature))
+                .map(|resolved_function| resolved_function.signature.clone())
+        });
+
+        let mut calls = FunctionCalls::default();
+        analyze_calls(map, &mut calls);
+        for external_call in calls.external_calls {
+            if !external_call.constant {
+                dynamic_calls += 1;
+                continue
+            }
+            calls_by_address.entry(external_call.target).or_default().push(DependencyCall {
+                selector: selector.clone(),
+                function: function.clone(),
+                opcode: external_call.opcode,
+            });
+        }
+    }
+    progress.finish_and_clear();
+
+    // label each dependency, preferring precompiles and the labels file over the explorer
+    let chain_id = match args.skip_resolving || args.rpc_url.is_empty() {
+        true => 1,
+        false => chain_id(&args.rpc_url).await.unwrap_or(1),
+    };
+    let mut dependencies = Vec::new();
+    for (address, mut calls) in calls_by_address {
+        calls.sort();
+        calls.dedup();
+
+        let (label, label_source) = if let Some(label) = precompile_label(&address) {
+            (Some(label.to_string()), Some(LabelSource::Precompile))
+        } else if let Some(label) = file_labels.get(&address) {
+            (Some(label.clone()), Some(LabelSource::File))
+        } else if args.skip_resolving {
+            (None, None)
+        } else {
+            match explorer_label(chain_id, &address, &args.etherscan_api_key).await {
+                Some(label) => (Some(label), Some(LabelSource::Explorer)),
+                None => (None, None),
+            }
+        };
+
+        dependencies.push(Dependency { address, label, label_source, calls });
+    }
+
+    logger.info("symbolic execution completed.");
+    logger.info(&format!(
+        "found {} dependencies, {} of which are labeled.",
+        dependencies.len(),
+        dependencies.iter().filter(|dependency| dependency.label.is_some()).count()
+    ));
+    logger.debug(&format!("dependency analysis completed in {:?}.", now.elapsed()));
+    trace.display();
+
+    Ok(DependencyReport { dependencies, dynamic_calls })
+}
@@ -0,0 +1,370 @@
+pub mod source;
+
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{eof::is_eof, evm::core::opcodes::Opcode, metadata::strip_metadata},
+    utils::{
+        io::logging::*,
+        strings::{decode_hex, encode_hex_reduced},
+    },
+};
+use serde::Serialize;
+
+use crate::{
+    analysis::{
+        blocks::{find_basic_blocks, BasicBlock},
+        diff::{diff_blocks, DiffStatus},
+    },
+    decompile::{decompile, DecompilerArgsBuilder},
+    diff::source::{diff_source, SourceDiff},
+    error::Error,
+    utils::get_contract_bytecode,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Compare two EVM bytecodes, ignoring metadata, addresses, and constants",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall diff <TARGET> <OTHER> [OPTIONS]"
+)]
+pub struct DiffArgs {
+    /// The target to compare, either a file, bytecode, contract address, or `-` to read bytecode
+    /// from stdin. This is treated as the old version of the contract.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// The target to compare against, either a file, bytecode, or contract address. This is
+    /// treated as the new version of the contract.
+    #[clap(required = true)]
+    pub other: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to decompile both targets and diff their Solidity, rather than their bytecode.
+    /// Functions are matched by selector, and the changed functions are summarized.
+    #[clap(long = "decompiled")]
+    pub decompiled: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl DiffArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            other: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            decompiled: Some(false),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A single normalized instruction in a [`BytecodeDiff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "instruction", rename_all = "lowercase")]
+pub enum DiffLine {
+    Unchanged(String),
+    Removed(String),
+    Added(String),
+}
+
+/// An opcode-level diff of two bytecodes, grouped by basic block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BytecodeDiff {
+    // the fraction of normalized instructions shared by both bytecodes, from 0 to 1
+    pub similarity: f64,
+
+    // the diffed instructions of each aligned basic block
+    pub blocks: Vec<Vec<DiffLine>>,
+}
+
+impl Display for BytecodeDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "; similarity: {:.2}%", self.similarity * 100.0)?;
+        for block in &self.blocks {
+            writeln!(f)?;
+            for line in block {
+                match line {
+                    DiffLine::Unchanged(instruction) => writeln!(f, "  {instruction}")?,
+                    DiffLine::Removed(instruction) => writeln!(f, "- {instruction}")?,
+                    DiffLine::Added(instruction) => writeln!(f, "+ {instruction}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether the value pushed by the given instruction should be ignored when comparing bytecode.
+/// Jump destinations shift whenever code is added or removed, while PUSH20 and PUSH32 values are
+/// usually addresses and immutables, which differ between deployments of the same contract.
+fn is_canonicalized(block: &BasicBlock, index: usize) -> bool {
+    matches!(
+        (block.instructions[index].0, block.instructions.get(index + 1)),
+        (0x73 | 0x7f, _) | (0x60..=0x7f, Some((0x56 | 0x57, _)))
+    )
+}
+
+/// Normalize a basic block by removing the pushed value of each canonicalized instruction.
+fn normalize_block(block: &BasicBlock) -> BasicBlock {
+    let mut normalized = block.clone();
+    for index in 0..block.instructions.len() {
+        if is_canonicalized(block, index) {
+            normalized.instructions[index].1 = None;
+        }
+    }
+    normalized
+}
+
+/// Format the instruction at `index` within a normalized block.
+fn format_instruction(block: &BasicBlock, index: usize) -> String {
+    let (opcode, value) = block.instructions[index];
+    let operand = match (opcode, value, block.instructions.get(index + 1)) {
+        (0x60..=0x7f, _, Some((0x56 | 0x57, _))) => String::from(" <jumpdest>"),
+        (0x73, ..) => String::from(" <address>"),
+        (0x7f, ..) => String::from(" <constant>"),
+        (_, Some(value), _) => format!(" {}", encode_hex_reduced(value)),
+        _ => String::new(),
+    };
+
+    format!("{}{}", Opcode::new(opcode).name, operand)
+}
+
+/// Normalize and diff the given bytecodes. Trailing compiler metadata is stripped, and
+/// canonicalized PUSH values (see [`is_canonicalized`]) are ignored, so recompiling or redeploying
+/// a contract with different constructor arguments won't affect the result.
+///
+/// Basic blocks are aligned with [`diff_blocks`], and the similarity is the fraction of
+/// instructions in either bytecode which were matched to an identical instruction in the other.
+///
+/// ```
+/// use heimdall_core::diff::diff_bytecode;
+///
+/// // PUSH1 0x01 PUSH20 <address> SSTORE STOP
+/// let mut old = vec![0x60, 0x01, 0x73];
+/// old.extend([0x11; 20]);
+/// old.extend([0x55, 0x00]);
+///
+/// // the same contract, deployed with a different address
+/// let mut new = vec![0x60, 0x01, 0x73];
+/// new.extend([0x22; 20]);
+/// new.extend([0x55, 0x00]);
+///
+/// assert_eq!(diff_bytecode(&old, &new).similarity, 1.0);
+/// ```
+pub fn diff_bytecode(old: &[u8], new: &[u8]) -> BytecodeDiff {
+    let old_blocks =
+        find_basic_blocks(strip_metadata(old)).iter().map(normalize_block).collect::<Vec<_>>();
+    let new_blocks =
+        find_basic_blocks(strip_metadata(new)).iter().map(normalize_block).collect::<Vec<_>>();
+
+    let block_diffs = diff_blocks(&old_blocks, &new_blocks);
+
+    // removed blocks are listed last by `diff_blocks`, so move each one after the block which
+    // precedes it in the old version
+    let mut removed: BTreeSet<usize> = block_diffs
+        .iter()
+        .filter(|block_diff| block_diff.status == DiffStatus::Removed)
+        .filter_map(|block_diff| block_diff.old)
+        .collect();
+    let mut ordered = Vec::new();
+    let mut take_removed_after = |ordered: &mut Vec<(Option<usize>, Option<usize>)>,
+                                  mut index: usize| {
+        while removed.remove(&index) {
+            ordered.push((Some(index), None));
+            index += 1;
+        }
+    };
+    take_removed_after(&mut ordered, 0);
+    for block_diff in block_diffs.iter().filter(|diff| diff.status != DiffStatus::Removed) {
+        ordered.push((block_diff.old, block_diff.new));
+        if let Some(old_index) = block_diff.old {
+            take_removed_after(&mut ordered, old_index + 1);
+        }
+    }
+    ordered.extend(removed.into_iter().map(|index| (Some(index), None)));
+
+    // diff the instructions of each aligned pair of blocks
+    let mut matched = 0;
+    let mut blocks = Vec::new();
+    for (old_index, new_index) in ordered {
+        let lines = match (old_index, new_index) {
+            (Some(old_index), Some(new_index)) => {
+                let (old_block, new_block) = (&old_blocks[old_index], &new_blocks[new_index]);
+
+                // aligned blocks always share the same opcodes
+                let mut lines = Vec::new();
+                for index in 0..new_block.instructions.len() {
+                    let old_line = format_instruction(old_block, index);
+                    let new_line = format_instruction(new_block, index);
+                    if old_line == new_line {
+                        matched += 1;
+                        lines.push(DiffLine::Unchanged(new_line));
+                    } else {
+                        lines.push(DiffLine::Removed(old_line));
+                        lines.push(DiffLine::Added(new_line));
+                    }
+                }
+                lines
+            }
+            (Some(old_index), None) => (0..old_blocks[old_index].instructions.len())
+                .map(|index| DiffLine::Removed(format_instruction(&old_blocks[old_index], index)))
+                .collect(),
+            (None, Some(new_index)) => (0..new_blocks[new_index].instructions.len())
+                .map(|index| DiffLine::Added(format_instruction(&new_blocks[new_index], index)))
+                .collect(),
+            (None, None) => continue,
+        };
+        blocks.push(lines);
+    }
+
+    let total = old_blocks.iter().chain(new_blocks.iter()).map(|b| b.instructions.len()).sum();
+    let similarity = match total {
+        0 => 1.0,
+        total => (2 * matched) as f64 / total as f64,
+    };
+
+    BytecodeDiff { similarity, blocks }
+}
+
+/// The main entry point for the diff module. Will normalize and compare the bytecode of
+/// `args.target` and `args.other`.
+pub async fn diff(args: DiffArgs) -> Result<BytecodeDiff, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    // fetch the bytecode of both targets
+    let old_bytecode =
+        decode_hex(&get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?)?;
+    let new_bytecode =
+        decode_hex(&get_contract_bytecode(&args.other, &args.rpc_url, &logger).await?)?;
+
+    // basic block alignment relies on legacy JUMPDESTs, which EOF code sections don't have
+    if is_eof(&old_bytecode) || is_eof(&new_bytecode) {
+        return Err(Error::VmError(String::from("EOF containers are not supported by diff.")))
+    }
+
+    let bytecode_diff = diff_bytecode(&old_bytecode, &new_bytecode);
+
+    logger.info(&format!("bytecode similarity: {:.2}%", bytecode_diff.similarity * 100.0));
+    logger.debug(&format!("diff completed in {:?}.", now.elapsed()));
+
+    Ok(bytecode_diff)
+}
+
+/// Decompile `args.target` and `args.other`, and diff their decompiled Solidity. Signatures aren't
+/// resolved, so both sources are named by selector alone and only differ where their bytecode
+/// does.
+pub async fn diff_decompiled(args: DiffArgs) -> Result<SourceDiff, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let mut sources = Vec::new();
+    for target in [&args.target, &args.other] {
+        let decompiler_args = DecompilerArgsBuilder::new()
+            .target(target.clone())
+            .rpc_url(args.rpc_url.clone())
+            .skip_resolving(true)
+            .include_solidity(true)
+            .force_decompile(true)
+            .build()
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        sources.push(decompile(decompiler_args).await?.source.ok_or_else(|| {
+            Error::GenericError(format!("failed to decompile '{target}' to Solidity."))
+        })?);
+    }
+
+    let source_diff = diff_source(&args.target, &sources[0], &args.other, &sources[1]);
+
+    logger.info(&format!("{} functions changed.", source_diff.functions.len()));
+    logger.debug(&format!("diff completed in {:?}.", now.elapsed()));
+
+    Ok(source_diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::*;
+
+    #[test]
+    fn test_diff_bytecode_ignores_metadata_and_jumpdests() {
+        // PUSH1 0x03 JUMP JUMPDEST STOP, followed by {"solc": 0x000814}
+        let old = [
+            0x60, 0x03, 0x56, 0x5b, 0x00, 0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63, 0x43, 0x00, 0x08,
+            0x14, 0x00, 0x0a,
+        ];
+
+        // PUSH1 0x04 JUMP INVALID JUMPDEST STOP
+        let new = [0x60, 0x04, 0x56, 0xfe, 0x5b, 0x00];
+
+        let bytecode_diff = diff_bytecode(&old, &new);
+        assert_eq!(
+            bytecode_diff.blocks,
+            vec![
+                vec![
+                    DiffLine::Unchanged(String::from("PUSH1 <jumpdest>")),
+                    DiffLine::Unchanged(String::from("JUMP"))
+                ],
+                vec![DiffLine::Added(String::from("INVALID"))],
+                vec![
+                    DiffLine::Unchanged(String::from("JUMPDEST")),
+                    DiffLine::Unchanged(String::from("STOP"))
+                ],
+            ]
+        );
+        assert_eq!(bytecode_diff.similarity, 8.0 / 9.0);
+    }
+
+    #[test]
+    fn test_diff_bytecode_changed_constant() {
+        // PUSH1 0x01 PUSH1 0x00 SSTORE STOP
+        let old = [0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+
+        // PUSH1 0x02 PUSH1 0x00 SSTORE STOP
+        let new = [0x60, 0x02, 0x60, 0x00, 0x55, 0x00];
+
+        assert_eq!(
+            diff_bytecode(&old, &new).to_string(),
+            "; similarity: 75.00%\n\n- PUSH1 0x01\n+ PUSH1 0x02\n  PUSH1 0\n  SSTORE\n  STOP\n"
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use serde::Serialize;
+
+use crate::{analysis::diff::DiffStatus, diff::DiffLine};
+
+// the number of unchanged lines shown around each change in a hunk
+const CONTEXT_LINES: usize = 3;
+
+// each decompiled function starts with its selector in its natspec header
+const SELECTOR_PREFIX: &str = "/// @custom:selector    0x";
+
+/// A function which was added, removed, or changed between two versions of a contract.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionDiff {
+    pub selector: String,
+    pub name: String,
+    pub status: DiffStatus,
+}
+
+/// A line-level diff of two contracts' decompiled Solidity, with a summary of the functions which
+/// changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceDiff {
+    pub old_target: String,
+    pub new_target: String,
+    pub functions: Vec<FunctionDiff>,
+    pub lines: Vec<DiffLine>,
+}
+
+/// A decompiled source, split into the lines before the first function, each function keyed by
+/// its selector, and the contract's closing lines.
+struct Sections {
+    preamble: Vec<String>,
+    functions: BTreeMap<String, Vec<String>>,
+    postamble: Vec<String>,
+}
+
+/// splits decompiled Solidity into sections, so functions can be compared by selector regardless
+/// of the order they were written in
+fn split_sections(source: &str) -> Sections {
+    let mut lines = source.lines().map(|line| line.to_string()).collect::<Vec<_>>();
+
+    // the contract's closing brace would otherwise belong to whichever function is last
+    let mut postamble = Vec::new();
+    while lines.last().is_some_and(|line| line.trim().is_empty() || line.trim() == "}") {
+        postamble.insert(0, lines.pop().unwrap_or_default());
+        if postamble.first().is_some_and(|line| line.trim() == "}") {
+            break
+        }
+    }
+
+    let mut preamble = Vec::new();
+    let mut functions: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in lines {
+        if let Some(selector) = line.trim().strip_prefix(SELECTOR_PREFIX) {
+            current = Some(selector.trim().to_string());
+        }
+        match &current {
+            Some(selector) => functions.entry(selector.clone()).or_default().push(line),
+            None => preamble.push(line),
+        }
+    }
+
+    Sections { preamble, functions, postamble }
+}
+
+/// gets the name of a decompiled function from its natspec header
+fn function_name(lines: &[String]) -> String {
+    lines
+        .iter()
+        .find_map(|line| line.trim().strip_prefix("/// @custom:name"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Diff two sequences of lines by their longest common subsequence.
+fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = match old[i] == new[j] {
+                true => lengths[i + 1][j + 1] + 1,
+                false => lengths[i + 1][j].max(lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(DiffLine::Unchanged(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            // removals are listed before the additions which replace them
+            lines.push(DiffLine::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Diff two contracts' decompiled Solidity. Functions are matched by selector and diffed line by
+/// line, so reordering functions doesn't produce a diff, and the source of both contracts is laid
+/// out with its functions in order of selector.
+pub fn diff_source(old_target: &str, old: &str, new_target: &str, new: &str) -> SourceDiff {
+    let (old, new) = (split_sections(old), split_sections(new));
+
+    let mut lines = diff_lines(&old.preamble, &new.preamble);
+    let mut functions = Vec::new();
+    let mut selectors = old.functions.keys().chain(new.functions.keys()).collect::<Vec<_>>();
+    selectors.sort();
+    selectors.dedup();
+    for selector in selectors {
+        let (old_lines, new_lines) = (old.functions.get(selector), new.functions.get(selector));
+        let (status, function_lines) = match (old_lines, new_lines) {
+            (Some(old_lines), Some(new_lines)) => {
+                let function_lines = diff_lines(old_lines, new_lines);
+                match function_lines.iter().all(|line| matches!(line, DiffLine::Unchanged(_))) {
+                    true => (DiffStatus::Unchanged, function_lines),
+                    false => (DiffStatus::Changed, function_lines),
+                }
+            }
+            (Some(old_lines), None) => {
+                (DiffStatus::Removed, old_lines.iter().cloned().map(DiffLine::Removed).collect())
+            }
+            (None, Some(new_lines)) => {
+                (DiffStatus::Added, new_lines.iter().cloned().map(DiffLine::Added).collect())
+            }
+            (None, None) => continue,
+        };
+
+        if status != DiffStatus::Unchanged {
+            functions.push(FunctionDiff {
+                selector: selector.clone(),
+                name: function_name(new_lines.or(old_lines).map(Vec::as_slice).unwrap_or_default()),
+                status,
+            });
+        }
+        lines.extend(function_lines);
+    }
+    lines.extend(diff_lines(&old.postamble, &new.postamble));
+
+    SourceDiff {
+        old_target: old_target.to_string(),
+        new_target: new_target.to_string(),
+        functions,
+        lines,
+    }
+}
+
+impl Display for SourceDiff {
+    /// Formats the diff as a unified diff, preceded by a summary of the changed functions.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for function in &self.functions {
+            let status = match function.status {
+                DiffStatus::Added => "added",
+                DiffStatus::Removed => "removed",
+                _ => "changed",
+            };
+            writeln!(f, "// {status:<8} 0x{} {}", function.selector, function.name)?;
+        }
+        if self.functions.is_empty() {
+            writeln!(f, "// no functions changed")?;
+        }
+        writeln!(f, "--- {}", self.old_target)?;
+        writeln!(f, "+++ {}", self.new_target)?;
+
+        // each changed line is shown with up to `CONTEXT_LINES` unchanged lines on either side,
+        // and changes whose context overlaps share a hunk
+        let changed = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, DiffLine::Unchanged(_)))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let mut hunks: Vec<(usize, usize)> = Vec::new();
+        for index in changed {
+            let start = index.saturating_sub(CONTEXT_LINES);
+            let end = (index + CONTEXT_LINES + 1).min(self.lines.len());
+            match hunks.last_mut() {
+                Some(hunk) if start <= hunk.1 => hunk.1 = end,
+                _ => hunks.push((start, end)),
+            }
+        }
+
+        // line numbers are counted from the start of each source, so they're tracked as lines are
+        // passed over
+        let (mut old_line, mut new_line, mut position) = (1, 1, 0);
+        for (start, end) in hunks {
+            for line in &self.lines[position..start] {
+                match line {
+                    DiffLine::Unchanged(_) => {
+                        old_line += 1;
+                        new_line += 1;
+                    }
+                    DiffLine::Removed(_) => old_line += 1,
+                    DiffLine::Added(_) => new_line += 1,
+                }
+            }
+
+            let hunk = &self.lines[start..end];
+            let old_count = hunk.iter().filter(|line| !matches!(line, DiffLine::Added(_))).count();
+            let new_count =
+                hunk.iter().filter(|line| !matches!(line, DiffLine::Removed(_))).count();
+            writeln!(f, "@@ -{old_line},{old_count} +{new_line},{new_count} @@")?;
+            for line in hunk {
+                match line {
+                    DiffLine::Unchanged(line) => writeln!(f, " {line}")?,
+                    DiffLine::Removed(line) => writeln!(f, "-{line}")?,
+                    DiffLine::Added(line) => writeln!(f, "+{line}")?,
+                }
+            }
+
+            old_line += old_count;
+            new_line += new_count;
+            position = end;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis::diff::DiffStatus,
+        diff::{
+            source::{diff_lines, diff_source},
+            DiffLine,
+        },
+    };
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        assert_eq!(
+            diff_lines(&lines(&["a", "b", "c"]), &lines(&["a", "c", "d"])),
+            vec![
+                DiffLine::Unchanged(String::from("a")),
+                DiffLine::Removed(String::from("b")),
+                DiffLine::Unchanged(String::from("c")),
+                DiffLine::Added(String::from("d")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_source() {
+        let old = [
+            "contract DecompiledContract {",
+            "/// @custom:selector    0x00000002",
+            "/// @custom:name        Unresolved_00000002",
+            "function Unresolved_00000002() public {",
+            "    stor_a = 0x01;",
+            "}",
+            "/// @custom:selector    0x00000001",
+            "/// @custom:name        Unresolved_00000001",
+            "function Unresolved_00000001() public {",
+            "}",
+            "}",
+        ]
+        .join("\n");
+        let new = [
+            "contract DecompiledContract {",
+            "/// @custom:selector    0x00000001",
+            "/// @custom:name        Unresolved_00000001",
+            "function Unresolved_00000001() public {",
+            "}",
+            "/// @custom:selector    0x00000002",
+            "/// @custom:name        Unresolved_00000002",
+            "function Unresolved_00000002() public {",
+            "    stor_a = 0x02;",
+            "}",
+            "/// @custom:selector    0x00000003",
+            "/// @custom:name        Unresolved_00000003",
+            "function Unresolved_00000003() public {",
+            "}",
+            "}",
+        ]
+        .join("\n");
+
+        let source_diff = diff_source("old", &old, "new", &new);
+
+        // the reordered function is unchanged
+        assert_eq!(
+            source_diff
+                .functions
+                .iter()
+                .map(|function| (function.selector.as_str(), function.status))
+                .collect::<Vec<_>>(),
+            [("00000002", DiffStatus::Changed), ("00000003", DiffStatus::Added)]
+        );
+        assert_eq!(
+            source_diff.to_string(),
+            [
+                "// changed  0x00000002 Unresolved_00000002",
+                "// added    0x00000003 Unresolved_00000003",
+                "--- old",
+                "+++ new",
+                "@@ -6,6 +6,10 @@",
+                " /// @custom:selector    0x00000002",
+                " /// @custom:name        Unresolved_00000002",
+                " function Unresolved_00000002() public {",
+                "-    stor_a = 0x01;",
+                "+    stor_a = 0x02;",
+                " }",
+                "+/// @custom:selector    0x00000003",
+                "+/// @custom:name        Unresolved_00000003",
+                "+function Unresolved_00000003() public {",
+                "+}",
+                " }",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
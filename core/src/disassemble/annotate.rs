@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use ethers::{types::U256, utils::keccak256};
+use heimdall_common::{
+    ether::{
+        evm::core::opcodes::Opcode,
+        selectors::resolve_selectors,
+        signatures::{score_signature, ResolvedFunction, ResolvedLog},
+    },
+    utils::strings::encode_hex,
+};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// Well-known mainnet contracts, keyed by their lowercase address without the 0x prefix.
+    static ref KNOWN_ADDRESSES: HashMap<&'static str, &'static str> = HashMap::from([
+        ("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", "WETH"),
+        ("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "USDC"),
+        ("dac17f958d2ee523a2206206994597c13d831ec7", "USDT"),
+        ("6b175474e89094c44da98b954eedeac495271d0f", "DAI"),
+        ("7a250d5630b4cf539739df2c5dacb4c659f2488d", "Uniswap V2: Router 2"),
+        ("5c69bee701ef814a2b6a3edd4b1652cb9cc5aa6f", "Uniswap V2: Factory"),
+        ("1f98431c8ad98523631ae4a59f267346ea31f984", "Uniswap V3: Factory"),
+        ("e592427a0aece92de3edee1f18e0157c05861564", "Uniswap V3: Router"),
+        ("000000000022d473030f116ddee9f6b43ac78ba3", "Permit2"),
+        ("ca11bde05977b3631167028862be2a173976ca11", "Multicall3"),
+        ("00000000000c2e074ec69a0dfb2997ba6c7d2e1e", "ENS: Registry"),
+        ("4e59b44847b379578588920ca78fbf26c0b4956c", "CREATE2 Deployer"),
+    ]);
+
+    /// Well-known 32-byte constants, such as access control roles and proxy storage slots.
+    static ref KNOWN_HASHES: HashMap<[u8; 32], String> = {
+        let mut hashes = HashMap::new();
+
+        // OpenZeppelin AccessControl roles are the keccak256 hash of their name
+        for role in [
+            "ADMIN_ROLE",
+            "MINTER_ROLE",
+            "BURNER_ROLE",
+            "PAUSER_ROLE",
+            "UPGRADER_ROLE",
+            "OPERATOR_ROLE",
+            "MANAGER_ROLE",
+            "GOVERNOR_ROLE",
+            "GUARDIAN_ROLE",
+            "EXECUTOR_ROLE",
+            "PROPOSER_ROLE",
+            "CANCELLER_ROLE",
+            "SNAPSHOT_ROLE",
+            "TIMELOCK_ADMIN_ROLE",
+        ] {
+            hashes.insert(keccak256(role), role.to_string());
+        }
+
+        // EIP-1967 proxy slots are the keccak256 hash of their label, minus one
+        for (label, name) in [
+            ("eip1967.proxy.implementation", "EIP-1967 implementation slot"),
+            ("eip1967.proxy.admin", "EIP-1967 admin slot"),
+            ("eip1967.proxy.beacon", "EIP-1967 beacon slot"),
+        ] {
+            let mut slot = [0u8; 32];
+            (U256::from(keccak256(label)) - 1).to_big_endian(&mut slot);
+            hashes.insert(slot, name.to_string());
+        }
+
+        hashes
+    };
+}
+
+/// Heuristically annotates PUSH operands with their likely meaning, such as the function a
+/// 4-byte selector belongs to, or the event a 32-byte topic identifies.
+#[derive(Debug, Clone, Default)]
+pub struct PushDataAnnotator {
+    // resolved function signatures, keyed by selector without the 0x prefix
+    pub functions: HashMap<String, Vec<ResolvedFunction>>,
+
+    // resolved event signatures, keyed by topic without the 0x prefix
+    pub events: HashMap<String, Vec<ResolvedLog>>,
+}
+
+impl PushDataAnnotator {
+    /// Create a new annotator for the given bytecode, resolving every PUSH4 operand as a function
+    /// selector and every PUSH32 operand as an event topic.
+    pub async fn new(bytecode: &[u8]) -> Self {
+        let mut selectors = Vec::new();
+        let mut topics = Vec::new();
+
+        let mut program_counter = 0;
+        while program_counter < bytecode.len() {
+            let operation = Opcode::new(bytecode[program_counter]);
+            let byte_count = match operation.code {
+                0x60..=0x7f => (operation.code - 0x5f) as usize,
+                _ => 0,
+            };
+
+            if let Some(bytes) = bytecode.get(program_counter + 1..program_counter + 1 + byte_count)
+            {
+                match byte_count {
+                    4 if bytes != [0xff; 4] => selectors.push(encode_hex(bytes.to_vec())),
+                    32 if !KNOWN_HASHES.contains_key(bytes) => {
+                        topics.push(encode_hex(bytes.to_vec()))
+                    }
+                    _ => {}
+                }
+            }
+
+            program_counter += 1 + byte_count;
+        }
+
+        selectors.sort();
+        selectors.dedup();
+        topics.sort();
+        topics.dedup();
+
+        PushDataAnnotator {
+            functions: resolve_selectors::<ResolvedFunction>(selectors).await,
+            events: resolve_selectors::<ResolvedLog>(topics).await,
+        }
+    }
+
+    /// Returns an annotation for the given PUSH operand, if one can be inferred.
+    ///
+    /// ```
+    /// use heimdall_core::disassemble::annotate::PushDataAnnotator;
+    ///
+    /// let annotator = PushDataAnnotator::default();
+    /// let weth = [
+    ///     0xc0, 0x2a, 0xaa, 0x39, 0xb2, 0x23, 0xfe, 0x8d, 0x0a, 0x0e, 0x5c, 0x4f, 0x27, 0xea, 0xd9,
+    ///     0x08, 0x3c, 0x75, 0x6c, 0xc2,
+    /// ];
+    ///
+    /// assert_eq!(annotator.annotate(&weth), Some(String::from("address (WETH)")));
+    /// ```
+    pub fn annotate(&self, push_data: &[u8]) -> Option<String> {
+        let key = encode_hex(push_data.to_vec());
+
+        match push_data.len() {
+            4 => self
+                .functions
+                .get(&key)?
+                .iter()
+                .max_by_key(|function| score_signature(&function.signature))
+                .map(|function| function.signature.clone()),
+            20 if push_data != [0xff; 20] => Some(match KNOWN_ADDRESSES.get(key.as_str()) {
+                Some(label) => format!("address ({label})"),
+                None => String::from("address"),
+            }),
+            32 => match KNOWN_HASHES.get(push_data) {
+                Some(name) => Some(name.clone()),
+                None => self
+                    .events
+                    .get(&key)?
+                    .iter()
+                    .max_by_key(|event| score_signature(&event.signature))
+                    .map(|event| format!("event {}", event.signature)),
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::{ether::signatures::ResolvedFunction, utils::strings::decode_hex};
+
+    use crate::disassemble::annotate::*;
+
+    #[test]
+    fn test_annotate_selector() {
+        let mut annotator = PushDataAnnotator::default();
+        annotator.functions.insert(
+            String::from("a9059cbb"),
+            vec![ResolvedFunction {
+                name: String::from("transfer"),
+                signature: String::from("transfer(address,uint256)"),
+                inputs: vec![String::from("address"), String::from("uint256")],
+                decoded_inputs: None,
+            }],
+        );
+
+        assert_eq!(
+            annotator.annotate(&[0xa9, 0x05, 0x9c, 0xbb]),
+            Some(String::from("transfer(address,uint256)"))
+        );
+        assert_eq!(annotator.annotate(&[0x12, 0x34, 0x56, 0x78]), None);
+    }
+
+    #[test]
+    fn test_annotate_address() {
+        assert_eq!(
+            PushDataAnnotator::default().annotate(&[0x11; 20]),
+            Some(String::from("address"))
+        );
+        assert_eq!(PushDataAnnotator::default().annotate(&[0xff; 20]), None);
+    }
+
+    #[test]
+    fn test_annotate_role_and_slot() {
+        let annotator = PushDataAnnotator::default();
+
+        assert_eq!(
+            annotator.annotate(&keccak256("MINTER_ROLE")),
+            Some(String::from("MINTER_ROLE"))
+        );
+        assert_eq!(
+            annotator.annotate(
+                &decode_hex("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc")
+                    .unwrap()
+            ),
+            Some(String::from("EIP-1967 implementation slot"))
+        );
+        assert_eq!(annotator.annotate(&[0x01; 32]), None);
+    }
+}
@@ -1,15 +1,26 @@
-use std::fs;
+pub mod annotate;
+
+use std::collections::{BTreeMap, HashSet};
 
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
 use heimdall_common::{
-    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
-    ether::{evm::core::opcodes::Opcode, rpc::get_code},
+    ether::{
+        eof::{decode_eof_instructions, is_eof, is_eof_terminator, EofContainer},
+        evm::core::{hardfork::Hardfork, opcodes::Opcode},
+        metadata::{decode_metadata, find_metadata_offset},
+    },
     utils::{
         io::logging::Logger,
         strings::{decode_hex, encode_hex},
     },
 };
+use serde::Serialize;
+
+use crate::{
+    analysis::blocks::is_terminator, disassemble::annotate::PushDataAnnotator, error::Error,
+    utils::get_contract_bytecode,
+};
 
 #[derive(Debug, Clone, Parser, Builder)]
 #[clap(about = "Disassemble EVM bytecode to Assembly",
@@ -17,7 +28,9 @@ use heimdall_common::{
        global_setting = AppSettings::DeriveDisplayOrder,
        override_usage = "heimdall disassemble <TARGET> [OPTIONS]")]
 pub struct DisassemblerArgs {
-    /// The target to disassemble, either a file, bytecode, contract address, or ENS name.
+    /// The target to disassemble, either a file, bytecode, contract address, ENS name,
+    /// or `-` to read bytecode from stdin. Files may be forge, hardhat, or solc artifacts, whose
+    /// deployed bytecode is used, with `artifact.json:Name` selecting one of several contracts.
     #[clap(required = true)]
     pub target: String,
 
@@ -25,7 +38,8 @@ pub struct DisassemblerArgs {
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 
-    /// The RPC provider to use for fetching target bytecode.
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
     #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
     pub rpc_url: String,
 
@@ -36,6 +50,50 @@ pub struct DisassemblerArgs {
     /// The output directory to write the output to or 'print' to print to the console
     #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
     pub output: String,
+
+    /// Whether to label JUMPDESTs, render constant jump targets as labels, and separate basic
+    /// blocks in the assembly.
+    #[clap(long = "labels", short = 'l')]
+    pub labels: bool,
+
+    /// Whether to annotate PUSH operands with their likely meaning, such as resolved function
+    /// selectors, event topics, addresses, and access control roles.
+    #[clap(long = "annotate", short = 'a')]
+    pub annotate: bool,
+
+    /// Whether to output the disassembly as a JSON array of instructions, set by the global
+    /// `--json` flag.
+    #[clap(skip)]
+    pub json: bool,
+
+    /// The hardfork whose opcodes are valid. Opcodes from later hardforks are disassembled as
+    /// `unknown`.
+    #[clap(long = "evm-version", default_value = "cancun")]
+    pub evm_version: Hardfork,
+}
+
+/// A single disassembled instruction, as emitted by `--json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DisassembledInstruction {
+    // the program counter of the opcode
+    pub pc: usize,
+
+    // the name of the opcode, i.e. PUSH1
+    pub opcode: String,
+
+    // the hex-encoded bytes pushed by the instruction, if it is a PUSH
+    pub push_data: Option<String>,
+
+    // the minimum gas cost of the opcode
+    pub gas: u16,
+
+    // the EOF code section containing the opcode, if the bytecode is an EOF container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<usize>,
+
+    // the likely meaning of the pushed bytes, if `--annotate` is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
 }
 
 impl DisassemblerArgsBuilder {
@@ -46,12 +104,163 @@ impl DisassemblerArgsBuilder {
             rpc_url: Some(String::new()),
             decimal_counter: Some(false),
             output: Some(String::new()),
+            labels: Some(false),
+            annotate: Some(false),
+            json: Some(false),
+            evm_version: Some(Hardfork::default()),
+        }
+    }
+}
+
+/// Find the program counter of every valid JUMPDEST in the given bytecode, skipping over push
+/// data.
+fn find_jumpdests(bytecode: &[u8]) -> HashSet<usize> {
+    let mut jumpdests = HashSet::new();
+    let mut program_counter = 0;
+
+    while program_counter < bytecode.len() {
+        let operation = Opcode::new(bytecode[program_counter]);
+        if operation.name == "JUMPDEST" {
+            jumpdests.insert(program_counter);
+        } else if let Some(byte_count) = operation.name.strip_prefix("PUSH") {
+            program_counter += byte_count.parse::<usize>().unwrap_or(0);
+        }
+        program_counter += 1;
+    }
+
+    jumpdests
+}
+
+/// Warn about any opcodes in the given bytecode which aren't available in the given hardfork, since
+/// the bytecode was likely compiled for a later one.
+pub(crate) fn warn_unsupported_opcodes(bytecode: &[u8], hardfork: Hardfork, logger: &Logger) {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for (_, opcode) in hardfork.unsupported_opcodes(bytecode) {
+        *counts.entry(opcode.name).or_default() += 1;
+    }
+
+    if !counts.is_empty() {
+        logger.warn(&format!("bytecode uses opcodes which aren't available in {hardfork}:"));
+        for (name, count) in counts {
+            logger.warn(&format!("  - {name} ({count} times)"));
+        }
+    }
+}
+
+/// Disassemble each section of an EOF container. Program counters are offsets within the
+/// container, so they remain unique across code sections.
+fn disassemble_eof(
+    container: &EofContainer,
+    decimal_counter: bool,
+    labels: bool,
+    json: bool,
+) -> Result<String, Error> {
+    let format_counter = |pc: usize| {
+        if decimal_counter {
+            pc.to_string()
+        } else {
+            format!("{:06x}", pc)
+        }
+    };
+
+    let mut output = String::new();
+    let mut instructions: Vec<DisassembledInstruction> = Vec::new();
+
+    for (section, code_section) in container.code_sections.iter().enumerate() {
+        let section_instructions = decode_eof_instructions(&code_section.code, section)?;
+        let jump_targets: HashSet<isize> = section_instructions
+            .iter()
+            .flat_map(|instruction| instruction.jump_targets())
+            .collect();
+
+        output.push_str(&format!(
+            "; code section {} (inputs: {}, outputs: {}, max stack height: {})\n",
+            section,
+            code_section.inputs,
+            if code_section.outputs == 0x80 {
+                String::from("non-returning")
+            } else {
+                code_section.outputs.to_string()
+            },
+            code_section.max_stack_height
+        ));
+
+        let mut block_ended = false;
+        for instruction in section_instructions {
+            let pc = code_section.offset + instruction.pc;
+
+            if json {
+                instructions.push(DisassembledInstruction {
+                    pc,
+                    opcode: instruction.opcode.name.to_string(),
+                    push_data: (!instruction.immediate.is_empty())
+                        .then(|| encode_hex(instruction.immediate.clone())),
+                    gas: instruction.opcode.mingas,
+                    section: Some(section),
+                    annotation: None,
+                });
+                continue
+            }
+
+            let mut immediate = encode_hex(instruction.immediate.clone());
+            if labels {
+                // separate basic blocks with an empty line, and label each jump target
+                let is_target = jump_targets.contains(&(instruction.pc as isize));
+                if block_ended || (is_target && instruction.pc != 0) {
+                    output.push('\n');
+                }
+                if is_target {
+                    output.push_str(&format!("label_0x{:x}:\n", pc));
+                }
+                block_ended = is_eof_terminator(&instruction.opcode);
+
+                // render relative jump targets as labels
+                let targets = instruction.jump_targets();
+                if !targets.is_empty() {
+                    immediate = targets
+                        .iter()
+                        .map(|target| {
+                            format!("label_0x{:x}", code_section.offset as isize + target)
+                        })
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                }
+            }
+
+            output.push_str(&format!(
+                "{} {} {}\n",
+                format_counter(pc),
+                instruction.opcode.name,
+                immediate
+            ));
         }
+        output.push('\n');
+    }
+
+    if json {
+        return Ok(serde_json::to_string_pretty(&instructions)?)
+    }
+
+    for (index, container_section) in container.container_sections.iter().enumerate() {
+        output.push_str(&format!(
+            "; container section {} ({} bytes)\n{}\n\n",
+            index,
+            container_section.len(),
+            encode_hex(container_section.clone())
+        ));
     }
+    output.push_str(&format!(
+        "; data section at {} ({} bytes)\n{}\n",
+        format_counter(container.data_offset),
+        container.data.len(),
+        encode_hex(container.data.clone())
+    ));
+
+    Ok(output)
 }
 
 /// Disassemble the given target's bytecode to assembly.
-pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Box<dyn std::error::Error>> {
+pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Error> {
     use std::time::Instant;
     let now = Instant::now();
 
@@ -72,41 +281,47 @@ pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Box<dyn std::
         None => "SILENT",
     });
 
-    let contract_bytecode: String;
-    if ADDRESS_REGEX.is_match(&args.target)? {
-        // We are disassembling a contract address, so we need to fetch the bytecode from the RPC
-        // provider.
-        contract_bytecode = get_code(&args.target, &args.rpc_url).await?;
-    } else if BYTECODE_REGEX.is_match(&args.target)? {
-        contract_bytecode = args.target;
-    } else {
-        // We are disassembling a file, so we need to read the bytecode from the file.
-        contract_bytecode = match fs::read_to_string(&args.target) {
-            Ok(contents) => {
-                let _contents = contents.replace('\n', "");
-                if BYTECODE_REGEX.is_match(&_contents)? && _contents.len() % 2 == 0 {
-                    _contents
-                } else {
-                    logger
-                        .error(&format!("file '{}' doesn't contain valid bytecode.", &args.target));
-                    std::process::exit(1)
-                }
-            }
-            Err(_) => {
-                logger.error(&format!("failed to open file '{}' .", &args.target));
-                std::process::exit(1)
-            }
-        };
-    }
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
 
     let mut program_counter = 0;
     let mut output: String = String::new();
+    let mut instructions: Vec<DisassembledInstruction> = Vec::new();
 
     // Iterate over the bytecode, disassembling each instruction.
     let byte_array = decode_hex(&contract_bytecode.replacen("0x", "", 1))?;
 
+    // EOF containers are disassembled section by section
+    if is_eof(&byte_array) {
+        let container = EofContainer::parse(&byte_array)?;
+        logger.debug(&format!(
+            "found EOF container with {} code sections.",
+            container.code_sections.len()
+        ));
+
+        let output = disassemble_eof(&container, args.decimal_counter, args.labels, args.json)?;
+        logger.info(&format!("disassembled {} bytes successfully.", byte_array.len()));
+        logger.debug(&format!("disassembly completed in {} ms.", now.elapsed().as_millis()));
+
+        return Ok(output)
+    }
+    // trailing compiler metadata isn't executable, so it's decoded rather than disassembled
+    let (byte_array, metadata) =
+        byte_array.split_at(find_metadata_offset(&byte_array).unwrap_or(byte_array.len()));
+
+    warn_unsupported_opcodes(byte_array, args.evm_version, &logger);
+
+    let jumpdests = if args.labels { find_jumpdests(byte_array) } else { HashSet::new() };
+    let annotator = if args.annotate {
+        logger.debug("resolving PUSH operands for annotation.");
+        PushDataAnnotator::new(byte_array).await
+    } else {
+        PushDataAnnotator::default()
+    };
+    let mut block_ended = false;
+
     while program_counter < byte_array.len() {
-        let operation = Opcode::new(byte_array[program_counter]);
+        let operation = args.evm_version.opcode(byte_array[program_counter]);
+        let opcode_counter = program_counter;
         let mut pushed_bytes: String = String::new();
 
         if operation.name.contains("PUSH") {
@@ -121,22 +336,96 @@ pub async fn disassemble(args: DisassemblerArgs) -> Result<String, Box<dyn std::
             program_counter += byte_count_to_push as usize;
         }
 
+        let annotation = if args.annotate {
+            annotator.annotate(&byte_array[opcode_counter + 1..program_counter + 1])
+        } else {
+            None
+        };
+
+        if args.json {
+            instructions.push(DisassembledInstruction {
+                pc: opcode_counter,
+                opcode: operation.name.to_string(),
+                push_data: if operation.name.contains("PUSH") && operation.name != "PUSH0" {
+                    Some(pushed_bytes)
+                } else {
+                    None
+                },
+                gas: operation.mingas,
+                section: None,
+                annotation,
+            });
+            program_counter += 1;
+            continue
+        }
+
+        if args.labels {
+            // separate basic blocks with an empty line, and label each JUMPDEST
+            if (block_ended || operation.name == "JUMPDEST") && !output.is_empty() {
+                output.push('\n');
+            }
+            if operation.name == "JUMPDEST" {
+                output.push_str(&format!("label_0x{:x}:\n", opcode_counter));
+            }
+            block_ended = is_terminator(&operation);
+
+            // include the raw byte of unknown opcodes so the output can be reassembled
+            if operation.name == "unknown" {
+                pushed_bytes = format!("{:02x}", operation.code);
+            }
+
+            // render constant jump targets as labels
+            if matches!(byte_array.get(program_counter + 1), Some(0x56) | Some(0x57)) {
+                if let Ok(target) = usize::from_str_radix(&pushed_bytes, 16) {
+                    if jumpdests.contains(&target) {
+                        pushed_bytes = format!("label_0x{:x}", target);
+                    }
+                }
+            }
+        }
+
         output.push_str(
             format!(
-                "{} {} {}\n",
+                "{} {} {}{}\n",
                 if args.decimal_counter {
                     program_counter.to_string()
                 } else {
                     format!("{:06x}", program_counter)
                 },
                 operation.name,
-                pushed_bytes
+                pushed_bytes,
+                annotation.map(|annotation| format!(" ; {annotation}")).unwrap_or_default()
             )
             .as_str(),
         );
         program_counter += 1;
     }
 
+    if !metadata.is_empty() {
+        if args.labels {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "; metadata at {} ({} bytes)\n",
+            if args.decimal_counter {
+                byte_array.len().to_string()
+            } else {
+                format!("{:06x}", byte_array.len())
+            },
+            metadata.len()
+        ));
+        match decode_metadata(metadata) {
+            Some(fields) => fields
+                .iter()
+                .for_each(|(key, value)| output.push_str(&format!("; {key}: {value}\n"))),
+            None => output.push_str(&format!("; {}\n", encode_hex(metadata.to_vec()))),
+        }
+    }
+
+    if args.json {
+        output = serde_json::to_string_pretty(&instructions)?;
+    }
+
     logger.info(&format!("disassembled {program_counter} bytes successfully."));
     logger.debug(&format!("disassembly completed in {} ms.", now.elapsed().as_millis()));
 
@@ -4,6 +4,15 @@ use lazy_static::lazy_static;
 
 use crate::dump::structures::dump_state::DumpState;
 
+/// The signatures of the `Transfer`, `Approval`, and `ApprovalForAll` events, whose topics are the
+/// keys of most balance, allowance, and ownership mappings.
+pub const TRANSFER_EVENT: &str =
+    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+pub const APPROVAL_EVENT: &str =
+    "0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+pub const APPROVAL_FOR_ALL_EVENT: &str =
+    "0x17307eab39ab6107e8899845ad3d59bd9653f200f220920489ca2b5937696c31";
+
 lazy_static! {
     /// The global [`DumpState`] instance.
     pub static ref DUMP_STATE: Mutex<DumpState> = Mutex::new(DumpState::new());
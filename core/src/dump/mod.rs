@@ -1,22 +1,49 @@
 mod constants;
 mod menus;
+pub mod output;
 mod structures;
 mod util;
+pub mod watch;
 
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
-use ethers::types::H160;
+use ethers::types::{H160, H256};
 use heimdall_common::{
+    ether::{
+        history,
+        proof::{verify_account_proof, verify_storage_proof},
+        rpc::set_rpc_rate_limit,
+    },
     resources::transpose::{get_contract_creation, get_transaction_list},
     utils::io::logging::*,
 };
-use std::{collections::HashMap, env, str::FromStr, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    str::FromStr,
+    sync::Arc,
+    time::Instant,
+};
 
 use self::{
-    constants::DUMP_STATE,
+    constants::{APPROVAL_EVENT, APPROVAL_FOR_ALL_EVENT, DUMP_STATE, TRANSFER_EVENT},
     menus::TUIView,
-    structures::{dump_state::DumpState, transaction::Transaction},
-    util::csv::{build_csv, DumpRow},
+    structures::{
+        checkpoint::Checkpoint,
+        dump_state::DumpState,
+        proof::{AccountProof, SlotProof},
+        storage_slot::StorageSlot,
+        transaction::Transaction,
+    },
+    util::{
+        csv::{build_csv, DumpRow},
+        get_storage_proofs, get_storage_values,
+        layout::{event_keys, mapping_slots},
+    },
+};
+use crate::{
+    decompile::{decompile, DecompilerArgsBuilder},
+    error::Error,
 };
 
 #[derive(Debug, Clone, Parser, Builder)]
@@ -40,18 +67,31 @@ pub struct DumpArgs {
     #[clap(long = "output", short, default_value = "output", hide_default_value = true)]
     pub output: String,
 
-    /// The RPC URL to use for fetching data.
+    /// The RPC URL to use for fetching data. Separate multiple URLs with commas to fail over
+    /// between them.
     #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
     pub rpc_url: String,
 
-    /// Your Transpose.io API Key
+    /// Your Transpose.io API Key. Separate multiple keys with commas to rotate between them when
+    /// one is rate limited. Without a key, transactions are found with the RPC provider's
+    /// `trace_filter`, which requires an archive node.
     #[clap(long = "transpose-api-key", short, default_value = "", hide_default_value = true)]
     pub transpose_api_key: String,
 
-    /// The number of threads to use when fetching data.
+    /// The number of threads to use when fetching data. Each thread fetches one chunk of
+    /// transactions at a time.
     #[clap(long, default_value = "4", hide_default_value = true)]
     pub threads: usize,
 
+    /// The most requests per second to send to the RPC provider, shared by every thread, or 0 for
+    /// no limit. Public providers usually need a limit, while local nodes don't.
+    #[clap(long = "requests-per-second", default_value = "0", hide_default_value = true)]
+    pub requests_per_second: f64,
+
+    /// The most transactions whose state diffs are fetched in a single batch of requests.
+    #[clap(long = "chunk-size", default_value = "25", hide_default_value = true)]
+    pub chunk_size: usize,
+
     /// The block number to start dumping from.
     #[clap(long, default_value = "0", hide_default_value = true)]
     pub from_block: u128,
@@ -67,6 +107,45 @@ pub struct DumpArgs {
     /// The chain of the target. Valid chains are ethereum, polygon, goerli, canto, and arbitrum.
     #[clap(long, default_value = "ethereum", hide_default_value = true)]
     pub chain: String,
+
+    /// The format to write the dump in, either 'csv', 'sqlite', or 'parquet'. SQLite databases
+    /// hold each slot's modifications too, and are indexed by slot and block.
+    #[clap(long = "output-format", default_value = "csv")]
+    pub output_format: String,
+
+    /// Resume an interrupted dump of the same target and block range from its last checkpoint,
+    /// rather than indexing every transaction again.
+    #[clap(long)]
+    pub resume: bool,
+
+    /// Decompile the target to recover its storage layout, and label each dumped slot with the
+    /// variable it belongs to, including the keys of mapping entries. The decoded slots are
+    /// written to `dump.decoded.csv`.
+    #[clap(long = "decode-layout")]
+    pub decode_layout: bool,
+
+    /// Find the keys of balance, allowance, and ownership mappings in the target's `Transfer`,
+    /// `Approval`, and `ApprovalForAll` events, and dump those mapping entries, even if no
+    /// transaction in the block range modified them. Implies `--decode-layout`.
+    #[clap(long = "enumerate-mappings")]
+    pub enumerate_mappings: bool,
+
+    /// Only dump the slots whose values changed between the start of `--from-block` and the end
+    /// of `--to-block`, with each slot's old and new values and the transactions which modified
+    /// it.
+    #[clap(long)]
+    pub diff: bool,
+
+    /// Fetch a Merkle proof of each dumped slot with `eth_getProof`, and verify it against the
+    /// state root of `--to-block`, or the latest block. The proofs are written to
+    /// `dump.proof.json`, so the dump can be checked independently.
+    #[clap(long)]
+    pub verify: bool,
+
+    /// Once the dump has finished, keep watching new blocks for transactions which write to the
+    /// target's storage, and append each write to `dump.watch.csv` as its block is traced.
+    #[clap(long)]
+    pub watch: bool,
 }
 
 impl DumpArgsBuilder {
@@ -78,17 +157,26 @@ impl DumpArgsBuilder {
             rpc_url: Some(String::new()),
             transpose_api_key: Some(String::new()),
             threads: Some(8),
+            requests_per_second: Some(0.0),
+            chunk_size: Some(25),
             from_block: Some(0),
             to_block: Some(9999999999),
             no_tui: Some(true),
             chain: Some(String::from("ethereum")),
+            output_format: Some(String::from("csv")),
+            resume: Some(false),
+            decode_layout: Some(false),
+            enumerate_mappings: Some(false),
+            diff: Some(false),
+            verify: Some(false),
+            watch: Some(false),
         }
     }
 }
 
 /// entry point for the dump module. Will fetch all storage slots accessed by the target contract,
 /// and dump them to a CSV file or the TUI.
-pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Error>> {
+pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Error> {
     // set logger environment variable if not already set
     if std::env::var("RUST_LOG").is_err() {
         std::env::set_var(
@@ -105,36 +193,37 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
         None => "SILENT",
     });
 
+    // every request to the RPC provider shares the rate limit, across all of the threads
+    set_rpc_rate_limit(args.requests_per_second);
+
     // parse the output directory
     let mut output_dir = args.output.clone();
     if args.output.is_empty() {
-        output_dir = match env::current_dir() {
-            Ok(dir) => dir.into_os_string().into_string().unwrap(),
-            Err(_) => {
-                logger.error("failed to get current directory.");
-                std::process::exit(1);
-            }
-        };
+        output_dir = env::current_dir()?.to_string_lossy().to_string();
         output_dir.push_str("/output");
     }
 
-    // check if transpose api key is set
-    if args.transpose_api_key.is_empty() {
-        logger.error("you must provide a Transpose API key, which is used to fetch all normal and internal transactions for your target.");
+    // without a Transpose API key, transactions are found with the RPC provider's trace APIs
+    let use_transpose = !args.transpose_api_key.is_empty();
+    if !use_transpose {
+        logger.info("no Transpose API key was given, so transactions will be found with your RPC provider's `trace_filter`, which requires an archive node.");
         logger.info("you can get a free API key at https://app.transpose.io/?utm_medium=organic&utm_source=heimdall-rs");
-        std::process::exit(1);
     }
 
     // get the contract creation tx
+    let contract_creation_tx = match use_transpose {
+        true => get_contract_creation(&args.chain, &args.target, &args.transpose_api_key).await?,
+        false => history::get_contract_creation(&args.rpc_url, &args.target)
+            .await
+            .map_err(|e| logger.debug(&format!("failed to trace contract creation: {e}")))
+            .ok(),
+    };
     let contract_creation_tx =
-        match get_contract_creation(&args.chain, &args.target, &args.transpose_api_key).await {
+        match contract_creation_tx {
             Some(tx) => tx,
-            None => {
-                logger.error(
+            None => return Err(Error::ResolutionError(String::from(
                 "failed to get contract creation transaction. Is the target a contract address?",
-            );
-                std::process::exit(1);
-            }
+            ))),
         };
 
     // add the contract creation tx to the transactions list to be indexed
@@ -149,8 +238,10 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
     let addr_hash = match H160::from_str(&args.target) {
         Ok(addr) => addr,
         Err(_) => {
-            logger.error(&format!("failed to parse target '{}' .", &args.target));
-            std::process::exit(1);
+            return Err(Error::ResolutionError(format!(
+                "failed to parse target '{}' .",
+                &args.target
+            )))
         }
     };
 
@@ -159,14 +250,36 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
         output_dir.push_str(&format!("/{}", &args.target));
     }
 
+    // when watching, new blocks are traced from the first one the transaction list doesn't cover
+    let last_block = match args.watch {
+        true => history::get_block_number(&args.rpc_url)
+            .await
+            .map_err(|e| Error::RpcError(format!("failed to get the latest block: {e}")))?
+            .min(args.to_block),
+        false => args.to_block,
+    };
+
     // fetch transactions
-    let transaction_list = get_transaction_list(
-        &args.chain,
-        &args.target,
-        &args.transpose_api_key,
-        (&args.from_block, &args.to_block),
-    )
-    .await;
+    let transaction_list = match use_transpose {
+        true => {
+            get_transaction_list(
+                &args.chain,
+                &args.target,
+                &args.transpose_api_key,
+                (&args.from_block, &args.to_block),
+            )
+            .await?
+        }
+
+        // nothing calls the contract before it's created, so earlier blocks aren't searched
+        false => history::get_transaction_list(
+            &args.rpc_url,
+            &args.target,
+            (&args.from_block.max(contract_creation_tx.0), &args.to_block),
+        )
+        .await
+        .map_err(|e| Error::RpcError(format!("failed to trace transactions: {e}")))?,
+    };
 
     // convert to vec of Transaction
     for transaction in transaction_list {
@@ -177,20 +290,83 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
         });
     }
 
+    // restore the progress of an interrupted dump
+    let mut storage = HashMap::new();
+    let mut accounts = HashSet::new();
+    if args.resume {
+        match Checkpoint::load(&args) {
+            Some(checkpoint) => {
+                for transaction in transactions.iter_mut() {
+                    transaction.indexed = checkpoint.indexed.contains(&transaction.hash);
+                }
+                logger.info(&format!(
+                    "resuming dump with {} of {} transactions already indexed.",
+                    transactions.iter().filter(|tx| tx.indexed).count(),
+                    transactions.len()
+                ));
+                storage = checkpoint.storage;
+                accounts = checkpoint.accounts;
+            }
+            None => logger.warn("no checkpoint was found for this dump, starting from scratch."),
+        }
+    }
+
+    // recover the storage layout, so slots can be labeled with the variables they belong to
+    let mut layout = Vec::new();
+    if args.decode_layout || args.enumerate_mappings {
+        logger.info("decompiling the target to recover its storage layout.");
+        let decompiler_args = DecompilerArgsBuilder::new()
+            .target(args.target.clone())
+            .rpc_url(args.rpc_url.clone())
+            .skip_resolving(true)
+            .force_decompile(true)
+            .build()
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        layout = decompile(decompiler_args).await?.storage_layout;
+        logger.debug(&format!("recovered {} storage variables.", layout.len()));
+    }
+
+    // find mapping keys in the target's event history, from its creation onwards, since entries
+    // set before the dumped block range are still read
+    let (mut keys, mut key_pairs) = (HashSet::new(), HashSet::new());
+    if args.enumerate_mappings {
+        logger.info("searching the target's event history for mapping keys.");
+        let events = history::get_event_topics(
+            &args.rpc_url,
+            &args.target,
+            &[TRANSFER_EVENT, APPROVAL_EVENT, APPROVAL_FOR_ALL_EVENT],
+            (&contract_creation_tx.0, &args.to_block),
+        )
+        .await
+        .map_err(|e| Error::RpcError(format!("failed to fetch event history: {e}")))?;
+        (keys, key_pairs) = event_keys(&events);
+        logger.debug(&format!(
+            "found {} mapping keys in {} events.",
+            keys.len() + key_pairs.len(),
+            events.len()
+        ));
+    }
+
     // update state
-    let mut state = DUMP_STATE.lock().unwrap();
-    *state = DumpState {
+    *DUMP_STATE.lock().unwrap() = DumpState {
         args: args.clone(),
         transactions,
         scroll_index: 0,
         selection_size: 1,
-        storage: HashMap::new(),
+        storage,
         view: TUIView::Main,
         start_time: Instant::now(),
         input_buffer: String::new(),
         filter: String::new(),
+        accounts,
+        layout,
+        event_keys: keys,
+        event_key_pairs: key_pairs,
+        previous_values: HashMap::new(),
+        proofs: HashMap::new(),
+        last_block,
+        failure: None,
     };
-    drop(state);
 
     let _output_dir = output_dir.clone();
     let _args = args.clone();
@@ -208,24 +384,154 @@ pub async fn dump(args: DumpArgs) -> Result<Vec<DumpRow>, Box<dyn std::error::Er
 
     // if no-tui flag is set, wait for the indexing thread to finish
     if _args.no_tui {
-        match dump_thread.join() {
-            Ok(_) => {}
-            Err(e) => {
-                logger.error("failed to join indexer thread.");
-                logger.error(&format!("{e:?}"));
-                std::process::exit(1);
-            }
-        }
+        dump_thread
+            .join()
+            .map_err(|e| Error::GenericError(format!("failed to join indexer thread: {e:?}")))?;
     } else {
         // wait for the TUI thread to finish
-        match tui_thread.join() {
-            Ok(_) => {}
-            Err(e) => {
-                logger.error("failed to join TUI thread.");
-                logger.error(&format!("{e:?}"));
-                std::process::exit(1);
+        tui_thread
+            .join()
+            .map_err(|e| Error::GenericError(format!("failed to join TUI thread: {e:?}")))?;
+    }
+
+    // a failed dump is checkpointed, so it can be resumed once the failure is fixed
+    {
+        let state = DUMP_STATE.lock().unwrap();
+        if let Some(failure) = state.failure.clone() {
+            Checkpoint::new(&state).save(&_args);
+            return Err(failure.into())
+        }
+    }
+
+    // a finished dump doesn't need to be resumed, but one which was quit early might be. the
+    // checkpoint is taken before mapping entries are read or a diff is taken, which both change
+    // the dumped storage
+    {
+        let state = DUMP_STATE.lock().unwrap();
+        if state.transactions.iter().all(|tx| tx.indexed) {
+            Checkpoint::delete(&_args);
+        } else {
+            Checkpoint::new(&state).save(&_args);
+        }
+    }
+
+    // read the mapping entries found in the event history which no indexed transaction modified
+    if _args.enumerate_mappings {
+        let slots = {
+            let state = DUMP_STATE.lock().unwrap();
+            mapping_slots(&state.layout, &state.event_keys, &state.event_key_pairs)
+                .into_iter()
+                .filter(|slot| !state.storage.contains_key(slot))
+                .collect::<Vec<_>>()
+        };
+
+        let values = get_storage_values(&slots, _args.to_block, &_args).await?;
+        let mut state = DUMP_STATE.lock().unwrap();
+        let mut found = 0;
+        for (slot, value) in slots.into_iter().zip(values) {
+            if !value.is_zero() {
+                state.storage.insert(
+                    slot,
+                    StorageSlot {
+                        alias: None,
+                        value,
+                        modifiers: Vec::new(),
+                        decode_as_type_index: 0,
+                    },
+                );
+                found += 1;
             }
         }
+        logger.info(&format!("found {found} mapping entries from the event history."));
+    }
+
+    // compare the values of the modified slots at either end of the block range, keeping only
+    // those which changed and the transactions within the range which modified them
+    if _args.diff {
+        let slots = DUMP_STATE.lock().unwrap().storage.keys().cloned().collect::<Vec<_>>();
+        let old_values = match _args.from_block {
+            0 => vec![H256::zero(); slots.len()],
+            from_block => get_storage_values(&slots, from_block - 1, &_args).await?,
+        };
+        let new_values = get_storage_values(&slots, _args.to_block, &_args).await?;
+
+        let mut state = DUMP_STATE.lock().unwrap();
+        for ((slot, old_value), new_value) in slots.into_iter().zip(old_values).zip(new_values) {
+            match old_value == new_value {
+                true => {
+                    state.storage.remove(&slot);
+                }
+                false => {
+                    if let Some(storage_slot) = state.storage.get_mut(&slot) {
+                        storage_slot.value = new_value;
+                        storage_slot.modifiers.retain(|(block, _)| {
+                            (_args.from_block..=_args.to_block).contains(block)
+                        });
+                    }
+                    state.previous_values.insert(slot, old_value);
+                }
+            }
+        }
+        logger.info(&format!(
+            "{} storage values changed between blocks {} and {} .",
+            state.storage.len(),
+            _args.from_block,
+            _args.to_block
+        ));
+    }
+
+    // prove each slot's value against the state root, so the dump can be checked independently
+    let slots = DUMP_STATE.lock().unwrap().storage.keys().cloned().collect::<Vec<_>>();
+    if _args.verify && !slots.is_empty() {
+        let (block_number, state_root, response) =
+            get_storage_proofs(&slots, _args.to_block, &_args).await?;
+
+        // the account's storage root must be proven before any of its slots can be
+        let account_verified = verify_account_proof(state_root, addr_hash, &response.account_proof)
+            .map_err(|e| logger.warn(&format!("the account proof is invalid: {e}")))
+            .is_ok_and(|storage_root| storage_root == response.storage_hash);
+        let account = Arc::new(AccountProof {
+            block_number,
+            state_root,
+            storage_hash: response.storage_hash,
+            proof: response.account_proof,
+        });
+
+        let mut state = DUMP_STATE.lock().unwrap();
+        for storage_proof in response.storage_proof {
+            let value =
+                verify_storage_proof(account.storage_hash, storage_proof.key, &storage_proof.proof)
+                    .map_err(|e| {
+                        logger.warn(&format!(
+                            "the proof of slot '{:?}' is invalid: {e}",
+                            storage_proof.key
+                        ))
+                    });
+            let verified = account_verified &&
+                value.is_ok_and(|value| {
+                    state.storage.get(&storage_proof.key).is_some_and(|slot| slot.value == value)
+                });
+            state.proofs.insert(
+                storage_proof.key,
+                SlotProof {
+                    account: account.clone(),
+                    value: H256::from(<[u8; 32]>::from(storage_proof.value)),
+                    proof: storage_proof.proof,
+                    verified,
+                },
+            );
+        }
+
+        let num_verified = state.proofs.values().filter(|proof| proof.verified).count();
+        logger.info(&format!(
+            "verified {} of {} storage values against the state root of block {} .",
+            num_verified,
+            state.storage.len(),
+            block_number
+        ));
+        if num_verified < state.storage.len() {
+            logger.warn("some storage values couldn't be verified. the dump may be incomplete, or the RPC provider's proofs may be invalid.");
+        }
     }
 
     // write storage slots to csv
@@ -0,0 +1,138 @@
+use heimdall_common::utils::io::{
+    parquet::{ColumnData, ParquetTable},
+    sqlite::{Database, SqlValue, Table},
+};
+
+use super::util::csv::DumpRow;
+
+/// Build a SQLite database of the dumped storage, with a `slots` table holding each slot's
+/// decoded value and the block it was last modified in, and a `modifications` table holding every
+/// transaction which modified each slot. Both tables are indexed by slot and block, so large dumps
+/// can be queried directly.
+pub fn build_sqlite(rows: &[DumpRow]) -> Database {
+    let text = |value: &str| SqlValue::Text(value.to_string());
+    let mut database = Database::new();
+
+    database.add_table(Table {
+        name: String::from("slots"),
+        columns: [
+            ("slot", "TEXT"),
+            ("alias", "TEXT"),
+            ("decoded_type", "TEXT"),
+            ("value", "TEXT"),
+            ("last_modified", "INTEGER"),
+        ]
+        .iter()
+        .map(|(name, kind)| (name.to_string(), kind.to_string()))
+        .collect(),
+        rows: rows
+            .iter()
+            .map(|row| {
+                vec![
+                    text(&row.slot),
+                    text(&row.alias),
+                    text(&row.decoded_type),
+                    text(&row.value),
+                    row.last_modified
+                        .parse::<i64>()
+                        .map(SqlValue::Integer)
+                        .unwrap_or(SqlValue::Null),
+                ]
+            })
+            .collect(),
+        indexes: vec![
+            (String::from("slots_slot"), vec![String::from("slot")]),
+            (String::from("slots_last_modified"), vec![String::from("last_modified")]),
+        ],
+    });
+
+    database.add_table(Table {
+        name: String::from("modifications"),
+        columns: [("slot", "TEXT"), ("block", "INTEGER"), ("transaction_hash", "TEXT")]
+            .iter()
+            .map(|(name, kind)| (name.to_string(), kind.to_string()))
+            .collect(),
+        rows: rows
+            .iter()
+            .flat_map(|row| {
+                row.modifiers.iter().map(|(block, transaction_hash)| {
+                    vec![text(&row.slot), SqlValue::Integer(*block as i64), text(transaction_hash)]
+                })
+            })
+            .collect(),
+        indexes: vec![
+            (String::from("modifications_slot"), vec![String::from("slot")]),
+            (String::from("modifications_block"), vec![String::from("block")]),
+        ],
+    });
+
+    database
+}
+
+/// Build a Parquet table of the dumped storage, with each slot's decoded value and the block and
+/// transaction it was last modified in.
+pub fn build_parquet(rows: &[DumpRow]) -> ParquetTable {
+    let last_modifiers = rows
+        .iter()
+        .map(|row| {
+            row.modifiers.iter().max_by_key(|(block, _)| *block).cloned().unwrap_or_default()
+        })
+        .collect::<Vec<(u128, String)>>();
+
+    let mut table = ParquetTable::new();
+    table.add_column("slot", ColumnData::Text(rows.iter().map(|row| row.slot.clone()).collect()));
+    table.add_column("value", ColumnData::Text(rows.iter().map(|row| row.value.clone()).collect()));
+    table.add_column(
+        "block",
+        ColumnData::Int64(last_modifiers.iter().map(|(block, _)| *block as i64).collect()),
+    );
+    table.add_column(
+        "transaction_hash",
+        ColumnData::Text(last_modifiers.into_iter().map(|(_, hash)| hash).collect()),
+    );
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use heimdall_common::utils::io::{parquet::ColumnData, sqlite::SqlValue};
+
+    use crate::dump::{
+        output::{build_parquet, build_sqlite},
+        util::csv::DumpRow,
+    };
+
+    fn rows() -> Vec<DumpRow> {
+        vec![DumpRow {
+            last_modified: String::from("17000001"),
+            alias: String::from("None"),
+            slot: "00".repeat(32),
+            decoded_type: String::from("uint256"),
+            value: String::from("1"),
+            modifiers: vec![(17000000, String::from("0xaa")), (17000001, String::from("0xbb"))],
+            decoded: None,
+            previous_value: None,
+            proof: None,
+        }]
+    }
+
+    #[test]
+    fn test_build_sqlite() {
+        let database = build_sqlite(&rows());
+
+        assert_eq!(database.tables[0].rows[0][4], SqlValue::Integer(17000001));
+        assert_eq!(database.tables[1].rows.len(), 2);
+        assert_eq!(database.tables[1].rows[1][2], SqlValue::Text(String::from("0xbb")));
+        let path = std::env::temp_dir().join("heimdall_test_build_sqlite.db");
+        assert!(database.write(path.to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_build_parquet() {
+        let table = build_parquet(&rows());
+
+        assert_eq!(table.columns[2].1, ColumnData::Int64(vec![17000001]));
+        assert_eq!(table.columns[3].1, ColumnData::Text(vec![String::from("0xbb")]));
+        assert!(table.write_to(Vec::new()).is_ok());
+    }
+}
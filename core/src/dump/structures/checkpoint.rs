@@ -0,0 +1,111 @@
+use std::collections::{HashMap, HashSet};
+
+use ethers::types::{H160, H256};
+use heimdall_cache::{delete_cache, read_cache, store_cache};
+use serde::{Deserialize, Serialize};
+
+use crate::dump::DumpArgs;
+
+use super::{dump_state::DumpState, storage_slot::StorageSlot};
+
+/// The progress of a dump, which is saved as transactions are indexed so an interrupted dump can
+/// be resumed with `--resume` rather than indexing every transaction again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    // the block range being dumped, which must match for the checkpoint to be resumed
+    pub from_block: u128,
+    pub to_block: u128,
+
+    // the hashes of the transactions which have been indexed
+    pub indexed: HashSet<String>,
+    pub storage: HashMap<H256, StorageSlot>,
+
+    // the accounts touched by the indexed transactions
+    pub accounts: HashSet<H160>,
+}
+
+impl Checkpoint {
+    /// Take a checkpoint of the given dump's progress.
+    pub fn new(state: &DumpState) -> Self {
+        Self {
+            from_block: state.args.from_block,
+            to_block: state.args.to_block,
+            indexed: state
+                .transactions
+                .iter()
+                .filter(|tx| tx.indexed)
+                .map(|tx| tx.hash.clone())
+                .collect(),
+            storage: state.storage.clone(),
+            accounts: state.accounts.clone(),
+        }
+    }
+
+    /// the cache key of the checkpoint for the given dump
+    fn key(args: &DumpArgs) -> String {
+        format!("dump.checkpoint.{}.{}", args.chain, args.target.to_lowercase())
+    }
+
+    /// Save the checkpoint, replacing any earlier checkpoint of the same dump.
+    pub fn save(&self, args: &DumpArgs) {
+        store_cache(&Self::key(args), self, None);
+    }
+
+    /// Load the checkpoint of the given dump, if it was interrupted while dumping the same block
+    /// range.
+    pub fn load(args: &DumpArgs) -> Option<Self> {
+        read_cache::<Self>(&Self::key(args)).filter(|checkpoint| {
+            checkpoint.from_block == args.from_block && checkpoint.to_block == args.to_block
+        })
+    }
+
+    /// Delete the checkpoint of the given dump, once it has finished.
+    pub fn delete(args: &DumpArgs) {
+        delete_cache(&Self::key(args));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::types::H256;
+
+    use crate::dump::structures::{
+        checkpoint::Checkpoint, dump_state::DumpState, storage_slot::StorageSlot,
+        transaction::Transaction,
+    };
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let mut state = DumpState::new();
+        state.args.target = String::from("0xCHECKPOINT_TEST");
+        state.transactions = vec![
+            Transaction { indexed: true, hash: String::from("0xaa"), block_number: 1 },
+            Transaction { indexed: false, hash: String::from("0xbb"), block_number: 2 },
+        ];
+        state.storage = HashMap::from([(
+            H256::zero(),
+            StorageSlot {
+                alias: None,
+                value: H256::repeat_byte(1),
+                modifiers: vec![(1, String::from("0xaa"))],
+                decode_as_type_index: 0,
+            },
+        )]);
+        Checkpoint::new(&state).save(&state.args);
+
+        let checkpoint = Checkpoint::load(&state.args).unwrap();
+        assert_eq!(checkpoint.indexed.len(), 1);
+        assert!(checkpoint.indexed.contains("0xaa"));
+        assert_eq!(checkpoint.storage[&H256::zero()].value, H256::repeat_byte(1));
+
+        // checkpoints of a different block range aren't resumed
+        let mut args = state.args.clone();
+        args.from_block = 100;
+        assert!(Checkpoint::load(&args).is_none());
+
+        Checkpoint::delete(&state.args);
+        assert!(Checkpoint::load(&state.args).is_none());
+    }
+}
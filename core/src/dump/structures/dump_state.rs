@@ -1,10 +1,17 @@
-use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
-use ethers::types::H256;
+use ethers::types::{H160, H256};
+use heimdall_common::utils::failure::Failure;
 
-use crate::dump::{menus::TUIView, DumpArgs};
+use crate::{
+    decompile::out::storage::StorageVariable,
+    dump::{menus::TUIView, DumpArgs},
+};
 
-use super::{storage_slot::StorageSlot, transaction::Transaction};
+use super::{proof::SlotProof, storage_slot::StorageSlot, transaction::Transaction};
 
 /// The state of the Dump process, which will be updated as the process continues.
 /// This struct is also used to store the state of the TUI, and is often passed to the TUI renderer
@@ -20,6 +27,29 @@ pub struct DumpState {
     pub start_time: Instant,
     pub input_buffer: String,
     pub filter: String,
+
+    // the accounts touched by the indexed transactions, which are candidate mapping keys
+    pub accounts: HashSet<H160>,
+
+    // the storage layout recovered by the decompiler, if the dump is being decoded with one
+    pub layout: Vec<StorageVariable>,
+
+    // the mapping keys found in the target's event history, and the pairs of keys nested mappings
+    // are indexed by
+    pub event_keys: HashSet<H256>,
+    pub event_key_pairs: HashSet<(H256, H256)>,
+
+    // the value of each changed slot at the start of the block range, if the dump is a diff
+    pub previous_values: HashMap<H256, H256>,
+
+    // the proof of each slot's value, if the dump was verified with `eth_getProof`
+    pub proofs: HashMap<H256, SlotProof>,
+
+    // the last block whose transactions were dumped
+    pub last_block: u128,
+
+    // why indexing stopped, if it failed, which also closes the TUI
+    pub failure: Option<Failure>,
 }
 
 impl DumpState {
@@ -32,10 +62,19 @@ impl DumpState {
                 rpc_url: String::new(),
                 transpose_api_key: String::new(),
                 threads: 4,
+                requests_per_second: 0.0,
+                chunk_size: 25,
                 from_block: 0,
                 to_block: 9999999999,
                 no_tui: false,
                 chain: String::from("ethereum"),
+                output_format: String::from("csv"),
+                resume: false,
+                decode_layout: false,
+                enumerate_mappings: false,
+                diff: false,
+                verify: false,
+                watch: false,
             },
             scroll_index: 0,
             selection_size: 1,
@@ -45,6 +84,14 @@ impl DumpState {
             start_time: Instant::now(),
             input_buffer: String::new(),
             filter: String::new(),
+            accounts: HashSet::new(),
+            layout: Vec::new(),
+            event_keys: HashSet::new(),
+            event_key_pairs: HashSet::new(),
+            previous_values: HashMap::new(),
+            proofs: HashMap::new(),
+            last_block: 9999999999,
+            failure: None,
         }
     }
 }
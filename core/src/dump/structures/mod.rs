@@ -1,3 +1,5 @@
+pub mod checkpoint;
 pub mod dump_state;
+pub mod proof;
 pub mod storage_slot;
 pub mod transaction;
@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use ethers::types::{Bytes, H256};
+use serde::Serialize;
+
+/// The proof of the target's account at the block the dump was verified at, which ties the root
+/// of its storage trie to the block's state root.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountProof {
+    pub block_number: u128,
+    pub state_root: H256,
+    pub storage_hash: H256,
+    pub proof: Vec<Bytes>,
+}
+
+/// The proof of a dumped slot's value, which anyone can check against the state root of the block
+/// it was taken at.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotProof {
+    // the proof of the target's account, which is shared by every slot
+    pub account: Arc<AccountProof>,
+
+    // the value the proof shows the slot holds, and the proof itself
+    pub value: H256,
+    pub proof: Vec<Bytes>,
+
+    // whether the proof is valid, and shows the same value as the dump
+    pub verified: bool,
+}
@@ -1,7 +1,8 @@
 use ethers::types::H256;
+use serde::{Deserialize, Serialize};
 
 /// A storage slot, which contains the alias, value, and modifiers of a storage slot.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageSlot {
     pub alias: Option<String>,
     pub value: H256,
@@ -1,64 +1,108 @@
+use std::collections::HashMap;
+
 use ethers::{
     abi::{decode, ParamType},
-    types::U256,
+    types::{H256, U256},
 };
 use heimdall_common::utils::{
     io::file::write_lines_to_file,
     strings::{encode_hex, hex_to_ascii},
 };
+use serde::Serialize;
 
-use crate::dump::{constants::DECODE_AS_TYPES, structures::dump_state::DumpState};
+use crate::dump::{
+    constants::DECODE_AS_TYPES,
+    structures::{dump_state::DumpState, proof::SlotProof},
+    util::layout::{candidate_keys, decode_storage, DecodedSlot},
+};
 
 /// A single row in the CSV
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct DumpRow {
     pub last_modified: String,
     pub alias: String,
     pub slot: String,
     pub decoded_type: String,
     pub value: String,
+
+    // the block number and transaction hash of every transaction which modified the slot
+    pub modifiers: Vec<(u128, String)>,
+
+    // the variable the slot belongs to, if the dump was decoded with a recovered storage layout
+    pub decoded: Option<DecodedSlot>,
+
+    // the slot's value at the start of the block range, if the dump is a diff between two blocks
+    pub previous_value: Option<String>,
+
+    // the proof of the slot's value, if the dump was verified with `eth_getProof`
+    pub proof: Option<SlotProof>,
+}
+
+/// decodes a stored value as the type at the given index of [`DECODE_AS_TYPES`]
+fn decode_value(value: &H256, decode_as_type_index: usize) -> String {
+    match decode_as_type_index {
+        0 => format!("0x{}", encode_hex(value.to_fixed_bytes().into())),
+        1 => format!("{}", !value.is_zero()),
+        2 => format!("0x{}", encode_hex(value.to_fixed_bytes().into()).get(24..).unwrap_or("")),
+        3 => match decode(&[ParamType::String], value.as_bytes()) {
+            Ok(decoded) => decoded[0].to_string(),
+            Err(_) => hex_to_ascii(&encode_hex(value.to_fixed_bytes().into())),
+        },
+        4 => {
+            let decoded = U256::from_big_endian(&value.to_fixed_bytes());
+            format!("{decoded}")
+        }
+        _ => "decoding error".to_string(),
+    }
 }
 
 /// Convert [`DumpState`] to a Vec of [`DumpRow`]s, which can be used to build a CSV.
 pub fn build_csv(state: &DumpState) -> Vec<DumpRow> {
     let mut lines: Vec<DumpRow> = Vec::new();
+    let mut decoded = match state.layout.is_empty() {
+        true => HashMap::new(),
+        false => decode_storage(
+            &state.storage,
+            &state.layout,
+            &candidate_keys(state),
+            &state.event_key_pairs,
+        ),
+    };
 
     // sort by key ascending
     let mut storage_iter = state.storage.iter().collect::<Vec<_>>();
     storage_iter.sort_by_key(|(slot, _)| *slot);
 
     for (slot, value) in storage_iter {
-        let decoded_value = match value.decode_as_type_index {
-            0 => format!("0x{}", encode_hex(value.value.to_fixed_bytes().into())),
-            1 => format!("{}", !value.value.is_zero()),
-            2 => format!(
-                "0x{}",
-                encode_hex(value.value.to_fixed_bytes().into()).get(24..).unwrap_or("")
-            ),
-            3 => match decode(&[ParamType::String], value.value.as_bytes()) {
-                Ok(decoded) => decoded[0].to_string(),
-                Err(_) => hex_to_ascii(&encode_hex(value.value.to_fixed_bytes().into())),
-            },
-            4 => {
-                let decoded = U256::from_big_endian(&value.value.to_fixed_bytes());
-                format!("{decoded}")
-            }
-            _ => "decoding error".to_string(),
-        };
-
         lines.push(DumpRow {
-            last_modified: value.modifiers.iter().max_by_key(|m| m.0).unwrap().0.to_string(),
+            last_modified: value
+                .modifiers
+                .iter()
+                .max_by_key(|m| m.0)
+                .map(|m| m.0.to_string())
+                .unwrap_or_default(),
             alias: value.alias.as_ref().unwrap_or(&String::from("None")).to_string(),
             slot: encode_hex(slot.to_fixed_bytes().into()),
             decoded_type: DECODE_AS_TYPES[value.decode_as_type_index].to_string(),
-            value: decoded_value,
+            value: decode_value(&value.value, value.decode_as_type_index),
+            modifiers: value.modifiers.clone(),
+            decoded: decoded.remove(slot),
+            previous_value: state
+                .previous_values
+                .get(slot)
+                .map(|previous| decode_value(previous, value.decode_as_type_index)),
+            proof: state.proofs.get(slot).cloned(),
         })
     }
     lines
 }
 
 /// Write the storage to a CSV file.
-pub fn write_storage_to_csv(output_dir: &str, file_name: &str, state: &DumpState) {
+pub fn write_storage_to_csv(
+    output_dir: &str,
+    file_name: &str,
+    state: &DumpState,
+) -> Result<(), std::io::Error> {
     let mut csv_rows = build_csv(state);
     let mut lines: Vec<String> = Vec::new();
 
@@ -77,5 +121,37 @@ pub fn write_storage_to_csv(output_dir: &str, file_name: &str, state: &DumpState
     }
 
     // write to file
-    write_lines_to_file(&format!("{}/{}", output_dir, file_name), lines);
+    write_lines_to_file(&format!("{}/{}", output_dir, file_name), lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::types::H256;
+
+    use crate::dump::{
+        structures::{dump_state::DumpState, storage_slot::StorageSlot},
+        util::csv::build_csv,
+    };
+
+    #[test]
+    fn test_build_csv_diff() {
+        let mut state = DumpState::new();
+        state.storage = HashMap::from([(
+            H256::zero(),
+            StorageSlot {
+                alias: None,
+                value: H256::from_low_u64_be(2),
+                modifiers: vec![(10, String::from("0xaa"))],
+                decode_as_type_index: 4,
+            },
+        )]);
+        state.previous_values = HashMap::from([(H256::zero(), H256::from_low_u64_be(1))]);
+
+        let rows = build_csv(&state);
+        assert_eq!(rows[0].value, "2");
+        assert_eq!(rows[0].previous_value.as_deref(), Some("1"));
+        assert_eq!(rows[0].last_modified, "10");
+    }
 }
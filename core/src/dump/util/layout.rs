@@ -0,0 +1,340 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use ethers::{
+    types::{H160, H256, U256},
+    utils::keccak256,
+};
+use heimdall_common::utils::strings::encode_hex;
+use serde::Serialize;
+
+use crate::{
+    decompile::out::storage::StorageVariable,
+    dump::{
+        constants::{APPROVAL_EVENT, APPROVAL_FOR_ALL_EVENT},
+        structures::{dump_state::DumpState, storage_slot::StorageSlot},
+    },
+};
+
+// nested mappings are only enumerated when there are few enough candidate keys, since every pair
+// of keys is tried
+const MAX_NESTED_KEYS: usize = 2000;
+
+/// A dumped storage slot, labeled with the variable it belongs to in the layout recovered by the
+/// decompiler.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecodedSlot {
+    // the variable the slot holds, and for mappings the keys of the entry, such as
+    // `stor_map_b[0x6b17...]`
+    pub variable: String,
+    pub value_type: String,
+    pub value: String,
+}
+
+/// Get the keys which mapping entries are looked up by, since a mapping slot is a hash of its key.
+/// These are the accounts touched by the indexed transactions, the keys found in the contract's
+/// event history, the values stored by the contract, and small integers, which cover most
+/// balances, allowances, and indexed ownerships.
+pub fn candidate_keys(state: &DumpState) -> Vec<H256> {
+    let mut keys = state.event_keys.clone();
+    keys.extend(state.accounts.iter().map(|account| H256::from(*account)));
+    for slot in state.storage.values() {
+        keys.insert(slot.value);
+        keys.insert(H256::from(H160::from(slot.value)));
+    }
+    keys.extend((0..=255u64).map(H256::from_low_u64_be));
+
+    let mut keys = keys.into_iter().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Get the mapping keys in the topics of the given `Transfer`, `Approval`, and `ApprovalForAll`
+/// events: every indexed address or token id, and the pairs of owner and spender which allowances
+/// and operator approvals are keyed by.
+pub fn event_keys(events: &[Vec<String>]) -> (HashSet<H256>, HashSet<(H256, H256)>) {
+    let mut keys = HashSet::new();
+    let mut pairs = HashSet::new();
+    for topics in events {
+        let indexed = topics
+            .iter()
+            .skip(1)
+            .filter_map(|topic| H256::from_str(topic).ok())
+            .collect::<Vec<_>>();
+        keys.extend(&indexed);
+
+        if let (Some(signature), [owner, spender, ..]) = (topics.first(), indexed.as_slice()) {
+            if [APPROVAL_EVENT, APPROVAL_FOR_ALL_EVENT].contains(&signature.as_str()) {
+                pairs.insert((*owner, *spender));
+            }
+        }
+    }
+    (keys, pairs)
+}
+
+/// Get the slots of the entries with the given keys in each of the layout's mappings, using
+/// single keys for mappings and pairs of keys for nested mappings.
+pub fn mapping_slots(
+    layout: &[StorageVariable],
+    keys: &HashSet<H256>,
+    pairs: &HashSet<(H256, H256)>,
+) -> HashSet<H256> {
+    let mut slots = HashSet::new();
+    for variable in layout {
+        let entries = match variable.depth {
+            1 => keys.iter().map(|key| mapping_slot(key, variable.slot)).collect::<Vec<_>>(),
+            2 => pairs
+                .iter()
+                .map(|(outer, inner)| mapping_slot(inner, mapping_slot(outer, variable.slot)))
+                .collect(),
+            _ => continue,
+        };
+        for entry in entries {
+            for offset in &variable.offsets {
+                let (slot, _) = entry.overflowing_add(U256::from(*offset));
+                slots.insert(H256::from(<[u8; 32]>::from(slot)));
+            }
+        }
+    }
+    slots
+}
+
+/// Label each dumped slot with the variable it belongs to in the given layout, computing the
+/// slots of mapping entries from the candidate keys. Slots which don't belong to any variable, or
+/// whose keys aren't candidates, aren't decoded. Nested mappings are decoded with the given pairs
+/// of keys, and with every pair of candidate keys if there are few enough.
+pub fn decode_storage(
+    storage: &HashMap<H256, StorageSlot>,
+    layout: &[StorageVariable],
+    keys: &[H256],
+    pairs: &HashSet<(H256, H256)>,
+) -> HashMap<H256, DecodedSlot> {
+    let mut decoded = HashMap::new();
+    let mut label = |slot: U256, offset: u64, variable: String, value_type: &str| {
+        let (slot, _) = slot.overflowing_add(U256::from(offset));
+        let slot = H256::from(<[u8; 32]>::from(slot));
+        if let Some(value) = storage.get(&slot) {
+            let variable = match offset {
+                0 => variable,
+                _ => format!("{variable}+{offset}"),
+            };
+            decoded.insert(
+                slot,
+                DecodedSlot {
+                    variable,
+                    value_type: value_type.to_string(),
+                    value: format_value(&value.value, value_type),
+                },
+            );
+        }
+    };
+
+    for variable in layout {
+        match variable.depth {
+            0 => label(variable.slot, 0, variable.name.clone(), &variable.value_type),
+            1 => {
+                for key in keys {
+                    let slot = mapping_slot(key, variable.slot);
+                    for offset in &variable.offsets {
+                        label(
+                            slot,
+                            *offset,
+                            format!("{}[{}]", variable.name, format_key(key)),
+                            &variable.value_type,
+                        );
+                    }
+                }
+            }
+            2 => {
+                let mut label_entry = |outer: &H256, inner: &H256| {
+                    let slot = mapping_slot(inner, mapping_slot(outer, variable.slot));
+                    for offset in &variable.offsets {
+                        label(
+                            slot,
+                            *offset,
+                            format!(
+                                "{}[{}][{}]",
+                                variable.name,
+                                format_key(outer),
+                                format_key(inner)
+                            ),
+                            &variable.value_type,
+                        );
+                    }
+                };
+
+                for (outer, inner) in pairs {
+                    label_entry(outer, inner);
+                }
+                if keys.len() <= MAX_NESTED_KEYS {
+                    for outer in keys {
+                        for inner in keys {
+                            label_entry(outer, inner);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    decoded
+}
+
+/// the slot of the entry with the given key in a mapping declared at the given slot
+fn mapping_slot(key: &H256, slot: U256) -> U256 {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(key.as_bytes());
+    slot.to_big_endian(&mut preimage[32..]);
+    U256::from_big_endian(&keccak256(preimage))
+}
+
+/// formats a mapping key as an integer or address if it looks like one, or as hex otherwise
+fn format_key(key: &H256) -> String {
+    let value = U256::from_big_endian(key.as_bytes());
+    if value.bits() <= 32 {
+        value.to_string()
+    } else if value.bits() <= 160 {
+        format!("{:?}", H160::from(*key))
+    } else {
+        format!("0x{}", encode_hex(key.as_bytes().to_vec()))
+    }
+}
+
+/// formats a stored value as the given type
+fn format_value(value: &H256, value_type: &str) -> String {
+    match value_type {
+        "bool" => (!value.is_zero()).to_string(),
+        "address" => format!("{:?}", H160::from(*value)),
+        _ => U256::from_big_endian(value.as_bytes()).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap, HashSet};
+
+    use ethers::{
+        types::{H160, H256, U256},
+        utils::keccak256,
+    };
+
+    use crate::{
+        decompile::out::storage::StorageVariable,
+        dump::{
+            constants::{APPROVAL_EVENT, APPROVAL_FOR_ALL_EVENT, TRANSFER_EVENT},
+            structures::storage_slot::StorageSlot,
+            util::layout::{decode_storage, event_keys, format_key, mapping_slot, mapping_slots},
+        },
+    };
+
+    fn variable(name: &str, slot: u64, depth: usize, value_type: &str) -> StorageVariable {
+        StorageVariable {
+            name: name.to_string(),
+            slot: U256::from(slot),
+            depth,
+            offsets: BTreeSet::from([0]),
+            value_type: value_type.to_string(),
+        }
+    }
+
+    fn storage_slot(value: H256) -> StorageSlot {
+        StorageSlot { alias: None, value, modifiers: Vec::new(), decode_as_type_index: 0 }
+    }
+
+    #[test]
+    fn test_decode_storage() {
+        let owner = H256::from(H160::repeat_byte(0xaa));
+        let balance_slot = H256::from(<[u8; 32]>::from(mapping_slot(&owner, U256::one())));
+        let storage = HashMap::from([
+            (H256::zero(), storage_slot(owner)),
+            (balance_slot, storage_slot(H256::from_low_u64_be(100))),
+            (H256::repeat_byte(0xff), storage_slot(H256::from_low_u64_be(1))),
+        ]);
+
+        let decoded = decode_storage(
+            &storage,
+            &[variable("stor_a", 0, 0, "address"), variable("stor_map_b", 1, 1, "uint256")],
+            &[H256::from_low_u64_be(1), owner],
+            &HashSet::new(),
+        );
+
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[&H256::zero()].variable, "stor_a");
+        assert_eq!(decoded[&H256::zero()].value, format!("{:?}", H160::repeat_byte(0xaa)));
+        assert_eq!(
+            decoded[&balance_slot].variable,
+            format!("stor_map_b[{:?}]", H160::repeat_byte(0xaa))
+        );
+        assert_eq!(decoded[&balance_slot].value, "100");
+    }
+
+    #[test]
+    fn test_format_key() {
+        assert_eq!(format_key(&H256::from_low_u64_be(42)), "42");
+        assert_eq!(
+            format_key(&H256::from(H160::repeat_byte(0x11))),
+            "0x1111111111111111111111111111111111111111"
+        );
+        assert_eq!(format_key(&H256::repeat_byte(0xff)), format!("0x{}", "ff".repeat(32)));
+    }
+
+    #[test]
+    fn test_event_keys() {
+        let owner = format!("{:?}", H256::from(H160::repeat_byte(0xaa)));
+        let spender = format!("{:?}", H256::from(H160::repeat_byte(0xbb)));
+        let (keys, pairs) = event_keys(&[
+            vec![String::from(TRANSFER_EVENT), owner.clone(), spender.clone()],
+            vec![String::from(APPROVAL_EVENT), owner.clone(), spender.clone()],
+        ]);
+
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            pairs,
+            HashSet::from([(
+                H256::from(H160::repeat_byte(0xaa)),
+                H256::from(H160::repeat_byte(0xbb))
+            )])
+        );
+
+        // the signatures are the hashes of the events
+        assert_eq!(
+            format!("{:?}", H256::from(keccak256("Transfer(address,address,uint256)"))),
+            TRANSFER_EVENT
+        );
+        assert_eq!(
+            format!("{:?}", H256::from(keccak256("Approval(address,address,uint256)"))),
+            APPROVAL_EVENT
+        );
+        assert_eq!(
+            format!("{:?}", H256::from(keccak256("ApprovalForAll(address,address,bool)"))),
+            APPROVAL_FOR_ALL_EVENT
+        );
+    }
+
+    #[test]
+    fn test_mapping_slots() {
+        let owner = H256::from(H160::repeat_byte(0xaa));
+        let spender = H256::from(H160::repeat_byte(0xbb));
+        let slots = mapping_slots(
+            &[
+                variable("stor_a", 0, 0, "uint256"),
+                variable("stor_map_b", 1, 1, "uint256"),
+                variable("stor_map_c", 2, 2, "uint256"),
+            ],
+            &HashSet::from([owner]),
+            &HashSet::from([(owner, spender)]),
+        );
+
+        let allowance_slot = mapping_slot(&spender, mapping_slot(&owner, U256::from(2)));
+        assert_eq!(
+            slots,
+            HashSet::from([
+                H256::from(<[u8; 32]>::from(mapping_slot(&owner, U256::one()))),
+                H256::from(<[u8; 32]>::from(allowance_slot)),
+            ])
+        );
+    }
+}
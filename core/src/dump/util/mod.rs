@@ -1,4 +1,5 @@
 pub mod csv;
+pub mod layout;
 pub mod table;
 pub mod threads;
 
@@ -9,15 +10,27 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, LeaveAlternateScreen},
 };
-use ethers::{
-    providers::{Http, Middleware, Provider},
-    types::{StateDiff, TraceType, H256},
-};
+use ethers::types::{BlockTrace, EIP1186ProofResponse, StateDiff, H256};
 use heimdall_cache::{read_cache, store_cache};
-use heimdall_common::utils::io::logging::Logger;
+use heimdall_common::{
+    ether::{
+        history::get_block_number,
+        pool::RpcPool,
+        rpc::{batch_request, rpc_cache_enabled},
+    },
+    utils::{
+        failure::{Failure, FailureClass},
+        io::logging::Logger,
+    },
+};
+use serde_json::json;
 use tui::{backend::CrosstermBackend, Terminal};
 
 use super::{structures::transaction::Transaction, DumpArgs};
+use crate::error::Error;
+
+// the most slots whose proofs are fetched in a single `eth_getProof` request
+const PROOF_CHUNK_SIZE: usize = 100;
 
 /// cleanup the terminal, disable raw mode, and leave the alternate screen
 pub fn cleanup_terminal() {
@@ -29,8 +42,12 @@ pub fn cleanup_terminal() {
     terminal.show_cursor().unwrap();
 }
 
-/// get the state diff for the given transaction
-pub async fn get_storage_diff(tx: &Transaction, args: &DumpArgs) -> Option<StateDiff> {
+/// get the state diff for each of the given transactions, fetching the ones which aren't cached in
+/// a single batch of requests
+pub async fn get_storage_diffs(
+    txs: &[Transaction],
+    args: &DumpArgs,
+) -> Result<Vec<Option<StateDiff>>, Failure> {
     // create new logger
     let (logger, _) = Logger::new(match args.verbose.log_level() {
         Some(level) => level.as_str(),
@@ -38,64 +55,137 @@ pub async fn get_storage_diff(tx: &Transaction, args: &DumpArgs) -> Option<State
     });
 
     // get chain_id
-    let chain_id = heimdall_common::ether::rpc::chain_id(&args.rpc_url).await.unwrap();
+    let chain_id = heimdall_common::ether::rpc::chain_id(&args.rpc_url).await.unwrap_or(1);
 
-    // check the cache for a matching address
-    if let Some(state_diff) = read_cache(&format!("diff.{}.{}", &chain_id, &tx.hash)) {
-        logger.debug_max(&format!("found cached state diff for transaction '{}' .", &tx.hash));
-        return state_diff
-    }
+    // check the cache for each transaction
+    let mut state_diffs: Vec<Option<Option<StateDiff>>> = txs
+        .iter()
+        .map(|tx| {
+            let state_diff = read_cache(&format!("diff.{}.{}", &chain_id, &tx.hash))
+                .filter(|_| rpc_cache_enabled());
+            if state_diff.is_some() {
+                logger.debug_max(&format!(
+                    "found cached state diff for transaction '{}' .",
+                    &tx.hash
+                ));
+            }
+            state_diff
+        })
+        .collect();
 
     // make sure the RPC provider isn't empty
-    if args.rpc_url.is_empty() {
-        cleanup_terminal();
-        logger.error("fetching an on-chain transaction requires an RPC provider. Use `heimdall dump --help` for more information.");
-        std::process::exit(1);
+    if RpcPool::new(&args.rpc_url).is_empty() {
+        return Err(Failure::new(FailureClass::InvalidInput, "fetching an on-chain transaction requires an RPC provider. Use `heimdall dump --help` for more information."))
     }
 
-    // create new provider
-    let provider = match Provider::<Http>::try_from(&args.rpc_url) {
-        Ok(provider) => provider,
-        Err(_) => {
-            cleanup_terminal();
-            logger.error(&format!("failed to connect to RPC provider '{}' .", &args.rpc_url));
-            std::process::exit(1)
+    // fetch the state diffs of the remaining transactions
+    let missing = (0..txs.len()).filter(|i| state_diffs[*i].is_none()).collect::<Vec<usize>>();
+    let requests = missing
+        .iter()
+        .map(|i| ("trace_replayTransaction", json!([txs[*i].hash, ["stateDiff"]])))
+        .collect::<Vec<_>>();
+    let results = match batch_request(&args.rpc_url, &requests).await {
+        Ok(results) => results,
+        Err(e) => {
+            return Err(Failure::new(
+                FailureClass::Rpc,
+                format!(
+                "failed to replay and trace transactions: {e}. does your RPC provider support it?"
+            ),
+            ))
         }
     };
 
-    // safely unwrap the transaction hash
-    let transaction_hash = match H256::from_str(&tx.hash) {
-        Ok(transaction_hash) => transaction_hash,
-        Err(_) => {
-            cleanup_terminal();
-            logger.error(&format!("failed to parse transaction hash '{}' .", &tx.hash));
-            std::process::exit(1)
-        }
-    };
-
-    // fetch the state diff for the transaction
-    let state_diff =
-        match provider.trace_replay_transaction(transaction_hash, vec![TraceType::StateDiff]).await
-        {
-            Ok(traces) => traces.state_diff,
+    for (i, result) in missing.into_iter().zip(results) {
+        let tx = &txs[i];
+        let state_diff = match result.and_then(|trace| {
+            serde_json::from_value::<BlockTrace>(trace).map_err(|e| e.to_string())
+        }) {
+            Ok(trace) => trace.state_diff,
             Err(e) => {
-                cleanup_terminal();
-                logger.error(&format!(
-                "failed to replay and trace transaction '{}' . does your RPC provider support it?",
-                &tx.hash
-            ));
-                logger.error(&format!("error: '{e}' ."));
-                std::process::exit(1)
+                return Err(Failure::new(FailureClass::Rpc, format!("failed to replay and trace transaction '{}': {e}. does your RPC provider support it?", &tx.hash)))
             }
         };
 
-    // write the state diff to the cache
-    let expiry =
-        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() +
-            60 * 60 * 24 * 7;
-    store_cache(&format!("diff.{}.{}", &chain_id, &tx.hash), &state_diff, Some(expiry));
+        // write the state diff to the cache
+        let expiry =
+            std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() +
+                60 * 60 * 24 * 7;
+        if rpc_cache_enabled() {
+            store_cache(&format!("diff.{}.{}", &chain_id, &tx.hash), &state_diff, Some(expiry));
+        }
+
+        logger.debug_max(&format!("fetched state diff for transaction '{}' .", &tx.hash));
+        state_diffs[i] = Some(state_diff);
+    }
+
+    Ok(state_diffs.into_iter().map(|state_diff| state_diff.flatten()).collect())
+}
+
+/// get the target's value of each of the given slots at the end of the given block, or the latest
+/// block if it hasn't been mined yet, in a single batch of requests
+pub async fn get_storage_values(
+    slots: &[H256],
+    block: u128,
+    args: &DumpArgs,
+) -> Result<Vec<H256>, Error> {
+    let block = get_block_number(&args.rpc_url).await.map_err(Error::from_rpc)?.min(block);
+    let requests = slots
+        .iter()
+        .map(|slot| ("eth_getStorageAt", json!([args.target, slot, format!("{block:#x}")])))
+        .collect::<Vec<_>>();
+
+    batch_request(&args.rpc_url, &requests)
+        .await
+        .map_err(Error::from_rpc)?
+        .into_iter()
+        .map(|result| {
+            let value =
+                result.map_err(|e| Error::RpcError(format!("eth_getStorageAt failed: {e}")))?;
+            H256::from_str(value.as_str().unwrap_or_default())
+                .map_err(|e| Error::RpcError(format!("malformed eth_getStorageAt response: {e}")))
+        })
+        .collect()
+}
+
+/// get the state root of the given block, or the latest block if it hasn't been mined yet, and
+/// the proof of each of the given slots of the target at that block, in a single batch of requests
+pub async fn get_storage_proofs(
+    slots: &[H256],
+    block: u128,
+    args: &DumpArgs,
+) -> Result<(u128, H256, EIP1186ProofResponse), Error> {
+    let block = get_block_number(&args.rpc_url).await.map_err(Error::from_rpc)?.min(block);
+    let mut requests = vec![("eth_getBlockByNumber", json!([format!("{block:#x}"), false]))];
+    requests.extend(
+        slots
+            .chunks(PROOF_CHUNK_SIZE)
+            .map(|chunk| ("eth_getProof", json!([args.target, chunk, format!("{block:#x}")]))),
+    );
+
+    let mut results =
+        batch_request(&args.rpc_url, &requests).await.map_err(Error::from_rpc)?.into_iter();
+    let state_root = results
+        .next()
+        .and_then(|header| header.ok())
+        .and_then(|header| header.get("stateRoot")?.as_str().map(H256::from_str))
+        .and_then(|state_root| state_root.ok())
+        .ok_or_else(|| Error::RpcError(format!("failed to get the state root of block {block}")))?;
 
-    logger.debug_max(&format!("fetched state diff for transaction '{}' .", &tx.hash));
+    // every chunk has the same account proof, so their storage proofs are merged
+    let mut proof: Option<EIP1186ProofResponse> = None;
+    for result in results {
+        let response = result
+            .map_err(|e| Error::RpcError(format!("eth_getProof failed: {e}")))
+            .and_then(|response| {
+                serde_json::from_value::<EIP1186ProofResponse>(response)
+                    .map_err(|e| Error::RpcError(format!("malformed eth_getProof response: {e}")))
+            })?;
+        match proof.as_mut() {
+            Some(proof) => proof.storage_proof.extend(response.storage_proof),
+            None => proof = Some(response),
+        }
+    }
 
-    state_diff
+    Ok((block, state_root, proof.unwrap_or_default()))
 }
@@ -66,7 +66,14 @@ pub fn build_rows(state: &mut DumpState, max_row_height: usize) -> Vec<Row<'stat
 
         rows.push(
             Row::new(vec![
-                Cell::from(value.modifiers.iter().max_by_key(|m| m.0).unwrap().0.to_string()),
+                Cell::from(
+                    value
+                        .modifiers
+                        .iter()
+                        .max_by_key(|m| m.0)
+                        .map(|m| m.0.to_string())
+                        .unwrap_or_default(),
+                ),
                 Cell::from(format!("0x{}", encode_hex(slot.to_fixed_bytes().into()))),
                 Cell::from(DECODE_AS_TYPES[value.decode_as_type_index].clone()),
                 Cell::from(decoded_value),
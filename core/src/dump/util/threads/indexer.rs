@@ -1,18 +1,31 @@
-use std::time::Duration;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use ethers::types::{Diff, H160};
-use heimdall_common::utils::{io::logging::Logger, threading::task_pool};
-use indicatif::ProgressBar;
+use ethers::types::{Diff, H160, H256};
+use heimdall_common::utils::{
+    io::{logging::Logger, progress::Progress},
+    threading::task_pool,
+};
 
 use crate::dump::{
-    constants::DUMP_STATE, structures::storage_slot::StorageSlot, util::get_storage_diff,
+    constants::DUMP_STATE,
+    structures::{checkpoint::Checkpoint, storage_slot::StorageSlot, transaction::Transaction},
+    util::get_storage_diffs,
 };
 
+// how often the dump's progress is checkpointed, so it can be resumed if it's interrupted
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
 /// The main function for indexing storage slots. Will fetch the storage diff for each transaction
 /// in a threaded task pool, updating the state accordingly.
 pub async fn handle(addr_hash: H160) {
     let state = DUMP_STATE.lock().unwrap();
-    let transactions = state.transactions.clone();
+
+    // transactions indexed before a resumed dump was interrupted are skipped
+    let transactions =
+        state.transactions.iter().filter(|tx| !tx.indexed).cloned().collect::<Vec<Transaction>>();
     let args = state.args.clone();
     drop(state);
 
@@ -25,91 +38,114 @@ pub async fn handle(addr_hash: H160) {
         None => "SILENT",
     });
 
-    // get a new progress bar
-    let transaction_list_progress = ProgressBar::new_spinner();
-    transaction_list_progress.enable_steady_tick(Duration::from_millis(100));
-    transaction_list_progress.set_style(logger.info_spinner());
+    // get a new progress bar, unless the TUI shows the dump's progress
+    let transaction_list_progress = args.no_tui.then(|| {
+        let progress = Progress::new("dump", transactions.len() as u64, &logger);
+        progress.set_message("dumping storage");
+        progress
+    });
+    let finished_progress = transaction_list_progress.clone();
 
-    if !args.no_tui {
-        transaction_list_progress.finish_and_clear();
-    }
+    // transactions are fetched in chunks, but small enough that every thread gets some
+    let batch_size =
+        transactions.len().div_ceil(num_indexing_threads.max(1)).clamp(1, args.chunk_size.max(1));
+    let batches = transactions.chunks(batch_size).map(|batch| batch.to_vec()).collect();
+    let last_checkpoint = Arc::new(Mutex::new(Instant::now()));
+    let cleared = H256::zero();
 
-    task_pool(transactions, num_indexing_threads, move |tx| {
+    task_pool(batches, num_indexing_threads, move |batch: Vec<Transaction>| {
         // get new blocking runtime
         let rt = tokio::runtime::Runtime::new().unwrap();
 
-        // get the storage diff for this transaction
-        let state_diff = rt.block_on(get_storage_diff(&tx, &args));
-
-        // unlock state
-        let mut state = DUMP_STATE.lock().unwrap();
-
-        // find the transaction in the state
-        let all_txs = state.transactions.clone();
-        let txs = state.transactions.iter_mut().find(|t| t.hash == tx.hash).unwrap();
-        let block_number = tx.block_number;
-
-        if args.no_tui {
-            let num_done = all_txs.iter().filter(|t| t.indexed).count();
-            let total = all_txs.len();
-            transaction_list_progress.set_message(format!(
-                "dumping storage. Progress {}/{} ({:.2}%)",
-                num_done,
-                total,
-                (num_done as f64 / total as f64) * 100.0
-            ));
-
-            if num_done == total - 1 {
-                transaction_list_progress.finish_and_clear();
-            }
+        // once indexing has failed, the remaining batches are skipped
+        if DUMP_STATE.lock().unwrap().failure.is_some() {
+            return
         }
-        txs.indexed = true;
-
-        // unwrap the state diff
-        if let Some(state_diff) = state_diff {
-            // get diff for this address
-            if let Some(diff) = state_diff.0.get(&addr_hash) {
-                // build diff of StorageSlots and append to state
-                for (slot, diff_type) in &diff.storage {
-                    // parse value from diff type
-                    let value = match diff_type {
-                        Diff::Born(value) => value,
-                        Diff::Changed(changed) => &changed.to,
-                        Diff::Died(_) => {
-                            state.storage.remove(slot);
-                            continue
-                        }
-                        _ => continue,
-                    };
-
-                    // get the slot from the state
-                    match state.storage.get_mut(slot) {
-                        Some(slot) => {
-                            // update value if newest modifier
-                            if slot.modifiers.iter().all(|m| m.0 < block_number) {
-                                slot.value = *value;
-                            }
 
-                            slot.modifiers.push((block_number, tx.hash.clone().to_owned()));
-                        }
-                        None => {
-                            // insert into state
-                            state.storage.insert(
-                                *slot,
-                                StorageSlot {
-                                    value: *value,
-                                    modifiers: vec![(block_number, tx.hash.clone().to_owned())],
-                                    alias: None,
-                                    decode_as_type_index: 0,
-                                },
-                            );
+        // get the storage diff for each transaction in this batch
+        let state_diffs = match rt.block_on(get_storage_diffs(&batch, &args)) {
+            Ok(state_diffs) => state_diffs,
+            Err(failure) => {
+                DUMP_STATE.lock().unwrap().failure = Some(failure);
+                return
+            }
+        };
+
+        for (tx, state_diff) in batch.into_iter().zip(state_diffs) {
+            // unlock state
+            let mut state = DUMP_STATE.lock().unwrap();
+
+            // find the transaction in the state
+            let txs = state.transactions.iter_mut().find(|t| t.hash == tx.hash).unwrap();
+            let block_number = tx.block_number;
+
+            if let Some(progress) = &transaction_list_progress {
+                progress.inc(1);
+            }
+            txs.indexed = true;
+
+            // unwrap the state diff
+            if let Some(state_diff) = state_diff {
+                state.accounts.extend(state_diff.0.keys());
+
+                // get diff for this address
+                if let Some(diff) = state_diff.0.get(&addr_hash) {
+                    // build diff of StorageSlots and append to state
+                    for (slot, diff_type) in &diff.storage {
+                        // parse value from diff type
+                        let value = match diff_type {
+                            Diff::Born(value) => value,
+                            Diff::Changed(changed) => &changed.to,
+
+                            // a diff reports slots which were cleared, rather than dropping them
+                            Diff::Died(_) if args.diff => &cleared,
+                            Diff::Died(_) => {
+                                state.storage.remove(slot);
+                                continue
+                            }
+                            _ => continue,
+                        };
+
+                        // get the slot from the state
+                        match state.storage.get_mut(slot) {
+                            Some(slot) => {
+                                // update value if newest modifier
+                                if slot.modifiers.iter().all(|m| m.0 < block_number) {
+                                    slot.value = *value;
+                                }
+
+                                slot.modifiers.push((block_number, tx.hash.clone().to_owned()));
+                            }
+                            None => {
+                                // insert into state
+                                state.storage.insert(
+                                    *slot,
+                                    StorageSlot {
+                                        value: *value,
+                                        modifiers: vec![(block_number, tx.hash.clone().to_owned())],
+                                        alias: None,
+                                        decode_as_type_index: 0,
+                                    },
+                                );
+                            }
                         }
                     }
                 }
             }
+
+            // drop state
+            drop(state);
         }
 
-        // drop state
-        drop(state);
+        // checkpoint the progress made, at most once per interval
+        let mut last_checkpoint = last_checkpoint.lock().unwrap();
+        if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+            Checkpoint::new(&DUMP_STATE.lock().unwrap()).save(&args);
+            *last_checkpoint = Instant::now();
+        }
     });
+
+    if let Some(progress) = finished_progress {
+        progress.finish_and_clear();
+    }
 }
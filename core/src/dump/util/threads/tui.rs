@@ -5,6 +5,7 @@ use crossterm::{
     execute,
     terminal::{enable_raw_mode, EnterAlternateScreen},
 };
+use heimdall_common::utils::failure::{Failure, FailureClass};
 use tui::{backend::CrosstermBackend, Terminal};
 
 use crate::dump::{
@@ -30,6 +31,12 @@ pub fn handle(args: &DumpArgs, output_dir: &str) {
 
     loop {
         let mut state = DUMP_STATE.lock().unwrap();
+
+        // the dump is over once indexing fails
+        if state.failure.is_some() {
+            break
+        }
+
         terminal
             .draw(|f| {
                 render_ui(f, &mut state);
@@ -80,7 +87,14 @@ pub fn handle(args: &DumpArgs, output_dir: &str) {
                                         }
                                         ":e" | ":export" => {
                                             if !args.is_empty() {
-                                                write_storage_to_csv(output_dir, args[0], &state);
+                                                if let Err(e) = write_storage_to_csv(
+                                                    output_dir, args[0], &state,
+                                                ) {
+                                                    state.failure = Some(Failure::new(
+                                                        FailureClass::Internal,
+                                                        e.to_string(),
+                                                    ));
+                                                }
                                             }
                                             state.view = TUIView::Main;
                                         }
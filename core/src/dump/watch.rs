@@ -0,0 +1,227 @@
+use std::{str::FromStr, time::Duration};
+
+use ethers::{
+    providers::{Middleware, StreamExt},
+    types::{BlockTrace, Diff, H160, H256},
+};
+use heimdall_common::{
+    ether::{history::get_block_number, pool::RpcPool, rpc::batch_request},
+    utils::io::logging::Logger,
+};
+use serde::Serialize;
+use serde_json::json;
+
+use super::{constants::DUMP_STATE, structures::storage_slot::StorageSlot, DumpArgs};
+use crate::error::Error;
+
+// how often the RPC provider is polled for new blocks, if it doesn't support subscriptions
+const POLL_INTERVAL: Duration = Duration::from_secs(4);
+
+/// A write to one of the target's storage slots, made by a transaction in a block which was mined
+/// while watching.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageWrite {
+    pub block_number: u128,
+    pub transaction_hash: String,
+    pub slot: H256,
+    pub old_value: H256,
+    pub new_value: H256,
+}
+
+/// Watch each block mined after the dump for transactions which write to the target's storage,
+/// calling `on_write` with each write as its block is traced. New blocks are subscribed to if the
+/// RPC provider has a WebSocket or IPC endpoint, and polled for otherwise. This runs until the
+/// process is stopped, or the RPC provider fails.
+pub async fn watch(args: &DumpArgs, mut on_write: impl FnMut(&StorageWrite)) -> Result<(), Error> {
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+    let target = H160::from_str(&args.target)
+        .map_err(|_| Error::ParseError(format!("failed to parse target '{}' .", &args.target)))?;
+
+    // blocks are traced from the first one the dump didn't cover
+    let mut next_block = DUMP_STATE.lock().unwrap().last_block + 1;
+
+    let pubsub = RpcPool::new(&args.rpc_url).pubsub_provider().await;
+    let mut subscription = match &pubsub {
+        Some((_, provider)) => provider.subscribe_blocks().await.ok(),
+        None => None,
+    };
+    if subscription.is_none() {
+        logger.info(&format!(
+            "no WebSocket or IPC endpoint was given, so new blocks will be polled for every {}s.",
+            POLL_INTERVAL.as_secs()
+        ));
+    }
+    logger.info(&format!(
+        "watching '{}' for storage writes from block {} .",
+        args.target, next_block
+    ));
+
+    loop {
+        let head = get_block_number(&args.rpc_url)
+            .await
+            .map_err(|e| Error::RpcError(format!("failed to get the latest block: {e}")))?;
+
+        // every block since the last one traced is traced, in case any were skipped
+        while next_block <= head {
+            let response = batch_request(
+                &args.rpc_url,
+                &[(
+                    "trace_replayBlockTransactions",
+                    json!([format!("{next_block:#x}"), ["stateDiff"]]),
+                )],
+            )
+            .await
+            .map_err(Error::from_rpc)?
+            .pop()
+            .ok_or_else(|| Error::RpcError(String::from("no response")))?
+            .map_err(|e| Error::RpcError(format!("failed to trace block {next_block}: {e}")))?;
+            let traces = serde_json::from_value::<Vec<BlockTrace>>(response)?;
+
+            for write in storage_writes(next_block, &traces, target) {
+                apply_write(&write);
+                on_write(&write);
+            }
+            logger.debug_max(&format!("traced block {next_block} ."));
+            next_block += 1;
+        }
+
+        // wait for the next block to be mined
+        match subscription.as_mut() {
+            Some(blocks) => {
+                if blocks.next().await.is_none() {
+                    return Err(Error::RpcError(String::from("the block subscription ended")))
+                }
+            }
+            None => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+/// gets the writes to the target's storage from the state diffs of a block's transactions
+fn storage_writes(block_number: u128, traces: &[BlockTrace], target: H160) -> Vec<StorageWrite> {
+    let mut writes = Vec::new();
+    for trace in traces {
+        let diff = match trace.state_diff.as_ref().and_then(|state_diff| state_diff.0.get(&target))
+        {
+            Some(diff) => diff,
+            None => continue,
+        };
+
+        for (slot, diff) in &diff.storage {
+            let (old_value, new_value) = match diff {
+                Diff::Born(value) => (H256::zero(), *value),
+                Diff::Changed(changed) => (changed.from, changed.to),
+                Diff::Died(value) => (*value, H256::zero()),
+                Diff::Same => continue,
+            };
+            writes.push(StorageWrite {
+                block_number,
+                transaction_hash: format!("{:?}", trace.transaction_hash.unwrap_or_default()),
+                slot: *slot,
+                old_value,
+                new_value,
+            });
+        }
+    }
+    writes
+}
+
+/// updates the dumped storage with a write, as the indexer does
+fn apply_write(write: &StorageWrite) {
+    let mut state = DUMP_STATE.lock().unwrap();
+    if write.new_value.is_zero() {
+        state.storage.remove(&write.slot);
+        return
+    }
+
+    let modifier = (write.block_number, write.transaction_hash.clone());
+    match state.storage.get_mut(&write.slot) {
+        Some(slot) => {
+            slot.value = write.new_value;
+            slot.modifiers.push(modifier);
+        }
+        None => {
+            state.storage.insert(
+                write.slot,
+                StorageSlot {
+                    alias: None,
+                    value: write.new_value,
+                    modifiers: vec![modifier],
+                    decode_as_type_index: 0,
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ethers::types::{AccountDiff, BlockTrace, Bytes, ChangedType, Diff, StateDiff, H160, H256};
+
+    use crate::dump::watch::storage_writes;
+
+    fn trace(
+        transaction_hash: H256,
+        account: H160,
+        storage: Vec<(H256, Diff<H256>)>,
+    ) -> BlockTrace {
+        BlockTrace {
+            output: Bytes::new(),
+            trace: None,
+            vm_trace: None,
+            state_diff: Some(StateDiff(BTreeMap::from([(
+                account,
+                AccountDiff {
+                    balance: Diff::Same,
+                    nonce: Diff::Same,
+                    code: Diff::Same,
+                    storage: storage.into_iter().collect(),
+                },
+            )]))),
+            transaction_hash: Some(transaction_hash),
+        }
+    }
+
+    #[test]
+    fn test_storage_writes() {
+        let target = H160::repeat_byte(0xaa);
+        let traces = [
+            trace(
+                H256::repeat_byte(1),
+                target,
+                vec![
+                    (
+                        H256::zero(),
+                        Diff::Changed(ChangedType {
+                            from: H256::from_low_u64_be(1),
+                            to: H256::from_low_u64_be(2),
+                        }),
+                    ),
+                    (H256::from_low_u64_be(1), Diff::Died(H256::from_low_u64_be(3))),
+                ],
+            ),
+            trace(
+                H256::repeat_byte(2),
+                H160::repeat_byte(0xbb),
+                vec![(H256::zero(), Diff::Born(H256::from_low_u64_be(4)))],
+            ),
+        ];
+
+        let writes = storage_writes(100, &traces, target);
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].transaction_hash, format!("{:?}", H256::repeat_byte(1)));
+        assert_eq!(
+            (writes[0].old_value, writes[0].new_value),
+            (H256::from_low_u64_be(1), H256::from_low_u64_be(2))
+        );
+        assert_eq!(
+            (writes[1].old_value, writes[1].new_value),
+            (H256::from_low_u64_be(3), H256::zero())
+        );
+    }
+}
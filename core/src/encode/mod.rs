@@ -0,0 +1,160 @@
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::utils::{io::logging::Logger, strings::encode_hex};
+
+use crate::{error::Error, simulate::encode_call};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Encode calldata from a function signature and its arguments",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall encode <SIGNATURE> [ARGS]... [OPTIONS]"
+)]
+pub struct EncodeArgs {
+    /// The signature of the function to encode a call to, such as `transfer(address,uint256)` or
+    /// `transfer(address to, uint256 amount)`.
+    #[clap(required = true)]
+    pub signature: String,
+
+    /// The arguments to encode, in order. Arrays and tuples are written as `[1,2]` and `(0x..,1)`.
+    #[clap(multiple_values = true, allow_hyphen_values = true)]
+    pub arguments: Vec<String>,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+}
+
+impl EncodeArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            signature: Some(String::new()),
+            arguments: Some(Vec::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+        }
+    }
+}
+
+/// The entrypoint for the encode module, the inverse of decode. This will parse each argument as
+/// the type of its parameter in the signature, and return the calldata of the call as a hex
+/// string.
+pub async fn encode(args: EncodeArgs) -> Result<String, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    // get a new logger
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let call = encode_call(&args.signature, &args.arguments)?;
+    logger.debug(&format!(
+        "encoded {} arguments for '{}' in {} bytes.",
+        args.arguments.len(),
+        call.signature,
+        call.calldata.len()
+    ));
+
+    Ok(format!("0x{}", encode_hex(call.calldata)))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::{decode, ParamType, Token},
+        types::{H160, U256},
+    };
+    use heimdall_common::utils::strings::decode_hex;
+
+    use crate::encode::{encode, EncodeArgsBuilder};
+
+    #[tokio::test]
+    async fn test_encode() {
+        let calldata = encode(
+            EncodeArgsBuilder::new()
+                .signature(String::from("swap(address,uint256)"))
+                .arguments(vec![
+                    String::from("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"),
+                    String::from("1000"),
+                ])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            calldata,
+            "0xd004f0f7000000000000000000000000aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa00000000000000000000000000000000000000000000000000000000000003e8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_human_readable_complex() {
+        let calldata = encode(
+            EncodeArgsBuilder::new()
+                .signature(String::from(
+                    "function fill((address maker, uint96 amount)[] calldata orders, bytes32[2] salts)",
+                ))
+                .arguments(vec![
+                    String::from("[(0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,5)]"),
+                    String::from(
+                        "[0x0000000000000000000000000000000000000000000000000000000000000001,0x0000000000000000000000000000000000000000000000000000000000000002]",
+                    ),
+                ])
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let calldata = decode_hex(&calldata[2..]).unwrap();
+        assert_eq!(
+            decode(
+                &[
+                    ParamType::Array(Box::new(ParamType::Tuple(vec![
+                        ParamType::Address,
+                        ParamType::Uint(96)
+                    ]))),
+                    ParamType::FixedArray(Box::new(ParamType::FixedBytes(32)), 2)
+                ],
+                &calldata[4..]
+            )
+            .unwrap(),
+            vec![
+                Token::Array(vec![Token::Tuple(vec![
+                    Token::Address(H160::repeat_byte(0xaa)),
+                    Token::Uint(U256::from(5))
+                ])]),
+                Token::FixedArray(vec![
+                    Token::FixedBytes([vec![0; 31], vec![1]].concat()),
+                    Token::FixedBytes([vec![0; 31], vec![2]].concat())
+                ])
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_invalid_argument() {
+        let result = encode(
+            EncodeArgsBuilder::new()
+                .signature(String::from("transfer(address,uint256)"))
+                .arguments(vec![String::from("0xaa"), String::from("1")])
+                .build()
+                .unwrap(),
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -1,5 +1,39 @@
+use std::num::{ParseIntError, TryFromIntError};
+
+use heimdall_common::{
+    ether::{
+        eof::EofError,
+        evm::core::{
+            fault::{Fault, FaultKind},
+            opcodes::Opcode,
+            types::SignatureError,
+        },
+    },
+    utils::failure::{Failure, FailureClass},
+};
+
+/// The errors returned by heimdall's modules. Failures are grouped by category, so library users
+/// can tell a bad RPC provider apart from bad input or a VM fault:
+/// - [`Error::RpcError`] for failures to fetch on-chain data
+/// - [`Error::ResolutionError`] for targets that can't be resolved to bytecode or calldata
+/// - [`Error::VmError`] for bytecode the VM can't execute, along with the VM faults below
+/// - [`Error::ParseError`] and [`Error::EofError`] for malformed hex, numbers, patterns, or EOF
+///   containers
+/// - [`Error::OutputError`] for failures to read or write files and terminals
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("RpcError: {0}")]
+    RpcError(String),
+    #[error("ResolutionError: {0}")]
+    ResolutionError(String),
+    #[error("VmError: {0}")]
+    VmError(String),
+    #[error("ParseError: {0}")]
+    ParseError(String),
+    #[error("OutputError: {0}")]
+    OutputError(#[from] std::io::Error),
+    #[error("EofError: {0}")]
+    EofError(#[from] EofError),
     #[error("Json error: {0}")]
     SerdeError(#[from] serde_json::Error),
     #[error("FromStrRadixError: {0}")]
@@ -8,6 +42,118 @@ pub enum Error {
     BoundsError,
     #[error("DecodeError")]
     DecodeError,
+    #[error("AssembleError: line {0}: {1}")]
+    AssembleError(usize, String),
+    #[error("StackUnderflow: {opcode} at pc {pc} in {address} (depth {depth}) requires {required} stack items, but {available} are available")]
+    StackUnderflow {
+        pc: u128,
+        opcode: String,
+        address: String,
+        depth: usize,
+        required: usize,
+        available: usize,
+    },
+    #[error("InvalidJump: {opcode} at pc {pc} in {address} (depth {depth}) jumps to {destination}, which isn't a JUMPDEST")]
+    InvalidJump { pc: u128, opcode: String, address: String, depth: usize, destination: String },
+    #[error("MemoryBoundsError: {opcode} at pc {pc} in {address} (depth {depth}) accesses {size} bytes at offset {offset}")]
+    MemoryBoundsError {
+        pc: u128,
+        opcode: String,
+        address: String,
+        depth: usize,
+        offset: String,
+        size: String,
+    },
     #[error("Error: {0}")]
     GenericError(String),
 }
+
+impl Error {
+    /// The [`FailureClass`] of this error, which decides the CLI's exit code and whether the
+    /// failure is worth retrying.
+    pub fn failure_class(&self) -> FailureClass {
+        match self {
+            Error::RpcError(_) => FailureClass::Rpc,
+            Error::ResolutionError(_) => FailureClass::UnresolvedTarget,
+            Error::ParseError(_) |
+            Error::EofError(_) |
+            Error::FromStrRadixError(_) |
+            Error::DecodeError |
+            Error::AssembleError(..) => FailureClass::InvalidInput,
+            _ => FailureClass::Internal,
+        }
+    }
+}
+
+impl Error {
+    /// Convert an error returned while fetching on-chain data into an [`Error`], keeping its
+    /// [`FailureClass`] if it has one, so a missing RPC provider isn't reported as an RPC failure.
+    pub fn from_rpc(error: Box<dyn std::error::Error>) -> Self {
+        match error.downcast::<Failure>() {
+            Ok(failure) => Error::from(*failure),
+            Err(error) => Error::RpcError(error.to_string()),
+        }
+    }
+}
+
+impl From<Failure> for Error {
+    fn from(failure: Failure) -> Self {
+        match failure.class {
+            FailureClass::Rpc => Error::RpcError(failure.message),
+            FailureClass::UnresolvedTarget => Error::ResolutionError(failure.message),
+            FailureClass::InvalidInput => Error::ParseError(failure.message),
+            _ => Error::GenericError(failure.message),
+        }
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(error: ParseIntError) -> Self {
+        Error::ParseError(error.to_string())
+    }
+}
+
+impl From<TryFromIntError> for Error {
+    fn from(error: TryFromIntError) -> Self {
+        Error::ParseError(error.to_string())
+    }
+}
+
+impl From<fancy_regex::Error> for Error {
+    fn from(error: fancy_regex::Error) -> Self {
+        Error::ParseError(error.to_string())
+    }
+}
+
+impl From<SignatureError> for Error {
+    fn from(error: SignatureError) -> Self {
+        Error::ParseError(error.to_string())
+    }
+}
+
+impl From<Fault> for Error {
+    fn from(fault: Fault) -> Self {
+        let opcode = Opcode::new(fault.opcode).name.to_string();
+        let (pc, address, depth) = (fault.pc, fault.address, fault.depth);
+        match fault.kind {
+            FaultKind::StackUnderflow { required, available } => {
+                Error::StackUnderflow { pc, opcode, address, depth, required, available }
+            }
+            FaultKind::InvalidJump(destination) => Error::InvalidJump {
+                pc,
+                opcode,
+                address,
+                depth,
+                destination: format!("{destination:#x}"),
+            },
+            FaultKind::MemoryOutOfBounds { offset, size } => Error::MemoryBoundsError {
+                pc,
+                opcode,
+                address,
+                depth,
+                offset: format!("{offset:#x}"),
+                size: format!("{size:#x}"),
+            },
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        eof::is_eof,
+        evm::core::{
+            fork::Fork,
+            hardfork::Hardfork,
+            trace::{ExecutionTrace, Tracer},
+            vm::VM,
+        },
+    },
+    utils::{
+        io::logging::*,
+        strings::{decode_hex, encode_hex},
+    },
+};
+
+use crate::{error::Error, utils::get_contract_bytecode};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Execute EVM bytecode with the given calldata",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall exec <TARGET> [OPTIONS]"
+)]
+pub struct ExecArgs {
+    /// The target to execute, either a file, bytecode, contract address, or `-` to read bytecode
+    /// from stdin.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. When the target is a contract
+    /// address, its state is also read from this provider. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The calldata to execute the target with.
+    #[clap(long = "calldata", short, default_value = "0x", hide_default_value = true)]
+    pub calldata: String,
+
+    /// The value, in wei, to send with the call.
+    #[clap(long = "value", default_value = "0", hide_default_value = true)]
+    pub value: u128,
+
+    /// The gas limit of the call.
+    #[clap(long = "gas-limit", default_value = "30000000")]
+    pub gas_limit: u128,
+
+    /// The hardfork whose gas schedule the VM follows.
+    #[clap(long = "evm-version", default_value = "cancun")]
+    pub evm_version: Hardfork,
+
+    /// Whether to record every step of execution in geth's `structLogs` format.
+    #[clap(long = "trace", short)]
+    pub trace: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl ExecArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            calldata: Some(String::from("0x")),
+            value: Some(0),
+            gas_limit: Some(30_000_000),
+            evm_version: Some(Hardfork::default()),
+            trace: Some(false),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// The main entry point for the exec module. Will execute the target with the given calldata and
+/// return the result, along with every step of execution if `trace` is set.
+pub async fn exec(args: ExecArgs) -> Result<ExecutionTrace, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let mut evm =
+        build_vm(&args.target, &args.rpc_url, &args.calldata, args.value, args.gas_limit, &logger)
+            .await?;
+    evm.hardfork = args.evm_version;
+
+    let tracer = Tracer::default();
+    if args.trace {
+        evm.tracer = Some(tracer.clone());
+    }
+
+    let result = evm.execute();
+    logger.debug(&format!(
+        "execution completed with exit code {} in {:?}.",
+        result.exitcode,
+        now.elapsed()
+    ));
+    if let Some(fault) = result.fault {
+        logger.warn(&Error::from(fault).to_string());
+    }
+
+    Ok(ExecutionTrace {
+        gas: result.gas_used,
+        // RETURN, STOP, and running past the end of the code are the only successful exits
+        failed: !matches!(result.exitcode, 0 | 10 | 255),
+        return_value: encode_hex(result.returndata),
+        struct_logs: tracer.logs(),
+    })
+}
+
+/// Builds a [`VM`] which will execute the given target with the given calldata. When the target is
+/// a contract address, the VM runs at that address, with the rest of the chain's state read from
+/// the RPC provider.
+pub(crate) async fn build_vm(
+    target: &str,
+    rpc_url: &str,
+    calldata: &str,
+    value: u128,
+    gas_limit: u128,
+    logger: &Logger,
+) -> Result<VM, Error> {
+    // fetch bytecode
+    let mut address = String::from("0x6865696d64616c6c000000000061646472657373");
+    let mut fork = None;
+    let contract_bytecode = get_contract_bytecode(target, rpc_url, logger).await?;
+    if ADDRESS_REGEX.is_match(target)? {
+        // contracts read the rest of their state from the RPC provider too
+        address = target.to_string();
+        fork = Some(Fork::new(rpc_url, None));
+    }
+
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by the VM. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
+    }
+
+    if decode_hex(&calldata.replacen("0x", "", 1)).is_err() {
+        return Err(Error::ParseError(format!("invalid calldata '{calldata}'.")))
+    }
+
+    let mut evm = VM::new(
+        contract_bytecode,
+        calldata.to_string(),
+        address,
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        value,
+        gas_limit,
+    );
+    evm.fork = fork;
+
+    Ok(evm)
+}
@@ -10,7 +10,7 @@ mod snapshot;
 use clap::{Parser, Subcommand};
 
 use cfg::{cfg, CFGArgs};
-use colored::Colorize;
+use heimdall_common::utils::io::theme::{Paint, Role};
 use crossterm::{
     event::DisableMouseCapture,
     execute,
@@ -91,12 +91,19 @@ fn main() {
         let (logger, _) = Logger::new("TRACE");
         logger.fatal(&format!(
             "thread 'main' encountered a fatal error: '{}'!",
-            panic_info.to_string().bright_white().on_bright_red().bold(),
+            panic_info.to_string().paint(Role::Fatal),
         ));
         logger.fatal(&format!("Stack Trace:\n\n{backtrace:#?}"));
     }));
 
-    let configuration = get_config();
+    let configuration = match get_config() {
+        Ok(configuration) => configuration,
+        Err(e) => {
+            let (logger, _) = Logger::new("");
+            logger.error(&e.to_string());
+            std::process::exit(1)
+        }
+    };
     match args.sub {
         Subcommands::Disassemble(mut cmd) => {
             // if the user has not specified a rpc url, use the default
@@ -228,7 +235,11 @@ fn main() {
             snapshot(cmd);
         }
         Subcommands::Config(cmd) => {
-            config(cmd);
+            if let Err(e) = config(cmd) {
+                let (logger, _) = Logger::new("");
+                logger.error(&e.to_string());
+                std::process::exit(1)
+            }
         }
 
         Subcommands::Cache(cmd) => {
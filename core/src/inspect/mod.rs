@@ -0,0 +1,298 @@
+pub mod report;
+pub mod tenderly;
+
+use std::{collections::HashMap, path::Path};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::{
+    abi::decode as decode_abi,
+    types::{BlockTrace, Bytes, Log},
+};
+use heimdall_common::{
+    constants::TRANSACTION_HASH_REGEX,
+    ether::{
+        evm::core::types::parse_function_parameters,
+        rpc::{batch_request, get_transaction, get_transaction_receipt},
+        signatures::{ResolveSelector, ResolvedFunction, ResolvedLog},
+    },
+    utils::{io::logging::Logger, strings::encode_hex},
+};
+use serde_json::json;
+
+use crate::{
+    decode::{decode, DecodeArgsBuilder},
+    error::Error,
+    inspect::{
+        report::{
+            build_call_tree, calldata_gas, intrinsic_gas, storage_changes, value_transfers,
+            DecodedLog, GasBreakdown, InspectReport,
+        },
+        tenderly::TenderlyTrace,
+    },
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Inspect a transaction's calls, events, value transfers, and storage writes",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall inspect <TARGET> [OPTIONS]"
+)]
+pub struct InspectArgs {
+    /// The hash of the transaction to inspect, or a trace of it exported from Tenderly, which is
+    /// used instead of fetching the transaction and its traces.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to fetch the transaction and its traces from. The provider must support
+    /// `trace_replayTransaction` for the call tree, value transfers, and storage writes. Separate
+    /// multiple providers with commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function and event signatures, leaving calldata and logs
+    /// undecoded.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The format to output the report in, either `json`, or `tenderly` for Tenderly's trace
+    /// format.
+    #[clap(long = "format", default_value = "json")]
+    pub format: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl InspectArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            format: Some(String::from("json")),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// The main entry point for the inspect module. Will fetch the target transaction, its receipt,
+/// and its traces, and report on its decoded input, events, internal calls, value transfers,
+/// storage writes, and gas usage.
+pub async fn inspect(args: InspectArgs) -> Result<InspectReport, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    if !["json", "tenderly"].contains(&args.format.as_str()) {
+        return Err(Error::ParseError(format!("unknown format '{}' .", &args.format)))
+    }
+
+    // an exported Tenderly trace holds everything the report needs, so nothing is fetched
+    let (transaction, receipt, traces, storage_writes) = if Path::new(&args.target).is_file() {
+        let trace = TenderlyTrace::from_file(&args.target)?;
+        logger.debug(&format!("read {} calls from Tenderly trace.", trace.trace.len()));
+        (trace.transaction(), trace.receipt(), trace.transaction_traces(), trace.storage_changes())
+    } else if TRANSACTION_HASH_REGEX.is_match(&args.target)? {
+        let transaction =
+            get_transaction(&args.target, &args.rpc_url).await.map_err(Error::from_rpc)?;
+        let receipt =
+            get_transaction_receipt(&args.target, &args.rpc_url).await.map_err(Error::from_rpc)?;
+
+        // the call traces and state diff are replayed together, and the report is still built
+        // without them if the provider doesn't support tracing
+        let block_trace = match batch_request(
+            &args.rpc_url,
+            &[("trace_replayTransaction", json!([&args.target, ["trace", "stateDiff"]]))],
+        )
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|mut results| results.pop().ok_or_else(|| String::from("no response")))
+        .and_then(|result| result.map_err(|e| e.to_string()))
+        .and_then(|result| serde_json::from_value::<BlockTrace>(result).map_err(|e| e.to_string()))
+        {
+            Ok(block_trace) => Some(block_trace),
+            Err(e) => {
+                logger.warn(&format!("failed to trace transaction '{}': {e}", &args.target));
+                logger.warn("the call tree, value transfers, and storage writes will be missing.");
+                None
+            }
+        };
+        let traces = block_trace.as_ref().and_then(|trace| trace.trace.clone()).unwrap_or_default();
+        let storage_writes = block_trace
+            .as_ref()
+            .and_then(|trace| trace.state_diff.as_ref())
+            .map(storage_changes)
+            .unwrap_or_default();
+        (transaction, receipt, traces, storage_writes)
+    } else {
+        return Err(Error::ParseError(format!("invalid transaction hash '{}' .", &args.target)))
+    };
+
+    // decode the calldata of the transaction and each call it made, once per unique input
+    let mut decoded_calls = HashMap::new();
+    if !args.skip_resolving {
+        let mut inputs = vec![transaction.input.clone()];
+        inputs.extend(traces.iter().filter_map(|trace| match &trace.action {
+            ethers::types::Action::Call(call) => Some(call.input.clone()),
+            _ => None,
+        }));
+        for input in inputs {
+            if input.len() < 4 || decoded_calls.contains_key(&input) {
+                continue
+            }
+            if let Some(function) = decode_calldata(&input, &args.rpc_url).await {
+                decoded_calls.insert(input, function);
+            }
+        }
+        logger.debug(&format!("decoded {} unique calldata.", decoded_calls.len()));
+    }
+
+    let logs = decode_logs(&receipt.logs, args.skip_resolving).await;
+
+    let intrinsic_gas = intrinsic_gas(
+        &transaction.input,
+        transaction.to.is_none(),
+        transaction.access_list.as_ref(),
+    );
+    let effective_gas_price = receipt.effective_gas_price.or(transaction.gas_price);
+    let gas = GasBreakdown {
+        gas_limit: transaction.gas,
+        gas_used: receipt.gas_used,
+        effective_gas_price,
+        fee: receipt.gas_used.zip(effective_gas_price).map(|(used, price)| used * price),
+        intrinsic_gas,
+        calldata_gas: calldata_gas(&transaction.input),
+        execution_gas: receipt.gas_used.map(|used| used.saturating_sub(intrinsic_gas.into())),
+    };
+
+    Ok(InspectReport {
+        transaction_hash: transaction.hash,
+        block_number: transaction.block_number,
+        from: transaction.from,
+        to: transaction.to,
+        value: transaction.value,
+        success: receipt.status.map(|status| !status.is_zero()).unwrap_or(true),
+        decoded_input: decoded_calls.get(&transaction.input).cloned(),
+        logs,
+        call_tree: build_call_tree(&traces, &decoded_calls),
+        value_transfers: value_transfers(&traces),
+        storage_writes,
+        gas,
+    })
+}
+
+/// decodes calldata with the decode module, using its best match
+async fn decode_calldata(input: &Bytes, rpc_url: &str) -> Option<ResolvedFunction> {
+    let args = DecodeArgsBuilder::new()
+        .target(format!("0x{}", encode_hex(input.to_vec())))
+        .rpc_url(rpc_url.to_string())
+        .build()
+        .ok()?;
+    decode(args).await.ok()?.into_iter().next()
+}
+
+/// Decode each log with the signatures its first topic resolves to, resolving each signature
+/// once. Logs are left undecoded if `skip_resolving` is set.
+pub(crate) async fn decode_logs(logs: &[Log], skip_resolving: bool) -> Vec<DecodedLog> {
+    let mut decoded = Vec::new();
+    let mut resolved_events: HashMap<String, Option<Vec<ResolvedLog>>> = HashMap::new();
+    for log in logs {
+        decoded.push(match (skip_resolving, log.topics.first()) {
+            (false, Some(topic)) => {
+                let selector = encode_hex(topic.as_bytes().to_vec());
+                if !resolved_events.contains_key(&selector) {
+                    let events = ResolvedLog::resolve(&selector).await;
+                    resolved_events.insert(selector.clone(), events);
+                }
+                decode_log(log, resolved_events[&selector].as_deref().unwrap_or_default())
+            }
+            _ => decode_log(log, &[]),
+        });
+    }
+    decoded
+}
+
+/// Decodes a log with the first of the given event signatures which fits it. Indexed parameters
+/// are decoded from the log's topics, so this assumes they're declared before the event's other
+/// parameters, as they usually are.
+fn decode_log(log: &Log, events: &[ResolvedLog]) -> DecodedLog {
+    let mut encoded = log.topics.iter().skip(1).flat_map(|topic| topic.0).collect::<Vec<_>>();
+    encoded.extend(log.data.iter());
+
+    let decoded = events.iter().find_map(|event| {
+        let inputs = parse_function_parameters(&event.signature)?;
+        decode_abi(&inputs, &encoded).ok().map(|tokens| (event.clone(), tokens))
+    });
+
+    DecodedLog {
+        log_index: log.log_index,
+        address: log.address,
+        topics: log.topics.clone(),
+        data: log.data.clone(),
+        event: decoded.as_ref().map(|(event, _)| event.clone()).or_else(|| events.first().cloned()),
+        decoded_inputs: decoded.map(|(_, tokens)| tokens),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::{encode, Token},
+        types::{Bytes, Log, H160, H256, U256},
+    };
+    use heimdall_common::ether::signatures::ResolvedLog;
+
+    use crate::inspect::decode_log;
+
+    #[test]
+    fn test_decode_log() {
+        let transfer = ResolvedLog {
+            name: String::from("Transfer"),
+            signature: String::from("Transfer(address,address,uint256)"),
+            inputs: vec![String::from("address"), String::from("address"), String::from("uint256")],
+        };
+        let log = Log {
+            topics: vec![
+                H256::repeat_byte(0xdd),
+                H256::from(H160::repeat_byte(0xaa)),
+                H256::from(H160::repeat_byte(0xbb)),
+            ],
+            data: Bytes::from(encode(&[Token::Uint(U256::from(100))])),
+            ..Default::default()
+        };
+
+        let decoded = decode_log(&log, &[transfer]);
+        assert_eq!(decoded.event.unwrap().name, "Transfer");
+        assert_eq!(
+            decoded.decoded_inputs.unwrap(),
+            vec![
+                Token::Address(H160::repeat_byte(0xaa)),
+                Token::Address(H160::repeat_byte(0xbb)),
+                Token::Uint(U256::from(100)),
+            ]
+        );
+
+        // logs without a resolved signature are left undecoded
+        assert!(decode_log(&log, &[]).decoded_inputs.is_none());
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use ethers::{
+    abi::Token,
+    types::{
+        transaction::eip2930::AccessList, Action, Bytes, CallType, Diff, Res, StateDiff,
+        TransactionTrace, H160, H256, U256, U64,
+    },
+};
+use heimdall_common::ether::signatures::{ResolvedFunction, ResolvedLog};
+use serde::Serialize;
+
+/// A report on a mined transaction, covering what it called, emitted, transferred, and wrote.
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectReport {
+    pub transaction_hash: H256,
+    pub block_number: Option<U64>,
+    pub from: H160,
+    pub to: Option<H160>,
+    pub value: U256,
+    pub success: bool,
+    pub decoded_input: Option<ResolvedFunction>,
+    pub logs: Vec<DecodedLog>,
+
+    // the call tree, rooted at the transaction's top-level call, which is only available if the
+    // RPC provider supports `trace_replayTransaction`
+    pub call_tree: Option<CallNode>,
+    pub value_transfers: Vec<ValueTransfer>,
+    pub storage_writes: Vec<StorageChange>,
+    pub gas: GasBreakdown,
+}
+
+/// An event emitted by the transaction, decoded with the first resolved signature which fits its
+/// topics and data.
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedLog {
+    pub log_index: Option<U256>,
+    pub address: H160,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+    pub event: Option<ResolvedLog>,
+    pub decoded_inputs: Option<Vec<Token>>,
+}
+
+/// A call, create, or selfdestruct made during the transaction, with the calls it made in turn.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallNode {
+    // one of `call`, `staticcall`, `delegatecall`, `callcode`, `create`, or `selfdestruct`
+    pub call_type: String,
+    pub from: H160,
+    pub to: H160,
+    pub value: U256,
+    pub gas: U256,
+    pub gas_used: U256,
+    pub input: Bytes,
+    pub output: Bytes,
+    pub decoded_input: Option<ResolvedFunction>,
+    pub error: Option<String>,
+    pub calls: Vec<CallNode>,
+}
+
+/// A transfer of ether between accounts, made by a call, create, or selfdestruct.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValueTransfer {
+    // the position of the call in the call tree, such as `[0, 2]` for the third call made by the
+    // first call the transaction made
+    pub trace_address: Vec<usize>,
+    pub from: H160,
+    pub to: H160,
+    pub value: U256,
+}
+
+/// A write to an account's storage which changed the slot's value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageChange {
+    pub address: H160,
+    pub slot: H256,
+    pub old_value: H256,
+    pub new_value: H256,
+}
+
+/// How the transaction's gas was spent and paid for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GasBreakdown {
+    pub gas_limit: U256,
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+    pub fee: Option<U256>,
+
+    // the gas charged before execution: the base cost, calldata, contract creation, and access
+    // list
+    pub intrinsic_gas: u64,
+    pub calldata_gas: u64,
+
+    // the gas used by execution, after refunds
+    pub execution_gas: Option<U256>,
+}
+
+/// Get the gas charged for the given calldata, at 4 gas per zero byte and 16 per non-zero byte.
+pub fn calldata_gas(input: &[u8]) -> u64 {
+    input.iter().map(|byte| if *byte == 0 { 4 } else { 16 }).sum()
+}
+
+/// Get the intrinsic gas of a transaction with the given input, which is charged before it
+/// executes. Contract creations pay for each word of their init code, as of Shanghai.
+pub fn intrinsic_gas(input: &[u8], is_create: bool, access_list: Option<&AccessList>) -> u64 {
+    let mut gas = 21000 + calldata_gas(input);
+    if is_create {
+        gas += 32000 + 2 * (input.len() as u64).div_ceil(32);
+    }
+    if let Some(access_list) = access_list {
+        for item in &access_list.0 {
+            gas += 2400 + 1900 * item.storage_keys.len() as u64;
+        }
+    }
+    gas
+}
+
+/// Build the call tree from a transaction's traces, which are in the order they were made,
+/// decoding each call's input with the given decoded calldata.
+pub fn build_call_tree(
+    traces: &[TransactionTrace],
+    decoded: &HashMap<Bytes, ResolvedFunction>,
+) -> Option<CallNode> {
+    let mut root: Option<CallNode> = None;
+    for trace in traces {
+        let node = match call_node(trace, decoded) {
+            Some(node) => node,
+            None => continue,
+        };
+
+        // each call is a child of the call at its trace address, less the last index
+        let (_, parent_address) = match trace.trace_address.split_last() {
+            Some(split) => split,
+            None => {
+                root = Some(node);
+                continue
+            }
+        };
+        if let Some(root) = root.as_mut() {
+            insert_call(root, parent_address, node);
+        }
+    }
+    root
+}
+
+/// adds a call to the children of the call at the given trace address, relative to `parent`
+fn insert_call(parent: &mut CallNode, trace_address: &[usize], node: CallNode) {
+    match trace_address.split_first() {
+        Some((index, rest)) => {
+            if let Some(child) = parent.calls.get_mut(*index) {
+                insert_call(child, rest, node);
+            }
+        }
+        None => parent.calls.push(node),
+    }
+}
+
+/// builds the node of the call tree for a single trace, without its children
+fn call_node(
+    trace: &TransactionTrace,
+    decoded: &HashMap<Bytes, ResolvedFunction>,
+) -> Option<CallNode> {
+    let (gas_used, output, created) = match &trace.result {
+        Some(Res::Call(result)) => (result.gas_used, result.output.clone(), None),
+        Some(Res::Create(result)) => (result.gas_used, result.code.clone(), Some(result.address)),
+        _ => (U256::zero(), Bytes::new(), None),
+    };
+
+    let node = match &trace.action {
+        Action::Call(call) => CallNode {
+            call_type: match call.call_type {
+                CallType::StaticCall => "staticcall",
+                CallType::DelegateCall => "delegatecall",
+                CallType::CallCode => "callcode",
+                _ => "call",
+            }
+            .to_string(),
+            from: call.from,
+            to: call.to,
+            value: call.value,
+            gas: call.gas,
+            gas_used,
+            input: call.input.clone(),
+            output,
+            decoded_input: decoded.get(&call.input).cloned(),
+            error: trace.error.clone(),
+            calls: Vec::new(),
+        },
+        Action::Create(create) => CallNode {
+            call_type: String::from("create"),
+            from: create.from,
+            to: created.unwrap_or_default(),
+            value: create.value,
+            gas: create.gas,
+            gas_used,
+            input: create.init.clone(),
+            output,
+            decoded_input: None,
+            error: trace.error.clone(),
+            calls: Vec::new(),
+        },
+        Action::Suicide(suicide) => CallNode {
+            call_type: String::from("selfdestruct"),
+            from: suicide.address,
+            to: suicide.refund_address,
+            value: suicide.balance,
+            gas: U256::zero(),
+            gas_used,
+            input: Bytes::new(),
+            output,
+            decoded_input: None,
+            error: trace.error.clone(),
+            calls: Vec::new(),
+        },
+        Action::Reward(_) => return None,
+    };
+    Some(node)
+}
+
+/// Get the transfers of ether made by a transaction's traces. Delegate calls and call codes run in
+/// the caller's context, so their value isn't transferred, and failed calls are reverted.
+pub fn value_transfers(traces: &[TransactionTrace]) -> Vec<ValueTransfer> {
+    traces
+        .iter()
+        .filter(|trace| trace.error.is_none())
+        .filter_map(|trace| {
+            let (from, to, value) = match (&trace.action, &trace.result) {
+                (Action::Call(call), _)
+                    if !matches!(call.call_type, CallType::DelegateCall | CallType::CallCode) =>
+                {
+                    (call.from, call.to, call.value)
+                }
+                (Action::Create(create), Some(Res::Create(result))) => {
+                    (create.from, result.address, create.value)
+                }
+                (Action::Suicide(suicide), _) => {
+                    (suicide.address, suicide.refund_address, suicide.balance)
+                }
+                _ => return None,
+            };
+            match value.is_zero() {
+                true => None,
+                false => Some(ValueTransfer {
+                    trace_address: trace.trace_address.clone(),
+                    from,
+                    to,
+                    value,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Get the writes which changed a storage slot from a transaction's state diff, in order of
+/// account and slot.
+pub fn storage_changes(state_diff: &StateDiff) -> Vec<StorageChange> {
+    let mut changes = Vec::new();
+    for (address, diff) in &state_diff.0 {
+        for (slot, diff) in &diff.storage {
+            let (old_value, new_value) = match diff {
+                Diff::Born(value) => (H256::zero(), *value),
+                Diff::Changed(changed) => (changed.from, changed.to),
+                Diff::Died(value) => (*value, H256::zero()),
+                Diff::Same => continue,
+            };
+            changes.push(StorageChange { address: *address, slot: *slot, old_value, new_value });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use ethers::types::{
+        AccountDiff, Action, ActionType, Bytes, Call, CallResult, CallType, ChangedType, Diff, Res,
+        StateDiff, TransactionTrace, H160, H256, U256,
+    };
+
+    use crate::inspect::report::{
+        build_call_tree, calldata_gas, intrinsic_gas, storage_changes, value_transfers,
+    };
+
+    fn call(trace_address: Vec<usize>, call_type: CallType, value: u64) -> TransactionTrace {
+        TransactionTrace {
+            trace_address,
+            subtraces: 0,
+            action: Action::Call(Call {
+                from: H160::repeat_byte(0xaa),
+                to: H160::repeat_byte(0xbb),
+                value: U256::from(value),
+                gas: U256::from(100_000),
+                input: Bytes::new(),
+                call_type,
+            }),
+            action_type: ActionType::Call,
+            result: Some(Res::Call(CallResult {
+                gas_used: U256::from(21_000),
+                output: Bytes::new(),
+            })),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_build_call_tree() {
+        let traces = [
+            call(vec![], CallType::Call, 0),
+            call(vec![0], CallType::StaticCall, 0),
+            call(vec![1], CallType::DelegateCall, 0),
+            call(vec![1, 0], CallType::Call, 0),
+        ];
+
+        let tree = build_call_tree(&traces, &HashMap::new()).unwrap();
+        assert_eq!(tree.calls.len(), 2);
+        assert_eq!(tree.calls[0].call_type, "staticcall");
+        assert_eq!(tree.calls[1].call_type, "delegatecall");
+        assert_eq!(tree.calls[1].calls.len(), 1);
+        assert_eq!(tree.calls[1].calls[0].gas_used, U256::from(21_000));
+    }
+
+    #[test]
+    fn test_value_transfers() {
+        let mut failed = call(vec![2], CallType::Call, 5);
+        failed.error = Some(String::from("Reverted"));
+        let traces = [
+            call(vec![], CallType::Call, 10),
+            call(vec![0], CallType::DelegateCall, 10),
+            call(vec![1], CallType::Call, 0),
+            failed,
+        ];
+
+        let transfers = value_transfers(&traces);
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].value, U256::from(10));
+        assert!(transfers[0].trace_address.is_empty());
+    }
+
+    #[test]
+    fn test_storage_changes() {
+        let state_diff = StateDiff(BTreeMap::from([(
+            H160::repeat_byte(0xaa),
+            AccountDiff {
+                balance: Diff::Same,
+                nonce: Diff::Same,
+                code: Diff::Same,
+                storage: BTreeMap::from([
+                    (H256::zero(), Diff::Born(H256::from_low_u64_be(1))),
+                    (
+                        H256::from_low_u64_be(1),
+                        Diff::Changed(ChangedType {
+                            from: H256::from_low_u64_be(2),
+                            to: H256::from_low_u64_be(3),
+                        }),
+                    ),
+                    (H256::from_low_u64_be(2), Diff::Same),
+                ]),
+            },
+        )]));
+
+        let changes = storage_changes(&state_diff);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            (changes[0].old_value, changes[0].new_value),
+            (H256::zero(), H256::from_low_u64_be(1))
+        );
+        assert_eq!(changes[1].new_value, H256::from_low_u64_be(3));
+    }
+
+    #[test]
+    fn test_intrinsic_gas() {
+        assert_eq!(calldata_gas(&[0, 0, 1]), 24);
+        assert_eq!(intrinsic_gas(&[], false, None), 21000);
+        assert_eq!(intrinsic_gas(&[1; 33], true, None), 21000 + 33 * 16 + 32000 + 4);
+    }
+}
@@ -0,0 +1,408 @@
+use ethers::types::{
+    Action, ActionType, Bytes, Call, CallResult, CallType, Create, CreateResult, Log, Res, Suicide,
+    Transaction, TransactionReceipt, TransactionTrace, H160, H256, U256, U64,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    inspect::report::{CallNode, InspectReport, StorageChange},
+};
+
+/// A transaction trace in the format Tenderly exports, and returns from
+/// `tenderly_traceTransaction`. Only the fields heimdall reads or writes are kept.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TenderlyTrace {
+    pub transaction_hash: Option<H256>,
+    pub block_number: Option<U64>,
+    pub status: bool,
+    pub gas_used: Option<U256>,
+    pub effective_gas_price: Option<U256>,
+    pub logs: Vec<TenderlyLog>,
+
+    // every call made during the transaction, in the order they were made, starting with the
+    // transaction's top-level call
+    pub trace: Vec<TenderlyCall>,
+    pub state_changes: Vec<TenderlyStateChange>,
+}
+
+/// An event emitted during the transaction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TenderlyLog {
+    pub name: Option<String>,
+    pub raw: TenderlyRawLog,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenderlyRawLog {
+    pub address: H160,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+/// A call, create, or selfdestruct made during the transaction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TenderlyCall {
+    // one of `CALL`, `STATICCALL`, `DELEGATECALL`, `CALLCODE`, `CREATE`, `CREATE2`, or
+    // `SELFDESTRUCT`
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub from: H160,
+    pub to: H160,
+    pub gas: U256,
+    pub gas_used: U256,
+    pub value: Option<U256>,
+    pub input: Bytes,
+    pub output: Bytes,
+    pub subtraces: usize,
+    pub trace_address: Vec<usize>,
+    pub error: Option<String>,
+
+    // the name of the function called, if it was decoded
+    pub method: Option<String>,
+}
+
+/// The changes the transaction made to an account's storage.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenderlyStateChange {
+    pub address: H160,
+    pub storage: Vec<TenderlyStorageChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TenderlyStorageChange {
+    pub slot: H256,
+    pub previous_value: H256,
+    pub new_value: H256,
+}
+
+impl TenderlyTrace {
+    /// Read a trace exported from Tenderly.
+    pub fn from_file(path: &str) -> Result<TenderlyTrace, Error> {
+        let trace: TenderlyTrace = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if trace.trace.is_empty() {
+            return Err(Error::ParseError(format!("'{path}' isn't a Tenderly trace.")))
+        }
+        Ok(trace)
+    }
+
+    /// Get the transaction, as far as the trace describes it. Its top-level call holds its
+    /// sender, target, value, gas limit, and input.
+    pub fn transaction(&self) -> Transaction {
+        let call = self.trace.first().cloned().unwrap_or_default();
+        Transaction {
+            hash: self.transaction_hash.unwrap_or_default(),
+            block_number: self.block_number,
+            from: call.from,
+            to: (!call.call_type.starts_with("CREATE")).then_some(call.to),
+            value: call.value.unwrap_or_default(),
+            gas: call.gas,
+            input: call.input,
+            ..Default::default()
+        }
+    }
+
+    /// Get the transaction's receipt, as far as the trace describes it.
+    pub fn receipt(&self) -> TransactionReceipt {
+        TransactionReceipt {
+            transaction_hash: self.transaction_hash.unwrap_or_default(),
+            block_number: self.block_number,
+            status: Some(U64::from(self.status as u64)),
+            gas_used: self.gas_used,
+            effective_gas_price: self.effective_gas_price,
+            logs: self
+                .logs
+                .iter()
+                .map(|log| Log {
+                    address: log.raw.address,
+                    topics: log.raw.topics.clone(),
+                    data: log.raw.data.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Get the calls made during the transaction as the traces `trace_replayTransaction`
+    /// returns.
+    pub fn transaction_traces(&self) -> Vec<TransactionTrace> {
+        self.trace
+            .iter()
+            .map(|call| {
+                let value = call.value.unwrap_or_default();
+                let (action, action_type, result) = match call.call_type.as_str() {
+                    "CREATE" | "CREATE2" => (
+                        Action::Create(Create {
+                            from: call.from,
+                            value,
+                            gas: call.gas,
+                            init: call.input.clone(),
+                        }),
+                        ActionType::Create,
+                        Res::Create(CreateResult {
+                            gas_used: call.gas_used,
+                            code: call.output.clone(),
+                            address: call.to,
+                        }),
+                    ),
+                    "SELFDESTRUCT" => (
+                        Action::Suicide(Suicide {
+                            address: call.from,
+                            refund_address: call.to,
+                            balance: value,
+                        }),
+                        ActionType::Suicide,
+                        Res::None,
+                    ),
+                    call_type => (
+                        Action::Call(Call {
+                            from: call.from,
+                            to: call.to,
+                            value,
+                            gas: call.gas,
+                            input: call.input.clone(),
+                            call_type: match call_type {
+                                "STATICCALL" => CallType::StaticCall,
+                                "DELEGATECALL" => CallType::DelegateCall,
+                                "CALLCODE" => CallType::CallCode,
+                                _ => CallType::Call,
+                            },
+                        }),
+                        ActionType::Call,
+                        Res::Call(CallResult {
+                            gas_used: call.gas_used,
+                            output: call.output.clone(),
+                        }),
+                    ),
+                };
+                TransactionTrace {
+                    trace_address: call.trace_address.clone(),
+                    subtraces: call.subtraces,
+                    action,
+                    action_type,
+                    result: Some(result),
+                    error: call.error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Get the writes which changed a storage slot, in order of account and slot.
+    pub fn storage_changes(&self) -> Vec<StorageChange> {
+        let mut changes = self
+            .state_changes
+            .iter()
+            .flat_map(|change| {
+                change.storage.iter().filter(|slot| slot.previous_value != slot.new_value).map(
+                    |slot| StorageChange {
+                        address: change.address,
+                        slot: slot.slot,
+                        old_value: slot.previous_value,
+                        new_value: slot.new_value,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|change| (change.address, change.slot));
+        changes
+    }
+}
+
+/// Build a Tenderly trace from an inspect report, so heimdall's analysis can be shared with tools
+/// which read Tenderly's format.
+pub fn build_tenderly_trace(report: &InspectReport) -> TenderlyTrace {
+    let mut trace = Vec::new();
+    if let Some(call_tree) = &report.call_tree {
+        flatten_call_tree(call_tree, Vec::new(), &mut trace);
+    }
+
+    let mut state_changes: Vec<TenderlyStateChange> = Vec::new();
+    for change in &report.storage_writes {
+        let storage_change = TenderlyStorageChange {
+            slot: change.slot,
+            previous_value: change.old_value,
+            new_value: change.new_value,
+        };
+        match state_changes.iter_mut().find(|state_change| state_change.address == change.address) {
+            Some(state_change) => state_change.storage.push(storage_change),
+            None => state_changes.push(TenderlyStateChange {
+                address: change.address,
+                storage: vec![storage_change],
+            }),
+        }
+    }
+
+    TenderlyTrace {
+        transaction_hash: Some(report.transaction_hash),
+        block_number: report.block_number,
+        status: report.success,
+        gas_used: report.gas.gas_used,
+        effective_gas_price: report.gas.effective_gas_price,
+        logs: report
+            .logs
+            .iter()
+            .map(|log| TenderlyLog {
+                name: log.event.as_ref().map(|event| event.name.clone()),
+                raw: TenderlyRawLog {
+                    address: log.address,
+                    topics: log.topics.clone(),
+                    data: log.data.clone(),
+                },
+            })
+            .collect(),
+        trace,
+        state_changes,
+    }
+}
+
+/// appends a call and the calls it made to the trace, in the order they were made
+fn flatten_call_tree(node: &CallNode, trace_address: Vec<usize>, trace: &mut Vec<TenderlyCall>) {
+    trace.push(TenderlyCall {
+        call_type: match node.call_type.as_str() {
+            "selfdestruct" => String::from("SELFDESTRUCT"),
+            call_type => call_type.to_uppercase(),
+        },
+        from: node.from,
+        to: node.to,
+        gas: node.gas,
+        gas_used: node.gas_used,
+        value: Some(node.value),
+        input: node.input.clone(),
+        output: node.output.clone(),
+        subtraces: node.calls.len(),
+        trace_address: trace_address.clone(),
+        error: node.error.clone(),
+        method: node.decoded_input.as_ref().map(|function| function.name.clone()),
+    });
+    for (i, call) in node.calls.iter().enumerate() {
+        let mut child_address = trace_address.clone();
+        child_address.push(i);
+        flatten_call_tree(call, child_address, trace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::types::{H160, H256, U256};
+
+    use crate::inspect::{
+        report::{build_call_tree, value_transfers, GasBreakdown, InspectReport},
+        tenderly::{build_tenderly_trace, TenderlyTrace},
+    };
+
+    const TRACE: &str = r#"{
+        "status": true,
+        "gasUsed": "0x5208",
+        "blockNumber": "0x10",
+        "logs": [
+            {
+                "name": "Transfer",
+                "raw": {
+                    "address": "0x00000000000000000000000000000000000000bb",
+                    "topics": ["0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"],
+                    "data": "0x"
+                }
+            }
+        ],
+        "trace": [
+            {
+                "type": "CALL",
+                "from": "0x00000000000000000000000000000000000000aa",
+                "to": "0x00000000000000000000000000000000000000bb",
+                "gas": "0x10000",
+                "gasUsed": "0x5208",
+                "value": "0x5",
+                "input": "0xa9059cbb",
+                "output": "0x",
+                "subtraces": 1,
+                "traceAddress": []
+            },
+            {
+                "type": "DELEGATECALL",
+                "from": "0x00000000000000000000000000000000000000bb",
+                "to": "0x00000000000000000000000000000000000000cc",
+                "gas": "0x8000",
+                "gasUsed": "0x100",
+                "value": "0x0",
+                "input": "0x",
+                "output": "0x",
+                "subtraces": 0,
+                "traceAddress": [0]
+            }
+        ],
+        "stateChanges": [
+            {
+                "address": "0x00000000000000000000000000000000000000bb",
+                "storage": [
+                    {
+                        "slot": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "previousValue": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "newValue": "0x0000000000000000000000000000000000000000000000000000000000000001"
+                    }
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_import_tenderly_trace() {
+        let trace: TenderlyTrace = serde_json::from_str(TRACE).unwrap();
+
+        let transaction = trace.transaction();
+        assert_eq!(transaction.from, H160::from_low_u64_be(0xaa));
+        assert_eq!(transaction.to, Some(H160::from_low_u64_be(0xbb)));
+        assert_eq!(transaction.value, U256::from(5));
+        assert_eq!(trace.receipt().logs.len(), 1);
+
+        let traces = trace.transaction_traces();
+        let call_tree = build_call_tree(&traces, &HashMap::new()).unwrap();
+        assert_eq!(call_tree.calls.len(), 1);
+        assert_eq!(call_tree.calls[0].call_type, "delegatecall");
+        assert_eq!(value_transfers(&traces).len(), 1);
+
+        let changes = trace.storage_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].new_value, H256::from_low_u64_be(1));
+    }
+
+    #[test]
+    fn test_export_tenderly_trace() {
+        let trace: TenderlyTrace = serde_json::from_str(TRACE).unwrap();
+        let report = InspectReport {
+            transaction_hash: H256::repeat_byte(0x11),
+            block_number: trace.block_number,
+            from: H160::from_low_u64_be(0xaa),
+            to: Some(H160::from_low_u64_be(0xbb)),
+            value: U256::from(5),
+            success: true,
+            decoded_input: None,
+            logs: Vec::new(),
+            call_tree: build_call_tree(&trace.transaction_traces(), &HashMap::new()),
+            value_transfers: Vec::new(),
+            storage_writes: trace.storage_changes(),
+            gas: GasBreakdown {
+                gas_limit: U256::from(0x10000),
+                gas_used: trace.gas_used,
+                effective_gas_price: None,
+                fee: None,
+                intrinsic_gas: 21000,
+                calldata_gas: 64,
+                execution_gas: None,
+            },
+        };
+
+        // exporting the report round-trips the trace's calls and storage writes
+        let exported = build_tenderly_trace(&report);
+        assert_eq!(exported.trace, trace.trace);
+        assert_eq!(exported.state_changes, trace.state_changes);
+    }
+}
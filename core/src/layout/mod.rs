@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::utils::io::logging::Logger;
+use serde::Serialize;
+
+use crate::{
+    decompile::{decompile, out::storage::StorageVariable, DecompilerArgsBuilder},
+    error::Error,
+};
+
+// the name decompiled contracts are declared with
+const CONTRACT_NAME: &str = "DecompiledContract";
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Recover a contract's storage layout, in solc's storage layout format",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall layout <TARGET> [OPTIONS]"
+)]
+pub struct LayoutArgs {
+    /// The target to recover the storage layout of, either a file, bytecode, contract address,
+    /// or ENS name.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl LayoutArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A storage layout in the format solc emits with `--storage-layout`, so it can be read by tools
+/// such as slither and foundry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+    pub types: BTreeMap<String, StorageType>,
+}
+
+/// A variable in a storage layout, or a member of a struct.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntry {
+    pub ast_id: usize,
+    pub contract: String,
+    pub label: String,
+
+    // the byte offset of the variable within its slot
+    pub offset: u64,
+
+    // the slot the variable is declared at, in decimal
+    pub slot: String,
+    #[serde(rename = "type")]
+    pub type_id: String,
+
+    // how likely the variable's recovered type is to be correct, from 0 to 1. solc doesn't
+    // emit this, and struct members don't have one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+/// A type referenced by a storage layout, keyed by its type identifier.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageType {
+    pub encoding: String,
+    pub label: String,
+    pub number_of_bytes: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<StorageEntry>>,
+}
+
+/// The main entry point for the layout module. Will decompile the target to recover the storage
+/// variables it writes to, and format them as a solc storage layout.
+pub async fn layout(args: LayoutArgs) -> Result<StorageLayout, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let decompiler_args = DecompilerArgsBuilder::new()
+        .target(args.target.clone())
+        .verbose(args.verbose.clone())
+        .rpc_url(args.rpc_url.clone())
+        .skip_resolving(true)
+        .force_decompile(true)
+        .build()
+        .map_err(|e| Error::GenericError(e.to_string()))?;
+    let variables = decompile(decompiler_args).await?.storage_layout;
+    logger.debug(&format!("recovered {} storage variables.", variables.len()));
+
+    Ok(build_solc_layout(CONTRACT_NAME, &variables))
+}
+
+/// Format recovered storage variables as a solc storage layout. Mapping keys can't be recovered,
+/// so they're typed as `uint256`, and mappings of structs have a struct with a member at each
+/// offset written to.
+pub fn build_solc_layout(contract: &str, variables: &[StorageVariable]) -> StorageLayout {
+    let mut types = BTreeMap::new();
+    let storage = variables
+        .iter()
+        .enumerate()
+        .map(|(i, variable)| {
+            // mappings of structs write past each entry's slot
+            let mut value_type = elementary_type(&variable.value_type, &mut types);
+            if variable.depth > 0 && variable.offsets.iter().any(|offset| *offset > 0) {
+                value_type = struct_type(contract, variable, &value_type, &mut types);
+            }
+
+            let mut type_id = value_type;
+            for _ in 0..variable.depth {
+                type_id = mapping_type(&type_id, &mut types);
+            }
+
+            StorageEntry {
+                ast_id: i,
+                contract: contract.to_string(),
+                label: variable.name.clone(),
+                offset: 0,
+                slot: variable.slot.to_string(),
+                type_id,
+                confidence: Some(confidence(variable)),
+            }
+        })
+        .collect();
+
+    StorageLayout { storage, types }
+}
+
+/// how likely a variable's recovered type is to be correct. values typed by the operation which
+/// computed them are likely correct, while `uint256` is only a default, and mapping keys are
+/// always guessed
+fn confidence(variable: &StorageVariable) -> f64 {
+    let confidence = match variable.value_type.as_str() {
+        "uint256" => 0.5,
+        _ => 0.9,
+    };
+    (confidence - 0.1 * variable.depth as f64).max(0.1)
+}
+
+/// adds an elementary type to the layout's types, returning its identifier
+fn elementary_type(label: &str, types: &mut BTreeMap<String, StorageType>) -> String {
+    let number_of_bytes = match label {
+        "bool" => 1,
+        "address" => 20,
+        _ => 32,
+    };
+    let type_id = format!("t_{label}");
+    types.entry(type_id.clone()).or_insert_with(|| StorageType {
+        encoding: String::from("inplace"),
+        label: label.to_string(),
+        number_of_bytes: number_of_bytes.to_string(),
+        key: None,
+        value: None,
+        members: None,
+    });
+    type_id
+}
+
+/// adds a mapping from `uint256` to the given type to the layout's types, returning its
+/// identifier
+fn mapping_type(value_type: &str, types: &mut BTreeMap<String, StorageType>) -> String {
+    let key_type = elementary_type("uint256", types);
+    let type_id = format!("t_mapping({key_type},{value_type})");
+    let label = format!("mapping(uint256 => {})", types[value_type].label);
+    types.entry(type_id.clone()).or_insert_with(|| StorageType {
+        encoding: String::from("mapping"),
+        label,
+        number_of_bytes: String::from("32"),
+        key: Some(key_type),
+        value: Some(value_type.to_string()),
+        members: None,
+    });
+    type_id
+}
+
+/// adds a struct with a member at each of the variable's offsets to the layout's types,
+/// returning its identifier
+fn struct_type(
+    contract: &str,
+    variable: &StorageVariable,
+    member_type: &str,
+    types: &mut BTreeMap<String, StorageType>,
+) -> String {
+    let name = format!("{}_entry", variable.name);
+    let type_id = format!("t_struct({name})storage");
+    let members = variable
+        .offsets
+        .iter()
+        .enumerate()
+        .map(|(i, offset)| StorageEntry {
+            ast_id: i,
+            contract: contract.to_string(),
+            label: format!("member_{offset}"),
+            offset: 0,
+            slot: offset.to_string(),
+            type_id: member_type.to_string(),
+            confidence: None,
+        })
+        .collect();
+    let size = variable.offsets.iter().max().copied().unwrap_or_default() + 1;
+
+    types.insert(
+        type_id.clone(),
+        StorageType {
+            encoding: String::from("inplace"),
+            label: format!("struct {name}"),
+            number_of_bytes: (size * 32).to_string(),
+            key: None,
+            value: None,
+            members: Some(members),
+        },
+    );
+    type_id
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use ethers::types::U256;
+
+    use crate::{decompile::out::storage::StorageVariable, layout::build_solc_layout};
+
+    fn variable(
+        name: &str,
+        slot: u64,
+        depth: usize,
+        offsets: &[u64],
+        value_type: &str,
+    ) -> StorageVariable {
+        StorageVariable {
+            name: name.to_string(),
+            slot: U256::from(slot),
+            depth,
+            offsets: offsets.iter().copied().collect::<BTreeSet<_>>(),
+            value_type: value_type.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_build_solc_layout() {
+        let layout = build_solc_layout(
+            "DecompiledContract",
+            &[
+                variable("stor_a", 0, 0, &[0], "address"),
+                variable("stor_map_b", 1, 2, &[0], "uint256"),
+                variable("stor_map_c", 2, 1, &[0, 2], "uint256"),
+            ],
+        );
+
+        assert_eq!(layout.storage[0].type_id, "t_address");
+        assert_eq!(layout.storage[0].confidence, Some(0.9));
+        assert_eq!(layout.storage[1].slot, "1");
+        assert_eq!(
+            layout.storage[1].type_id,
+            "t_mapping(t_uint256,t_mapping(t_uint256,t_uint256))"
+        );
+        assert_eq!(
+            layout.types["t_mapping(t_uint256,t_mapping(t_uint256,t_uint256))"].label,
+            "mapping(uint256 => mapping(uint256 => uint256))"
+        );
+        assert_eq!(
+            layout.storage[2].type_id,
+            "t_mapping(t_uint256,t_struct(stor_map_c_entry)storage)"
+        );
+
+        let entry = &layout.types["t_struct(stor_map_c_entry)storage"];
+        assert_eq!(entry.number_of_bytes, "96");
+        assert_eq!(
+            entry
+                .members
+                .as_ref()
+                .unwrap()
+                .iter()
+                .map(|member| member.slot.as_str())
+                .collect::<Vec<_>>(),
+            ["0", "2"]
+        );
+    }
+}
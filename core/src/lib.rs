@@ -1,7 +1,31 @@
+pub mod analysis;
+pub mod assemble;
+pub mod audit;
+pub mod callgraph;
 pub mod cfg;
+pub mod collisions;
+pub mod conformance;
+pub mod debug;
 pub mod decode;
 pub mod decompile;
+pub mod dependencies;
+pub mod diff;
 pub mod disassemble;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod dump;
+pub mod encode;
 pub mod error;
+pub mod exec;
+pub mod inspect;
+pub mod layout;
+pub mod proxy;
+pub mod scan;
+pub mod selectors;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod serve;
+pub mod simulate;
 pub mod snapshot;
+mod utils;
+pub mod verify;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
@@ -0,0 +1,35 @@
+// the EIP-1967 storage slots, which are the keccak256 hash of their label, minus one
+pub const IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+pub const ADMIN_SLOT: &str = "0xb53127684a568b3173ae13b9f8a6016e243e63b6e8ee1178d6a717850b5d6103";
+pub const BEACON_SLOT: &str = "0xa3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50";
+
+// the EIP-1822 storage slot, which is the keccak256 hash of "PROXIABLE"
+pub const PROXIABLE_SLOT: &str =
+    "0xc5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7";
+
+// the events EIP-1967 proxies emit when they're upgraded
+pub const UPGRADED_EVENT: &str =
+    "0xbc7cd75a20ee27fd9adebab32041f755214dbc6bffa90cc0225b39da2e5c2d3b";
+pub const BEACON_UPGRADED_EVENT: &str =
+    "0x1cf3b03a6cf19fa2baba4df148e9dcabedea7f8a5c07840e207e5c089be95d3e";
+
+// the runtime code of EIP-1167 minimal proxies, before and after the implementation address
+pub const MINIMAL_PROXY_PREFIX: &str = "363d3d373d3d3d363d73";
+pub const MINIMAL_PROXY_SUFFIX: &str = "5af43d82803e903d91602b57fd5bf3";
+
+// `implementation()`, which beacons expose
+pub const IMPLEMENTATION_SELECTOR: &str = "5c60da1b";
+
+// `facetAddresses()`, which EIP-2535 diamonds expose through their loupe
+pub const FACET_ADDRESSES_SELECTOR: &str = "52ef6b2c";
+
+// `facetAddress(bytes4)`, which returns the facet a diamond delegates a selector to
+pub const FACET_ADDRESS_SELECTOR: &str = "cdffacc6";
+
+// `proxiableUUID()`, which UUPS implementations expose
+pub const PROXIABLE_UUID_SELECTOR: &str = "52d1902d";
+
+// `upgradeTo(address)` and `upgradeToAndCall(address,bytes)`, which transparent proxies handle
+// themselves, rather than delegating
+pub const UPGRADE_SELECTORS: [&str; 2] = ["3659cfe6", "4f1ef286"];
@@ -0,0 +1,419 @@
+pub mod constants;
+
+use std::str::FromStr;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::{
+    abi::{decode as decode_abi, ParamType, Token},
+    types::{H160, H256, U256},
+};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        history::{get_contract_creation, get_event_logs},
+        rpc::{batch_request, get_code, get_storage_batch},
+    },
+    utils::{io::logging::Logger, strings::decode_hex},
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::Error,
+    proxy::constants::{
+        ADMIN_SLOT, BEACON_SLOT, BEACON_UPGRADED_EVENT, FACET_ADDRESSES_SELECTOR,
+        IMPLEMENTATION_SELECTOR, IMPLEMENTATION_SLOT, MINIMAL_PROXY_PREFIX, MINIMAL_PROXY_SUFFIX,
+        PROXIABLE_SLOT, PROXIABLE_UUID_SELECTOR, UPGRADED_EVENT, UPGRADE_SELECTORS,
+    },
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Detect whether a contract is a proxy, and report its pattern, implementation, and upgrades",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall proxy <TARGET> [OPTIONS]"
+)]
+pub struct ProxyArgs {
+    /// The address of the contract to analyze.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to read the target's code and storage from. Separate multiple providers
+    /// with commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip searching the target's event history for upgrades, which requires
+    /// `eth_getLogs` over every block since its creation.
+    #[clap(long = "skip-history")]
+    pub skip_history: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl ProxyArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_history: Some(false),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// The pattern a proxy follows to delegate calls to its implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyPattern {
+    // an EIP-1167 minimal proxy, whose implementation is part of its code
+    Minimal,
+
+    // an EIP-1967 proxy whose upgrades are handled by the proxy itself, for its admin
+    Transparent,
+
+    // an EIP-1967 or EIP-1822 proxy whose upgrades are handled by its implementation
+    Uups,
+
+    // an EIP-1967 proxy whose implementation is read from a beacon
+    Beacon,
+
+    // an EIP-2535 diamond, which delegates each function to one of its facets
+    Diamond,
+
+    // an EIP-1967 proxy whose upgrade mechanism couldn't be determined
+    Eip1967,
+}
+
+/// An upgrade of the proxy's implementation or beacon, found in its event history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Upgrade {
+    pub block_number: u128,
+    pub transaction_hash: String,
+
+    // either `Upgraded` or `BeaconUpgraded`
+    pub event: String,
+    pub address: H160,
+}
+
+/// A report on whether a contract is a proxy, and if so, how it delegates and has been upgraded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProxyReport {
+    pub address: String,
+    pub is_proxy: bool,
+    pub pattern: Option<ProxyPattern>,
+    pub implementation: Option<H160>,
+    pub admin: Option<H160>,
+    pub beacon: Option<H160>,
+    pub facets: Vec<H160>,
+    pub upgrades: Vec<Upgrade>,
+}
+
+/// The main entry point for the proxy module. Will read the target's code and the standard proxy
+/// storage slots, determine which proxy pattern it follows, if any, and search its event history
+/// for upgrades.
+pub async fn proxy(args: ProxyArgs) -> Result<ProxyReport, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    if !ADDRESS_REGEX.is_match(&args.target)? {
+        return Err(Error::ParseError(format!("invalid target address '{}' .", &args.target)))
+    }
+
+    let bytecode =
+        get_code(&args.target, &args.rpc_url).await.map_err(Error::from_rpc)?.replacen("0x", "", 1);
+
+    let slots = [IMPLEMENTATION_SLOT, ADMIN_SLOT, BEACON_SLOT, PROXIABLE_SLOT]
+        .iter()
+        .map(|slot| U256::from_str(slot))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::ParseError(e.to_string()))?;
+    let values = get_storage_batch(&args.target, &slots, None, &args.rpc_url)
+        .await
+        .map_err(|e| Error::RpcError(format!("failed to read proxy slots: {e}")))?;
+    let [implementation_slot, admin, beacon, proxiable_slot] =
+        [values[0], values[1], values[2], values[3]].map(slot_address);
+    logger.debug(&format!(
+        "read implementation {implementation_slot:?}, admin {admin:?}, and beacon {beacon:?} from the EIP-1967 slots."
+    ));
+
+    // beacons hold the implementation, rather than the proxy
+    let beacon_implementation = match beacon {
+        Some(beacon) => call(&args.rpc_url, beacon, IMPLEMENTATION_SELECTOR)
+            .await
+            .and_then(|output| decode_abi(&[ParamType::Address], &output).ok())
+            .and_then(|tokens| tokens.into_iter().next()?.into_address()),
+        None => None,
+    };
+
+    // a diamond's loupe is one of its facets, rather than part of its code, so only contracts
+    // which aren't another kind of proxy are asked for their facets
+    let is_other_proxy = minimal_proxy_implementation(&bytecode).is_some() ||
+        [implementation_slot, beacon, proxiable_slot].iter().any(Option::is_some);
+    let facets = match !is_other_proxy {
+        true => call(&args.rpc_url, target_address(&args.target)?, FACET_ADDRESSES_SELECTOR)
+            .await
+            .and_then(|output| {
+                decode_abi(&[ParamType::Array(Box::new(ParamType::Address))], &output).ok()
+            })
+            .and_then(|tokens| tokens.into_iter().next()?.into_array())
+            .map(|facets| facets.into_iter().filter_map(Token::into_address).collect())
+            .unwrap_or_default(),
+        false => Vec::new(),
+    };
+
+    let implementation = minimal_proxy_implementation(&bytecode)
+        .or(implementation_slot)
+        .or(beacon_implementation)
+        .or(proxiable_slot);
+    let implementation_code = match implementation {
+        Some(implementation) => get_code(&format!("{implementation:?}"), &args.rpc_url)
+            .await
+            .map(|code| code.replacen("0x", "", 1))
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+    let pattern = classify(&ProxyState {
+        bytecode: &bytecode,
+        implementation_slot,
+        admin,
+        beacon,
+        proxiable_slot,
+        facets: &facets,
+        implementation_code: &implementation_code,
+    });
+    logger.info(&format!("'{}' follows the {pattern:?} proxy pattern.", &args.target));
+
+    // minimal proxies and diamonds don't emit the EIP-1967 events
+    let mut upgrades = Vec::new();
+    if !args.skip_history &&
+        !matches!(pattern, None | Some(ProxyPattern::Minimal) | Some(ProxyPattern::Diamond))
+    {
+        let from_block = match get_contract_creation(&args.rpc_url, &args.target).await {
+            Ok((block_number, _)) => block_number,
+            Err(e) => {
+                logger.warn(&format!("failed to find the target's creation block: {e}"));
+                logger.warn("its event history will be searched from the genesis block.");
+                0
+            }
+        };
+        upgrades = get_event_logs(
+            &args.rpc_url,
+            &args.target,
+            &[UPGRADED_EVENT, BEACON_UPGRADED_EVENT],
+            (&from_block, &u128::MAX),
+        )
+        .await
+        .map_err(|e| Error::RpcError(format!("failed to fetch upgrade history: {e}")))?
+        .into_iter()
+        .filter_map(|log| {
+            Some(Upgrade {
+                block_number: log.block_number,
+                transaction_hash: log.transaction_hash,
+                event: match log.topics.first()?.as_str() {
+                    UPGRADED_EVENT => String::from("Upgraded"),
+                    _ => String::from("BeaconUpgraded"),
+                },
+                address: slot_address(H256::from_str(log.topics.get(1)?).ok()?)?,
+            })
+        })
+        .collect();
+        logger.debug(&format!("found {} upgrades.", upgrades.len()));
+    }
+
+    Ok(ProxyReport {
+        address: args.target.to_lowercase(),
+        is_proxy: pattern.is_some(),
+        pattern,
+        implementation: match pattern {
+            Some(ProxyPattern::Diamond) => None,
+            _ => implementation,
+        },
+        admin,
+        beacon,
+        facets,
+        upgrades,
+    })
+}
+
+/// What was read from a contract's code and storage, from which its proxy pattern is determined.
+struct ProxyState<'a> {
+    bytecode: &'a str,
+    implementation_slot: Option<H160>,
+    admin: Option<H160>,
+    beacon: Option<H160>,
+    proxiable_slot: Option<H160>,
+    facets: &'a [H160],
+    implementation_code: &'a str,
+}
+
+/// determines which proxy pattern a contract follows, if any
+fn classify(state: &ProxyState) -> Option<ProxyPattern> {
+    let has_selector = |code: &str, selector: &str| code.contains(&format!("63{selector}"));
+
+    if minimal_proxy_implementation(state.bytecode).is_some() {
+        return Some(ProxyPattern::Minimal)
+    }
+    if !state.facets.is_empty() {
+        return Some(ProxyPattern::Diamond)
+    }
+    if state.beacon.is_some() {
+        return Some(ProxyPattern::Beacon)
+    }
+    if state.implementation_slot.is_some() {
+        if state.admin.is_some() ||
+            UPGRADE_SELECTORS.iter().any(|selector| has_selector(state.bytecode, selector))
+        {
+            return Some(ProxyPattern::Transparent)
+        }
+        return match has_selector(state.implementation_code, PROXIABLE_UUID_SELECTOR) {
+            true => Some(ProxyPattern::Uups),
+            false => Some(ProxyPattern::Eip1967),
+        }
+    }
+    if state.proxiable_slot.is_some() {
+        return Some(ProxyPattern::Uups)
+    }
+    None
+}
+
+/// gets the implementation of an EIP-1167 minimal proxy from its runtime code
+fn minimal_proxy_implementation(bytecode: &str) -> Option<H160> {
+    let implementation = bytecode.strip_prefix(MINIMAL_PROXY_PREFIX)?;
+    match implementation.get(40..)? == MINIMAL_PROXY_SUFFIX {
+        true => Some(H160::from_slice(&decode_hex(&implementation[..40]).ok()?)),
+        false => None,
+    }
+}
+
+/// gets the address stored in a slot, or `None` if the slot is empty
+fn slot_address(value: H256) -> Option<H160> {
+    match value.is_zero() {
+        true => None,
+        false => Some(H160::from(value)),
+    }
+}
+
+/// parses the target as an address
+pub(crate) fn target_address(target: &str) -> Result<H160, Error> {
+    H160::from_str(target)
+        .map_err(|_| Error::ParseError(format!("failed to parse target '{target}' .")))
+}
+
+/// calls a function with `eth_call`, where `calldata` is its hex-encoded selector and
+/// arguments, returning its output if it succeeded
+pub(crate) async fn call(rpc_url: &str, to: H160, calldata: &str) -> Option<Vec<u8>> {
+    let output = batch_request(
+        rpc_url,
+        &[("eth_call", json!([{ "to": to, "data": format!("0x{calldata}") }, "latest"]))],
+    )
+    .await
+    .ok()?
+    .pop()?
+    .ok()?;
+    decode_hex(output.as_str()?.trim_start_matches("0x")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        types::{H160, H256, U256},
+        utils::keccak256,
+    };
+
+    use crate::proxy::{
+        classify, constants::*, minimal_proxy_implementation, ProxyPattern, ProxyState,
+    };
+
+    fn state<'a>(bytecode: &'a str, implementation_code: &'a str) -> ProxyState<'a> {
+        ProxyState {
+            bytecode,
+            implementation_slot: None,
+            admin: None,
+            beacon: None,
+            proxiable_slot: None,
+            facets: &[],
+            implementation_code,
+        }
+    }
+
+    #[test]
+    fn test_constants() {
+        let eip1967_slot = |label: &str| format!("{:#066x}", U256::from(keccak256(label)) - 1);
+        assert_eq!(IMPLEMENTATION_SLOT, eip1967_slot("eip1967.proxy.implementation"));
+        assert_eq!(ADMIN_SLOT, eip1967_slot("eip1967.proxy.admin"));
+        assert_eq!(BEACON_SLOT, eip1967_slot("eip1967.proxy.beacon"));
+        assert_eq!(PROXIABLE_SLOT, format!("{:?}", H256::from(keccak256("PROXIABLE"))));
+        assert_eq!(UPGRADED_EVENT, format!("{:?}", H256::from(keccak256("Upgraded(address)"))));
+        assert_eq!(
+            BEACON_UPGRADED_EVENT,
+            format!("{:?}", H256::from(keccak256("BeaconUpgraded(address)")))
+        );
+    }
+
+    #[test]
+    fn test_minimal_proxy_implementation() {
+        let bytecode = format!(
+            "{MINIMAL_PROXY_PREFIX}{}{MINIMAL_PROXY_SUFFIX}",
+            "bebebebebebebebebebebebebebebebebebebebe"
+        );
+        assert_eq!(minimal_proxy_implementation(&bytecode), Some(H160::repeat_byte(0xbe)));
+        assert_eq!(minimal_proxy_implementation("6080604052"), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let implementation = Some(H160::repeat_byte(0xbe));
+
+        assert_eq!(classify(&state("6080604052", "")), None);
+        assert_eq!(
+            classify(&ProxyState {
+                admin: Some(H160::repeat_byte(0xad)),
+                implementation_slot: implementation,
+                ..state("", "")
+            }),
+            Some(ProxyPattern::Transparent)
+        );
+        assert_eq!(
+            classify(&ProxyState {
+                implementation_slot: implementation,
+                ..state("", &format!("63{PROXIABLE_UUID_SELECTOR}"))
+            }),
+            Some(ProxyPattern::Uups)
+        );
+        assert_eq!(
+            classify(&ProxyState { implementation_slot: implementation, ..state("", "") }),
+            Some(ProxyPattern::Eip1967)
+        );
+        assert_eq!(
+            classify(&ProxyState { beacon: Some(H160::repeat_byte(0xbc)), ..state("", "") }),
+            Some(ProxyPattern::Beacon)
+        );
+        assert_eq!(
+            classify(&ProxyState { facets: &[H160::repeat_byte(0xfa)], ..state("", "") }),
+            Some(ProxyPattern::Diamond)
+        );
+    }
+}
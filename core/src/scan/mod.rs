@@ -0,0 +1,336 @@
+use std::path::Path;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        history::{get_contract_creations, ContractCreation},
+        rpc::get_code_batch,
+    },
+    utils::{
+        io::{file::read_file, logging::Logger},
+        strings::decode_hex,
+    },
+};
+use serde::Serialize;
+
+use crate::{diff::diff_bytecode, error::Error, utils::get_contract_bytecode};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Scan contracts created over a block range, or a list of addresses, for matching bytecode",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall scan [OPTIONS]"
+)]
+pub struct ScanArgs {
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to find contract creations and fetch bytecode with. Scanning a block
+    /// range requires `trace_block`. Separate multiple providers with commas to fail over
+    /// between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The block to start scanning for contract creations from.
+    #[clap(long = "from-block", short = 'f', default_value = "0")]
+    pub from_block: u128,
+
+    /// The block to stop scanning for contract creations at.
+    #[clap(long = "to-block", short = 't', default_value = "9999999999")]
+    pub to_block: u128,
+
+    /// The contracts to scan, rather than those created within the block range, either separated
+    /// by commas, or in a file with one address per line.
+    #[clap(long = "addresses", default_value = "", hide_default_value = true)]
+    pub addresses: String,
+
+    /// Flag contracts whose bytecode contains this hex pattern, where `??` matches any byte.
+    #[clap(long = "pattern", default_value = "", hide_default_value = true)]
+    pub pattern: String,
+
+    /// Flag contracts which contain every one of these function selectors, separated by commas.
+    #[clap(long = "selectors", default_value = "", hide_default_value = true)]
+    pub selectors: String,
+
+    /// Flag contracts similar to this reference contract, either a contract address, bytecode,
+    /// or file.
+    #[clap(long = "reference", default_value = "", hide_default_value = true)]
+    pub reference: String,
+
+    /// How similar contracts must be to the reference contract to be flagged, from 0 to 1.
+    #[clap(long = "threshold", default_value = "0.9")]
+    pub threshold: f64,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl ScanArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            from_block: Some(0),
+            to_block: Some(9999999999),
+            addresses: Some(String::new()),
+            pattern: Some(String::new()),
+            selectors: Some(String::new()),
+            reference: Some(String::new()),
+            threshold: Some(0.9),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// What contracts must match to be flagged. Contracts are only flagged if they match every
+/// criterion which is set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanCriteria {
+    // a pattern of bytes, where `None` matches any byte
+    pub pattern: Option<Vec<Option<u8>>>,
+
+    // function selectors, without the 0x prefix
+    pub selectors: Vec<String>,
+    pub reference: Option<Vec<u8>>,
+    pub threshold: f64,
+}
+
+/// A contract which matched the scan's criteria.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScanMatch {
+    pub address: String,
+
+    // the block and transaction which created the contract, if it was found by scanning a block
+    // range
+    pub block_number: Option<u128>,
+    pub transaction_hash: Option<String>,
+
+    // the offset of the first match of the pattern, if one was given
+    pub pattern_offset: Option<usize>,
+
+    // the similarity to the reference contract, if one was given
+    pub similarity: Option<f64>,
+}
+
+/// The main entry point for the scan module. Will find the contracts created within the block
+/// range, or use the given addresses, and flag each contract whose bytecode matches every given
+/// criterion.
+pub async fn scan(args: ScanArgs) -> Result<Vec<ScanMatch>, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let criteria = ScanCriteria {
+        pattern: match args.pattern.is_empty() {
+            true => None,
+            false => Some(parse_pattern(&args.pattern)?),
+        },
+        selectors: args
+            .selectors
+            .split(',')
+            .map(|selector| selector.trim().trim_start_matches("0x").to_lowercase())
+            .filter(|selector| !selector.is_empty())
+            .collect(),
+        reference: match args.reference.is_empty() {
+            true => None,
+            false => Some(decode_hex(
+                &get_contract_bytecode(&args.reference, &args.rpc_url, &logger)
+                    .await?
+                    .replacen("0x", "", 1),
+            )?),
+        },
+        threshold: args.threshold,
+    };
+    if criteria.pattern.is_none() && criteria.selectors.is_empty() && criteria.reference.is_none() {
+        return Err(Error::GenericError(String::from(
+            "at least one of '--pattern', '--selectors', or '--reference' is required.",
+        )))
+    }
+
+    // scan the given addresses, or every contract created within the block range
+    let creations = match args.addresses.is_empty() {
+        true => {
+            logger.info(&format!(
+                "searching blocks {} to {} for contract creations.",
+                args.from_block, args.to_block
+            ));
+            get_contract_creations(&args.rpc_url, (&args.from_block, &args.to_block))
+                .await
+                .map_err(|e| Error::RpcError(format!("failed to find contract creations: {e}")))?
+        }
+        false => parse_addresses(&args.addresses)?
+            .into_iter()
+            .map(|address| ContractCreation {
+                block_number: 0,
+                transaction_hash: String::new(),
+                address,
+            })
+            .collect(),
+    };
+    logger.info(&format!("scanning {} contracts.", creations.len()));
+
+    let addresses = creations.iter().map(|creation| creation.address.clone()).collect::<Vec<_>>();
+    let bytecode = get_code_batch(&addresses, &args.rpc_url)
+        .await
+        .map_err(|e| Error::RpcError(format!("failed to fetch bytecode: {e}")))?;
+
+    let mut matches = Vec::new();
+    for (creation, bytecode) in creations.into_iter().zip(bytecode) {
+        let bytecode = match decode_hex(&bytecode) {
+            Ok(bytecode) if !bytecode.is_empty() => bytecode,
+            _ => continue,
+        };
+        if let Some((pattern_offset, similarity)) = match_criteria(&bytecode, &criteria) {
+            logger.debug(&format!("'{}' matches.", creation.address));
+            matches.push(ScanMatch {
+                address: creation.address,
+                block_number: (!creation.transaction_hash.is_empty())
+                    .then_some(creation.block_number),
+                transaction_hash: (!creation.transaction_hash.is_empty())
+                    .then_some(creation.transaction_hash),
+                pattern_offset,
+                similarity,
+            });
+        }
+    }
+    logger.info(&format!("{} contracts matched.", matches.len()));
+
+    Ok(matches)
+}
+
+/// Check whether bytecode matches every criterion which is set, returning the offset of the
+/// pattern's first match, and the similarity to the reference contract, if it does.
+pub fn match_criteria(
+    bytecode: &[u8],
+    criteria: &ScanCriteria,
+) -> Option<(Option<usize>, Option<f64>)> {
+    let pattern_offset = match &criteria.pattern {
+        Some(pattern) => Some(find_pattern(bytecode, pattern)?),
+        None => None,
+    };
+
+    // selectors are matched by the PUSH4 instructions which compare them against calldata
+    for selector in &criteria.selectors {
+        let mut push = vec![0x63];
+        push.extend(decode_hex(selector).ok()?);
+        if !bytecode.windows(push.len()).any(|window| window == push) {
+            return None
+        }
+    }
+
+    let similarity = match &criteria.reference {
+        Some(reference) => {
+            let similarity = diff_bytecode(reference, bytecode).similarity;
+            if similarity < criteria.threshold {
+                return None
+            }
+            Some(similarity)
+        }
+        None => None,
+    };
+
+    Some((pattern_offset, similarity))
+}
+
+/// parses a hex pattern, where `??` matches any byte
+fn parse_pattern(pattern: &str) -> Result<Vec<Option<u8>>, Error> {
+    let pattern = pattern.trim_start_matches("0x");
+    if pattern.is_empty() || !pattern.len().is_multiple_of(2) {
+        return Err(Error::ParseError(format!("invalid pattern '{pattern}' .")))
+    }
+    (0..pattern.len())
+        .step_by(2)
+        .map(|i| match &pattern[i..i + 2] {
+            "??" => Ok(None),
+            byte => u8::from_str_radix(byte, 16)
+                .map(Some)
+                .map_err(|_| Error::ParseError(format!("invalid pattern '{pattern}' ."))),
+        })
+        .collect()
+}
+
+/// finds the offset of the first match of a pattern in bytecode
+fn find_pattern(bytecode: &[u8], pattern: &[Option<u8>]) -> Option<usize> {
+    bytecode.windows(pattern.len()).position(|window| {
+        window.iter().zip(pattern).all(|(byte, expected)| expected.is_none_or(|b| b == *byte))
+    })
+}
+
+/// parses addresses separated by commas, or read from a file with one per line
+fn parse_addresses(addresses: &str) -> Result<Vec<String>, Error> {
+    let addresses = match Path::new(addresses).is_file() {
+        true => read_file(addresses)?,
+        false => addresses.to_string(),
+    };
+    Ok(addresses
+        .split([',', '\n'])
+        .map(|address| address.trim().to_lowercase())
+        .filter(|address| !address.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scan::{find_pattern, match_criteria, parse_addresses, parse_pattern, ScanCriteria};
+
+    #[test]
+    fn test_find_pattern() {
+        let pattern = parse_pattern("0x63??aabb").unwrap();
+        assert_eq!(pattern, [Some(0x63), None, Some(0xaa), Some(0xbb)]);
+        assert_eq!(find_pattern(&[0x00, 0x63, 0x01, 0xaa, 0xbb], &pattern), Some(1));
+        assert_eq!(find_pattern(&[0x63, 0x01, 0xaa], &pattern), None);
+
+        assert!(parse_pattern("abc").is_err());
+        assert!(parse_pattern("zz").is_err());
+    }
+
+    #[test]
+    fn test_match_criteria() {
+        // PUSH4 0xa9059cbb, PUSH4 0x70a08231, STOP
+        let bytecode = [0x63, 0xa9, 0x05, 0x9c, 0xbb, 0x63, 0x70, 0xa0, 0x82, 0x31, 0x00];
+
+        let criteria = ScanCriteria {
+            selectors: vec![String::from("a9059cbb"), String::from("70a08231")],
+            ..Default::default()
+        };
+        assert_eq!(match_criteria(&bytecode, &criteria), Some((None, None)));
+
+        let criteria = ScanCriteria {
+            pattern: Some(parse_pattern("6370a0").unwrap()),
+            selectors: vec![String::from("18160ddd")],
+            ..Default::default()
+        };
+        assert_eq!(match_criteria(&bytecode, &criteria), None);
+
+        let criteria = ScanCriteria {
+            reference: Some(bytecode.to_vec()),
+            threshold: 0.9,
+            ..Default::default()
+        };
+        assert_eq!(match_criteria(&bytecode, &criteria), Some((None, Some(1.0))));
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        assert_eq!(
+            parse_addresses("0xAA, 0xbb,,").unwrap(),
+            [String::from("0xaa"), String::from("0xbb")]
+        );
+    }
+}
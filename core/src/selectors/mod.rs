@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::{
+        eof::is_eof,
+        evm::core::{hardfork::Hardfork, vm::VM},
+        selectors::{find_function_selectors, resolve_selectors},
+        signatures::{score_signature, ResolvedFunction},
+    },
+    utils::{
+        io::{logging::*, table::TableFormat},
+        strings::decode_hex,
+    },
+};
+use serde::Serialize;
+
+use crate::{
+    disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
+    utils::get_contract_bytecode,
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Extract the function selectors from EVM bytecode",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall selectors <TARGET> [OPTIONS]"
+)]
+pub struct SelectorsArgs {
+    /// The target to extract selectors from, either a file, bytecode, contract address, or `-`
+    /// to read bytecode from stdin.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to skip resolving function selectors to their signatures.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// Whether to output the selectors as JSON, set by the global `--json` flag.
+    #[clap(skip)]
+    pub json: bool,
+
+    /// The format to write the selectors in, either `text` for each selector and its best
+    /// signature on a line, or `tsv` or `csv` for a table with the selector, signature, parameter
+    /// types, and entry point as columns.
+    #[clap(long = "format", default_value = "text")]
+    pub format: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl SelectorsArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            skip_resolving: Some(false),
+            json: Some(false),
+            format: Some(String::from("text")),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// A function selector found in the target's dispatcher.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionSelector {
+    // the 4-byte selector, with a 0x prefix
+    pub selector: String,
+
+    // the program counter of the function's entry point
+    pub entry_point: u128,
+
+    // the resolved signatures of the selector, best match first
+    pub signatures: Vec<String>,
+}
+
+/// Format the given selectors as one line per selector, with the best resolved signature, if any.
+///
+/// ```
+/// use heimdall_core::selectors::{format_selectors, FunctionSelector};
+///
+/// let selectors = vec![FunctionSelector {
+///     selector: String::from("0x06fdde03"),
+///     entry_point: 59,
+///     signatures: vec![String::from("name()")],
+/// }];
+///
+/// assert_eq!(format_selectors(&selectors), "0x06fdde03 name()\n");
+/// ```
+pub fn format_selectors(selectors: &[FunctionSelector]) -> String {
+    selectors
+        .iter()
+        .map(|selector| match selector.signatures.first() {
+            Some(signature) => format!("{} {}\n", selector.selector, signature),
+            None => format!("{}\n", selector.selector),
+        })
+        .collect()
+}
+
+/// Format the given selectors as a table, with a header row, and a row for each selector with its
+/// best resolved signature and the signature's parameter types, if any, and its entry point.
+///
+/// ```
+/// use heimdall_common::utils::io::table::TableFormat;
+/// use heimdall_core::selectors::{build_selectors_table, FunctionSelector};
+///
+/// let selectors = vec![FunctionSelector {
+///     selector: String::from("0xa9059cbb"),
+///     entry_point: 59,
+///     signatures: vec![String::from("transfer(address,uint256)")],
+/// }];
+///
+/// assert_eq!(
+///     build_selectors_table(&selectors, TableFormat::Tsv),
+///     "selector\tsignature\ttypes\tentry_point\n0xa9059cbb\ttransfer(address,uint256)\taddress,uint256\t59\n"
+/// );
+/// ```
+pub fn build_selectors_table(selectors: &[FunctionSelector], format: TableFormat) -> String {
+    let mut table = format.row(&["selector", "signature", "types", "entry_point"]) + "\n";
+    for selector in selectors {
+        let signature = selector.signatures.first().map(String::as_str).unwrap_or_default();
+        let types = signature
+            .split_once('(')
+            .and_then(|(_, parameters)| parameters.strip_suffix(')'))
+            .unwrap_or_default();
+
+        table.push_str(&format.row(&[
+            selector.selector.as_str(),
+            signature,
+            types,
+            &selector.entry_point.to_string(),
+        ]));
+        table.push('\n');
+    }
+    table
+}
+
+/// The main entry point for the selectors module. Will walk the target's function dispatcher and
+/// return every selector found, sorted by selector, and optionally resolved to signatures.
+pub async fn selectors(args: SelectorsArgs) -> Result<Vec<FunctionSelector>, Error> {
+    use std::time::Instant;
+    let now = Instant::now();
+
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    if !["text", "tsv", "csv"].contains(&args.format.as_str()) {
+        return Err(Error::ParseError(format!("unknown format '{}' .", &args.format)))
+    }
+
+    // fetch bytecode
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // the dispatcher is walked with the VM, which only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by selectors. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
+    }
+
+    // disassemble the bytecode
+    let disassembled_bytecode = disassemble(DisassemblerArgs {
+        target: contract_bytecode.clone(),
+        verbose: args.verbose.clone(),
+        rpc_url: args.rpc_url.clone(),
+        decimal_counter: false,
+        output: String::new(),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: Hardfork::default(),
+    })
+    .await?;
+
+    // create a new EVM instance
+    let evm = VM::new(
+        contract_bytecode.clone(),
+        String::from("0x"),
+        String::from("0x6865696d64616c6c000000000061646472657373"),
+        String::from("0x6865696d64616c6c0000000000006f726967696e"),
+        String::from("0x6865696d64616c6c00000000000063616c6c6572"),
+        0,
+        u128::MAX,
+    );
+
+    // find and resolve all selectors in the bytecode
+    let found_selectors = find_function_selectors(&evm, &disassembled_bytecode);
+    let mut resolved_selectors: HashMap<String, Vec<ResolvedFunction>> = HashMap::new();
+    if !args.skip_resolving {
+        resolved_selectors =
+            resolve_selectors::<ResolvedFunction>(found_selectors.keys().cloned().collect()).await;
+        logger.info(&format!(
+            "resolved {} possible functions from {} detected selectors.",
+            resolved_selectors.len(),
+            found_selectors.len()
+        ));
+    } else {
+        logger.info(&format!("found {} possible function selectors.", found_selectors.len()));
+    }
+
+    let mut function_selectors = found_selectors
+        .into_iter()
+        .map(|(selector, entry_point)| {
+            let mut signatures: Vec<String> = resolved_selectors
+                .get(&selector)
+                .map(|functions| {
+                    functions.iter().map(|function| function.signature.clone()).collect()
+                })
+                .unwrap_or_default();
+            signatures
+                .sort_by(|a, b| score_signature(b).cmp(&score_signature(a)).then_with(|| a.cmp(b)));
+
+            FunctionSelector { selector: format!("0x{selector}"), entry_point, signatures }
+        })
+        .collect::<Vec<FunctionSelector>>();
+    function_selectors.sort_by(|a, b| a.selector.cmp(&b.selector));
+
+    logger.debug(&format!("selector extraction completed in {:?}.", now.elapsed()));
+
+    Ok(function_selectors)
+}
@@ -0,0 +1,191 @@
+use heimdall_common::{
+    constants::{ADDRESS_REGEX, BYTECODE_REGEX, CALLDATA_REGEX, TRANSACTION_HASH_REGEX},
+    ether::evm::ext::exec::ExecutionLimits,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    cfg::{cfg, output::build_cfg_json, CFGArgsBuilder},
+    decode::{decode, DecodeArgsBuilder},
+    decompile::{decompile, DecompilerArgsBuilder},
+    error::Error,
+    snapshot::{snapshot, util::csv::generate_csv, SnapshotArgsBuilder},
+};
+
+/// An analysis the server exposes, with the path it's requested at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Decompile,
+    Decode,
+    Cfg,
+    Snapshot,
+}
+
+impl Endpoint {
+    /// Get the endpoint served at the given path, if any.
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path.trim_end_matches('/') {
+            "/decompile" => Some(Endpoint::Decompile),
+            "/decode" => Some(Endpoint::Decode),
+            "/cfg" => Some(Endpoint::Cfg),
+            "/snapshot" => Some(Endpoint::Snapshot),
+            _ => None,
+        }
+    }
+}
+
+/// The body of a request to any endpoint. Options which don't apply to an endpoint are ignored.
+/// On-chain targets are always fetched with the server's RPC provider, so clients can't make the
+/// server send requests elsewhere.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisRequest {
+    // the contract address, bytecode, or calldata to analyze
+    pub target: String,
+    pub skip_resolving: bool,
+
+    // whether decompile includes solidity source in its response
+    pub include_solidity: bool,
+}
+
+/// Check that a request's target is something the endpoint may analyze: hex bytecode, or a
+/// contract address, and for decode hex calldata, or a transaction hash. Anything else would be
+/// read as a file path, or from stdin, which clients mustn't be able to make the server do.
+/// On-chain targets are only accepted if the server has an RPC provider to fetch them with.
+///
+/// ```
+/// use heimdall_core::serve::handlers::{validate_target, Endpoint};
+///
+/// assert!(validate_target(Endpoint::Decompile, "0x6080604052", "").is_ok());
+/// assert!(validate_target(Endpoint::Decompile, "/etc/passwd", "").is_err());
+/// assert!(validate_target(Endpoint::Decompile, "-", "").is_err());
+/// ```
+pub fn validate_target(endpoint: Endpoint, target: &str, rpc_url: &str) -> Result<(), Error> {
+    let on_chain = match endpoint {
+        Endpoint::Decode => TRANSACTION_HASH_REGEX.is_match(target)?,
+        _ => ADDRESS_REGEX.is_match(target)?,
+    };
+    if on_chain {
+        return match rpc_url.is_empty() {
+            true => Err(Error::ParseError(String::from(
+                "the server has no RPC provider, so only bytecode and calldata can be analyzed.",
+            ))),
+            false => Ok(()),
+        }
+    }
+
+    let hex = match endpoint {
+        Endpoint::Decode => CALLDATA_REGEX.is_match(target)?,
+        _ => BYTECODE_REGEX.is_match(target)?,
+    };
+    match hex && !target.trim_start_matches("0x").is_empty() {
+        true => Ok(()),
+        false => Err(Error::ParseError(String::from(match endpoint {
+            Endpoint::Decode => "target must be hex calldata, or a transaction hash.",
+            _ => "target must be hex bytecode, or a contract address.",
+        }))),
+    }
+}
+
+/// Run the analysis for an endpoint, with the server's limits on symbolic execution, returning
+/// its result as JSON.
+pub async fn run_analysis(
+    endpoint: Endpoint,
+    request: AnalysisRequest,
+    rpc_url: String,
+    limits: ExecutionLimits,
+) -> Result<Value, Error> {
+    validate_target(endpoint, &request.target, &rpc_url)?;
+
+    match endpoint {
+        Endpoint::Decompile => {
+            let args = DecompilerArgsBuilder::new()
+                .target(request.target)
+                .rpc_url(rpc_url)
+                .skip_resolving(request.skip_resolving)
+                .include_solidity(request.include_solidity)
+                .limits(limits)
+                .build()
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+            let result = decompile(args).await?;
+            Ok(json!({
+                "abi": result.abi,
+                "source": result.source,
+                "storage_layout": result.storage_layout,
+            }))
+        }
+        Endpoint::Decode => {
+            let args = DecodeArgsBuilder::new()
+                .target(request.target)
+                .rpc_url(rpc_url)
+                .build()
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+            Ok(serde_json::to_value(decode(args).await?)?)
+        }
+        Endpoint::Cfg => {
+            let args = CFGArgsBuilder::new()
+                .target(request.target)
+                .rpc_url(rpc_url)
+                .limits(limits)
+                .build()
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+            Ok(serde_json::from_str(&build_cfg_json(&cfg(args).await?)?)?)
+        }
+        Endpoint::Snapshot => {
+            let args = SnapshotArgsBuilder::new()
+                .target(request.target)
+                .rpc_url(rpc_url)
+                .skip_resolving(request.skip_resolving)
+                .no_tui(true)
+                .limits(limits)
+                .build()
+                .map_err(|e| Error::GenericError(e.to_string()))?;
+            let result = snapshot(args).await?;
+
+            // snapshots are served as the rows of the CSV the CLI writes
+            Ok(json!(generate_csv(
+                &result.snapshots,
+                &result.resolved_errors,
+                &result.resolved_events
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::serve::handlers::{validate_target, AnalysisRequest, Endpoint};
+
+    #[test]
+    fn test_endpoint_from_path() {
+        assert_eq!(Endpoint::from_path("/decompile"), Some(Endpoint::Decompile));
+        assert_eq!(Endpoint::from_path("/snapshot/"), Some(Endpoint::Snapshot));
+        assert_eq!(Endpoint::from_path("/dump"), None);
+    }
+
+    #[test]
+    fn test_analysis_request_defaults() {
+        let request: AnalysisRequest = serde_json::from_str(r#"{ "target": "0x00" }"#).unwrap();
+        assert_eq!(request, AnalysisRequest { target: String::from("0x00"), ..Default::default() });
+
+        // requests can't choose their own RPC provider
+        assert!(serde_json::from_str::<AnalysisRequest>(
+            r#"{ "target": "0x00", "rpc_url": "http://169.254.169.254" }"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_target() {
+        let address = "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2";
+        let hash = "0x4b8a1b3d5b3d9f3f1b8c6b4f5c7e9a1d3b5c7e9f1a3b5c7d9e1f3a5b7c9d1e3f";
+
+        assert!(validate_target(Endpoint::Cfg, address, "http://localhost:8545").is_ok());
+        assert!(validate_target(Endpoint::Cfg, address, "").is_err());
+        assert!(validate_target(Endpoint::Decode, hash, "").is_err());
+        assert!(validate_target(Endpoint::Decode, "0xa9059cbb", "").is_ok());
+        assert!(validate_target(Endpoint::Snapshot, "Cargo.toml", "").is_err());
+        assert!(validate_target(Endpoint::Snapshot, "", "").is_err());
+    }
+}
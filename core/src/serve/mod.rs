@@ -0,0 +1,377 @@
+pub mod handlers;
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    ether::evm::ext::exec::ExecutionLimits,
+    utils::{failure::FailureClass, io::logging::Logger},
+};
+use hyper::{
+    body::HttpBody,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde_json::{json, Value};
+use tokio::sync::Semaphore;
+
+use crate::{
+    error::Error,
+    serve::handlers::{run_analysis, validate_target, AnalysisRequest, Endpoint},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Serve decompile, decode, cfg, and snapshot as JSON HTTP endpoints",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall serve [OPTIONS]"
+)]
+pub struct ServeArgs {
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider contract addresses and transaction hashes are fetched with. Without one,
+    /// only bytecode and calldata can be analyzed. Separate multiple providers with commas to fail
+    /// over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The address to listen on.
+    #[clap(long = "host", default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// The port to listen on.
+    #[clap(long = "port", short = 'p', default_value = "8080")]
+    pub port: u16,
+
+    /// The number of requests analyzed at once. Other requests wait in a queue.
+    #[clap(long = "concurrency", default_value = "2")]
+    pub concurrency: usize,
+
+    /// The number of requests which may wait in the queue before new requests are rejected.
+    #[clap(long = "queue-size", default_value = "32")]
+    pub queue_size: usize,
+
+    /// The longest a request may take, in seconds, including time spent in the queue.
+    #[clap(long = "request-timeout", default_value = "120")]
+    pub request_timeout: u64,
+
+    /// The largest request body accepted, in bytes.
+    #[clap(long = "max-body-size", default_value = "1048576")]
+    pub max_body_size: u64,
+
+    /// Limits on how much of each function symbolic execution will explore, for every request.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+}
+
+impl ServeArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            host: Some(String::from("127.0.0.1")),
+            port: Some(8080),
+            concurrency: Some(2),
+            queue_size: Some(32),
+            request_timeout: Some(120),
+            max_body_size: Some(1048576),
+            limits: Some(ExecutionLimits::default()),
+        }
+    }
+}
+
+/// The state shared by every request the server handles.
+struct ServerState {
+    args: ServeArgs,
+
+    // one permit per request which may be analyzed at once. a permit is held until its analysis
+    // stops, even if the request has already timed out, since blocking tasks can't be cancelled
+    permits: Arc<Semaphore>,
+
+    // the number of requests waiting for a permit
+    queued: AtomicUsize,
+}
+
+/// A request's place in the queue, which it gives up once it's given a permit, or once it's
+/// dropped, such as when the request times out while waiting.
+struct QueuedRequest<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedRequest<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The main entry point for the serve module. Will serve each analysis as a `POST` endpoint, such
+/// as `/decompile`, which takes a JSON body with a `target`, and responds with the analysis's
+/// result as JSON. This runs until the process is stopped.
+pub async fn serve(args: ServeArgs) -> Result<(), Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let address: SocketAddr = format!("{}:{}", args.host, args.port)
+        .parse()
+        .map_err(|e| Error::ParseError(format!("invalid address: {e}")))?;
+    let state = Arc::new(ServerState {
+        permits: Arc::new(Semaphore::new(args.concurrency.max(1))),
+        queued: AtomicUsize::new(0),
+        args,
+    });
+
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle_request(request, state).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&address)
+        .map_err(|e| Error::GenericError(format!("failed to listen on '{address}': {e}")))?
+        .serve(make_service);
+    logger.info(&format!("listening on http://{address} ."));
+
+    server.await.map_err(|e| Error::GenericError(format!("server failed: {e}")))
+}
+
+/// handles a single request, responding with the analysis's result, or an error
+async fn handle_request(request: Request<Body>, state: Arc<ServerState>) -> Response<Body> {
+    let endpoint = match Endpoint::from_path(request.uri().path()) {
+        Some(endpoint) => endpoint,
+        None => return error_response(StatusCode::NOT_FOUND, "unknown endpoint."),
+    };
+    if request.method() != Method::POST {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, "endpoints only accept POST.")
+    }
+    if request.body().size_hint().lower() > state.args.max_body_size {
+        return error_response(StatusCode::PAYLOAD_TOO_LARGE, "the request body is too large.")
+    }
+
+    let body = match read_body(request.into_body(), state.args.max_body_size).await {
+        Some(body) => body,
+        None => {
+            return error_response(StatusCode::PAYLOAD_TOO_LARGE, "the request body is too large.")
+        }
+    };
+    let analysis_request = match serde_json::from_slice::<AnalysisRequest>(&body) {
+        Ok(analysis_request) => analysis_request,
+        Err(e) => {
+            return error_response(StatusCode::BAD_REQUEST, &format!("invalid request body: {e}"))
+        }
+    };
+    if let Err(e) = validate_target(endpoint, &analysis_request.target, &state.args.rpc_url) {
+        return error_response(StatusCode::BAD_REQUEST, &e.to_string())
+    }
+
+    // requests beyond the queue's capacity are rejected rather than waiting indefinitely
+    if state.queued.fetch_add(1, Ordering::SeqCst) >= state.args.queue_size {
+        state.queued.fetch_sub(1, Ordering::SeqCst);
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, "the request queue is full.")
+    }
+    let queued = QueuedRequest(&state.queued);
+
+    // each function's symbolic execution is also stopped at the request's timeout, so analyses
+    // which outlive their request finish soon after
+    let timeout = Duration::from_secs(state.args.request_timeout);
+    let limits = ExecutionLimits {
+        timeout: Some(
+            state.args.limits.timeout.unwrap_or(u64::MAX).min(timeout.as_millis() as u64),
+        ),
+        ..state.args.limits.clone()
+    }
+    .with_defaults(&ExecutionLimits::non_interactive());
+
+    let result = tokio::time::timeout(timeout, async {
+        let permit = state.permits.clone().acquire_owned().await;
+        drop(queued);
+
+        // analyses are run on a blocking thread, since symbolic execution is CPU bound
+        let rpc_url = state.args.rpc_url.clone();
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            tokio::runtime::Handle::current().block_on(run_analysis(
+                endpoint,
+                analysis_request,
+                rpc_url,
+                limits,
+            ))
+        })
+        .await
+    })
+    .await;
+
+    match result {
+        Ok(Ok(Ok(value))) => json_response(StatusCode::OK, &value),
+        Ok(Ok(Err(e))) => error_response(
+            match e.failure_class() {
+                FailureClass::InvalidInput | FailureClass::UnresolvedTarget => {
+                    StatusCode::BAD_REQUEST
+                }
+                FailureClass::Rpc => StatusCode::BAD_GATEWAY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            &e.to_string(),
+        ),
+        Ok(Err(e)) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+        Err(_) => error_response(StatusCode::GATEWAY_TIMEOUT, "the request timed out."),
+    }
+}
+
+/// reads a request body, returning `None` if it's larger than `max_size`
+async fn read_body(mut body: Body, max_size: u64) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        bytes.extend(chunk.ok()?);
+        if bytes.len() as u64 > max_size {
+            return None
+        }
+    }
+    Some(bytes)
+}
+
+/// builds a JSON response
+fn json_response(status: StatusCode, value: &Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(value.to_string()))
+        .unwrap_or_default()
+}
+
+/// builds a JSON response with an error message
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    json_response(status, &json!({ "error": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use hyper::{body::to_bytes, Body, Request, StatusCode};
+    use tokio::sync::Semaphore;
+
+    use crate::serve::{handle_request, ServeArgsBuilder, ServerState};
+
+    fn state(queue_size: usize, max_body_size: u64, request_timeout: u64) -> Arc<ServerState> {
+        Arc::new(ServerState {
+            args: ServeArgsBuilder::new()
+                .queue_size(queue_size)
+                .max_body_size(max_body_size)
+                .request_timeout(request_timeout)
+                .build()
+                .unwrap(),
+            permits: Arc::new(Semaphore::new(1)),
+            queued: AtomicUsize::new(0),
+        })
+    }
+
+    async fn status(request: Request<Body>, queue_size: usize) -> (StatusCode, String) {
+        status_with_body_size(request, queue_size, 16).await
+    }
+
+    async fn status_with_body_size(
+        request: Request<Body>,
+        queue_size: usize,
+        max_body_size: u64,
+    ) -> (StatusCode, String) {
+        let response = handle_request(request, state(queue_size, max_body_size, 120)).await;
+        let status = response.status();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_errors() {
+        let request = Request::post("/dump").body(Body::from("{}")).unwrap();
+        assert_eq!(status(request, 1).await.0, StatusCode::NOT_FOUND);
+
+        let request = Request::get("/decode").body(Body::empty()).unwrap();
+        assert_eq!(status(request, 1).await.0, StatusCode::METHOD_NOT_ALLOWED);
+
+        let request = Request::post("/decode").body(Body::from("not json")).unwrap();
+        let (status_code, body) = status(request, 1).await;
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+        assert!(body.contains("invalid request body"));
+
+        let request =
+            Request::post("/decode").body(Body::from("{\"target\": \"0x0000\"}")).unwrap();
+        assert_eq!(status(request, 1).await.0, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let request = Request::post("/decode").body(Body::from("{}")).unwrap();
+        assert_eq!(status(request, 0).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_rejects_unsafe_targets() {
+        for body in [
+            // local files, and stdin, can't be read
+            r#"{ "target": "/etc/passwd" }"#,
+            r#"{ "target": "-" }"#,
+            // on-chain targets need the server's RPC provider, and requests can't give their own
+            r#"{ "target": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2" }"#,
+            r#"{ "target": "0x00", "rpc_url": "http://169.254.169.254" }"#,
+        ] {
+            let request = Request::post("/cfg").body(Body::from(body)).unwrap();
+            let (status_code, body) = status_with_body_size(request, 1, 1024).await;
+            assert_eq!(status_code, StatusCode::BAD_REQUEST, "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_queue_full() {
+        let request = Request::post("/cfg").body(Body::from(r#"{ "target": "0x00" }"#)).unwrap();
+        assert_eq!(
+            status_with_body_size(request, 0, 1024).await.0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handle_request_timeout_leaves_queue() {
+        let state = state(1, 1024, 1);
+        let request = || Request::post("/cfg").body(Body::from(r#"{ "target": "0x00" }"#)).unwrap();
+
+        // while the only permit is held, requests time out waiting in the queue, and each gives
+        // up its place as it does
+        let permit = state.permits.clone().acquire_owned().await.unwrap();
+        for _ in 0..2 {
+            let response = handle_request(request(), state.clone()).await;
+            assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+        }
+        assert_eq!(state.queued.load(Ordering::SeqCst), 0);
+
+        // so the server recovers once the permit is released
+        drop(permit);
+        assert_eq!(handle_request(request(), state.clone()).await.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,481 @@
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::{
+    abi::{
+        decode as decode_abi, encode,
+        token::{LenientTokenizer, Tokenizer},
+        ParamType, Token,
+    },
+    types::{Bytes, Log, H160, H256, U256},
+    utils::keccak256,
+};
+use heimdall_common::{
+    constants::ADDRESS_REGEX,
+    ether::{
+        evm::core::{
+            fork::Fork, hardfork::Hardfork, log::Log as VmLog, storage::Storage,
+            types::parse_function_parameters, vm::VM,
+        },
+        rpc::batch_request,
+        signatures::{ResolveSelector, ResolvedError},
+    },
+    utils::{
+        io::logging::*,
+        strings::{decode_hex, encode_hex, find_balanced_encapsulator},
+    },
+};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    error::Error,
+    exec::build_vm,
+    inspect::{
+        decode_logs,
+        report::{DecodedLog, StorageChange},
+    },
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Simulate a call to a contract, decoding its result, events, and storage writes",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall simulate <TARGET> <SIGNATURE> [ARGS]... [OPTIONS]"
+)]
+pub struct SimulateArgs {
+    /// The address of the contract to call.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// The signature of the function to call, such as `transfer(address,uint256)`. The types it
+    /// returns may follow, such as `balanceOf(address)(uint256)`, to decode its return data.
+    #[clap(required = true)]
+    pub signature: String,
+
+    /// The arguments to call the function with, in order.
+    #[clap(multiple_values = true, allow_hyphen_values = true)]
+    pub arguments: Vec<String>,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider whose chain state the call is simulated against. Separate multiple
+    /// providers with commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The address to make the call from.
+    #[clap(long = "from", default_value = "", hide_default_value = true)]
+    pub from: String,
+
+    /// The value, in wei, to send with the call.
+    #[clap(long = "value", default_value = "0", hide_default_value = true)]
+    pub value: u128,
+
+    /// The gas limit of the call.
+    #[clap(long = "gas-limit", default_value = "30000000")]
+    pub gas_limit: u128,
+
+    /// The block whose state the call is simulated against, rather than the latest block.
+    #[clap(long = "block")]
+    pub block: Option<u64>,
+
+    /// The hardfork whose gas schedule the VM follows.
+    #[clap(long = "evm-version", default_value = "cancun")]
+    pub evm_version: Hardfork,
+
+    /// Whether to simulate the call with the node's `eth_call`, rather than heimdall's VM. The
+    /// node's result is exact, but its events and storage writes aren't reported.
+    #[clap(long = "eth-call")]
+    pub eth_call: bool,
+
+    /// Whether to skip resolving the signatures of events and custom errors.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl SimulateArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            signature: Some(String::new()),
+            arguments: Some(Vec::new()),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            from: Some(String::new()),
+            value: Some(0),
+            gas_limit: Some(30_000_000),
+            block: Some(None),
+            evm_version: Some(Hardfork::default()),
+            eth_call: Some(false),
+            skip_resolving: Some(false),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// The result of a simulated call, with its return data, revert reason, and events decoded.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationResult {
+    // the canonical signature of the function called, such as `transfer(address,uint256)`
+    pub signature: String,
+    pub calldata: String,
+    pub success: bool,
+
+    // the gas used by the call, which is only known when simulated by the VM
+    pub gas_used: Option<u128>,
+    pub return_data: String,
+    pub decoded_return: Option<Vec<Token>>,
+    pub revert_reason: Option<String>,
+    pub logs: Vec<DecodedLog>,
+    pub state_changes: Vec<StorageChange>,
+}
+
+/// A function call, encoded from a signature and its arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedCall {
+    pub signature: String,
+    pub calldata: Vec<u8>,
+    pub returns: Option<Vec<ParamType>>,
+}
+
+/// Encode a call to the function with the given signature, parsing each argument as the type of
+/// its parameter. Types the function returns may follow the signature, such as
+/// `balanceOf(address)(uint256)`.
+///
+/// ```
+/// use heimdall_core::simulate::encode_call;
+///
+/// let call = encode_call("balanceOf(address)(uint256)", &[
+///     String::from("0x0000000000000000000000000000000000000001"),
+/// ]).unwrap();
+/// assert_eq!(call.signature, "balanceOf(address)");
+/// assert_eq!(call.calldata[..4], [0x70, 0xa0, 0x82, 0x31]);
+/// ```
+pub fn encode_call(signature: &str, arguments: &[String]) -> Result<EncodedCall, Error> {
+    let signature = signature.trim();
+    let (start, end, valid) = find_balanced_encapsulator(signature, ('(', ')'));
+    if !valid || start == 0 {
+        return Err(Error::ParseError(format!("invalid function signature '{signature}'.")))
+    }
+
+    // functions without parameters can't be parsed, since there are no types to extract
+    let inputs = match signature[start + 1..end - 1].trim() {
+        "" => Vec::new(),
+        _ => parse_function_parameters(&signature[..end]).ok_or_else(|| {
+            Error::ParseError(format!("invalid function signature '{signature}'."))
+        })?,
+    };
+    let returns = match signature[end..].trim() {
+        "" | "()" => None,
+        returns => Some(
+            parse_function_parameters(returns)
+                .ok_or_else(|| Error::ParseError(format!("invalid return types '{returns}'.")))?,
+        ),
+    };
+
+    if arguments.len() != inputs.len() {
+        return Err(Error::ParseError(format!(
+            "'{signature}' takes {} arguments, but {} were given.",
+            inputs.len(),
+            arguments.len()
+        )))
+    }
+    let tokens = inputs
+        .iter()
+        .zip(arguments)
+        .map(|(input, argument)| {
+            LenientTokenizer::tokenize(input, argument).map_err(|e| {
+                Error::ParseError(format!("failed to parse '{argument}' as {input}: {e}"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // the name is the last word before the parameters, after any `function` keyword
+    let signature = format!(
+        "{}({})",
+        signature[..start].split_whitespace().last().unwrap_or_default(),
+        inputs.iter().map(|input| input.to_string()).collect::<Vec<_>>().join(",")
+    );
+    let mut calldata = keccak256(&signature)[..4].to_vec();
+    calldata.extend(encode(&tokens));
+
+    Ok(EncodedCall { signature, calldata, returns })
+}
+
+/// Decode the reason a call reverted from its revert data, as an `Error(string)` message, a
+/// `Panic(uint256)` code, or a custom error, if its signature resolves. Reverts without data have
+/// no reason.
+pub async fn decode_revert_reason(data: &[u8], skip_resolving: bool) -> Option<String> {
+    let (selector, arguments) = match data.len() {
+        0..=3 => return None,
+        _ => data.split_at(4),
+    };
+
+    match selector {
+        [0x08, 0xc3, 0x79, 0xa0] => {
+            match decode_abi(&[ParamType::String], arguments).ok()?.pop()? {
+                Token::String(reason) => Some(reason),
+                _ => None,
+            }
+        }
+        [0x4e, 0x48, 0x7b, 0x71] => {
+            match decode_abi(&[ParamType::Uint(256)], arguments).ok()?.pop()? {
+                Token::Uint(code) => Some(format!("Panic({code:#x})")),
+                _ => None,
+            }
+        }
+        _ if !skip_resolving => {
+            let errors = ResolvedError::resolve(&encode_hex(selector.to_vec())).await?;
+            errors.iter().find_map(|error| {
+                let inputs = parse_function_parameters(&error.signature).unwrap_or_default();
+                let tokens = decode_abi(&inputs, arguments).ok()?;
+                Some(format!(
+                    "{}({})",
+                    error.name,
+                    tokens.iter().map(|token| token.to_string()).collect::<Vec<_>>().join(", ")
+                ))
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The main entry point for the simulate module. Will encode a call to the target from the given
+/// signature and arguments, simulate it against the chain's state, and decode its result.
+pub async fn simulate(args: SimulateArgs) -> Result<SimulationResult, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    if !ADDRESS_REGEX.is_match(&args.target)? {
+        return Err(Error::ParseError(format!("invalid target address '{}' .", &args.target)))
+    }
+    let call = encode_call(&args.signature, &args.arguments)?;
+    let calldata = format!("0x{}", encode_hex(call.calldata.clone()));
+    logger.debug(&format!("calling '{}' with calldata '{calldata}' .", call.signature));
+
+    let (success, gas_used, return_data, logs, state_changes) = match args.eth_call {
+        true => {
+            let (success, return_data) = eth_call(&args, &calldata).await?;
+            (success, None, return_data, Vec::new(), Vec::new())
+        }
+        false => {
+            let mut evm = build_vm(
+                &args.target,
+                &args.rpc_url,
+                &calldata,
+                args.value,
+                args.gas_limit,
+                &logger,
+            )
+            .await?;
+            evm.hardfork = args.evm_version;
+            if args.block.is_some() {
+                evm.fork = Some(Fork::new(&args.rpc_url, args.block));
+            }
+            if !args.from.is_empty() {
+                evm.caller = decode_hex(&args.from.replacen("0x", "", 1))?;
+                evm.origin = evm.caller.clone();
+            }
+
+            let result = evm.execute();
+            if let Some(fault) = &result.fault {
+                logger.warn(&Error::from(fault.clone()).to_string());
+            }
+
+            // RETURN, STOP, and running past the end of the code are the only successful exits
+            let success = matches!(result.exitcode, 0 | 10 | 255);
+            let target = H160::from_slice(&evm.address);
+            let logs = result.events.iter().map(|log| to_log(target, log)).collect::<Vec<_>>();
+            (
+                success,
+                Some(result.gas_used),
+                result.returndata,
+                decode_logs(&logs, args.skip_resolving).await,
+                match success {
+                    true => vm_state_changes(&evm),
+                    false => Vec::new(),
+                },
+            )
+        }
+    };
+
+    let (decoded_return, revert_reason) = match success {
+        true => {
+            (call.returns.as_ref().and_then(|returns| decode_abi(returns, &return_data).ok()), None)
+        }
+        false => (None, decode_revert_reason(&return_data, args.skip_resolving).await),
+    };
+
+    Ok(SimulationResult {
+        signature: call.signature,
+        calldata,
+        success,
+        gas_used,
+        return_data: format!("0x{}", encode_hex(return_data)),
+        decoded_return,
+        revert_reason,
+        logs,
+        state_changes,
+    })
+}
+
+/// simulates the call with the node's `eth_call`, returning whether it succeeded and its return
+/// or revert data
+async fn eth_call(args: &SimulateArgs, calldata: &str) -> Result<(bool, Vec<u8>), Error> {
+    let mut transaction = json!({
+        "to": args.target,
+        "data": calldata,
+        "value": format!("{:#x}", args.value),
+        "gas": format!("{:#x}", args.gas_limit),
+    });
+    if !args.from.is_empty() {
+        transaction["from"] = json!(args.from);
+    }
+    let block = match args.block {
+        Some(block) => format!("{block:#x}"),
+        None => String::from("latest"),
+    };
+
+    let response = batch_request(&args.rpc_url, &[("eth_call", json!([transaction, block]))])
+        .await
+        .map_err(Error::from_rpc)?
+        .pop()
+        .ok_or_else(|| Error::RpcError(String::from("no response")))?;
+
+    // nodes report reverts as errors, and only some include the revert data
+    match response {
+        Ok(return_data) => {
+            let return_data = return_data.as_str().unwrap_or_default().replacen("0x", "", 1);
+            Ok((true, decode_hex(&return_data)?))
+        }
+        Err(e) => match e.split("0x").last().and_then(|data| decode_hex(data.trim()).ok()) {
+            Some(revert_data) if e.contains("0x") => Ok((false, revert_data)),
+            _ => Err(Error::RpcError(format!("eth_call failed: {e}"))),
+        },
+    }
+}
+
+/// converts a log emitted by the VM to the node's format
+fn to_log(address: H160, log: &VmLog) -> Log {
+    Log {
+        address,
+        topics: log
+            .topics
+            .iter()
+            .map(|topic| {
+                let mut bytes = [0u8; 32];
+                topic.to_big_endian(&mut bytes);
+                H256::from(bytes)
+            })
+            .collect(),
+        data: Bytes::from(log.data.clone()),
+        log_index: Some(U256::from(log.index)),
+        ..Default::default()
+    }
+}
+
+/// Get the storage writes which changed a slot's value during the VM's execution, in the target
+/// and every contract it called.
+fn vm_state_changes(evm: &VM) -> Vec<StorageChange> {
+    let mut changes = storage_changes(H160::from_slice(&evm.address), &evm.storage);
+    for (address, contract) in &evm.contracts {
+        let mut bytes = [0u8; 32];
+        address.to_big_endian(&mut bytes);
+        changes.extend(storage_changes(H160::from_slice(&bytes[12..]), &contract.storage));
+    }
+    changes.sort_by_key(|change| (change.address, change.slot));
+    changes
+}
+
+/// gets the slots whose value differs from their value before execution
+fn storage_changes(address: H160, storage: &Storage) -> Vec<StorageChange> {
+    storage
+        .storage
+        .iter()
+        .filter_map(|(slot, value)| {
+            let original = storage.original(*slot)?;
+            match original != *value {
+                true => Some(StorageChange {
+                    address,
+                    slot: H256::from(*slot),
+                    old_value: H256::from(original),
+                    new_value: H256::from(*value),
+                }),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::{encode, ParamType, Token},
+        types::{H160, U256},
+    };
+
+    use crate::simulate::{decode_revert_reason, encode_call};
+
+    #[test]
+    fn test_encode_call() {
+        let call = encode_call(
+            "transfer(address, uint)(bool)",
+            &[String::from("0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"), String::from("100")],
+        )
+        .unwrap();
+
+        assert_eq!(call.signature, "transfer(address,uint256)");
+        assert_eq!(call.calldata[..4], [0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(
+            call.calldata[4..],
+            encode(&[Token::Address(H160::repeat_byte(0xaa)), Token::Uint(U256::from(100))])
+        );
+        assert_eq!(call.returns, Some(vec![ParamType::Bool]));
+
+        let call = encode_call("totalSupply()", &[]).unwrap();
+        assert_eq!(call.calldata, [0x18, 0x16, 0x0d, 0xdd]);
+        assert_eq!(call.returns, None);
+
+        // the number of arguments must match the signature
+        assert!(encode_call("transfer(address,uint256)", &[]).is_err());
+        assert!(encode_call("transfer(address,uint256)", &[String::from("a"), String::from("1")])
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_revert_reason() {
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend(encode(&[Token::String(String::from("insufficient balance"))]));
+        assert_eq!(
+            decode_revert_reason(&data, true).await,
+            Some(String::from("insufficient balance"))
+        );
+
+        let mut data = vec![0x4e, 0x48, 0x7b, 0x71];
+        data.extend(encode(&[Token::Uint(U256::from(0x11))]));
+        assert_eq!(decode_revert_reason(&data, true).await, Some(String::from("Panic(0x11)")));
+
+        assert_eq!(decode_revert_reason(&[], true).await, None);
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef], true).await, None);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::decompile::constants::AND_BITMASK_REGEX;
 
 use super::{
-    constants::VARIABLE_SIZE_CHECK_REGEX,
+    constants::{DANGEROUS_OPCODES, VARIABLE_SIZE_CHECK_REGEX},
     structures::snapshot::{CalldataFrame, Snapshot, StorageFrame},
 };
 use ethers::{
@@ -13,7 +13,7 @@ use heimdall_common::{
         evm::{
             core::{
                 opcodes::WrappedOpcode,
-                types::{byte_size_to_type, convert_bitmask},
+                types::{byte_size_to_type, convert_bitmask, signed_operands},
             },
             ext::exec::VMTrace,
         },
@@ -77,8 +77,12 @@ pub fn snapshot_trace(
                 "CHAINID",
                 "SELFBALANCE",
                 "BASEFEE",
+                "BLOBHASH",
+                "BLOBBASEFEE",
                 "SLOAD",
                 "SSTORE",
+                "TLOAD",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -104,6 +108,7 @@ pub fn snapshot_trace(
         if snapshot.view &&
             [
                 "SSTORE",
+                "TSTORE",
                 "CREATE",
                 "SELFDESTRUCT",
                 "CALL",
@@ -125,6 +130,15 @@ pub fn snapshot_trace(
             );
         }
 
+        // paths which end in a REVERT or INVALID instruction revert the call
+        if ["REVERT", "INVALID"].contains(&opcode_name) {
+            snapshot.can_revert = true;
+        }
+
+        if DANGEROUS_OPCODES.contains(&opcode_name) {
+            snapshot.dangerous_opcodes.insert(opcode_name.to_string());
+        }
+
         if (0xA0..=0xA4).contains(&opcode_number) {
             // LOG0, LOG1, LOG2, LOG3, LOG4
             let logged_event = match operation.events.last() {
@@ -336,11 +350,13 @@ pub fn snapshot_trace(
                         frame.operation == calldata_slot_operation.inputs[0].to_string()
                     })
                 {
-                    // append the current potential types to the new vector and remove
-                    // duplicates
-                    potential_types.append(&mut arg.1.clone());
-                    potential_types.sort();
-                    potential_types.dedup();
+                    // keep the mask's candidates first, as they're ordered by likelihood, followed
+                    // by any other types the argument could be
+                    for potential_type in &arg.1 {
+                        if !potential_types.contains(potential_type) {
+                            potential_types.push(potential_type.clone());
+                        }
+                    }
 
                     // replace mask size and potential types
                     snapshot.arguments.insert(
@@ -464,6 +480,24 @@ pub fn snapshot_trace(
             ));
         }
 
+        // signed operations imply a signed integer type for the arguments they operate on
+        for (operand, byte_size) in signed_operands(&instruction) {
+            if operand.opcode.code != 0x35 {
+                continue
+            }
+            let slot = operand.inputs.first().map(|input| input.to_string());
+            for (frame, potential_types) in snapshot.arguments.values_mut() {
+                if slot.as_ref() != Some(&frame.operation) {
+                    continue
+                }
+
+                let signed_type = format!("int{}", byte_size * 8);
+                potential_types.retain(|potential_type| potential_type != &signed_type);
+                potential_types.insert(0, signed_type);
+                frame.mask_size = byte_size;
+            }
+        }
+
         // handle type heuristics
         if [
             "MUL",
@@ -5,6 +5,11 @@ use lazy_static::lazy_static;
 
 use crate::snapshot::structures::state::State;
 
+// opcodes which let a function destroy the contract, run foreign code in its context, deploy
+// contracts, or authenticate with `tx.origin`
+pub const DANGEROUS_OPCODES: [&str; 6] =
+    ["SELFDESTRUCT", "DELEGATECALL", "CALLCODE", "CREATE", "CREATE2", "ORIGIN"];
+
 lazy_static! {
     /// global state for the snapshot module
     pub static ref STATE: Mutex<State> = Mutex::new(State::new());
@@ -274,6 +274,38 @@ pub fn render_tui_command_palette<B: Backend>(f: &mut Frame<B>, state: &mut Stat
         );
     }
 
+    // add tainted sinks
+    if !snapshot.tainted_sinks.is_empty() {
+        text.append(&mut vec![
+            Spans::from(""), // buffer
+            Spans::from(Span::styled(
+                " Tainted Sinks ",
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+        ]);
+        text.append(
+            &mut snapshot
+                .tainted_sinks
+                .iter()
+                .map(|x| Spans::from(format!(" {}", x)))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // add faults
+    if !snapshot.faults.is_empty() {
+        text.append(&mut vec![
+            Spans::from(""), // buffer
+            Spans::from(Span::styled(
+                " Faults ",
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+        ]);
+        text.append(
+            &mut snapshot.faults.iter().map(|x| Spans::from(format!(" {}", x))).collect::<Vec<_>>(),
+        );
+    }
+
     // about text
     let snapshot_header = format!(
         " {}Snapshot of 0x{}{} ",
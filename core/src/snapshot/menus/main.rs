@@ -123,15 +123,16 @@ pub fn render_tui_view_main<B: Backend>(f: &mut Frame<B>, state: &mut State) {
         // add modifiers and arguments
         Spans::from(""), // buffer
         Spans::from(Span::styled(
-            " Modifiers       Returns        Entry Point      Branch Count",
+            " Modifiers       Returns        Entry Point      Branch Count     Can Revert",
             Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
         )),
         Spans::from(format!(
-            " {:<16}{:<15}{:<17}{}",
+            " {:<16}{:<15}{:<17}{:<17}{}",
             modifiers.join(" "),
             snapshot.returns.clone().unwrap_or("None".to_owned()),
             snapshot.entry_point,
-            snapshot.branch_count
+            snapshot.branch_count,
+            snapshot.can_revert
         )),
     ]);
 
@@ -291,6 +292,57 @@ pub fn render_tui_view_main<B: Backend>(f: &mut Frame<B>, state: &mut State) {
         );
     }
 
+    // add tainted sinks
+    if !snapshot.tainted_sinks.is_empty() {
+        text.append(&mut vec![
+            Spans::from(""), // buffer
+            Spans::from(Span::styled(
+                " Tainted Sinks ",
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+        ]);
+        text.append(
+            &mut snapshot
+                .tainted_sinks
+                .iter()
+                .map(|x| Spans::from(format!(" {}", x)))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // add dangerous opcodes
+    if !snapshot.dangerous_opcodes.is_empty() {
+        let mut dangerous_opcodes = snapshot.dangerous_opcodes.iter().collect::<Vec<_>>();
+        dangerous_opcodes.sort();
+        text.append(&mut vec![
+            Spans::from(""), // buffer
+            Spans::from(Span::styled(
+                " Dangerous Opcodes ",
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+        ]);
+        text.append(
+            &mut dangerous_opcodes
+                .iter()
+                .map(|x| Spans::from(format!(" {}", x)))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    // add faults
+    if !snapshot.faults.is_empty() {
+        text.append(&mut vec![
+            Spans::from(""), // buffer
+            Spans::from(Span::styled(
+                " Faults ",
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+        ]);
+        text.append(
+            &mut snapshot.faults.iter().map(|x| Spans::from(format!(" {}", x))).collect::<Vec<_>>(),
+        );
+    }
+
     // about text
     let snapshot_header = format!(
         " {}Snapshot of 0x{}{} ",
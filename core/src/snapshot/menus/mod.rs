@@ -1,9 +1,14 @@
+#[cfg(not(target_arch = "wasm32"))]
 use tui::{backend::Backend, Frame};
 
+#[cfg(not(target_arch = "wasm32"))]
 use super::structures::state::State;
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod command_palette;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod help;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod main;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -17,6 +22,7 @@ pub enum TUIView {
 
 #[allow(unreachable_patterns)]
 /// Render the TUI view based on the current state
+#[cfg(not(target_arch = "wasm32"))]
 pub fn render_ui<B: Backend>(f: &mut Frame<B>, state: &mut State) {
     match state.view {
         TUIView::Main => main::render_tui_view_main(f, state),
@@ -5,38 +5,37 @@ pub mod resolve;
 pub mod structures;
 pub mod util;
 
-use std::{
-    collections::{HashMap, HashSet},
-    fs,
-    time::Duration,
-};
+use std::collections::{HashMap, HashSet};
 
 use clap::{AppSettings, Parser};
 use derive_builder::Builder;
 use heimdall_common::{
-    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
     ether::{
         compiler::detect_compiler,
-        evm::core::vm::VM,
-        rpc::get_code,
+        eof::is_eof,
+        evm::{
+            core::{hardfork::Hardfork, taint::TaintTracker, vm::VM},
+            ext::exec::ExecutionLimits,
+        },
         selectors::{find_function_selectors, resolve_selectors},
         signatures::{score_signature, ResolvedError, ResolvedFunction, ResolvedLog},
     },
     utils::{
-        io::logging::*,
+        determinism::deterministic,
+        io::{logging::*, progress::spinner},
         strings::{decode_hex, encode_hex_reduced},
     },
 };
-use indicatif::ProgressBar;
 
 use crate::{
     disassemble::{disassemble, DisassemblerArgs},
+    error::Error,
     snapshot::{
         analyze::snapshot_trace,
         resolve::match_parameters,
         structures::snapshot::{GasUsed, Snapshot},
-        util::tui,
     },
+    utils::get_contract_bytecode,
 };
 #[derive(Debug, Clone, Parser, Builder)]
 #[clap(
@@ -46,7 +45,8 @@ use crate::{
     override_usage = "heimdall snapshot <TARGET> [OPTIONS]"
 )]
 pub struct SnapshotArgs {
-    /// The target to analyze. This may be a file, bytecode, or contract address.
+    /// The target to analyze. This may be a file, bytecode, contract address, or `-`
+    /// to read bytecode from stdin.
     #[clap(required = true)]
     pub target: String,
 
@@ -54,7 +54,8 @@ pub struct SnapshotArgs {
     #[clap(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 
-    /// The RPC provider to use for fetching target bytecode.
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
     #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
     pub rpc_url: String,
 
@@ -70,6 +71,14 @@ pub struct SnapshotArgs {
     #[clap(long)]
     pub no_tui: bool,
 
+    /// The hardfork whose gas schedule the VM follows.
+    #[clap(long = "evm-version", default_value = "cancun")]
+    pub evm_version: Hardfork,
+
+    /// Limits on how much of each function symbolic execution will explore.
+    #[clap(flatten)]
+    pub limits: ExecutionLimits,
+
     /// The output directory to write the output to, or 'print' to print to the console.
     #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
     pub output: String,
@@ -84,6 +93,8 @@ impl SnapshotArgsBuilder {
             default: Some(true),
             skip_resolving: Some(false),
             no_tui: Some(true),
+            evm_version: Some(Hardfork::default()),
+            limits: Some(ExecutionLimits::default()),
             output: Some(String::new()),
         }
     }
@@ -99,7 +110,7 @@ pub struct SnapshotResult {
 /// The main snapshot function, which will be called from the main thread. This module is
 /// responsible for generating a high-level overview of the target contract, including function
 /// signatures, access control, gas consumption, storage accesses, event emissions, and more.
-pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std::error::Error>> {
+pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Error> {
     use std::time::Instant;
     let now = Instant::now();
 
@@ -137,34 +148,13 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
         "()".to_string(),
     );
 
-    let contract_bytecode: String;
-    if ADDRESS_REGEX.is_match(&args.target)? {
-        // We are snapshotting a contract address, so we need to fetch the bytecode from the RPC
-        // provider.
-        contract_bytecode = get_code(&args.target, &args.rpc_url).await?;
-    } else if BYTECODE_REGEX.is_match(&args.target)? {
-        logger.debug_max("using provided bytecode for snapshotting.");
-        contract_bytecode = args.target.clone().replacen("0x", "", 1);
-    } else {
-        logger.debug_max("using provided file for snapshotting.");
-
-        // We are snapshotting a file, so we need to read the bytecode from the file.
-        contract_bytecode = match fs::read_to_string(&args.target) {
-            Ok(contents) => {
-                let _contents = contents.replace('\n', "");
-                if BYTECODE_REGEX.is_match(&_contents)? && _contents.len() % 2 == 0 {
-                    _contents.replacen("0x", "", 1)
-                } else {
-                    logger
-                        .error(&format!("file '{}' doesn't contain valid bytecode.", &args.target));
-                    std::process::exit(1)
-                }
-            }
-            Err(_) => {
-                logger.error(&format!("failed to open file '{}' .", &args.target));
-                std::process::exit(1)
-            }
-        };
+    let contract_bytecode = get_contract_bytecode(&args.target, &args.rpc_url, &logger).await?;
+
+    // symbolic execution only supports legacy bytecode
+    if is_eof(&decode_hex(&contract_bytecode.replacen("0x", "", 1))?) {
+        return Err(Error::VmError(String::from(
+            "EOF containers are not supported by snapshot. try `heimdall disassemble` or `heimdall cfg` instead.",
+        )))
     }
 
     // disassemble the bytecode
@@ -174,6 +164,10 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
         rpc_url: args.rpc_url,
         decimal_counter: false,
         output: String::new(),
+        labels: false,
+        annotate: false,
+        json: false,
+        evm_version: args.evm_version,
     })
     .await?;
     trace.add_call(
@@ -204,7 +198,7 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
     }
 
     // create a new EVM instance
-    let evm = VM::new(
+    let mut evm = VM::new(
         contract_bytecode.clone(),
         String::from("0x"),
         String::from("0x6865696d64616c6c000000000061646472657373"),
@@ -213,6 +207,7 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
         0,
         u128::max_value(),
     );
+    evm.hardfork = args.evm_version;
     let mut shortened_target = contract_bytecode.clone();
     if shortened_target.len() > 66 {
         shortened_target = shortened_target.chars().take(66).collect::<String>() +
@@ -255,9 +250,13 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
     logger.info(&format!("performing symbolic execution on '{shortened_target}' ."));
 
     // get a new progress bar
-    let mut snapshot_progress = ProgressBar::new_spinner();
-    snapshot_progress.enable_steady_tick(Duration::from_millis(100));
-    snapshot_progress.set_style(logger.info_spinner());
+    let mut snapshot_progress = spinner(logger.info_spinner());
+
+    // in deterministic mode, functions are snapshotted in selector order
+    let mut selectors = selectors.into_iter().collect::<Vec<_>>();
+    if deterministic() {
+        selectors.sort();
+    }
 
     // perform EVM analysis
     let mut snapshots: Vec<Snapshot> = Vec::new();
@@ -279,9 +278,23 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
             &format!("discovered entry point: {function_entry_point}"),
         );
 
-        // get a map of possible jump destinations
-        let (map, jumpdest_count) =
-            &evm.clone().symbolic_exec_selector(&selector, function_entry_point);
+        // get a map of possible jump destinations, tracking which calldata reaches each sink
+        let mut function_evm = evm.clone();
+        let taint = TaintTracker::default();
+        function_evm.taint = Some(taint.clone());
+        let (map, jumpdest_count, limit_reached) =
+            &function_evm.symbolic_exec_selector(&selector, function_entry_point, &args.limits);
+        if let Some(limit) = limit_reached {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' stopped early after reaching the {limit}."
+            ));
+        }
+        for fault in map.faults() {
+            logger.warn(&format!(
+                "symbolic execution of '0x{selector}' faulted: {}",
+                Error::from(fault)
+            ));
+        }
 
         trace.add_debug(
             func_analysis_trace,
@@ -323,6 +336,14 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
                 addresses: HashSet::new(),
                 branch_count: *jumpdest_count,
                 control_statements: HashSet::new(),
+                tainted_sinks: taint.sinks(),
+                faults: map
+                    .faults()
+                    .into_iter()
+                    .map(|fault| Error::from(fault).to_string())
+                    .collect(),
+                can_revert: !map.faults().is_empty(),
+                dangerous_opcodes: HashSet::new(),
             },
             &mut trace,
             func_analysis_trace,
@@ -537,17 +558,16 @@ pub async fn snapshot(args: SnapshotArgs) -> Result<SnapshotResult, Box<dyn std:
         snapshots.push(snapshot);
 
         // get a new progress bar
-        snapshot_progress = ProgressBar::new_spinner();
-        snapshot_progress.enable_steady_tick(Duration::from_millis(100));
-        snapshot_progress.set_style(logger.info_spinner());
+        snapshot_progress = spinner(logger.info_spinner());
     }
     snapshot_progress.finish_and_clear();
     logger.info("symbolic execution completed.");
     logger.debug(&format!("snapshot completed in {:?}.", now.elapsed()));
 
-    // open the tui
+    // open the tui, which isn't available when targeting wasm32
+    #[cfg(not(target_arch = "wasm32"))]
     if !args.no_tui {
-        tui::handle(
+        crate::snapshot::util::tui::handle(
             snapshots.clone(),
             &all_resolved_errors,
             &all_resolved_events,
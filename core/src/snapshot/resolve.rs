@@ -39,9 +39,9 @@ pub fn match_parameters(
             ));
             match function.arguments.get(&index) {
                 Some((_, potential_types)) => {
-                    // arrays are typically recorded as bytes by the decompiler's potential
-                    // types
-                    if input.contains("[]") {
+                    // arrays of any dimension are typically recorded as bytes by the
+                    // decompiler's potential types
+                    if input.ends_with(']') {
                         if !potential_types.contains(&"bytes".to_string()) {
                             logger.debug_max(&format!(
                                 "        parameter {} does not match type {} for function {}({})",
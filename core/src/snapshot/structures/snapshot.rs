@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use ethers::types::U256;
 use heimdall_common::ether::{
-    evm::core::{log::Log, opcodes::WrappedOpcode},
+    evm::core::{log::Log, opcodes::WrappedOpcode, taint::TaintedSink},
     signatures::{ResolvedError, ResolvedFunction, ResolvedLog},
 };
 
@@ -69,6 +69,19 @@ pub struct Snapshot {
 
     // control statements, such as access control
     pub control_statements: HashSet<String>,
+
+    // storage writes, call targets, and value transfers influenced by calldata
+    pub tainted_sinks: Vec<TaintedSink>,
+
+    // exceptional halts, such as invalid jumps, which ended paths through the function
+    pub faults: Vec<String>,
+
+    // whether any path through the function reverts or halts exceptionally, including the
+    // compiler's checks, such as rejecting value sent to non-payable functions
+    pub can_revert: bool,
+
+    // opcodes used by the function which warrant review, such as SELFDESTRUCT and DELEGATECALL
+    pub dangerous_opcodes: HashSet<String>,
 }
 
 #[derive(Clone, Debug)]
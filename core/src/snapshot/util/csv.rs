@@ -36,6 +36,10 @@ pub fn generate_csv(
             "Average Gas Used",
             "External Calls Made",
             "Control Statements",
+            "Tainted Sinks",
+            "Faults",
+            "Can Revert",
+            "Dangerous Opcodes",
         ]
         .join(","),
     );
@@ -105,6 +109,23 @@ pub fn generate_csv(
         let control_statements_column =
             snapshot.control_statements.clone().into_iter().collect::<Vec<_>>().join("\n");
 
+        // build tainted sinks column
+        let tainted_sinks_column = snapshot
+            .tainted_sinks
+            .iter()
+            .map(|sink| sink.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // build faults column
+        let faults_column = snapshot.faults.join("\n");
+
+        // build dangerous opcodes column
+        let mut dangerous_opcodes = snapshot.dangerous_opcodes.iter().collect::<Vec<_>>();
+        dangerous_opcodes.sort();
+        let dangerous_opcodes_column =
+            dangerous_opcodes.into_iter().cloned().collect::<Vec<_>>().join("\n");
+
         // push column values
         line.push(snapshot.selector.clone());
         line.push(match &snapshot.resolved_function {
@@ -127,6 +148,10 @@ pub fn generate_csv(
         line.push(snapshot.gas_used.avg.to_string());
         line.push(format!("\"{external_calls_column}\""));
         line.push(format!("\"{control_statements_column}\""));
+        line.push(format!("\"{tainted_sinks_column}\""));
+        line.push(format!("\"{faults_column}\""));
+        line.push(snapshot.can_revert.to_string());
+        line.push(format!("\"{dangerous_opcodes_column}\""));
 
         lines.push(line.join(","));
     }
@@ -0,0 +1,100 @@
+use std::collections::{HashMap, HashSet};
+
+use heimdall_common::{
+    ether::signatures::{ResolvedError, ResolvedLog},
+    utils::strings::encode_hex_reduced,
+};
+use serde_json::{json, Value};
+
+use crate::snapshot::structures::snapshot::Snapshot;
+
+/// Build the snapshot data as JSON, with the same fields as the CSV, one object per function.
+pub fn generate_json(
+    snapshots: &[Snapshot],
+    resolved_errors: &HashMap<String, ResolvedError>,
+    resolved_events: &HashMap<String, ResolvedLog>,
+) -> Value {
+    // sets are sorted, so the output is the same between runs
+    let sorted = |set: &HashSet<String>| {
+        let mut values = set.iter().cloned().collect::<Vec<_>>();
+        values.sort();
+        values
+    };
+
+    snapshots
+        .iter()
+        .map(|snapshot| {
+            let arguments = match &snapshot.resolved_function {
+                Some(function) => function.inputs.clone(),
+                None => {
+                    let mut arguments = snapshot.arguments.iter().collect::<Vec<_>>();
+                    arguments.sort_by_key(|(index, _)| **index);
+                    arguments
+                        .into_iter()
+                        .map(|(_, (_, potential_types))| {
+                            potential_types.first().cloned().unwrap_or_default()
+                        })
+                        .collect()
+                }
+            };
+
+            let events = snapshot
+                .events
+                .keys()
+                .map(|topic| {
+                    let key = encode_hex_reduced(*topic).replacen("0x", "", 1);
+                    match resolved_events.get(&key) {
+                        Some(event) => format!("{}({})", event.name, event.inputs.join(",")),
+                        None => format!("Event_{}()", &key[0..8]),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let errors = snapshot
+                .errors
+                .keys()
+                .map(|selector| {
+                    let key = encode_hex_reduced(*selector).replacen("0x", "", 1);
+                    match resolved_errors.get(&key) {
+                        Some(error) => format!("{}({})", error.name, error.inputs.join(",")),
+                        None => format!("Error_{}()", &key[0..8]),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            json!({
+                "selector": snapshot.selector,
+                "signature": match &snapshot.resolved_function {
+                    Some(function) => format!("{}({})", function.name, arguments.join(",")),
+                    None => format!("Unresolved_{}({})", snapshot.selector, arguments.join(",")),
+                },
+                "arguments": arguments,
+                "payable": snapshot.payable,
+                "view": snapshot.view && !snapshot.pure,
+                "pure": snapshot.pure,
+                "returns": snapshot.returns,
+                "entry_point": snapshot.entry_point,
+                "branch_count": snapshot.branch_count,
+                "events": events,
+                "errors": errors,
+                "storage": sorted(&snapshot.storage),
+                "strings": sorted(&snapshot.strings),
+                "addresses": sorted(&snapshot.addresses),
+                "gas_used": {
+                    "min": snapshot.gas_used.min,
+                    "max": snapshot.gas_used.max,
+                    "avg": snapshot.gas_used.avg,
+                },
+                "external_calls": snapshot.external_calls,
+                "control_statements": sorted(&snapshot.control_statements),
+                "tainted_sinks": snapshot
+                    .tainted_sinks
+                    .iter()
+                    .map(|sink| sink.to_string())
+                    .collect::<Vec<_>>(),
+                "faults": snapshot.faults,
+                "can_revert": snapshot.can_revert,
+                "dangerous_opcodes": sorted(&snapshot.dangerous_opcodes),
+            })
+        })
+        .collect()
+}
@@ -1,3 +1,6 @@
 pub mod csv;
+pub mod json;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod table;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod tui;
@@ -0,0 +1,34 @@
+use heimdall_common::{
+    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
+    ether::rpc::get_code,
+    utils::io::{file::read_bytecode, logging::Logger},
+};
+
+use crate::error::Error;
+
+/// Fetch the bytecode of the given target, which may be a contract address, raw bytecode, or a
+/// file containing bytecode.
+pub(crate) async fn get_contract_bytecode(
+    target: &str,
+    rpc_url: &str,
+    logger: &Logger,
+) -> Result<String, Error> {
+    let contract_bytecode: String;
+    if ADDRESS_REGEX.is_match(target)? {
+        // We are working with a contract address, so we need to fetch the bytecode from the RPC
+        // provider
+        contract_bytecode = get_code(target, rpc_url).await.map_err(Error::from_rpc)?;
+    } else if BYTECODE_REGEX.is_match(target)? {
+        logger.debug_max("using provided bytecode");
+        contract_bytecode = target.replacen("0x", "", 1);
+    } else {
+        logger.debug_max("using provided file.");
+
+        // We are analyzing a file or stdin, so we need to read the bytecode from it.
+        contract_bytecode = read_bytecode(target).map_err(|e| {
+            Error::ResolutionError(format!("failed to read bytecode from '{}': {e}", target))
+        })?;
+    }
+
+    Ok(contract_bytecode)
+}
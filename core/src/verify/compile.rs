@@ -0,0 +1,170 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use heimdall_common::utils::strings::decode_hex;
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// A contract's runtime bytecode, compiled from local source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledContract {
+    pub bytecode: Vec<u8>,
+
+    // the offset and length of each immutable's placeholder in the bytecode, if the compiler
+    // reports them
+    pub immutable_references: Option<Vec<(usize, usize)>>,
+}
+
+/// Build the Foundry project at `root` with `forge build`, and read the named contract's runtime
+/// bytecode and immutable references from its artifact.
+pub fn compile_forge(root: &Path, contract: &str) -> Result<CompiledContract, Error> {
+    let output = Command::new("forge")
+        .arg("build")
+        .arg("--root")
+        .arg(root)
+        .output()
+        .map_err(|e| Error::GenericError(format!("failed to run forge: {e}")))?;
+    if !output.status.success() {
+        return Err(Error::GenericError(format!(
+            "forge build failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+
+    let artifact_path = find_artifact(&root.join("out"), &format!("{contract}.json"))
+        .ok_or_else(|| Error::GenericError(format!("no artifact was found for '{contract}'.")))?;
+    let artifact: Value = serde_json::from_str(&std::fs::read_to_string(artifact_path)?)?;
+    parse_forge_artifact(&artifact)
+}
+
+/// Compile the Solidity file at `path` with `solc`, and read the named contract's runtime
+/// bytecode. solc's combined JSON doesn't include immutable references.
+pub fn compile_solc(path: &Path, contract: &str) -> Result<CompiledContract, Error> {
+    let output = Command::new("solc")
+        .args(["--combined-json", "bin-runtime"])
+        .arg(path)
+        .output()
+        .map_err(|e| Error::GenericError(format!("failed to run solc: {e}")))?;
+    if !output.status.success() {
+        return Err(Error::GenericError(format!(
+            "solc failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+
+    let combined: Value = serde_json::from_slice(&output.stdout)?;
+    parse_solc_output(&combined, contract)
+}
+
+/// finds the first file with the given name in a directory, or any of its subdirectories
+fn find_artifact(directory: &Path, name: &str) -> Option<PathBuf> {
+    let mut subdirectories = Vec::new();
+    for entry in std::fs::read_dir(directory).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirectories.push(path);
+        } else if path.file_name().is_some_and(|file_name| file_name == name) {
+            return Some(path)
+        }
+    }
+    subdirectories.iter().find_map(|subdirectory| find_artifact(subdirectory, name))
+}
+
+/// reads the runtime bytecode and immutable references from a forge artifact
+fn parse_forge_artifact(artifact: &Value) -> Result<CompiledContract, Error> {
+    let deployed_bytecode = &artifact["deployedBytecode"];
+    let bytecode = deployed_bytecode["object"]
+        .as_str()
+        .ok_or_else(|| Error::ParseError(String::from("the artifact has no runtime bytecode.")))?;
+
+    let immutable_references = deployed_bytecode["immutableReferences"]
+        .as_object()
+        .map(|references| {
+            references
+                .values()
+                .filter_map(Value::as_array)
+                .flatten()
+                .filter_map(|reference| {
+                    Some((
+                        reference["start"].as_u64()? as usize,
+                        reference["length"].as_u64()? as usize,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(CompiledContract {
+        bytecode: decode_bytecode(bytecode)?,
+        immutable_references: Some(immutable_references),
+    })
+}
+
+/// reads the named contract's runtime bytecode from solc's combined JSON, whose contracts are
+/// keyed by `<path>:<name>`
+fn parse_solc_output(combined: &Value, contract: &str) -> Result<CompiledContract, Error> {
+    let bytecode = combined["contracts"]
+        .as_object()
+        .and_then(|contracts| {
+            contracts.iter().find_map(|(key, value)| {
+                match key.rsplit(':').next() == Some(contract) {
+                    true => value["bin-runtime"].as_str(),
+                    false => None,
+                }
+            })
+        })
+        .ok_or_else(|| Error::GenericError(format!("solc didn't output '{contract}'.")))?;
+
+    Ok(CompiledContract { bytecode: decode_bytecode(bytecode)?, immutable_references: None })
+}
+
+/// decodes hex bytecode, which can't contain unlinked library placeholders
+fn decode_bytecode(bytecode: &str) -> Result<Vec<u8>, Error> {
+    let bytecode = bytecode.trim_start_matches("0x");
+    if bytecode.contains("__") {
+        return Err(Error::GenericError(String::from(
+            "the compiled bytecode references unlinked libraries.",
+        )))
+    }
+    decode_hex(bytecode).map_err(|e| Error::ParseError(format!("invalid bytecode: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::verify::compile::{parse_forge_artifact, parse_solc_output};
+
+    #[test]
+    fn test_parse_forge_artifact() {
+        let artifact = json!({
+            "deployedBytecode": {
+                "object": "0x6080604052",
+                "immutableReferences": { "12": [{ "start": 1, "length": 32 }] },
+            },
+        });
+        let contract = parse_forge_artifact(&artifact).unwrap();
+        assert_eq!(contract.bytecode, [0x60, 0x80, 0x60, 0x40, 0x52]);
+        assert_eq!(contract.immutable_references, Some(vec![(1, 32)]));
+
+        let unlinked = json!({ "deployedBytecode": { "object": "0x73__$aaaa$__" } });
+        assert!(parse_forge_artifact(&unlinked).is_err());
+    }
+
+    #[test]
+    fn test_parse_solc_output() {
+        let combined = json!({
+            "contracts": {
+                "src/Token.sol:Ownable": { "bin-runtime": "00" },
+                "src/Token.sol:Token": { "bin-runtime": "6080604052" },
+            },
+        });
+        let contract = parse_solc_output(&combined, "Token").unwrap();
+        assert_eq!(contract.bytecode, [0x60, 0x80, 0x60, 0x40, 0x52]);
+        assert_eq!(contract.immutable_references, None);
+        assert!(parse_solc_output(&combined, "Missing").is_err());
+    }
+}
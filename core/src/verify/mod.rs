@@ -0,0 +1,277 @@
+pub mod compile;
+
+use std::path::Path;
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use heimdall_common::{
+    constants::{ADDRESS_REGEX, BYTECODE_REGEX},
+    ether::{metadata::strip_metadata, rpc::get_code},
+    utils::{io::logging::Logger, strings::decode_hex},
+};
+use serde::Serialize;
+
+use crate::{
+    error::Error,
+    verify::compile::{compile_forge, compile_solc, CompiledContract},
+};
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Verify that a contract's bytecode was compiled from local source",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall verify <TARGET> --contract <NAME> [OPTIONS]"
+)]
+pub struct VerifyArgs {
+    /// The target to verify, either a contract address or its runtime bytecode.
+    #[clap(required = true)]
+    pub target: String,
+
+    /// The name of the contract in the local source to compare against.
+    #[clap(long = "contract", short = 'c', required = true)]
+    pub contract: String,
+
+    /// The Foundry project, or Solidity file, to compile.
+    #[clap(long = "project", short = 'p', default_value = ".")]
+    pub project: String,
+
+    /// The compiler to use, either `forge` or `solc`. By default, Foundry projects are built with
+    /// `forge`, and Solidity files are compiled with `solc`.
+    #[clap(long = "compiler", default_value = "auto")]
+    pub compiler: String,
+
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to use for fetching target bytecode. Separate multiple providers with
+    /// commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// The output directory to write the output to or 'print' to print to the console
+    #[clap(long = "output", short = 'o', default_value = "output", hide_default_value = true)]
+    pub output: String,
+}
+
+impl VerifyArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            target: Some(String::new()),
+            contract: Some(String::new()),
+            project: Some(String::from(".")),
+            compiler: Some(String::from("auto")),
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            output: Some(String::new()),
+        }
+    }
+}
+
+/// How closely the compiled bytecode matches the target's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchStatus {
+    // the bytecode matches, including its metadata
+    Exact,
+
+    // the bytecode matches once its metadata is removed, so the source matches, but its
+    // comments, file paths, or compiler settings may not
+    Partial,
+
+    // the bytecode doesn't match
+    None,
+}
+
+/// The result of comparing a contract's bytecode with the bytecode compiled from local source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VerifyResult {
+    pub target: String,
+    pub contract: String,
+    pub compiler: String,
+    pub status: MatchStatus,
+    pub target_length: usize,
+    pub compiled_length: usize,
+
+    // the number of immutables whose values were copied from the target before comparing
+    pub immutables: usize,
+
+    // the offset of the first byte which differs, ignoring metadata, if the bytecode doesn't
+    // match
+    pub first_difference: Option<usize>,
+}
+
+/// The main entry point for the verify module. Will compile the local source, and compare the
+/// named contract's runtime bytecode with the target's, ignoring the values of immutables.
+pub async fn verify(args: VerifyArgs) -> Result<VerifyResult, Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    let target_bytecode = if ADDRESS_REGEX.is_match(&args.target)? {
+        get_code(&args.target, &args.rpc_url).await.map_err(Error::from_rpc)?
+    } else if BYTECODE_REGEX.is_match(&args.target)? {
+        args.target.clone()
+    } else {
+        return Err(Error::ParseError(format!("invalid target '{}' .", &args.target)))
+    };
+    let target_bytecode = decode_hex(&target_bytecode.replacen("0x", "", 1))
+        .map_err(|e| Error::ParseError(format!("invalid bytecode: {e}")))?;
+
+    let project = Path::new(&args.project);
+    let compiler = match args.compiler.as_str() {
+        "auto" if project.join("foundry.toml").exists() => "forge",
+        "auto" if project.extension().is_some_and(|extension| extension == "sol") => "solc",
+        "auto" => {
+            return Err(Error::GenericError(format!(
+            "'{}' isn't a Foundry project or Solidity file. use `--compiler` to choose a compiler.",
+            &args.project
+        )))
+        }
+        "forge" => "forge",
+        "solc" => "solc",
+        compiler => return Err(Error::ParseError(format!("unknown compiler '{compiler}' ."))),
+    };
+    logger.info(&format!("compiling '{}' with {compiler}.", &args.project));
+    let compiled = match compiler {
+        "forge" => compile_forge(project, &args.contract)?,
+        _ => compile_solc(project, &args.contract)?,
+    };
+
+    let immutables = compiled
+        .immutable_references
+        .clone()
+        .unwrap_or_else(|| find_immutable_placeholders(&compiled.bytecode));
+    logger.debug(&format!("found {} immutable references.", immutables.len()));
+    let (status, first_difference) = compare_bytecode(&compiled, &immutables, &target_bytecode);
+
+    Ok(VerifyResult {
+        target: args.target.clone(),
+        contract: args.contract.clone(),
+        compiler: compiler.to_string(),
+        status,
+        target_length: target_bytecode.len(),
+        compiled_length: compiled.bytecode.len(),
+        immutables: immutables.len(),
+        first_difference,
+    })
+}
+
+/// Find the placeholders solc leaves for immutables in runtime bytecode, for compilers which don't
+/// report them. Immutables are read with `PUSH32`, whose operand is zeroed until deployment.
+pub fn find_immutable_placeholders(bytecode: &[u8]) -> Vec<(usize, usize)> {
+    let code = strip_metadata(bytecode);
+    let mut placeholders = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let push_size = match code[pc] {
+            opcode @ 0x60..=0x7f => (opcode - 0x5f) as usize,
+            _ => 0,
+        };
+        if push_size == 32 &&
+            code.get(pc + 1..pc + 33)
+                .is_some_and(|operand| operand.iter().all(|byte| *byte == 0))
+        {
+            placeholders.push((pc + 1, 32));
+        }
+        pc += push_size + 1;
+    }
+    placeholders
+}
+
+/// Compare compiled bytecode with the target's, after copying the target's immutable values into
+/// the compiled bytecode. Returns how closely they match, and the offset of the first byte which
+/// differs, ignoring metadata, if they don't.
+pub fn compare_bytecode(
+    compiled: &CompiledContract,
+    immutables: &[(usize, usize)],
+    target: &[u8],
+) -> (MatchStatus, Option<usize>) {
+    let mut normalized = compiled.bytecode.clone();
+    for (start, length) in immutables {
+        let end = start + length;
+        if end <= normalized.len() && end <= target.len() {
+            normalized[*start..end].copy_from_slice(&target[*start..end]);
+        }
+    }
+
+    if normalized == target {
+        return (MatchStatus::Exact, None)
+    }
+    let (normalized, target) = (strip_metadata(&normalized), strip_metadata(target));
+    if normalized == target {
+        return (MatchStatus::Partial, None)
+    }
+
+    let first_difference = normalized
+        .iter()
+        .zip(target)
+        .position(|(compiled, target)| compiled != target)
+        .unwrap_or_else(|| normalized.len().min(target.len()));
+    (MatchStatus::None, Some(first_difference))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verify::{
+        compare_bytecode, compile::CompiledContract, find_immutable_placeholders, MatchStatus,
+    };
+
+    // {"solc": 0x000814}
+    const METADATA: [u8; 12] =
+        [0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63, 0x43, 0x00, 0x08, 0x14, 0x00, 0x0a];
+
+    fn bytecode(immutable: u8, metadata: &[u8]) -> Vec<u8> {
+        // PUSH32 <immutable>, PUSH1 0x00, SSTORE, STOP
+        let mut bytecode = vec![0x7f];
+        bytecode.extend([immutable; 32]);
+        bytecode.extend([0x60, 0x00, 0x55, 0x00]);
+        bytecode.extend(metadata);
+        bytecode
+    }
+
+    #[test]
+    fn test_find_immutable_placeholders() {
+        assert_eq!(find_immutable_placeholders(&bytecode(0, &METADATA)), [(1, 32)]);
+        assert!(find_immutable_placeholders(&bytecode(0xff, &METADATA)).is_empty());
+    }
+
+    #[test]
+    fn test_compare_bytecode() {
+        let compiled =
+            CompiledContract { bytecode: bytecode(0, &METADATA), immutable_references: None };
+        let immutables = find_immutable_placeholders(&compiled.bytecode);
+
+        assert_eq!(
+            compare_bytecode(&compiled, &immutables, &bytecode(0xaa, &METADATA)),
+            (MatchStatus::Exact, None)
+        );
+
+        let mut other_metadata = METADATA;
+        other_metadata[9] = 0x13;
+        assert_eq!(
+            compare_bytecode(&compiled, &immutables, &bytecode(0xaa, &other_metadata)),
+            (MatchStatus::Partial, None)
+        );
+
+        let mut other_code = bytecode(0xaa, &METADATA);
+        other_code[34] = 0x01;
+        assert_eq!(
+            compare_bytecode(&compiled, &immutables, &other_code),
+            (MatchStatus::None, Some(34))
+        );
+    }
+}
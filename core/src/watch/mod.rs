@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use clap::{AppSettings, Parser};
+use derive_builder::Builder;
+use ethers::{
+    abi::decode as decode_abi,
+    providers::{Middleware, StreamExt},
+    types::{Transaction, H160, H256, U256},
+};
+use heimdall_common::{
+    ether::{
+        evm::core::types::parse_function_parameters,
+        pool::RpcPool,
+        signatures::{ResolveSelector, ResolvedFunction},
+    },
+    utils::{io::logging::Logger, strings::encode_hex},
+};
+use serde::Serialize;
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Parser, Builder)]
+#[clap(
+    about = "Stream decoded calldata of pending transactions from the mempool",
+    after_help = "For more information, read the wiki: https://jbecker.dev/r/heimdall-rs/wiki",
+    global_setting = AppSettings::DeriveDisplayOrder,
+    override_usage = "heimdall watch --pending [OPTIONS]"
+)]
+pub struct WatchArgs {
+    /// Set the output verbosity level, 1 - 5.
+    #[clap(flatten)]
+    pub verbose: clap_verbosity_flag::Verbosity,
+
+    /// The RPC provider to subscribe to, which must have a WebSocket or IPC endpoint. Separate
+    /// multiple providers with commas to fail over between them.
+    #[clap(long = "rpc-url", short, default_value = "", hide_default_value = true)]
+    pub rpc_url: String,
+
+    /// Whether to watch pending transactions. This is currently the only source which can be
+    /// watched.
+    #[clap(long = "pending")]
+    pub pending: bool,
+
+    /// Only stream transactions sent to these addresses, separated by commas.
+    #[clap(long = "target", short = 't', default_value = "", hide_default_value = true)]
+    pub target: String,
+
+    /// Only stream transactions calling these function selectors, separated by commas.
+    #[clap(long = "selectors", default_value = "", hide_default_value = true)]
+    pub selectors: String,
+
+    /// Whether to stream each transaction as a line of JSON, rather than text, set by the global
+    /// `--json` flag.
+    #[clap(skip)]
+    pub json: bool,
+
+    /// Whether to skip resolving function selectors, leaving calldata undecoded.
+    #[clap(long = "skip-resolving")]
+    pub skip_resolving: bool,
+}
+
+impl WatchArgsBuilder {
+    pub fn new() -> Self {
+        Self {
+            verbose: Some(clap_verbosity_flag::Verbosity::new(0, 1)),
+            rpc_url: Some(String::new()),
+            pending: Some(true),
+            target: Some(String::new()),
+            selectors: Some(String::new()),
+            json: Some(false),
+            skip_resolving: Some(false),
+        }
+    }
+}
+
+/// A pending transaction, with its calldata decoded if its selector resolved.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingCall {
+    pub hash: H256,
+    pub from: H160,
+    pub to: Option<H160>,
+    pub value: U256,
+
+    // the first four bytes of the calldata, without the 0x prefix
+    pub selector: String,
+    pub function: Option<ResolvedFunction>,
+}
+
+impl Display for PendingCall {
+    /// Formats the call as a single line, such as
+    /// `0x… 0xfrom -> 0xto transfer(address,uint256)(0x…, 100)`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let to = self.to.map(|to| format!("{to:?}")).unwrap_or_else(|| String::from("create"));
+        write!(f, "{:?} {:?} -> {to}", self.hash, self.from)?;
+        if !self.value.is_zero() {
+            write!(f, " ({} wei)", self.value)?;
+        }
+        match &self.function {
+            Some(function) => {
+                write!(f, " {}", function.signature)?;
+                if let Some(inputs) = &function.decoded_inputs {
+                    let inputs = inputs.iter().map(|input| input.to_string()).collect::<Vec<_>>();
+                    write!(f, "({})", inputs.join(", "))?;
+                }
+                Ok(())
+            }
+            None if self.selector.is_empty() => Ok(()),
+            None => write!(f, " 0x{}", self.selector),
+        }
+    }
+}
+
+/// Which pending transactions are streamed. Transactions must match every filter which is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchFilter {
+    pub targets: Vec<H160>,
+
+    // function selectors, without the 0x prefix
+    pub selectors: Vec<String>,
+}
+
+impl WatchFilter {
+    /// Whether the transaction matches the filter.
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let selector = encode_hex(transaction.input.iter().take(4).copied().collect());
+        (self.targets.is_empty() || transaction.to.is_some_and(|to| self.targets.contains(&to))) &&
+            (self.selectors.is_empty() || self.selectors.contains(&selector))
+    }
+}
+
+/// Watch the mempool for pending transactions which match the given filters, calling `on_call`
+/// with each as it's received and decoded. Each selector is only resolved once. This runs until
+/// the process is stopped, or the subscription ends.
+pub async fn watch(args: WatchArgs, mut on_call: impl FnMut(&PendingCall)) -> Result<(), Error> {
+    // set logger environment variable if not already set
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var(
+            "RUST_LOG",
+            match args.verbose.log_level() {
+                Some(level) => level.as_str(),
+                None => "SILENT",
+            },
+        );
+    }
+
+    let (logger, _) = Logger::new(match args.verbose.log_level() {
+        Some(level) => level.as_str(),
+        None => "SILENT",
+    });
+
+    if !args.pending {
+        return Err(Error::GenericError(String::from(
+            "only pending transactions can be watched. use `--pending` to watch the mempool.",
+        )))
+    }
+
+    let filter = WatchFilter {
+        targets: args
+            .target
+            .split(',')
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+            .map(|target| {
+                H160::from_str(target)
+                    .map_err(|_| Error::ParseError(format!("invalid target address '{target}' .")))
+            })
+            .collect::<Result<_, _>>()?,
+        selectors: args
+            .selectors
+            .split(',')
+            .map(|selector| selector.trim().trim_start_matches("0x").to_lowercase())
+            .filter(|selector| !selector.is_empty())
+            .collect(),
+    };
+
+    let (_, provider) = RpcPool::new(&args.rpc_url).pubsub_provider().await.ok_or_else(|| {
+        Error::RpcError(String::from(
+            "watching pending transactions requires a WebSocket or IPC endpoint.",
+        ))
+    })?;
+    let mut pending = provider.subscribe_pending_txs().await.map_err(|e| {
+        Error::RpcError(format!("failed to subscribe to pending transactions: {e}"))
+    })?;
+    logger.info("watching for pending transactions.");
+
+    let mut resolved_functions: HashMap<String, Option<Vec<ResolvedFunction>>> = HashMap::new();
+    while let Some(hash) = pending.next().await {
+        // transactions may be dropped from the mempool before they're fetched
+        let transaction = match provider.get_transaction(hash).await {
+            Ok(Some(transaction)) => transaction,
+            Ok(None) => continue,
+            Err(e) => {
+                logger.debug(&format!("failed to fetch pending transaction '{hash:?}': {e}"));
+                continue
+            }
+        };
+        if !filter.matches(&transaction) {
+            continue
+        }
+
+        let selector = encode_hex(transaction.input.iter().take(4).copied().collect());
+        let function = match (args.skip_resolving, transaction.input.len() >= 4) {
+            (false, true) => {
+                if !resolved_functions.contains_key(&selector) {
+                    let functions = ResolvedFunction::resolve(&selector).await;
+                    resolved_functions.insert(selector.clone(), functions);
+                }
+                decode_call(
+                    &transaction.input[4..],
+                    resolved_functions[&selector].as_deref().unwrap_or_default(),
+                )
+            }
+            _ => None,
+        };
+
+        on_call(&PendingCall {
+            hash: transaction.hash,
+            from: transaction.from,
+            to: transaction.to,
+            value: transaction.value,
+            selector,
+            function,
+        });
+    }
+
+    Err(Error::RpcError(String::from("the pending transaction subscription ended")))
+}
+
+/// decodes calldata, without its selector, with the first of the given signatures which fits it
+fn decode_call(calldata: &[u8], functions: &[ResolvedFunction]) -> Option<ResolvedFunction> {
+    functions.iter().find_map(|function| {
+        let inputs = parse_function_parameters(&function.signature).unwrap_or_default();
+        let decoded_inputs = decode_abi(&inputs, calldata).ok()?;
+        Some(ResolvedFunction { decoded_inputs: Some(decoded_inputs), ..function.clone() })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        abi::{encode, Token},
+        types::{Bytes, Transaction, H160, H256, U256},
+    };
+    use heimdall_common::ether::signatures::ResolvedFunction;
+
+    use crate::watch::{decode_call, PendingCall, WatchFilter};
+
+    fn transfer() -> ResolvedFunction {
+        ResolvedFunction {
+            name: String::from("transfer"),
+            signature: String::from("transfer(address,uint256)"),
+            inputs: vec![String::from("address"), String::from("uint256")],
+            decoded_inputs: None,
+        }
+    }
+
+    #[test]
+    fn test_watch_filter() {
+        let transaction = Transaction {
+            to: Some(H160::repeat_byte(0xaa)),
+            input: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            ..Default::default()
+        };
+
+        assert!(WatchFilter::default().matches(&transaction));
+        assert!(WatchFilter { targets: vec![H160::repeat_byte(0xaa)], selectors: Vec::new() }
+            .matches(&transaction));
+        assert!(!WatchFilter { targets: vec![H160::repeat_byte(0xbb)], selectors: Vec::new() }
+            .matches(&transaction));
+        assert!(!WatchFilter {
+            targets: vec![H160::repeat_byte(0xaa)],
+            selectors: vec![String::from("095ea7b3")],
+        }
+        .matches(&transaction));
+    }
+
+    #[test]
+    fn test_decode_call() {
+        let calldata = encode(&[Token::Address(H160::repeat_byte(0xaa)), Token::Uint(100.into())]);
+        let function = decode_call(&calldata, &[transfer()]).unwrap();
+        assert_eq!(
+            function.decoded_inputs,
+            Some(vec![Token::Address(H160::repeat_byte(0xaa)), Token::Uint(100.into())])
+        );
+
+        // calldata which doesn't fit any signature is left undecoded
+        assert_eq!(decode_call(&[0x01], &[transfer()]), None);
+    }
+
+    #[test]
+    fn test_pending_call_display() {
+        let call = PendingCall {
+            hash: H256::repeat_byte(0x11),
+            from: H160::repeat_byte(0x22),
+            to: None,
+            value: U256::from(5),
+            selector: String::new(),
+            function: None,
+        };
+        assert_eq!(call.to_string(), format!("{:?} {:?} -> create (5 wei)", call.hash, call.from));
+    }
+}
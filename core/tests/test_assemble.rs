@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+
+    use heimdall_common::ether::evm::core::hardfork::Hardfork;
+    use heimdall_core::{
+        assemble::{assemble, assemble_instructions, AssemblerArgs},
+        disassemble::{disassemble, DisassemblerArgs},
+    };
+
+    #[tokio::test]
+    async fn test_assemble_round_trip() {
+        let bytecode = "608060405234801561001057600080fd5b50600436106100365760003560e01c806306fdde031461003b578063095ea7b314610059575b600080fd5b61004361008f565b005b61005c0c5b00fe";
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            output: String::from(""),
+            labels: true,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
+        })
+        .await
+        .unwrap();
+
+        let reassembled = assemble(AssemblerArgs {
+            target: assembly,
+            verbose: Verbosity::new(0, 0),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reassembled, format!("0x{bytecode}"));
+    }
+
+    #[test]
+    fn test_assemble_patched_instruction() {
+        // replace the REVERT with a RETURN, which shouldn't affect any labels
+        let assembly =
+            "000001 PUSH1 00\n000002 DUP1\n000003 REVERT\n\nlabel_0x4:\n000004 JUMPDEST\n";
+        let patched = assembly.replace("REVERT", "RETURN");
+
+        assert_eq!(assemble_instructions(assembly).unwrap(), vec![0x60, 0x00, 0x80, 0xfd, 0x5b]);
+        assert_eq!(assemble_instructions(&patched).unwrap(), vec![0x60, 0x00, 0x80, 0xf3, 0x5b]);
+    }
+}
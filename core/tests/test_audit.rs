@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use heimdall_common::ether::evm::ext::exec::ExecutionLimits;
+    use heimdall_core::audit::{
+        access::Access,
+        heuristics::{Check, Severity},
+        AuditArgs,
+    };
+
+    #[tokio::test]
+    async fn test_audit_simple() {
+        // dispatches selector 0x12345678 to a function which selfdestructs to msg.sender
+        let bytecode = "0x60003560e01c80631234567814601157005b33ff00";
+        let report = heimdall_core::audit::audit(AuditArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            skip_resolving: true,
+            limits: ExecutionLimits::default(),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(report.functions.len(), 1);
+        assert_eq!(report.functions[0].access, Access::Open);
+
+        let findings = report.findings;
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].check, Check::UnprotectedSelfdestruct);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert_eq!(findings[0].selector, "12345678");
+        assert_eq!(findings[0].instruction, 20);
+    }
+}
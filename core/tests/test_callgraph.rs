@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use heimdall_common::ether::evm::ext::exec::ExecutionLimits;
+    use heimdall_core::callgraph::{output::build_callgraph, CallGraphArgs};
+
+    #[tokio::test]
+    async fn test_callgraph_simple() {
+        // dispatches selector 0x12345678 to a function which emits an event with topic 0xaa and
+        // calls 0x1111111111111111111111111111111111111111
+        let bytecode = "0x60003560e01c80631234567814601157005b60aa60006000a160006000600060006000731111111111111111111111111111111111111111\
+                        5af100";
+        let result = heimdall_core::callgraph::callgraph(CallGraphArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            skip_resolving: true,
+            output: String::from(""),
+            limits: ExecutionLimits::default(),
+        })
+        .await
+        .unwrap();
+
+        let output = build_callgraph(&result);
+
+        for line in &[
+            String::from("0 [ label = \"function Unresolved_12345678()\" ]"),
+            String::from("1 [ label = \"0x1111111111111111111111111111111111111111\" ]"),
+            String::from("2 [ label = \"event Event_aa()\" ]"),
+            String::from("0 -> 1 [ label = \"CALL\" ]"),
+            String::from("0 -> 2 [ label = \"emits\" ]"),
+        ] {
+            assert!(output.contains(line))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_callgraph_internal_calls() {
+        // dispatches selectors 0x11111111 and 0x22222222 to functions which both call an internal
+        // function at 0x2b, which emits an event with topic 0xaa and returns to its caller
+        let bytecode = "0x60003560e01c80631111111114601b57806322222222146023570\
+                        05b6021602b565b005b6029602b565b005b60aa60006000a156";
+        let result = heimdall_core::callgraph::callgraph(CallGraphArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            skip_resolving: true,
+            output: String::from(""),
+            limits: ExecutionLimits::default(),
+        })
+        .await
+        .unwrap();
+
+        let output = build_callgraph(&result);
+
+        for line in &[
+            String::from("0 [ label = \"function Unresolved_11111111()\" ]"),
+            String::from("1 [ label = \"function Unresolved_22222222()\" ]"),
+            String::from("2 [ label = \"internal Internal_2b()\" ]"),
+            String::from("0 -> 2 [ label = \"internal\" ]"),
+            String::from("1 -> 2 [ label = \"internal\" ]"),
+        ] {
+            assert!(output.contains(line), "{output}")
+        }
+
+        // the functions don't call each other, and their own code isn't an internal function
+        assert!(!output.contains("0 -> 1"));
+        assert!(!output.contains("1 -> 0"));
+        assert_eq!(result.node_count(), 4);
+    }
+}
@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod benchmark {
     use clap_verbosity_flag::Verbosity;
-    use heimdall_common::utils::testing::benchmarks::async_bench;
+    use heimdall_common::{
+        ether::evm::{core::hardfork::Hardfork, ext::exec::ExecutionLimits},
+        utils::testing::benchmarks::async_bench,
+    };
 
     use heimdall_core::cfg::CFGArgs;
 
@@ -14,7 +17,11 @@ mod benchmark {
                 rpc_url: String::from("https://eth.llamarpc.com"),
                 default: true,
                 color_edges: false,
+                diff: String::new(),
+                format: String::from("dot"),
                 output: String::from(""),
+                evm_version: Hardfork::default(),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::cfg::cfg(args).await;
         }
@@ -31,7 +38,11 @@ mod benchmark {
                 rpc_url: String::from("https://eth.llamarpc.com"),
                 default: true,
                 color_edges: false,
+                diff: String::new(),
+                format: String::from("dot"),
                 output: String::from(""),
+                evm_version: Hardfork::default(),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::cfg::cfg(args).await;
         }
@@ -43,6 +54,7 @@ mod benchmark {
 #[cfg(test)]
 mod integration_tests {
     use clap_verbosity_flag::Verbosity;
+    use heimdall_common::ether::evm::{core::hardfork::Hardfork, ext::exec::ExecutionLimits};
     use heimdall_core::cfg::CFGArgs;
     use petgraph::dot::Dot;
 
@@ -54,7 +66,11 @@ mod integration_tests {
             rpc_url: String::from("https://eth.llamarpc.com"),
             default: true,
             color_edges: false,
+            diff: String::new(),
+            format: String::from("dot"),
             output: String::from(""),
+            evm_version: Hardfork::default(),
+            limits: ExecutionLimits::default(),
         })
         .await
         .unwrap();
@@ -77,7 +93,11 @@ mod integration_tests {
             rpc_url: String::from("https://eth.llamarpc.com"),
             default: true,
             color_edges: false,
+            diff: String::new(),
+            format: String::from("dot"),
             output: String::from(""),
+            evm_version: Hardfork::default(),
+            limits: ExecutionLimits::default(),
         })
         .await
         .unwrap();
@@ -88,4 +108,55 @@ mod integration_tests {
             assert!(output.contains(line))
         }
     }
+
+    #[tokio::test]
+    async fn test_cfg_ignores_metadata() {
+        // revert(0, 0), followed by the solc metadata
+        let result = heimdall_core::cfg::cfg(CFGArgs {
+            target: String::from("0x600080fdfea2646970667358221220aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa64736f6c63430008130033"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            color_edges: false,
+            diff: String::new(),
+            format: String::from("dot"),
+            output: String::from(""),
+            evm_version: Hardfork::default(),
+            limits: ExecutionLimits::default(),
+        })
+        .await
+        .unwrap();
+
+        let output = format!("{}", Dot::with_config(&result, &[]));
+
+        // only the trailing INVALID is unreachable, the metadata isn't code
+        assert_eq!(result.node_count(), 2, "{output}");
+        assert!(!output.contains("SWAP3"));
+    }
+
+    #[cfg(feature = "z3")]
+    #[tokio::test]
+    async fn test_cfg_prunes_infeasible_paths() {
+        // if (calldataload(4) > 10) { if (calldataload(4) < 5) { ... } }
+        let result = heimdall_core::cfg::cfg(CFGArgs {
+            target: String::from("0x600a60043511600a57005b600560043510601557005b00"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            color_edges: false,
+            diff: String::new(),
+            format: String::from("dot"),
+            output: String::from(""),
+            evm_version: Hardfork::default(),
+            limits: ExecutionLimits::default(),
+        })
+        .await
+        .unwrap();
+
+        let output = format!("{}", Dot::with_config(&result, &[]));
+
+        // the inner branch can never be taken, so its block is never reached
+        assert!(output.contains("1 -> 2 [ label = \"false\" ]"));
+        assert!(!output.contains("1 -> 4"));
+    }
 }
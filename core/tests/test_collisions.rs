@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod integration_tests {
+    #[cfg(unix)]
+    use std::{io::Write, os::unix::net::UnixListener};
+
+    use clap_verbosity_flag::Verbosity;
+    use heimdall_core::collisions::{CollisionReport, CollisionsArgs};
+    #[cfg(unix)]
+    use heimdall_core::{collisions::Collision, proxy::constants::IMPLEMENTATION_SLOT};
+    #[cfg(unix)]
+    use serde_json::{json, Deserializer, Value};
+
+    #[tokio::test]
+    async fn test_collisions_bytecode() {
+        // dispatches selector 0x12345678, and has no implementation or facets to collide with
+        let bytecode = "0x60003560e01c80631234567814601157005b600100";
+        let report = heimdall_core::collisions::collisions(CollisionsArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            skip_resolving: true,
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(report, CollisionReport { collisions: Vec::new(), ambiguous: Vec::new() });
+        assert_eq!(report.to_string(), "no selector collisions found.\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_collisions_proxy() {
+        // an EIP-1967 proxy which dispatches 0x12345678, and an implementation which dispatches
+        // 0x12345678 and 0xaabbccdd
+        let proxy = "0x00000000000000000000000000000000c0111de5";
+        let implementation = "0x000000000000000000000000000000000001a1e5";
+        let proxy_code = "0x60003560e01c80631234567814601157005b600100";
+        let implementation_code =
+            "0x60003560e01c80631234567814601b578063aabbccdd14601d57005b005b00";
+
+        // a node, reached over IPC, which serves the proxy and its implementation
+        let path = std::env::temp_dir().join("heimdall_test_collisions_proxy.ipc");
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut writer = stream.unwrap();
+                let reader = writer.try_clone().unwrap();
+                std::thread::spawn(move || {
+                    for request in Deserializer::from_reader(reader).into_iter::<Value>() {
+                        let Ok(request) = request else { return };
+                        let params = &request["params"];
+                        let result = match request["method"].as_str() {
+                            Some("eth_chainId") => json!("0x48e1d"),
+                            Some("eth_getCode") if params[0] == proxy => json!(proxy_code),
+                            Some("eth_getCode") if params[0] == implementation => {
+                                json!(implementation_code)
+                            }
+                            Some("eth_getStorageAt") if params[1] == IMPLEMENTATION_SLOT => {
+                                json!(format!("0x{:0>64}", &implementation[2..]))
+                            }
+                            Some("eth_getStorageAt") => json!(format!("0x{:064}", 0)),
+                            _ => json!("0x"),
+                        };
+                        let response =
+                            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result });
+                        writer.write_all(response.to_string().as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        let report = heimdall_core::collisions::collisions(CollisionsArgs {
+            target: String::from(proxy),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: path.to_str().unwrap().to_string(),
+            skip_resolving: true,
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            report.collisions,
+            vec![Collision {
+                selector: String::from("12345678"),
+                signature: None,
+                contracts: vec![proxy.to_string(), implementation.to_string()],
+                dispatched_to: None,
+            }]
+        );
+    }
+}
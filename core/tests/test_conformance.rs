@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use heimdall_common::ether::evm::ext::exec::ExecutionLimits;
+    use heimdall_core::conformance::{standards::Standard, ConformanceArgs, Status};
+
+    #[tokio::test]
+    async fn test_conformance_transfer_returns_nothing() {
+        // dispatches transfer(address,uint256) to a function which emits Transfer and returns
+        // nothing, like USDT
+        let bytecode = "0x60003560e01c8063a9059cbb14601157005b\
+                        7fddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef60006000a100";
+        let report = heimdall_core::conformance::conformance(ConformanceArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            skip_resolving: true,
+            standards: vec![Standard::Erc20],
+            limits: ExecutionLimits::default(),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        let erc20 = &report.standards[0];
+        assert!(!erc20.conforms);
+        for function in &erc20.functions {
+            match function.signature.as_str() {
+                "transfer(address,uint256)" => assert_eq!(
+                    function.status,
+                    Status::Mismatched {
+                        reason: String::from("returns nothing, but should return bool")
+                    }
+                ),
+                _ => assert_eq!(function.status, Status::Missing),
+            }
+        }
+        assert_eq!(erc20.events[0].status, Status::Present);
+        assert_eq!(erc20.events[1].status, Status::Missing);
+        assert!(report.extra_functions.is_empty());
+    }
+}
@@ -16,6 +16,10 @@ mod benchmark {
                 explain: false,
                 default: true,
                 truncate_calldata: false,
+                format: String::from("trace"),
+                no_header: false,
+                cast_command: String::new(),
+                signature: String::new(),
             };
             let _ = heimdall_core::decode::decode(args).await;
         }
@@ -34,6 +38,10 @@ mod benchmark {
                 explain: false,
                 default: true,
                 truncate_calldata: false,
+                format: String::from("trace"),
+                no_header: false,
+                cast_command: String::new(),
+                signature: String::new(),
             };
             let _ = heimdall_core::decode::decode(args).await;
         }
@@ -52,6 +60,10 @@ mod benchmark {
                 explain: false,
                 default: true,
                 truncate_calldata: false,
+                format: String::from("trace"),
+                no_header: false,
+                cast_command: String::new(),
+                signature: String::new(),
             };
             let _ = heimdall_core::decode::decode(args).await;
         }
@@ -70,6 +82,10 @@ mod benchmark {
                 explain: false,
                 default: true,
                 truncate_calldata: false,
+                format: String::from("trace"),
+                no_header: false,
+                cast_command: String::new(),
+                signature: String::new(),
             };
             let _ = heimdall_core::decode::decode(args).await;
         }
@@ -93,6 +109,10 @@ mod tests {
             explain: false,
             default: true,
             truncate_calldata: false,
+            format: String::from("trace"),
+            no_header: false,
+            cast_command: String::new(),
+            signature: String::new(),
         };
         let _ = heimdall_core::decode::decode(args).await;
     }
@@ -107,6 +127,10 @@ mod tests {
             explain: false,
             default: true,
             truncate_calldata: false,
+            format: String::from("trace"),
+            no_header: false,
+            cast_command: String::new(),
+            signature: String::new(),
         };
         let _ = heimdall_core::decode::decode(args).await;
     }
@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod benchmark {
     use clap_verbosity_flag::Verbosity;
-    use heimdall_common::utils::testing::benchmarks::async_bench;
+    use heimdall_common::{
+        ether::evm::ext::exec::ExecutionLimits, utils::testing::benchmarks::async_bench,
+    };
 
     use heimdall_core::decompile::DecompilerArgs;
 
@@ -16,7 +18,13 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: true,
                 include_yul: false,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -35,7 +43,13 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: true,
                 include_yul: false,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -54,7 +68,13 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: true,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -73,7 +93,13 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: true,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -92,7 +118,13 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: false,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -111,7 +143,13 @@ mod benchmark {
                 skip_resolving: true,
                 include_solidity: false,
                 include_yul: false,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::decompile::decompile(args).await;
         }
@@ -123,7 +161,7 @@ mod benchmark {
 #[cfg(test)]
 mod integration_tests {
     use clap_verbosity_flag::Verbosity;
-    use heimdall_common::utils::io::file::delete_path;
+    use heimdall_common::{ether::evm::ext::exec::ExecutionLimits, utils::io::file::delete_path};
     use heimdall_core::decompile::DecompilerArgs;
 
     #[tokio::test]
@@ -136,7 +174,13 @@ mod integration_tests {
             skip_resolving: true,
             include_solidity: true,
             include_yul: false,
+            etherscan_api_key: String::new(),
+            force_decompile: false,
+            slither: false,
+            include_artifact: false,
+            signatures: Vec::new(),
             output: String::from(""),
+            limits: ExecutionLimits::default(),
         })
         .await
         .unwrap();
@@ -164,7 +208,13 @@ mod integration_tests {
             skip_resolving: true,
             include_solidity: true,
             include_yul: false,
+            etherscan_api_key: String::new(),
+            force_decompile: false,
+            slither: false,
+            include_artifact: false,
+            signatures: Vec::new(),
             output: String::from(""),
+            limits: ExecutionLimits::default(),
         })
         .await
         .unwrap();
@@ -199,7 +249,13 @@ mod integration_tests {
             skip_resolving: true,
             include_solidity: true,
             include_yul: false,
+            etherscan_api_key: String::new(),
+            force_decompile: false,
+            slither: false,
+            include_artifact: false,
+            signatures: Vec::new(),
             output: String::from(""),
+            limits: ExecutionLimits::default(),
         })
         .await
         .unwrap();
@@ -216,6 +272,69 @@ mod integration_tests {
         delete_path(&String::from("./output/tests/decompile/test3"));
     }
 
+    #[tokio::test]
+    async fn test_decompile_transient_lock() {
+        // increment() guarded by a transient storage reentrancy lock in slot 0
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            target: String::from("0x60003560e01c8063d09de08a1461001557600080fd5b60005c1561002257600080fd5b600160005d600054600101600055600060005d00"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            skip_resolving: true,
+            include_solidity: true,
+            include_yul: false,
+            etherscan_api_key: String::new(),
+            force_decompile: false,
+            slither: false,
+            include_artifact: false,
+            signatures: Vec::new(),
+            output: String::from(""),
+            limits: ExecutionLimits::default(),
+        })
+        .await
+        .unwrap();
+
+        // assert that the output is correct
+        for line in &[
+            "/// @notice             transient storage slot 0 is used as a reentrancy lock",
+            "require(!transient[0]);",
+            "transient[0] = 0x01;",
+            "transient[0] = 0;",
+        ] {
+            println!("{line}");
+            assert!(result.source.clone().unwrap().contains(line));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decompile_execution_limits() {
+        // the same function as above, with no branches allowed past the entry point
+        let result = heimdall_core::decompile::decompile(DecompilerArgs {
+            target: String::from("0x60003560e01c8063d09de08a1461001557600080fd5b60005c1561002257600080fd5b600160005d600054600101600055600060005d00"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            default: true,
+            skip_resolving: true,
+            include_solidity: true,
+            include_yul: false,
+            etherscan_api_key: String::new(),
+            force_decompile: false,
+            slither: false,
+            include_artifact: false,
+            signatures: Vec::new(),
+            output: String::from(""),
+            limits: ExecutionLimits { max_branches: Some(0), ..Default::default() },
+        })
+        .await
+        .unwrap();
+
+        let source = result.source.unwrap();
+        assert!(source.contains(
+            "symbolic execution stopped early after reaching the branch limit, so some paths may be missing"
+        ));
+        assert!(!source.contains("transient[0] = 0x01;"));
+    }
+
     /// Thorough testing for decompilation across a large number of contracts
     /// Runs on the top 100 contracts for 2023-06-26
     ///
@@ -305,7 +424,13 @@ mod integration_tests {
                 skip_resolving: true,
                 include_solidity: true,
                 include_yul: false,
+                etherscan_api_key: String::new(),
+                force_decompile: false,
+                slither: false,
+                include_artifact: false,
+                signatures: Vec::new(),
                 output: String::from(""),
+                limits: ExecutionLimits::default(),
             })
             .await
             .unwrap();
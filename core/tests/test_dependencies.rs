@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod integration_tests {
+    use clap_verbosity_flag::Verbosity;
+    use heimdall_common::ether::evm::ext::exec::ExecutionLimits;
+    use heimdall_core::dependencies::{
+        labels::LabelSource, DependenciesArgs, Dependency, DependencyCall,
+    };
+
+    #[tokio::test]
+    async fn test_dependencies_simple() {
+        // dispatches selector 0x12345678 to a function which calls
+        // 0x1111111111111111111111111111111111111111, masked with 0xff..ff, and then staticcalls
+        // the ecrecover precompile
+        let bytecode = "0x60003560e01c80631234567814601157005b600060006000600060007311111111111111111111111111111111111111117\
+                        3ffffffffffffffffffffffffffffffffffffffff165af150600060006000600060015afa00";
+        let labels = std::env::temp_dir().join("heimdall_test_dependencies_labels.json");
+        std::fs::write(&labels, r#"{ "0x1111111111111111111111111111111111111111": "Router" }"#)
+            .unwrap();
+
+        let result = heimdall_core::dependencies::dependencies(DependenciesArgs {
+            target: String::from(bytecode),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            skip_resolving: true,
+            labels: labels.to_string_lossy().to_string(),
+            etherscan_api_key: String::from(""),
+            limits: ExecutionLimits::default(),
+            output: String::from(""),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(result.dynamic_calls, 0);
+        assert_eq!(
+            result.dependencies,
+            vec![
+                Dependency {
+                    address: String::from("0x0000000000000000000000000000000000000001"),
+                    label: Some(String::from("ecrecover")),
+                    label_source: Some(LabelSource::Precompile),
+                    calls: vec![DependencyCall {
+                        selector: String::from("12345678"),
+                        function: None,
+                        opcode: String::from("STATICCALL"),
+                    }],
+                },
+                Dependency {
+                    address: String::from("0x1111111111111111111111111111111111111111"),
+                    label: Some(String::from("Router")),
+                    label_source: Some(LabelSource::File),
+                    calls: vec![DependencyCall {
+                        selector: String::from("12345678"),
+                        function: None,
+                        opcode: String::from("CALL"),
+                    }],
+                },
+            ]
+        );
+    }
+}
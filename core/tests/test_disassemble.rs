@@ -2,7 +2,9 @@
 mod benchmarks {
     use clap_verbosity_flag::Verbosity;
 
-    use heimdall_common::utils::testing::benchmarks::async_bench;
+    use heimdall_common::{
+        ether::evm::core::hardfork::Hardfork, utils::testing::benchmarks::async_bench,
+    };
     use heimdall_core::disassemble::{disassemble, DisassemblerArgs};
 
     #[tokio::test]
@@ -14,6 +16,10 @@ mod benchmarks {
                 rpc_url: String::from(""),
                 decimal_counter: true,
                 output: String::from(""),
+                labels: false,
+                annotate: false,
+                json: false,
+                evm_version: Hardfork::default(),
             }).await.unwrap();
         }
 
@@ -27,6 +33,7 @@ mod integration_tests {
 
     use clap_verbosity_flag::Verbosity;
 
+    use heimdall_common::ether::evm::core::hardfork::Hardfork;
     use heimdall_core::disassemble::{disassemble, DisassemblerArgs};
 
     #[tokio::test]
@@ -40,6 +47,10 @@ mod integration_tests {
             rpc_url: String::from(""),
             decimal_counter: false,
             output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
         })
         .await
         .unwrap();
@@ -58,6 +69,110 @@ mod integration_tests {
             rpc_url: String::from(""),
             decimal_counter: true,
             output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(expected, assembly);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_json() {
+        let bytecode = "6000611000365f00";
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: true,
+            evm_version: Hardfork::default(),
+        })
+        .await
+        .unwrap();
+
+        let instructions: serde_json::Value = serde_json::from_str(&assembly).unwrap();
+        assert_eq!(
+            instructions,
+            serde_json::json!([
+                { "pc": 0, "opcode": "PUSH1", "push_data": "00", "gas": 3 },
+                { "pc": 2, "opcode": "PUSH2", "push_data": "1000", "gas": 3 },
+                { "pc": 5, "opcode": "CALLDATASIZE", "push_data": null, "gas": 2 },
+                { "pc": 6, "opcode": "PUSH0", "push_data": null, "gas": 2 },
+                { "pc": 7, "opcode": "STOP", "push_data": null, "gas": 0 },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_labels() {
+        // PUSH1 0x01 PUSH1 0x06 JUMPI STOP JUMPDEST PUSH1 0x06 JUMP
+        let bytecode = "6001600657005b600656";
+        let expected = String::from("000001 PUSH1 01\n000003 PUSH1 label_0x6\n000004 JUMPI \n\n000005 STOP \n\nlabel_0x6:\n000006 JUMPDEST \n000008 PUSH1 label_0x6\n000009 JUMP \n");
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            output: String::from(""),
+            labels: true,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(expected, assembly);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_eof() {
+        // section 0: PUSH0 RJUMPI +1 STOP CALLF 1 STOP, section 1: RETF, data: 0xaabb
+        let bytecode = "ef000101000802000200090001040002000080000100000000\
+                        5fe1000100e3000100e4aabb";
+        let expected = String::from("; code section 0 (inputs: 0, outputs: non-returning, max stack height: 1)\n000019 PUSH0 \n00001a RJUMPI 0001\n00001d STOP \n00001e CALLF 0001\n000021 STOP \n\n; code section 1 (inputs: 0, outputs: 0, max stack height: 0)\n000022 RETF \n\n; data section at 000023 (2 bytes)\naabb\n");
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(expected, assembly);
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_metadata() {
+        let bytecode = "6080604052600080fdfea2646970667358221220aacffa0494cd3f043493eee9c720bca9d5ef505ae7230ffc3d88c49ceeb7441e64736f6c63430008140033";
+        let expected = String::from("000001 PUSH1 80\n000003 PUSH1 40\n000004 MSTORE \n000006 PUSH1 00\n000007 DUP1 \n000008 REVERT \n000009 INVALID \n; metadata at 00000a (53 bytes)\n; ipfs: QmZqRxGenqp39eEpBmVi6gxGGrjc1bk5Cx2Z9K125cNqcy\n; solc: 0.8.20\n");
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
         })
         .await
         .unwrap();
@@ -76,6 +191,10 @@ mod integration_tests {
             rpc_url: String::from(""),
             decimal_counter: true,
             output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
         })
         .await
         .unwrap();
@@ -94,6 +213,10 @@ mod integration_tests {
             rpc_url: String::from(""),
             decimal_counter: true,
             output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
         })
         .await
         .unwrap();
@@ -115,6 +238,10 @@ mod integration_tests {
             rpc_url: String::from(""),
             decimal_counter: true,
             output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
         })
         .await
         .unwrap();
@@ -125,9 +252,59 @@ mod integration_tests {
         std::fs::remove_file("test_disassemble_from_file").unwrap();
     }
 
+    #[tokio::test]
+    async fn test_disassemble_from_binary_file() {
+        let bytecode = [0x36, 0x60, 0x00, 0x60, 0x00, 0x37];
+        let expected = String::from("0 CALLDATASIZE \n2 PUSH1 00\n4 PUSH1 00\n5 CALLDATACOPY \n");
+
+        // write raw bytecode to a .bin file at the cwd
+        let mut file = std::fs::File::create("test_disassemble_from_binary_file.bin").unwrap();
+        file.write_all(&bytecode).unwrap();
+        let assembly = disassemble(DisassemblerArgs {
+            target: String::from("test_disassemble_from_binary_file.bin"),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: true,
+            output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(expected, assembly);
+
+        // delete the file
+        std::fs::remove_file("test_disassemble_from_binary_file.bin").unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disassemble_evm_version() {
+        // PUSH0 PUSH0 RETURN
+        let bytecode = "5f5ff3";
+
+        let assembly = disassemble(DisassemblerArgs {
+            target: bytecode.to_owned(),
+            verbose: Verbosity::new(0, 0),
+            rpc_url: String::from(""),
+            decimal_counter: false,
+            output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::London,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(assembly, "000000 unknown \n000001 unknown \n000002 RETURN \n");
+    }
+
     #[tokio::test]
     async fn test_disassemble_from_rpc() {
-        let expected = String::from("1 PUSH1 80\n3 PUSH1 40\n4 MSTORE \n25 PUSH20 ffffffffffffffffffffffffffffffffffffffff\n27 PUSH1 00\n28 SLOAD \n29 AND \n30 CALLDATASIZE \n32 PUSH1 00\n33 DUP1 \n34 CALLDATACOPY \n36 PUSH1 00\n37 DUP1 \n38 CALLDATASIZE \n40 PUSH1 00\n41 DUP5 \n42 GAS \n43 DELEGATECALL \n44 RETURNDATASIZE \n46 PUSH1 00\n47 DUP1 \n48 RETURNDATACOPY \n50 PUSH1 00\n51 DUP2 \n52 EQ \n53 ISZERO \n55 PUSH1 3d\n56 JUMPI \n57 RETURNDATASIZE \n59 PUSH1 00\n60 REVERT \n61 JUMPDEST \n62 RETURNDATASIZE \n64 PUSH1 00\n65 RETURN \n66 INVALID \n67 LOG1 \n74 PUSH6 627a7a723058\n75 SHA3 \n76 unknown \n107 PUSH30 648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8\n108 STOP \n109 unknown \n");
+        let expected = String::from("1 PUSH1 80\n3 PUSH1 40\n4 MSTORE \n25 PUSH20 ffffffffffffffffffffffffffffffffffffffff\n27 PUSH1 00\n28 SLOAD \n29 AND \n30 CALLDATASIZE \n32 PUSH1 00\n33 DUP1 \n34 CALLDATACOPY \n36 PUSH1 00\n37 DUP1 \n38 CALLDATASIZE \n40 PUSH1 00\n41 DUP5 \n42 GAS \n43 DELEGATECALL \n44 RETURNDATASIZE \n46 PUSH1 00\n47 DUP1 \n48 RETURNDATACOPY \n50 PUSH1 00\n51 DUP2 \n52 EQ \n53 ISZERO \n55 PUSH1 3d\n56 JUMPI \n57 RETURNDATASIZE \n59 PUSH1 00\n60 REVERT \n61 JUMPDEST \n62 RETURNDATASIZE \n64 PUSH1 00\n65 RETURN \n66 INVALID \n; metadata at 67 (43 bytes)\n; bzzr0: 0x");
 
         let assembly = disassemble(DisassemblerArgs {
             target: String::from("0xafc2f2d803479a2af3a72022d54cc0901a0ec0d6"),
@@ -135,10 +312,18 @@ mod integration_tests {
             rpc_url: String::from("https://eth.llamarpc.com"),
             decimal_counter: true,
             output: String::from(""),
+            labels: false,
+            annotate: false,
+            json: false,
+            evm_version: Hardfork::default(),
         })
         .await
         .unwrap();
 
-        assert_eq!(expected, assembly);
+        // the swarm hash is decoded from the trailing metadata rather than disassembled
+        assert!(assembly.starts_with(&expected));
+        assert!(
+            assembly.ends_with("7d648b83cfac072cbccefc2ffc62a6999d4a050ee87a721942de1da9670db8\n")
+        );
     }
 }
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod integration_tests {
+    use heimdall_core::{
+        error::Error,
+        exec::{exec, ExecArgsBuilder},
+    };
+
+    #[tokio::test]
+    async fn test_exec_trace() {
+        // returns calldataload(0) + 1
+        let execution = exec(
+            ExecArgsBuilder::new()
+                .target(String::from("0x60003560010160005260206000f3"))
+                .calldata(String::from(
+                    "0x0000000000000000000000000000000000000000000000000000000000000029",
+                ))
+                .trace(true)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert!(!execution.failed);
+        assert_eq!(
+            execution.return_value,
+            "000000000000000000000000000000000000000000000000000000000000002a"
+        );
+        assert_eq!(
+            execution.struct_logs.iter().map(|log| log.op.as_str()).collect::<Vec<&str>>(),
+            vec![
+                "PUSH1",
+                "CALLDATALOAD",
+                "PUSH1",
+                "ADD",
+                "PUSH1",
+                "MSTORE",
+                "PUSH1",
+                "PUSH1",
+                "RETURN"
+            ]
+        );
+        assert_eq!(execution.struct_logs[3].stack, vec!["0x29", "0x1"]);
+
+        // the trace is serialized in geth's format
+        let json = serde_json::to_value(&execution).unwrap();
+        assert_eq!(json["structLogs"][0]["gasCost"], 3);
+        assert!(json["structLogs"][0].get("storage").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_exec_without_trace() {
+        let execution =
+            exec(ExecArgsBuilder::new().target(String::from("0x60006000fd")).build().unwrap())
+                .await
+                .unwrap();
+
+        assert!(execution.failed);
+        assert!(execution.struct_logs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exec_errors() {
+        let invalid_calldata = exec(
+            ExecArgsBuilder::new()
+                .target(String::from("0x00"))
+                .calldata(String::from("0xzz"))
+                .build()
+                .unwrap(),
+        )
+        .await;
+        assert!(matches!(invalid_calldata, Err(Error::ParseError(_))));
+
+        let eof =
+            exec(ExecArgsBuilder::new().target(String::from("0xef0001")).build().unwrap()).await;
+        assert!(matches!(eof, Err(Error::VmError(_))));
+
+        let missing_file = exec(
+            ExecArgsBuilder::new().target(String::from("./does-not-exist.bin")).build().unwrap(),
+        )
+        .await;
+        assert!(matches!(missing_file, Err(Error::ResolutionError(_))));
+    }
+}
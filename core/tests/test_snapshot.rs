@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod benchmark {
     use clap_verbosity_flag::Verbosity;
-    use heimdall_common::utils::testing::benchmarks::async_bench;
+    use heimdall_common::{
+        ether::evm::{core::hardfork::Hardfork, ext::exec::ExecutionLimits},
+        utils::testing::benchmarks::async_bench,
+    };
 
     use heimdall_core::snapshot::SnapshotArgs;
 
@@ -16,6 +19,8 @@ mod benchmark {
                 skip_resolving: true,
                 no_tui: true,
                 output: String::from(""),
+                evm_version: Hardfork::default(),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
         }
@@ -34,6 +39,8 @@ mod benchmark {
                 skip_resolving: true,
                 no_tui: true,
                 output: String::from(""),
+                evm_version: Hardfork::default(),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
         }
@@ -45,7 +52,10 @@ mod benchmark {
 #[cfg(test)]
 mod integration_tests {
     use clap_verbosity_flag::Verbosity;
-    use heimdall_common::utils::io::file::delete_path;
+    use heimdall_common::{
+        ether::evm::{core::hardfork::Hardfork, ext::exec::ExecutionLimits},
+        utils::io::file::delete_path,
+    };
     use heimdall_core::snapshot::SnapshotArgs;
 
     #[tokio::test]
@@ -58,6 +68,8 @@ mod integration_tests {
             skip_resolving: true,
             no_tui: true,
             output: String::from(""),
+            evm_version: Hardfork::default(),
+            limits: ExecutionLimits::default(),
         };
 
         let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
@@ -73,6 +85,8 @@ mod integration_tests {
             skip_resolving: true,
             no_tui: true,
             output: String::from(""),
+            evm_version: Hardfork::default(),
+            limits: ExecutionLimits::default(),
         };
 
         let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
@@ -162,6 +176,8 @@ mod integration_tests {
                 skip_resolving: true,
                 no_tui: true,
                 output: String::from(""),
+                evm_version: Hardfork::default(),
+                limits: ExecutionLimits::default(),
             };
             let _ = heimdall_core::snapshot::snapshot(args).await.unwrap();
         }
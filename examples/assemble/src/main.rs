@@ -0,0 +1,15 @@
+use heimdall_core::assemble::AssemblerArgsBuilder;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let result = heimdall_core::assemble::assemble(
+        AssemblerArgsBuilder::new()
+            .target("PUSH1 label_0x4\nJUMP\nINVALID\nlabel_0x4:\nJUMPDEST\nSTOP".to_string())
+            .build()?,
+    )
+    .await?;
+
+    println!("Assembled bytecode: {:#?}", result);
+
+    Ok(())
+}
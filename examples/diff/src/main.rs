@@ -0,0 +1,17 @@
+use heimdall_core::diff::DiffArgsBuilder;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let result = heimdall_core::diff::diff(
+        DiffArgsBuilder::new()
+            .target("0x9f00c43700bc0000Ff91bE00841F8e04c0495000".to_string())
+            .other("0x1bf797219482a29013d804ad96d1c6f84fba4c45".to_string())
+            .rpc_url("https://eth.llamarpc.com".to_string())
+            .build()?,
+    )
+    .await?;
+
+    println!("Bytecode diff:\n{}", result);
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+#ifndef HEIMDALL_H
+#define HEIMDALL_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Decompiles a contract address or bytecode, returning a JSON object with its `abi`, `source`,
+ * and `storage_layout`, or `{"error": "..."}` if it couldn't be decompiled. `rpc_url` may be
+ * NULL if `target` is bytecode. The returned string must be freed with `free_string`.
+ */
+char *decompile_to_json(const char *target, const char *rpc_url);
+
+/*
+ * Decodes calldata, or the calldata of a transaction hash, returning a JSON array of the
+ * functions it may have called, or `{"error": "..."}` if it couldn't be decoded. `rpc_url` may
+ * be NULL if `target` is calldata. The returned string must be freed with `free_string`.
+ */
+char *decode_calldata_to_json(const char *target, const char *rpc_url);
+
+/*
+ * Frees a string returned by this library. Passing NULL does nothing.
+ */
+void free_string(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HEIMDALL_H */
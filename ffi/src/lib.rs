@@ -0,0 +1,172 @@
+use std::{
+    ffi::{CStr, CString},
+    future::Future,
+    os::raw::c_char,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use heimdall_core::{
+    decode::{decode, DecodeArgsBuilder},
+    decompile::{decompile, DecompilerArgsBuilder},
+    error::Error,
+};
+use serde_json::{json, Value};
+
+/// Decompile a contract address or bytecode, returning a JSON object with its `abi`, `source`, and
+/// `storage_layout`, or an `error` if it couldn't be decompiled. `rpc_url` may be null if the
+/// target is bytecode. The returned string must be freed with [`free_string`].
+///
+/// # Safety
+///
+/// `target` and `rpc_url` must each be null or a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn decompile_to_json(
+    target: *const c_char,
+    rpc_url: *const c_char,
+) -> *mut c_char {
+    let (target, rpc_url) = match (read_string(target), read_string(rpc_url)) {
+        (Some(target), rpc_url) => (target, rpc_url.unwrap_or_default()),
+        (None, _) => return to_c_string(&error_json("a target is required.")),
+    };
+
+    to_c_string(&run(|| async move {
+        let args = DecompilerArgsBuilder::new()
+            .target(target)
+            .rpc_url(rpc_url)
+            .build()
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        let result = decompile(args).await?;
+        Ok(json!({
+            "abi": result.abi,
+            "source": result.source,
+            "storage_layout": result.storage_layout,
+        }))
+    }))
+}
+
+/// Decode calldata, or the calldata of a transaction hash, returning a JSON array of the
+/// functions it may have called, or an `error` if it couldn't be decoded. `rpc_url` may be null
+/// if the target is calldata. The returned string must be freed with [`free_string`].
+///
+/// # Safety
+///
+/// `target` and `rpc_url` must each be null or a valid, NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn decode_calldata_to_json(
+    target: *const c_char,
+    rpc_url: *const c_char,
+) -> *mut c_char {
+    let (target, rpc_url) = match (read_string(target), read_string(rpc_url)) {
+        (Some(target), rpc_url) => (target, rpc_url.unwrap_or_default()),
+        (None, _) => return to_c_string(&error_json("a target is required.")),
+    };
+
+    to_c_string(&run(|| async move {
+        let args = DecodeArgsBuilder::new()
+            .target(target)
+            .rpc_url(rpc_url)
+            .build()
+            .map_err(|e| Error::GenericError(e.to_string()))?;
+        Ok(serde_json::to_value(decode(args).await?)?)
+    }))
+}
+
+/// Free a string returned by this library. Passing null does nothing.
+///
+/// # Safety
+///
+/// `string` must be null, or a string returned by this library which hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// runs an analysis to completion on a new runtime, converting failures, including panics, into
+/// an error object, since neither may cross the C boundary
+fn run<A, F>(analysis: A) -> Value
+where
+    A: FnOnce() -> F,
+    F: Future<Output = Result<Value, Error>>, {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => return error_json(&format!("failed to start runtime: {e}")),
+    };
+    catch_unwind(AssertUnwindSafe(|| {
+        runtime.block_on(analysis()).unwrap_or_else(|e| error_json(&e.to_string()))
+    }))
+    .unwrap_or_else(|_| error_json("the analysis panicked."))
+}
+
+/// reads a NUL-terminated string, returning `None` if it's null or isn't valid UTF-8
+unsafe fn read_string(string: *const c_char) -> Option<String> {
+    match string.is_null() {
+        true => None,
+        false => CStr::from_ptr(string).to_str().ok().map(str::to_string),
+    }
+}
+
+/// converts JSON into a string owned by the caller, which is freed with [`free_string`]
+fn to_c_string(value: &Value) -> *mut c_char {
+    // serialized JSON escapes NUL, so it never contains one
+    CString::new(value.to_string()).map(CString::into_raw).unwrap_or(std::ptr::null_mut())
+}
+
+fn error_json(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{CStr, CString},
+        ptr::{null, null_mut},
+    };
+
+    use serde_json::Value;
+
+    use crate::{decode_calldata_to_json, decompile_to_json, free_string};
+
+    fn take_json(string: *mut std::os::raw::c_char) -> Value {
+        assert!(!string.is_null());
+        let value = serde_json::from_str(unsafe { CStr::from_ptr(string) }.to_str().unwrap());
+        unsafe { free_string(string) };
+        value.unwrap()
+    }
+
+    #[test]
+    fn test_missing_target() {
+        let value = take_json(unsafe { decompile_to_json(null(), null()) });
+        assert_eq!(value["error"], "a target is required.");
+
+        let value = take_json(unsafe { decode_calldata_to_json(null(), null()) });
+        assert_eq!(value["error"], "a target is required.");
+    }
+
+    #[test]
+    fn test_invalid_target() {
+        let target = CString::new("not a target").unwrap();
+        let value = take_json(unsafe { decompile_to_json(target.as_ptr(), null()) });
+        assert!(value["error"].is_string());
+    }
+
+    #[test]
+    fn test_address_without_rpc_url() {
+        // on-chain targets need an RPC provider, which is reported rather than exiting the host
+        let target = CString::new("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").unwrap();
+        let value = take_json(unsafe { decompile_to_json(target.as_ptr(), null()) });
+        assert!(value["error"].as_str().unwrap().contains("requires an RPC provider"));
+
+        let target =
+            CString::new("0x4b8a1b3d5b3d9f3f1b8c6b4f5c7e9a1d3b5c7e9f1a3b5c7d9e1f3a5b7c9d1e3f")
+                .unwrap();
+        let value = take_json(unsafe { decode_calldata_to_json(target.as_ptr(), null()) });
+        assert!(value["error"].as_str().unwrap().contains("requires an RPC provider"));
+    }
+
+    #[test]
+    fn test_free_null_string() {
+        unsafe { free_string(null_mut()) };
+    }
+}
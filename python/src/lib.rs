@@ -0,0 +1,115 @@
+use std::future::Future;
+
+use heimdall_core::{
+    cfg::{cfg as build_cfg, output::build_cfg_json, CFGArgsBuilder},
+    decode::{decode as decode_calldata, DecodeArgsBuilder},
+    decompile::{decompile as decompile_contract, DecompilerArgsBuilder},
+    disassemble::{disassemble as disassemble_contract, DisassemblerArgsBuilder},
+};
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+
+/// Decompile a contract address or bytecode, returning a dictionary with its `abi`, `source`, and
+/// `storage_layout`.
+#[pyfunction]
+#[pyo3(signature = (
+    target,
+    rpc_url = String::new(),
+    skip_resolving = false,
+    include_solidity = false,
+))]
+fn decompile(
+    py: Python<'_>,
+    target: String,
+    rpc_url: String,
+    skip_resolving: bool,
+    include_solidity: bool,
+) -> PyResult<PyObject> {
+    let args = DecompilerArgsBuilder::new()
+        .target(target)
+        .rpc_url(rpc_url)
+        .skip_resolving(skip_resolving)
+        .include_solidity(include_solidity)
+        .build()
+        .map_err(to_py_error)?;
+    let result = run(py, move || decompile_contract(args))?;
+    to_py_object(
+        py,
+        &serde_json::json!({
+            "abi": result.abi,
+            "source": result.source,
+            "storage_layout": result.storage_layout,
+        })
+        .to_string(),
+    )
+}
+
+/// Decode calldata, or the calldata of a transaction hash, returning a list of the functions it
+/// may have called, each as a dictionary.
+#[pyfunction]
+#[pyo3(signature = (target, rpc_url = String::new()))]
+fn decode(py: Python<'_>, target: String, rpc_url: String) -> PyResult<PyObject> {
+    let args =
+        DecodeArgsBuilder::new().target(target).rpc_url(rpc_url).build().map_err(to_py_error)?;
+    let result = run(py, move || decode_calldata(args))?;
+    to_py_object(py, &serde_json::to_string(&result).map_err(to_py_error)?)
+}
+
+/// Disassemble a contract address or bytecode, returning a list of its instructions, each as a
+/// dictionary.
+#[pyfunction]
+#[pyo3(signature = (target, rpc_url = String::new()))]
+fn disassemble(py: Python<'_>, target: String, rpc_url: String) -> PyResult<PyObject> {
+    let args = DisassemblerArgsBuilder::new()
+        .target(target)
+        .rpc_url(rpc_url)
+        .json(true)
+        .build()
+        .map_err(to_py_error)?;
+    let result = run(py, move || disassemble_contract(args))?;
+    to_py_object(py, &result)
+}
+
+/// Generate the control flow graph of a contract address or bytecode, returning a dictionary of
+/// its `nodes` and `edges`.
+#[pyfunction]
+#[pyo3(name = "cfg", signature = (target, rpc_url = String::new()))]
+fn control_flow_graph(py: Python<'_>, target: String, rpc_url: String) -> PyResult<PyObject> {
+    let args =
+        CFGArgsBuilder::new().target(target).rpc_url(rpc_url).build().map_err(to_py_error)?;
+    let result = run(py, move || build_cfg(args))?;
+    to_py_object(py, &build_cfg_json(&result).map_err(to_py_error)?)
+}
+
+/// runs an analysis to completion on a new runtime, releasing the GIL while it runs
+fn run<A, F, T, E>(py: Python<'_>, analysis: A) -> PyResult<T>
+where
+    A: FnOnce() -> F + Send,
+    F: Future<Output = Result<T, E>>,
+    T: Send,
+    E: ToString, {
+    py.allow_threads(|| {
+        tokio::runtime::Runtime::new()
+            .map_err(to_py_error)?
+            .block_on(analysis())
+            .map_err(to_py_error)
+    })
+}
+
+/// converts a JSON string into the equivalent python dictionaries and lists
+fn to_py_object(py: Python<'_>, json: &str) -> PyResult<PyObject> {
+    Ok(py.import("json")?.call_method1("loads", (json,))?.into())
+}
+
+fn to_py_error<E: ToString>(e: E) -> PyErr {
+    PyRuntimeError::new_err(e.to_string())
+}
+
+/// Heimdall's analyses, as python functions which return dictionaries.
+#[pymodule]
+fn heimdall(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(decompile, m)?)?;
+    m.add_function(wrap_pyfunction!(decode, m)?)?;
+    m.add_function(wrap_pyfunction!(disassemble, m)?)?;
+    m.add_function(wrap_pyfunction!(control_flow_graph, m)?)?;
+    Ok(())
+}
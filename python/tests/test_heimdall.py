@@ -0,0 +1,28 @@
+import unittest
+
+import heimdall
+
+
+class TestHeimdall(unittest.TestCase):
+    def test_bytecode(self):
+        instructions = heimdall.disassemble("0x6001600201")
+        self.assertEqual(instructions[0]["opcode"], "PUSH1")
+
+        graph = heimdall.cfg("0x6001600201")
+        self.assertIn("nodes", graph)
+
+    def test_address_without_rpc_url(self):
+        # on-chain targets need an RPC provider, which is raised rather than exiting the interpreter
+        with self.assertRaisesRegex(RuntimeError, "requires an RPC provider"):
+            heimdall.decompile("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2")
+
+        with self.assertRaisesRegex(RuntimeError, "requires an RPC provider"):
+            heimdall.decode("0x4b8a1b3d5b3d9f3f1b8c6b4f5c7e9a1d3b5c7e9f1a3b5c7d9e1f3a5b7c9d1e3f")
+
+    def test_invalid_target(self):
+        with self.assertRaises(RuntimeError):
+            heimdall.disassemble("not a target")
+
+
+if __name__ == "__main__":
+    unittest.main()